@@ -1,49 +1,62 @@
 // TypeScript interface for Rust WebAssembly engine
 export interface TextAnalysisResult {
-  word_count: number;
-  character_count: number;
-  paragraph_count: number;
-  sentence_count: number;
-  readability_score: number;
-  complexity_metrics: ComplexityMetrics;
-  style_metrics: StyleMetrics;
-  content_hash: string;
+  wordCount: number;
+  characterCount: number;
+  paragraphCount: number;
+  sentenceCount: number;
+  readabilityScore: number;
+  complexityMetrics: ComplexityMetrics;
+  styleMetrics: StyleMetrics;
+  contentHash: string;
 }
 
 export interface ComplexityMetrics {
-  avg_words_per_sentence: number;
-  avg_syllables_per_word: number;
-  fog_index: number;
-  flesch_reading_ease: number;
-  unique_word_ratio: number;
+  avgWordsPerSentence: number;
+  avgSyllablesPerWord: number;
+  fogIndex: number;
+  fleschReadingEase: number;
+  uniqueWordRatio: number;
 }
 
 export interface StyleMetrics {
-  passive_voice_ratio: number;
-  adverb_ratio: number;
-  dialogue_ratio: number;
-  action_ratio: number;
-  description_ratio: number;
+  passiveVoiceRatio: number;
+  adverbRatio: number;
+  dialogueRatio: number;
+  actionRatio: number;
+  descriptionRatio: number;
 }
 
 export interface OptimizationSuggestion {
-  suggestion_type: string;
+  suggestionType: string;
   priority: string;
   message: string;
-  start_pos: number;
-  end_pos: number;
-  suggested_replacement?: string;
+  startPos: number;
+  endPos: number;
+  suggestedReplacement?: string;
 }
 
 export interface CollaborationConflict {
-  conflict_id: string;
-  conflict_type: string;
-  start_pos: number;
-  end_pos: number;
-  user_a_change: string;
-  user_b_change: string;
+  conflictId: string;
+  conflictType: string;
+  startPos: number;
+  endPos: number;
+  userAChange: string;
+  userBChange: string;
   timestamp: string;
-  resolution_suggestion: string;
+  resolutionSuggestion: string;
+  expectedText?: string;
+}
+
+export interface RejectedConflict {
+  index: number;
+  conflictId?: string;
+  reason: string;
+  detail: string;
+}
+
+export interface ConflictResolutionResponse {
+  resolved: CollaborationConflict[];
+  rejected: RejectedConflict[];
 }
 
 class RustEngineWrapper {
@@ -105,7 +118,9 @@ class RustEngineWrapper {
     if (!this.isInitialized) await this.initialize();
     
     if (this.wasmModule) {
-      return this.textProcessor.resolve_conflicts(conflicts);
+      // Malformed entries are reported in `rejected` and left out of `resolved`
+      const response: ConflictResolutionResponse = this.textProcessor.resolve_conflicts(conflicts);
+      return response.resolved;
     } else {
       return this.textProcessor.resolve_conflicts(conflicts);
     }
@@ -137,26 +152,26 @@ class RustEngineWrapper {
     const fleschScore = 206.835 - 1.015 * avgWordsPerSentence - 84.6 * avgSyllablesPerWord;
     
     return {
-      word_count: wordCount,
-      character_count: characterCount,
-      paragraph_count: paragraphCount,
-      sentence_count: sentenceCount,
-      readability_score: fleschScore,
-      complexity_metrics: {
-        avg_words_per_sentence: avgWordsPerSentence,
-        avg_syllables_per_word: avgSyllablesPerWord,
-        fog_index: 0.4 * (avgWordsPerSentence + 100 * 0.1),
-        flesch_reading_ease: fleschScore,
-        unique_word_ratio: new Set(words.map(w => w.toLowerCase())).size / wordCount,
+      wordCount: wordCount,
+      characterCount: characterCount,
+      paragraphCount: paragraphCount,
+      sentenceCount: sentenceCount,
+      readabilityScore: fleschScore,
+      complexityMetrics: {
+        avgWordsPerSentence: avgWordsPerSentence,
+        avgSyllablesPerWord: avgSyllablesPerWord,
+        fogIndex: 0.4 * (avgWordsPerSentence + 100 * 0.1),
+        fleschReadingEase: fleschScore,
+        uniqueWordRatio: new Set(words.map(w => w.toLowerCase())).size / wordCount,
       },
-      style_metrics: {
-        passive_voice_ratio: 0.1,
-        adverb_ratio: 0.05,
-        dialogue_ratio: 0.2,
-        action_ratio: 0.3,
-        description_ratio: 0.4,
+      styleMetrics: {
+        passiveVoiceRatio: 0.1,
+        adverbRatio: 0.05,
+        dialogueRatio: 0.2,
+        actionRatio: 0.3,
+        descriptionRatio: 0.4,
       },
-      content_hash: this.fallbackGenerateHash(text),
+      contentHash: this.fallbackGenerateHash(text),
     };
   }
 
@@ -169,11 +184,11 @@ class RustEngineWrapper {
       const words = sentence.match(/\b\w+\b/g) || [];
       if (words.length > 25) {
         suggestions.push({
-          suggestion_type: 'sentence_length',
+          suggestionType: 'sentence_length',
           priority: 'medium',
           message: 'Consider breaking this long sentence into shorter ones.',
-          start_pos: index * 50,
-          end_pos: (index + 1) * 50,
+          startPos: index * 50,
+          endPos: (index + 1) * 50,
         });
       }
     });
@@ -184,7 +199,7 @@ class RustEngineWrapper {
   private fallbackResolveConflicts(conflicts: CollaborationConflict[]): CollaborationConflict[] {
     return conflicts.map(conflict => ({
       ...conflict,
-      resolution_suggestion: `Auto-resolved: ${conflict.user_b_change}`,
+      resolutionSuggestion: `Auto-resolved: ${conflict.userBChange}`,
     }));
   }
 
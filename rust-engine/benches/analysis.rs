@@ -0,0 +1,97 @@
+//! The single tokenizer pass against the six document-wide scans it
+//! replaced (words, sentences, paragraphs, passives, adverbs, dialogue), and
+//! full analysis on top of it, over a 1 MB manuscript.
+//! Run with `cargo bench --no-default-features --bench analysis`.
+
+use std::time::{Duration, Instant};
+
+use omniauthor_engine::TextProcessor;
+use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+
+const PARAGRAPH: &str = "\u{201C}We should leave before the storm,\u{201D} she said quietly. The letter was written by \
+    the clerk and was sealed in haste. Mara walked slowly to the old mill, where the wheel had not turned in years, \
+    and she felt the cold come up from the river. \"At the end of the day,\" he said, \"the barn was still standing.\"";
+
+const TARGET_BYTES: usize = 1 << 20;
+const ITERATIONS: u32 = 5;
+
+/// The built-in patterns as `perform_analysis` used them before the
+/// tokenizer.
+struct Patterns {
+    sentence: Regex,
+    paragraph: Regex,
+    passive: Regex,
+    adverb: Regex,
+    dialogue: Regex,
+}
+
+impl Patterns {
+    fn new() -> Patterns {
+        Patterns {
+            sentence: Regex::new(r"[.!?]+").unwrap(),
+            paragraph: Regex::new(r"\n\s*\n").unwrap(),
+            passive: Regex::new(r"\b(was|were|been|being)\s+\w+ed\b").unwrap(),
+            adverb: Regex::new(r"\b\w+ly\b").unwrap(),
+            dialogue: Regex::new(r#""[^"]*""#).unwrap(),
+        }
+    }
+
+    /// The old path: every scan over the whole document. The per-word
+    /// dialogue lookup that followed is left out, so this is a lower bound.
+    fn document_scans(&self, text: &str) -> [usize; 6] {
+        [
+            text.unicode_word_indices().count(),
+            self.sentence.find_iter(text).count(),
+            self.paragraph.find_iter(text).count(),
+            self.passive.find_iter(text).count(),
+            self.adverb.find_iter(text).count(),
+            self.dialogue.find_iter(text).count(),
+        ]
+    }
+
+    /// The new path: one token scan for words, sentences and paragraphs,
+    /// then the style patterns per sentence.
+    fn single_pass(&self, processor: &TextProcessor, text: &str) -> [usize; 3] {
+        let mut found = [0; 3];
+        for sentence in processor.split_sentences(text) {
+            let text = &sentence.text;
+            found[0] += self.passive.find_iter(text).count();
+            found[1] += self.adverb.find_iter(text).count();
+            found[2] += self.dialogue.find_iter(text).count();
+        }
+        found
+    }
+}
+
+fn per_pass<T>(mut pass: impl FnMut() -> T) -> (T, Duration) {
+    let started = Instant::now();
+    let mut result = pass();
+    for _ in 1..ITERATIONS {
+        result = pass();
+    }
+    (result, started.elapsed() / ITERATIONS)
+}
+
+fn main() {
+    let copies = TARGET_BYTES / (PARAGRAPH.len() + 2) + 1;
+    let manuscript = vec![PARAGRAPH; copies].join("\n\n");
+    let mut processor = TextProcessor::new();
+    let patterns = Patterns::new();
+
+    let (scanned, scans) = per_pass(|| patterns.document_scans(&manuscript));
+    let (passed, single) = per_pass(|| patterns.single_pass(&processor, &manuscript));
+    // Every pass misses the cache, as an edited document would
+    let (words, analysis) = per_pass(|| {
+        processor.clear_cache();
+        processor.analyze_text(&manuscript).word_count
+    });
+
+    let megabytes = manuscript.len() as f64 / (1 << 20) as f64;
+    let rate = |elapsed: Duration| megabytes / elapsed.as_secs_f64();
+    println!("analysis: {:.2} MB, {} words", megabytes, words);
+    println!("  six document scans: {:?} per pass ({:.1} MB/s), counts {:?}", scans, rate(scans), scanned);
+    println!("  single pass:        {:?} per pass ({:.1} MB/s), style counts {:?}", single, rate(single), passed);
+    println!("  single pass / scans: {:.2}", single.as_secs_f64() / scans.as_secs_f64());
+    println!("  analyze_text:       {:?} per pass ({:.1} MB/s)", analysis, rate(analysis));
+}
@@ -0,0 +1,132 @@
+//! One `analyze_batch` call over 60 chapters against 60 separate
+//! `analyze_text` calls, the way the project dashboard used to work. The
+//! chapters are generated prose over a few thousand distinct words, most of
+//! them shared between chapters the way a novel's are. Each side also turns
+//! its results into JSON, once per call, standing in for the conversion at
+//! the JavaScript boundary (`wasm-tests/tests/batch_timing.rs` times the
+//! exports themselves).
+//! Run with `cargo bench --no-default-features --bench batch`.
+
+use std::time::{Duration, Instant};
+
+use omniauthor_engine::{BatchDocument, TextProcessor};
+
+const COMMON: &[&str] = &[
+    "the", "and", "was", "she", "he", "had", "not", "of", "to", "in", "a", "her", "his", "it", "that", "for", "on",
+    "at", "with", "from", "by", "but", "they", "were", "been", "would", "could", "there", "when", "into", "over",
+    "river", "window", "letter", "morning", "brother", "farm", "road", "house", "night", "rain", "stone", "field",
+    "walked", "watched", "said", "turned", "waited", "opened", "carried", "quietly", "slowly", "suddenly", "old",
+    "cold", "long", "dark", "small", "heavy", "felt", "saw", "heard", "thought", "knew", "remembered", "again",
+];
+const SYLLABLES: &[&str] = &["ka", "ren", "mo", "til", "sa", "dor", "ve", "an", "lis", "tho", "mer", "il", "gan", "ro"];
+
+const CHAPTERS: usize = 60;
+const PARAGRAPHS_PER_CHAPTER: usize = 80;
+const COINED_WORDS: usize = 6000;
+const ITERATIONS: usize = 9;
+
+/// A fixed-seed linear congruential generator, so every run sees the same
+/// chapters.
+struct Sequence(u64);
+
+impl Sequence {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Roughly Zipfian: low indices are drawn far more often than high ones.
+    fn skewed(&mut self, n: usize) -> usize {
+        let unit = self.next() as f64 / (1u64 << 31) as f64;
+        ((n as f64).powf(unit) as usize).saturating_sub(1).min(n - 1)
+    }
+}
+
+fn coined(mut n: usize) -> String {
+    let mut word = String::new();
+    loop {
+        word.push_str(SYLLABLES[n % SYLLABLES.len()]);
+        n /= SYLLABLES.len();
+        if n == 0 {
+            return word;
+        }
+    }
+}
+
+fn chapter(number: usize, coined_words: &[String], sequence: &mut Sequence) -> String {
+    let mut text = format!("Chapter {}", number);
+    for _ in 0..PARAGRAPHS_PER_CHAPTER {
+        text.push_str("\n\n");
+        for sentence in 0..4 + sequence.below(4) {
+            let dialogue = sequence.below(5) == 0;
+            if sentence > 0 {
+                text.push(' ');
+            }
+            if dialogue {
+                text.push('\u{201C}');
+            }
+            for position in 0..6 + sequence.below(14) {
+                let word = if sequence.below(3) == 0 {
+                    coined_words[sequence.skewed(coined_words.len())].as_str()
+                } else {
+                    COMMON[sequence.skewed(COMMON.len())]
+                };
+                if position == 0 {
+                    let mut letters = word.chars();
+                    text.extend(letters.next().map(|c| c.to_ascii_uppercase()));
+                    text.push_str(letters.as_str());
+                } else {
+                    text.push(' ');
+                    text.push_str(word);
+                }
+            }
+            text.push_str(if dialogue { ",\u{201D} she said." } else { "." });
+        }
+    }
+    text
+}
+
+fn timed<T>(pass: impl FnOnce() -> T) -> (T, Duration) {
+    let started = Instant::now();
+    let result = pass();
+    (result, started.elapsed())
+}
+
+fn main() {
+    let coined_words: Vec<String> = (0..COINED_WORDS).map(|n| coined(n + SYLLABLES.len())).collect();
+    let mut sequence = Sequence(0x5eed);
+    let chapters: Vec<String> = (1..=CHAPTERS).map(|n| chapter(n, &coined_words, &mut sequence)).collect();
+    let mut processor = TextProcessor::new();
+    // Separate calls must not answer from the cache
+    processor.set_cache_capacity(0);
+    let documents = || chapters.iter().cloned().map(BatchDocument::Text).collect();
+
+    // The four passes take turns, so a slow spell of the machine does not
+    // land on one of them only; each keeps its fastest run
+    let mut fastest = [Duration::MAX; 4];
+    let (mut words, mut json_bytes, mut vocabulary) = (0, 0, 0);
+    for _ in 0..ITERATIONS {
+        let passes = [
+            timed(|| words = chapters.iter().map(|chapter| processor.analyze_text(chapter).word_count).sum()).1,
+            timed(|| vocabulary = processor.analyze_batch(documents()).summary.vocabulary_size).1,
+            timed(|| json_bytes = chapters.iter().map(|chapter| processor.analyze_text_json(chapter).unwrap().len()).sum()).1,
+            timed(|| serde_json::to_string(&processor.analyze_batch(documents())).unwrap().len()).1,
+        ];
+        for (fastest, pass) in fastest.iter_mut().zip(passes) {
+            *fastest = (*fastest).min(pass);
+        }
+    }
+    let [separate, batch, separate_json, batch_json] = fastest;
+
+    let share = |batch: Duration, separate: Duration| 100.0 * batch.as_secs_f64() / separate.as_secs_f64();
+    println!("batch: {} chapters, {} words, {} distinct words; fastest of {} runs", CHAPTERS, words, vocabulary, ITERATIONS);
+    println!("  separate calls: {:?}", separate);
+    println!("  analyze_batch:  {:?} ({:.0}%)", batch, share(batch, separate));
+    println!("with results as JSON ({} bytes from separate calls):", json_bytes);
+    println!("  separate calls: {:?}", separate_json);
+    println!("  analyze_batch:  {:?} ({:.0}%)", batch_json, share(batch_json, separate_json));
+}
@@ -0,0 +1,37 @@
+//! Size and serialization time of the full and compact suggestion forms for
+//! a long manuscript, where most strings repeat.
+//! Run with `cargo bench --no-default-features --bench compact`.
+
+use std::time::{Duration, Instant};
+
+use omniauthor_engine::{compact_suggestions, TextProcessor};
+
+const PARAGRAPH: &str = "The letter was written by the clerk and was quickly sealed. She really felt that the house \
+    was very quiet, and at the end of the day the rain was still falling. The gate was opened by her brother, who \
+    basically walked in slowly and said nothing at all about the farm or the debts.";
+
+const ITERATIONS: u32 = 5;
+
+/// Mean time of `ITERATIONS` runs of `f`, with the output of the last.
+fn time<T>(mut f: impl FnMut() -> T) -> (T, Duration) {
+    let started = Instant::now();
+    let mut output = f();
+    for _ in 1..ITERATIONS {
+        output = f();
+    }
+    (output, started.elapsed() / ITERATIONS)
+}
+
+fn main() {
+    let manuscript = vec![PARAGRAPH; 2_000].join("\n\n");
+    let suggestions = TextProcessor::new().optimize_text(&manuscript);
+
+    let (full, full_time) = time(|| serde_json::to_vec(&suggestions).unwrap());
+    let (compact, compact_time) = time(|| serde_json::to_vec(&compact_suggestions(&suggestions)).unwrap());
+    let strings = compact_suggestions(&suggestions).strings.len();
+
+    println!("compact: {} suggestions, {} distinct strings", suggestions.len(), strings);
+    println!("  full:    {:>10} bytes, {:?} per pass", full.len(), full_time);
+    println!("  compact: {:>10} bytes, {:?} per pass", compact.len(), compact_time);
+    println!("  compact is {:.1}% of the full size", compact.len() as f64 * 100.0 / full.len() as f64);
+}
@@ -0,0 +1,49 @@
+//! Repeated phrase detection over a novel-length manuscript (about 120,000
+//! words) whose sentences recur in shifting orders, as a writer's tics do.
+//! Run with `cargo bench --no-default-features --bench phrases`.
+
+use std::time::Instant;
+
+use omniauthor_engine::TextProcessor;
+
+const SENTENCES: [&str; 12] = [
+    "A moment later she let out a breath she had not known she was holding.",
+    "The rain had not stopped since morning, and the kettle ticked on the stove.",
+    "Her brother arrived at the door, shaking water from his coat.",
+    "For a while neither of them spoke about the farm or the debts.",
+    "She let out a breath and turned back toward the window.",
+    "Somewhere across the valley a dog barked twice and fell silent.",
+    "He took off his boots and set them by the fire to dry.",
+    "A moment later the lamp guttered and the room went gray.",
+    "The letter lay on the table where the postman had left it.",
+    "She told herself a hundred times that it made no difference.",
+    "In the morning the north field was white with frost.",
+    "He shook his head slowly, as if the question had no answer.",
+];
+
+const SENTENCES_PER_PARAGRAPH: usize = 5;
+const TARGET_WORDS: usize = 120_000;
+const ITERATIONS: u32 = 5;
+
+fn main() {
+    let mut paragraphs = Vec::new();
+    let mut words = 0;
+    while words < TARGET_WORDS {
+        let i = paragraphs.len();
+        let paragraph: Vec<&str> =
+            (0..SENTENCES_PER_PARAGRAPH).map(|k| SENTENCES[(i * 7 + k * 5 + i / 12) % SENTENCES.len()]).collect();
+        let paragraph = paragraph.join(" ");
+        words += paragraph.split_whitespace().count();
+        paragraphs.push(paragraph);
+    }
+    let manuscript = paragraphs.join("\n\n");
+    let processor = TextProcessor::new();
+
+    let started = Instant::now();
+    let mut found = 0;
+    for _ in 0..ITERATIONS {
+        found = processor.repeated_phrases(&manuscript, 3, 5, 3).len();
+    }
+    let elapsed = started.elapsed() / ITERATIONS;
+    println!("phrases: {} words, {} repeated phrases, {:?} per pass", words, found, elapsed);
+}
@@ -11,7 +11,8 @@ fi
 
 # Build the WebAssembly module
 echo "Compiling Rust to WebAssembly..."
-wasm-pack build --target web --out-dir ../client/src/wasm --out-name omniauthor-engine
+OMNIAUTHOR_GIT_HASH="$(git rev-parse --short HEAD 2>/dev/null || true)" \
+    wasm-pack build --target web --out-dir ../client/src/wasm --out-name omniauthor-engine
 
 echo "Rust engine built successfully!"
 echo "WebAssembly module available at: client/src/wasm/"
\ No newline at end of file
@@ -0,0 +1,221 @@
+//! Acronym glossary: all-caps words of two to six letters, where each was
+//! first used, and whether and where it was spelled out, in either order
+//! ("Open Authoring Protocol (OAP)" or "OAP (Open Authoring Protocol)").
+//! Plurals ("APIs") count toward the singular.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::segmentation::DocumentIndex;
+use crate::OptimizationSuggestion;
+
+const MIN_LETTERS: usize = 2;
+const MAX_LETTERS: usize = 6;
+
+/// Uses an acronym needs before a missing definition is worth a suggestion.
+const MIN_USES_FOR_SUGGESTION: usize = 2;
+
+/// Small words an expansion may contain without a letter in the acronym
+/// ("Bureau of Labor Statistics (BLS)").
+const MINOR_WORDS: &[&str] = &["a", "an", "and", "at", "by", "for", "in", "of", "on", "or", "the", "to", "with"];
+
+/// Longest expansion considered before a parenthesized acronym, in words.
+const MAX_EXPANSION_WORDS: usize = MAX_LETTERS * 2;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Acronym {
+    pub acronym: String,
+    /// The spelled-out form, as written.
+    pub expansion: Option<String>,
+    pub first_use_pos: usize,
+    /// Spelled out at its first use (or earlier); false when it was first
+    /// used bare or never spelled out.
+    pub defined_before_first_use: bool,
+    /// Uses, plurals and the one in the definition included.
+    pub count: usize,
+}
+
+/// The acronym a word is a use of: two to six capitals, optionally with a
+/// plural "s" or a possessive.
+fn acronym_of(word: &str) -> Option<&str> {
+    let word = word.strip_suffix("'s").or_else(|| word.strip_suffix("\u{2019}s")).unwrap_or(word);
+    let stem = word.strip_suffix('s').unwrap_or(word);
+    let is_caps = |s: &str| (MIN_LETTERS..=MAX_LETTERS).contains(&s.len()) && s.bytes().all(|b| b.is_ascii_uppercase());
+    [word, stem].into_iter().find(|s| is_caps(s))
+}
+
+/// Whether `words` spell out `acronym`: each word's initial is the next
+/// letter, apart from minor words, and the first word is never skipped.
+fn expands(words: &[&str], acronym: &str) -> bool {
+    let mut letters = acronym.chars().map(|c| c.to_ascii_lowercase()).peekable();
+    for (k, word) in words.iter().enumerate() {
+        let initial = word.chars().next().map(|c| c.to_lowercase().next().unwrap_or(c));
+        if initial.is_some() && initial == letters.peek().copied() {
+            letters.next();
+        } else if k == 0 || !MINOR_WORDS.contains(&word.to_lowercase().as_str()) {
+            return false;
+        }
+    }
+    letters.next().is_none() && !words.is_empty()
+}
+
+/// One use of an acronym.
+struct Use {
+    acronym: String,
+    start: usize,
+}
+
+/// A sentence whose words are mostly capitals is shouted, not abbreviated.
+fn is_shouted(text: &str, words: &[(usize, usize)]) -> bool {
+    let caps = words.iter().filter(|&&(s, e)| text[s..e].chars().all(|c| !c.is_lowercase())).count();
+    words.len() >= 3 && caps * 2 > words.len()
+}
+
+/// The expansion after `words[k]`, when the next word opens a parenthesis
+/// right after it: "OAP (Open Authoring Protocol)".
+fn expansion_after(text: &str, words: &[(usize, usize)], k: usize, acronym: &str) -> Option<String> {
+    let rest = &text[words[k].1..];
+    let inner = rest.trim_start_matches([' ', '\u{a0}']).strip_prefix('(')?;
+    let close = inner.find(')')?;
+    let inner = inner[..close].trim();
+    let parts: Vec<&str> = inner.split(|c: char| c.is_whitespace() || c == '-').filter(|w| !w.is_empty()).collect();
+    expands(&parts, acronym).then(|| inner.to_string())
+}
+
+/// The expansion before `words[k]`, when it is parenthesized right after
+/// it: "Open Authoring Protocol (OAP)". The longest spelling-out wins.
+fn expansion_before(text: &str, words: &[(usize, usize)], k: usize, acronym: &str) -> Option<String> {
+    let before = text[..words[k].0].strip_suffix('(')?;
+    if !text[words[k].1..].starts_with(')') {
+        return None;
+    }
+    let end = before.trim_end_matches([' ', '\u{a0}']).len();
+    let window = k.saturating_sub(MAX_EXPANSION_WORDS);
+    let candidates: Vec<(usize, usize)> = words[window..k].iter().copied().filter(|&(_, e)| e <= end).collect();
+    (0..candidates.len())
+        .find(|&first| {
+            let parts: Vec<&str> = candidates[first..].iter().map(|&(s, e)| &text[s..e]).collect();
+            // Nothing but spaces and hyphens between the words of the expansion
+            let joined = text[candidates[first].1..end].chars().all(|c| c.is_whitespace() || c == '-' || c.is_alphanumeric());
+            joined && expands(&parts, acronym)
+        })
+        .map(|first| text[candidates[first].0..end].to_string())
+}
+
+/// Every acronym not in `ignored`, in order of first use.
+pub fn extract(index: &DocumentIndex, ignored: &[String]) -> Vec<Acronym> {
+    let text = index.text;
+    let mut uses = Vec::new();
+    let mut definitions: HashMap<String, (usize, String)> = HashMap::new();
+    for &(sentence_start, sentence_end) in &index.sentences {
+        let words = index.words_in(sentence_start, sentence_end);
+        if is_shouted(text, words) {
+            continue;
+        }
+        for (k, &(start, end)) in words.iter().enumerate() {
+            let Some(acronym) = acronym_of(&text[start..end]) else { continue };
+            if ignored.iter().any(|i| i.eq_ignore_ascii_case(acronym)) {
+                continue;
+            }
+            let acronym = acronym.to_string();
+            if !definitions.contains_key(&acronym) {
+                let expansion =
+                    expansion_after(text, words, k, &acronym).or_else(|| expansion_before(text, words, k, &acronym));
+                if let Some(expansion) = expansion {
+                    definitions.insert(acronym.clone(), (start, expansion));
+                }
+            }
+            uses.push(Use { acronym, start });
+        }
+    }
+
+    let mut acronyms: Vec<Acronym> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for u in uses {
+        match positions.get(&u.acronym) {
+            Some(&i) => acronyms[i].count += 1,
+            None => {
+                let definition = definitions.get(&u.acronym);
+                positions.insert(u.acronym.clone(), acronyms.len());
+                acronyms.push(Acronym {
+                    defined_before_first_use: definition.is_some_and(|&(pos, _)| pos <= u.start),
+                    expansion: definition.map(|(_, expansion)| expansion.clone()),
+                    acronym: u.acronym,
+                    first_use_pos: u.start,
+                    count: 1,
+                });
+            }
+        }
+    }
+    acronyms
+}
+
+/// One `undefined_acronym` suggestion at the first use of each acronym used
+/// repeatedly and never spelled out.
+pub fn undefined_acronyms(index: &DocumentIndex, ignored: &[String]) -> Vec<OptimizationSuggestion> {
+    extract(index, ignored)
+        .into_iter()
+        .filter(|a| a.expansion.is_none() && a.count >= MIN_USES_FOR_SUGGESTION)
+        .map(|a| {
+            let end = a.first_use_pos + a.acronym.len();
+            OptimizationSuggestion::instance(
+                "undefined_acronym",
+                "low",
+                format!("\"{}\" is used {} times but never spelled out; define it at its first use.", a.acronym, a.count),
+                a.first_use_pos,
+                end,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    fn acronym<'a>(acronyms: &'a [Acronym], name: &str) -> &'a Acronym {
+        acronyms.iter().find(|a| a.acronym == name).unwrap_or_else(|| panic!("{} not found", name))
+    }
+
+    #[test]
+    fn extract_acronyms_reads_both_definition_orders() {
+        let text = "The Open Authoring Protocol (OAP) syncs drafts. Each client calls the REST API (Application Programming Interface) directly, and the APIs return JSON. OAP clients retry.";
+        let acronyms = TextProcessor::new().extract_acronyms(text);
+        let oap = acronym(&acronyms, "OAP");
+        assert_eq!(oap.expansion.as_deref(), Some("Open Authoring Protocol"));
+        assert!(oap.defined_before_first_use);
+        assert_eq!(oap.count, 2);
+        assert_eq!(&text[oap.first_use_pos..oap.first_use_pos + 3], "OAP");
+        let api = acronym(&acronyms, "API");
+        assert_eq!(api.expansion.as_deref(), Some("Application Programming Interface"));
+        assert!(api.defined_before_first_use);
+        // "APIs" counts toward "API"
+        assert_eq!(api.count, 2);
+        assert!(acronym(&acronyms, "REST").expansion.is_none());
+    }
+
+    #[test]
+    fn extract_acronyms_flags_definitions_after_first_use() {
+        let text = "Send it over SFTP tonight. We chose the Secure File Transfer Protocol (SFTP) for audits.";
+        let acronyms = TextProcessor::new().extract_acronyms(text);
+        let sftp = acronym(&acronyms, "SFTP");
+        assert_eq!(sftp.expansion.as_deref(), Some("Secure File Transfer Protocol"));
+        assert!(!sftp.defined_before_first_use);
+        assert_eq!(sftp.first_use_pos, text.find("SFTP").unwrap());
+    }
+
+    #[test]
+    fn undefined_acronym_suggested_at_first_of_repeated_uses() {
+        let text = "Our team filed the RFC on Monday. The RFC went to review, and everyone watched TV after the RFC passed. A lone NDA was signed.";
+        let processor = TextProcessor::new();
+        let acronyms = processor.extract_acronyms(text);
+        assert!(acronyms.iter().all(|a| a.acronym != "TV"));
+        let rfc = acronym(&acronyms, "RFC");
+        assert!(rfc.expansion.is_none() && !rfc.defined_before_first_use);
+        assert_eq!(rfc.count, 3);
+        let suggestions = processor.generate_optimization_suggestions(text, &|rule| rule == "undefined_acronym");
+        let starts: Vec<usize> = suggestions.iter().map(|s| s.start_pos).collect();
+        assert_eq!(starts, [text.find("RFC").unwrap()]);
+    }
+}
@@ -0,0 +1,121 @@
+//! Adverbs: words with the language's adverb ending ("-ly", "-mente"),
+//! minus the common ones that aren't adverbs ("family", "only", "July") and
+//! capitalized words mid-sentence, which are most likely names ("Kelly").
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::lang::Language;
+use crate::passive;
+
+/// Verbs not recognizable from their ending, for `near_verbs_only`: dialogue
+/// tags and common irregular forms.
+const VERBS: &[&str] = &[
+    "add", "added", "adds", "answer", "answered", "answers", "ask", "asked", "asks", "ate", "began", "came", "come",
+    "comes", "cried", "drank", "eat", "eats", "fell", "felt", "gave", "go", "goes", "got", "grew", "knew", "left",
+    "looked", "looks", "ran", "replied", "run", "runs", "said", "sang", "sat", "saw", "say", "says", "shouted", "sit",
+    "sits", "spoke", "speak", "speaks", "stood", "swam", "take", "takes", "thought", "took", "walk", "walks", "went",
+    "whispered", "wrote",
+];
+
+fn is_verb(word: &str) -> bool {
+    let lower = word.to_lowercase();
+    VERBS.contains(&lower.as_str()) || (lower.len() > 4 && lower.ends_with("ing")) || passive::is_participle(&lower)
+}
+
+/// Whether `word` is an adverb of `language`, going by its form alone.
+pub fn is_adverb(word: &str, language: &Language) -> bool {
+    let lower = word.to_lowercase();
+    language.adverb_suffixes.iter().any(|suffix| lower.len() > suffix.len() && lower.ends_with(suffix))
+        && !language.not_adverbs.contains(&lower.as_str())
+}
+
+/// Whether the word at `start` opens a sentence or quotation, where
+/// capitals don't mark a name.
+fn opens_clause(sentence: &str, start: usize) -> bool {
+    let before = sentence[..start].trim_end();
+    before.is_empty() || before.ends_with(['"', '\u{201C}', '\u{2018}', '(', ':', '\u{2014}'])
+}
+
+/// Byte spans of the adverbs of one sentence. With `near_verbs_only`, only
+/// adverbs right before or after a verb ("said softly", "quickly ran") count.
+pub fn find(sentence: &str, near_verbs_only: bool, language: &Language) -> Vec<(usize, usize)> {
+    let words: Vec<(usize, &str)> = sentence.unicode_word_indices().collect();
+    let adjacent_verb = |k: usize| {
+        let (start, word) = words[k];
+        let before = k.checked_sub(1).map(|p| words[p]).filter(|&(s, w)| sentence[s + w.len()..start].trim().is_empty());
+        let after = words.get(k + 1).copied().filter(|&(s, _)| sentence[start + word.len()..s].trim().is_empty());
+        [before, after].into_iter().flatten().any(|(_, w)| is_verb(w))
+    };
+    (0..words.len())
+        .filter(|&k| {
+            let (start, word) = words[k];
+            if !is_adverb(word, language) {
+                return false;
+            }
+            // Capitalized but not all caps, mid-sentence: a name
+            let capitalized = word.starts_with(char::is_uppercase) && word.chars().any(char::is_lowercase);
+            if capitalized && !opens_clause(sentence, start) {
+                return false;
+            }
+            !near_verbs_only || adjacent_verb(k)
+        })
+        .map(|k| (words[k].0, words[k].0 + words[k].1.len()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+    use crate::config::{AnalysisOptions, SuggestionConfig};
+    use crate::suggestions::SCOPE_INSTANCE;
+
+    fn adverbs(sentence: &str, near_verbs_only: bool) -> Vec<&str> {
+        find(sentence, near_verbs_only, &crate::lang::ENGLISH).into_iter().map(|(s, e)| &sentence[s..e]).collect()
+    }
+
+    #[test]
+    fn non_adverb_ly_words_are_never_flagged() {
+        let sentence = "My family only came early in July to supply the assembly, and it was lovely.";
+        assert!(adverbs(sentence, false).is_empty());
+        assert_eq!(adverbs("She quickly left, and he answered angrily.", false), ["quickly", "angrily"]);
+    }
+
+    #[test]
+    fn capitalized_words_mid_sentence_are_names() {
+        assert!(adverbs("We met Kelly and Beasley at the Wembly stadium.", false).is_empty());
+        // A capital opening the sentence or a quotation is not a name
+        assert_eq!(adverbs("Slowly, the door opened.", false), ["Slowly"]);
+        assert_eq!(adverbs("He said, \"Honestly, no.\"", false), ["Honestly"]);
+        assert_eq!(adverbs("It ended ABRUPTLY.", false), ["ABRUPTLY"]);
+    }
+
+    #[test]
+    fn near_verbs_only_keeps_adverbs_beside_a_verb() {
+        let sentence = "She said softly that the room was really quiet and ran quickly home.";
+        assert_eq!(adverbs(sentence, false), ["softly", "really", "quickly"]);
+        assert_eq!(adverbs(sentence, true), ["softly", "quickly"]);
+        // Punctuation separates the adverb from the verb
+        assert!(adverbs("He walked, happily.", true).is_empty());
+    }
+
+    #[test]
+    fn adverb_ratio_counts_the_adverbs_that_are_suggested() {
+        let text = "My family only arrived early. Kelly quickly and angrily left.";
+        let processor = TextProcessor::new();
+        let report = processor.build_optimization_report(text);
+        let flagged: Vec<&str> = report
+            .suggestions
+            .iter()
+            .filter(|s| s.suggestion_type == "adverb_usage" && s.scope == SCOPE_INSTANCE)
+            .map(|s| &text[s.start_pos..s.end_pos])
+            .collect();
+        assert_eq!(flagged, ["quickly", "angrily"]);
+        let analysis = processor.perform_analysis(text);
+        assert_eq!(analysis.style_metrics.adverb_ratio, 2.0 / analysis.word_count as f64);
+
+        let near_verbs =
+            TextProcessor::with_patterns(&Default::default(), SuggestionConfig { analysis: AnalysisOptions { adverbs_near_verbs_only: true, ..Default::default() }, ..Default::default() });
+        assert_eq!(near_verbs.perform_analysis(text).style_metrics.adverb_ratio, 1.0 / analysis.word_count as f64);
+    }
+}
@@ -0,0 +1,385 @@
+//! Dialogue tags: the "she said" attached to quoted speech, found before a
+//! quotation, after it, or interrupting it, and classed as plain ("said",
+//! "asked"), neutral ("replied"), or said-bookisms ("exclaimed"), with any
+//! adverb carrying the tag ("said angrily").
+
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::adverbs;
+use crate::lang;
+use crate::{OptimizationSuggestion, RelatedSpan};
+
+const PLAIN_VERBS: &[&str] = &["ask", "asked", "asks", "say", "said", "says"];
+
+/// Speech verbs that tag a line without calling attention to themselves.
+const NEUTRAL_VERBS: &[&str] = &[
+    "added", "agreed", "answered", "began", "called", "continued", "explained", "repeated", "replied", "responded",
+    "told", "wrote",
+];
+
+const BOOKISMS: &[&str] = &[
+    "admitted", "announced", "argued", "barked", "begged", "bellowed", "blurted", "boomed", "breathed", "chided",
+    "chirped", "chuckled", "countered", "cried", "croaked", "declared", "demanded", "exclaimed", "gasped", "giggled",
+    "groaned", "growled", "grumbled", "grunted", "hissed", "howled", "inquired", "insisted", "interjected",
+    "interrupted", "laughed", "moaned", "mumbled", "murmured", "muttered", "opined", "pleaded", "proclaimed",
+    "protested", "purred", "queried", "remarked", "retorted", "roared", "scoffed", "scolded", "screamed", "shouted",
+    "shrieked", "sighed", "smirked", "snapped", "snarled", "sneered", "sobbed", "spat", "stammered", "stuttered",
+    "teased", "wailed", "whimpered", "whined", "whispered", "yelled",
+];
+
+const PRONOUNS: &[&str] = &["he", "she", "they", "i", "we", "you", "it"];
+
+/// Words that can open a noun phrase naming the speaker ("the old man").
+const DETERMINERS: &[&str] = &["a", "an", "her", "his", "my", "our", "the", "their", "your"];
+
+/// Words after a determiner before the speech verb must come.
+const MAX_DESCRIBED_WORDS: usize = 2;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TagKind {
+    Plain,
+    Neutral,
+    Bookism,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TagPosition {
+    Before,
+    After,
+    /// Between two quotations of one utterance ("Well," he said, "maybe.").
+    Interrupting,
+}
+
+#[derive(Serialize)]
+pub struct DialogueTag {
+    pub start_pos: usize,
+    pub end_pos: usize,
+    /// Lowercased, as written.
+    pub verb: String,
+    pub kind: TagKind,
+    pub adverb: Option<String>,
+    pub position: TagPosition,
+    /// The quotations the tag attributes, two for an interrupting tag.
+    pub quotes: Vec<RelatedSpan>,
+}
+
+#[derive(Serialize)]
+pub struct DialogueReport {
+    pub tags: Vec<DialogueTag>,
+    /// Quotations, counting an interrupted utterance once.
+    pub utterance_count: usize,
+    /// Shares of `tags`; 0 when there are none.
+    pub said_ratio: f64,
+    pub bookism_ratio: f64,
+    pub adverb_tag_ratio: f64,
+}
+
+/// The class of speech verb `word` is, trying the past tense of a present
+/// form ("whispers", "hisses", "replies").
+fn verb_kind(word: &str) -> Option<TagKind> {
+    let lower = word.to_lowercase();
+    let mut forms = vec![lower.clone()];
+    if let Some(stem) = lower.strip_suffix("ies") {
+        forms.push(format!("{}ied", stem));
+    }
+    if let Some(stem) = lower.strip_suffix("es") {
+        forms.push(format!("{}ed", stem));
+    }
+    if let Some(stem) = lower.strip_suffix('s') {
+        forms.push(format!("{}ed", stem));
+        forms.push(format!("{}d", stem));
+    }
+    forms.iter().find_map(|form| {
+        if PLAIN_VERBS.contains(&form.as_str()) {
+            Some(TagKind::Plain)
+        } else if NEUTRAL_VERBS.contains(&form.as_str()) {
+            Some(TagKind::Neutral)
+        } else if BOOKISMS.contains(&form.as_str()) {
+            Some(TagKind::Bookism)
+        } else {
+            None
+        }
+    })
+}
+
+fn is_pronoun(word: &str) -> bool {
+    PRONOUNS.contains(&word.to_lowercase().as_str())
+}
+
+fn is_name(word: &str) -> bool {
+    word.starts_with(char::is_uppercase) && verb_kind(word).is_none() && !DETERMINERS.contains(&word.to_lowercase().as_str())
+}
+
+/// Word indexes just past each way the speaker can be named from `words[k]`:
+/// a pronoun, one or two capitalized words, or a determiner and a few words.
+fn subject_ends(words: &[(usize, &str)], k: usize) -> Vec<usize> {
+    let Some(&(_, word)) = words.get(k) else { return Vec::new() };
+    if is_pronoun(word) {
+        return vec![k + 1];
+    }
+    if DETERMINERS.contains(&word.to_lowercase().as_str()) {
+        return (k + 2..=(k + 1 + MAX_DESCRIBED_WORDS).min(words.len())).collect();
+    }
+    if is_name(word) {
+        let mut ends = vec![k + 1];
+        if words.get(k + 1).is_some_and(|w| is_name(w.1)) {
+            ends.push(k + 2);
+        }
+        return ends;
+    }
+    Vec::new()
+}
+
+fn adverb_at<'t>(words: &[(usize, &'t str)], k: usize) -> Option<&'t str> {
+    words.get(k).map(|w| w.1).filter(|w| adverbs::is_adverb(w, &lang::ENGLISH))
+}
+
+/// A tag found among `words`: first and last word index, verb, adverb.
+struct Parsed<'t> {
+    first: usize,
+    last: usize,
+    verb: &'t str,
+    adverb: Option<&'t str>,
+}
+
+/// A tag opening `words`: "she said", "said Harry", "the old man said
+/// softly", "she quietly said".
+fn tag_at_start<'t>(words: &[(usize, &'t str)]) -> Option<Parsed<'t>> {
+    // "said Harry", "said the old man softly"
+    if words.first().is_some_and(|w| verb_kind(w.1).is_some()) {
+        let ends = subject_ends(words, 1);
+        let end = ends.iter().rev().find(|&&e| e == words.len() || adverb_at(words, e).is_some()).or(ends.first());
+        if let Some(&end) = end {
+            let adverb = adverb_at(words, end);
+            return Some(Parsed { first: 0, last: end - 1 + adverb.is_some() as usize, verb: words[0].1, adverb });
+        }
+    }
+    for end in subject_ends(words, 0) {
+        let before = adverb_at(words, end);
+        let v = end + before.is_some() as usize;
+        if words.get(v).is_some_and(|w| verb_kind(w.1).is_some()) {
+            let after = adverb_at(words, v + 1);
+            return Some(Parsed { first: 0, last: v + after.is_some() as usize, verb: words[v].1, adverb: before.or(after) });
+        }
+    }
+    None
+}
+
+/// A tag closing `words`: "She said", "Then the old man whispered softly".
+fn tag_at_end<'t>(words: &[(usize, &'t str)]) -> Option<Parsed<'t>> {
+    let n = words.len();
+    let after = n.checked_sub(1).and_then(|k| adverb_at(words, k));
+    let v = n.checked_sub(1 + after.is_some() as usize)?;
+    verb_kind(words[v].1)?;
+    let before = v.checked_sub(1).and_then(|k| adverb_at(words, k));
+    let subject_end = v - before.is_some() as usize;
+    // The nearest start naming the speaker, so "Then Mary said" is "Mary said"
+    let first = (subject_end.saturating_sub(1 + MAX_DESCRIBED_WORDS)..subject_end)
+        .rev()
+        .find(|&k| subject_ends(words, k).contains(&subject_end))?;
+    Some(Parsed { first, last: n - 1, verb: words[v].1, adverb: before.or(after) })
+}
+
+fn words_between(text: &str, start: usize, end: usize) -> Vec<(usize, &str)> {
+    text[start..end].unicode_word_indices().map(|(i, w)| (start + i, w)).collect()
+}
+
+fn tag(words: &[(usize, &str)], parsed: &Parsed, position: TagPosition, quotes: &[(usize, usize)]) -> DialogueTag {
+    DialogueTag {
+        start_pos: words[parsed.first].0,
+        end_pos: words[parsed.last].0 + words[parsed.last].1.len(),
+        verb: parsed.verb.to_lowercase(),
+        kind: verb_kind(parsed.verb).unwrap_or(TagKind::Plain),
+        adverb: parsed.adverb.map(str::to_lowercase),
+        position,
+        quotes: quotes.iter().map(|&(s, e)| RelatedSpan { start_pos: s, end_pos: e }).collect(),
+    }
+}
+
+/// Where the clause after a quotation ending at `from` stops: a sentence or
+/// clause break, a line break, or the next quotation.
+fn clause_end(text: &str, from: usize, next_quote: Option<usize>) -> usize {
+    let limit = next_quote.unwrap_or(text.len()).max(from);
+    let from = limit - text[from..limit].trim_start_matches([',', ' ']).len();
+    text[from..limit].find(['.', '!', '?', ';', ',', '\n', '\u{2014}']).map_or(limit, |i| from + i)
+}
+
+/// Where the clause before a quotation starting at `to` begins, not before
+/// the previous quotation's end.
+fn clause_start(text: &str, to: usize, previous_quote: usize) -> usize {
+    text[previous_quote..to]
+        .rmatch_indices(['.', '!', '?', ';', '\n', '\u{2014}'])
+        .next()
+        .map_or(previous_quote, |(i, mark)| previous_quote + i + mark.len())
+}
+
+/// Every dialogue tag attached to the quotations at `quote_spans`.
+pub fn analyze(text: &str, quote_spans: &[(usize, usize)]) -> DialogueReport {
+    let mut tags = Vec::new();
+    let mut utterance_count = 0;
+    let mut k = 0;
+    while k < quote_spans.len() {
+        let (start, end) = quote_spans[k];
+        let next = quote_spans.get(k + 1).copied();
+        utterance_count += 1;
+        k += 1;
+
+        // "Go." She said nothing. starts a new sentence rather than tagging
+        let closes_sentence = text[start..end].trim_end_matches(['"', '\u{201D}', '\u{2019}', '\'']).ends_with('.');
+        let words = words_between(text, end, clause_end(text, end, next.map(|n| n.0)));
+        if let Some(parsed) = tag_at_start(&words).filter(|_| !closes_sentence) {
+            let tag_end = words[parsed.last].0 + words[parsed.last].1.len();
+            // "Well," he said, "maybe." is one utterance
+            let resumes = next.filter(|n| text[tag_end..n.0].chars().all(|c| c == ',' || c == ' '));
+            match resumes {
+                Some(resumed) => {
+                    tags.push(tag(&words, &parsed, TagPosition::Interrupting, &[(start, end), resumed]));
+                    k += 1;
+                }
+                None => tags.push(tag(&words, &parsed, TagPosition::After, &[(start, end)])),
+            }
+            continue;
+        }
+
+        // She said, "..."
+        let previous_end = if k >= 2 { quote_spans[k - 2].1 } else { 0 };
+        let lead_start = clause_start(text, start, previous_end.min(start));
+        let lead = text[lead_start..start].trim_end();
+        if let Some(body) = lead.strip_suffix([',', ':']) {
+            let words = words_between(text, lead_start, lead_start + body.len());
+            if let Some(parsed) = tag_at_end(&words) {
+                tags.push(tag(&words, &parsed, TagPosition::Before, &[(start, end)]));
+            }
+        }
+    }
+
+    let share = |n: usize| if tags.is_empty() { 0.0 } else { n as f64 / tags.len() as f64 };
+    let said_ratio = share(tags.iter().filter(|t| t.kind == TagKind::Plain).count());
+    let bookism_ratio = share(tags.iter().filter(|t| t.kind == TagKind::Bookism).count());
+    let adverb_tag_ratio = share(tags.iter().filter(|t| t.adverb.is_some()).count());
+    DialogueReport { tags, utterance_count, said_ratio, bookism_ratio, adverb_tag_ratio }
+}
+
+/// A `dialogue_tag` suggestion over each said-bookism and each tag carried
+/// by an adverb.
+pub fn suggestions(report: &DialogueReport) -> Vec<OptimizationSuggestion> {
+    report
+        .tags
+        .iter()
+        .filter_map(|tag| {
+            let message = match (tag.kind == TagKind::Bookism, &tag.adverb) {
+                (true, Some(adverb)) => format!(
+                    "\"{}\" and \"{}\" both tell how the line is spoken; a plain \"said\" lets the dialogue show it.",
+                    tag.verb, adverb
+                ),
+                (true, None) => {
+                    format!("\"{}\" draws attention to the tag; \"said\" or \"asked\" usually reads invisibly.", tag.verb)
+                }
+                (false, Some(adverb)) => format!("\"{}\" tells how the line is spoken; let the dialogue show it.", adverb),
+                (false, None) => return None,
+            };
+            Some(OptimizationSuggestion::instance("dialogue_tag", "low", message, tag.start_pos, tag.end_pos))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue;
+    use crate::TextProcessor;
+    use crate::suggestions::SCOPE_INSTANCE;
+
+    fn report(text: &str) -> DialogueReport {
+        analyze(text, &dialogue::spans(text))
+    }
+
+    /// Each tag as (text, verb, adverb).
+    fn tags(text: &str) -> Vec<(&str, String, Option<String>)> {
+        report(text).tags.into_iter().map(|t| (&text[t.start_pos..t.end_pos], t.verb, t.adverb)).collect()
+    }
+
+    #[test]
+    fn tags_after_a_quotation_are_found_with_their_speaker() {
+        let text = "\"Come here,\" she whispered. \"Why?\" asked Harry. \"Now,\" the old man said softly.";
+        let found = tags(text);
+        assert_eq!(
+            found,
+            [
+                ("she whispered", "whispered".to_string(), None),
+                ("asked Harry", "asked".to_string(), None),
+                ("the old man said softly", "said".to_string(), Some("softly".to_string())),
+            ]
+        );
+        assert!(report(text).tags.iter().all(|t| t.position == TagPosition::After));
+    }
+
+    #[test]
+    fn tags_before_a_quotation_are_found() {
+        let text = "She said, \"Stay.\" Then Mary shouted: \"Run!\"";
+        let r = report(text);
+        let found: Vec<&str> = r.tags.iter().map(|t| &text[t.start_pos..t.end_pos]).collect();
+        assert_eq!(found, ["She said", "Mary shouted"]);
+        assert!(r.tags.iter().all(|t| t.position == TagPosition::Before));
+        assert_eq!(&text[r.tags[0].quotes[0].start_pos..r.tags[0].quotes[0].end_pos], "\"Stay.\"");
+    }
+
+    #[test]
+    fn an_interrupting_tag_makes_one_utterance() {
+        for text in ["\"Well,\" he said, \"maybe.\"", "\u{201C}Well,\u{201D} he said, \u{201C}maybe.\u{201D}"] {
+            let r = report(text);
+            assert_eq!(r.utterance_count, 1, "{}", text);
+            assert_eq!(r.tags.len(), 1);
+            assert!(r.tags[0].position == TagPosition::Interrupting);
+            let quotes: Vec<&str> = r.tags[0].quotes.iter().map(|q| &text[q.start_pos..q.end_pos]).collect();
+            assert_eq!(quotes.len(), 2);
+            assert!(quotes[1].contains("maybe"));
+        }
+    }
+
+    #[test]
+    fn a_quotation_closing_its_sentence_is_not_tagged_by_the_next() {
+        let text = "\"Go.\" She said nothing for a while.";
+        assert!(report(text).tags.is_empty());
+        assert_eq!(report(text).utterance_count, 1);
+    }
+
+    #[test]
+    fn present_tense_tags_are_classed_like_past_tense_ones() {
+        let r = report("\"Hush,\" she whispers. \"Fine,\" he replies. \"Why?\" she asks.");
+        let kinds: Vec<TagKind> = r.tags.iter().map(|t| t.kind).collect();
+        assert!(kinds == [TagKind::Bookism, TagKind::Neutral, TagKind::Plain]);
+    }
+
+    #[test]
+    fn ratios_are_shares_of_the_tags() {
+        let r = report("\"Yes,\" she said. \"No,\" he retorted angrily. \"Maybe,\" she said quietly. \"Go,\" he replied.");
+        assert_eq!((r.said_ratio, r.bookism_ratio, r.adverb_tag_ratio), (0.5, 0.25, 0.5));
+        let empty = report("No dialogue here.");
+        assert_eq!((empty.said_ratio, empty.bookism_ratio, empty.adverb_tag_ratio, empty.utterance_count), (0.0, 0.0, 0.0, 0));
+    }
+
+    #[test]
+    fn suggestions_cover_bookisms_and_adverbed_tags_only() {
+        let text = "\"Yes,\" she said. \"No,\" he retorted angrily. \"Maybe,\" she said quietly. \"Stop,\" he hissed.";
+        let spans: Vec<&str> = suggestions(&report(text)).iter().map(|s| &text[s.start_pos..s.end_pos]).collect();
+        assert_eq!(spans, ["he retorted angrily", "she said quietly", "he hissed"]);
+        assert!(suggestions(&report(text)).iter().all(|s| s.suggestion_type == "dialogue_tag"));
+    }
+
+    #[test]
+    fn dialogue_tag_suggestions_cover_the_tag() {
+        let text = "\u{201C}Leave,\u{201D} he snarled. \u{201C}Fine,\u{201D} she said.";
+        let report = TextProcessor::new().build_optimization_report(text);
+        let tags: Vec<&str> = report
+            .suggestions
+            .iter()
+            .filter(|s| s.suggestion_type == "dialogue_tag" && s.scope == SCOPE_INSTANCE)
+            .map(|s| &text[s.start_pos..s.end_pos])
+            .collect();
+        assert_eq!(tags, ["he snarled"]);
+    }
+}
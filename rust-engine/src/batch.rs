@@ -0,0 +1,154 @@
+//! Many documents in one call, for a project dashboard: each is analyzed as
+//! `analyze_text` would analyze it, with totals across the set.
+
+use serde::{Deserialize, Serialize};
+
+use crate::TextAnalysisResult;
+
+/// A bare string (identified by its position) or an `{id, text}` object.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum BatchDocument {
+    Text(String),
+    Named { id: String, text: String },
+}
+
+impl BatchDocument {
+    pub fn into_parts(self, position: usize) -> (String, String) {
+        match self {
+            BatchDocument::Text(text) => (position.to_string(), text),
+            BatchDocument::Named { id, text } => (id, text),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct DocumentShare {
+    pub id: String,
+    pub word_count: usize,
+    /// Fraction of the corpus's words.
+    pub share: f64,
+}
+
+#[derive(Serialize, Default)]
+pub struct BatchSummary {
+    pub document_count: usize,
+    pub total_words: usize,
+    pub word_shares: Vec<DocumentShare>,
+    /// Distinct lowercased words across every document.
+    pub vocabulary_size: usize,
+    /// Mean readability score of the documents that have words.
+    pub average_readability: f64,
+    pub most_readable: Option<String>,
+    pub least_readable: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BatchAnalysis {
+    pub results: Vec<TextAnalysisResult>,
+    pub summary: BatchSummary,
+}
+
+/// `ids` and `results` are in document order; `vocabulary_size` counts the
+/// distinct lowercased words of them all.
+pub fn summarize(ids: Vec<String>, results: &[TextAnalysisResult], vocabulary_size: usize) -> BatchSummary {
+    let total_words: usize = results.iter().map(|r| r.word_count).sum();
+    let measured: Vec<(&String, f64)> =
+        ids.iter().zip(results).filter(|(_, r)| r.word_count > 0).map(|(id, r)| (id, r.readability_score)).collect();
+    let average_readability = if measured.is_empty() {
+        0.0
+    } else {
+        measured.iter().fold(0.0, |sum, m| sum + m.1) / measured.len() as f64
+    };
+    // Ties go to the earlier document
+    let most_readable = measured.iter().fold(None, |best: Option<&(&String, f64)>, m| match best {
+        Some(b) if b.1 >= m.1 => Some(b),
+        _ => Some(m),
+    });
+    let least_readable = measured.iter().fold(None, |best: Option<&(&String, f64)>, m| match best {
+        Some(b) if b.1 <= m.1 => Some(b),
+        _ => Some(m),
+    });
+
+    BatchSummary {
+        document_count: results.len(),
+        total_words,
+        most_readable: most_readable.map(|m| m.0.clone()),
+        least_readable: least_readable.map(|m| m.0.clone()),
+        word_shares: ids
+            .into_iter()
+            .zip(results)
+            .map(|(id, r)| DocumentShare {
+                id,
+                word_count: r.word_count,
+                share: if total_words > 0 { r.word_count as f64 / total_words as f64 } else { 0.0 },
+            })
+            .collect(),
+        vocabulary_size,
+        average_readability,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    fn result(word_count: usize, readability_score: f64) -> TextAnalysisResult {
+        TextAnalysisResult { word_count, readability_score, ..Default::default() }
+    }
+
+    #[test]
+    fn documents_are_strings_or_named_objects() {
+        let documents: Vec<BatchDocument> =
+            serde_json::from_value(serde_json::json!(["One text.", {"id": "ch2", "text": "Two."}])).unwrap();
+        let parts: Vec<(String, String)> = documents.into_iter().enumerate().map(|(i, d)| d.into_parts(i)).collect();
+        assert_eq!(parts, [("0".to_string(), "One text.".to_string()), ("ch2".to_string(), "Two.".to_string())]);
+        assert!(serde_json::from_value::<Vec<BatchDocument>>(serde_json::json!([{"text": "no id"}])).is_err());
+    }
+
+    #[test]
+    fn summary_shares_and_readability_extremes() {
+        let ids = vec!["a".to_string(), "b".to_string(), "empty".to_string(), "c".to_string()];
+        let results = [result(30, 60.0), result(10, 80.0), result(0, 0.0), result(60, 80.0)];
+        let summary = summarize(ids, &results, 0);
+        assert_eq!((summary.document_count, summary.total_words), (4, 100));
+        let shares: Vec<f64> = summary.word_shares.iter().map(|s| s.share).collect();
+        assert_eq!(shares, [0.3, 0.1, 0.0, 0.6]);
+        // The empty document has no readability to average or rank
+        assert!((summary.average_readability - 220.0 / 3.0).abs() < 1e-9);
+        assert_eq!(summary.most_readable.as_deref(), Some("b"));
+        assert_eq!(summary.least_readable.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn an_empty_batch_has_a_zeroed_summary() {
+        let summary = summarize(Vec::new(), &[], 0);
+        assert_eq!((summary.document_count, summary.total_words, summary.vocabulary_size), (0, 0, 0));
+        assert_eq!(summary.average_readability, 0.0);
+        assert!(summary.word_shares.is_empty() && summary.most_readable.is_none() && summary.least_readable.is_none());
+    }
+
+    #[test]
+    fn batch_results_match_separate_analyses() {
+        let processor = TextProcessor::new();
+        let chapters = ["The storm came at night. Everyone hid.", "", "Mara walked to the river and the river was cold."];
+        let documents = chapters.iter().map(|&text| BatchDocument::Text(text.to_string())).collect();
+        let batch = processor.analyze_batch(documents);
+        assert_eq!(batch.results.len(), 3);
+        for (result, text) in batch.results.iter().zip(chapters) {
+            let mut alone = processor.perform_analysis(text);
+            processor.config.output.apply_to_analysis(&mut alone);
+            assert_eq!(serde_json::to_value(result).unwrap(), serde_json::to_value(&alone).unwrap());
+        }
+        assert_eq!(batch.summary.total_words, 17);
+        // "the" and "river" are counted once across documents
+        assert_eq!(batch.summary.vocabulary_size, 14);
+        let ids: Vec<&str> = batch.summary.word_shares.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["0", "1", "2"]);
+
+        let empty = processor.analyze_batch(Vec::new());
+        assert!(empty.results.is_empty());
+        assert_eq!((empty.summary.total_words, empty.summary.average_readability), (0, 0.0));
+    }
+}
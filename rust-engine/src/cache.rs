@@ -0,0 +1,152 @@
+//! Least-recently-used cache of `analyze_text` results, keyed by content
+//! hash, for apps that analyze the same unchanged text from several places.
+
+use serde::{Deserialize, Serialize};
+
+use crate::TextAnalysisResult;
+
+pub const DEFAULT_CAPACITY: usize = 8;
+
+#[derive(Serialize, Deserialize)]
+pub struct CacheStats {
+    pub capacity: usize,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Capacities are small, so entries live in a vector ordered from least to
+/// most recently used.
+pub struct AnalysisCache {
+    capacity: usize,
+    entries: Vec<(String, TextAnalysisResult)>,
+    hits: u64,
+    misses: u64,
+}
+
+impl AnalysisCache {
+    pub fn new(capacity: usize) -> AnalysisCache {
+        AnalysisCache { capacity, entries: Vec::new(), hits: 0, misses: 0 }
+    }
+
+    /// The stored result for `hash`, marked most recently used.
+    pub fn get(&mut self, hash: &str) -> Option<TextAnalysisResult> {
+        let Some(i) = self.entries.iter().position(|(key, _)| key == hash) else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        let entry = self.entries.remove(i);
+        let result = entry.1.clone();
+        self.entries.push(entry);
+        Some(result)
+    }
+
+    pub fn insert(&mut self, hash: String, result: TextAnalysisResult) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.retain(|(key, _)| *key != hash);
+        self.entries.push((hash, result));
+        self.evict();
+    }
+
+    /// 0 disables caching. Shrinking evicts the least recently used entries.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Drops every entry; the hit and miss counters are kept.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats { capacity: self.capacity, entries: self.entries.len(), hits: self.hits, misses: self.misses }
+    }
+
+    fn evict(&mut self) {
+        let excess = self.entries.len().saturating_sub(self.capacity);
+        self.entries.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    fn result(word_count: usize) -> TextAnalysisResult {
+        TextAnalysisResult { word_count, ..Default::default() }
+    }
+
+    fn keys(cache: &AnalysisCache) -> Vec<&str> {
+        cache.entries.iter().map(|(key, _)| key.as_str()).collect()
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted_first() {
+        let mut cache = AnalysisCache::new(3);
+        for (i, key) in ["a", "b", "c"].into_iter().enumerate() {
+            cache.insert(key.to_string(), result(i));
+        }
+        assert_eq!(cache.get("a").map(|r| r.word_count), Some(0));
+        cache.insert("d".to_string(), result(3));
+        assert_eq!(keys(&cache), ["c", "a", "d"]);
+        assert!(cache.get("b").is_none());
+
+        // Re-inserting a key replaces it rather than storing it twice
+        cache.insert("c".to_string(), result(9));
+        assert_eq!(keys(&cache), ["a", "d", "c"]);
+        assert_eq!(cache.get("c").map(|r| r.word_count), Some(9));
+
+        let stats = cache.stats();
+        assert_eq!((stats.capacity, stats.entries, stats.hits, stats.misses), (3, 3, 2, 1));
+    }
+
+    #[test]
+    fn shrinking_evicts_and_zero_disables() {
+        let mut cache = AnalysisCache::new(4);
+        for key in ["a", "b", "c", "d"] {
+            cache.insert(key.to_string(), result(0));
+        }
+        cache.set_capacity(2);
+        assert_eq!(keys(&cache), ["c", "d"]);
+        cache.set_capacity(0);
+        cache.insert("e".to_string(), result(0));
+        assert!(keys(&cache).is_empty());
+
+        cache.set_capacity(2);
+        cache.insert("f".to_string(), result(0));
+        cache.get("f");
+        cache.clear();
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits), (0, 1));
+    }
+
+    #[test]
+    fn unchanged_text_is_served_from_the_analysis_cache() {
+        let mut processor = TextProcessor::new();
+        let text = "The storm came at night. Mara was woken by the thunder.";
+        let first = serde_json::to_value(processor.analyze_text(text)).unwrap();
+        let second = serde_json::to_value(processor.analyze_text(text)).unwrap();
+        assert_eq!(first, second);
+        let stats = processor.cache.borrow().stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (1, 1, 1));
+
+        processor.analyze_text("Something else entirely.");
+        processor.set_cache_capacity(1);
+        assert!(processor.analyze_text(text).word_count > 0);
+        let stats = processor.cache.borrow().stats();
+        assert_eq!((stats.capacity, stats.entries, stats.hits, stats.misses), (1, 1, 1, 3));
+
+        processor.clear_cache();
+        processor.analyze_text(text);
+        assert_eq!(processor.cache.borrow().stats().misses, 4);
+    }
+}
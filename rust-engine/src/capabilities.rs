@@ -0,0 +1,190 @@
+//! Runtime description of what this build of the engine supports, so the
+//! frontend can gate UI on capabilities instead of probing for methods.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::chunks::ChunkMode;
+use crate::config::{DifficultyNormalization, InputFormat, Profile};
+use crate::conflicts::CONFLICT_TYPES;
+use crate::context::ContextMode;
+use crate::gap;
+use crate::hashing;
+use crate::lang;
+use crate::language;
+use crate::screening;
+use crate::merge::MARKER_SOURCES;
+use crate::readability;
+use crate::report;
+use crate::resolution::ConflictPolicy;
+use crate::similarity::SimilarityMethod;
+use crate::suggestions::{SCOPE_DOCUMENT, SCOPE_INSTANCE};
+
+#[derive(Serialize, Clone, Copy)]
+pub struct RuleInfo {
+    pub id: &'static str,
+    pub version: u32,
+    pub category: &'static str,
+    /// Language the rule's patterns are written for; `None` if it applies to any.
+    pub language: Option<&'static str>,
+}
+
+/// Document shape and mechanics: kept in every profile, including drafting.
+pub const CATEGORY_STRUCTURAL: &str = "structural";
+/// Word-level style nits, suppressed by the drafting profile.
+pub const CATEGORY_STYLE: &str = "style";
+/// Content the author asked to be warned about, kept in every profile.
+pub const CATEGORY_RISK: &str = "risk";
+
+pub const CATEGORIES: &[&str] = &[CATEGORY_STRUCTURAL, CATEGORY_STYLE, CATEGORY_RISK];
+
+/// Every suggestion rule the engine can emit. Bump a rule's version whenever
+/// its matching behavior changes in a way consumers may notice.
+pub const RULES: &[RuleInfo] = &[
+    RuleInfo { id: "sentence_length", version: 1, category: CATEGORY_STRUCTURAL, language: None },
+    RuleInfo { id: "passive_voice", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+    RuleInfo { id: "adverb_usage", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+    RuleInfo { id: "first_person", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+    RuleInfo { id: "second_person", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+    RuleInfo { id: "filter_word", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+    RuleInfo { id: "dialogue_tag", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+    RuleInfo { id: "filler_word", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+    RuleInfo { id: "consistency", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+    RuleInfo { id: "flagged_term", version: 1, category: CATEGORY_RISK, language: None },
+    RuleInfo { id: "chapter_opener_echo", version: 1, category: CATEGORY_STYLE, language: None },
+    RuleInfo { id: "chapter_closer_echo", version: 1, category: CATEGORY_STYLE, language: None },
+    RuleInfo { id: "repetitive_opener", version: 1, category: CATEGORY_STYLE, language: None },
+    RuleInfo { id: "word_echo", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+    RuleInfo { id: "unbalanced_quotes", version: 2, category: CATEGORY_STRUCTURAL, language: None },
+    RuleInfo { id: "repeated_paragraph", version: 1, category: CATEGORY_STRUCTURAL, language: None },
+    RuleInfo { id: "missing_punctuation", version: 1, category: CATEGORY_STRUCTURAL, language: None },
+    RuleInfo { id: "double_space", version: 1, category: CATEGORY_STRUCTURAL, language: None },
+    RuleInfo { id: "tab_in_text", version: 1, category: CATEGORY_STRUCTURAL, language: None },
+    RuleInfo { id: "space_before_punctuation", version: 1, category: CATEGORY_STRUCTURAL, language: Some("en") },
+    RuleInfo { id: "missing_space_after_punctuation", version: 1, category: CATEGORY_STRUCTURAL, language: None },
+    RuleInfo { id: "repeated_punctuation", version: 1, category: CATEGORY_STRUCTURAL, language: None },
+    RuleInfo { id: "unmatched_parenthesis", version: 1, category: CATEGORY_STRUCTURAL, language: None },
+    RuleInfo { id: "mixed_quotes", version: 1, category: CATEGORY_STRUCTURAL, language: None },
+    RuleInfo { id: "undefined_acronym", version: 1, category: CATEGORY_STYLE, language: None },
+    RuleInfo { id: "weak_verb", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+    RuleInfo { id: "nominalization", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+    RuleInfo { id: "expletive_opener", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+    RuleInfo { id: "cliche", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+    RuleInfo { id: "possible_typo", version: 1, category: CATEGORY_STYLE, language: Some("en") },
+];
+
+/// Category of a rule id; unknown ids are treated as style.
+pub fn rule_category(id: &str) -> &'static str {
+    RULES.iter().find(|r| r.id == id).map_or(CATEGORY_STYLE, |r| r.category)
+}
+
+/// Whether a rule only makes sense for text in its own language.
+pub fn rule_is_language_specific(id: &str) -> bool {
+    RULES.iter().any(|r| r.id == id && r.language.is_some())
+}
+
+pub const PRIORITIES: &[&str] = &["low", "medium", "high"];
+pub const SCOPES: &[&str] = &[SCOPE_INSTANCE, SCOPE_DOCUMENT];
+
+// Cargo features compiled into this build; add cfg-gated entries as features are introduced.
+const FEATURES: &[&str] = &[
+    #[cfg(feature = "wasm")]
+    "wasm",
+];
+
+#[derive(Serialize)]
+pub struct EngineInfo {
+    pub engine_version: &'static str,
+    pub git_hash: Option<&'static str>,
+    pub features: Vec<&'static str>,
+    pub languages: Vec<&'static str>,
+    pub rules: Vec<RuleInfo>,
+    pub hash_algorithms: Vec<&'static str>,
+    pub hash_encodings: Vec<&'static str>,
+    pub input_formats: Vec<&'static str>,
+    pub option_values: BTreeMap<&'static str, Vec<&'static str>>,
+}
+
+pub fn engine_info() -> EngineInfo {
+    let mut option_values = BTreeMap::new();
+    option_values.insert("priority", PRIORITIES.to_vec());
+    option_values.insert("scope", SCOPES.to_vec());
+    option_values.insert("profile", Profile::ALL.to_vec());
+    option_values.insert("difficulty_normalization", DifficultyNormalization::ALL.to_vec());
+    option_values.insert("input_format", InputFormat::ALL.to_vec());
+    option_values.insert("difficulty_bucket", readability::DIFFICULTY_BUCKETS.to_vec());
+    option_values.insert("context_mode", ContextMode::ALL.to_vec());
+    option_values.insert("rule_category", CATEGORIES.to_vec());
+    option_values.insert("detected_language", language::DETECTABLE_LANGUAGES.to_vec());
+    option_values.insert("merge_marker_source", MARKER_SOURCES.to_vec());
+    option_values.insert("conflict_policy", ConflictPolicy::ALL.to_vec());
+    option_values.insert("similarity_method", SimilarityMethod::ALL.to_vec());
+    option_values.insert("screening_category", screening::BUILTIN_CATEGORIES.to_vec());
+    option_values.insert("report_format", report::FORMATS.to_vec());
+    option_values.insert("readability_target", gap::target_names());
+    option_values.insert("chunk_mode", ChunkMode::ALL.to_vec());
+    option_values.insert("conflict_type", CONFLICT_TYPES.to_vec());
+
+    EngineInfo {
+        engine_version: env!("CARGO_PKG_VERSION"),
+        git_hash: option_env!("OMNIAUTHOR_GIT_HASH").filter(|h| !h.is_empty()),
+        features: FEATURES.to_vec(),
+        languages: lang::LANGUAGES.iter().map(|l| l.code).collect(),
+        rules: RULES.to_vec(),
+        hash_algorithms: hashing::ALGORITHMS.to_vec(),
+        hash_encodings: hashing::ENCODINGS.to_vec(),
+        input_formats: InputFormat::ALL.to_vec(),
+        option_values,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    #[test]
+    fn report_lists_every_rule_and_option_value() {
+        let report = engine_info();
+        assert_eq!(report.rules.iter().map(|r| r.id).collect::<Vec<_>>(), RULES.iter().map(|r| r.id).collect::<Vec<_>>());
+        let expected: Vec<(&str, Vec<&str>)> = vec![
+            ("chunk_mode", ChunkMode::ALL.to_vec()),
+            ("conflict_policy", ConflictPolicy::ALL.to_vec()),
+            ("conflict_type", CONFLICT_TYPES.to_vec()),
+            ("context_mode", ContextMode::ALL.to_vec()),
+            ("detected_language", language::DETECTABLE_LANGUAGES.to_vec()),
+            ("difficulty_bucket", readability::DIFFICULTY_BUCKETS.to_vec()),
+            ("difficulty_normalization", DifficultyNormalization::ALL.to_vec()),
+            ("input_format", InputFormat::ALL.to_vec()),
+            ("merge_marker_source", MARKER_SOURCES.to_vec()),
+            ("priority", PRIORITIES.to_vec()),
+            ("profile", Profile::ALL.to_vec()),
+            ("readability_target", gap::target_names()),
+            ("report_format", report::FORMATS.to_vec()),
+            ("rule_category", CATEGORIES.to_vec()),
+            ("scope", SCOPES.to_vec()),
+            ("screening_category", screening::BUILTIN_CATEGORIES.to_vec()),
+            ("similarity_method", SimilarityMethod::ALL.to_vec()),
+        ];
+        assert_eq!(report.option_values.into_iter().collect::<Vec<_>>(), expected);
+        assert!(RULES.iter().all(|r| CATEGORIES.contains(&r.category)));
+        assert_eq!(report.engine_version, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn features_list_what_was_compiled_in() {
+        assert_eq!(engine_info().features.contains(&"wasm"), cfg!(feature = "wasm"));
+    }
+
+    #[test]
+    fn every_emitted_suggestion_is_a_registered_rule() {
+        let long = vec!["word"; 40].join(" ");
+        let text = format!("The letter was signed by the clerk. She really slowly read it. {}.", long);
+        let report = TextProcessor::new().build_optimization_report(&text);
+        assert!(report.suggestions.len() >= 3);
+        for suggestion in &report.suggestions {
+            assert!(RULES.iter().any(|r| r.id == suggestion.suggestion_type), "{} is not registered", suggestion.suggestion_type);
+            assert!(PRIORITIES.contains(&suggestion.priority.as_ref()) && SCOPES.contains(&suggestion.scope.as_ref()));
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Per-chunk content hashes with a Merkle root, so two copies of a document
+//! can tell which paragraphs (or word-count chunks) diverged and sync only
+//! those.
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::manifest;
+use crate::segmentation::DocumentIndex;
+
+/// Larger unmatched middles are compared position by position instead of
+/// aligned, to bound the alignment table.
+const MAX_ALIGNED_CELLS: usize = 1_000_000;
+
+pub enum ChunkMode {
+    Paragraph,
+    Words(usize),
+}
+
+impl ChunkMode {
+    /// `words:N` stands for any N > 0.
+    pub const ALL: &'static [&'static str] = &["paragraph", "words:N"];
+
+    /// "paragraph", or "words:N" for chunks of N words.
+    pub fn parse(value: &str) -> Result<ChunkMode, String> {
+        if value == "paragraph" {
+            return Ok(ChunkMode::Paragraph);
+        }
+        match value.strip_prefix("words:").map(str::parse::<usize>) {
+            Some(Ok(size)) if size > 0 => Ok(ChunkMode::Words(size)),
+            _ => Err(format!("Unknown chunk mode \"{}\"; expected \"paragraph\" or \"words:N\" with N > 0", value)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ChunkHash {
+    pub index: usize,
+    pub start_pos: usize,
+    pub end_pos: usize,
+    /// SHA-256 of the chunk's text, base64 like `generate_content_hash`.
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChunkHashes {
+    pub mode: String,
+    /// Merkle root over the chunk hashes; the hash of empty input when there
+    /// are no chunks, and the chunk's own hash when there is one.
+    pub root_hash: String,
+    pub chunks: Vec<ChunkHash>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChunkRange {
+    pub index: usize,
+    pub start_pos: usize,
+    pub end_pos: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChunkComparison {
+    pub root_matches: bool,
+    /// Local chunks with no identical counterpart in the remote list, in order.
+    pub local_mismatches: Vec<ChunkRange>,
+    pub remote_mismatches: Vec<ChunkRange>,
+}
+
+fn encode(digest: &[u8]) -> String {
+    general_purpose::STANDARD.encode(digest)
+}
+
+/// Pairs of nodes hash together, SHA-256 over the two digests; an odd node
+/// is carried up unchanged.
+fn merkle_root(mut level: Vec<Vec<u8>>) -> Vec<u8> {
+    if level.is_empty() {
+        return Sha256::digest(b"").to_vec();
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => Sha256::new().chain_update(left).chain_update(right).finalize().to_vec(),
+                _ => pair[0].clone(),
+            })
+            .collect();
+    }
+    level.remove(0)
+}
+
+/// Chunks cover the text end to end: each runs from the end of the previous
+/// chunk to the end of its own last paragraph or word, and the last takes
+/// any trailing text, so any edit changes the root while appending a
+/// paragraph leaves the earlier chunks alone. Empty text has no chunks, and
+/// text without words is a single chunk.
+pub fn chunk_hashes(index: &DocumentIndex, mode: &ChunkMode, mode_name: &str) -> ChunkHashes {
+    let text = index.text;
+    let mut ends: Vec<usize> = match mode {
+        ChunkMode::Paragraph => index.paragraphs.iter().map(|p| p.1).collect(),
+        ChunkMode::Words(size) => index.words.chunks(*size).map(|words| words[words.len() - 1].1).collect(),
+    };
+    ends.pop();
+    if !text.is_empty() {
+        ends.push(text.len());
+    }
+
+    let mut digests = Vec::with_capacity(ends.len());
+    let chunks = ends
+        .iter()
+        .enumerate()
+        .map(|(i, &end_pos)| {
+            let start_pos = if i == 0 { 0 } else { ends[i - 1] };
+            let digest = Sha256::digest(&text.as_bytes()[start_pos..end_pos]).to_vec();
+            let hash = encode(&digest);
+            digests.push(digest);
+            ChunkHash { index: i, start_pos, end_pos, hash }
+        })
+        .collect();
+    ChunkHashes { mode: mode_name.to_string(), root_hash: encode(&merkle_root(digests)), chunks }
+}
+
+fn range(chunk: &ChunkHash) -> ChunkRange {
+    ChunkRange { index: chunk.index, start_pos: chunk.start_pos, end_pos: chunk.end_pos }
+}
+
+/// Chunks that differ between two lists. The common prefix and suffix are
+/// trimmed, and the rest is aligned on hashes so an inserted paragraph
+/// doesn't mark everything after it as changed.
+pub fn compare(local: &ChunkHashes, remote: &ChunkHashes) -> Result<ChunkComparison, String> {
+    if local.mode != remote.mode {
+        return Err(format!("Chunk lists use different modes: \"{}\" and \"{}\"", local.mode, remote.mode));
+    }
+    let root_matches = local.root_hash == remote.root_hash;
+    if root_matches {
+        return Ok(ChunkComparison { root_matches, local_mismatches: Vec::new(), remote_mismatches: Vec::new() });
+    }
+
+    let (l, r) = (&local.chunks, &remote.chunks);
+    let prefix = l.iter().zip(r).take_while(|(a, b)| a.hash == b.hash).count();
+    let suffix = l[prefix..].iter().rev().zip(r[prefix..].iter().rev()).take_while(|(a, b)| a.hash == b.hash).count();
+    let (l_mid, r_mid) = (&l[prefix..l.len() - suffix], &r[prefix..r.len() - suffix]);
+
+    let pairs: Vec<(usize, usize)> = if l_mid.len() * r_mid.len() <= MAX_ALIGNED_CELLS {
+        let l_hashes: Vec<&str> = l_mid.iter().map(|c| c.hash.as_str()).collect();
+        let r_hashes: Vec<&str> = r_mid.iter().map(|c| c.hash.as_str()).collect();
+        manifest::lcs_pairs(&l_hashes, &r_hashes)
+    } else {
+        (0..l_mid.len().min(r_mid.len())).filter(|&i| l_mid[i].hash == r_mid[i].hash).map(|i| (i, i)).collect()
+    };
+
+    let mut local_matched = vec![false; l_mid.len()];
+    let mut remote_matched = vec![false; r_mid.len()];
+    for (i, j) in pairs {
+        local_matched[i] = true;
+        remote_matched[j] = true;
+    }
+    Ok(ChunkComparison {
+        root_matches,
+        local_mismatches: l_mid.iter().zip(&local_matched).filter(|(_, &m)| !m).map(|(c, _)| range(c)).collect(),
+        remote_mismatches: r_mid.iter().zip(&remote_matched).filter(|(_, &m)| !m).map(|(c, _)| range(c)).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    fn hashes(text: &str, mode: &str) -> ChunkHashes {
+        chunk_hashes(&TextProcessor::new().index(text), &ChunkMode::parse(mode).unwrap(), mode)
+    }
+
+    fn ranges<'t>(text: &'t str, hashes: &ChunkHashes) -> Vec<&'t str> {
+        hashes.chunks.iter().map(|c| &text[c.start_pos..c.end_pos]).collect()
+    }
+
+    fn mismatched(ranges: &[ChunkRange]) -> Vec<usize> {
+        ranges.iter().map(|r| r.index).collect()
+    }
+
+    const DRAFT: &str = "The storm came.\n\nMara ran home.\n\nShe slept.\n";
+
+    #[test]
+    fn chunks_cover_the_text_and_the_root_is_stable() {
+        let paragraphs = hashes(DRAFT, "paragraph");
+        assert_eq!(ranges(DRAFT, &paragraphs), ["The storm came.", "\n\nMara ran home.", "\n\nShe slept.\n"]);
+        assert_eq!(paragraphs.root_hash, hashes(DRAFT, "paragraph").root_hash);
+        let words = hashes(DRAFT, "words:4");
+        assert_eq!(ranges(DRAFT, &words), ["The storm came.\n\nMara", " ran home.\n\nShe slept.\n"]);
+        assert_ne!(words.root_hash, paragraphs.root_hash);
+    }
+
+    #[test]
+    fn empty_and_single_chunk_roots_are_defined() {
+        let empty = hashes("", "paragraph");
+        assert!(empty.chunks.is_empty());
+        assert_eq!(empty.root_hash, "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=");
+        let blank = hashes(" \n\n ", "paragraph");
+        assert_eq!(ranges(" \n\n ", &blank), [" \n\n "]);
+        let single = hashes("One line.", "paragraph");
+        assert_eq!(single.root_hash, single.chunks[0].hash);
+    }
+
+    #[test]
+    fn the_merkle_root_pairs_digests_and_carries_odd_ones_up() {
+        let leaf = |s: &str| Sha256::digest(s.as_bytes()).to_vec();
+        let pair = |a: &[u8], b: &[u8]| Sha256::new().chain_update(a).chain_update(b).finalize().to_vec();
+        let (a, b, c) = (leaf("a"), leaf("b"), leaf("c"));
+        assert_eq!(merkle_root(vec![a.clone(), b.clone(), c.clone()]), pair(&pair(&a, &b), &c));
+        assert_eq!(merkle_root(vec![c.clone()]), c);
+    }
+
+    #[test]
+    fn comparison_finds_only_the_changed_paragraphs() {
+        let local = hashes(DRAFT, "paragraph");
+        let same = compare(&local, &hashes(DRAFT, "paragraph")).unwrap();
+        assert!(same.root_matches && same.local_mismatches.is_empty() && same.remote_mismatches.is_empty());
+
+        let edited = DRAFT.replace("Mara ran", "Mara walked");
+        let comparison = compare(&local, &hashes(&edited, "paragraph")).unwrap();
+        assert!(!comparison.root_matches);
+        assert_eq!((mismatched(&comparison.local_mismatches), mismatched(&comparison.remote_mismatches)), (vec![1], vec![1]));
+
+        // An inserted paragraph doesn't mark the ones after it as changed
+        let inserted = DRAFT.replace("\n\nShe slept.", "\n\nThe door creaked.\n\nShe slept.");
+        let comparison = compare(&local, &hashes(&inserted, "paragraph")).unwrap();
+        assert_eq!((mismatched(&comparison.local_mismatches), mismatched(&comparison.remote_mismatches)), (vec![], vec![2]));
+    }
+
+    #[test]
+    fn modes_must_parse_and_agree() {
+        for bad in ["words:0", "words:", "sentence", "words:-2"] {
+            assert!(ChunkMode::parse(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(
+            compare(&hashes(DRAFT, "paragraph"), &hashes(DRAFT, "words:4")).err().unwrap(),
+            "Chunk lists use different modes: \"paragraph\" and \"words:4\""
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Clichés, matched word by word against a trie of stemmed phrases: one
+//! pass over the text however long the list, case-insensitive, across any
+//! run of whitespace (line breaks included), and tolerant of simple
+//! inflection ("ticking all the boxes" matches "tick all the boxes").
+
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::config::SuggestionConfig;
+use crate::segmentation::DocumentIndex;
+use crate::OptimizationSuggestion;
+
+const BUILTIN_CLICHES: &[&str] = &[
+    "a blessing in disguise", "a dime a dozen", "a drop in the bucket", "a far cry from", "a fish out of water",
+    "a force to be reckoned with", "a diamond in the rough", "a labor of love", "a matter of time", "a perfect storm",
+    "a piece of cake", "a slap in the face", "a stone's throw", "a taste of his own medicine",
+    "a taste of her own medicine", "a tough nut to crack", "a walk in the park", "a whole new ball game",
+    "actions speak louder than words", "add insult to injury", "against all odds", "ahead of the curve",
+    "all bark and no bite", "all in a day's work", "all's fair in love and war", "all's well that ends well",
+    "an arm and a leg", "at a loss for words", "at the crack of dawn", "at the drop of a hat",
+    "at the end of the day", "at this point in time", "avoid like the plague", "back against the wall",
+    "back to square one", "back to the drawing board", "bark up the wrong tree", "beat a dead horse",
+    "beat around the bush", "beggars can't be choosers", "behind the eight ball", "bend over backwards",
+    "best thing since sliced bread", "better late than never", "better safe than sorry", "between a rock and a hard place",
+    "beyond the shadow of a doubt", "bite off more than you can chew", "bite the bullet", "bite the dust",
+    "blind as a bat", "blood is thicker than water", "blow off steam", "bone of contention", "bored to tears",
+    "brave as a lion", "break the bank", "break the ice", "bright and early", "bring home the bacon",
+    "bring to the table", "burn the midnight oil", "burn bridges", "bury the hatchet", "busy as a bee",
+    "by the skin of his teeth", "by the skin of her teeth", "by the skin of my teeth", "call it a day",
+    "calm before the storm", "can't judge a book by its cover", "can of worms", "cat got your tongue",
+    "caught red-handed", "chip off the old block", "clean as a whistle", "climb the corporate ladder",
+    "cold as ice", "come full circle", "cool as a cucumber", "cost an arm and a leg", "couldn't care less",
+    "crack of dawn", "crystal clear", "cut corners", "cut to the chase", "dark and stormy night",
+    "dead as a doornail", "dead of night", "devil's advocate", "don't count your chickens before they hatch",
+    "down the rabbit hole", "down to earth", "drop the ball", "dry as a bone", "easier said than done",
+    "easy as pie", "every cloud has a silver lining", "every dog has its day", "explore every avenue",
+    "eyes bigger than his stomach", "face the music", "fall on deaf ears", "fall through the cracks",
+    "few and far between", "fit as a fiddle", "fit the bill", "for all intents and purposes", "free as a bird",
+    "from the bottom of my heart", "get a kick out of", "get cold feet", "get down to brass tacks",
+    "get the ball rolling", "give the benefit of the doubt", "go against the grain", "go the extra mile",
+    "go with the flow", "good as gold", "grab the bull by the horns", "happy as a clam", "hard as nails",
+    "head over heels", "heart of gold", "heart skipped a beat", "hit the ground running", "hit the hay",
+    "hit the nail on the head", "hit the road", "hit the sack", "hold your horses", "in a nutshell",
+    "in a pickle", "in hot water", "in one fell swoop", "in the blink of an eye", "in the heat of the moment",
+    "in the long run", "in the nick of time", "in the same boat", "in this day and age", "it goes without saying",
+    "it's not rocket science", "jump on the bandwagon", "jump the gun", "keep an eye out", "keep your chin up",
+    "kill two birds with one stone", "knock on wood", "last but not least", "leave no stone unturned",
+    "let the cat out of the bag", "level playing field", "light as a feather", "light at the end of the tunnel",
+    "like a kid in a candy store", "like two peas in a pod", "live and learn", "lock, stock, and barrel",
+    "low-hanging fruit", "mad as a hatter", "make a long story short", "many moons ago", "method to the madness",
+    "mind your own business", "miss the boat", "more than meets the eye", "move the needle", "my heart sank",
+    "needle in a haystack", "neither here nor there", "new kid on the block", "nip it in the bud",
+    "no pain, no gain", "no time like the present", "not my cup of tea", "off the beaten path",
+    "off the top of my head", "old as the hills", "on cloud nine", "on pins and needles", "on the ball",
+    "on the fence", "on the same page", "once in a blue moon", "only time will tell", "opposites attract",
+    "out of the blue", "out of the box", "over the moon", "over the top", "paint the town red",
+    "par for the course", "pass with flying colors", "pay the piper", "peace and quiet", "pie in the sky",
+    "plain as day", "play it by ear", "play with fire", "plenty of fish in the sea", "pull out all the stops",
+    "pull yourself together", "push the envelope", "put all your eggs in one basket", "put your best foot forward",
+    "quiet as a mouse", "rain on my parade", "raining cats and dogs", "read between the lines", "red as a beet",
+    "reinvent the wheel", "rings a bell", "rise and shine", "rock the boat", "rome wasn't built in a day",
+    "rule of thumb", "run like the wind", "sadder but wiser", "save for a rainy day", "scared to death",
+    "see eye to eye", "set in stone", "sharp as a tack", "shoot yourself in the foot", "sick as a dog",
+    "sigh of relief", "sink or swim", "sleep like a baby", "sleep like a log", "slow as molasses",
+    "smart as a whip", "so far so good", "spill the beans", "steal the show", "stick out like a sore thumb",
+    "still waters run deep", "stop and smell the roses", "straight from the horse's mouth", "strong as an ox",
+    "take it with a grain of salt", "take the bull by the horns", "take the cake", "take the plunge",
+    "talk is cheap", "the bottom line", "the calm before the storm", "the elephant in the room",
+    "the last straw", "the moment of truth", "the next big thing", "the tip of the iceberg",
+    "the whole nine yards", "the writing is on the wall", "think outside the box", "through thick and thin",
+    "throw caution to the wind", "throw in the towel", "tick all the boxes", "tie the knot", "time flies",
+    "time heals all wounds", "time is money", "tip of the iceberg", "to each his own", "tongue in cheek",
+    "too little, too late", "touch base", "turn over a new leaf", "two sides of the same coin", "under the weather",
+    "until the cows come home", "up in the air", "wake-up call", "water under the bridge", "wear your heart on your sleeve",
+    "what goes around comes around", "when all is said and done", "when pigs fly", "white as a sheet",
+    "white as snow", "win-win situation", "without further ado", "wolf in sheep's clothing", "worth its weight in gold",
+    "you can't teach an old dog new tricks", "young at heart",
+];
+
+/// `word` lowercased with a plural, possessive, "-ed", or "-ing" ending
+/// cut, so inflections of a word stem to the same string. Only ever
+/// compared with other stems, so the stems need not be words.
+pub fn stem(word: &str) -> String {
+    let mut stem = word.to_lowercase().replace('\u{2019}', "'");
+    if stem.ends_with("'s") {
+        stem.truncate(stem.len() - 2);
+    }
+    if let Some(suffix) = ["ing", "ed", "es", "s"].iter().find(|s| stem.len() >= s.len() + 3 && stem.ends_with(*s)) {
+        stem.truncate(stem.len() - suffix.len());
+    }
+    if stem.len() > 3 && stem.ends_with('e') {
+        stem.pop();
+    }
+    // "running" -> "runn" -> "run"
+    let bytes = stem.as_bytes();
+    if let [.., a, b] = bytes {
+        if a == b && b.is_ascii_alphabetic() && !b"aeiou".contains(b) {
+            stem.pop();
+        }
+    }
+    stem
+}
+
+#[derive(Default)]
+struct Node {
+    next: HashMap<String, usize>,
+    /// A cliché ends here.
+    terminal: bool,
+}
+
+/// The built-in clichés plus the configured extras, as a trie of stems.
+pub struct Cliches {
+    nodes: Vec<Node>,
+}
+
+impl Cliches {
+    pub fn from_config(config: &SuggestionConfig) -> Cliches {
+        let mut cliches = Cliches { nodes: vec![Node::default()] };
+        for phrase in BUILTIN_CLICHES.iter().copied().chain(config.extra_cliches.iter().map(String::as_str)) {
+            cliches.insert(phrase);
+        }
+        cliches
+    }
+
+    fn insert(&mut self, phrase: &str) {
+        let mut node = 0;
+        for word in phrase.unicode_words() {
+            let key = stem(word);
+            node = match self.nodes[node].next.get(&key) {
+                Some(&next) => next,
+                None => {
+                    self.nodes.push(Node::default());
+                    let next = self.nodes.len() - 1;
+                    self.nodes[node].next.insert(key, next);
+                    next
+                }
+            };
+        }
+        // An empty phrase ends at the root and never matches
+        if node != 0 {
+            self.nodes[node].terminal = true;
+        }
+    }
+
+    /// `(start, end)` of every cliché in the text of `index`, longest match
+    /// first and none overlapping. Words of a match may be separated only by
+    /// whitespace, hyphens, and commas ("no pain, no gain").
+    pub fn find(&self, index: &DocumentIndex) -> Vec<(usize, usize)> {
+        let stems: Vec<String> = index.word_strs().into_iter().map(stem).collect();
+        self.find_stems(index, &stems)
+    }
+
+    /// `find`, given the `stem` of every word of `index`.
+    pub fn find_stems(&self, index: &DocumentIndex, stems: &[impl AsRef<str>]) -> Vec<(usize, usize)> {
+        let text = index.text;
+        let mut matches = Vec::new();
+        let mut i = 0;
+        while i < stems.len() {
+            let (mut node, mut longest) = (0, None);
+            for j in i..stems.len() {
+                let joined = j == i || text[index.words[j - 1].1..index.words[j].0].chars().all(|c| c.is_whitespace() || c == '-' || c == ',');
+                let Some(&next) = self.nodes[node].next.get(stems[j].as_ref()).filter(|_| joined) else { break };
+                node = next;
+                if self.nodes[node].terminal {
+                    longest = Some(j);
+                }
+            }
+            match longest {
+                Some(j) => {
+                    matches.push((index.words[i].0, index.words[j].1));
+                    i = j + 1;
+                }
+                None => i += 1,
+            }
+        }
+        matches
+    }
+
+    /// One `cliche` suggestion per match.
+    pub fn suggestions(&self, index: &DocumentIndex) -> Vec<OptimizationSuggestion> {
+        self.find(index)
+            .into_iter()
+            .map(|(start, end)| {
+                let phrase = index.text[start..end].split_whitespace().collect::<Vec<_>>().join(" ");
+                OptimizationSuggestion::instance(
+                    "cliche",
+                    "low",
+                    format!("\"{}\" is a cliché; consider saying it in your own words.", phrase),
+                    start,
+                    end,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TextProcessor;
+
+    #[test]
+    fn cliches_matched_across_line_breaks_and_inflections() {
+        let text = "At the end\n  of the day, the plan was ticking all the boxes. We arrived in the nick of time, and the day ended.";
+        let processor = TextProcessor::new();
+        let suggestions = processor.generate_optimization_suggestions(text, &|rule| rule == "cliche");
+        let spans: Vec<&str> = suggestions.iter().map(|s| &text[s.start_pos..s.end_pos]).collect();
+        assert_eq!(spans, ["At the end\n  of the day", "ticking all the boxes", "in the nick of time"]);
+        assert!(suggestions[0].message.contains("\"At the end of the day\""));
+        let per_1000 = 3.0 * 1000.0 / processor.analyze_text(text).word_count as f64;
+        assert!((processor.analyze_text(text).style_metrics.cliches_per_1000_words - per_1000).abs() < 0.01);
+    }
+
+    #[test]
+    fn cliches_are_extensible() {
+        let text = "Her writing was a breath of fresh air.";
+        let mut processor = TextProcessor::new();
+        assert!(processor.generate_optimization_suggestions(text, &|rule| rule == "cliche").is_empty());
+        processor.add_cliches(vec!["a breath of fresh air".to_string()]);
+        let suggestions = processor.generate_optimization_suggestions(text, &|rule| rule == "cliche");
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(&text[suggestions[0].start_pos..suggestions[0].end_pos], "a breath of fresh air");
+    }
+}
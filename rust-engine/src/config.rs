@@ -0,0 +1,444 @@
+//! Tunable settings for suggestion generation and post-processing.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::context::ContextOptions;
+use crate::diversity;
+use crate::output::OutputOptions;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SuggestionConfig {
+    pub profile: Profile,
+    /// Per-rule density thresholds (hits per 1,000 words) that escalate priority.
+    /// Keyed by `suggestion_type`; rules without an entry are never escalated.
+    pub density_thresholds: BTreeMap<String, DensityThresholds>,
+    /// When set, dialect spellings inside dialogue never produce suggestions
+    /// from the rules listed in `dialect_protected_rules`.
+    pub protect_dialogue_dialect: bool,
+    /// Tokens treated as deliberate dialect when they appear inside dialogue.
+    /// Apostrophe-final words ("runnin'") are protected automatically.
+    pub protected_tokens: Vec<String>,
+    pub dialect_protected_rules: Vec<String>,
+    pub person_pronouns: PersonPronounConfig,
+    pub difficulty_normalization: DifficultyNormalization,
+    /// Watchlist terms reported as `flagged_term` suggestions.
+    pub flagged_terms: Vec<String>,
+    /// Opt-in matching through obfuscations (separators, repeats, leetspeak).
+    pub fuzzy_flagged_terms: bool,
+    /// All-caps words that are not acronyms worth a glossary entry or a
+    /// definition.
+    pub ignored_acronyms: Vec<String>,
+    /// Replaces the bundled `screen_content` list of a category, keyed by
+    /// category; other keys add categories.
+    pub screening_lists: BTreeMap<String, Vec<String>>,
+    /// Language code selecting stopwords, syllable rules, adverb endings,
+    /// and the reading-ease formula; unknown codes get English. "auto" has
+    /// `analyze_text` detect the language of each text.
+    pub language: String,
+    /// Replaces the built-in stopword list for a language, keyed by language code.
+    pub stopwords: BTreeMap<String, Vec<String>>,
+    /// Added to the active language's stopword list.
+    pub extra_stopwords: Vec<String>,
+    /// Replaces the built-in English sentiment lexicon: word to valence,
+    /// about -5 (most negative) to 5.
+    pub sentiment_lexicon: Option<BTreeMap<String, f64>>,
+    /// Reflow input (join hyphen-split words, unwrap hard line breaks) before
+    /// analysis; reported positions still refer to the original text.
+    pub reflow_input: bool,
+    /// Markdown and HTML input have their syntax stripped before analysis
+    /// (and before reflow); positions still refer to the source markup.
+    pub input_format: InputFormat,
+    /// A front matter block opening the text ("---" or "+++" fenced
+    /// metadata) is left out of analysis and suggestions, before any other
+    /// preparation; positions still refer to the whole text. A block with no
+    /// closing fence is analyzed as body text.
+    pub skip_front_matter: bool,
+    /// Crutch words reported as `filler_word` suggestions on top of the
+    /// built-in list; their suggested replacement removes them.
+    pub extra_filler_words: Vec<String>,
+    /// Spellings of one word (or name) to keep consistent, checked by the
+    /// `consistency` rule alongside the built-in US/UK and hyphenation groups.
+    pub variant_groups: Vec<Vec<String>>,
+    /// Phrases reported as `cliche` suggestions on top of the built-in list.
+    pub extra_cliches: Vec<String>,
+    /// "To be" + adjective phrases (without the "to be": "aware of") and the
+    /// verb replacing them, on top of the built-in `weak_verb` table.
+    pub extra_weak_verbs: BTreeMap<String, String>,
+    /// Light verb + noun phrases, with the verb in its base form ("make a
+    /// decision"), and the verb they bury, on top of the built-in
+    /// `nominalization` table.
+    pub extra_nominalizations: BTreeMap<String, String>,
+    /// Words the `possible_typo` rule accepts on top of the bundled English
+    /// word list, and may offer as the fix for a typo.
+    pub dictionary_words: Vec<String>,
+    /// Words the `possible_typo` rule never flags but never offers either.
+    pub ignored_words: Vec<String>,
+    /// Minimum bigram similarity for chapter opener/closer echo suggestions.
+    pub echo_similarity_threshold: f64,
+    /// Detect each paragraph's language and keep language-specific rules off
+    /// paragraphs that are confidently not in `language`.
+    pub detect_paragraph_language: bool,
+    /// Below this many words results are flagged `low_confidence` and
+    /// document-level suggestions are not emitted.
+    pub min_word_count: usize,
+    /// Words longer than this many characters are treated as opaque tokens
+    /// (pasted data, URLs) and kept out of syllable, readability, and style
+    /// rule analysis.
+    pub max_token_length: usize,
+    /// Thresholds and priorities of the core readability and style rules.
+    pub analysis: AnalysisOptions,
+    /// Rounding and units applied to float metrics at serialization time.
+    pub output: OutputOptions,
+    /// Rows of the suggestions table in `export_report`'s Markdown report.
+    pub report_suggestion_rows: usize,
+    /// Per-suggestion context excerpts in optimization results.
+    pub context: ContextOptions,
+}
+
+/// Writing profile; gates rules that only make sense for certain kinds of text.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Profile {
+    #[default]
+    General,
+    Academic,
+    FormalReport,
+    /// First-draft mode: only structural and risk suggestions are surfaced.
+    Drafting,
+}
+
+impl Profile {
+    pub const ALL: &'static [&'static str] = &["general", "academic", "formal_report", "drafting"];
+
+    pub fn surfaces_style_rules(self) -> bool {
+        self != Profile::Drafting
+    }
+}
+
+/// How sentence difficulty scores are normalized.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DifficultyNormalization {
+    /// Fixed grade-level thresholds.
+    #[default]
+    Absolute,
+    /// Percentile rank among the document's own sentences.
+    Percentile,
+}
+
+impl DifficultyNormalization {
+    pub const ALL: &'static [&'static str] = &["absolute", "percentile"];
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InputFormat {
+    #[default]
+    Plain,
+    /// Headings, emphasis, list bullets, and link URLs are stripped; fenced
+    /// and inline code is left out entirely.
+    Markdown,
+    /// Tags are skipped and entities decoded; block tags and `<br>` become
+    /// paragraph and line breaks, and scripts and styles are left out.
+    Html,
+}
+
+impl InputFormat {
+    pub const ALL: &'static [&'static str] = &["plain", "markdown", "html"];
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AnalysisOptions {
+    /// Sentences with more words than this get a `sentence_length` suggestion.
+    pub long_sentence_threshold: usize,
+    /// Words with at least this many syllables count as complex (fog index,
+    /// polysyllable density).
+    pub complex_word_syllables: usize,
+    pub sentence_length_priority: Priority,
+    pub passive_voice_priority: Priority,
+    pub adverb_priority: Priority,
+    /// Only adverbs right next to a verb ("said softly") are reported and
+    /// counted in `adverb_ratio`.
+    pub adverbs_near_verbs_only: bool,
+    /// Adverb ratio (0–1) above which analysis adds an `adverb_ratio_high`
+    /// warning; unset never warns.
+    pub adverb_ratio_warning_level: Option<f64>,
+    /// Passive voice ratio (per sentence) above which analysis adds a
+    /// `passive_voice_ratio_high` warning; unset never warns.
+    pub passive_voice_ratio_warning_level: Option<f64>,
+    /// Content words repeated within this many words get a `word_echo`
+    /// suggestion; 0 disables the rule.
+    pub word_echo_window: usize,
+    /// Window in words for `mattr`; texts shorter than this get the plain
+    /// type-token ratio.
+    pub mattr_window: usize,
+    /// Silent reading speed behind `estimated_reading_time_seconds`.
+    pub reading_words_per_minute: f64,
+    /// Read-aloud speed behind `estimated_speaking_time_seconds`.
+    pub speaking_words_per_minute: f64,
+    /// Dialogue is read this many times faster than narration.
+    pub dialogue_reading_speedup: f64,
+    /// Added to both estimates for every paragraph break.
+    pub paragraph_pause_seconds: f64,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        AnalysisOptions {
+            long_sentence_threshold: 25,
+            complex_word_syllables: 3,
+            sentence_length_priority: Priority::Medium,
+            passive_voice_priority: Priority::Low,
+            adverb_priority: Priority::Low,
+            adverbs_near_verbs_only: false,
+            adverb_ratio_warning_level: None,
+            passive_voice_ratio_warning_level: None,
+            word_echo_window: 50,
+            mattr_window: diversity::DEFAULT_MATTR_WINDOW,
+            reading_words_per_minute: 238.0,
+            speaking_words_per_minute: 150.0,
+            dialogue_reading_speedup: 1.1,
+            paragraph_pause_seconds: 0.5,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PersonPronounConfig {
+    /// Overrides profile gating; `None` enables the rule only for the
+    /// academic and formal-report profiles.
+    pub enabled: Option<bool>,
+    /// Pronouns permitted by convention, e.g. "we" in mathematics papers.
+    pub allowed: Vec<String>,
+}
+
+impl SuggestionConfig {
+    pub fn person_pronoun_rule_active(&self) -> bool {
+        self.person_pronouns
+            .enabled
+            .unwrap_or(matches!(self.profile, Profile::Academic | Profile::FormalReport))
+    }
+
+    /// Whether analysis runs on a prepared copy of the input (stripped or
+    /// reflowed) rather than the text itself.
+    pub fn transforms_input(&self) -> bool {
+        self.reflow_input || self.input_format != InputFormat::Plain
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct DensityThresholds {
+    pub medium_per_1000_words: f64,
+    pub high_per_1000_words: f64,
+}
+
+impl Default for SuggestionConfig {
+    fn default() -> Self {
+        let mut density_thresholds = BTreeMap::new();
+        density_thresholds.insert(
+            "adverb_usage".to_string(),
+            DensityThresholds { medium_per_1000_words: 20.0, high_per_1000_words: 40.0 },
+        );
+        density_thresholds.insert(
+            "passive_voice".to_string(),
+            DensityThresholds { medium_per_1000_words: 10.0, high_per_1000_words: 20.0 },
+        );
+        density_thresholds.insert(
+            "sentence_length".to_string(),
+            DensityThresholds { medium_per_1000_words: 5.0, high_per_1000_words: 10.0 },
+        );
+
+        SuggestionConfig {
+            profile: Profile::General,
+            density_thresholds,
+            protect_dialogue_dialect: true,
+            protected_tokens: [
+                "gonna", "wanna", "gotta", "kinda", "sorta", "lemme", "gimme", "dunno",
+                "ain't", "y'all", "ya", "yer", "'em", "innit", "nothin'", "somethin'",
+            ]
+            .iter()
+            .map(|t| t.to_string())
+            .collect(),
+            dialect_protected_rules: vec!["adverb_usage".to_string(), "passive_voice".to_string()],
+            person_pronouns: PersonPronounConfig::default(),
+            difficulty_normalization: DifficultyNormalization::Absolute,
+            flagged_terms: Vec::new(),
+            fuzzy_flagged_terms: false,
+            ignored_acronyms: [
+                "OK", "TV", "USA", "US", "UK", "EU", "AM", "PM", "AD", "BC", "DIY", "FAQ", "ID", "II", "III", "IV",
+                "VI", "VII", "VIII", "IX", "XI", "XII",
+            ]
+            .iter()
+            .map(|a| a.to_string())
+            .collect(),
+            screening_lists: BTreeMap::new(),
+            language: "en".to_string(),
+            stopwords: BTreeMap::new(),
+            extra_stopwords: Vec::new(),
+            sentiment_lexicon: None,
+            reflow_input: false,
+            input_format: InputFormat::Plain,
+            skip_front_matter: true,
+            extra_filler_words: Vec::new(),
+            variant_groups: Vec::new(),
+            extra_cliches: Vec::new(),
+            extra_weak_verbs: BTreeMap::new(),
+            extra_nominalizations: BTreeMap::new(),
+            dictionary_words: Vec::new(),
+            ignored_words: Vec::new(),
+            echo_similarity_threshold: 0.5,
+            detect_paragraph_language: true,
+            min_word_count: 10,
+            max_token_length: 64,
+            analysis: AnalysisOptions::default(),
+            output: OutputOptions::default(),
+            report_suggestion_rows: 10,
+            context: ContextOptions::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+    use crate::suggestions::{SCOPE_DOCUMENT, SCOPE_INSTANCE};
+    use crate::capabilities;
+
+    fn processor(analysis: AnalysisOptions) -> TextProcessor {
+        TextProcessor::with_patterns(&Default::default(), SuggestionConfig { analysis, ..SuggestionConfig::default() })
+    }
+
+    fn long_sentences(processor: &TextProcessor, text: &str) -> Vec<String> {
+        processor
+            .build_optimization_report(text)
+            .suggestions
+            .iter()
+            .filter(|s| s.suggestion_type == "sentence_length" && s.scope == "instance")
+            .map(|s| s.priority.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn raising_the_long_sentence_threshold_to_40_suppresses_a_default_suggestion() {
+        let filler = "Mara walked home. ".repeat(40);
+        let text = format!("{}{}. {}", filler, vec!["word"; 30].join(" "), filler);
+        assert_eq!(long_sentences(&processor(AnalysisOptions::default()), &text), ["medium"]);
+        assert!(long_sentences(&processor(AnalysisOptions { long_sentence_threshold: 40, ..Default::default() }), &text).is_empty());
+        let high = AnalysisOptions { sentence_length_priority: Priority::High, ..Default::default() };
+        assert_eq!(long_sentences(&processor(high), &text), ["high"]);
+    }
+
+    #[test]
+    fn partial_options_keep_the_remaining_defaults() {
+        let options: AnalysisOptions = serde_json::from_value(serde_json::json!({ "passive_voice_priority": "medium" })).unwrap();
+        assert!(options.passive_voice_priority == Priority::Medium);
+        let defaults = serde_json::to_value(AnalysisOptions::default()).unwrap();
+        let mut expected = defaults.clone();
+        expected["passive_voice_priority"] = "medium".into();
+        assert_eq!(serde_json::to_value(&options).unwrap(), expected);
+        assert_eq!(serde_json::to_value(serde_json::from_value::<AnalysisOptions>(serde_json::json!({})).unwrap()).unwrap(), defaults);
+        assert!(serde_json::from_value::<AnalysisOptions>(serde_json::json!({ "adverb_priority": "urgent" })).is_err());
+    }
+
+    #[test]
+    fn complex_word_syllables_and_ratio_warning_levels() {
+        let text = "The committee deliberately postponed the unanimous decision. Everybody was informed quickly.";
+        let fog = |syllables| processor(AnalysisOptions { complex_word_syllables: syllables, ..Default::default() }).perform_analysis(text).complexity_metrics.fog_index;
+        assert!(fog(2) > fog(3) && fog(3) > fog(9));
+
+        let codes = |options| processor(options).perform_analysis(text).warnings.into_iter().map(|w| w.code).collect::<Vec<_>>();
+        assert!(!codes(AnalysisOptions::default()).iter().any(|c| c.ends_with("_high")));
+        let strict = AnalysisOptions { adverb_ratio_warning_level: Some(0.05), passive_voice_ratio_warning_level: Some(0.1), ..Default::default() };
+        let warned = codes(strict);
+        assert!(warned.contains(&"adverb_ratio_high".to_string()) && warned.contains(&"passive_voice_ratio_high".to_string()));
+    }
+
+    fn with_profile(profile: Profile) -> TextProcessor {
+        let mut processor = TextProcessor::new();
+        processor.config = SuggestionConfig { profile, ..SuggestionConfig::default() };
+        processor
+    }
+
+    #[test]
+    fn person_pronouns_flagged_in_academic_body_text_only() {
+        let text = "I measured it and you can see why. \"We loved it,\" she said.\n> My quoted line\nThe US data agree.";
+        let processor = with_profile(Profile::Academic);
+        let suggestions = processor.generate_optimization_suggestions(text, &|_| true);
+        let flagged: Vec<&str> = suggestions
+            .iter()
+            .filter(|s| s.scope == SCOPE_INSTANCE && s.suggestion_type.ends_with("_person"))
+            .map(|s| &text[s.start_pos..s.end_pos])
+            .collect();
+        assert_eq!(flagged, ["I", "you"]);
+        let documents = suggestions.iter().filter(|s| s.scope == SCOPE_DOCUMENT).count();
+        assert_eq!(documents, 2);
+        assert!(processor.perform_analysis(text).first_person_per_1000_words.unwrap() > 0.0);
+
+        let general = with_profile(Profile::General);
+        assert!(general.generate_optimization_suggestions(text, &|_| true).iter().all(|s| !s.suggestion_type.ends_with("_person")));
+        assert!(general.perform_analysis(text).first_person_per_1000_words.is_none());
+    }
+
+    #[test]
+    fn allowed_pronouns_are_not_flagged() {
+        let mut processor = with_profile(Profile::Academic);
+        processor.config.person_pronouns.allowed = vec!["we".to_string()];
+        let text = "We prove the lemma. Then our result follows.";
+        let flagged: Vec<&str> = processor
+            .generate_optimization_suggestions(text, &|_| true)
+            .iter()
+            .filter(|s| s.scope == SCOPE_INSTANCE && s.suggestion_type == "first_person")
+            .map(|s| &text[s.start_pos..s.end_pos])
+            .collect();
+        assert_eq!(flagged, ["our"]);
+    }
+
+    #[test]
+    fn drafting_profile_surfaces_a_strict_subset_of_categories() {
+        let text = "The letter was signed slowly by the clerk and then it was filed quietly. She said \"wait.\n\nThe letter was signed slowly by the clerk and then it was filed quietly. She said \"wait.\n\nThis last paragraph just trails off without any punctuation at all";
+        let categories = |processor: &TextProcessor| {
+            let mut categories: Vec<&str> = processor
+                .build_optimization_report(text)
+                .suggestions
+                .iter()
+                .map(|s| capabilities::rule_category(&s.suggestion_type))
+                .collect();
+            categories.sort_unstable();
+            categories.dedup();
+            categories
+        };
+        let revision = categories(&with_profile(Profile::General));
+        let drafting_processor = with_profile(Profile::Drafting);
+        let drafting = categories(&drafting_processor);
+        assert_eq!(revision, [capabilities::CATEGORY_STRUCTURAL, capabilities::CATEGORY_STYLE]);
+        assert_eq!(drafting, [capabilities::CATEGORY_STRUCTURAL]);
+
+        let analysis = drafting_processor.perform_analysis(text);
+        assert!(analysis.style_metrics.passive_voice_ratio > 0.0);
+        assert!(analysis.not_surfaced.iter().any(|r| r == "passive_voice"));
+        assert!(with_profile(Profile::General).perform_analysis(text).not_surfaced.is_empty());
+    }
+}
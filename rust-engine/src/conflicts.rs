@@ -0,0 +1,185 @@
+//! Validation of collaboration conflict batches coming from JS.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::reconcile::SpanReconciliation;
+use crate::CollaborationConflict;
+
+pub const CONFLICT_TYPES: &[&str] = &["text_insertion", "text_deletion", "text_modification", COMPOSITE_CONFLICT];
+
+/// Several overlapping conflicts merged into one by `resolve_conflicts`.
+pub const COMPOSITE_CONFLICT: &str = "composite";
+
+/// Each required key in camelCase, then the snake_case spelling also
+/// accepted.
+const REQUIRED_FIELDS: &[(&str, &str)] = &[
+    ("conflictId", "conflict_id"),
+    ("conflictType", "conflict_type"),
+    ("startPos", "start_pos"),
+    ("endPos", "end_pos"),
+    ("userAChange", "user_a_change"),
+    ("userBChange", "user_b_change"),
+    ("timestamp", "timestamp"),
+];
+
+fn field<'a>(entry: &'a Value, (camel, snake): (&str, &str)) -> Option<&'a Value> {
+    entry.get(camel).or_else(|| entry.get(snake)).filter(|value| !value.is_null())
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedConflict {
+    /// Position of the entry in the submitted batch.
+    pub index: usize,
+    pub conflict_id: Option<String>,
+    /// Machine-readable reason code.
+    pub reason: String,
+    pub detail: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictResolutionResponse {
+    pub resolved: Vec<CollaborationConflict>,
+    pub rejected: Vec<RejectedConflict>,
+    /// Present when the batch was reconciled against the current text first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reconciliation: Vec<SpanReconciliation>,
+}
+
+/// Validates each entry independently so one malformed conflict doesn't
+/// poison the rest of the batch.
+pub fn validate_entries(entries: Vec<Value>) -> (Vec<CollaborationConflict>, Vec<RejectedConflict>) {
+    let mut valid = Vec::new();
+    let mut rejected = Vec::new();
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        match validate_entry(&entry) {
+            Ok(conflict) => valid.push(conflict),
+            Err((reason, detail)) => rejected.push(RejectedConflict {
+                index,
+                conflict_id: field(&entry, REQUIRED_FIELDS[0]).and_then(Value::as_str).map(str::to_string),
+                reason: reason.to_string(),
+                detail,
+            }),
+        }
+    }
+
+    (valid, rejected)
+}
+
+fn validate_entry(entry: &Value) -> Result<CollaborationConflict, (&'static str, String)> {
+    if !entry.is_object() {
+        return Err(("not_an_object", "conflict entry must be an object".to_string()));
+    }
+
+    for &required in REQUIRED_FIELDS {
+        if field(entry, required).is_none() {
+            return Err(("missing_field", format!("missing required field \"{}\"", required.0)));
+        }
+    }
+
+    for &position in &REQUIRED_FIELDS[2..4] {
+        if field(entry, position).and_then(Value::as_i64).is_some_and(|n| n < 0) {
+            return Err(("negative_position", format!("\"{}\" must not be negative", position.0)));
+        }
+    }
+
+    let conflict: CollaborationConflict = serde_json::from_value(entry.clone())
+        .map_err(|e| ("invalid_field", e.to_string()))?;
+
+    if !CONFLICT_TYPES.contains(&conflict.conflict_type.as_str()) {
+        return Err(("unknown_conflict_type", format!("unknown conflictType \"{}\"", conflict.conflict_type)));
+    }
+    if conflict.start_pos > conflict.end_pos {
+        return Err((
+            "inverted_span",
+            format!("startPos {} is after endPos {}", conflict.start_pos, conflict.end_pos),
+        ));
+    }
+
+    Ok(conflict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn conflict(id: &str, conflict_type: &str, start_pos: i64, end_pos: i64) -> Value {
+        json!({
+            "conflict_id": id,
+            "conflict_type": conflict_type,
+            "start_pos": start_pos,
+            "end_pos": end_pos,
+            "user_a_change": "the storm",
+            "user_b_change": "the gale",
+            "timestamp": "2024-01-01T00:00:00Z",
+        })
+    }
+
+    #[test]
+    fn mixed_batch_keeps_valid_entries_and_reports_each_rejection() {
+        let mut missing = conflict("c4", "text_deletion", 0, 4);
+        missing.as_object_mut().unwrap().remove("user_b_change");
+        let entries = vec![
+            conflict("c0", "text_insertion", 0, 0),
+            conflict("c1", "text_rename", 0, 4),
+            conflict("c2", "text_modification", 9, 3),
+            conflict("c3", "text_modification", -2, 3),
+            missing,
+            json!("not a conflict"),
+            conflict("c6", "text_deletion", 5, 12),
+        ];
+
+        let (valid, rejected) = validate_entries(entries);
+
+        let ids: Vec<&str> = valid.iter().map(|c| c.conflict_id.as_str()).collect();
+        assert_eq!(ids, ["c0", "c6"]);
+        let reasons: Vec<(usize, &str)> = rejected.iter().map(|r| (r.index, r.reason.as_str())).collect();
+        assert_eq!(
+            reasons,
+            [
+                (1, "unknown_conflict_type"),
+                (2, "inverted_span"),
+                (3, "negative_position"),
+                (4, "missing_field"),
+                (5, "not_an_object"),
+            ]
+        );
+        assert_eq!(rejected[0].conflict_id.as_deref(), Some("c1"));
+        assert_eq!(rejected[4].conflict_id, None);
+
+        let resolved = crate::TextProcessor::new().auto_resolve_conflicts(valid, crate::resolution::ConflictPolicy::Auto);
+        assert_eq!(resolved.len(), 2);
+        assert!(resolved.iter().all(|c| !c.resolution_suggestion.is_empty()));
+    }
+
+    #[test]
+    fn wrongly_typed_fields_are_rejected_as_invalid() {
+        let mut entry = conflict("c0", "text_insertion", 0, 0);
+        entry["start_pos"] = json!("zero");
+        let (valid, rejected) = validate_entries(vec![entry]);
+        assert!(valid.is_empty());
+        assert_eq!(rejected[0].reason, "invalid_field");
+    }
+
+    #[test]
+    fn null_timestamp_rejects_only_its_entry_and_names_the_field() {
+        let mut bad = conflict("c1", "text_modification", 0, 4);
+        bad["timestamp"] = Value::Null;
+        let (valid, rejected) = validate_entries(vec![conflict("c0", "text_insertion", 0, 0), bad]);
+        assert_eq!(valid.len(), 1);
+        assert_eq!((rejected[0].index, rejected[0].reason.as_str()), (1, "missing_field"));
+        assert!(rejected[0].detail.contains("timestamp"), "{}", rejected[0].detail);
+    }
+
+    #[test]
+    fn malformed_conflict_entries_are_rejected_rather_than_panicking() {
+        let entries = vec![serde_json::json!(null), serde_json::json!("text"), serde_json::json!({}), serde_json::json!([1, 2])];
+        let (valid, rejected) = validate_entries(entries);
+        assert!(valid.is_empty());
+        assert_eq!(rejected.iter().map(|r| r.index).collect::<Vec<_>>(), [0, 1, 2, 3]);
+    }
+}
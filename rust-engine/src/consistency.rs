@@ -0,0 +1,215 @@
+//! Spelling consistency: groups of interchangeable spellings (US and UK,
+//! hyphenated or closed compounds), with every use of a minority spelling
+//! reported against the form the document mostly uses.
+
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::config::SuggestionConfig;
+use crate::rewrite;
+use crate::OptimizationSuggestion;
+
+/// Built-in variant groups. Inflected forms are groups of their own, so
+/// "colors" is weighed against "colours" rather than "colour".
+const BUILTIN_GROUPS: &[&[&str]] = &[
+    &["color", "colour"],
+    &["colors", "colours"],
+    &["colored", "coloured"],
+    &["favorite", "favourite"],
+    &["favorites", "favourites"],
+    &["honor", "honour"],
+    &["honored", "honoured"],
+    &["humor", "humour"],
+    &["labor", "labour"],
+    &["neighbor", "neighbour"],
+    &["neighbors", "neighbours"],
+    &["flavor", "flavour"],
+    &["behavior", "behaviour"],
+    &["harbor", "harbour"],
+    &["center", "centre"],
+    &["centers", "centres"],
+    &["theater", "theatre"],
+    &["meter", "metre"],
+    &["fiber", "fibre"],
+    &["realize", "realise"],
+    &["realized", "realised"],
+    &["organize", "organise"],
+    &["organized", "organised"],
+    &["recognize", "recognise"],
+    &["recognized", "recognised"],
+    &["apologize", "apologise"],
+    &["apologized", "apologised"],
+    &["analyze", "analyse"],
+    &["defense", "defence"],
+    &["offense", "offence"],
+    &["license", "licence"],
+    &["catalog", "catalogue"],
+    &["traveled", "travelled"],
+    &["traveling", "travelling"],
+    &["canceled", "cancelled"],
+    &["jewelry", "jewellery"],
+    &["gray", "grey"],
+    &["toward", "towards"],
+    &["afterward", "afterwards"],
+    &["email", "e-mail"],
+    &["online", "on-line"],
+    &["website", "web site"],
+    &["okay", "ok"],
+    &["cooperate", "co-operate"],
+    &["nonetheless", "none the less"],
+];
+
+/// Lowercase with internal whitespace collapsed.
+fn normalize(variant: &str) -> String {
+    variant.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// The built-in variant groups plus the configured ones, compiled into one
+/// whole-word, case-insensitive pattern.
+pub struct VariantGroups {
+    pattern: Option<Regex>,
+    /// Normalized variant -> (group, position within the group)
+    variants: HashMap<String, (usize, usize)>,
+    /// Each group's variants as written in the configuration
+    groups: Vec<Vec<String>>,
+}
+
+impl VariantGroups {
+    pub fn from_config(config: &SuggestionConfig) -> VariantGroups {
+        let builtin = BUILTIN_GROUPS.iter().map(|g| g.iter().map(|v| v.to_string()).collect::<Vec<_>>());
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        let mut variants = HashMap::new();
+        // A variant belongs to the first group listing it
+        for group in builtin.chain(config.variant_groups.iter().cloned()) {
+            let mut kept: Vec<(String, String)> = Vec::new();
+            for variant in &group {
+                let key = normalize(variant);
+                if !key.is_empty() && !variants.contains_key(&key) && !kept.iter().any(|k| k.0 == key) {
+                    kept.push((key, variant.split_whitespace().collect::<Vec<_>>().join(" ")));
+                }
+            }
+            if kept.len() < 2 {
+                continue;
+            }
+            for (i, (key, _)) in kept.iter().enumerate() {
+                variants.insert(key.clone(), (groups.len(), i));
+            }
+            groups.push(kept.into_iter().map(|(_, form)| form).collect());
+        }
+
+        // Longest first, so "web site" wins over a shorter variant at the same position
+        let mut all: Vec<&String> = variants.keys().collect();
+        all.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        let alternatives: Vec<String> = all.iter().map(|v| v.split(' ').map(regex::escape).collect::<Vec<_>>().join(r"\s+")).collect();
+        let pattern = Regex::new(&format!(r"(?i)\b(?:{})\b", alternatives.join("|"))).ok();
+        VariantGroups { pattern, variants, groups }
+    }
+
+    /// A `consistency` suggestion at every use of a variant other than the
+    /// one used most in its group; ties go to the one used first.
+    pub fn suggestions(&self, text: &str) -> Vec<OptimizationSuggestion> {
+        let Some(pattern) = &self.pattern else {
+            return Vec::new();
+        };
+        // (start, end, group, variant)
+        let hits: Vec<(usize, usize, usize, usize)> = pattern
+            .find_iter(text)
+            .filter_map(|m| self.variants.get(&normalize(m.as_str())).map(|&(g, v)| (m.start(), m.end(), g, v)))
+            .collect();
+        // Per group: use count and first position of each variant
+        let mut usage: HashMap<usize, HashMap<usize, (usize, usize)>> = HashMap::new();
+        for &(start, _, group, variant) in &hits {
+            usage.entry(group).or_default().entry(variant).or_insert((0, start)).0 += 1;
+        }
+        let majority: HashMap<usize, usize> = usage
+            .iter()
+            .filter(|(_, variants)| variants.len() > 1)
+            .map(|(&group, variants)| {
+                let (&variant, _) = variants.iter().max_by(|a, b| a.1 .0.cmp(&b.1 .0).then(b.1 .1.cmp(&a.1 .1))).unwrap();
+                (group, variant)
+            })
+            .collect();
+
+        hits.into_iter()
+            .filter_map(|(start, end, group, variant)| {
+                let &preferred = majority.get(&group).filter(|&&p| p != variant)?;
+                let preferred_form = &self.groups[group][preferred];
+                let counts = &usage[&group];
+                let found = &text[start..end];
+                let message = format!(
+                    "\"{}\" is spelled \"{}\" {} of {} times in this document; consider using one spelling throughout.",
+                    found,
+                    preferred_form,
+                    counts[&preferred].0,
+                    counts.values().map(|c| c.0).sum::<usize>()
+                );
+                Some(OptimizationSuggestion {
+                    suggested_replacement: Some(rewrite::match_case(found, preferred_form)),
+                    ..OptimizationSuggestion::instance("consistency", "low", message, start, end)
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    fn flagged(text: &str, config: &SuggestionConfig) -> Vec<(usize, String)> {
+        VariantGroups::from_config(config)
+            .suggestions(text)
+            .into_iter()
+            .map(|s| (s.start_pos, s.suggested_replacement.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn only_the_minority_spelling_is_flagged() {
+        let text = "The color faded. Colour drained. Her color, his color, their COLOR and one more color. A colour-blind dog.";
+        let suggestions = VariantGroups::from_config(&SuggestionConfig::default()).suggestions(text);
+        let spans: Vec<(&str, &str)> =
+            suggestions.iter().map(|s| (&text[s.start_pos..s.end_pos], s.suggested_replacement.as_deref().unwrap())).collect();
+        // Case is kept in the replacement
+        assert_eq!(spans, [("Colour", "Color"), ("colour", "color")]);
+        assert_eq!(suggestions[0].message, "\"Colour\" is spelled \"color\" 5 of 7 times in this document; consider using one spelling throughout.");
+        assert_eq!(suggestions[0].suggestion_type, "consistency");
+    }
+
+    #[test]
+    fn consistent_documents_and_partial_words_are_left_alone() {
+        let config = SuggestionConfig::default();
+        // "greyhound" and "colourful" aren't "grey" and "colour"
+        assert_eq!(flagged("Grey skies, a greyhound, and Gray's coat.", &config), [(29, "Grey".to_string())]);
+        assert!(flagged("A colourful, discolored color.", &config).is_empty());
+        assert!(flagged("The colour was grey; the colour stayed grey.", &config).is_empty());
+    }
+
+    #[test]
+    fn hyphenated_and_spaced_variants_tie_to_the_first_used() {
+        let config = SuggestionConfig::default();
+        assert_eq!(flagged("Send an e-mail. Check the email.", &config), [(26, "e-mail".to_string())]);
+        assert_eq!(flagged("Our website and our Web\n  site.", &config), [(20, "Website".to_string())]);
+    }
+
+    #[test]
+    fn custom_groups_add_to_the_built_in_ones() {
+        let config = SuggestionConfig {
+            variant_groups: vec![vec!["Caitlin".to_string(), "Kaitlyn".to_string()], vec!["color".to_string(), "hue".to_string()], vec!["lonely".to_string()]],
+            ..Default::default()
+        };
+        assert_eq!(flagged("Caitlin ran. Kaitlyn hid. Caitlin won.", &config), [(13, "Caitlin".to_string())]);
+        // "color" already belongs to a built-in group; a group left with one variant is dropped
+        assert!(flagged("The hue and the color.", &config).is_empty());
+    }
+
+    #[test]
+    fn mixed_spellings_are_reported_at_the_minority_positions() {
+        let text = "Red color. Blue colour. Green color. Pink color. Gold colour. Grey color. Teal color.";
+        let report = TextProcessor::new().build_optimization_report(text);
+        let positions: Vec<usize> = report.suggestions.iter().filter(|s| s.suggestion_type == "consistency").map(|s| s.start_pos).collect();
+        let expected: Vec<usize> = text.match_indices("colour").map(|(i, _)| i).collect();
+        assert_eq!(positions, expected);
+    }
+}
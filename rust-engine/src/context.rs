@@ -0,0 +1,192 @@
+//! Short context excerpts attached to suggestions, so clients can render a
+//! suggestion without holding the whole manuscript.
+
+use serde::{Deserialize, Serialize};
+
+use crate::offsets;
+use crate::segmentation::DocumentIndex;
+use crate::suggestions::SCOPE_INSTANCE;
+use crate::OptimizationSuggestion;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextMode {
+    /// The sentence (or sentences) containing the flagged range.
+    #[default]
+    Sentence,
+    /// A fixed number of words on either side of the flagged range.
+    Words,
+}
+
+impl ContextMode {
+    pub const ALL: &'static [&'static str] = &["sentence", "words"];
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ContextOptions {
+    pub enabled: bool,
+    pub mode: ContextMode,
+    /// Words on each side in `words` mode.
+    pub words: usize,
+    /// Upper bound on each excerpt in bytes; longer excerpts are trimmed
+    /// around the flagged range at grapheme boundaries.
+    pub max_bytes: usize,
+}
+
+impl Default for ContextOptions {
+    fn default() -> Self {
+        ContextOptions { enabled: false, mode: ContextMode::Sentence, words: 8, max_bytes: 320 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestionContext {
+    pub text: String,
+    /// Byte offset of the excerpt in the document.
+    pub start_pos: usize,
+    /// Flagged range relative to the excerpt, in bytes.
+    pub highlight_start: usize,
+    pub highlight_end: usize,
+    /// The excerpt was cut to fit `max_bytes`.
+    pub truncated: bool,
+}
+
+fn natural_bounds(index: &DocumentIndex, start: usize, end: usize, options: &ContextOptions) -> (usize, usize) {
+    match options.mode {
+        ContextMode::Sentence => {
+            let first = index.sentences.iter().find(|s| s.1 > start).map_or(start, |s| s.0.min(start));
+            let last = index.sentences.iter().rev().find(|s| s.0 < end.max(start + 1)).map_or(end, |s| s.1.max(end));
+            (first, last)
+        }
+        ContextMode::Words => {
+            let before = index.words.partition_point(|w| w.1 <= start);
+            let after = index.words.partition_point(|w| w.0 < end);
+            let first = index.words[before.saturating_sub(options.words)..before].first().map_or(start, |w| w.0);
+            let last = index.words[after..(after + options.words).min(index.words.len())].last().map_or(end, |w| w.1);
+            (first.min(start), last.max(end))
+        }
+    }
+}
+
+/// Excerpt for `start..end`, trimmed to `max_bytes` by giving the flagged
+/// range priority and splitting the remaining budget between both sides.
+pub fn excerpt(index: &DocumentIndex, start: usize, end: usize, options: &ContextOptions) -> SuggestionContext {
+    let text = index.text;
+    let (mut first, mut last) = natural_bounds(index, start, end, options);
+    let mut truncated = false;
+
+    if last - first > options.max_bytes {
+        truncated = true;
+        let flagged = end - start;
+        if flagged >= options.max_bytes {
+            (first, last) = (start, offsets::grapheme_floor(text, start + options.max_bytes).max(start));
+        } else {
+            let budget = options.max_bytes - flagged;
+            let before = (start - first).min(budget / 2);
+            let after = (last - end).min(budget - before);
+            let before = (start - first).min(budget - after);
+            first = offsets::grapheme_ceil(text, start - before).min(start);
+            last = offsets::grapheme_floor(text, end + after).max(end);
+            // Prefer not to cut a word in half at either edge
+            if let Some(word) = index.words.get(index.words.partition_point(|w| w.0 < first)) {
+                first = word.0.min(start);
+            }
+            if let Some(word) = index.words.partition_point(|w| w.1 <= last).checked_sub(1).map(|i| index.words[i]) {
+                last = word.1.max(end);
+            }
+        }
+    }
+
+    let end = end.min(last);
+    SuggestionContext {
+        text: text[first..last].to_string(),
+        start_pos: first,
+        highlight_start: start - first,
+        highlight_end: end - first,
+        truncated,
+    }
+}
+
+/// Adds a context excerpt to every instance-scope suggestion and returns the
+/// total excerpt payload in bytes.
+pub fn attach(index: &DocumentIndex, suggestions: &mut [OptimizationSuggestion], options: &ContextOptions) -> usize {
+    let mut payload = 0;
+    for suggestion in suggestions.iter_mut().filter(|s| s.scope == SCOPE_INSTANCE) {
+        let start = offsets::char_floor(index.text, suggestion.start_pos);
+        let end = offsets::char_ceil(index.text, suggestion.end_pos.max(start));
+        let context = excerpt(index, start, end, options);
+        payload += context.text.len();
+        suggestion.context = Some(context);
+    }
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SuggestionConfig;
+    use crate::TextProcessor;
+    use unicode_segmentation::UnicodeSegmentation;
+
+    const TEXT: &str = "Rain fell all night. The letter was opened by her brother, who read it twice. Nobody slept.";
+
+    fn processor(context: ContextOptions) -> TextProcessor {
+        TextProcessor::with_patterns(&Default::default(), SuggestionConfig { context, ..SuggestionConfig::default() })
+    }
+
+    fn highlighted(context: &SuggestionContext) -> &str {
+        &context.text[context.highlight_start..context.highlight_end]
+    }
+
+    #[test]
+    fn sentence_excerpts_highlight_the_flagged_text() {
+        let report = processor(ContextOptions { enabled: true, ..ContextOptions::default() }).build_optimization_report(TEXT);
+        let instances: Vec<&OptimizationSuggestion> = report.suggestions.iter().filter(|s| s.scope == SCOPE_INSTANCE).collect();
+        assert!(!instances.is_empty());
+        let mut payload = 0;
+        for suggestion in &instances {
+            let context = suggestion.context.as_ref().unwrap();
+            assert_eq!(highlighted(context), &TEXT[suggestion.start_pos..suggestion.end_pos]);
+            assert_eq!(&TEXT[context.start_pos..context.start_pos + context.text.len()], context.text);
+            payload += context.text.len();
+        }
+        let passive = instances.iter().find(|s| s.suggestion_type == "passive_voice").unwrap().context.as_ref().unwrap();
+        assert_eq!(passive.text, "The letter was opened by her brother, who read it twice.");
+        // The highlight covers the span the active-voice rewrite replaces
+        assert_eq!(highlighted(passive), "The letter was opened by her brother");
+        assert_eq!(report.context_payload_bytes, Some(payload));
+        assert!(report.suggestions.iter().filter(|s| s.scope != SCOPE_INSTANCE).all(|s| s.context.is_none()));
+    }
+
+    #[test]
+    fn word_windows_and_caps_keep_the_highlight_intact() {
+        let index = TextProcessor::new().index(TEXT);
+        let start = TEXT.find("was opened").unwrap();
+        let end = start + "was opened".len();
+
+        let words = ContextOptions { enabled: true, mode: ContextMode::Words, words: 2, max_bytes: 320 };
+        let context = excerpt(&index, start, end, &words);
+        assert_eq!((context.text.as_str(), highlighted(&context), context.truncated), ("The letter was opened by her", "was opened", false));
+
+        let capped = ContextOptions { max_bytes: 24, ..ContextOptions::default() };
+        let context = excerpt(&index, start, end, &capped);
+        assert!(context.truncated);
+        assert!(context.text.len() <= 24 + 8, "{}", context.text);
+        assert_eq!(highlighted(&context), "was opened");
+        assert!(!context.text.starts_with(char::is_whitespace));
+    }
+
+    #[test]
+    fn truncation_never_splits_a_grapheme() {
+        let text = "Ahead 👩‍👩‍👧👩‍👩‍👧 the word was hidden 👩‍👩‍👧👩‍👩‍👧 behind.";
+        let index = TextProcessor::new().index(text);
+        let start = text.find("was hidden").unwrap();
+        let options = ContextOptions { max_bytes: 30, ..ContextOptions::default() };
+        let context = excerpt(&index, start, start + "was hidden".len(), &options);
+        assert_eq!(highlighted(&context), "was hidden");
+        let boundaries: Vec<usize> = text.grapheme_indices(true).map(|(i, _)| i).chain([text.len()]).collect();
+        assert!(boundaries.contains(&context.start_pos) && boundaries.contains(&(context.start_pos + context.text.len())));
+    }
+}
@@ -0,0 +1,307 @@
+//! Aggregate statistics over a reference set of documents, used to place a
+//! new document relative to an author's previous books or a house corpus.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{TextAnalysisResult, TextProcessor};
+
+const CORPUS_FORMAT_VERSION: u32 = 1;
+const DISTINCTIVE_WORD_LIMIT: usize = 20;
+const DISTINCTIVE_WORD_MIN_COUNT: usize = 3;
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct MetricSamples {
+    avg_words_per_sentence: Vec<f64>,
+    readability_score: Vec<f64>,
+    fog_index: Vec<f64>,
+    unique_word_ratio: Vec<f64>,
+    passive_voice_ratio: Vec<f64>,
+    adverb_ratio: Vec<f64>,
+    dialogue_ratio: Vec<f64>,
+}
+
+impl MetricSamples {
+    fn push(&mut self, result: &TextAnalysisResult) {
+        for (name, samples) in self.named_mut() {
+            samples.push(metric_value(result, name));
+        }
+    }
+
+    fn named(&self) -> [(&'static str, &Vec<f64>); 7] {
+        [
+            ("avg_words_per_sentence", &self.avg_words_per_sentence),
+            ("readability_score", &self.readability_score),
+            ("fog_index", &self.fog_index),
+            ("unique_word_ratio", &self.unique_word_ratio),
+            ("passive_voice_ratio", &self.passive_voice_ratio),
+            ("adverb_ratio", &self.adverb_ratio),
+            ("dialogue_ratio", &self.dialogue_ratio),
+        ]
+    }
+
+    fn named_mut(&mut self) -> [(&'static str, &mut Vec<f64>); 7] {
+        [
+            ("avg_words_per_sentence", &mut self.avg_words_per_sentence),
+            ("readability_score", &mut self.readability_score),
+            ("fog_index", &mut self.fog_index),
+            ("unique_word_ratio", &mut self.unique_word_ratio),
+            ("passive_voice_ratio", &mut self.passive_voice_ratio),
+            ("adverb_ratio", &mut self.adverb_ratio),
+            ("dialogue_ratio", &mut self.dialogue_ratio),
+        ]
+    }
+}
+
+fn metric_value(result: &TextAnalysisResult, name: &str) -> f64 {
+    match name {
+        "avg_words_per_sentence" => result.complexity_metrics.avg_words_per_sentence,
+        "readability_score" => result.readability_score,
+        "fog_index" => result.complexity_metrics.fog_index,
+        "unique_word_ratio" => result.complexity_metrics.unique_word_ratio,
+        "passive_voice_ratio" => result.style_metrics.passive_voice_ratio,
+        "adverb_ratio" => result.style_metrics.adverb_ratio,
+        "dialogue_ratio" => result.style_metrics.dialogue_ratio,
+        _ => 0.0,
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize)]
+pub struct ReferenceCorpus {
+    format_version: u32,
+    document_count: usize,
+    total_words: usize,
+    word_frequencies: BTreeMap<String, usize>,
+    samples: MetricSamples,
+    #[serde(skip)]
+    processor: Option<TextProcessor>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MetricPosition {
+    pub metric: String,
+    pub value: f64,
+    pub corpus_mean: f64,
+    pub percentile: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DistinctiveWord {
+    pub word: String,
+    pub count: usize,
+    pub per_1000_words: f64,
+    pub corpus_per_1000_words: f64,
+    pub ratio: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CorpusComparison {
+    pub document_count: usize,
+    pub metrics: Vec<MetricPosition>,
+    pub distinctive_words: Vec<DistinctiveWord>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ReferenceCorpus {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> ReferenceCorpus {
+        ReferenceCorpus {
+            format_version: CORPUS_FORMAT_VERSION,
+            document_count: 0,
+            total_words: 0,
+            word_frequencies: BTreeMap::new(),
+            samples: MetricSamples::default(),
+            processor: None,
+        }
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn document_count(&self) -> usize {
+        self.document_count
+    }
+
+    pub fn add_document(&mut self, text: &str) {
+        let processor = self.processor.get_or_insert_with(TextProcessor::new);
+        let result = processor.perform_analysis(text);
+
+        for word in processor.words.words(text) {
+            *self.word_frequencies.entry(word.to_lowercase()).or_insert(0) += 1;
+        }
+        self.total_words += result.word_count;
+        self.document_count += 1;
+        self.samples.push(&result);
+    }
+
+    /// Folds another corpus into this one, so statistics can be built up
+    /// incrementally across sessions.
+    pub fn merge(&mut self, other: &ReferenceCorpus) {
+        for (word, count) in &other.word_frequencies {
+            *self.word_frequencies.entry(word.clone()).or_insert(0) += count;
+        }
+        self.total_words += other.total_words;
+        self.document_count += other.document_count;
+        for ((_, mine), (_, theirs)) in self.samples.named_mut().into_iter().zip(other.samples.named()) {
+            mine.extend_from_slice(theirs);
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap()
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl ReferenceCorpus {
+    #[wasm_bindgen(js_name = from_bytes)]
+    pub fn from_bytes_js(bytes: &[u8]) -> Result<ReferenceCorpus, JsError> {
+        ReferenceCorpus::from_bytes(bytes).map_err(|e| JsError::new(&e))
+    }
+}
+
+impl Default for ReferenceCorpus {
+    fn default() -> Self {
+        ReferenceCorpus::new()
+    }
+}
+
+impl ReferenceCorpus {
+    pub fn from_bytes(bytes: &[u8]) -> Result<ReferenceCorpus, String> {
+        let corpus: ReferenceCorpus =
+            serde_json::from_slice(bytes).map_err(|e| format!("Invalid corpus buffer: {}", e))?;
+        if corpus.format_version > CORPUS_FORMAT_VERSION {
+            return Err(format!(
+                "Corpus format version {} is newer than supported version {}",
+                corpus.format_version, CORPUS_FORMAT_VERSION
+            ));
+        }
+        Ok(corpus)
+    }
+
+    pub(crate) fn compare(&self, processor: &TextProcessor, text: &str) -> CorpusComparison {
+        let result = processor.perform_analysis(text);
+
+        let metrics = self
+            .samples
+            .named()
+            .iter()
+            .map(|(name, samples)| {
+                let value = metric_value(&result, name);
+                MetricPosition {
+                    metric: name.to_string(),
+                    value,
+                    corpus_mean: mean(samples),
+                    percentile: percentile_rank(samples, value),
+                }
+            })
+            .collect();
+
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for word in processor.words.words(text) {
+            *counts.entry(word.to_lowercase()).or_insert(0) += 1;
+        }
+
+        // Frequency relative to the corpus (add-one smoothed), not to general English
+        let vocabulary = self.word_frequencies.len().max(1) as f64;
+        let mut distinctive_words: Vec<DistinctiveWord> = counts
+            .into_iter()
+            .filter(|(word, count)| {
+                *count >= DISTINCTIVE_WORD_MIN_COUNT && word.chars().count() >= 3 && processor.stopwords.is_content_word(word)
+            })
+            .map(|(word, count)| {
+                let per_1000_words = count as f64 * 1000.0 / result.word_count.max(1) as f64;
+                let corpus_count = self.word_frequencies.get(&word).copied().unwrap_or(0);
+                let corpus_per_1000_words = (corpus_count as f64 + 1.0) * 1000.0 / (self.total_words as f64 + vocabulary);
+                DistinctiveWord {
+                    word,
+                    count,
+                    per_1000_words,
+                    corpus_per_1000_words,
+                    ratio: per_1000_words / corpus_per_1000_words,
+                }
+            })
+            .filter(|w| w.ratio > 1.0)
+            .collect();
+        distinctive_words.sort_by(|a, b| b.ratio.total_cmp(&a.ratio).then_with(|| a.word.cmp(&b.word)));
+        distinctive_words.truncate(DISTINCTIVE_WORD_LIMIT);
+
+        CorpusComparison {
+            document_count: self.document_count,
+            metrics,
+            distinctive_words,
+        }
+    }
+}
+
+fn mean(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        0.0
+    } else {
+        samples.iter().sum::<f64>() / samples.len() as f64
+    }
+}
+
+// Mid-rank percentile: ties count as half below, half above
+fn percentile_rank(samples: &[f64], value: f64) -> f64 {
+    if samples.is_empty() {
+        return 50.0;
+    }
+    let below = samples.iter().filter(|&&s| s < value).count() as f64;
+    let equal = samples.iter().filter(|&&s| s == value).count() as f64;
+    100.0 * (below + equal / 2.0) / samples.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOKS: [&str; 3] = [
+        "The rain fell on the farm. Mara watched the field and waited for the letter.",
+        "The storm came in the night. Mara walked the field and read the letter twice.",
+        "Morning light reached the farm. Her brother arrived and they talked about the debts.",
+    ];
+
+    #[test]
+    fn merged_corpora_equal_one_built_at_once() {
+        let mut whole = ReferenceCorpus::new();
+        for book in BOOKS {
+            whole.add_document(book);
+        }
+        let mut first = ReferenceCorpus::new();
+        first.add_document(BOOKS[0]);
+        let mut rest = ReferenceCorpus::new();
+        rest.add_document(BOOKS[1]);
+        rest.add_document(BOOKS[2]);
+        let mut restored = ReferenceCorpus::from_bytes(&rest.to_bytes()).unwrap_or_else(|_| panic!("round trip"));
+        first.merge(&restored);
+        restored.merge(&ReferenceCorpus::new());
+
+        assert_eq!(first.document_count(), 3);
+        assert_eq!(first.to_bytes(), whole.to_bytes());
+    }
+
+    #[test]
+    fn comparison_places_metrics_and_finds_words_frequent_against_the_corpus() {
+        let mut corpus = ReferenceCorpus::new();
+        for book in BOOKS {
+            corpus.add_document(book);
+        }
+        let processor = TextProcessor::new();
+        let comparison = corpus.compare(&processor, "The spaceship hummed. The spaceship turned. The spaceship landed on the farm.");
+        assert_eq!(comparison.document_count, 3);
+        assert_eq!(comparison.metrics.len(), 7);
+        assert!(comparison.metrics.iter().all(|m| (0.0..=100.0).contains(&m.percentile)));
+        assert_eq!(comparison.distinctive_words[0].word, "spaceship");
+        assert!(comparison.distinctive_words.iter().all(|w| w.word != "farm"));
+    }
+
+    #[test]
+    fn percentile_rank_splits_ties() {
+        assert_eq!(percentile_rank(&[1.0, 2.0, 3.0, 4.0], 3.0), 62.5);
+        assert_eq!(percentile_rank(&[], 3.0), 50.0);
+        assert_eq!(percentile_rank(&[1.0, 2.0], 9.0), 100.0);
+    }
+}
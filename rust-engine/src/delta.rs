@@ -0,0 +1,282 @@
+//! Incremental suggestion updates keyed by fingerprint, so a collaboration
+//! server can push what changed instead of the whole list.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::context::SuggestionContext;
+use crate::{OptimizationSuggestion, RelatedSpan};
+
+/// Same fingerprint, new position. Sent instead of remove + add so client
+/// state attached to the suggestion survives.
+#[derive(Serialize, Deserialize)]
+pub struct SuggestionMove {
+    pub fingerprint: String,
+    pub start_pos: usize,
+    pub end_pos: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related_spans: Vec<RelatedSpan>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<SuggestionContext>,
+    #[serde(default)]
+    pub start_utf16: usize,
+    #[serde(default)]
+    pub end_utf16: usize,
+    #[serde(default)]
+    pub line: usize,
+    #[serde(default)]
+    pub column: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AddedSuggestion {
+    /// Position in the new list.
+    pub index: usize,
+    pub suggestion: OptimizationSuggestion,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct SuggestionDelta {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub moved: Vec<SuggestionMove>,
+    /// Same fingerprint with other fields changed, e.g. an escalated priority.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub updated: Vec<OptimizationSuggestion>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<AddedSuggestion>,
+    /// Full fingerprint order of the new list, sent only when surviving
+    /// suggestions changed relative order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<Vec<String>>,
+}
+
+impl SuggestionMove {
+    fn of(suggestion: &OptimizationSuggestion) -> SuggestionMove {
+        SuggestionMove {
+            fingerprint: suggestion.fingerprint.clone(),
+            start_pos: suggestion.start_pos,
+            end_pos: suggestion.end_pos,
+            related_spans: suggestion.related_spans.clone(),
+            context: suggestion.context.clone(),
+            start_utf16: suggestion.start_utf16,
+            end_utf16: suggestion.end_utf16,
+            line: suggestion.line,
+            column: suggestion.column,
+        }
+    }
+
+    fn apply_to(&self, suggestion: &mut OptimizationSuggestion) {
+        suggestion.start_pos = self.start_pos;
+        suggestion.end_pos = self.end_pos;
+        suggestion.related_spans = self.related_spans.clone();
+        suggestion.context = self.context.clone();
+        suggestion.start_utf16 = self.start_utf16;
+        suggestion.end_utf16 = self.end_utf16;
+        suggestion.line = self.line;
+        suggestion.column = self.column;
+    }
+}
+
+fn by_fingerprint<'a>(suggestions: &'a [OptimizationSuggestion], side: &str) -> Result<HashMap<&'a str, usize>, String> {
+    let mut positions = HashMap::with_capacity(suggestions.len());
+    for (i, suggestion) in suggestions.iter().enumerate() {
+        if suggestion.fingerprint.is_empty() {
+            return Err(format!("{} suggestion {} has no fingerprint", side, i));
+        }
+        if positions.insert(suggestion.fingerprint.as_str(), i).is_some() {
+            return Err(format!("{} suggestions repeat fingerprint \"{}\"", side, suggestion.fingerprint));
+        }
+    }
+    Ok(positions)
+}
+
+pub fn diff(old: &[OptimizationSuggestion], new: &[OptimizationSuggestion]) -> Result<SuggestionDelta, String> {
+    let old_positions = by_fingerprint(old, "old")?;
+    let new_positions = by_fingerprint(new, "new")?;
+    let mut delta = SuggestionDelta {
+        removed: old.iter().filter(|s| !new_positions.contains_key(s.fingerprint.as_str())).map(|s| s.fingerprint.clone()).collect(),
+        ..SuggestionDelta::default()
+    };
+
+    let mut surviving_order = Vec::new();
+    for (index, suggestion) in new.iter().enumerate() {
+        let Some(&i) = old_positions.get(suggestion.fingerprint.as_str()) else {
+            delta.added.push(AddedSuggestion { index, suggestion: suggestion.clone() });
+            continue;
+        };
+        surviving_order.push(i);
+        let previous = &old[i];
+        if previous == suggestion {
+            continue;
+        }
+        let mut moved = previous.clone();
+        SuggestionMove::of(suggestion).apply_to(&mut moved);
+        if moved == *suggestion {
+            delta.moved.push(SuggestionMove::of(suggestion));
+        } else {
+            delta.updated.push(suggestion.clone());
+        }
+    }
+
+    if surviving_order.windows(2).any(|w| w[0] > w[1]) {
+        delta.order = Some(new.iter().map(|s| s.fingerprint.clone()).collect());
+    }
+    Ok(delta)
+}
+
+/// Rebuilds the new list from the old one and a delta produced by `diff`.
+pub fn apply(old: Vec<OptimizationSuggestion>, delta: SuggestionDelta) -> Result<Vec<OptimizationSuggestion>, String> {
+    by_fingerprint(&old, "old")?;
+    let unknown = |fingerprint: &str| format!("delta refers to unknown fingerprint \"{}\"", fingerprint);
+
+    let removed: HashSet<&str> = delta.removed.iter().map(String::as_str).collect();
+    let mut survivors: Vec<OptimizationSuggestion> = old.into_iter().filter(|s| !removed.contains(s.fingerprint.as_str())).collect();
+    let positions: HashMap<String, usize> = survivors.iter().enumerate().map(|(i, s)| (s.fingerprint.clone(), i)).collect();
+
+    for movement in &delta.moved {
+        let &i = positions.get(&movement.fingerprint).ok_or_else(|| unknown(&movement.fingerprint))?;
+        movement.apply_to(&mut survivors[i]);
+    }
+    for updated in delta.updated {
+        let &i = positions.get(&updated.fingerprint).ok_or_else(|| unknown(&updated.fingerprint))?;
+        survivors[i] = updated;
+    }
+
+    let total = survivors.len() + delta.added.len();
+    if let Some(order) = delta.order {
+        if order.len() != total {
+            return Err(format!("delta order lists {} suggestions, expected {}", order.len(), total));
+        }
+        let mut pool: HashMap<String, OptimizationSuggestion> =
+            survivors.into_iter().chain(delta.added.into_iter().map(|a| a.suggestion)).map(|s| (s.fingerprint.clone(), s)).collect();
+        return order.iter().map(|f| pool.remove(f).ok_or_else(|| unknown(f))).collect();
+    }
+
+    let mut added = delta.added;
+    added.sort_by_key(|a| a.index);
+    let mut result = Vec::with_capacity(total);
+    let mut survivors = survivors.into_iter();
+    for entry in added {
+        if entry.index > total {
+            return Err(format!("added suggestion index {} is past the end of the list ({})", entry.index, total));
+        }
+        while result.len() < entry.index {
+            result.push(survivors.next().ok_or_else(|| format!("added suggestion index {} leaves a gap", entry.index))?);
+        }
+        result.push(entry.suggestion);
+    }
+    result.extend(survivors);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SuggestionConfig;
+    use crate::TextProcessor;
+    use crate::test_support::Cases;
+
+    const SENTENCES: &[&str] = &[
+        "The letter was opened by her brother.",
+        "I really think we should leave now.",
+        "Mara walked quickly across the frozen field.",
+        "The report was finished by the committee after a very long and tiring night of arguments about nothing at all.",
+        "The storm came.",
+        "You should obviously read it again.",
+    ];
+
+    fn round_trip(old: &[OptimizationSuggestion], new: &[OptimizationSuggestion]) -> SuggestionDelta {
+        let delta = diff(old, new).unwrap();
+        // Travels as JSON between server and client
+        let wire: SuggestionDelta = serde_json::from_str(&serde_json::to_string(&delta).unwrap()).unwrap();
+        assert!(apply(old.to_vec(), wire).unwrap() == new, "delta did not reproduce the new list");
+        delta
+    }
+
+    #[test]
+    fn randomized_edit_sequences_round_trip() {
+        let processor = TextProcessor::with_patterns(&Default::default(), SuggestionConfig::default());
+        let mut cases = Cases(0x2545_f491_4f6c_dd1d);
+        for _ in 0..12 {
+            let mut paragraph: Vec<&str> = (0..6).map(|_| SENTENCES[cases.below(SENTENCES.len())]).collect();
+            let mut old = processor.build_optimization_report(&paragraph.join(" ")).suggestions;
+            for _ in 0..8 {
+                match cases.below(3) {
+                    0 => paragraph.insert(cases.below(paragraph.len() + 1), SENTENCES[cases.below(SENTENCES.len())]),
+                    1 if paragraph.len() > 1 => {
+                        paragraph.remove(cases.below(paragraph.len()));
+                    }
+                    _ => {
+                        let (a, b) = (cases.below(paragraph.len()), cases.below(paragraph.len()));
+                        paragraph.swap(a, b);
+                    }
+                }
+                let new = processor.build_optimization_report(&paragraph.join(" ")).suggestions;
+                round_trip(&old, &new);
+                old = new;
+            }
+        }
+    }
+
+    #[test]
+    fn randomized_list_shuffles_round_trip() {
+        let suggestion = |i: usize| {
+            let mut s = OptimizationSuggestion::instance("adverb_usage".to_string(), "low", "m", i * 10, i * 10 + 4);
+            s.fingerprint = format!("f{}", i);
+            s
+        };
+        let mut cases = Cases(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..200 {
+            let old: Vec<OptimizationSuggestion> = (0..cases.below(8)).map(suggestion).collect();
+            let mut new: Vec<OptimizationSuggestion> = old.iter().filter(|_| cases.below(4) != 0).cloned().collect();
+            for s in new.iter_mut() {
+                match cases.below(4) {
+                    0 => s.start_pos += 1,
+                    1 => s.priority = "high".into(),
+                    _ => {}
+                }
+            }
+            for i in 0..cases.below(3) {
+                let at = cases.below(new.len() + 1);
+                new.insert(at, suggestion(100 + i));
+            }
+            if new.len() > 1 && cases.below(3) == 0 {
+                let (a, b) = (cases.below(new.len()), cases.below(new.len()));
+                new.swap(a, b);
+            }
+            round_trip(&old, &new);
+        }
+    }
+
+    #[test]
+    fn edits_before_a_suggestion_are_sent_as_moves() {
+        let processor = TextProcessor::with_patterns(&Default::default(), SuggestionConfig::default());
+        let text = "The storm came at night and the old barn held until morning. Mara walked quickly across the field.";
+        let old = processor.build_optimization_report(text).suggestions;
+        let new = processor.build_optimization_report(&format!("It was cold. {}", text)).suggestions;
+        let delta = round_trip(&old, &new);
+        assert!(delta.removed.is_empty() && delta.added.is_empty() && delta.order.is_none());
+        let instances: Vec<&OptimizationSuggestion> = old.iter().filter(|s| s.scope == "instance").collect();
+        assert!(!instances.is_empty());
+        assert_eq!(delta.moved.len(), instances.len());
+        assert_eq!(delta.moved[0].fingerprint, instances[0].fingerprint);
+        assert_eq!(delta.moved[0].start_pos, instances[0].start_pos + "It was cold. ".len());
+        // Only whole-document suggestions, whose span is the text, change otherwise
+        assert!(delta.updated.iter().all(|s| s.scope == "document"));
+    }
+
+    #[test]
+    fn malformed_lists_and_deltas_are_rejected() {
+        let unmarked = OptimizationSuggestion::instance("adverb_usage".to_string(), "low", "m", 0, 4);
+        assert!(diff(std::slice::from_ref(&unmarked), &[]).err().unwrap().contains("no fingerprint"));
+        let mut marked = unmarked;
+        marked.fingerprint = "f".to_string();
+        assert!(diff(&[marked.clone(), marked.clone()], &[]).err().unwrap().contains("repeat"));
+        let stray = SuggestionDelta { removed: vec![], moved: vec![SuggestionMove::of(&marked)], ..Default::default() };
+        assert!(apply(Vec::new(), stray).err().unwrap().contains("unknown fingerprint"));
+        let gap = SuggestionDelta { added: vec![AddedSuggestion { index: 3, suggestion: marked }], ..Default::default() };
+        assert!(apply(Vec::new(), gap).is_err());
+    }
+}
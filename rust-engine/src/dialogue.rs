@@ -0,0 +1,179 @@
+//! Quoted speech: straight and typographic double quotes, and typographic
+//! single quotes as UK-style dialogue uses them, with apostrophes told apart
+//! from closing quotes.
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Quote {
+    Straight,
+    /// “ ”
+    Double,
+    /// ‘ ’
+    Single,
+}
+
+impl Quote {
+    fn opener(self) -> char {
+        match self {
+            Quote::Straight => '"',
+            Quote::Double => '\u{201C}',
+            Quote::Single => '\u{2018}',
+        }
+    }
+}
+
+fn prev_char(text: &str, at: usize) -> Option<char> {
+    text[..at].chars().next_back()
+}
+
+fn next_char(text: &str, at: usize) -> Option<char> {
+    text[at..].chars().nth(1)
+}
+
+/// The quote `c` at `at` opens, if any. A ‘ opens only at the start of a
+/// word, and not before a digit ("‘90s").
+fn opens(text: &str, at: usize, c: char) -> Option<Quote> {
+    match c {
+        '"' => Some(Quote::Straight),
+        '\u{201C}' => Some(Quote::Double),
+        '\u{2018}' => {
+            let word_start = !prev_char(text, at).is_some_and(char::is_alphanumeric);
+            let next = next_char(text, at);
+            (word_start && next.is_some_and(|n| !n.is_whitespace() && !n.is_ascii_digit())).then_some(Quote::Single)
+        }
+        _ => None,
+    }
+}
+
+/// Whether the ’ at `at` can close a single quote: not between letters
+/// ("don’t") or after a space.
+fn can_close_single(text: &str, at: usize) -> bool {
+    prev_char(text, at).is_some_and(|p| !p.is_whitespace()) && !next_char(text, at).is_some_and(char::is_alphanumeric)
+}
+
+/// Whether `c` at `at` closes `quote`. A ’ after an "s" is taken for a
+/// plural possessive ("the girls’ fault") when another closing quote
+/// follows in the same paragraph.
+fn closes(text: &str, at: usize, c: char, quote: Quote) -> bool {
+    match quote {
+        Quote::Straight => c == '"',
+        // Straight closers turn up where autocorrect missed one
+        Quote::Double => c == '\u{201D}' || c == '"',
+        Quote::Single => {
+            if c != '\u{2019}' || !can_close_single(text, at) {
+                return false;
+            }
+            if !prev_char(text, at).is_some_and(|p| p == 's' || p == 'S') {
+                return true;
+            }
+            let rest = &text[at + c.len_utf8()..];
+            let paragraph = &rest[..rest.find("\n\n").unwrap_or(rest.len())];
+            let later = paragraph
+                .char_indices()
+                .take_while(|&(_, n)| n != '\u{2018}')
+                .any(|(i, n)| n == '\u{2019}' && can_close_single(text, at + c.len_utf8() + i));
+            !later
+        }
+    }
+}
+
+/// End of the whitespace run at `at` when it is a paragraph break (two or
+/// more line breaks).
+fn paragraph_break_end(text: &str, at: usize) -> Option<usize> {
+    let rest = &text[at..];
+    let run = rest.len() - rest.trim_start().len();
+    (rest[..run].matches('\n').count() >= 2).then_some(at + run)
+}
+
+/// Byte spans of quoted speech, each from its opening through its closing
+/// quote. Quotes nested inside a quotation are part of it. A quotation
+/// running over several paragraphs leaves each one open and reopens the
+/// next, so it spans them all; one left open without a reopening ends with
+/// its paragraph.
+pub fn spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut open: Option<(Quote, usize)> = None;
+    // Characters before this were consumed by a paragraph break
+    let mut resume = 0;
+    for (i, c) in text.char_indices() {
+        if i < resume {
+            continue;
+        }
+        let Some((quote, start)) = open else {
+            open = opens(text, i, c).map(|quote| (quote, i));
+            continue;
+        };
+        if closes(text, i, c, quote) {
+            spans.push((start, i + c.len_utf8()));
+            open = None;
+        } else if c == '\n' {
+            if let Some(next) = paragraph_break_end(text, i) {
+                if text[next..].starts_with(quote.opener()) {
+                    resume = next + quote.opener().len_utf8();
+                } else {
+                    spans.push((start, i));
+                    open = None;
+                    resume = next;
+                }
+            }
+        }
+    }
+    if let Some((_, start)) = open {
+        spans.push((start, text.trim_end().len().max(start)));
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    fn quoted(text: &str) -> Vec<&str> {
+        spans(text).into_iter().map(|(s, e)| &text[s..e]).collect()
+    }
+
+    #[test]
+    fn straight_and_curly_double_quotes() {
+        assert_eq!(quoted("\"Run,\" she said. \u{201C}Now!\u{201D}"), ["\"Run,\"", "\u{201C}Now!\u{201D}"]);
+        // A straight closer after a curly opener
+        assert_eq!(quoted("\u{201C}Wait.\" He left."), ["\u{201C}Wait.\""]);
+    }
+
+    #[test]
+    fn single_quoted_dialogue_keeps_its_contractions() {
+        let text = "\u{2018}I don\u{2019}t know,\u{2019} said Mara. \u{2018}We\u{2019}re late.\u{2019} It was the \u{2018}90s.";
+        assert_eq!(quoted(text), ["\u{2018}I don\u{2019}t know,\u{2019}", "\u{2018}We\u{2019}re late.\u{2019}"]);
+        // Apostrophes alone are never dialogue
+        assert!(quoted("Mara\u{2019}s dog didn\u{2019}t bark. Rock \u{2019}n\u{2019} roll.").is_empty());
+    }
+
+    #[test]
+    fn plural_possessives_do_not_close_early() {
+        let text = "\u{2018}It was the girls\u{2019} fault, not mine,\u{2019} he said.";
+        assert_eq!(quoted(text), ["\u{2018}It was the girls\u{2019} fault, not mine,\u{2019}"]);
+        // With no later closer, the possessive-looking quote closes
+        assert_eq!(quoted("\u{2018}Ask the girls\u{2019} she said."), ["\u{2018}Ask the girls\u{2019}"]);
+    }
+
+    #[test]
+    fn a_quotation_spans_paragraphs_it_reopens() {
+        let text = "\u{201C}It began in spring.\n\n\u{201C}By summer it was over.\u{201D}\n\nShe stopped.";
+        assert_eq!(quoted(text), ["\u{201C}It began in spring.\n\n\u{201C}By summer it was over.\u{201D}"]);
+        // Not reopened: the quotation ends with its paragraph
+        let text = "\"Unclosed speech.\n\nNarration follows. \"Then more.\"";
+        assert_eq!(quoted(text), ["\"Unclosed speech.", "\"Then more.\""]);
+        // Open at the end of the document
+        assert_eq!(quoted("He said, \u{201C}and then  \n"), ["\u{201C}and then"]);
+    }
+
+    #[test]
+    fn typographic_quotes_count_as_dialogue_like_straight_ones() {
+        let processor = TextProcessor::new();
+        let ratio = |text: &str| processor.perform_analysis(text).style_metrics.dialogue_ratio;
+        let straight = ratio("\"We should go now, before the storm,\" she said. The road was empty.");
+        assert!(straight > 0.0);
+        assert_eq!(ratio("\u{201C}We should go now, before the storm,\u{201D} she said. The road was empty."), straight);
+        assert_eq!(ratio("\u{2018}We shouldn\u{2019}t stay, not before the storm,\u{2019} she said. The road was empty."), straight);
+        assert_eq!(ratio("Mara didn\u{2019}t wait. She wasn\u{2019}t going to."), 0.0);
+    }
+}
@@ -0,0 +1,339 @@
+//! Text diff for carrying spans across edits. Lines are matched first, then
+//! the tokens of each changed run of lines, so a heavily revised manuscript
+//! stays cheap to compare while edits inside a line are still located
+//! precisely.
+
+/// Edit distance past which a comparison gives up and treats the whole
+/// region as replaced. Bounds memory on unrelated texts.
+const MAX_EDIT_DISTANCE: usize = 2048;
+
+/// A run of bytes that is identical in both texts.
+#[derive(Clone, Copy)]
+pub struct Segment {
+    pub old_start: usize,
+    pub new_start: usize,
+    pub len: usize,
+}
+
+pub struct TextDiff {
+    /// Increasing in both `old_start` and `new_start`, never overlapping.
+    pub segments: Vec<Segment>,
+}
+
+/// Myers' O(ND) shortest edit script, returned as matched index pairs.
+/// `None` when the edit distance exceeds `max_d`.
+fn myers<T: PartialEq>(a: &[T], b: &[T], max_d: usize) -> Option<Vec<(usize, usize)>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let limit = (n + m).min(max_d as isize);
+    let offset = limit + 1;
+    let mut v = vec![0isize; (2 * limit + 3) as usize];
+    // trace[d] holds v[-d..=d] after step d
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    for d in 0..=limit {
+        let mut k = -d;
+        let mut done = false;
+        while k <= d {
+            let down = k == -d || (k != d && v[(offset + k - 1) as usize] < v[(offset + k + 1) as usize]);
+            let mut x = if down { v[(offset + k + 1) as usize] } else { v[(offset + k - 1) as usize] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(offset + k) as usize] = x;
+            if x >= n && y >= m {
+                done = true;
+                break;
+            }
+            k += 2;
+        }
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        if done {
+            return Some(backtrack(&trace, n, m));
+        }
+    }
+    None
+}
+
+fn backtrack(trace: &[Vec<isize>], n: isize, m: isize) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..trace.len() as isize).rev() {
+        let previous = &trace[(d - 1) as usize];
+        let at = |k: isize| previous[(k + d - 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            pairs.push((x as usize, y as usize));
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    while x > 0 && y > 0 {
+        x -= 1;
+        y -= 1;
+        pairs.push((x as usize, y as usize));
+    }
+    pairs.reverse();
+    pairs
+}
+
+/// Pieces of `text[start..end]` with their byte offsets.
+type Pieces<'t> = Vec<(usize, &'t str)>;
+
+fn lines(text: &str) -> Pieces<'_> {
+    let mut offset = 0;
+    text.split_inclusive('\n')
+        .map(|line| {
+            let piece = (offset, line);
+            offset += line.len();
+            piece
+        })
+        .collect()
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum TokenClass {
+    Word,
+    Space,
+    Other,
+}
+
+fn class(c: char) -> TokenClass {
+    if c.is_alphanumeric() || c == '\'' || c == '\u{2019}' {
+        TokenClass::Word
+    } else if c.is_whitespace() {
+        TokenClass::Space
+    } else {
+        TokenClass::Other
+    }
+}
+
+/// Word runs, whitespace runs, and single punctuation characters.
+fn tokens(text: &str, start: usize, end: usize) -> Pieces<'_> {
+    let mut pieces = Vec::new();
+    let mut token_start = start;
+    let mut previous: Option<TokenClass> = None;
+    for (i, c) in text[start..end].char_indices() {
+        let current = class(c);
+        if let Some(previous) = previous {
+            if previous != current || current == TokenClass::Other {
+                pieces.push((token_start, &text[token_start..start + i]));
+                token_start = start + i;
+            }
+        }
+        previous = Some(current);
+    }
+    if token_start < end {
+        pieces.push((token_start, &text[token_start..end]));
+    }
+    pieces
+}
+
+fn push_segment(segments: &mut Vec<Segment>, old_start: usize, new_start: usize, len: usize) {
+    if len == 0 {
+        return;
+    }
+    if let Some(last) = segments.last_mut() {
+        if last.old_start + last.len == old_start && last.new_start + last.len == new_start {
+            last.len += len;
+            return;
+        }
+    }
+    segments.push(Segment { old_start, new_start, len });
+}
+
+/// Matches the tokens of one changed region and appends the equal runs.
+fn diff_region(old: &str, new: &str, (old_start, old_end): (usize, usize), (new_start, new_end): (usize, usize), segments: &mut Vec<Segment>) {
+    if old_start == old_end || new_start == new_end {
+        return;
+    }
+    let a = tokens(old, old_start, old_end);
+    let b = tokens(new, new_start, new_end);
+    let a_strs: Vec<&str> = a.iter().map(|t| t.1).collect();
+    let b_strs: Vec<&str> = b.iter().map(|t| t.1).collect();
+    for (i, j) in myers(&a_strs, &b_strs, MAX_EDIT_DISTANCE).unwrap_or_default() {
+        push_segment(segments, a[i].0, b[j].0, a[i].1.len());
+    }
+}
+
+fn is_word_char(c: Option<char>) -> bool {
+    c.is_some_and(|c| class(c) == TokenClass::Word)
+}
+
+/// Backs the shared prefix and suffix off to token boundaries, so a word
+/// edited in the middle is treated as replaced rather than half-matched.
+fn snap_to_tokens(old: &str, new: &str, mut prefix: usize, mut suffix: usize) -> (usize, usize) {
+    while prefix > 0
+        && is_word_char(old[..prefix].chars().next_back())
+        && (is_word_char(old[prefix..].chars().next()) || is_word_char(new[prefix..].chars().next()))
+    {
+        prefix -= old[..prefix].chars().next_back().map_or(1, char::len_utf8);
+    }
+    while suffix > 0 {
+        let (old_split, new_split) = (old.len() - suffix, new.len() - suffix);
+        if !is_word_char(old[old_split..].chars().next())
+            || !(is_word_char(old[..old_split].chars().next_back()) || is_word_char(new[..new_split].chars().next_back()))
+        {
+            break;
+        }
+        suffix -= old[old_split..].chars().next().map_or(1, char::len_utf8);
+    }
+    (prefix, suffix)
+}
+
+/// How natural a pure insertion or deletion looks with this text: ending
+/// a sentence or line beats ending at a space, which beats mid-word.
+fn gap_score(gap: &str) -> u8 {
+    match gap.chars().next_back() {
+        Some('.' | '!' | '?' | '\n') => 2,
+        Some(c) if c.is_whitespace() => 1,
+        _ => 0,
+    }
+}
+
+/// A pure insertion or deletion can often sit at several positions with the
+/// same diff ("gamma[. Human bit]. Delta" vs "gamma.[ Human bit.] Delta").
+/// Slides each one right to the position whose boundaries read best.
+fn slide_gaps(old: &str, new: &str, segments: &mut [Segment]) {
+    for i in 1..segments.len() {
+        let (before, after) = (segments[i - 1], segments[i]);
+        let old_gap = after.old_start - (before.old_start + before.len);
+        let new_gap = after.new_start - (before.new_start + before.len);
+        if (old_gap == 0) == (new_gap == 0) {
+            continue;
+        }
+        let (text, gap_start, next_start) = if new_gap == 0 {
+            (old, before.old_start + before.len, after.old_start)
+        } else {
+            (new, before.new_start + before.len, after.new_start)
+        };
+        let gap_len = next_start - gap_start;
+
+        let (mut best, mut best_score) = (0, gap_score(&text[gap_start..next_start]));
+        let mut shift = 0;
+        while shift < after.len {
+            let (Some(a), Some(b)) = (text[gap_start + shift..].chars().next(), text[next_start + shift..].chars().next()) else {
+                break;
+            };
+            if a != b {
+                break;
+            }
+            shift += a.len_utf8();
+            let score = gap_score(&text[gap_start + shift..gap_start + shift + gap_len]);
+            if score > best_score && shift < after.len {
+                (best, best_score) = (shift, score);
+            }
+        }
+
+        segments[i - 1].len += best;
+        segments[i].old_start += best;
+        segments[i].new_start += best;
+        segments[i].len -= best;
+    }
+}
+
+impl TextDiff {
+    pub fn compute(old: &str, new: &str) -> TextDiff {
+        let mut segments = Vec::new();
+
+        // Shared prefix and suffix are matched directly, at char boundaries
+        let prefix = old.char_indices().zip(new.chars()).take_while(|((_, a), b)| a == b).last().map_or(0, |((i, c), _)| i + c.len_utf8());
+        let suffix = old[prefix..]
+            .chars()
+            .rev()
+            .zip(new[prefix..].chars().rev())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .sum::<usize>();
+        let (prefix, suffix) = snap_to_tokens(old, new, prefix, suffix);
+        push_segment(&mut segments, 0, 0, prefix);
+
+        let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+        let old_lines: Pieces = lines(&old[prefix..old_end]).into_iter().map(|(i, l)| (prefix + i, l)).collect();
+        let new_lines: Pieces = lines(&new[prefix..new_end]).into_iter().map(|(i, l)| (prefix + i, l)).collect();
+        let a: Vec<&str> = old_lines.iter().map(|l| l.1).collect();
+        let b: Vec<&str> = new_lines.iter().map(|l| l.1).collect();
+
+        let mut cursor = (prefix, prefix);
+        for (i, j) in myers(&a, &b, MAX_EDIT_DISTANCE).unwrap_or_default() {
+            let (old_line, new_line) = (old_lines[i], new_lines[j]);
+            diff_region(old, new, (cursor.0, old_line.0), (cursor.1, new_line.0), &mut segments);
+            push_segment(&mut segments, old_line.0, new_line.0, old_line.1.len());
+            cursor = (old_line.0 + old_line.1.len(), new_line.0 + new_line.1.len());
+        }
+        diff_region(old, new, (cursor.0, old_end), (cursor.1, new_end), &mut segments);
+
+        push_segment(&mut segments, old_end, new_end, suffix);
+        slide_gaps(old, new, &mut segments);
+        TextDiff { segments }
+    }
+
+    /// Where the surviving parts of `old[start..end]` ended up in the new
+    /// text. Edited or inserted text inside the span is excluded, so a span
+    /// with an insertion in the middle comes back as two ranges.
+    pub fn map_span(&self, start: usize, end: usize) -> Vec<(usize, usize)> {
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        let first = self.segments.partition_point(|s| s.old_start + s.len <= start);
+        for segment in self.segments[first..].iter().take_while(|s| s.old_start < end) {
+            let from = start.max(segment.old_start);
+            let to = end.min(segment.old_start + segment.len);
+            if from >= to {
+                continue;
+            }
+            let range = (segment.new_start + from - segment.old_start, segment.new_start + to - segment.old_start);
+            match ranges.last_mut() {
+                Some(last) if last.1 == range.0 => last.1 = range.1,
+                _ => ranges.push(range),
+            }
+        }
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapped<'t>(old: &str, new: &'t str, needle: &str) -> Vec<&'t str> {
+        let start = old.find(needle).unwrap();
+        TextDiff::compute(old, new).map_span(start, start + needle.len()).into_iter().map(|(s, e)| &new[s..e]).collect()
+    }
+
+    #[test]
+    fn spans_follow_edits_before_them() {
+        let old = "The storm came.\nMara walked the field.\nThe letter was late.";
+        let new = "A long rainy night.\nThe storm came early.\nMara walked the field.\nThe letter was late.";
+        assert_eq!(mapped(old, new, "Mara walked"), ["Mara walked"]);
+        assert_eq!(mapped(old, new, "letter was late"), ["letter was late"]);
+    }
+
+    #[test]
+    fn insertions_split_a_span_and_deletions_shrink_it() {
+        let old = "Mara walked the field at dawn.";
+        assert_eq!(mapped(old, "Mara walked slowly across the field at dawn.", "walked the field"), ["walked ", "the field"]);
+        assert_eq!(mapped(old, "Mara walked at dawn.", "walked the field at"), ["walked at"]);
+        assert!(mapped(old, "At dawn.", "walked the field").is_empty());
+    }
+
+    #[test]
+    fn segments_increase_and_cover_identical_text() {
+        let old = "One.\nTwo three four.\nFive.";
+        let new = "One.\nTwo 3 four.\nFive six.";
+        let diff = TextDiff::compute(old, new);
+        for pair in diff.segments.windows(2) {
+            assert!(pair[0].old_start + pair[0].len <= pair[1].old_start);
+            assert!(pair[0].new_start + pair[0].len <= pair[1].new_start);
+        }
+        for s in &diff.segments {
+            assert_eq!(old[s.old_start..s.old_start + s.len], new[s.new_start..s.new_start + s.len]);
+        }
+        let same = TextDiff::compute(old, old);
+        assert_eq!(same.segments.iter().map(|s| s.len).sum::<usize>(), old.len());
+    }
+}
@@ -0,0 +1,171 @@
+//! Lexical diversity that holds up across text lengths, unlike the plain
+//! type-token ratio: MTLD and the moving-average type-token ratio.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+pub const DEFAULT_MATTR_WINDOW: usize = 100;
+
+/// MTLD closes a factor each time the running type-token ratio falls to this.
+const MTLD_THRESHOLD: f64 = 0.72;
+
+/// Factors in one MTLD pass, the unfinished last one counted in part.
+fn mtld_factors<'w, T: Eq + Hash + 'w>(words: impl Iterator<Item = &'w T>) -> f64 {
+    let mut seen: HashSet<&T> = HashSet::new();
+    let (mut factors, mut tokens) = (0.0, 0usize);
+    for word in words {
+        seen.insert(word);
+        tokens += 1;
+        if (seen.len() as f64 / tokens as f64) <= MTLD_THRESHOLD {
+            factors += 1.0;
+            seen.clear();
+            tokens = 0;
+        }
+    }
+    if tokens > 0 {
+        factors += (1.0 - seen.len() as f64 / tokens as f64) / (1.0 - MTLD_THRESHOLD);
+    }
+    factors
+}
+
+/// Measure of textual lexical diversity (McCarthy and Jarvis): words per
+/// factor, averaged over a forward and a backward pass. Text too short to
+/// close a factor counts as one, so the score never exceeds the word count;
+/// empty text scores 0. Words are compared for equality only, so they can
+/// be lowercased strings or ids standing for them.
+pub fn mtld<T: Eq + Hash>(words: &[T]) -> f64 {
+    if words.is_empty() {
+        return 0.0;
+    }
+    let n = words.len() as f64;
+    let forward = mtld_factors(words.iter());
+    let backward = mtld_factors(words.iter().rev());
+    (n / forward.max(1.0) + n / backward.max(1.0)) / 2.0
+}
+
+/// Mean type-token ratio over every run of `window` consecutive words; text
+/// shorter than the window gets its plain type-token ratio.
+pub fn mattr<T: Eq + Hash>(words: &[T], window: usize) -> f64 {
+    if words.is_empty() {
+        return 0.0;
+    }
+    let window = window.max(1);
+    let mut counts: HashMap<&T, usize> = HashMap::new();
+    if words.len() <= window {
+        for word in words {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+        return counts.len() as f64 / words.len() as f64;
+    }
+    let mut total = 0.0;
+    for (i, word) in words.iter().enumerate() {
+        *counts.entry(word).or_insert(0) += 1;
+        if i >= window {
+            let dropped = &words[i - window];
+            let count = counts.get_mut(dropped).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(dropped);
+            }
+        }
+        if i + 1 >= window {
+            total += counts.len() as f64 / window as f64;
+        }
+    }
+    total / (words.len() - window + 1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+    use crate::config::{AnalysisOptions, SuggestionConfig};
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    /// `count` words that never repeat, for the varied side of a comparison.
+    fn varied(count: usize) -> Vec<String> {
+        let (onsets, vowels, codas) = (["b", "d", "f", "g", "k", "l", "m", "n", "p", "r", "s", "t"], ["a", "e", "i", "o", "u"], ["", "n", "r", "st"]);
+        let mut out = Vec::new();
+        'outer: for o in onsets {
+            for v in vowels {
+                for c in codas {
+                    for v2 in vowels {
+                        out.push(format!("{}{}{}{}", o, v, c, v2));
+                        if out.len() == count {
+                            break 'outer;
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn repetitive(count: usize) -> Vec<String> {
+        ["the", "cat", "sat", "on", "the", "mat"].iter().cycle().take(count).map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn varied_text_outscores_repetitive_text() {
+        for count in [40, 300] {
+            let (varied, repetitive) = (varied(count), repetitive(count));
+            assert!(mtld(&varied) > mtld(&repetitive), "mtld at {} words", count);
+            assert!(mattr(&varied, DEFAULT_MATTR_WINDOW) > mattr(&repetitive, DEFAULT_MATTR_WINDOW), "mattr at {} words", count);
+        }
+    }
+
+    #[test]
+    fn mattr_averages_the_type_token_ratio_of_each_window() {
+        assert_eq!(mattr(&words("a b a b"), 2), 1.0);
+        assert_eq!(mattr(&words("a a a b"), 2), 2.0 / 3.0);
+        // Shorter than the window: the plain type-token ratio
+        assert_eq!(mattr(&words("a a a b"), 100), 0.5);
+        assert_eq!(mattr(&words("a a a b"), 0), mattr(&words("a a a b"), 1));
+    }
+
+    #[test]
+    fn mattr_is_stable_across_lengths_of_the_same_style() {
+        let short = mattr(&repetitive(600), DEFAULT_MATTR_WINDOW);
+        let long = mattr(&repetitive(6000), DEFAULT_MATTR_WINDOW);
+        assert!((short - long).abs() < 0.01, "{} vs {}", short, long);
+    }
+
+    #[test]
+    fn mtld_never_exceeds_the_word_count() {
+        assert_eq!(mtld(&words("every word here differs")), 4.0);
+        assert!(mtld(&repetitive(300)) < 10.0);
+        assert!(mtld(&repetitive(300)) > 0.0);
+    }
+
+    #[test]
+    fn empty_text_scores_zero() {
+        assert_eq!(mtld::<&str>(&[]), 0.0);
+        assert_eq!(mattr::<&str>(&[], DEFAULT_MATTR_WINDOW), 0.0);
+    }
+
+    #[test]
+    fn diversity_metrics_rank_varied_prose_above_repetitive_prose() {
+        let repetitive = "It was the cat. It was the cat. It was on the mat. ".repeat(6);
+        let varied = "Rain hammered the tin roof while Mara sorted brittle letters by candlelight. \
+            Somewhere below, a ferry horn moaned across the harbor, and gulls quarrelled over scraps. \
+            She found her grandmother's recipe for quince jam folded inside a tax receipt from 1962, \
+            smudged with violet ink and something sweeter.";
+        let options = |mattr_window: usize| AnalysisOptions { mattr_window, ..Default::default() };
+        // The default window is longer than either text, so mattr falls back to the plain ratio
+        for analysis in [options(100), options(10)] {
+            let processor = TextProcessor::with_patterns(&Default::default(), SuggestionConfig { analysis, ..Default::default() });
+            let low = processor.perform_analysis(&repetitive).complexity_metrics;
+            let high = processor.perform_analysis(varied).complexity_metrics;
+            assert!(high.mtld > low.mtld, "mtld {} vs {}", high.mtld, low.mtld);
+            assert!(high.mattr > low.mattr, "mattr {} vs {}", high.mattr, low.mattr);
+            assert!(high.lexical_density > low.lexical_density, "density {} vs {}", high.lexical_density, low.lexical_density);
+        }
+        let metrics = TextProcessor::new().perform_analysis(&repetitive).complexity_metrics;
+        assert_eq!(metrics.mattr, metrics.unique_word_ratio);
+        // "cat" and "mat" are the only content words
+        assert_eq!(metrics.lexical_density, 3.0 / 13.0);
+    }
+}
@@ -0,0 +1,146 @@
+//! Chapters that open or close on structurally identical lines.
+
+use crate::opaque;
+use crate::segmentation::DocumentIndex;
+use crate::similarity;
+use crate::structure::Section;
+use crate::suggestions::SCOPE_DOCUMENT;
+use crate::{OptimizationSuggestion, RelatedSpan};
+
+// Bigrams: opening lines are short, so trigrams rarely overlap even when the
+// sentence pattern is plainly repeated
+const ECHO_SHINGLE_SIZE: usize = 2;
+/// Headings quoted in messages are cut to this many characters.
+const HEADING_LABEL_CHARS: usize = 80;
+
+type Span = (usize, usize);
+
+/// First and last body sentence of a section, clipped to exclude the heading
+/// line (heading lines have no terminator, so the sentence splitter glues
+/// them onto the first sentence).
+fn edge_sentences(index: &DocumentIndex, section: &Section) -> Option<(Span, Span)> {
+    let text = index.text;
+    let body_start = if section.heading.is_some() {
+        text[section.start..section.end].find('\n').map_or(section.end, |i| section.start + i + 1)
+    } else {
+        section.start
+    };
+    let body: Vec<(usize, usize)> = index
+        .sentences
+        .iter()
+        .filter(|&&(start, end)| end > body_start && start < section.end)
+        .map(|&(start, end)| {
+            let (start, end) = (start.max(body_start), end.min(section.end));
+            let slice = &text[start..end];
+            let leading = slice.len() - slice.trim_start().len();
+            (start + leading, start + slice.trim_end().len())
+        })
+        .filter(|&(start, end)| !index.words_in(start, end).is_empty())
+        .collect();
+    Some((*body.first()?, *body.last()?))
+}
+
+fn words(index: &DocumentIndex, span: (usize, usize)) -> Vec<String> {
+    index.words_in(span.0, span.1).iter().map(|&(s, e)| index.text[s..e].to_lowercase()).collect()
+}
+
+fn label(section: &Section, position: usize) -> String {
+    match &section.heading {
+        Some(heading) => format!("\"{}\"", opaque::truncate(heading, HEADING_LABEL_CHARS)),
+        None => format!("section {}", position + 1),
+    }
+}
+
+/// Compares every pair of section openers (and closers) and reports pairs at
+/// or above `threshold` as document-scope suggestions on the later sentence,
+/// with both sentences in `related_spans`.
+pub fn chapter_echoes(index: &DocumentIndex, sections: &[Section], threshold: f64) -> Vec<OptimizationSuggestion> {
+    let edges: Vec<(usize, Span, Span)> = sections
+        .iter()
+        .enumerate()
+        .filter_map(|(i, section)| edge_sentences(index, section).map(|(first, last)| (i, first, last)))
+        .collect();
+
+    let mut suggestions = Vec::new();
+    for (rule, verb, pick_last) in [("chapter_opener_echo", "opens", false), ("chapter_closer_echo", "closes", true)] {
+        let spans: Vec<(usize, (usize, usize), Vec<String>)> = edges
+            .iter()
+            .map(|&(i, first, last)| {
+                let span = if pick_last { last } else { first };
+                (i, span, words(index, span))
+            })
+            .collect();
+        for (a, (section_a, span_a, words_a)) in spans.iter().enumerate() {
+            for (section_b, span_b, words_b) in &spans[a + 1..] {
+                let score = similarity::shingle_jaccard(words_a, words_b, ECHO_SHINGLE_SIZE);
+                if score < threshold {
+                    continue;
+                }
+                let message = format!(
+                    "{} {} much like {} (similarity {:.2}); consider varying one of them.",
+                    label(&sections[*section_b], *section_b),
+                    verb,
+                    label(&sections[*section_a], *section_a),
+                    score
+                );
+                suggestions.push(OptimizationSuggestion {
+                    scope: SCOPE_DOCUMENT.into(),
+                    related_spans: vec![
+                        RelatedSpan { start_pos: span_a.0, end_pos: span_a.1 },
+                        RelatedSpan { start_pos: span_b.0, end_pos: span_b.1 },
+                    ],
+                    score: Some(score),
+                    ..OptimizationSuggestion::instance(rule, "low", message, span_b.0, span_b.1)
+                });
+            }
+        }
+    }
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TextProcessor;
+
+    const NOVEL: &str = "# One\nThe sun rose over the quiet valley. Mara left early. She never looked back.\n\n\
+# Two\nRain fell all night on the farm. The letter came. She never looked back.\n\n\
+# Three\nThe sun rose over the empty valley. Nobody spoke.\n\n\
+# Four\nThe sun rose over the valley again. It was over.\n";
+
+    fn echoes(text: &str) -> Vec<(String, String, String)> {
+        let processor = TextProcessor::new();
+        processor
+            .build_optimization_report(text)
+            .suggestions
+            .into_iter()
+            .filter(|s| s.suggestion_type.starts_with("chapter_"))
+            .map(|s| {
+                let span = |i: usize| text[s.related_spans[i].start_pos..s.related_spans[i].end_pos].to_string();
+                (s.suggestion_type.to_string(), span(0), span(1))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn three_chapters_sharing_an_opener_produce_each_pair_once() {
+        let own = |s: &str| s.to_string();
+        let expected = vec![
+            ("chapter_opener_echo".to_string(), own("The sun rose over the quiet valley."), own("The sun rose over the empty valley.")),
+            ("chapter_opener_echo".to_string(), own("The sun rose over the quiet valley."), own("The sun rose over the valley again.")),
+            ("chapter_opener_echo".to_string(), own("The sun rose over the empty valley."), own("The sun rose over the valley again.")),
+            ("chapter_closer_echo".to_string(), own("She never looked back."), own("She never looked back.")),
+        ];
+        assert_eq!(echoes(NOVEL), expected);
+    }
+
+    #[test]
+    fn echo_suggestions_carry_score_and_point_at_the_later_sentence() {
+        let processor = TextProcessor::new();
+        let report = processor.build_optimization_report(NOVEL);
+        let closer = report.suggestions.iter().find(|s| s.suggestion_type == "chapter_closer_echo").unwrap();
+        assert_eq!(closer.score, Some(1.0));
+        assert_eq!((closer.start_pos, closer.end_pos), (closer.related_spans[1].start_pos, closer.related_spans[1].end_pos));
+        assert!(closer.message.starts_with("\"Two\" closes much like \"One\""));
+        assert!(echoes("# One\nThe sun rose.\n\n# Two\nRain fell hard.\n").is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+//! Applying accepted suggestions' replacements to the text they were
+//! computed on.
+
+use crate::OptimizationSuggestion;
+
+/// Splices the `suggested_replacement` of every suggestion listed in
+/// `accepted` (indices into `suggestions`) into `text` in one pass.
+/// Suggestions without a replacement are skipped; out-of-range indices,
+/// spans outside the text, and overlapping spans are errors, so nothing is
+/// applied unless everything can be.
+pub fn apply_suggestions(text: &str, suggestions: &[OptimizationSuggestion], accepted: &[usize]) -> Result<String, String> {
+    let mut edits: Vec<(usize, &OptimizationSuggestion, &str)> = Vec::with_capacity(accepted.len());
+    for &i in accepted {
+        let suggestion = suggestions
+            .get(i)
+            .ok_or_else(|| format!("accepted index {} is out of range ({} suggestions)", i, suggestions.len()))?;
+        let Some(replacement) = suggestion.suggested_replacement.as_deref() else {
+            continue;
+        };
+        let (start, end) = (suggestion.start_pos, suggestion.end_pos);
+        if start > end || end > text.len() || !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+            return Err(format!("suggestion {} has span {}..{}, which is not a valid range of the text", i, start, end));
+        }
+        if !edits.iter().any(|e| e.0 == i) {
+            edits.push((i, suggestion, replacement));
+        }
+    }
+
+    // Stable, so insertions at the same offset keep their accepted order
+    edits.sort_by_key(|e| (e.1.start_pos, e.1.end_pos));
+    for pair in edits.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        if b.1.start_pos < a.1.end_pos {
+            return Err(format!(
+                "suggestions {} ({}..{}) and {} ({}..{}) overlap; accept only one of them",
+                a.0, a.1.start_pos, a.1.end_pos, b.0, b.1.start_pos, b.1.end_pos
+            ));
+        }
+    }
+
+    let mut edited = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for (_, suggestion, replacement) in edits {
+        edited.push_str(&text[cursor..suggestion.start_pos]);
+        edited.push_str(replacement);
+        cursor = suggestion.end_pos;
+    }
+    edited.push_str(&text[cursor..]);
+    Ok(edited)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "She ran quickly home and said loudly that it was late";
+
+    fn replacing(needle: &str, replacement: Option<&str>) -> OptimizationSuggestion {
+        let start = TEXT.find(needle).unwrap();
+        let mut suggestion = OptimizationSuggestion::instance("adverb_usage".to_string(), "low", "m", start, start + needle.len());
+        suggestion.suggested_replacement = replacement.map(String::from);
+        suggestion
+    }
+
+    #[test]
+    fn non_adjacent_replacements_apply_in_any_accepted_order() {
+        let suggestions = [replacing("ran quickly", Some("sprinted")), replacing("said loudly", Some("shouted"))];
+        let expected = "She sprinted home and shouted that it was late";
+        assert_eq!(apply_suggestions(TEXT, &suggestions, &[0, 1]).unwrap(), expected);
+        assert_eq!(apply_suggestions(TEXT, &suggestions, &[1, 0, 1]).unwrap(), expected);
+        assert_eq!(apply_suggestions(TEXT, &suggestions, &[1]).unwrap(), "She ran quickly home and shouted that it was late");
+        assert_eq!(apply_suggestions(TEXT, &suggestions, &[]).unwrap(), TEXT);
+    }
+
+    #[test]
+    fn overlapping_replacements_are_rejected_and_nothing_is_applied() {
+        let suggestions = [replacing("ran quickly", Some("sprinted")), replacing("quickly home", Some("homeward"))];
+        let error = apply_suggestions(TEXT, &suggestions, &[1, 0]).err().unwrap();
+        assert_eq!(error, "suggestions 0 (4..15) and 1 (8..20) overlap; accept only one of them");
+        // Touching spans do not overlap
+        let touching = [replacing("ran ", Some("")), replacing("quickly", Some("fled"))];
+        assert_eq!(apply_suggestions(TEXT, &touching, &[0, 1]).unwrap(), "She fled home and said loudly that it was late");
+    }
+
+    #[test]
+    fn replacement_at_the_end_of_the_text_and_skipped_suggestions() {
+        let suggestions = [replacing("was late", Some("ran late")), replacing("loudly", None)];
+        assert_eq!(apply_suggestions(TEXT, &suggestions, &[0, 1]).unwrap(), "She ran quickly home and said loudly that it ran late");
+        let mut insertion = replacing("", Some("!"));
+        (insertion.start_pos, insertion.end_pos) = (TEXT.len(), TEXT.len());
+        assert!(apply_suggestions(TEXT, &[insertion], &[0]).unwrap().ends_with("late!"));
+    }
+
+    #[test]
+    fn bad_indices_and_spans_are_errors() {
+        let suggestions = [replacing("ran", Some("sprinted"))];
+        assert!(apply_suggestions(TEXT, &suggestions, &[3]).err().unwrap().contains("out of range"));
+        let mut past_end = replacing("late", Some("early"));
+        past_end.end_pos = TEXT.len() + 1;
+        assert!(apply_suggestions(TEXT, &[past_end], &[0]).err().unwrap().contains("not a valid range"));
+        let mut split = replacing("late", Some("x"));
+        (split.start_pos, split.end_pos) = (1, 2);
+        assert!(apply_suggestions("\u{e9}t\u{e9}", &[split], &[0]).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Character names from capitalization: runs of capitalized words that are
+//! seen mid-sentence, where a capital can't be explained by the sentence
+//! start, and that recur. A name that is the start of a longer one
+//! ("Elizabeth", "Elizabeth Bennet") is the same character.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::lexicon::Stopwords;
+use crate::segmentation::DocumentIndex;
+use crate::RelatedSpan;
+
+/// Capitalized words that open sentences or address people without being
+/// names themselves, on top of the stopwords.
+const NOT_NAMES: &[&str] = &[
+    "ah", "alas", "also", "although", "anyway", "aunt", "besides", "captain", "chapter", "colonel", "dear",
+    "doctor", "dr", "even", "everyone", "everything", "father", "finally", "god", "hello", "hey", "however",
+    "instead", "lady", "later", "lord", "madam", "maybe", "meanwhile", "miss", "mister", "mother", "mr", "mrs",
+    "ms", "nobody", "nothing", "oh", "ok", "okay", "perhaps", "please", "professor", "returning", "sir",
+    "sometimes", "soon", "still", "suddenly", "thank", "thanks", "though", "today", "tomorrow", "tonight",
+    "uncle", "unless", "well", "whatever", "yes", "yesterday", "yet",
+];
+
+/// Days, months, holidays, and place words, which are capitalized but name
+/// no character.
+const PLACES_AND_DATES: &[&str] = &[
+    "april", "august", "avenue", "christmas", "december", "east", "easter", "england", "february", "friday",
+    "hall", "january", "july", "june", "lane", "london", "march", "monday", "north", "november", "october",
+    "park", "river", "road", "saturday", "september", "south", "square", "street", "sunday", "thursday",
+    "tuesday", "wednesday", "west",
+];
+
+/// Longest run of capitalized words taken as one name.
+const MAX_NAME_WORDS: usize = 3;
+
+/// Mentions an entity needs to be reported.
+const MIN_MENTIONS: usize = 2;
+
+#[derive(Serialize)]
+pub struct Entity {
+    /// The longest form the name appears in.
+    pub name: String,
+    pub mention_count: usize,
+    pub first_pos: usize,
+    /// Every mention, possessives included, in text order.
+    pub positions: Vec<RelatedSpan>,
+}
+
+/// A run of capitalized words.
+struct Mention {
+    name: String,
+    start: usize,
+    end: usize,
+    /// At a sentence start or just inside a quotation, where any word is
+    /// capitalized.
+    at_start: bool,
+}
+
+fn is_capitalized(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars.next().is_some_and(char::is_uppercase) && chars.any(char::is_lowercase)
+}
+
+/// `word` without a possessive "'s" or "’s", and whether it had one.
+fn strip_possessive(word: &str) -> (&str, bool) {
+    match word.strip_suffix("'s").or_else(|| word.strip_suffix("\u{2019}s")) {
+        Some(stem) if !stem.is_empty() => (stem, true),
+        _ => (word, false),
+    }
+}
+
+fn is_name_word(word: &str, stopwords: &Stopwords) -> bool {
+    let lower = word.to_lowercase();
+    is_capitalized(word) && stopwords.is_content_word(word) && !NOT_NAMES.contains(&lower.as_str())
+}
+
+fn mentions(index: &DocumentIndex, stopwords: &Stopwords) -> Vec<Mention> {
+    let text = index.text;
+    let mut found = Vec::new();
+    for &(sentence_start, sentence_end) in &index.sentences {
+        let words = index.words_in(sentence_start, sentence_end);
+        let mut k = 0;
+        while k < words.len() {
+            let (start, _) = words[k];
+            let gap_before = &text[if k == 0 { sentence_start } else { words[k - 1].1 }..start];
+            let at_start = k == 0 || gap_before.contains(['"', '\u{201C}', '\u{2018}', '(', ':', '.', '!', '?']);
+            let mut parts: Vec<&str> = Vec::new();
+            let mut end = start;
+            while k < words.len() && parts.len() < MAX_NAME_WORDS {
+                let (s, e) = words[k];
+                if !parts.is_empty() && &text[end..s] != " " {
+                    break;
+                }
+                let (word, possessive) = strip_possessive(&text[s..e]);
+                if !is_name_word(word, stopwords) || PLACES_AND_DATES.contains(&word.to_lowercase().as_str()) {
+                    break;
+                }
+                parts.push(word);
+                end = s + word.len();
+                k += 1;
+                if possessive {
+                    break;
+                }
+            }
+            if parts.is_empty() {
+                k += 1;
+            } else {
+                found.push(Mention { name: parts.join(" "), start, end, at_start });
+            }
+        }
+    }
+    found
+}
+
+/// Recurring character names, most mentioned first. A name counts once it
+/// appears mid-sentence somewhere; sentence-initial mentions then count too.
+pub fn extract(index: &DocumentIndex, stopwords: &Stopwords) -> Vec<Entity> {
+    let mut by_name: HashMap<String, Vec<Mention>> = HashMap::new();
+    for mention in mentions(index, stopwords) {
+        by_name.entry(mention.name.clone()).or_default().push(mention);
+    }
+
+    // Shorter forms join the most mentioned longer name they start
+    let mut names: Vec<String> = by_name.keys().cloned().collect();
+    names.sort_by_key(|n| std::cmp::Reverse(n.split(' ').count()));
+    for name in &names {
+        let prefix = format!("{} ", name);
+        let longer = by_name
+            .iter()
+            .filter(|(other, _)| other.starts_with(&prefix))
+            .max_by(|a, b| a.1.len().cmp(&b.1.len()).then_with(|| b.0.cmp(a.0)))
+            .map(|(other, _)| other.clone());
+        if let Some(longer) = longer {
+            let merged = by_name.remove(name).unwrap_or_default();
+            by_name.entry(longer).or_default().extend(merged);
+        }
+    }
+
+    let mut entities: Vec<Entity> = by_name
+        .into_iter()
+        .filter(|(_, mentions)| mentions.len() >= MIN_MENTIONS && mentions.iter().any(|m| !m.at_start))
+        .map(|(name, mut mentions)| {
+            mentions.sort_by_key(|m| m.start);
+            Entity {
+                name,
+                mention_count: mentions.len(),
+                first_pos: mentions[0].start,
+                positions: mentions.iter().map(|m| RelatedSpan { start_pos: m.start, end_pos: m.end }).collect(),
+            }
+        })
+        .collect();
+    entities.sort_by(|a, b| b.mention_count.cmp(&a.mention_count).then(a.first_pos.cmp(&b.first_pos)));
+    entities
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::index;
+
+    use super::*;
+    use crate::config::SuggestionConfig;
+
+    fn entities(text: &str) -> Vec<Entity> {
+        let index = index(text);
+        extract(&index, &Stopwords::from_config(&SuggestionConfig::default()))
+    }
+
+    const EXCERPT: &str = "Elizabeth Bennet walked to Netherfield on Monday. When she arrived, Jane was still ill. \
+        Mr. Darcy watched Elizabeth from the window. Elizabeth's boots were muddy, and Mrs. Hurst laughed. \
+        However, Jane smiled at her sister. Darcy said nothing. Later, Elizabeth sat with Jane's maid. \
+        Suddenly Darcy spoke to Elizabeth Bennet. In London, nobody would have noticed.";
+
+    #[test]
+    fn the_main_characters_of_an_excerpt_are_found() {
+        let found = entities(EXCERPT);
+        let top: Vec<(&str, usize)> = found.iter().take(3).map(|e| (e.name.as_str(), e.mention_count)).collect();
+        // Elizabeth: two full names, a possessive, and two short forms
+        assert_eq!(top, [("Elizabeth Bennet", 5), ("Jane", 3), ("Darcy", 3)]);
+        let mentions: Vec<&str> = found[0].positions.iter().map(|p| &EXCERPT[p.start_pos..p.end_pos]).collect();
+        assert_eq!(mentions, ["Elizabeth Bennet", "Elizabeth", "Elizabeth", "Elizabeth", "Elizabeth Bennet"]);
+        assert_eq!(found[0].first_pos, 0);
+        // Titles, sentence openers, days, places, and names seen once are left out
+        for name in ["Mr", "Mrs", "However", "Later", "Suddenly", "Monday", "London", "Netherfield", "Hurst", "When", "In"] {
+            assert!(found.iter().all(|e| e.name != name), "{} found", name);
+        }
+    }
+
+    #[test]
+    fn names_only_ever_seen_opening_sentences_are_skipped() {
+        assert!(entities("Storms came. Storms went. Storms stayed.").is_empty());
+        let found = entities("Mara ran. Mara hid. They found Mara.");
+        assert_eq!((found[0].name.as_str(), found[0].mention_count), ("Mara", 3));
+        // Inside a quotation, a capital is no evidence either
+        assert!(entities("He said, \"Wait.\" She said, \"Wait!\"").is_empty());
+    }
+
+    #[test]
+    fn curly_possessives_and_ties() {
+        let found = entities("They saw Tom\u{2019}s car. Then Ann called Tom. Later Ann left.");
+        let names: Vec<(&str, usize)> = found.iter().map(|e| (e.name.as_str(), e.mention_count)).collect();
+        // Equal counts keep text order
+        assert_eq!(names, [("Tom", 2), ("Ann", 2)]);
+        assert_eq!(found[0].positions[0].end_pos - found[0].positions[0].start_pos, "Tom".len());
+    }
+}
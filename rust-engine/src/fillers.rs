@@ -0,0 +1,128 @@
+//! Filler words and wordy stock phrases, with a trimmed replacement where
+//! one is safe.
+
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::config::SuggestionConfig;
+use crate::rewrite;
+use crate::OptimizationSuggestion;
+
+/// Built-in fillers and their replacements: `""` drops the word, `None`
+/// means the phrase needs rewording by hand.
+const BUILTIN_FILLERS: &[(&str, Option<&str>)] = &[
+    ("very", Some("")),
+    ("really", Some("")),
+    ("just", Some("")),
+    ("quite", Some("")),
+    ("somewhat", Some("")),
+    ("actually", Some("")),
+    ("basically", Some("")),
+    ("in order to", Some("to")),
+    ("due to the fact that", Some("because")),
+    ("the fact that", None),
+];
+
+/// Lowercase with internal whitespace collapsed, so "In  order\nto" looks
+/// up as "in order to".
+fn normalize(phrase: &str) -> String {
+    phrase.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// The built-in fillers plus the configured extras, compiled into one
+/// whole-word, case-insensitive pattern.
+pub struct FillerWords {
+    pattern: Option<Regex>,
+    replacements: HashMap<String, Option<String>>,
+}
+
+impl FillerWords {
+    pub fn from_config(config: &SuggestionConfig) -> FillerWords {
+        let mut replacements: HashMap<String, Option<String>> =
+            BUILTIN_FILLERS.iter().map(|&(phrase, replacement)| (phrase.to_string(), replacement.map(str::to_string))).collect();
+        for word in &config.extra_filler_words {
+            let word = normalize(word);
+            if !word.is_empty() {
+                replacements.entry(word).or_insert(Some(String::new()));
+            }
+        }
+
+        // Longest phrases first: the leftmost alternative wins at a given
+        // position, so "in order to" is reported once rather than as "to"
+        let mut phrases: Vec<&String> = replacements.keys().collect();
+        phrases.sort_by(|a, b| b.split(' ').count().cmp(&a.split(' ').count()).then(b.len().cmp(&a.len())).then(a.cmp(b)));
+        let alternatives: Vec<String> =
+            phrases.iter().map(|p| p.split(' ').map(regex::escape).collect::<Vec<_>>().join(r"\s+")).collect();
+        let pattern = Regex::new(&format!(r"(?i)\b(?:{})\b", alternatives.join("|"))).ok();
+        FillerWords { pattern, replacements }
+    }
+
+    /// Spans of the fillers in `text`.
+    pub fn find(&self, text: &str) -> Vec<(usize, usize)> {
+        self.pattern.as_ref().map_or_else(Vec::new, |pattern| pattern.find_iter(text).map(|m| (m.start(), m.end())).collect())
+    }
+
+    /// One `filler_word` suggestion per match in `text`.
+    pub fn suggestions(&self, text: &str) -> Vec<OptimizationSuggestion> {
+        let Some(pattern) = &self.pattern else {
+            return Vec::new();
+        };
+        pattern
+            .find_iter(text)
+            .map(|m| {
+                let replacement = self.replacements.get(&normalize(m.as_str())).cloned().flatten().map(|r| rewrite::match_case(m.as_str(), &r));
+                let message = match replacement.as_deref() {
+                    Some("") => format!("\"{}\" rarely adds meaning; consider cutting it.", m.as_str()),
+                    Some(shorter) => format!("\"{}\" can usually be shortened to \"{}\".", m.as_str(), shorter),
+                    None => format!("\"{}\" is wordy; consider rephrasing.", m.as_str()),
+                };
+                OptimizationSuggestion {
+                    suggested_replacement: replacement,
+                    ..OptimizationSuggestion::instance("filler_word", "low", message, m.start(), m.end())
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(fillers: &FillerWords, text: &str) -> Vec<(String, Option<String>)> {
+        fillers
+            .suggestions(text)
+            .into_iter()
+            .map(|s| (text[s.start_pos..s.end_pos].to_string(), s.suggested_replacement))
+            .collect()
+    }
+
+    fn own(matched: &str, replacement: Option<&str>) -> (String, Option<String>) {
+        (matched.to_string(), replacement.map(str::to_string))
+    }
+
+    #[test]
+    fn phrases_win_over_the_words_inside_them() {
+        let fillers = FillerWords::from_config(&SuggestionConfig::default());
+        let text = "Due to the fact that it rained, we left. The fact that he stayed was Very odd.";
+        assert_eq!(found(&fillers, text), [own("Due to the fact that", Some("Because")), own("The fact that", None), own("Very", Some(""))]);
+        assert_eq!(found(&fillers, "We ran In  order\nto win."), [own("In  order\nto", Some("To"))]);
+    }
+
+    #[test]
+    fn matches_are_whole_words_only() {
+        let fillers = FillerWords::from_config(&SuggestionConfig::default());
+        assert!(found(&fillers, "Adjust the quiter everyday reallyness.").is_empty());
+        assert_eq!(found(&fillers, "It was JUST fine."), [own("JUST", Some(""))]);
+    }
+
+    #[test]
+    fn configured_words_extend_the_builtin_list() {
+        let config = SuggestionConfig { extra_filler_words: vec!["  Suddenly ".to_string(), "all of a sudden".to_string(), " ".to_string()], ..Default::default() };
+        let fillers = FillerWords::from_config(&config);
+        let text = "Suddenly the door opened, and all of a sudden it was really quiet.";
+        assert_eq!(found(&fillers, text), [own("Suddenly", Some("")), own("all of a sudden", Some("")), own("really", Some(""))]);
+        let messages: Vec<String> = fillers.suggestions("Suddenly.").into_iter().map(|s| s.message.to_string()).collect();
+        assert_eq!(messages, ["\"Suddenly\" rarely adds meaning; consider cutting it."]);
+    }
+}
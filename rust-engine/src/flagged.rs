@@ -0,0 +1,267 @@
+//! Whole-word matching of flagged terms (watchlists, screening lists) with an
+//! opt-in fuzzy mode that sees through common obfuscations.
+//!
+//! Fuzzy normalization is applied only to the comparison keys: spans are
+//! always reported against the raw text, and matching is always whole-token,
+//! so a term never matches inside an innocent word ("Scunthorpe", "basement").
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FlaggedMatch {
+    pub term: String,
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub matched_text: String,
+    /// True when the raw text differs from the term and only the fuzzy
+    /// normalization made it match.
+    pub obfuscated: bool,
+}
+
+// Characters that may separate single letters in a spelled-out word (f.u.c.k)
+const LETTER_SEPARATORS: &[char] = &['.', '-', '_', '*', ' ', '\u{2022}'];
+
+/// A term, its words as exact keys, and the keys each word matches.
+type CompiledTerm<'a> = (&'a String, Vec<String>, Vec<Vec<String>>);
+
+struct Token {
+    start: usize,
+    end: usize,
+    // Separated single letters merged into one token
+    merged: bool,
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '@' || c == '$' || c == '\'' || c == '\u{2019}'
+}
+
+fn raw_tokens(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (is_token_char(c), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                tokens.push(Token { start: s, end: i, merged: false });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(Token { start: s, end: text.len(), merged: false });
+    }
+    tokens
+        .into_iter()
+        .map(|t| {
+            // Leading/trailing apostrophes are quote marks, not part of the word
+            let slice = &text[t.start..t.end];
+            let trimmed = slice.trim_matches(['\'', '\u{2019}']);
+            let offset = slice.len() - slice.trim_start_matches(['\'', '\u{2019}']).len();
+            Token { start: t.start + offset, end: t.start + offset + trimmed.len(), merged: false }
+        })
+        .filter(|t| t.end > t.start)
+        .collect()
+}
+
+/// Merges runs of three or more single-character tokens separated by a
+/// single separator character ("f.u.c.k", "s h i t") into one token.
+fn merge_spelled_out(text: &str, tokens: Vec<Token>) -> Vec<Token> {
+    let single = |t: &Token| text[t.start..t.end].chars().count() == 1;
+    let mut merged = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        // The whole run must use one separator, so "f.u.c.k s.h.i.t" stays two words
+        let mut j = i;
+        let mut separator = None;
+        while j + 1 < tokens.len() && single(&tokens[j]) && single(&tokens[j + 1]) {
+            let gap = &text[tokens[j].end..tokens[j + 1].start];
+            let mut chars = gap.chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else { break };
+            if !LETTER_SEPARATORS.contains(&c) || separator.is_some_and(|s| s != c) {
+                break;
+            }
+            separator = Some(c);
+            j += 1;
+        }
+        if j - i >= 2 {
+            merged.push(Token { start: tokens[i].start, end: tokens[j].end, merged: true });
+            i = j + 1;
+        } else {
+            merged.push(Token { start: tokens[i].start, end: tokens[i].end, merged: false });
+            i += 1;
+        }
+    }
+    merged
+}
+
+fn deleet(c: char) -> char {
+    match c {
+        '0' => 'o',
+        '1' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        '8' => 'b',
+        '9' => 'g',
+        other => other,
+    }
+}
+
+/// Collapses runs of three or more identical characters down to `keep`
+/// characters ("sooooo" → "so" or "soo"). Doubled letters are left alone so
+/// "good" never collapses into "god".
+fn collapse_runs(s: &str, keep: usize) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = String::with_capacity(s.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let mut j = i;
+        while j < chars.len() && chars[j] == chars[i] {
+            j += 1;
+        }
+        let run = j - i;
+        let emit = if run >= 3 { keep } else { run };
+        out.extend(std::iter::repeat_n(chars[i], emit));
+        i = j;
+    }
+    out
+}
+
+/// Comparison keys for a token in fuzzy mode. Purely numeric tokens are
+/// never de-leeted so "100" stays a number.
+fn fuzzy_keys(raw: &str) -> Vec<String> {
+    let lowered: String = raw
+        .chars()
+        .filter(|c| !LETTER_SEPARATORS.contains(c) && *c != '\'' && *c != '\u{2019}')
+        .flat_map(char::to_lowercase)
+        .collect();
+    let base = if lowered.chars().any(|c| c.is_alphabetic()) { lowered.chars().map(deleet).collect() } else { lowered };
+    let mut keys = vec![collapse_runs(&base, 1), collapse_runs(&base, 2)];
+    keys.dedup();
+    keys
+}
+
+fn exact_key(raw: &str) -> String {
+    raw.replace('\u{2019}', "'").to_lowercase()
+}
+
+/// Finds whole-token occurrences of `terms` (single words or phrases).
+/// Results are sorted by position; a longer phrase wins over a shorter term
+/// starting at the same token.
+pub fn find_flagged_terms(text: &str, terms: &[String], fuzzy: bool) -> Vec<FlaggedMatch> {
+    let tokens = if fuzzy { merge_spelled_out(text, raw_tokens(text)) } else { raw_tokens(text) };
+    let token_keys: Vec<Vec<String>> = tokens
+        .iter()
+        .map(|t| {
+            let raw = &text[t.start..t.end];
+            if fuzzy { fuzzy_keys(raw) } else { vec![exact_key(raw)] }
+        })
+        .collect();
+
+    // Each term becomes a sequence of per-word key sets, normalized the same
+    // way as the tokens
+    let mut compiled: Vec<CompiledTerm> = terms
+        .iter()
+        .map(|term| {
+            let exact: Vec<String> = term.split_whitespace().map(exact_key).collect();
+            let keys = exact.iter().map(|w| if fuzzy { fuzzy_keys(w) } else { vec![w.clone()] }).collect();
+            (term, exact, keys)
+        })
+        .filter(|(_, exact, _)| !exact.is_empty())
+        .collect();
+    compiled.sort_by_key(|term| std::cmp::Reverse(term.1.len()));
+
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let hit = compiled.iter().find(|(_, _, keys)| {
+            i + keys.len() <= tokens.len()
+                && keys
+                    .iter()
+                    .enumerate()
+                    .all(|(k, word_keys)| token_keys[i + k].iter().any(|key| word_keys.contains(key)))
+        });
+        match hit {
+            Some((term, exact, _)) => {
+                let span = &tokens[i..i + exact.len()];
+                let start_pos = span[0].start;
+                let end_pos = span[span.len() - 1].end;
+                let obfuscated = span
+                    .iter()
+                    .zip(exact)
+                    .any(|(t, word)| t.merged || exact_key(&text[t.start..t.end]) != *word);
+                matches.push(FlaggedMatch {
+                    term: (*term).clone(),
+                    start_pos,
+                    end_pos,
+                    matched_text: text[start_pos..end_pos].to_string(),
+                    obfuscated,
+                });
+                i += exact.len();
+            }
+            None => i += 1,
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(list: &[&str]) -> Vec<String> {
+        list.iter().map(|t| t.to_string()).collect()
+    }
+
+    fn matched(text: &str, list: &[&str], fuzzy: bool) -> Vec<(String, bool)> {
+        find_flagged_terms(text, &terms(list), fuzzy).into_iter().map(|m| (m.matched_text, m.obfuscated)).collect()
+    }
+
+    #[test]
+    fn obfuscated_spellings_match_only_in_fuzzy_mode() {
+        let text = "Well h.e.l.l, that was d a m n sooooo bad. Total cr4p, and h3lllll no.";
+        let list = ["hell", "damn", "so", "crap"];
+        assert!(matched(text, &list, false).is_empty());
+        assert_eq!(
+            matched(text, &list, true),
+            [
+                ("h.e.l.l".to_string(), true),
+                ("d a m n".to_string(), true),
+                ("sooooo".to_string(), true),
+                ("cr4p".to_string(), true),
+                ("h3lllll".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn spans_refer_to_the_raw_text() {
+        let text = "Oh — “h.e.l.l” indeed. HELL.";
+        let hits = find_flagged_terms(text, &terms(&["hell"]), true);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(&text[hits[0].start_pos..hits[0].end_pos], "h.e.l.l");
+        assert_eq!(&text[hits[1].start_pos..hits[1].end_pos], "HELL");
+        assert!(!hits[1].obfuscated);
+    }
+
+    #[test]
+    fn terms_never_match_inside_innocent_words() {
+        let text = "Scunthorpe and Penistone: the basement class would assess the bass, shell the peas, and say hello. Good, good god.";
+        for fuzzy in [false, true] {
+            assert!(matched(text, &["cunt", "penis", "ass", "hell"], fuzzy).is_empty(), "fuzzy: {}", fuzzy);
+        }
+        // Doubled letters never collapse, and numbers are never de-leeted
+        assert_eq!(matched(text, &["god"], true), [("god".to_string(), false)]);
+        assert!(matched("Chapter 101 opens here.", &["ioi"], true).is_empty());
+    }
+
+    #[test]
+    fn phrases_match_whole_tokens_and_the_longest_term_wins() {
+        let text = "The dark web and the dark webs.";
+        let hits = find_flagged_terms(text, &terms(&["dark", "dark web"]), false);
+        let found: Vec<(&str, &str)> = hits.iter().map(|m| (m.term.as_str(), m.matched_text.as_str())).collect();
+        assert_eq!(found, [("dark web", "dark web"), ("dark", "dark")]);
+    }
+}
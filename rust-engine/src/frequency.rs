@@ -0,0 +1,83 @@
+//! Word frequency tables for "most used words" panels.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::lexicon::Stopwords;
+
+#[derive(Serialize, Deserialize)]
+pub struct WordFrequency {
+    /// Lowercased.
+    pub word: String,
+    pub count: usize,
+    /// Share of all words in the text, stopwords included, 0–1.
+    pub ratio: f64,
+}
+
+/// Lowercased words by descending count, ties alphabetical. With
+/// `stopwords`, only content words are listed. `top_n` of 0 lists every word.
+pub fn word_frequencies(words: &[&str], stopwords: Option<&Stopwords>, top_n: usize) -> Vec<WordFrequency> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for word in words {
+        if stopwords.is_none_or(|s| s.is_content_word(word)) {
+            *counts.entry(word.to_lowercase()).or_insert(0) += 1;
+        }
+    }
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    if top_n > 0 {
+        ranked.truncate(top_n);
+    }
+    ranked
+        .into_iter()
+        .map(|(word, count)| WordFrequency { word, count, ratio: count as f64 / words.len() as f64 })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SuggestionConfig;
+    use crate::TextProcessor;
+
+    fn ranked(words: &[&str], exclude_stop_words: bool, top_n: usize) -> Vec<(String, usize)> {
+        let stopwords = Stopwords::from_config(&SuggestionConfig::default());
+        word_frequencies(words, exclude_stop_words.then_some(&stopwords), top_n)
+            .into_iter()
+            .map(|entry| (entry.word, entry.count))
+            .collect()
+    }
+
+    #[test]
+    fn ties_break_alphabetically_and_zero_lists_everything() {
+        let words = ["pear", "Apple", "pear", "fig", "apple", "fig"];
+        let expected = [("apple", 2), ("fig", 2), ("pear", 2)].map(|(w, c)| (w.to_string(), c));
+        assert_eq!(ranked(&words, false, 0), expected);
+        assert_eq!(ranked(&words, false, 2), expected[..2]);
+        assert_eq!(ranked(&words, false, 10), expected);
+    }
+
+    #[test]
+    fn ratios_are_shares_of_every_word() {
+        let stopwords = Stopwords::from_config(&SuggestionConfig::default());
+        let frequencies = word_frequencies(&["the", "dragon", "the", "sky"], Some(&stopwords), 0);
+        let ratios: Vec<(&str, f64)> = frequencies.iter().map(|f| (f.word.as_str(), f.ratio)).collect();
+        assert_eq!(ratios, [("dragon", 0.25), ("sky", 0.25)]);
+        assert!(word_frequencies(&[], None, 0).is_empty());
+    }
+
+    #[test]
+    fn word_frequencies_rank_content_words_and_match_word_count() {
+        let processor = TextProcessor::new();
+        let text = "The dragon woke. The dragon flew over the hills, and the dragon burned the barn. \
+                    The village saw the dragon, the dragon saw the village, and the dragon slept. \
+                    In the morning the dragon was gone.";
+        let index = processor.index(text);
+        let words = index.word_strs();
+        let content = word_frequencies(&words, Some(&processor.stopwords), 1);
+        assert_eq!((content[0].word.as_str(), content[0].count), ("dragon", 7));
+        let all = word_frequencies(&words, None, 0);
+        assert_eq!(all[0].word, "the");
+        assert_eq!(all.iter().map(|f| f.count).sum::<usize>(), processor.perform_analysis(text).word_count);
+    }
+}
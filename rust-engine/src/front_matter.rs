@@ -0,0 +1,143 @@
+//! Metadata blocks opening a manuscript: `key: value` lines between two
+//! "---" fences (YAML style) or `key = value` lines between two "+++"
+//! fences (TOML style). A block without its closing fence is body text.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Delimiters of a YAML ("---") or TOML ("+++") front matter block.
+const FENCES: &[&str] = &["---", "+++"];
+
+/// Key giving the manuscript's target length in words.
+const TARGET_WORDS_KEY: &str = "target_words";
+
+#[derive(Serialize, Deserialize)]
+pub struct FrontMatter {
+    /// Values by key, with surrounding quotes removed. Comments, list
+    /// items, indented lines, and TOML table headers are skipped; a
+    /// repeated key keeps its last value.
+    pub fields: BTreeMap<String, String>,
+    /// Byte span of the block from the opening fence through the line
+    /// break after the closing one.
+    pub start_pos: usize,
+    pub end_pos: usize,
+}
+
+impl FrontMatter {
+    /// `target_words` as a positive number; "80,000" and "80_000" are read
+    /// as 80000.
+    pub fn target_words(&self) -> Option<f64> {
+        let value = self.fields.get(TARGET_WORDS_KEY)?.replace([',', '_'], "");
+        value.parse::<f64>().ok().filter(|target| target.is_finite() && *target > 0.0)
+    }
+}
+
+fn opening_fence(text: &str) -> Option<&'static str> {
+    let first_line = text.lines().next()?;
+    FENCES.iter().copied().find(|f| first_line.trim_end() == *f)
+}
+
+/// End of a front matter block opening the text, if there is one.
+pub fn end(text: &str) -> Option<usize> {
+    let fence = opening_fence(text)?;
+    let mut offset = text.find('\n')? + 1;
+    for line in text[offset..].split_inclusive('\n') {
+        offset += line.len();
+        if line.trim_end() == fence {
+            return Some(offset);
+        }
+    }
+    None
+}
+
+/// Whether more text could still change what front matter opens `text`:
+/// its first line is unfinished and could become a fence, or is a fence
+/// whose closing one hasn't arrived or has no line break yet.
+pub fn may_open(text: &str) -> bool {
+    match text.find('\n') {
+        None => FENCES.iter().any(|f| f.starts_with(text.trim_end())),
+        Some(_) => opening_fence(text).is_some() && end(text).is_none_or(|end| !text[..end].ends_with('\n')),
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    ['"', '\''].iter().find_map(|&q| value.strip_prefix(q).and_then(|v| v.strip_suffix(q))).unwrap_or(value)
+}
+
+/// The front matter block opening `text`, if it has one.
+pub fn parse(text: &str) -> Option<FrontMatter> {
+    let end_pos = end(text)?;
+    let fence = opening_fence(text)?;
+    let separator = if fence == "+++" { '=' } else { ':' };
+    let mut fields = BTreeMap::new();
+    let body = &text[text.find('\n')? + 1..end_pos];
+    for line in body.lines().filter(|line| line.trim_end() != fence) {
+        if line.starts_with(char::is_whitespace) || line.starts_with(['#', '-', '[']) {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(separator) {
+            let key = unquote(key.trim());
+            if !key.is_empty() {
+                fields.insert(key.to_string(), unquote(value.trim()).to_string());
+            }
+        }
+    }
+    Some(FrontMatter { fields, start_pos: 0, end_pos })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TextProcessor;
+    use crate::config::SuggestionConfig;
+    use crate::suggestions::SCOPE_DOCUMENT;
+
+    const FRONT_MATTER: &str = "---\ntitle: \"The Long Road\"\nauthor: A. Writer\ngenre: literary fiction\ntarget_words: 20\n---\n";
+
+    #[test]
+    fn front_matter_is_parsed_and_left_out_of_analysis() {
+        let body = "The letter was written by the clerk. She read it twice.\n";
+        let text = format!("{}{}", FRONT_MATTER, body);
+        let processor = TextProcessor::new();
+        let front_matter = processor.parse_front_matter(&text).expect("front matter parsed");
+        assert_eq!((front_matter.start_pos, front_matter.end_pos), (0, FRONT_MATTER.len()));
+        assert_eq!(front_matter.fields["title"], "The Long Road");
+        assert_eq!(front_matter.fields["genre"], "literary fiction");
+
+        let analysis = processor.analyze_text(&text);
+        assert_eq!((analysis.word_count, analysis.sentence_count), (11, 2));
+        assert_eq!(analysis.progress_to_target, Some(0.55));
+        let suggestions = processor.optimize_text(&text);
+        let passive = suggestions.iter().find(|s| s.suggestion_type == "passive_voice").expect("passive voice flagged");
+        assert!(text[passive.start_pos..passive.end_pos].contains("was written"));
+        assert!(suggestions.iter().all(|s| s.scope == SCOPE_DOCUMENT || s.start_pos >= FRONT_MATTER.len()));
+    }
+
+    #[test]
+    fn text_without_front_matter_is_analyzed_whole() {
+        let text = "The letter was written by the clerk.\n---\ntitle: not front matter\n---\nShe read it.";
+        let processor = TextProcessor::new();
+        assert!(processor.parse_front_matter(text).is_none());
+        let analysis = processor.analyze_text(text);
+        assert_eq!(analysis.word_count, 14);
+        assert_eq!(analysis.progress_to_target, None);
+
+        // With skipping off the block is prose, but its target still counts
+        let text = format!("{}She read it.", FRONT_MATTER);
+        let processor = TextProcessor::with_config(SuggestionConfig { skip_front_matter: false, ..SuggestionConfig::default() });
+        let analysis = processor.analyze_text(&text);
+        assert_eq!(analysis.word_count, 15);
+        assert_eq!(analysis.progress_to_target, Some(0.75));
+    }
+
+    #[test]
+    fn unterminated_front_matter_is_body_text() {
+        let text = "---\ntitle: Draft\ntarget_words: 10\nThe letter was written by the clerk.";
+        let processor = TextProcessor::new();
+        assert!(processor.parse_front_matter(text).is_none());
+        let analysis = processor.analyze_text(text);
+        assert_eq!(analysis.word_count, 11);
+        assert_eq!(analysis.progress_to_target, None);
+        let unskipped = TextProcessor::with_config(SuggestionConfig { skip_front_matter: false, ..SuggestionConfig::default() });
+        assert_eq!(serde_json::to_value(unskipped.analyze_text(text)).unwrap(), serde_json::to_value(analysis).unwrap());
+    }
+}
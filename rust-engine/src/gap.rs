@@ -0,0 +1,251 @@
+//! Readability targets: how far a document's Flesch-Kincaid grade is from
+//! an audience's grade band, and the concrete changes (longest sentences to
+//! split, most frequent complex words to replace) that would close the gap.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::segmentation::DocumentIndex;
+use crate::OptimizationSuggestion;
+
+/// Named audiences and their grade bands.
+const TARGETS: &[(&str, f64, f64)] = &[
+    ("elementary", 1.0, 5.0),
+    ("middle school", 6.0, 8.0),
+    ("general adult", 7.0, 9.0),
+    ("high school", 9.0, 12.0),
+    ("college", 13.0, 16.0),
+    ("academic", 13.0, 18.0),
+];
+
+const MAX_NUMBERED_GRADE: u32 = 12;
+
+/// The named audiences; school grades ("8th grade") are accepted too.
+pub fn target_names() -> Vec<&'static str> {
+    TARGETS.iter().map(|(name, _, _)| *name).collect()
+}
+
+/// Words of this many syllables or more are complex.
+const COMPLEX_SYLLABLES: usize = 3;
+
+/// Syllables assumed for the plainer word replacing a complex one.
+const REPLACEMENT_SYLLABLES: usize = 2;
+
+/// Most complex words listed.
+const MAX_COMPLEX_WORDS: usize = 25;
+
+#[derive(Serialize, Deserialize)]
+pub struct ComplexWord {
+    /// Lowercased.
+    pub word: String,
+    pub syllables: usize,
+    pub count: usize,
+    /// Every use, as `complex_word` suggestions.
+    pub occurrences: Vec<OptimizationSuggestion>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReadabilityGap {
+    pub target: String,
+    pub target_grade_min: f64,
+    pub target_grade_max: f64,
+    /// Flesch-Kincaid grade of the document.
+    pub current_grade: f64,
+    /// The document is at or below the top of the target band; the change
+    /// lists are then empty.
+    pub meets_target: bool,
+    /// Longest sentences first, as `sentence_length` suggestions; splitting
+    /// each in two is assumed.
+    pub sentences_to_split: Vec<OptimizationSuggestion>,
+    /// Uses of the listed complex words, all of which need replacing once
+    /// the sentences are split.
+    pub complex_words_over_budget: usize,
+    /// Most frequent first.
+    pub complex_words: Vec<ComplexWord>,
+    /// Grade after the listed changes, each complex word replaced by one of
+    /// two syllables.
+    pub estimated_grade: f64,
+}
+
+/// The grade band of `target`: a named audience or a school grade ("8th
+/// grade", "grade 8").
+fn grade_band(target: &str) -> Result<(f64, f64), String> {
+    let normalized = target.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    if let Some(&(_, min, max)) = TARGETS.iter().find(|(name, _, _)| *name == normalized) {
+        return Ok((min, max));
+    }
+    let number = normalized
+        .strip_suffix(" grade")
+        .map(|n| n.trim_end_matches(|c: char| c.is_alphabetic()))
+        .or_else(|| normalized.strip_prefix("grade "))
+        .and_then(|n| n.parse::<u32>().ok())
+        .filter(|n| (1..=MAX_NUMBERED_GRADE).contains(n));
+    match number {
+        Some(grade) => Ok(((grade - 1) as f64, grade as f64)),
+        None => {
+            Err(format!(
+                "Unknown readability target \"{}\"; use a school grade (\"8th grade\") or one of {}",
+                target,
+                target_names().join(", ")
+            ))
+        }
+    }
+}
+
+fn flesch_kincaid(words: usize, sentences: usize, syllables: usize) -> f64 {
+    if words == 0 || sentences == 0 {
+        return 0.0;
+    }
+    (0.39 * words as f64 / sentences as f64 + 11.8 * syllables as f64 / words as f64 - 15.59).max(0.0)
+}
+
+/// A complex word's uses, and whether any of them is lowercase or opens a
+/// sentence (one capitalized everywhere else is a name).
+struct Uses {
+    syllables: usize,
+    spans: Vec<(usize, usize)>,
+    common: bool,
+}
+
+/// The gap between the text of `index` and `target`. Words `measured`
+/// rejects (pasted data) are left out of every count; splitting is only
+/// proposed for sentences over `long_sentence_threshold` words.
+pub fn readability_gap(
+    index: &DocumentIndex,
+    target: &str,
+    syllables: impl Fn(&str) -> usize,
+    measured: impl Fn(&str) -> bool,
+    long_sentence_threshold: usize,
+) -> Result<ReadabilityGap, String> {
+    let (target_grade_min, target_grade_max) = grade_band(target)?;
+    let text = index.text;
+
+    let (mut words, mut sentence_count, mut syllable_count) = (0, 0, 0);
+    let mut long_sentences = Vec::new();
+    let mut uses: HashMap<String, Uses> = HashMap::new();
+    for &(start, end) in &index.sentences {
+        let sentence_words: Vec<(usize, usize)> =
+            index.words_in(start, end).iter().copied().filter(|&(s, e)| measured(&text[s..e])).collect();
+        if sentence_words.is_empty() {
+            continue;
+        }
+        sentence_count += 1;
+        words += sentence_words.len();
+        if sentence_words.len() > long_sentence_threshold {
+            long_sentences.push((start, end, sentence_words.len()));
+        }
+        for (k, &(s, e)) in sentence_words.iter().enumerate() {
+            let word = &text[s..e];
+            let count = syllables(word);
+            syllable_count += count;
+            if count >= COMPLEX_SYLLABLES {
+                let entry = uses.entry(word.to_lowercase()).or_insert(Uses { syllables: count, spans: Vec::new(), common: false });
+                entry.spans.push((s, e));
+                entry.common |= k == 0 || !word.starts_with(char::is_uppercase);
+            }
+        }
+    }
+
+    let current_grade = flesch_kincaid(words, sentence_count, syllable_count);
+    let mut gap = ReadabilityGap {
+        target: target.to_string(),
+        target_grade_min,
+        target_grade_max,
+        current_grade,
+        meets_target: current_grade <= target_grade_max,
+        sentences_to_split: Vec::new(),
+        complex_words_over_budget: 0,
+        complex_words: Vec::new(),
+        estimated_grade: current_grade,
+    };
+    if gap.meets_target {
+        return Ok(gap);
+    }
+
+    let above_target = |sentences: usize, syllables: usize| flesch_kincaid(words, sentences, syllables) > target_grade_max;
+    long_sentences.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+    for (start, end, length) in long_sentences {
+        if !above_target(sentence_count, syllable_count) {
+            break;
+        }
+        sentence_count += 1;
+        gap.sentences_to_split.push(OptimizationSuggestion::instance(
+            "sentence_length",
+            "medium",
+            format!("This {}-word sentence is one of the longest; splitting it brings the text closer to the target grade.", length),
+            start,
+            end,
+        ));
+    }
+
+    let mut complex: Vec<(String, Uses)> = uses.into_iter().filter(|(_, u)| u.common).collect();
+    complex.sort_by(|a, b| b.1.spans.len().cmp(&a.1.spans.len()).then(b.1.syllables.cmp(&a.1.syllables)).then(a.0.cmp(&b.0)));
+    for (word, uses) in complex.into_iter().take(MAX_COMPLEX_WORDS) {
+        if !above_target(sentence_count, syllable_count) {
+            break;
+        }
+        syllable_count -= (uses.syllables - REPLACEMENT_SYLLABLES) * uses.spans.len();
+        gap.complex_words_over_budget += uses.spans.len();
+        let occurrences = uses
+            .spans
+            .iter()
+            .map(|&(start, end)| {
+                OptimizationSuggestion::instance(
+                    "complex_word",
+                    "low",
+                    format!("\"{}\" has {} syllables; a plainer word brings the text closer to the target grade.", &text[start..end], uses.syllables),
+                    start,
+                    end,
+                )
+            })
+            .collect();
+        gap.complex_words.push(ComplexWord { word, syllables: uses.syllables, count: uses.spans.len(), occurrences });
+    }
+
+    gap.estimated_grade = flesch_kincaid(words, sentence_count, syllable_count);
+    Ok(gap)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TextProcessor;
+    use crate::config::AnalysisOptions;
+
+    const DENSE_PASSAGE: &str = "The organizational implementation of interdisciplinary methodologies necessitates considerable \
+        institutional investment, particularly when administrative infrastructure is insufficiently developed and \
+        departmental priorities are fundamentally incompatible with collaborative experimentation across university \
+        boundaries. Consequently, organizational leadership frequently underestimates the complexity of implementation. \
+        Methodologies evolve. Institutional memory, meanwhile, deteriorates considerably whenever organizational \
+        restructuring eliminates the individuals responsible for maintaining continuity between successive generations \
+        of researchers and administrators.";
+
+    #[test]
+    fn readability_gap_lists_longest_sentences_and_frequent_complex_words() {
+        let processor = TextProcessor::with_options(AnalysisOptions { long_sentence_threshold: 20, ..AnalysisOptions::default() });
+        let gap = processor.readability_gap(DENSE_PASSAGE, "8th grade").unwrap();
+        assert!(!gap.meets_target);
+        assert_eq!((gap.target_grade_min, gap.target_grade_max), (7.0, 8.0));
+        assert!(gap.current_grade > 15.0);
+        let lengths: Vec<usize> = gap.sentences_to_split.iter().map(|s| DENSE_PASSAGE[s.start_pos..s.end_pos].split_whitespace().count()).collect();
+        assert_eq!(lengths, [29, 22]);
+        assert_eq!(&DENSE_PASSAGE[gap.sentences_to_split[0].start_pos..][..3], "The");
+        assert_eq!(gap.complex_words[0].word, "organizational");
+        assert_eq!(gap.complex_words[0].count, 3);
+        let first = &gap.complex_words[0].occurrences[0];
+        assert_eq!(&DENSE_PASSAGE[first.start_pos..first.end_pos], "organizational");
+        assert_eq!(gap.complex_words_over_budget, gap.complex_words.iter().map(|w| w.count).sum::<usize>());
+        assert!(gap.estimated_grade < gap.current_grade);
+    }
+
+    #[test]
+    fn readability_gap_met_or_unknown_target() {
+        let processor = TextProcessor::new();
+        let gap = processor.readability_gap("The cat sat on the mat. It was warm. We went home.", "general adult").unwrap();
+        assert!(gap.meets_target);
+        assert!(gap.sentences_to_split.is_empty() && gap.complex_words.is_empty());
+        assert_eq!(gap.estimated_grade, gap.current_grade);
+        let by_number = processor.readability_gap(DENSE_PASSAGE, "Grade 8").unwrap();
+        assert_eq!((by_number.target_grade_min, by_number.target_grade_max), (7.0, 8.0));
+        assert!(processor.readability_gap(DENSE_PASSAGE, "toddler").err().is_some_and(|e| e.contains("toddler")));
+    }
+}
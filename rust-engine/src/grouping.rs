@@ -0,0 +1,180 @@
+//! Reorganizes already-computed suggestions into paragraph → sentence groups
+//! for digest views. No rules are run here.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::segmentation::DocumentIndex;
+use crate::suggestions::{priority_name, priority_rank, SCOPE_DOCUMENT};
+use crate::OptimizationSuggestion;
+
+#[derive(Serialize, Default)]
+pub struct GroupSummary {
+    pub total: usize,
+    /// Suggestion counts keyed by `suggestion_type`.
+    pub counts: BTreeMap<String, usize>,
+    pub worst_priority: Option<&'static str>,
+}
+
+impl GroupSummary {
+    fn add(&mut self, suggestion: &OptimizationSuggestion) {
+        self.total += 1;
+        *self.counts.entry(suggestion.suggestion_type.to_string()).or_insert(0) += 1;
+        let rank = priority_rank(&suggestion.priority);
+        if self.worst_priority.is_none_or(|worst| rank > priority_rank(worst)) {
+            self.worst_priority = Some(priority_name(rank));
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SentenceGroup {
+    pub index: usize,
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub summary: GroupSummary,
+    pub suggestions: Vec<OptimizationSuggestion>,
+}
+
+#[derive(Serialize)]
+pub struct ParagraphGroup {
+    pub index: usize,
+    pub start_pos: usize,
+    pub end_pos: usize,
+    /// Covers the paragraph-level suggestions and every sentence group.
+    pub summary: GroupSummary,
+    /// Suggestions spanning more than one sentence.
+    pub suggestions: Vec<OptimizationSuggestion>,
+    pub sentences: Vec<SentenceGroup>,
+}
+
+#[derive(Serialize)]
+pub struct SuggestionDigest {
+    pub paragraphs: Vec<ParagraphGroup>,
+    /// Document-scope suggestions and any that fall outside every paragraph.
+    pub document: Vec<OptimizationSuggestion>,
+    pub summary: GroupSummary,
+}
+
+fn containing(spans: &[(usize, usize)], start: usize, end: usize) -> Option<usize> {
+    // Spans are sorted and disjoint, so only the last one starting at or
+    // before `start` can contain the range
+    let i = spans.partition_point(|s| s.0 <= start).checked_sub(1)?;
+    (end <= spans[i].1).then_some(i)
+}
+
+/// Groups suggestions by containing paragraph and sentence. Only groups with
+/// at least one suggestion are listed, in document order.
+pub fn group(index: &DocumentIndex, suggestions: Vec<OptimizationSuggestion>) -> SuggestionDigest {
+    let mut paragraphs: BTreeMap<usize, ParagraphGroup> = BTreeMap::new();
+    let mut document = Vec::new();
+    let mut summary = GroupSummary::default();
+
+    for suggestion in suggestions {
+        summary.add(&suggestion);
+        let paragraph = match containing(&index.paragraphs, suggestion.start_pos, suggestion.end_pos) {
+            Some(p) if suggestion.scope != SCOPE_DOCUMENT => p,
+            _ => {
+                document.push(suggestion);
+                continue;
+            }
+        };
+        let (start_pos, end_pos) = index.paragraphs[paragraph];
+        let group = paragraphs.entry(paragraph).or_insert_with(|| ParagraphGroup {
+            index: paragraph,
+            start_pos,
+            end_pos,
+            summary: GroupSummary::default(),
+            suggestions: Vec::new(),
+            sentences: Vec::new(),
+        });
+        group.summary.add(&suggestion);
+
+        match containing(&index.sentences, suggestion.start_pos, suggestion.end_pos) {
+            Some(sentence) => {
+                let position = group.sentences.iter().position(|s| s.index == sentence).unwrap_or_else(|| {
+                    let (start_pos, end_pos) = index.sentences[sentence];
+                    group.sentences.push(SentenceGroup {
+                        index: sentence,
+                        start_pos,
+                        end_pos,
+                        summary: GroupSummary::default(),
+                        suggestions: Vec::new(),
+                    });
+                    group.sentences.len() - 1
+                });
+                let sentence_group = &mut group.sentences[position];
+                sentence_group.summary.add(&suggestion);
+                sentence_group.suggestions.push(suggestion);
+            }
+            None => group.suggestions.push(suggestion),
+        }
+    }
+
+    let mut paragraphs: Vec<ParagraphGroup> = paragraphs.into_values().collect();
+    for paragraph in &mut paragraphs {
+        paragraph.sentences.sort_by_key(|s| s.index);
+    }
+    SuggestionDigest { paragraphs, document, summary }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    const TEXT: &str = "The door was opened. She walked in slowly.\n\nRain fell. It was heard by all.";
+
+    fn suggestion(rule: &str, priority: &str, span: (usize, usize), scope: &str) -> OptimizationSuggestion {
+        serde_json::from_value(serde_json::json!({
+            "suggestionType": rule,
+            "priority": priority,
+            "message": "",
+            "startPos": span.0,
+            "endPos": span.1,
+            "scope": scope,
+        }))
+        .unwrap()
+    }
+
+    fn span(needle: &str) -> (usize, usize) {
+        let start = TEXT.find(needle).unwrap();
+        (start, start + needle.len())
+    }
+
+    #[test]
+    fn suggestions_nest_under_their_paragraph_and_sentence() {
+        let index = TextProcessor::new().index(TEXT);
+        let suggestions = vec![
+            suggestion("adverb_usage", "low", span("slowly"), "instance"),
+            suggestion("passive_voice", "low", span("was heard"), "instance"),
+            suggestion("passive_voice", "medium", span("was opened"), "instance"),
+            suggestion("paragraph_length", "high", span("The door was opened. She walked"), "instance"),
+            suggestion("passive_voice", "medium", (0, TEXT.len()), "document"),
+        ];
+        let digest = group(&index, suggestions);
+
+        assert_eq!(digest.summary.total, 5);
+        assert_eq!(digest.summary.worst_priority, Some("high"));
+        assert_eq!(digest.document.len(), 1);
+
+        let first = &digest.paragraphs[0];
+        assert_eq!((first.index, first.summary.total, first.summary.worst_priority), (0, 3, Some("high")));
+        assert_eq!(first.suggestions[0].suggestion_type, "paragraph_length");
+        let sentences: Vec<(usize, usize)> = first.sentences.iter().map(|s| (s.index, s.summary.total)).collect();
+        assert_eq!(sentences, [(0, 1), (1, 1)]);
+        assert_eq!(first.sentences[0].summary.counts["passive_voice"], 1);
+
+        let second = &digest.paragraphs[1];
+        assert_eq!((second.index, second.sentences[0].index, second.summary.worst_priority), (1, 3, Some("low")));
+        assert_eq!(&TEXT[second.start_pos..second.end_pos], "Rain fell. It was heard by all.");
+    }
+
+    #[test]
+    fn suggestions_outside_every_paragraph_go_to_the_document() {
+        let index = TextProcessor::new().index(TEXT);
+        let digest = group(&index, vec![suggestion("custom", "low", (TEXT.len() + 5, TEXT.len() + 9), "instance")]);
+        assert!(digest.paragraphs.is_empty());
+        assert_eq!(digest.document.len(), 1);
+    }
+}
@@ -0,0 +1,242 @@
+//! Content hashing, encoding, and verification helpers.
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+
+pub const ALGORITHMS: &[&str] = &["sha256", "sha512", "blake3"];
+pub const ENCODINGS: &[&str] = &["base64", "base64url", "hex"];
+
+pub const STATUS_MATCH: &str = "match";
+pub const STATUS_MISMATCH: &str = "mismatch";
+pub const STATUS_MALFORMED: &str = "malformed_expected";
+
+#[derive(Serialize, Deserialize)]
+pub struct HashVerification {
+    pub status: String,
+    pub algorithm: String,
+    pub encoding: String,
+    pub reason: Option<String>,
+}
+
+pub fn digest(algorithm: &str, text: &str) -> Option<Vec<u8>> {
+    match algorithm {
+        "sha256" => Some(Sha256::digest(text.as_bytes()).to_vec()),
+        "sha512" => Some(Sha512::digest(text.as_bytes()).to_vec()),
+        "blake3" => Some(blake3::hash(text.as_bytes()).as_bytes().to_vec()),
+        _ => None,
+    }
+}
+
+/// `base64url` is written without padding; `decode` accepts either form.
+pub fn encode(encoding: &str, bytes: &[u8]) -> Option<String> {
+    match encoding {
+        "base64" => Some(general_purpose::STANDARD.encode(bytes)),
+        "base64url" => Some(general_purpose::URL_SAFE_NO_PAD.encode(bytes)),
+        "hex" => Some(to_hex(bytes)),
+        _ => None,
+    }
+}
+
+pub fn unsupported(kind: &str, value: &str, supported: &[&str]) -> String {
+    format!("Unsupported {} \"{}\"; expected one of {}", kind, value, supported.join(", "))
+}
+
+/// The hash of `text` under `algorithm`, written in `encoding`.
+pub fn hash(text: &str, algorithm: &str, encoding: &str) -> Result<String, String> {
+    let digest = digest(algorithm, text).ok_or_else(|| unsupported("algorithm", algorithm, ALGORITHMS))?;
+    encode(encoding, &digest).ok_or_else(|| unsupported("encoding", encoding, ENCODINGS))
+}
+
+/// Whether `expected` is the hash of `text`, compared in constant time. A
+/// value that doesn't decode, or decodes to the wrong length, doesn't match;
+/// only an unknown algorithm or encoding is an error.
+pub fn matches(text: &str, expected: &str, algorithm: &str, encoding: &str) -> Result<bool, String> {
+    let actual = digest(algorithm, text).ok_or_else(|| unsupported("algorithm", algorithm, ALGORITHMS))?;
+    if !ENCODINGS.contains(&encoding) {
+        return Err(unsupported("encoding", encoding, ENCODINGS));
+    }
+    Ok(decode(encoding, expected.trim()).is_ok_and(|expected| constant_time_eq(&actual, &expected)))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn decode(encoding: &str, value: &str) -> Result<Vec<u8>, String> {
+    match encoding {
+        "base64" => general_purpose::STANDARD
+            .decode(value)
+            .map_err(|e| format!("invalid base64: {}", e)),
+        "base64url" => {
+            // Accept both padded and unpadded forms
+            let trimmed = value.trim_end_matches('=');
+            general_purpose::URL_SAFE_NO_PAD
+                .decode(trimmed)
+                .map_err(|e| format!("invalid base64url: {}", e))
+        }
+        "hex" => {
+            if !value.len().is_multiple_of(2) {
+                return Err("hex value has an odd number of digits".to_string());
+            }
+            (0..value.len())
+                .step_by(2)
+                .map(|i| {
+                    value
+                        .get(i..i + 2)
+                        .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                        .ok_or_else(|| format!("invalid hex digits at offset {}", i))
+                })
+                .collect()
+        }
+        other => Err(format!("unsupported encoding \"{}\"", other)),
+    }
+}
+
+/// Compares two byte strings without short-circuiting on the first difference.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Splits an optional `"<algorithm>:"` prefix from an expected hash value.
+pub fn split_algorithm_prefix(expected: &str, default_algorithm: &str) -> (String, String) {
+    match expected.split_once(':') {
+        Some((algorithm, value)) => (algorithm.to_ascii_lowercase(), value.to_string()),
+        None => (default_algorithm.to_string(), expected.to_string()),
+    }
+}
+
+/// Recomputes the hash of `text` and compares it with `expected`, which is
+/// decoded according to `encoding` and may carry an algorithm prefix.
+pub fn verify(text: &str, expected: &str, encoding: &str) -> HashVerification {
+    let (algorithm, value) = split_algorithm_prefix(expected.trim(), "sha256");
+    let malformed = |reason: String| HashVerification {
+        status: STATUS_MALFORMED.to_string(),
+        algorithm: algorithm.clone(),
+        encoding: encoding.to_string(),
+        reason: Some(reason),
+    };
+
+    let Some(actual) = digest(&algorithm, text) else {
+        return malformed(format!("unsupported algorithm \"{}\"", algorithm));
+    };
+    let expected_bytes = match decode(encoding, &value) {
+        Ok(bytes) => bytes,
+        Err(reason) => return malformed(reason),
+    };
+    if expected_bytes.len() != actual.len() {
+        return malformed(format!(
+            "decoded hash is {} bytes, expected {} for {}",
+            expected_bytes.len(),
+            actual.len(),
+            algorithm
+        ));
+    }
+
+    let status = if constant_time_eq(&actual, &expected_bytes) { STATUS_MATCH } else { STATUS_MISMATCH };
+    HashVerification {
+        status: status.to_string(),
+        algorithm: algorithm.clone(),
+        encoding: encoding.to_string(),
+        reason: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "It was a dark and stormy night.";
+
+    fn encoded(encoding: &str) -> String {
+        let bytes = digest("sha256", TEXT).unwrap();
+        match encoding {
+            "base64" => general_purpose::STANDARD.encode(&bytes),
+            "base64url" => general_purpose::URL_SAFE_NO_PAD.encode(&bytes),
+            _ => bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+        }
+    }
+
+    #[test]
+    fn correct_hashes_match_in_every_encoding() {
+        for encoding in ENCODINGS {
+            assert_eq!(verify(TEXT, &encoded(encoding), encoding).status, STATUS_MATCH, "{}", encoding);
+        }
+        assert_eq!(verify(TEXT, &format!("SHA256:{}", encoded("hex")), "hex").status, STATUS_MATCH);
+        assert_eq!(verify(TEXT, &format!("{}=", encoded("base64url")), "base64url").status, STATUS_MATCH);
+        assert_eq!(verify("Another night.", &encoded("base64"), "base64").status, STATUS_MISMATCH);
+    }
+
+    #[test]
+    fn truncated_padded_and_wrongly_encoded_values_are_malformed() {
+        let base64 = encoded("base64");
+        let hex = encoded("hex");
+        let cases = [
+            (base64[..20].to_string(), "base64"),
+            (base64.trim_end_matches('=').to_string(), "base64"),
+            (format!("{}==", base64), "base64"),
+            (hex[..hex.len() - 1].to_string(), "hex"),
+            (hex[..hex.len() - 2].to_string(), "hex"),
+            (base64.clone(), "hex"),
+            (hex.replace('a', "z"), "hex"),
+            (format!("md5:{}", hex), "hex"),
+        ];
+        for (value, encoding) in cases {
+            let verification = verify(TEXT, &value, encoding);
+            assert_eq!(verification.status, STATUS_MALFORMED, "{} as {}", value, encoding);
+            assert!(verification.reason.is_some());
+        }
+    }
+
+    #[test]
+    fn constant_time_eq_compares_length_and_content() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+    }
+
+    const FOX: &str = "The quick brown fox jumps over the lazy dog";
+
+    #[test]
+    fn pinned_digests_for_every_algorithm_and_encoding() {
+        let pinned = [
+            ("sha256", "hex", "d7a8fbb307d7809469ca9abcb0082e4f8d5651e46d3cdb762d02d0bf37c9e592"),
+            ("sha256", "base64", "16j7swfXgJRpypq8sAguT41WUeRtPNt2LQLQvzfJ5ZI="),
+            ("sha256", "base64url", "16j7swfXgJRpypq8sAguT41WUeRtPNt2LQLQvzfJ5ZI"),
+            (
+                "sha512",
+                "hex",
+                "07e547d9586f6a73f73fbac0435ed76951218fb7d0c8d788a309d785436bbb64\
+                 2e93a252a954f23912547d1e8a3b5ed6e1bfd7097821233fa0538f3db854fee6",
+            ),
+            ("sha512", "base64", "B+VH2VhvanP3P7rAQ17XaVEhj7fQyNeIownXhUNru2Quk6JSqVTyORJUfR6KO17W4b/XCXghIz+gU489uFT+5g=="),
+            ("sha512", "base64url", "B-VH2VhvanP3P7rAQ17XaVEhj7fQyNeIownXhUNru2Quk6JSqVTyORJUfR6KO17W4b_XCXghIz-gU489uFT-5g"),
+            ("blake3", "hex", "2f1514181aadccd913abd94cfa592701a5686ab23f8df1dff1b74710febc6d4a"),
+            ("blake3", "base64", "LxUUGBqtzNkTq9lM+lknAaVoarI/jfHf8bdHEP68bUo="),
+            ("blake3", "base64url", "LxUUGBqtzNkTq9lM-lknAaVoarI_jfHf8bdHEP68bUo"),
+        ];
+        assert_eq!(pinned.len(), ALGORITHMS.len() * ENCODINGS.len());
+        for (algorithm, encoding, expected) in pinned {
+            assert_eq!(hash(FOX, algorithm, encoding).unwrap(), expected, "{} {}", algorithm, encoding);
+            assert_eq!(matches(FOX, expected, algorithm, encoding), Ok(true), "{} {}", algorithm, encoding);
+            assert_eq!(matches("The quick brown fox", expected, algorithm, encoding), Ok(false));
+        }
+        // The published BLAKE3 digest of empty input
+        assert_eq!(hash("", "blake3", "hex").unwrap(), "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262");
+    }
+
+    #[test]
+    fn unknown_algorithms_and_encodings_are_errors_but_bad_values_are_false() {
+        assert_eq!(hash(FOX, "md5", "hex").err().unwrap(), "Unsupported algorithm \"md5\"; expected one of sha256, sha512, blake3");
+        assert_eq!(hash(FOX, "sha256", "base32").err().unwrap(), "Unsupported encoding \"base32\"; expected one of base64, base64url, hex");
+        assert!(matches(FOX, "00", "blake3", "base32").is_err());
+        let sha256 = hash(FOX, "sha256", "hex").unwrap();
+        assert_eq!(matches(FOX, &sha256, "sha512", "hex"), Ok(false));
+        assert_eq!(matches(FOX, &sha256[..10], "sha256", "hex"), Ok(false));
+        assert_eq!(matches(FOX, "not hex at all", "sha256", "hex"), Ok(false));
+        assert_eq!(matches(FOX, &format!(" {}\n", sha256), "sha256", "hex"), Ok(true));
+    }
+}
@@ -0,0 +1,205 @@
+//! Compact per-revision health snapshots and the trend math over them, so
+//! dashboards never depend on the full result schema.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::suggestions::priority_rank;
+use crate::{OptimizationSuggestion, TextAnalysisResult};
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Default quality drop (in score points) that counts as a regression when
+/// the word count grew at the same time.
+pub const DEFAULT_REGRESSION_THRESHOLD: f64 = 5.0;
+
+// Penalty weights per priority, indexed by `priority_rank`
+const PRIORITY_WEIGHTS: [f64; 3] = [1.0, 2.0, 3.0];
+
+/// Versioned summary of one analysis. Every field has a default and unknown
+/// fields are ignored, so snapshots written by newer engines still load.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct HealthSnapshot {
+    pub version: u32,
+    pub timestamp: String,
+    pub word_count: usize,
+    pub quality_score: f64,
+    pub readability_score: f64,
+    /// Suggestion counts keyed by `suggestion_type`.
+    pub suggestion_counts: BTreeMap<String, usize>,
+}
+
+/// Quality is 100 minus half the severity-weighted suggestion density
+/// (per 1,000 words), clamped to [0, 100]. Document-scope summaries are not
+/// counted so escalated rules are not penalized twice.
+pub fn quality_score(suggestions: &[&OptimizationSuggestion], word_count: usize) -> f64 {
+    if word_count == 0 {
+        return 100.0;
+    }
+    let weighted: f64 = suggestions.iter().map(|s| PRIORITY_WEIGHTS[priority_rank(&s.priority) as usize]).sum();
+    (100.0 - weighted * 1000.0 / word_count as f64 / 2.0).clamp(0.0, 100.0)
+}
+
+pub fn snapshot(analysis: &TextAnalysisResult, suggestions: &[&OptimizationSuggestion], timestamp: &str) -> HealthSnapshot {
+    let mut suggestion_counts = BTreeMap::new();
+    for suggestion in suggestions {
+        *suggestion_counts.entry(suggestion.suggestion_type.to_string()).or_insert(0) += 1;
+    }
+    HealthSnapshot {
+        version: SNAPSHOT_VERSION,
+        timestamp: timestamp.to_string(),
+        word_count: analysis.word_count,
+        quality_score: quality_score(suggestions, analysis.word_count),
+        readability_score: analysis.readability_score,
+        suggestion_counts,
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SnapshotDelta {
+    pub from_timestamp: String,
+    pub to_timestamp: String,
+    pub word_count: i64,
+    pub quality_score: f64,
+    pub readability_score: f64,
+    /// Change per suggestion type; types absent from one side count as zero.
+    pub suggestion_counts: BTreeMap<String, i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Regression {
+    /// Index of the snapshot where quality dropped.
+    pub index: usize,
+    pub timestamp: String,
+    pub quality_drop: f64,
+    pub words_added: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HealthTrend {
+    /// One value per snapshot, in input order. Suggestion counts appear as
+    /// `suggestions.<type>`.
+    pub series: BTreeMap<String, Vec<f64>>,
+    pub deltas: Vec<SnapshotDelta>,
+    pub regressions: Vec<Regression>,
+}
+
+/// Trend over snapshots given oldest first. A regression is a revision whose
+/// quality dropped by more than `threshold` while the word count grew.
+pub fn history(snapshots: &[HealthSnapshot], threshold: f64) -> HealthTrend {
+    let categories: Vec<&String> = {
+        let mut all: Vec<&String> = snapshots.iter().flat_map(|s| s.suggestion_counts.keys()).collect();
+        all.sort();
+        all.dedup();
+        all
+    };
+
+    let mut series = BTreeMap::new();
+    series.insert("word_count".to_string(), snapshots.iter().map(|s| s.word_count as f64).collect());
+    series.insert("quality_score".to_string(), snapshots.iter().map(|s| s.quality_score).collect());
+    series.insert("readability_score".to_string(), snapshots.iter().map(|s| s.readability_score).collect());
+    for category in &categories {
+        let values = snapshots.iter().map(|s| s.suggestion_counts.get(*category).copied().unwrap_or(0) as f64).collect();
+        series.insert(format!("suggestions.{}", category), values);
+    }
+
+    let mut deltas = Vec::new();
+    let mut regressions = Vec::new();
+    for (i, pair) in snapshots.windows(2).enumerate() {
+        let (before, after) = (&pair[0], &pair[1]);
+        let words_added = after.word_count as i64 - before.word_count as i64;
+        let quality_change = after.quality_score - before.quality_score;
+        let suggestion_counts = categories
+            .iter()
+            .map(|category| {
+                let count = |s: &HealthSnapshot| s.suggestion_counts.get(*category).copied().unwrap_or(0) as i64;
+                ((*category).clone(), count(after) - count(before))
+            })
+            .collect();
+        deltas.push(SnapshotDelta {
+            from_timestamp: before.timestamp.clone(),
+            to_timestamp: after.timestamp.clone(),
+            word_count: words_added,
+            quality_score: quality_change,
+            readability_score: after.readability_score - before.readability_score,
+            suggestion_counts,
+        });
+        if -quality_change > threshold && words_added > 0 {
+            regressions.push(Regression {
+                index: i + 1,
+                timestamp: after.timestamp.clone(),
+                quality_drop: -quality_change,
+                words_added,
+            });
+        }
+    }
+
+    HealthTrend { series, deltas, regressions }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(timestamp: &str, word_count: usize, quality_score: f64, counts: &[(&str, usize)]) -> HealthSnapshot {
+        HealthSnapshot {
+            version: SNAPSHOT_VERSION,
+            timestamp: timestamp.to_string(),
+            word_count,
+            quality_score,
+            readability_score: 60.0,
+            suggestion_counts: counts.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+        }
+    }
+
+    #[test]
+    fn history_reports_series_deltas_and_regressions() {
+        let snapshots = [
+            at("t0", 1000, 90.0, &[("passive_voice", 4)]),
+            at("t1", 1500, 80.0, &[("passive_voice", 9), ("adverb_usage", 3)]),
+            at("t2", 1400, 70.0, &[("adverb_usage", 1)]),
+            at("t3", 1600, 67.0, &[]),
+        ];
+        let trend = history(&snapshots, DEFAULT_REGRESSION_THRESHOLD);
+
+        assert_eq!(trend.series["quality_score"], [90.0, 80.0, 70.0, 67.0]);
+        assert_eq!(trend.series["suggestions.passive_voice"], [4.0, 9.0, 0.0, 0.0]);
+        assert_eq!(trend.series["suggestions.adverb_usage"], [0.0, 3.0, 1.0, 0.0]);
+        assert_eq!(trend.deltas.len(), 3);
+        assert_eq!((trend.deltas[1].word_count, trend.deltas[1].quality_score), (-100, -10.0));
+        assert_eq!(trend.deltas[1].suggestion_counts["passive_voice"], -9);
+
+        // t2 lost quality while shrinking and t3 stayed under the threshold
+        let regressions: Vec<(usize, &str, f64, i64)> =
+            trend.regressions.iter().map(|r| (r.index, r.timestamp.as_str(), r.quality_drop, r.words_added)).collect();
+        assert_eq!(regressions, [(1, "t1", 10.0, 500)]);
+    }
+
+    #[test]
+    fn snapshots_from_newer_or_older_engines_still_load() {
+        let newer: HealthSnapshot = serde_json::from_str(
+            r#"{"version": 7, "timestamp": "t9", "word_count": 12, "quality_score": 88.5, "sentiment": {"tone": "warm"}}"#,
+        )
+        .unwrap();
+        assert_eq!((newer.version, newer.word_count, newer.quality_score), (7, 12, 88.5));
+        assert!(newer.suggestion_counts.is_empty());
+
+        let older: HealthSnapshot = serde_json::from_str(r#"{"word_count": 3}"#).unwrap();
+        assert_eq!((older.version, older.timestamp.as_str()), (0, ""));
+    }
+
+    #[test]
+    fn snapshot_scores_weighted_instance_density() {
+        let processor = crate::TextProcessor::new();
+        let text = "The door was opened slowly. She walked in.";
+        let analysis = processor.perform_analysis(text);
+        let report = processor.build_optimization_report(text);
+        let instances: Vec<&OptimizationSuggestion> = report.suggestions.iter().filter(|s| s.scope == "instance").collect();
+        let snapshot = snapshot(&analysis, &instances, "t0");
+        assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+        assert_eq!(snapshot.suggestion_counts.values().sum::<usize>(), instances.len());
+        assert!(snapshot.quality_score < 100.0);
+        assert_eq!(quality_score(&[], 0), 100.0);
+    }
+}
@@ -0,0 +1,290 @@
+//! HTML markup stripped ahead of analysis, for text coming from rich-text
+//! editors: tags are skipped, entities decoded, and block boundaries kept as
+//! breaks. Keeps an offset map back to the HTML source.
+
+use crate::input::StripBuilder;
+use crate::reflow::OffsetMap;
+
+/// Elements whose contents are never visible text.
+const HIDDEN: &[&str] = &["script", "style"];
+
+/// Elements that open and close a block; either boundary is a paragraph break.
+const BLOCKS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "dd", "div", "dl", "dt", "figcaption", "figure", "footer", "h1", "h2",
+    "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "nav", "ol", "p", "pre", "section", "table", "td", "th", "tr",
+    "ul",
+];
+
+/// Entity names of U+00A0 to U+00FF, in code point order.
+const LATIN1: [&str; 96] = [
+    "nbsp", "iexcl", "cent", "pound", "curren", "yen", "brvbar", "sect", "uml", "copy", "ordf", "laquo", "not", "shy",
+    "reg", "macr", "deg", "plusmn", "sup2", "sup3", "acute", "micro", "para", "middot", "cedil", "sup1", "ordm",
+    "raquo", "frac14", "frac12", "frac34", "iquest", "Agrave", "Aacute", "Acirc", "Atilde", "Auml", "Aring", "AElig",
+    "Ccedil", "Egrave", "Eacute", "Ecirc", "Euml", "Igrave", "Iacute", "Icirc", "Iuml", "ETH", "Ntilde", "Ograve",
+    "Oacute", "Ocirc", "Otilde", "Ouml", "times", "Oslash", "Ugrave", "Uacute", "Ucirc", "Uuml", "Yacute", "THORN",
+    "szlig", "agrave", "aacute", "acirc", "atilde", "auml", "aring", "aelig", "ccedil", "egrave", "eacute", "ecirc",
+    "euml", "igrave", "iacute", "icirc", "iuml", "eth", "ntilde", "ograve", "oacute", "ocirc", "otilde", "ouml",
+    "divide", "oslash", "ugrave", "uacute", "ucirc", "uuml", "yacute", "thorn", "yuml",
+];
+
+/// Other named entities decoded; unknown ones are left as written.
+const ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("ensp", '\u{2002}'),
+    ("emsp", '\u{2003}'),
+    ("thinsp", '\u{2009}'),
+    ("ndash", '\u{2013}'),
+    ("mdash", '\u{2014}'),
+    ("lsquo", '\u{2018}'),
+    ("rsquo", '\u{2019}'),
+    ("ldquo", '\u{201C}'),
+    ("rdquo", '\u{201D}'),
+    ("bull", '\u{2022}'),
+    ("hellip", '\u{2026}'),
+    ("trade", '\u{2122}'),
+];
+
+/// The character and end of the entity at `at` (an `&`), when it is a known
+/// named or a valid numeric reference terminated by `;`.
+fn entity(text: &str, at: usize) -> Option<(char, usize)> {
+    let semicolon = at + text[at..].bytes().take(34).position(|b| b == b';')?;
+    let name = &text[at + 1..semicolon];
+    let c = match name.strip_prefix('#') {
+        Some(number) => {
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code).filter(|&c| c != '\0')?
+        }
+        None => match LATIN1.iter().position(|n| *n == name) {
+            Some(i) => char::from_u32(0xA0 + i as u32)?,
+            None => ENTITIES.iter().find(|(n, _)| *n == name)?.1,
+        },
+    };
+    Some((c, semicolon + 1))
+}
+
+/// End of the tag opening at `open`, skipping `>` inside quoted attribute
+/// values. A `<` before the tag closes means the first one was a stray.
+fn tag_end(text: &str, open: usize) -> Option<usize> {
+    let b = text.as_bytes();
+    let mut quote = None;
+    for (offset, &c) in b[open + 1..].iter().enumerate() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                b'"' | b'\'' => quote = Some(c),
+                b'>' => return Some(open + 1 + offset + 1),
+                b'<' => return None,
+                _ => {}
+            },
+        }
+    }
+    // An unbalanced quote: fall back to the first `>`
+    let close = open + text[open..].find('>')?;
+    (!text[open + 1..close].contains('<')).then_some(close + 1)
+}
+
+/// End of the comment, declaration, or tag at `open`, or `None` when the `<`
+/// is literal text.
+fn markup_end(text: &str, open: usize) -> Option<usize> {
+    let rest = &text[open..];
+    if let Some(comment) = rest.strip_prefix("<!--") {
+        return comment.find("-->").map(|i| open + 4 + i + 3);
+    }
+    match rest.as_bytes().get(1)? {
+        b'!' | b'?' => rest.find('>').map(|i| open + i + 1),
+        b'/' if rest.as_bytes().get(2).is_some_and(u8::is_ascii_alphabetic) => tag_end(text, open),
+        c if c.is_ascii_alphabetic() => tag_end(text, open),
+        _ => None,
+    }
+}
+
+/// Lowercased element name of a tag, and whether it is a closing tag.
+fn tag_name(tag: &str) -> (String, bool) {
+    let inner = &tag[1..];
+    let (inner, closing) = match inner.strip_prefix('/') {
+        Some(rest) => (rest, true),
+        None => (inner, false),
+    };
+    let len = inner.bytes().take_while(|b| b.is_ascii_alphanumeric() || *b == b'-' || *b == b':').count();
+    (inner[..len].to_ascii_lowercase(), closing)
+}
+
+/// `text` as visible text, and the map back to it. Whitespace collapses as a
+/// browser renders it (except inside `<pre>`), block tags become paragraph
+/// breaks and `<br>` a line break. Unclosed tags and stray `<` are kept as
+/// text; an unclosed `<script>` or `<style>` hides the rest of the document.
+pub fn strip(text: &str) -> (String, OffsetMap) {
+    let b = text.as_bytes();
+    let mut builder = StripBuilder::new(text);
+    let mut preformatted = 0usize;
+    // Start of the source text not yet copied
+    let mut kept = 0;
+    let mut i = 0;
+    while i < b.len() {
+        match b[i] {
+            b'<' => {
+                let Some(end) = markup_end(text, i) else {
+                    i += 1;
+                    continue;
+                };
+                builder.keep(kept, i);
+                let (name, closing) = tag_name(&text[i..end]);
+                let self_closing = text[i..end].ends_with("/>");
+                let mut next = end;
+                if HIDDEN.contains(&name.as_str()) && !closing && !self_closing {
+                    let close = format!("</{}", name);
+                    next = match text[end..].to_ascii_lowercase().find(&close) {
+                        Some(at) => markup_end(text, end + at).unwrap_or(b.len()),
+                        None => b.len(),
+                    };
+                } else if name == "br" {
+                    builder.substitute("\n", i, end);
+                } else if BLOCKS.contains(&name.as_str()) {
+                    if !builder.ends_with("\n\n") {
+                        builder.substitute("\n\n", i, end);
+                    }
+                    if name == "pre" && !self_closing {
+                        preformatted = if closing { preformatted.saturating_sub(1) } else { preformatted + 1 };
+                    }
+                }
+                kept = next;
+                i = next;
+            }
+            b'&' => {
+                let Some((c, end)) = entity(text, i) else {
+                    i += 1;
+                    continue;
+                };
+                builder.keep(kept, i);
+                builder.substitute(c.encode_utf8(&mut [0; 4]), i, end);
+                kept = end;
+                i = end;
+            }
+            b' ' | b'\t' | b'\n' | b'\r' | b'\x0C' if preformatted == 0 => {
+                let run = b[i..].iter().take_while(|c| matches!(c, b' ' | b'\t' | b'\n' | b'\r' | b'\x0C')).count();
+                // Most runs are a single space, kept as is
+                if run > 1 || b[i] != b' ' {
+                    builder.keep(kept, i);
+                    builder.substitute(" ", i, i + 1);
+                    kept = i + run;
+                }
+                i += run;
+            }
+            _ => i += 1,
+        }
+    }
+    builder.keep(kept, b.len());
+    builder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+    use crate::config::{InputFormat, SuggestionConfig};
+    use crate::test_support::Cases;
+
+    fn stripped(text: &str) -> String {
+        strip(text).0
+    }
+
+    fn words(text: &str) -> usize {
+        text.split_whitespace().count()
+    }
+
+    #[test]
+    fn tags_are_skipped_and_entities_decoded() {
+        assert_eq!(stripped("<b>Fish &amp; chips</b>&nbsp;for&#32;two&#x21;"), "Fish & chips\u{a0}for two!");
+        assert_eq!(stripped("caf&eacute; &hellip; &mdash; &yuml;"), "caf\u{e9} \u{2026} \u{2014} \u{ff}");
+        // Unknown, unterminated, and invalid references stay as written
+        assert_eq!(stripped("&bogus; &amp &#xD800; &#0; AT&T"), "&bogus; &amp &#xD800; &#0; AT&T");
+    }
+
+    #[test]
+    fn blocks_and_line_breaks_separate_text() {
+        assert_eq!(stripped("<p>One.</p><p>Two.</p>"), "\n\nOne.\n\nTwo.\n\n");
+        assert_eq!(stripped("<div>Line<br>next<br/>last</div>"), "\n\nLine\nnext\nlast\n\n");
+        assert_eq!(stripped("<p>\n   spaced\n\n   out\n</p>"), "\n\n spaced out \n\n");
+        assert_eq!(stripped("<pre>keep\n   this</pre>"), "\n\nkeep\n   this\n\n");
+    }
+
+    #[test]
+    fn scripts_styles_and_comments_are_left_out() {
+        let html = "<style>p { color: red }</style>Visible<script>if (a < b) { alert('no words here'); }</script> text<!-- hidden note -->.";
+        assert_eq!(stripped(html), "Visible text.");
+        assert_eq!(stripped("<SCRIPT type=\"x\">one two</Script>left"), "left");
+        // An unclosed script hides the rest of the document
+        assert_eq!(stripped("Before<script>var x = 1;"), "Before");
+    }
+
+    #[test]
+    fn malformed_html_degrades_to_text() {
+        assert_eq!(stripped("3 < 4 and 5 > 2"), "3 < 4 and 5 > 2");
+        assert_eq!(stripped("a <b unclosed tag"), "a <b unclosed tag");
+        assert_eq!(stripped("<p>text <i>never closed"), "\n\ntext never closed");
+        assert_eq!(stripped("<a title=\"1 > 0\">link</a>"), "link");
+        assert_eq!(stripped("x <<b>y</b>"), "x <y");
+        assert_eq!(stripped("</ not a tag"), "</ not a tag");
+    }
+
+    #[test]
+    fn contenteditable_dump_counts_like_its_visible_text() {
+        let dump = "<div class=\"editor\" contenteditable=\"true\"><p><span style=\"font-weight: 700;\">The\u{a0}storm</span>\
+<span> broke</span>&nbsp;<span><span><i>over</i></span> the</span> ridge.</p>\
+<div><span>Mara&nbsp;&nbsp;waited</span><br></div><p><span>She didn&rsquo;t</span>&nbsp;<b>move</b>.</p></div>";
+        let visible = "The storm broke over the ridge.\n\nMara waited\n\nShe didn\u{2019}t move.";
+        let (text, _) = strip(dump);
+        assert_eq!(words(&text), words(visible));
+        assert_eq!(text.split_whitespace().collect::<Vec<_>>(), visible.split_whitespace().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn offsets_map_back_into_the_html() {
+        let html = "<p>Caf&eacute; <em>very</em> good&nbsp;now</p>";
+        let (text, map) = strip(html);
+        let at = |needle: &str| {
+            let start = text.find(needle).unwrap();
+            let (start, end) = map.map_span(start, start + needle.len());
+            &html[start..end]
+        };
+        assert_eq!(at("very"), "very");
+        assert_eq!(at("Caf\u{e9}"), "Caf&eacute;");
+        assert_eq!(at("good\u{a0}now"), "good&nbsp;now");
+    }
+
+    #[test]
+    fn random_markup_never_panics_and_maps_inside_the_source() {
+        const PIECES: &[&str] = &["<p>", "</p>", "<br>", "<", ">", "&amp;", "&", ";", "&#x41;", "<script>", "</script>", "\"", " ", "\n", "word", "caf\u{e9}", "<!--", "-->", "<pre>", "</pre>"];
+        let mut cases = Cases(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..2000 {
+            let html: String = (0..cases.below(20)).map(|_| PIECES[cases.below(PIECES.len())]).collect();
+            let (text, map) = strip(&html);
+            for (i, _) in text.char_indices() {
+                let original = map.to_original(i);
+                assert!(html.is_char_boundary(original), "byte {} of {:?} from {:?}", i, text, html);
+            }
+            assert!(text.len() <= html.len(), "{:?} grew into {:?}", html, text);
+        }
+    }
+
+    #[test]
+    fn html_input_counts_visible_text_and_maps_suggestions_to_the_source() {
+        let source = "<h1>Storm&nbsp;warning</h1><p>Mara was <b>very</b> tired.</p><script>var very = 1;</script><p>She left.</p>";
+        let html = TextProcessor::with_patterns(&Default::default(), SuggestionConfig { input_format: InputFormat::Html, ..Default::default() });
+        let analysis = html.perform_analysis(source);
+        assert_eq!((analysis.word_count, analysis.paragraph_count), (8, 3));
+
+        let report = html.build_optimization_report(source);
+        let fillers: Vec<(usize, &str)> =
+            report.suggestions.iter().filter(|s| s.suggestion_type == "filler_word").map(|s| (s.start_pos, &source[s.start_pos..s.end_pos])).collect();
+        assert_eq!(fillers, [(source.find("very").unwrap(), "very")]);
+    }
+}
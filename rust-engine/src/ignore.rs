@@ -0,0 +1,55 @@
+//! Byte ranges that body-text rules should skip: quoted material and block
+//! citations.
+
+pub struct IgnoreRanges {
+    ranges: Vec<(usize, usize)>,
+}
+
+impl IgnoreRanges {
+    /// Combines the given quoted spans with block citations: lines starting
+    /// with `>` and lines indented by a tab or four or more spaces.
+    pub fn detect(text: &str, quoted_spans: &[(usize, usize)]) -> IgnoreRanges {
+        let mut ranges = quoted_spans.to_vec();
+
+        let mut line_start = 0;
+        for line in text.split_inclusive('\n') {
+            let content = line.trim_end_matches(['\r', '\n']);
+            let is_block_citation = content.trim_start().starts_with('>')
+                || ((content.starts_with('\t') || content.starts_with("    ")) && !content.trim().is_empty());
+            if is_block_citation {
+                ranges.push((line_start, line_start + content.len()));
+            }
+            line_start += line.len();
+        }
+
+        ranges.sort_unstable();
+        IgnoreRanges { ranges }
+    }
+
+    pub fn contains(&self, start: usize, end: usize) -> bool {
+        self.ranges.iter().any(|&(s, e)| start >= s && end <= e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_and_block_citations_are_ignored() {
+        let text = "He said \"we tried\" twice.\n> I quote this line\n    Indented citation\nBody again.";
+        let at = |needle: &str| {
+            let start = text.find(needle).unwrap();
+            (start, start + needle.len())
+        };
+        let ranges = IgnoreRanges::detect(text, &[at("\"we tried\"")]);
+        for quoted in ["we tried", "I quote", "Indented"] {
+            let (start, end) = at(quoted);
+            assert!(ranges.contains(start, end), "{} should be ignored", quoted);
+        }
+        for body in ["He said", "Body again"] {
+            let (start, end) = at(body);
+            assert!(!ranges.contains(start, end), "{} should not be ignored", body);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Preparation of the text analysis actually runs on: front matter left
+//! out, Markdown or HTML stripped, and PDF reflow applied, per the
+//! configuration, with one offset map back to the caller's text.
+
+use crate::config::{InputFormat, SuggestionConfig};
+use crate::front_matter;
+use crate::html;
+use crate::markdown;
+use crate::reflow::{self, OffsetMap};
+use crate::warnings::AnalysisWarning;
+
+/// Copies the kept parts of a source, anchoring the offset map wherever
+/// kept text doesn't continue the previous kept range.
+pub struct StripBuilder<'t> {
+    pub source: &'t str,
+    out: String,
+    anchors: Vec<(usize, usize)>,
+    kept_end: usize,
+}
+
+impl<'t> StripBuilder<'t> {
+    pub fn new(source: &'t str) -> StripBuilder<'t> {
+        StripBuilder { source, out: String::with_capacity(source.len()), anchors: vec![(0, 0)], kept_end: 0 }
+    }
+
+    pub fn keep(&mut self, start: usize, end: usize) {
+        if start >= end {
+            return;
+        }
+        if start != self.kept_end {
+            self.anchors.push((self.out.len(), start));
+        }
+        self.out.push_str(&self.source[start..end]);
+        self.kept_end = end;
+    }
+
+    /// Text not in the source (a decoded entity, a line break for a block
+    /// tag) standing for `start..end`. The last byte maps to the end of the
+    /// range, so spans ending in it cover the whole entity.
+    pub fn substitute(&mut self, text: &str, start: usize, end: usize) {
+        let last = text.len() - 1;
+        self.anchors.push((self.out.len(), start));
+        if last > 0 && end > start + 1 {
+            self.anchors.push((self.out.len() + last, end - 1));
+        }
+        self.out.push_str(text);
+        // Whatever is kept next gets an anchor of its own
+        self.kept_end = usize::MAX;
+    }
+
+    pub fn ends_with(&self, suffix: &str) -> bool {
+        self.out.ends_with(suffix)
+    }
+
+    pub fn finish(self) -> (String, OffsetMap) {
+        (self.out, OffsetMap { anchors: self.anchors })
+    }
+}
+
+pub struct PreparedText {
+    pub text: String,
+    pub offset_map: OffsetMap,
+    /// Reported on analyses of the prepared text.
+    pub warnings: Vec<AnalysisWarning>,
+}
+
+/// `None` when the configuration analyzes input as given.
+pub fn prepare(text: &str, config: &SuggestionConfig) -> Option<PreparedText> {
+    let Some(start) = front_matter::end(text).filter(|_| config.skip_front_matter) else {
+        return prepare_body(text, config);
+    };
+    let skipped = OffsetMap { anchors: vec![(0, start)] };
+    Some(match prepare_body(&text[start..], config) {
+        Some(body) => PreparedText { offset_map: body.offset_map.then(&skipped), ..body },
+        None => PreparedText { text: text[start..].to_string(), offset_map: skipped, warnings: Vec::new() },
+    })
+}
+
+fn prepare_body(text: &str, config: &SuggestionConfig) -> Option<PreparedText> {
+    let stripped = match config.input_format {
+        InputFormat::Plain => None,
+        InputFormat::Markdown => Some(markdown::strip(text)),
+        InputFormat::Html => Some(html::strip(text)),
+    };
+    if !config.reflow_input {
+        return stripped.map(|(text, offset_map)| PreparedText { text, offset_map, warnings: Vec::new() });
+    }
+    let reflowed = reflow::reflow_text(stripped.as_ref().map_or(text, |s| &s.0));
+    let warnings = vec![reflowed.warning()];
+    let offset_map = match &stripped {
+        Some((_, markdown_map)) => reflowed.offset_map.then(markdown_map),
+        None => reflowed.offset_map,
+    };
+    Some(PreparedText { text: reflowed.text, offset_map, warnings })
+}
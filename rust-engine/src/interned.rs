@@ -0,0 +1,121 @@
+//! The distinct words of an analysis, each lowercased, measured and
+//! classified once. A chapter repeats most of its words and the chapters of
+//! a batch share most of their vocabulary, so `analyze_batch` keeps one
+//! table across its documents.
+
+use std::collections::HashMap;
+
+use crate::{cliches, language, narrative};
+
+/// What the metrics need about one spelling of a word.
+pub struct WordFacts {
+    /// Shared by every casing of the word.
+    pub lowercase: u32,
+    /// Characters of the lowercased word.
+    pub lowercase_chars: usize,
+    pub chars: usize,
+    /// Alphanumeric characters.
+    pub letters: usize,
+    /// `narrative::word_class` of the lowercased word.
+    pub class: u8,
+    /// `language::function_word_languages` of the lowercased word.
+    pub languages: u8,
+    /// `cliches::stem` of the word.
+    pub stem: Box<str>,
+    /// The language `measured` was computed under.
+    measured: Option<(&'static str, Measured)>,
+}
+
+/// The facts that depend on the language resources in use.
+#[derive(Clone, Copy)]
+pub struct Measured {
+    pub opaque: bool,
+    pub syllables: usize,
+    pub content: bool,
+}
+
+#[derive(Default)]
+pub struct WordTable {
+    ids: HashMap<Box<str>, u32>,
+    facts: Vec<WordFacts>,
+    lowercase: HashMap<Box<str>, u32>,
+}
+
+impl WordTable {
+    /// The id of `word`, adding it on first sight.
+    pub fn intern(&mut self, word: &str) -> u32 {
+        if let Some(&id) = self.ids.get(word) {
+            return id;
+        }
+        let lowercase = word.to_lowercase();
+        let facts = WordFacts {
+            lowercase: 0,
+            lowercase_chars: lowercase.chars().count(),
+            chars: word.chars().count(),
+            letters: word.chars().filter(|c| c.is_alphanumeric()).count(),
+            class: narrative::word_class(&lowercase),
+            languages: language::function_word_languages(&lowercase),
+            stem: cliches::stem(word).into_boxed_str(),
+            measured: None,
+        };
+        let next = self.lowercase.len() as u32;
+        let lowercase = *self.lowercase.entry(lowercase.into_boxed_str()).or_insert(next);
+        self.facts.push(WordFacts { lowercase, ..facts });
+        let id = (self.facts.len() - 1) as u32;
+        self.ids.insert(word.into(), id);
+        id
+    }
+
+    pub fn facts(&self, id: u32) -> &WordFacts {
+        &self.facts[id as usize]
+    }
+
+    /// `measure(word)` for the word `id`, computed once per language.
+    pub fn measured(&mut self, id: u32, word: &str, language: &'static str, measure: impl FnOnce(&str) -> Measured) -> Measured {
+        let facts = &mut self.facts[id as usize];
+        match facts.measured {
+            Some((measured_in, measured)) if measured_in == language => measured,
+            _ => {
+                let measured = measure(word);
+                facts.measured = Some((language, measured));
+                measured
+            }
+        }
+    }
+
+    /// Distinct lowercased words seen.
+    pub fn vocabulary_size(&self) -> usize {
+        self.lowercase.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn casings_share_a_lowercase_id_and_are_measured_once_per_language() {
+        let mut table = WordTable::default();
+        let (upper, lower) = (table.intern("Felt"), table.intern("felt"));
+        assert_ne!(upper, lower);
+        assert_eq!(table.intern("Felt"), upper);
+        assert_eq!(table.facts(upper).lowercase, table.facts(lower).lowercase);
+        assert_eq!(table.vocabulary_size(), 1);
+        table.intern("\u{c9}t\u{e9}");
+        table.intern("\u{e9}t\u{e9}");
+        assert_eq!(table.vocabulary_size(), 2);
+        assert_eq!(table.facts(upper).class & narrative::FILTER_VERB, narrative::FILTER_VERB);
+
+        let mut calls = 0;
+        let mut measure = |language| {
+            table.measured(upper, "Felt", language, |_| {
+                calls += 1;
+                Measured { opaque: false, syllables: 1, content: true }
+            })
+        };
+        measure("en");
+        measure("en");
+        measure("de");
+        assert_eq!(calls, 2);
+    }
+}
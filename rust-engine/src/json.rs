@@ -0,0 +1,83 @@
+//! JSON string forms of results, for consumers that persist them or whose
+//! bundler turns nested `JsValue` objects into `Map`s.
+//!
+//! The analysis, suggestion and conflict types are `rename_all =
+//! "camelCase"` (`word_count` is `wordCount`, `per_1000_words` is
+//! `per1000Words`, `start_utf16` is `startUtf16`), so these strings and the
+//! `JsValue` methods share one shape. Map keys are data and pass through
+//! unchanged. Conflicts also accept their snake_case field names.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|e| format!("Serialization failed: {}", e))
+}
+
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, String> {
+    serde_json::from_str(json).map_err(|e| format!("Invalid JSON: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use crate::{OptimizationSuggestion, TextAnalysisResult, TextProcessor};
+    use crate::conflicts::ConflictResolutionResponse;
+    use crate::resolution::ConflictPolicy;
+
+    #[test]
+    fn analysis_json_round_trips() {
+        let processor = TextProcessor::new();
+        let text = "The letter was written by the clerk. She read it twice, slowly.";
+        let json = processor.analyze_text_json(text).unwrap();
+        assert!(json.contains("\"wordCount\":12"));
+        assert!(json.contains("\"complexityMetrics\":{"));
+        let back: TextAnalysisResult = from_json(&json).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), serde_json::to_value(processor.analyze_text(text)).unwrap());
+    }
+
+    #[test]
+    fn suggestions_json_round_trips() {
+        let processor = TextProcessor::new();
+        let text = "The letter was written by the clerk. She really read it very slowly.";
+        let json = processor.optimize_text_json(text).unwrap();
+        assert!(json.contains("\"suggestionType\":") && json.contains("\"startUtf16\":"));
+        let back: Vec<OptimizationSuggestion> = from_json(&json).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), serde_json::to_value(processor.optimize_text(text)).unwrap());
+    }
+
+    #[test]
+    fn conflicts_json_accepts_either_case_and_round_trips() {
+        let processor = TextProcessor::new();
+        let input = r#"[
+            {"conflictId": "c1", "conflictType": "text_insertion", "startPos": 4, "endPos": 4,
+             "userAChange": "very", "userBChange": "rather", "timestamp": "2024-01-01T00:00:00Z"},
+            {"conflict_id": "c2", "conflict_type": "text_modification", "start_pos": 10, "end_pos": 14,
+             "user_a_change": "cold", "user_b_change": "warm", "timestamp": "2024-01-01T00:00:00Z"},
+            {"conflictId": "c3"}
+        ]"#;
+        let json = processor.resolve_conflicts_json(input, None, ConflictPolicy::Auto).unwrap();
+        let response: ConflictResolutionResponse = from_json(&json).unwrap();
+        assert_eq!(response.resolved.len(), 2);
+        assert_eq!(response.resolved[0].resolution_suggestion, "very rather");
+        assert_eq!(response.rejected.len(), 1);
+        assert_eq!(response.rejected[0].conflict_id.as_deref(), Some("c3"));
+        assert_eq!(to_json(&response).unwrap(), json);
+        assert!(processor.resolve_conflicts_json("{}", None, ConflictPolicy::Auto).is_err());
+    }
+
+    #[test]
+    fn keys_with_numbers_follow_the_serde_names() {
+        let processor = TextProcessor::new();
+        assert!(processor.analyze_text_json("I wrote this. You read it.").unwrap().contains("\"clichesPer1000Words\":"));
+        assert!(processor.optimize_text_json("It was very cold.").unwrap().contains("\"startUtf16\":"));
+    }
+
+    #[test]
+    fn map_keys_are_left_alone() {
+        let counts: BTreeMap<&str, usize> = [("passive_voice", 2), ("user_a", 1)].into_iter().collect();
+        assert_eq!(to_json(&counts).unwrap(), r#"{"passive_voice":2,"user_a":1}"#);
+        assert_eq!(from_json::<BTreeMap<String, usize>>(r#"{"per_1000_words":3}"#).unwrap()["per_1000_words"], 3);
+    }
+}
@@ -0,0 +1,173 @@
+//! Per-language resources: stopwords, syllable rules, adverb endings, and
+//! the reading-ease formula. Each language is a table of data; adding one
+//! means adding a `Language` to `LANGUAGES`. Unknown codes get English.
+
+use crate::readability::ReadingEase;
+use crate::syllables::{Syllables, VowelGroups};
+
+pub struct Language {
+    pub code: &'static str,
+    pub stopwords: &'static [&'static str],
+    pub syllables: Syllables,
+    /// Endings that mark a word as an adverb ("-ly", "-mente").
+    pub adverb_suffixes: &'static [&'static str],
+    /// Words with an adverb ending that are nouns, adjectives, verbs, or
+    /// names rather than adverbs worth flagging.
+    pub not_adverbs: &'static [&'static str],
+    pub reading_ease: ReadingEase,
+}
+
+impl Language {
+    pub fn count_syllables(&self, word: &str) -> usize {
+        self.syllables.count(word)
+    }
+}
+
+/// Languages with their own resources; English comes first and is the
+/// fallback.
+pub const LANGUAGES: &[Language] = &[ENGLISH, SPANISH, GERMAN];
+
+/// The language setting that has `analyze_text` detect the language of each
+/// text and use its resources.
+pub const AUTO: &str = "auto";
+
+/// Resources for a language code, English for unknown codes.
+pub fn resources(code: &str) -> &'static Language {
+    LANGUAGES.iter().find(|l| l.code == code).unwrap_or(&LANGUAGES[0])
+}
+
+pub const ENGLISH: Language = Language {
+    code: "en",
+    stopwords: &[
+        "a", "about", "above", "after", "again", "against", "all", "am", "an", "and", "any", "are", "as", "at",
+        "be", "because", "been", "before", "being", "below", "between", "both", "but", "by", "can", "could",
+        "did", "do", "does", "doing", "down", "during", "each", "few", "for", "from", "further", "had", "has",
+        "have", "having", "he", "her", "here", "hers", "herself", "him", "himself", "his", "how", "i", "if",
+        "in", "into", "is", "it", "its", "itself", "just", "me", "more", "most", "my", "myself", "no", "nor",
+        "not", "now", "of", "off", "on", "once", "only", "or", "other", "our", "ours", "ourselves", "out",
+        "over", "own", "same", "she", "should", "so", "some", "such", "than", "that", "the", "their",
+        "theirs", "them", "themselves", "then", "there", "these", "they", "this", "those", "through", "to",
+        "too", "under", "until", "up", "very", "was", "we", "were", "what", "when", "where", "which",
+        "while", "who", "whom", "why", "will", "with", "would", "you", "your", "yours", "yourself",
+        "yourselves", "s", "t", "d", "ll", "m", "re", "ve", "don", "didn", "doesn", "isn", "wasn", "won",
+    ],
+    syllables: Syllables::English,
+    adverb_suffixes: &["ly"],
+    not_adverbs: &[
+        "ally", "anomaly", "apply", "assembly", "belly", "beverly", "billy", "bully", "butterfly",
+        "chilly", "comply", "costly", "cowardly", "curly", "daily", "deadly", "dolly", "dragonfly", "early", "elderly",
+        "emily", "family", "firefly", "folly", "friendly", "ghastly", "ghostly", "gully", "heavenly", "hilly", "holly",
+        "holy", "homely", "hourly", "imply", "italy", "jelly", "jolly", "july", "kelly", "likely", "lily", "lively",
+        "lonely", "lovely", "lowly", "melancholy", "molly", "monopoly", "monthly", "multiply", "nightly", "oily", "only",
+        "orderly", "polly", "rally", "rely", "reply", "sally", "scholarly", "shelly", "sicily", "silly", "stately",
+        "supply", "surly", "tally", "timely", "ugly", "unlikely", "weekly", "wily", "worldly", "yearly",
+    ],
+    reading_ease: ReadingEase::Flesch,
+};
+
+pub const SPANISH: Language = Language {
+    code: "es",
+    stopwords: &[
+        "a", "al", "algo", "algunos", "ante", "antes", "aquel", "aquella", "aquellos", "aquí", "así", "aunque",
+        "cada", "como", "con", "contra", "cual", "cuando", "de", "del", "desde", "donde", "dos", "durante", "e",
+        "el", "él", "ella", "ellas", "ellos", "en", "entre", "era", "eran", "es", "esa", "ese", "eso", "esta",
+        "está", "están", "este", "esto", "estos", "fue", "fueron", "ha", "había", "han", "hasta", "hay", "la",
+        "las", "le", "les", "lo", "los", "más", "me", "mi", "mis", "mucho", "muy", "nada", "ni", "no", "nos",
+        "nosotros", "o", "otro", "otros", "para", "pero", "poco", "por", "porque", "que", "qué", "quien", "se",
+        "sea", "ser", "si", "sí", "sin", "sobre", "son", "su", "sus", "también", "tan", "te", "tiene", "todo",
+        "todos", "tu", "tú", "tus", "un", "una", "unas", "uno", "unos", "y", "ya", "yo",
+    ],
+    syllables: Syllables::VowelGroups(VowelGroups {
+        vowels: "aeiouyáéíóúü",
+        // Two strong vowels, or a stressed weak one, are in hiatus
+        splits: &[
+            "aa", "ae", "ao", "ea", "ee", "eo", "oa", "oe", "oo", "aí", "aú", "eí", "eú", "oí", "oú", "ía", "íe",
+            "ío", "úa", "úe", "úo", "áe", "áo", "éa", "éo", "óa", "óe",
+        ],
+        // "que", "guerra"; "ü" is sounded ("pingüino")
+        silent_u_after: "qg",
+    }),
+    adverb_suffixes: &["mente"],
+    // "-mente" nouns and adjectives, and subjunctives of "-mentar" verbs
+    not_adverbs: &[
+        "alimente", "argumente", "atormente", "aumente", "clemente", "comente", "complemente", "demente",
+        "documente", "experimente", "fomente", "fragmente", "implemente", "incremente", "lamente", "mente",
+        "vehemente",
+    ],
+    reading_ease: ReadingEase::FleschSzigriszt,
+};
+
+pub const GERMAN: Language = Language {
+    code: "de",
+    stopwords: &[
+        "aber", "alle", "als", "also", "am", "an", "auch", "auf", "aus", "bei", "bin", "bis", "bist", "da",
+        "damit", "dann", "das", "dass", "dein", "dem", "den", "denn", "der", "des", "die", "dies", "diese",
+        "dieser", "doch", "du", "durch", "ein", "eine", "einem", "einen", "einer", "er", "es", "euch", "für",
+        "hat", "hatte", "ich", "ihm", "ihn", "ihr", "im", "in", "ist", "ja", "kann", "kein", "man", "mein",
+        "mich", "mir", "mit", "nach", "nicht", "noch", "nun", "nur", "ob", "oder", "ohne", "sein", "sich",
+        "sie", "sind", "so", "um", "und", "uns", "unter", "von", "vor", "war", "waren", "was", "weil", "wenn",
+        "wer", "wie", "wir", "wird", "wo", "zu", "zum", "zur",
+    ],
+    syllables: Syllables::VowelGroups(VowelGroups {
+        vowels: "aeiouyäöü",
+        // "Theater", "Nation", "Duell", "Feuer"
+        splits: &["ea", "eo", "ia", "io", "iu", "oa", "ue"],
+        // "Quelle"
+        silent_u_after: "q",
+    }),
+    adverb_suffixes: &["weise"],
+    // "Weise" and nouns ending in "-weise"
+    not_adverbs: &["anweise", "beweise", "hinweise", "nachweise", "verweise", "wegweise", "weise"],
+    reading_ease: ReadingEase::Amstad,
+};
+
+#[cfg(test)]
+mod tests {
+    use crate::TextProcessor;
+    use crate::suggestions::SCOPE_INSTANCE;
+
+    const SPANISH_PASSAGE: &str = "El sol salía lentamente sobre las montañas. Los niños caminaban felizmente hacia la escuela. \
+        Su madre los miraba desde la ventana de la casa. El pueblo era pequeño y tranquilo, y todos se conocían. \
+        Cada mañana, el panadero abría su tienda muy temprano.";
+
+    #[test]
+    fn spanish_readability_uses_szigriszt() {
+        let result = TextProcessor::with_language("es").analyze_text(SPANISH_PASSAGE);
+        let metrics = &result.complexity_metrics;
+        let expected = 206.835 - metrics.avg_words_per_sentence - 62.3 * metrics.avg_syllables_per_word;
+        assert!((metrics.flesch_reading_ease - expected).abs() < 1e-9);
+        // Simple narrative prose reads as "normal" to "fairly easy"
+        assert!((55.0..=90.0).contains(&metrics.flesch_reading_ease), "{}", metrics.flesch_reading_ease);
+        assert!((1.7..=2.3).contains(&metrics.avg_syllables_per_word), "{}", metrics.avg_syllables_per_word);
+    }
+
+    #[test]
+    fn spanish_adverbs_are_mente_words() {
+        let suggestions = TextProcessor::with_language("es").optimize_text(SPANISH_PASSAGE);
+        let adverbs: Vec<&str> = suggestions
+            .iter()
+            .filter(|s| s.suggestion_type == "adverb_usage" && s.scope == SCOPE_INSTANCE)
+            .map(|s| &SPANISH_PASSAGE[s.start_pos..s.end_pos])
+            .collect();
+        assert_eq!(adverbs, ["lentamente", "felizmente"]);
+    }
+
+    #[test]
+    fn unknown_language_falls_back_to_english() {
+        let text = "She really read it very slowly.";
+        let english = TextProcessor::new().analyze_text(text);
+        let unknown = TextProcessor::with_language("xx").analyze_text(text);
+        assert_eq!(unknown.complexity_metrics.flesch_reading_ease, english.complexity_metrics.flesch_reading_ease);
+        assert_eq!(unknown.complexity_metrics.content_word_count, english.complexity_metrics.content_word_count);
+    }
+
+    #[test]
+    fn auto_language_records_the_dominant_language() {
+        let mixed = format!("{}\n\nThe end came quickly.", SPANISH_PASSAGE);
+        let result = TextProcessor::with_language("auto").analyze_text(&mixed);
+        assert_eq!(result.detected_language.as_deref(), Some("es"));
+        let spanish = TextProcessor::with_language("es").analyze_text(&mixed);
+        assert_eq!(result.complexity_metrics.flesch_reading_ease, spanish.complexity_metrics.flesch_reading_ease);
+        assert!(TextProcessor::new().analyze_text(&mixed).detected_language.is_none());
+    }
+}
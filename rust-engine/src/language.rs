@@ -0,0 +1,416 @@
+//! Language detection from function-word frequencies: for whole documents,
+//! and per paragraph to keep English-specific rules off passages written in
+//! another language.
+
+use serde::{Deserialize, Serialize};
+
+use crate::capabilities;
+use crate::segmentation::DocumentIndex;
+use crate::warnings::AnalysisWarning;
+use crate::OptimizationSuggestion;
+
+/// The most frequent function words of each detectable language. Some are
+/// shared between languages ("a", "de", "que").
+const FUNCTION_WORDS: &[(&str, &[&str])] = &[
+    ("en", &[
+        "the", "and", "of", "to", "a", "in", "is", "it", "that", "was", "he", "she", "for", "on", "with",
+        "as", "his", "her", "they", "at", "be", "this", "from", "had", "not", "but", "what", "were", "we",
+        "you", "i", "my", "would", "there", "their", "been", "have", "said", "him", "me", "could", "when",
+        "which", "out", "into", "then", "them", "an", "or", "by",
+    ]),
+    ("es", &[
+        "el", "la", "de", "que", "y", "en", "los", "las", "un", "una", "del", "se", "por", "con", "no",
+        "su", "para", "es", "al", "lo", "como", "más", "pero", "sus", "le", "ya", "o", "fue", "este", "ha",
+        "sí", "porque", "esta", "muy", "yo", "tú", "está", "estaba", "dijo", "ella", "él", "mi", "me", "te",
+        "nos", "cuando", "donde", "aquí", "todo", "toda", "eso", "nada", "bien", "hay", "pues",
+    ]),
+    ("fr", &[
+        "le", "la", "les", "de", "des", "du", "et", "un", "une", "est", "que", "qui", "dans", "pour", "pas",
+        "sur", "au", "aux", "ne", "il", "elle", "je", "nous", "vous", "avec", "ce", "mais", "son", "sa",
+        "ses", "était", "sont", "ou", "comme", "dit", "en", "se", "lui", "moi", "toi", "mon", "ma", "mes",
+        "tout", "très", "bien", "ça", "où", "quand", "avait",
+    ]),
+    ("de", &[
+        "der", "die", "das", "und", "ist", "nicht", "ein", "eine", "zu", "den", "dem", "mit", "sich", "des",
+        "auf", "für", "im", "von", "es", "ich", "sie", "er", "wir", "auch", "war", "aber", "wie", "noch",
+        "nach", "wenn", "dass", "aus", "sagte", "so", "nur", "mir", "mich", "dich", "ihr", "ihm", "hat",
+        "habe", "bin", "schon", "doch", "hier",
+    ]),
+    ("it", &[
+        "il", "la", "di", "che", "e", "un", "una", "per", "non", "del", "della", "sono", "è", "gli", "le",
+        "con", "da", "si", "nel", "ma", "come", "anche", "lo", "io", "lui", "lei", "questo", "era", "disse",
+        "mi", "ti", "ci", "sei", "ho", "ha", "molto", "quando", "dove", "tutto", "perché", "cosa", "qui",
+    ]),
+    ("pt", &[
+        "o", "a", "os", "as", "de", "que", "e", "do", "da", "dos", "das", "em", "um", "uma", "para", "com",
+        "não", "no", "na", "se", "por", "mais", "mas", "como", "foi", "ele", "ela", "eu", "você", "está",
+        "disse", "muito", "quando", "onde", "aqui", "tudo", "isso", "nada", "bem", "também", "seu", "sua",
+    ]),
+];
+
+pub const DETECTABLE_LANGUAGES: &[&str] = &["en", "es", "fr", "de", "it", "pt"];
+
+/// Paragraphs shorter than this inherit the language of their neighbours.
+const MIN_WORDS_FOR_DETECTION: usize = 8;
+/// Share of a paragraph's words that must be function words of the winning
+/// language before its guess is trusted fully.
+const FULL_CONFIDENCE_COVERAGE: f64 = 0.25;
+/// Below this confidence a paragraph is never treated as foreign.
+const MIN_FOREIGN_CONFIDENCE: f64 = 0.4;
+/// Documents shorter than this get scaled-down, low-confidence guesses.
+const MIN_WORDS_FOR_CONFIDENCE: usize = 20;
+const MAX_CANDIDATES: usize = 3;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LanguageCandidate {
+    pub language: String,
+    /// 0–1.
+    pub confidence: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LanguageDetection {
+    /// Most likely first, at most three. In a mixed-language document the
+    /// language with the most function words leads. Empty when no function
+    /// word of any language was found.
+    pub candidates: Vec<LanguageCandidate>,
+    pub word_count: usize,
+    /// Fewer than 20 words: confidences are scaled down, and the top
+    /// candidate is a guess.
+    pub low_confidence: bool,
+}
+
+impl LanguageDetection {
+    pub fn language(&self) -> Option<&str> {
+        self.candidates.first().map(|c| c.language.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ParagraphLanguage {
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub word_count: usize,
+    pub language: String,
+    /// 0–1.
+    pub confidence: f64,
+    /// Too short to detect; language and confidence come from a neighbour.
+    pub inherited: bool,
+    /// Confidently in a language other than the document's.
+    pub foreign: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LanguageMap {
+    /// The configured language the English-specific rules assume.
+    pub document_language: String,
+    pub paragraphs: Vec<ParagraphLanguage>,
+    pub foreign_paragraph_count: usize,
+    pub foreign_word_count: usize,
+    /// Foreign words as a share of all words, 0–1.
+    pub foreign_word_share: f64,
+}
+
+/// The languages whose function-word list contains the lowercase `word`,
+/// one bit per `FUNCTION_WORDS` entry.
+pub fn function_word_languages(word: &str) -> u8 {
+    FUNCTION_WORDS.iter().enumerate().filter(|(_, (_, list))| list.contains(&word)).fold(0, |bits, (i, _)| bits | 1 << i)
+}
+
+/// `function_word_languages` of every word of `index`, in order.
+pub fn word_languages(index: &DocumentIndex) -> Vec<u8> {
+    index.word_strs().into_iter().map(|word| function_word_languages(&word.to_lowercase())).collect()
+}
+
+/// Function-word score of each language for a run of words, given their
+/// `function_word_languages`, highest first. A function word shared by
+/// several languages is split evenly between them.
+fn scores(words: &[u8]) -> Vec<(&'static str, f64)> {
+    let mut scores: Vec<(&'static str, f64)> = FUNCTION_WORDS.iter().map(|(language, _)| (*language, 0.0)).collect();
+    for &languages in words {
+        let shared = languages.count_ones();
+        for (i, score) in scores.iter_mut().enumerate() {
+            if languages & 1 << i != 0 {
+                score.1 += 1.0 / shared as f64;
+            }
+        }
+    }
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores
+}
+
+/// Best language for a run of words, given their `function_word_languages`,
+/// and the confidence in it.
+fn detect(words: &[u8]) -> Option<(&'static str, f64)> {
+    let scores = scores(words);
+    let (language, best) = scores[0];
+    if best == 0.0 {
+        return None;
+    }
+    let margin = (best - scores[1].1) / best;
+    let coverage = (best / words.len() as f64 / FULL_CONFIDENCE_COVERAGE).min(1.0);
+    Some((language, margin * coverage))
+}
+
+/// Candidate languages for the whole of `index`. A candidate's confidence
+/// is its share of the function words, scaled down when few of the words
+/// are function words of any language or the text is short.
+pub fn detect_document(index: &DocumentIndex) -> LanguageDetection {
+    let words = word_languages(index);
+    let scores = scores(&words);
+    let total: f64 = scores.iter().map(|s| s.1).sum();
+    let coverage = if words.is_empty() { 0.0 } else { (total / words.len() as f64 / FULL_CONFIDENCE_COVERAGE).min(1.0) };
+    let length = (words.len() as f64 / MIN_WORDS_FOR_CONFIDENCE as f64).min(1.0);
+    let candidates = scores
+        .into_iter()
+        .filter(|s| s.1 > 0.0)
+        .take(MAX_CANDIDATES)
+        .map(|(language, score)| LanguageCandidate { language: language.to_string(), confidence: score / total * coverage * length })
+        .collect();
+    LanguageDetection { candidates, word_count: words.len(), low_confidence: words.len() < MIN_WORDS_FOR_CONFIDENCE }
+}
+
+/// Words of the paragraph at `start..end`, and its language and confidence
+/// when it is long enough to tell. `languages` is `word_languages(index)`.
+fn paragraph_guess(index: &DocumentIndex, languages: &[u8], start: usize, end: usize) -> (usize, Option<(&'static str, f64)>) {
+    let words = &languages[index.word_range(start, end)];
+    let guess = if words.len() >= MIN_WORDS_FOR_DETECTION { detect(words) } else { None };
+    (words.len(), guess)
+}
+
+fn is_foreign(language: &str, confidence: f64, document_language: &str) -> bool {
+    language != document_language && confidence >= MIN_FOREIGN_CONFIDENCE
+}
+
+pub fn language_map(index: &DocumentIndex, document_language: &str) -> LanguageMap {
+    let languages = word_languages(index);
+    let detected: Vec<(usize, Option<(&'static str, f64)>)> =
+        index.paragraphs.iter().map(|&(start, end)| paragraph_guess(index, &languages, start, end)).collect();
+
+    let mut paragraphs = Vec::with_capacity(detected.len());
+    for (i, (&(start, end), &(word_count, guess))) in index.paragraphs.iter().zip(&detected).enumerate() {
+        // Short or undetectable paragraphs take the previous paragraph's
+        // language, or the next one's at the start of the text
+        let inherited = guess.is_none();
+        let (language, confidence) = guess
+            .or_else(|| detected[..i].iter().rev().find_map(|d| d.1))
+            .or_else(|| detected[i + 1..].iter().find_map(|d| d.1))
+            .map_or((document_language.to_string(), 0.0), |(l, c)| (l.to_string(), c));
+        let foreign = is_foreign(&language, confidence, document_language);
+        paragraphs.push(ParagraphLanguage { start_pos: start, end_pos: end, word_count, language, confidence, inherited, foreign });
+    }
+
+    let foreign: Vec<&ParagraphLanguage> = paragraphs.iter().filter(|p| p.foreign).collect();
+    let foreign_word_count = foreign.iter().map(|p| p.word_count).sum();
+    LanguageMap {
+        document_language: document_language.to_string(),
+        foreign_paragraph_count: foreign.len(),
+        foreign_word_count,
+        foreign_word_share: if index.words.is_empty() { 0.0 } else { foreign_word_count as f64 / index.words.len() as f64 },
+        paragraphs,
+    }
+}
+
+impl LanguageMap {
+    fn is_foreign_at(&self, offset: usize) -> bool {
+        let i = self.paragraphs.partition_point(|p| p.end_pos <= offset);
+        self.paragraphs.get(i).is_some_and(|p| p.foreign && p.start_pos <= offset)
+    }
+
+    /// Drops suggestions from language-specific rules that start inside a
+    /// foreign paragraph. Returns how many were dropped.
+    pub fn exclude_foreign(&self, suggestions: &mut Vec<OptimizationSuggestion>) -> usize {
+        if self.foreign_paragraph_count == 0 {
+            return 0;
+        }
+        let before = suggestions.len();
+        suggestions.retain(|s| !(capabilities::rule_is_language_specific(&s.suggestion_type) && self.is_foreign_at(s.start_pos)));
+        before - suggestions.len()
+    }
+
+    pub fn warning(&self) -> Option<AnalysisWarning> {
+        let languages = self.paragraphs.iter().filter(|p| p.foreign).map(|p| p.language.as_str()).collect();
+        foreign_warning(self.foreign_paragraph_count, self.foreign_word_count, self.foreign_word_share, languages, &self.document_language)
+    }
+}
+
+fn foreign_warning(
+    paragraphs: usize,
+    words: usize,
+    share: f64,
+    mut languages: Vec<&str>,
+    document_language: &str,
+) -> Option<AnalysisWarning> {
+    if paragraphs == 0 {
+        return None;
+    }
+    languages.sort_unstable();
+    languages.dedup();
+    Some(AnalysisWarning::new(
+        "foreign_language_excluded",
+        format!(
+            "{} paragraph(s) ({} words, {:.1}% of the text) detected as {} were excluded from {}-specific style rules; their words still count toward the metrics.",
+            paragraphs,
+            words,
+            share * 100.0,
+            languages.join(", "),
+            document_language,
+        ),
+    ))
+}
+
+/// The foreign paragraphs `language_map` would find, counted a run of
+/// paragraphs at a time for `LanguageMap::warning` without keeping the map.
+pub struct ForeignParagraphs {
+    document_language: String,
+    /// Latest paragraph language guessed, which the short paragraphs after
+    /// it inherit.
+    last: Option<(&'static str, f64)>,
+    /// Paragraphs before the first guess and their words, which inherit it.
+    leading: (usize, usize),
+    paragraphs: usize,
+    words: usize,
+    languages: Vec<&'static str>,
+}
+
+impl ForeignParagraphs {
+    pub fn new(document_language: &str) -> ForeignParagraphs {
+        ForeignParagraphs {
+            document_language: document_language.to_string(),
+            last: None,
+            leading: (0, 0),
+            paragraphs: 0,
+            words: 0,
+            languages: Vec::new(),
+        }
+    }
+
+    fn count(&mut self, (language, confidence): (&'static str, f64), paragraphs: usize, words: usize) {
+        if paragraphs > 0 && is_foreign(language, confidence, &self.document_language) {
+            self.paragraphs += paragraphs;
+            self.words += words;
+            self.languages.push(language);
+        }
+    }
+
+    /// `languages` is `word_languages(index)`.
+    pub fn add(&mut self, index: &DocumentIndex, languages: &[u8]) {
+        for &(start, end) in &index.paragraphs {
+            let (words, guess) = paragraph_guess(index, languages, start, end);
+            match (guess, self.last) {
+                (Some(language), None) => {
+                    let (paragraphs, leading_words) = std::mem::take(&mut self.leading);
+                    self.count(language, paragraphs, leading_words);
+                    self.count(language, 1, words);
+                }
+                (Some(language), Some(_)) | (None, Some(language)) => self.count(language, 1, words),
+                (None, None) => self.leading = (self.leading.0 + 1, self.leading.1 + words),
+            }
+            self.last = guess.or(self.last);
+        }
+    }
+
+    /// `total_words` is the word count of the whole text.
+    pub fn warning(&self, total_words: usize) -> Option<AnalysisWarning> {
+        let share = if total_words == 0 { 0.0 } else { self.words as f64 / total_words as f64 };
+        foreign_warning(self.paragraphs, self.words, share, self.languages.clone(), &self.document_language)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SuggestionConfig;
+    use crate::TextProcessor;
+
+    const MANUSCRIPT: &str = "Mara walked quickly across the field and she was tired when the storm came in from the hills.\n\n\
+        Then silence.\n\n\
+        Emily dijo que no había nada en la casa, pero ella estaba muy cansada y se fue a la cama, totally.\n\n\
+        The letter was opened by her brother in the morning, and they read it slowly at the kitchen table.\n\n\
+        Lily no sabía qué hacer con la carta, y por eso la guardó en el cajón de su madre, finally.";
+
+    fn detected(text: &str) -> LanguageMap {
+        language_map(&TextProcessor::new().index(text), "en")
+    }
+
+    #[test]
+    fn spanish_paragraphs_are_detected_in_an_english_manuscript() {
+        let map = detected(MANUSCRIPT);
+        let languages: Vec<(&str, bool, bool)> = map.paragraphs.iter().map(|p| (p.language.as_str(), p.inherited, p.foreign)).collect();
+        assert_eq!(languages, [("en", false, false), ("en", true, false), ("es", false, true), ("en", false, false), ("es", false, true)]);
+        assert_eq!(map.foreign_paragraph_count, 2);
+        assert_eq!(map.foreign_word_count, map.paragraphs[2].word_count + map.paragraphs[4].word_count);
+        assert!(map.paragraphs.iter().filter(|p| !p.inherited).all(|p| p.confidence >= MIN_FOREIGN_CONFIDENCE));
+        let warning = map.warning().unwrap();
+        assert_eq!(warning.code, "foreign_language_excluded");
+        assert!(warning.message.contains("2 paragraph(s)") && warning.message.contains("detected as es"));
+    }
+
+    #[test]
+    fn short_paragraphs_inherit_from_their_neighbours() {
+        let map = detected("Oye.\n\nEmily dijo que no había nada en la casa, pero ella estaba muy cansada y se fue a la cama.");
+        assert_eq!((map.paragraphs[0].language.as_str(), map.paragraphs[0].inherited), ("es", true));
+        assert_eq!(map.paragraphs[0].confidence, map.paragraphs[1].confidence);
+        let english_only = detected("Hello there.");
+        assert_eq!((english_only.paragraphs[0].language.as_str(), english_only.foreign_paragraph_count), ("en", 0));
+        assert!(english_only.warning().is_none());
+    }
+
+    #[test]
+    fn english_rules_are_kept_off_foreign_paragraphs_but_words_still_count() {
+        let processor = TextProcessor::with_patterns(&Default::default(), SuggestionConfig::default());
+        let report = processor.build_optimization_report(MANUSCRIPT);
+        let adverbs: Vec<&str> = report
+            .suggestions
+            .iter()
+            .filter(|s| s.suggestion_type == "adverb_usage" && s.scope == "instance")
+            .map(|s| &MANUSCRIPT[s.start_pos..s.end_pos])
+            .collect();
+        assert_eq!(adverbs, ["quickly", "slowly"]);
+        // Every English rule stays out of the Spanish paragraphs, typos included
+        let foreign: Vec<(usize, usize)> =
+            detected(MANUSCRIPT).paragraphs.iter().filter(|p| p.foreign).map(|p| (p.start_pos, p.end_pos)).collect();
+        assert!(report
+            .suggestions
+            .iter()
+            .filter(|s| s.scope == "instance")
+            .all(|s| !foreign.iter().any(|&(start, end)| start <= s.start_pos && s.end_pos <= end)));
+        assert!(report.suppressed_by_language > 2);
+
+        let analysis = processor.perform_analysis(MANUSCRIPT);
+        assert_eq!(analysis.word_count, processor.index(MANUSCRIPT).words.len());
+        assert!(analysis.warnings.iter().any(|w| w.code == "foreign_language_excluded"));
+
+        let config = SuggestionConfig { detect_paragraph_language: false, ..Default::default() };
+        let report = TextProcessor::with_patterns(&Default::default(), config).build_optimization_report(MANUSCRIPT);
+        assert_eq!(report.suppressed_by_language, 0);
+        assert!(report.suggestions.iter().any(|s| &MANUSCRIPT[s.start_pos..s.end_pos] == "totally"));
+    }
+
+    #[test]
+    fn detect_language_recognizes_each_bundled_language() {
+        let paragraphs = [
+            ("en", "The old man walked down to the harbor in the morning. He had not been there for years, and the boats were smaller than he remembered, but the smell of the sea was the same."),
+            ("es", "El viejo bajó al puerto por la mañana. No había estado allí en muchos años, y los barcos eran más pequeños de lo que recordaba, pero el olor del mar era el mismo."),
+            ("fr", "Le vieil homme est descendu au port le matin. Il n'y était pas allé depuis des années, et les bateaux étaient plus petits que dans son souvenir, mais l'odeur de la mer était la même."),
+            ("de", "Der alte Mann ging am Morgen zum Hafen hinunter. Er war seit Jahren nicht mehr dort gewesen, und die Boote waren kleiner, als er sie in Erinnerung hatte, aber der Geruch des Meeres war noch derselbe."),
+            ("it", "Il vecchio scese al porto di mattina. Non ci era stato da molti anni, e le barche erano più piccole di come le ricordava, ma l'odore del mare era sempre lo stesso e anche il vento."),
+            ("pt", "O velho desceu ao porto de manhã. Não tinha estado lá há muitos anos, e os barcos eram mais pequenos do que ele se lembrava, mas o cheiro do mar era o mesmo e isso foi muito bom."),
+        ];
+        let processor = TextProcessor::new();
+        for (expected, text) in paragraphs {
+            let detection = processor.detect_language(text);
+            assert_eq!(detection.language(), Some(expected), "{}", text);
+            assert!(!detection.low_confidence);
+            assert!(detection.candidates[0].confidence >= 0.5, "{}: {}", expected, detection.candidates[0].confidence);
+        }
+    }
+
+    #[test]
+    fn detect_language_is_unsure_of_two_words() {
+        let detection = TextProcessor::new().detect_language("de la");
+        assert!(detection.low_confidence);
+        assert!(detection.candidates.len() > 1);
+        assert!(detection.candidates.iter().all(|c| c.confidence < 0.2));
+    }
+}
@@ -0,0 +1,98 @@
+//! Stopword lists and the shared notion of a "content word" used by every
+//! lexical statistic.
+
+use std::collections::HashSet;
+
+use crate::config::SuggestionConfig;
+use crate::lang;
+
+/// Built-in stopword list for a language code; unknown languages get
+/// English.
+pub fn builtin_stopwords(language: &str) -> &'static [&'static str] {
+    lang::resources(language).stopwords
+}
+
+/// The active stopword set: the configured list for the configured language
+/// (or the built-in one), plus any extra stopwords.
+pub struct Stopwords {
+    words: HashSet<String>,
+}
+
+impl Stopwords {
+    pub fn from_config(config: &SuggestionConfig) -> Stopwords {
+        Stopwords::for_language(config, &config.language)
+    }
+
+    /// The set `from_config` would build with the language set to `language`.
+    pub fn for_language(config: &SuggestionConfig, language: &str) -> Stopwords {
+        let mut words: HashSet<String> = match config.stopwords.get(language) {
+            Some(custom) => custom.iter().map(|w| w.to_lowercase()).collect(),
+            None => builtin_stopwords(language).iter().map(|w| w.to_string()).collect(),
+        };
+        words.extend(config.extra_stopwords.iter().map(|w| w.to_lowercase()));
+        Stopwords { words }
+    }
+
+    /// A content word contains a letter and is not a stopword. `word` is
+    /// compared case-insensitively, and a contraction is a stopword when the
+    /// word it contracts is ("don't", "they're", "couldn't").
+    pub fn is_content_word(&self, word: &str) -> bool {
+        if !word.chars().any(char::is_alphabetic) {
+            return false;
+        }
+        let word = word.to_lowercase().replace('\u{2019}', "'");
+        let stems = [word.strip_suffix("n't"), word.split_once('\'').map(|(stem, _)| stem)];
+        !self.words.contains(&word) && !stems.into_iter().flatten().any(|stem| self.words.contains(stem))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    #[test]
+    fn content_words_exclude_stopwords_and_non_words() {
+        let processor = TextProcessor::new();
+        let stats = processor.perform_analysis("The storm and the STORM broke 1999 over it.").complexity_metrics;
+        assert_eq!(stats.content_word_count, 3);
+        assert_eq!(stats.unique_content_word_ratio, 2.0 / 3.0);
+        assert_eq!(stats.avg_content_word_length, 5.0);
+        assert_eq!(processor.perform_analysis("The of.").complexity_metrics.content_word_count, 0);
+    }
+
+    #[test]
+    fn stopword_list_follows_the_configured_language() {
+        let mut config = SuggestionConfig { language: "fr".to_string(), ..SuggestionConfig::default() };
+        // No bundled French list: unknown languages fall back to English
+        assert!(!Stopwords::from_config(&config).is_content_word("the"));
+
+        config.stopwords.insert("fr".to_string(), vec!["le".to_string(), "La".to_string()]);
+        config.extra_stopwords.push("Orage".to_string());
+        let stopwords = Stopwords::from_config(&config);
+        assert!(!stopwords.is_content_word("la") && !stopwords.is_content_word("orage"));
+        assert!(stopwords.is_content_word("pluie"));
+    }
+
+    #[test]
+    fn old_and_new_lexical_fields_serialize_side_by_side() {
+        let result = TextProcessor::new().perform_analysis("The storm and the storm broke over the farm.");
+        let metrics = serde_json::to_value(&result.complexity_metrics).unwrap();
+        assert_eq!(metrics["uniqueWordRatio"], 6.0 / 9.0);
+        assert_eq!(metrics["contentWordCount"], 4);
+        assert_eq!(metrics["uniqueContentWordRatio"], 0.75);
+        assert_eq!(metrics["avgContentWordLength"], 4.75);
+        assert_eq!(serde_json::to_value(&result).unwrap()["wordCount"], 9);
+    }
+
+    #[test]
+    fn contractions_of_stopwords_are_stopwords() {
+        let stopwords = Stopwords::from_config(&SuggestionConfig::default());
+        for contraction in ["don't", "They're", "couldn\u{2019}t", "it's", "I'll"] {
+            assert!(!stopwords.is_content_word(contraction), "{}", contraction);
+        }
+        for word in ["Mara's", "storm's", "ain't'nt"] {
+            assert!(stopwords.is_content_word(word), "{}", word);
+        }
+    }
+}
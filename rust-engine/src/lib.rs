@@ -1,296 +1,2445 @@
-use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
 use regex::Regex;
 use unicode_segmentation::UnicodeSegmentation;
 use sha2::{Sha256, Digest};
 use base64::{Engine as _, engine::general_purpose};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
 
-// Console logging macro for debugging
+// Console logging macro for debugging; builds without the JavaScript API go
+// through the `log` facade, so nothing is printed unless the host installs a
+// logger
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
 macro_rules! console_log {
     ($($t:tt)*) => (web_sys::console::log_1(&format!($($t)*).into()));
 }
 
-#[derive(Serialize, Deserialize)]
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+macro_rules! console_log {
+    ($($t:tt)*) => (log::debug!($($t)*));
+}
+
+mod acronyms;
+mod adverbs;
+mod attribution;
+mod batch;
+mod cache;
+mod capabilities;
+mod chunks;
+mod cliches;
+mod config;
+mod conflicts;
+mod consistency;
+mod context;
+mod corpus;
+mod delta;
+mod dialogue;
+mod diff;
+mod diversity;
+mod echoes;
+mod edits;
+mod entities;
+mod fillers;
+mod flagged;
+mod frequency;
+mod front_matter;
+mod gap;
+mod grouping;
+mod hashing;
+mod health;
+mod html;
+mod ignore;
+mod input;
+mod interned;
+mod json;
+mod lang;
+mod language;
+mod lexicon;
+mod manifest;
+mod markdown;
+mod merge;
+mod narrative;
+mod offsets;
+mod opaque;
+mod output;
+mod pacing;
+mod paragraphs;
+mod passive;
+mod patterns;
+mod persona;
+mod phrases;
+mod provenance;
+mod query;
+mod quick;
+mod readability;
+mod reconcile;
+mod reflow;
+mod report;
+mod repetition;
+mod resolution;
+mod revisions;
+mod rewrite;
+mod screening;
+mod segmentation;
+mod sentiment;
+mod session;
+mod similarity;
+mod spelling;
+mod stream;
+mod structural;
+mod structure;
+mod suggestions;
+mod syllables;
+#[cfg(test)]
+mod test_support;
+mod threeway;
+mod tokens;
+mod transform;
+mod typography;
+mod typings;
+mod verbs;
+mod warnings;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod wordcount;
+
+use cache::AnalysisCache;
+use chunks::ChunkMode;
+use cliches::Cliches;
+use config::InputFormat;
+use consistency::VariantGroups;
+use context::SuggestionContext;
+use fillers::FillerWords;
+use offsets::TextPositions;
+use ignore::IgnoreRanges;
+use interned::{Measured, WordTable};
+use input::PreparedText;
+use language::ForeignParagraphs;
+use lexicon::Stopwords;
+use manifest::ManifestEntry;
+use narrative::SentenceMode;
+use paragraphs::ParagraphCounts;
+use patterns::{PatternCompiler, PatternFailure};
+use reflow::OffsetMap;
+use readability::{GradeInputs, ReadabilityInputs};
+use segmentation::{DocumentIndex, IndexBuffers, WordSegmenter};
+use sentiment::SentimentLexicon;
+use session::{Segmenters, Session};
+use similarity::SimilarityMethod;
+use spelling::Dictionary;
+use stream::{Stream, Totals};
+use structure::HeadingPatterns;
+use suggestions::{RuleSummary, SCOPE_DOCUMENT, SCOPE_INSTANCE};
+use verbs::WeakVerbs;
+use warnings::AnalysisWarning;
+
+// Everything the native API takes or returns
+pub use acronyms::Acronym;
+pub use attribution::DialogueReport;
+pub use batch::{BatchAnalysis, BatchDocument, BatchSummary};
+pub use cache::CacheStats;
+pub use capabilities::{engine_info, EngineInfo};
+pub use chunks::{ChunkComparison, ChunkHashes};
+pub use config::{AnalysisOptions, Priority, SuggestionConfig};
+pub use conflicts::{validate_entries, ConflictResolutionResponse, RejectedConflict};
+pub use corpus::{CorpusComparison, ReferenceCorpus};
+pub use delta::{apply as apply_suggestion_diff, diff as diff_suggestions, SuggestionDelta};
+pub use entities::Entity;
+pub use flagged::FlaggedMatch;
+pub use frequency::WordFrequency;
+pub use front_matter::FrontMatter;
+pub use gap::{ComplexWord, ReadabilityGap};
+pub use grouping::SuggestionDigest;
+pub use hashing::HashVerification;
+pub use json::{from_json, to_json};
+pub use health::{history as health_history, HealthSnapshot, HealthTrend, DEFAULT_REGRESSION_THRESHOLD};
+pub use language::{LanguageCandidate, LanguageDetection, LanguageMap};
+pub use manifest::{ChapterManifest, ManifestDiff};
+pub use merge::{accept as accept_preview, apply as apply_resolutions_lenient, AppliedResolutions, ResolutionPreview};
+#[cfg(feature = "wasm")]
+pub use offsets::OffsetIndex;
+pub use offsets::{
+    char_ceil as char_boundary_ceil, char_floor as char_boundary_floor, slice as slice_text, to_byte as utf16_to_offset,
+    to_utf16 as offset_to_utf16, TextSlice,
+};
+pub use pacing::PacingPoint;
+pub use paragraphs::ParagraphAnalysis;
+pub use patterns::InitializationReport;
+pub use persona::PersonaScores;
+pub use phrases::RepeatedPhrase;
+pub use provenance::{MarkedRange, ProvenanceMap, ProvenanceRemap, ProvenanceReport};
+pub use query::{SuggestionPage, SuggestionQuery};
+pub use quick::{QuickBatch, QuickMetrics};
+pub use readability::{SentenceDifficulty, SentenceReadability};
+pub use reconcile::SpanReconciliation;
+pub use resolution::ConflictPolicy;
+pub use revisions::{SessionStats, TextDiffReport};
+pub use screening::{ContentScreen, ScreeningMatch};
+pub use segmentation::SentenceSpan;
+pub use sentiment::SentimentProfile;
+pub use session::TextEdit;
+pub use similarity::Fingerprint;
+pub use structure::OutlineEntry;
+pub use transform::PositionEdit;
+pub use suggestions::filter_dismissed;
+pub use suggestions::{from_compact as expand_compact_suggestions, to_compact as compact_suggestions, CompactSuggestions};
+pub use threeway::ThreeWayMerge;
+pub use tokens::TokenStream;
+pub use typography::TypographyOptions;
+#[cfg(feature = "wasm")]
+pub use wasm::JsTextProcessor;
+pub use wordcount::WordCountDetail;
+
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct TextAnalysisResult {
     pub word_count: usize,
+    /// Unicode scalar values; an emoji ZWJ sequence counts several.
     pub character_count: usize,
+    /// User-perceived characters (grapheme clusters).
+    #[serde(default)]
+    pub grapheme_count: usize,
     pub paragraph_count: usize,
     pub sentence_count: usize,
+    /// Whole seconds at `reading_words_per_minute`, with dialogue read faster
+    /// and a pause per paragraph break.
+    #[serde(default)]
+    pub estimated_reading_time_seconds: f64,
+    /// Whole seconds read aloud at `speaking_words_per_minute`.
+    #[serde(default)]
+    pub estimated_speaking_time_seconds: f64,
+    /// Flesch Reading Ease clamped to [0, 100]; see `readability_score_raw`.
     pub readability_score: f64,
+    #[serde(default)]
+    pub readability_score_raw: f64,
     pub complexity_metrics: ComplexityMetrics,
     pub style_metrics: StyleMetrics,
     pub content_hash: String,
+    // Only reported while the first/second-person rule is active
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_person_per_1000_words: Option<f64>,
+    #[serde(default)]
+    pub warnings: Vec<AnalysisWarning>,
+    /// Fewer words than `min_word_count`: metrics are unstable and
+    /// document-level suggestions are withheld.
+    #[serde(default)]
+    pub low_confidence: bool,
+    /// Rules whose suggestions the active profile does not surface; their
+    /// metrics (e.g. in `style_metrics`) are still computed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub not_surfaced: Vec<String>,
+    /// Average valence per word; see `sentiment_profile`.
+    #[serde(default)]
+    pub sentiment_score: f64,
+    /// With the language set to "auto", the language detected for the text,
+    /// whose resources were used; see `detect_language`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_language: Option<String>,
+    /// `word_count` over the `target_words` of the text's front matter; see
+    /// `parse_front_matter`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress_to_target: Option<f64>,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Lexical fields come in two views. The original fields count every word;
+/// the content-word fields drop stopwords for the configured language, and
+/// are the view the corpus keyword comparison uses.
+///
+/// | All words           | Content words only          |
+/// |---------------------|-----------------------------|
+/// | `word_count` (top)  | `content_word_count`        |
+/// | `unique_word_ratio` | `unique_content_word_ratio` |
+/// | —                   | `avg_content_word_length`   |
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct ComplexityMetrics {
     pub avg_words_per_sentence: f64,
     pub avg_syllables_per_word: f64,
     pub fog_index: f64,
     pub flesch_reading_ease: f64,
     pub unique_word_ratio: f64,
+    #[serde(default)]
+    pub content_word_count: usize,
+    #[serde(default)]
+    pub unique_content_word_ratio: f64,
+    #[serde(default)]
+    pub avg_content_word_length: f64,
+    #[serde(default)]
+    pub smog_index: f64,
+    #[serde(default)]
+    pub coleman_liau_index: f64,
+    #[serde(default)]
+    pub automated_readability_index: f64,
+    #[serde(default)]
+    pub lix: f64,
+    /// Spread of words per sentence; 0.0 below two sentences.
+    #[serde(default)]
+    pub sentence_length_std_dev: f64,
+    /// Lexical diversity as average words per run holding a type-token ratio
+    /// above 0.72; unlike `unique_word_ratio`, comparable across lengths.
+    #[serde(default)]
+    pub mtld: f64,
+    /// Type-token ratio averaged over windows of `mattr_window` words; the
+    /// plain ratio for shorter text.
+    #[serde(default)]
+    pub mattr: f64,
+    /// Share of measured words that are content words.
+    #[serde(default)]
+    pub lexical_density: f64,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct StyleMetrics {
     pub passive_voice_ratio: f64,
     pub adverb_ratio: f64,
+    /// Share of sentences that are mostly quoted speech.
     pub dialogue_ratio: f64,
+    /// Share of sentences dominated by dynamic verbs.
     pub action_ratio: f64,
+    /// Share of sentences dominated by adjectives, adverbs, and linking verbs.
     pub description_ratio: f64,
+    /// Share of sentences containing a filter word ("she felt", "he saw").
+    #[serde(default)]
+    pub filter_word_ratio: f64,
+    #[serde(default)]
+    pub cliches_per_1000_words: f64,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub struct OptimizationSuggestion {
-    pub suggestion_type: String,
-    pub priority: String,
-    pub message: String,
+    // Rule ids, priorities, and canonical messages are static strings; only
+    // genuinely dynamic messages allocate.
+    pub suggestion_type: Cow<'static, str>,
+    pub priority: Cow<'static, str>,
+    pub message: Cow<'static, str>,
     pub start_pos: usize,
     pub end_pos: usize,
     pub suggested_replacement: Option<String>,
+    #[serde(default = "default_scope")]
+    pub scope: Cow<'static, str>,
+    #[serde(default)]
+    pub fingerprint: String,
+    /// Other spans the suggestion refers to, e.g. both halves of an echo.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related_spans: Vec<RelatedSpan>,
+    /// Rule-specific strength in [0, 1], such as a similarity score.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// Surrounding excerpt, when context excerpts are enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<SuggestionContext>,
+    /// `start_pos` and `end_pos` in UTF-16 code units, for slicing the
+    /// JavaScript string directly.
+    #[serde(default)]
+    pub start_utf16: usize,
+    #[serde(default)]
+    pub end_utf16: usize,
+    /// Zero-based line and UTF-16 column of `start_pos`.
+    #[serde(default)]
+    pub line: usize,
+    #[serde(default)]
+    pub column: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RelatedSpan {
+    pub start_pos: usize,
+    pub end_pos: usize,
+}
+
+impl OptimizationSuggestion {
+    fn instance(
+        suggestion_type: impl Into<Cow<'static, str>>,
+        priority: &'static str,
+        message: impl Into<Cow<'static, str>>,
+        start_pos: usize,
+        end_pos: usize,
+    ) -> OptimizationSuggestion {
+        OptimizationSuggestion {
+            suggestion_type: suggestion_type.into(),
+            priority: priority.into(),
+            message: message.into(),
+            start_pos,
+            end_pos,
+            suggested_replacement: None,
+            scope: SCOPE_INSTANCE.into(),
+            fingerprint: String::new(),
+            related_spans: Vec::new(),
+            score: None,
+            context: None,
+            start_utf16: 0,
+            end_utf16: 0,
+            line: 0,
+            column: 0,
+        }
+    }
+
+    /// Fills in the UTF-16 and line/column fields from the byte offsets.
+    fn locate(&mut self, positions: &TextPositions) {
+        self.start_utf16 = positions.utf16(self.start_pos);
+        self.end_utf16 = positions.utf16(self.end_pos);
+        (self.line, self.column) = positions.line_column(self.start_pos);
+    }
+
+    /// A document-scope suggestion spanning the whole text.
+    fn document(
+        suggestion_type: impl Into<Cow<'static, str>>,
+        priority: &'static str,
+        message: impl Into<Cow<'static, str>>,
+        text_len: usize,
+    ) -> OptimizationSuggestion {
+        OptimizationSuggestion {
+            scope: SCOPE_DOCUMENT.into(),
+            ..OptimizationSuggestion::instance(suggestion_type, priority, message, 0, text_len)
+        }
+    }
+}
+
+/// Widens `suggestion` to the span a rewrite replaces and attaches it.
+fn with_rewrite(suggestion: OptimizationSuggestion, rewrite: Option<(usize, usize, String)>) -> OptimizationSuggestion {
+    match rewrite {
+        Some((start_pos, end_pos, replacement)) => {
+            OptimizationSuggestion { start_pos, end_pos, suggested_replacement: Some(replacement), ..suggestion }
+        }
+        None => suggestion,
+    }
+}
+
+fn default_scope() -> Cow<'static, str> {
+    Cow::Borrowed(SCOPE_INSTANCE)
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimizationReport {
+    pub suggestions: Vec<OptimizationSuggestion>,
+    pub rule_summaries: Vec<RuleSummary>,
+    pub suppressed_by_dialect: usize,
+    /// Language-specific suggestions dropped inside foreign-language paragraphs.
+    #[serde(default)]
+    pub suppressed_by_language: usize,
+    /// Fewer words than `min_word_count`; document-scope suggestions were dropped.
+    #[serde(default)]
+    pub low_confidence: bool,
+    /// Total bytes of context excerpts, reported when excerpts are enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_payload_bytes: Option<usize>,
 }
 
 #[derive(Serialize, Deserialize)]
+pub struct FullAnalysis {
+    pub analysis: TextAnalysisResult,
+    pub sentences: Vec<SentenceDifficulty>,
+}
+
+#[derive(Serialize)]
+pub struct RangeContext {
+    /// Indices of the document paragraphs the range overlaps.
+    pub paragraphs: Vec<usize>,
+    pub starts_mid_sentence: bool,
+    pub ends_mid_sentence: bool,
+}
+
+#[derive(Serialize)]
+pub struct RangeAnalysis {
+    pub analysis: TextAnalysisResult,
+    pub sentences: Vec<SentenceDifficulty>,
+    pub suggestions: Vec<OptimizationSuggestion>,
+    pub context: RangeContext,
+}
+
+/// One outline section and the analysis of its text alone.
+#[derive(Serialize)]
+pub struct SectionAnalysis {
+    pub title: Option<String>,
+    pub level: usize,
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub analysis: TextAnalysisResult,
+}
+
+/// Keys are camelCase; the snake_case field names are accepted on input.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct CollaborationConflict {
+    #[serde(alias = "conflict_id")]
     pub conflict_id: String,
+    #[serde(alias = "conflict_type")]
     pub conflict_type: String,
+    #[serde(alias = "start_pos")]
     pub start_pos: usize,
+    #[serde(alias = "end_pos")]
     pub end_pos: usize,
+    #[serde(alias = "user_a_change")]
     pub user_a_change: String,
+    #[serde(alias = "user_b_change")]
     pub user_b_change: String,
     pub timestamp: String,
+    /// When each user made their change (RFC 3339), compared by the
+    /// `prefer_latest` policy.
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "user_a_timestamp")]
+    pub user_a_timestamp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "user_b_timestamp")]
+    pub user_b_timestamp: Option<String>,
+    #[serde(default, alias = "resolution_suggestion")]
     pub resolution_suggestion: String,
+    /// The text the client believes occupies the span; enables span
+    /// reconciliation against the server's copy.
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "expected_text")]
+    pub expected_text: Option<String>,
+    /// `start_pos` and `end_pos` in UTF-16 code units, and the zero-based
+    /// line and UTF-16 column of `start_pos`; set when the engine has the
+    /// text the span refers to.
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "start_utf16")]
+    pub start_utf16: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "end_utf16")]
+    pub end_utf16: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+impl CollaborationConflict {
+    fn locate(&mut self, positions: &TextPositions) {
+        let (line, column) = positions.line_column(self.start_pos);
+        self.start_utf16 = Some(positions.utf16(self.start_pos));
+        self.end_utf16 = Some(positions.utf16(self.end_pos));
+        self.line = Some(line);
+        self.column = Some(column);
+    }
 }
 
-#[wasm_bindgen]
 pub struct TextProcessor {
-    word_patterns: Regex,
+    words: WordSegmenter,
     sentence_patterns: Regex,
     paragraph_patterns: Regex,
-    passive_voice_patterns: Regex,
-    adverb_patterns: Regex,
-    dialogue_patterns: Regex,
+    // Rule patterns are optional: a pattern that fails to compile disables
+    // its rules instead of aborting construction.
+    passive_voice_patterns: Option<Regex>,
+    adverb_patterns: Option<Regex>,
+    dialogue_patterns: Option<Regex>,
+    person_pronoun_patterns: Option<Regex>,
+    heading_patterns: HeadingPatterns,
+    stopwords: Stopwords,
+    fillers: FillerWords,
+    weak_verbs: WeakVerbs,
+    cliches: Cliches,
+    variant_groups: VariantGroups,
+    dictionary: Dictionary,
+    sentiment: SentimentLexicon,
+    pattern_failures: Vec<PatternFailure>,
+    config: SuggestionConfig,
+    /// Text retained by `begin_session` for incremental analysis.
+    session: Option<Session>,
+    /// Totals and unfinished tail of the text fed since
+    /// `start_stream_analysis`.
+    stream: Option<Stream>,
+    /// `analyze_text` results by content hash; cleared whenever the
+    /// configuration or patterns change.
+    cache: RefCell<AnalysisCache>,
+    /// Resources for the detected language while a text is analyzed with the
+    /// language set to "auto".
+    detected_language: Cell<Option<&'static lang::Language>>,
 }
 
-#[wasm_bindgen]
+
+/// The engine as plain Rust: text in, the serializable result types out,
+/// errors as messages. The `wasm` feature wraps each method for JavaScript
+/// under the same name.
 impl TextProcessor {
-    #[wasm_bindgen(constructor)]
     pub fn new() -> TextProcessor {
-        console_log!("Initializing Rust Text Processing Engine");
-        
-        TextProcessor {
-            word_patterns: Regex::new(r"\b\w+\b").unwrap(),
-            sentence_patterns: Regex::new(r"[.!?]+").unwrap(),
-            paragraph_patterns: Regex::new(r"\n\s*\n").unwrap(),
-            passive_voice_patterns: Regex::new(r"\b(was|were|been|being)\s+\w+ed\b").unwrap(),
-            adverb_patterns: Regex::new(r"\b\w+ly\b").unwrap(),
-            dialogue_patterns: Regex::new(r#""[^"]*""#).unwrap(),
+        TextProcessor::with_patterns(&BTreeMap::new(), SuggestionConfig::default())
+    }
+
+    pub fn with_config(config: SuggestionConfig) -> TextProcessor {
+        TextProcessor::with_patterns(&BTreeMap::new(), config)
+    }
+
+    /// A processor for text in `language` (e.g. "es", "de", or "auto"); see
+    /// `set_language`.
+    pub fn with_language(language: &str) -> TextProcessor {
+        TextProcessor::with_config(SuggestionConfig { language: language.to_string(), ..SuggestionConfig::default() })
+    }
+
+    /// A processor with the default configuration except for `analysis`
+    /// (rule thresholds and priorities).
+    pub fn with_options(analysis: AnalysisOptions) -> TextProcessor {
+        TextProcessor::with_config(SuggestionConfig { analysis, ..SuggestionConfig::default() })
+    }
+
+    /// Replaces the built-in patterns with `overrides` (pattern ids to regex
+    /// sources) and returns the initialization report. Patterns that fail to
+    /// compile are reported rather than returned as errors.
+    pub fn load_pattern_pack(&mut self, overrides: &BTreeMap<String, String>) -> InitializationReport {
+        let config = std::mem::take(&mut self.config);
+        let session_text = self.session.take().map(|s| s.text);
+        let cache_capacity = self.cache.get_mut().capacity();
+        *self = TextProcessor::with_patterns(overrides, config);
+        self.cache.get_mut().set_capacity(cache_capacity);
+        // A running session is re-tokenized with the new patterns
+        self.session = session_text.map(|text| Session::new(text, &self.segmenters()));
+        self.initialization_report()
+    }
+
+    pub fn initialization_report(&self) -> InitializationReport {
+        patterns::report(&self.pattern_failures)
+    }
+
+    pub fn set_suggestion_config(&mut self, config: SuggestionConfig) {
+        self.config = config;
+        self.stopwords = Stopwords::from_config(&self.config);
+        self.fillers = FillerWords::from_config(&self.config);
+        self.weak_verbs = WeakVerbs::from_config(&self.config);
+        self.cliches = Cliches::from_config(&self.config);
+        self.variant_groups = VariantGroups::from_config(&self.config);
+        self.dictionary = Dictionary::from_config(&self.config);
+        self.sentiment = SentimentLexicon::from_config(&self.config);
+        self.cache.get_mut().clear();
+    }
+
+    /// Switches stopwords, syllable rules, adverb endings, and the
+    /// reading-ease formula to those of `language`. Codes without their
+    /// own resources get English; "auto" detects the language of each text
+    /// `analyze_text` is given.
+    pub fn set_language(&mut self, language: &str) {
+        self.config.language = language.to_string();
+        self.stopwords = Stopwords::from_config(&self.config);
+        self.cache.get_mut().clear();
+    }
+
+    /// Replaces the sentiment lexicon (words to valences, about -5 to 5),
+    /// e.g. with one for another language.
+    pub fn load_sentiment_lexicon(&mut self, lexicon: BTreeMap<String, f64>) {
+        self.config.sentiment_lexicon = Some(lexicon);
+        self.sentiment = SentimentLexicon::from_config(&self.config);
+        self.cache.get_mut().clear();
+    }
+
+    /// Adds genre-specific crutch words (phrases are allowed) to the
+    /// `filler_word` rule.
+    pub fn add_filler_words(&mut self, words: Vec<String>) {
+        self.config.extra_filler_words.extend(words);
+        self.fillers = FillerWords::from_config(&self.config);
+        self.cache.get_mut().clear();
+    }
+
+    /// Adds phrases to the `cliche` rule.
+    pub fn add_cliches(&mut self, phrases: Vec<String>) {
+        self.config.extra_cliches.extend(phrases);
+        self.cliches = Cliches::from_config(&self.config);
+        self.cache.get_mut().clear();
+    }
+
+    /// Adds "to be" + adjective phrases (e.g. `"cognizant of"` to `"know"`)
+    /// to the `weak_verb` rule.
+    pub fn add_weak_verbs(&mut self, phrases: BTreeMap<String, String>) {
+        self.config.extra_weak_verbs.extend(phrases);
+        self.weak_verbs = WeakVerbs::from_config(&self.config);
+        self.cache.get_mut().clear();
+    }
+
+    /// Adds light verb + noun phrases (e.g. `"make a purchase"` to `"buy"`)
+    /// to the `nominalization` rule.
+    pub fn add_nominalizations(&mut self, phrases: BTreeMap<String, String>) {
+        self.config.extra_nominalizations.extend(phrases);
+        self.weak_verbs = WeakVerbs::from_config(&self.config);
+        self.cache.get_mut().clear();
+    }
+
+    /// Adds groups of spellings to keep consistent (e.g. `["Caitlin",
+    /// "Kaitlyn"]`) to the `consistency` rule.
+    pub fn add_variant_groups(&mut self, groups: Vec<Vec<String>>) {
+        self.config.variant_groups.extend(groups);
+        self.variant_groups = VariantGroups::from_config(&self.config);
+        self.cache.get_mut().clear();
+    }
+
+    /// Adds words (names, invented terms) to the `possible_typo` rule's
+    /// dictionary; they are no longer flagged and may be offered as fixes.
+    pub fn add_dictionary_words(&mut self, words: Vec<String>) {
+        self.config.dictionary_words.extend(words);
+        self.dictionary = Dictionary::from_config(&self.config);
+        self.cache.get_mut().clear();
+    }
+
+    /// Stops the `possible_typo` rule flagging `word`, without offering it as
+    /// a fix.
+    pub fn ignore_word(&mut self, word: &str) {
+        self.config.ignored_words.push(word.to_string());
+        self.dictionary = Dictionary::from_config(&self.config);
+        self.cache.get_mut().clear();
+    }
+
+    /// Unchanged text is served from the analysis cache; see
+    /// `set_cache_capacity`.
+    pub fn analyze_text(&self, text: &str) -> TextAnalysisResult {
+        let hash = self.generate_content_hash(text);
+        if let Some(result) = self.cache.borrow_mut().get(&hash) {
+            return result;
         }
+        let mut result = self.perform_analysis(text);
+        self.config.output.apply_to_analysis(&mut result);
+        self.cache.borrow_mut().insert(hash, result.clone());
+        result
+    }
+
+    /// `analyze_text` as a JSON string with camelCase keys.
+    pub fn analyze_text_json(&self, text: &str) -> Result<String, String> {
+        json::to_json(&self.analyze_text(text))
+    }
+
+    /// `analyze_text` on each document, with corpus totals. Bare strings are
+    /// identified by position.
+    pub fn analyze_batch(&self, documents: Vec<BatchDocument>) -> BatchAnalysis {
+        // The documents share one word table and one set of index buffers:
+        // a word seen in an earlier chapter is not lowercased, classified or
+        // measured again, and each index reuses the last one's vectors. The
+        // cache would only churn
+        let mut words = WordTable::default();
+        let mut buffers = IndexBuffers::default();
+        let mut ids = Vec::with_capacity(documents.len());
+        let mut results = Vec::with_capacity(documents.len());
+        for (position, document) in documents.into_iter().enumerate() {
+            let (id, text) = document.into_parts(position);
+            let prepared = self.prepared(&text);
+            let index = self.index_in(prepared.as_ref().map_or(&text, |r| &r.text), &mut buffers);
+            let mut result = self.analyze_indexed_with(&index, &mut words);
+            buffers.reclaim(index);
+            if let Some(prepared) = &prepared {
+                self.note_preparation(&mut result, &text, prepared);
+            }
+            note_progress(&mut result, front_matter::parse(&text).as_ref());
+            results.push(result);
+            ids.push(id);
+        }
+        let mut summary = batch::summarize(ids, &results, words.vocabulary_size());
+
+        for result in &mut results {
+            self.config.output.apply_to_analysis(result);
+        }
+        self.config.output.readability(&mut summary.average_readability);
+        for share in &mut summary.word_shares {
+            self.config.output.ratio(&mut share.share);
+        }
+        BatchAnalysis { results, summary }
+    }
+
+    /// How many `analyze_text` results to keep (8 by default); 0 disables
+    /// the cache. Shrinking evicts the least recently used results.
+    pub fn set_cache_capacity(&mut self, capacity: usize) {
+        self.cache.get_mut().set_capacity(capacity);
     }
 
-    #[wasm_bindgen]
-    pub fn analyze_text(&self, text: &str) -> JsValue {
-        let result = self.perform_analysis(text);
-        serde_wasm_bindgen::to_value(&result).unwrap()
+    pub fn clear_cache(&mut self) {
+        self.cache.get_mut().clear();
     }
 
-    #[wasm_bindgen]
-    pub fn optimize_text(&self, text: &str) -> JsValue {
-        let suggestions = self.generate_optimization_suggestions(text);
-        serde_wasm_bindgen::to_value(&suggestions).unwrap()
+    /// Capacity, stored entries, and hit and miss counts of the analysis
+    /// cache, for diagnostics.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.borrow().stats()
     }
 
-    #[wasm_bindgen]
-    pub fn resolve_conflicts(&self, conflicts_js: &JsValue) -> JsValue {
-        let conflicts: Vec<CollaborationConflict> = serde_wasm_bindgen::from_value(conflicts_js.clone()).unwrap();
-        let resolved = self.auto_resolve_conflicts(conflicts);
-        serde_wasm_bindgen::to_value(&resolved).unwrap()
+    /// Starts an editing session on `text` and returns its analysis. Later
+    /// edits go through `analyze_incremental`.
+    pub fn begin_session(&mut self, text: &str) -> TextAnalysisResult {
+        self.session = Some(Session::new(text.to_string(), &self.segmenters()));
+        self.session_analysis().unwrap()
+    }
+
+    /// Applies `edit` (byte offsets into the session text) and returns the
+    /// analysis of the edited text, identical to `analyze_text` on it. Only
+    /// the paragraphs around the edit are re-tokenized.
+    pub fn analyze_incremental(&mut self, edit: &TextEdit) -> Result<TextAnalysisResult, String> {
+        let segmenters = Segmenters { words: &self.words, sentences: &self.sentence_patterns, paragraphs: &self.paragraph_patterns };
+        let session = self.session.as_mut().ok_or("No editing session; call begin_session first")?;
+        session.apply(edit, &segmenters)?;
+        self.session_analysis()
+    }
+
+    /// Ends the editing session and frees the retained text.
+    pub fn reset_session(&mut self) {
+        self.session = None;
+    }
+
+    /// Starts analyzing a text fed in pieces with `feed_chunk`, dropping any
+    /// stream not finished. Runs of whole paragraphs are measured as they
+    /// arrive, so memory stays near the chunk size plus the vocabulary
+    /// rather than the whole text. Stripped or reflowed input, and the
+    /// language set to "auto", need the whole text and keep it instead, as
+    /// does a text opening with a front matter fence until the block closes.
+    pub fn start_stream_analysis(&mut self) {
+        let foreign = self.config.detect_paragraph_language.then(|| ForeignParagraphs::new(&self.config.language));
+        let buffered = self.config.transforms_input() || self.config.language == lang::AUTO;
+        self.stream = Some(Stream::new(Totals::new(foreign), buffered));
+    }
+
+    /// Appends `chunk` to the streamed text. A sentence, paragraph, or
+    /// quotation left open at its end is carried into the next chunk.
+    pub fn feed_chunk(&mut self, chunk: &str) -> Result<(), String> {
+        let mut stream = self.stream.take().ok_or("No stream analysis; call start_stream_analysis first")?;
+        stream.push(chunk);
+        if stream.whole.is_none() && stream.awaiting_front_matter {
+            stream.settle_front_matter(self.config.skip_front_matter, false);
+        }
+        if stream.whole.is_none() && !stream.awaiting_front_matter {
+            let cut = stream::last_cut(&self.index(&stream.pending), &self.dialogue_spans(&stream.pending));
+            if let Some(cut) = cut {
+                self.add_totals(&self.index(&stream.pending[..cut]), stream.consumed, &mut stream.totals);
+                stream.advance(cut);
+            }
+        }
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    /// Ends the stream and returns the analysis of everything fed, identical
+    /// to `analyze_text` on the chunks joined.
+    pub fn finish_stream_analysis(&mut self) -> Result<TextAnalysisResult, String> {
+        let mut stream = self.stream.take().ok_or("No stream analysis; call start_stream_analysis first")?;
+        let mut result = match stream.whole.take() {
+            Some(text) => self.perform_analysis(&text),
+            None => {
+                if stream.awaiting_front_matter {
+                    stream.settle_front_matter(self.config.skip_front_matter, true);
+                }
+                let mut totals = std::mem::take(&mut stream.totals);
+                self.add_totals(&self.index(&stream.pending), stream.consumed, &mut totals);
+                let front_matter = stream.front_matter.take();
+                let mut result = if totals.nonblank {
+                    console_log!("Performing text analysis on {} characters", stream.consumed + stream.pending.len());
+                    self.analysis_from_totals(totals, stream.content_hash())
+                } else {
+                    self.empty_analysis(stream.content_hash())
+                };
+                note_progress(&mut result, front_matter.as_ref());
+                result
+            }
+        };
+        self.config.output.apply_to_analysis(&mut result);
+        Ok(result)
+    }
+
+    /// Per-sentence difficulty scores and buckets for editor heatmaps.
+    pub fn sentence_difficulty(&self, text: &str) -> Vec<SentenceDifficulty> {
+        let prepared = self.prepared(text);
+        let index = self.index(prepared.as_ref().map_or(text, |r| &r.text));
+        let mut sentences = self.sentence_difficulties(&index);
+        if let Some(prepared) = &prepared {
+            map_sentence_spans(&mut sentences, prepared);
+        }
+        self.config.output.apply_to_sentences(&mut sentences);
+        sentences
+    }
+
+    /// How far `text` is from a readability `target` ("8th grade",
+    /// "general adult", "academic", ...), with the longest sentences to split
+    /// and the most frequent complex words to replace to get there.
+    pub fn readability_gap(&self, text: &str, target: &str) -> Result<ReadabilityGap, String> {
+        let prepared = self.prepared(text);
+        let index = self.index(prepared.as_ref().map_or(text, |r| &r.text));
+        let mut gap = gap::readability_gap(
+            &index,
+            target,
+            |w| self.count_syllables(w),
+            |w| !self.is_opaque(w),
+            self.config.analysis.long_sentence_threshold,
+        )?;
+        let positions = TextPositions::new(text);
+        let occurrences = gap.complex_words.iter_mut().flat_map(|w| w.occurrences.iter_mut());
+        for suggestion in gap.sentences_to_split.iter_mut().chain(occurrences) {
+            if let Some(prepared) = &prepared {
+                (suggestion.start_pos, suggestion.end_pos) = prepared.offset_map.map_span(suggestion.start_pos, suggestion.end_pos);
+            }
+            suggestion.locate(&positions);
+        }
+        for grade in [&mut gap.current_grade, &mut gap.estimated_grade] {
+            self.config.output.readability(grade);
+        }
+        Ok(gap)
+    }
+
+    /// Word count, syllable density, Flesch reading ease, and passive-voice
+    /// presence for every sentence, with byte ranges into `text`.
+    pub fn analyze_sentences(&self, text: &str) -> Vec<SentenceReadability> {
+        let prepared = self.prepared(text);
+        let index = self.index(prepared.as_ref().map_or(text, |r| &r.text));
+        let mut sentences = self.sentence_readability(&index);
+        for sentence in &mut sentences {
+            if let Some(prepared) = &prepared {
+                (sentence.start_pos, sentence.end_pos) = prepared.offset_map.map_span(sentence.start_pos, sentence.end_pos);
+            }
+            self.config.output.average(&mut sentence.avg_syllables_per_word);
+            self.config.output.readability(&mut sentence.flesch_reading_ease);
+        }
+        sentences
+    }
+
+    /// Byte range, word and sentence counts, Flesch reading ease, dialogue
+    /// presence, and dominant style of every paragraph, for outline views.
+    /// The word counts add up to `analyze_text`'s `word_count`.
+    pub fn analyze_paragraphs(&self, text: &str) -> Vec<ParagraphAnalysis> {
+        let prepared = self.prepared(text);
+        let index = self.index(prepared.as_ref().map_or(text, |r| &r.text));
+        let mut paragraphs = self.paragraph_analyses(&index, prepared.as_ref().map(|p| (&p.offset_map, text)));
+        for paragraph in &mut paragraphs {
+            if let Some(prepared) = &prepared {
+                (paragraph.start_pos, paragraph.end_pos) = prepared.offset_map.map_span(paragraph.start_pos, paragraph.end_pos);
+            }
+            self.config.output.readability(&mut paragraph.flesch_reading_ease);
+        }
+        paragraphs
+    }
+
+    /// Dialogue tags ("she said", "said Harry", "he whispered softly") before,
+    /// after, or interrupting each quotation, with the share that use
+    /// "said" or "asked", said-bookisms, and adverbs.
+    pub fn analyze_dialogue(&self, text: &str) -> DialogueReport {
+        let prepared = self.prepared(text);
+        let source = prepared.as_ref().map_or(text, |r| &r.text);
+        let mut report = attribution::analyze(source, &self.dialogue_spans(source));
+        if let Some(prepared) = &prepared {
+            for tag in &mut report.tags {
+                (tag.start_pos, tag.end_pos) = prepared.offset_map.map_span(tag.start_pos, tag.end_pos);
+                for span in &mut tag.quotes {
+                    (span.start_pos, span.end_pos) = prepared.offset_map.map_span(span.start_pos, span.end_pos);
+                }
+            }
+        }
+        for ratio in [&mut report.said_ratio, &mut report.bookism_ratio, &mut report.adverb_tag_ratio] {
+            self.config.output.ratio(ratio);
+        }
+        report
+    }
+
+    /// Pacing across the document for a line chart: sentence length,
+    /// dialogue, and a 0 (slow) to 1 (fast) score per window of
+    /// `window_words` words (0 for the default of 250), half a window apart.
+    pub fn pacing_profile(&self, text: &str, window_words: usize) -> Vec<PacingPoint> {
+        let prepared = self.prepared(text);
+        let index = self.index(prepared.as_ref().map_or(text, |r| &r.text));
+        let window_words = if window_words == 0 { pacing::DEFAULT_WINDOW_WORDS } else { window_words };
+        let mut points = pacing::profile(&index, &self.dialogue_spans(index.text), window_words);
+        for point in &mut points {
+            if let Some(prepared) = &prepared {
+                (point.start_pos, point.end_pos) = prepared.offset_map.map_span(point.start_pos, point.end_pos);
+            }
+            self.config.output.average(&mut point.avg_sentence_length);
+            self.config.output.ratio(&mut point.dialogue_ratio);
+            self.config.output.ratio(&mut point.pacing_score);
+        }
+        points
+    }
+
+    /// Sentiment per paragraph for an emotional arc: valence per word, with
+    /// the words that weigh most, and the document's average.
+    pub fn sentiment_profile(&self, text: &str) -> SentimentProfile {
+        let prepared = self.prepared(text);
+        let index = self.index(prepared.as_ref().map_or(text, |r| &r.text));
+        let mut profile = sentiment::profile(&index, &self.sentiment);
+        for paragraph in &mut profile.paragraphs {
+            if let Some(prepared) = &prepared {
+                (paragraph.start_pos, paragraph.end_pos) = prepared.offset_map.map_span(paragraph.start_pos, paragraph.end_pos);
+            }
+            self.config.output.average(&mut paragraph.score);
+        }
+        self.config.output.average(&mut profile.average_score);
+        profile
+    }
+
+    /// Recurring character names with every mention, most mentioned first.
+    /// Short forms count toward the full name they start ("Elizabeth" in
+    /// "Elizabeth Bennet"), and possessives count as mentions.
+    pub fn extract_entities(&self, text: &str) -> Vec<Entity> {
+        let prepared = self.prepared(text);
+        let index = self.index(prepared.as_ref().map_or(text, |r| &r.text));
+        let mut entities = entities::extract(&index, &self.stopwords);
+        if let Some(prepared) = &prepared {
+            for entity in &mut entities {
+                for span in &mut entity.positions {
+                    (span.start_pos, span.end_pos) = prepared.offset_map.map_span(span.start_pos, span.end_pos);
+                }
+                entity.first_pos = entity.positions[0].start_pos;
+            }
+        }
+        entities
+    }
+
+    /// Every acronym (two to six capitals; `ignored_acronyms` aside) in order
+    /// of first use, with its expansion when the text spells it out as
+    /// "Open Authoring Protocol (OAP)" or "OAP (Open Authoring Protocol)".
+    /// Plurals count toward the singular.
+    pub fn extract_acronyms(&self, text: &str) -> Vec<Acronym> {
+        let prepared = self.prepared(text);
+        let index = self.index(prepared.as_ref().map_or(text, |r| &r.text));
+        let mut acronyms = acronyms::extract(&index, &self.config.ignored_acronyms);
+        if let Some(prepared) = &prepared {
+            for acronym in &mut acronyms {
+                acronym.first_use_pos = prepared.offset_map.map_span(acronym.first_use_pos, acronym.first_use_pos).0;
+            }
+        }
+        acronyms
+    }
+
+    /// Phrases of `min_n` to `max_n` words (default 3 to 5) repeated at
+    /// least `min_count` times (default 3), with every position; pass 0 for
+    /// a default. A phrase only ever seen inside a longer reported one is
+    /// left out.
+    pub fn repeated_phrases(&self, text: &str, min_n: usize, max_n: usize, min_count: usize) -> Vec<RepeatedPhrase> {
+        let or_default = |value: usize, default: usize| if value == 0 { default } else { value };
+        let min_n = or_default(min_n, phrases::DEFAULT_MIN_WORDS).max(2);
+        let max_n = or_default(max_n, phrases::DEFAULT_MAX_WORDS).max(min_n);
+        let min_count = or_default(min_count, phrases::DEFAULT_MIN_COUNT).max(2);
+        let prepared = self.prepared(text);
+        let index = self.index(prepared.as_ref().map_or(text, |r| &r.text));
+        let mut repeated = phrases::repeated(&index, &self.stopwords, min_n, max_n, min_count);
+        if let Some(prepared) = &prepared {
+            for span in repeated.iter_mut().flat_map(|p| &mut p.positions) {
+                (span.start_pos, span.end_pos) = prepared.offset_map.map_span(span.start_pos, span.end_pos);
+            }
+        }
+        repeated
+    }
+
+    /// The sentences of `text` with their byte ranges, as every analysis
+    /// counts them.
+    pub fn split_sentences(&self, text: &str) -> Vec<SentenceSpan> {
+        let prepared = self.prepared(text);
+        let index = self.index(prepared.as_ref().map_or(text, |r| &r.text));
+        index
+            .sentences
+            .iter()
+            .map(|&(start, end)| {
+                let (start_pos, end_pos) = prepared.as_ref().map_or((start, end), |p| p.offset_map.map_span(start, end));
+                SentenceSpan { start_pos, end_pos, text: text[start_pos..end_pos].to_string() }
+            })
+            .collect()
+    }
+
+    /// `analyze_text` and `sentence_difficulty` from a single tokenization pass.
+    pub fn analyze_full(&self, text: &str) -> FullAnalysis {
+        let prepared = self.prepared(text);
+        let index = self.index(prepared.as_ref().map_or(text, |r| &r.text));
+        let mut full = FullAnalysis {
+            analysis: self.analyze_indexed(&index),
+            sentences: self.sentence_difficulties(&index),
+        };
+        if let Some(prepared) = &prepared {
+            self.note_preparation(&mut full.analysis, text, prepared);
+            map_sentence_spans(&mut full.sentences, prepared);
+        }
+        note_progress(&mut full.analysis, front_matter::parse(text).as_ref());
+        self.config.output.apply_to_analysis(&mut full.analysis);
+        self.config.output.apply_to_sentences(&mut full.sentences);
+        full
+    }
+
+    /// Analysis, sentence difficulty, and suggestions for `text[start..end]`
+    /// alone, in positions of the whole text, with where the range sits in
+    /// the document. An empty range gives an empty analysis.
+    pub fn analyze_range(&self, text: &str, start: usize, end: usize) -> Result<RangeAnalysis, String> {
+        if start > end || end > text.len() {
+            return Err(format!("Range {}..{} is out of bounds for a text of {} bytes", start, end, text.len()));
+        }
+        if !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+            return Err(format!("Range {}..{} does not fall on character boundaries", start, end));
+        }
+        let slice = &text[start..end];
+        let prepared = self.prepared(slice);
+        let index = self.index(prepared.as_ref().map_or(slice, |r| &r.text));
+        let mut analysis = self.analyze_indexed(&index);
+        let mut sentences = self.sentence_difficulties(&index);
+        if let Some(prepared) = &prepared {
+            self.note_preparation(&mut analysis, slice, prepared);
+            map_sentence_spans(&mut sentences, prepared);
+        }
+        note_progress(&mut analysis, front_matter::parse(slice).as_ref());
+        for sentence in &mut sentences {
+            sentence.start_pos += start;
+            sentence.end_pos += start;
+        }
+        self.config.output.apply_to_analysis(&mut analysis);
+        self.config.output.apply_to_sentences(&mut sentences);
+        let mut suggestions = self.build_optimization_report(slice).suggestions;
+        shift_suggestions(&mut suggestions, start, &TextPositions::new(text));
+
+        // Where the range sits, by the whole document's units
+        let document = self.index(text);
+        let inside = |&(s, e): &(usize, usize), at: usize| s < at && at < e;
+        let context = RangeContext {
+            paragraphs: (0..document.paragraphs.len())
+                .filter(|&i| {
+                    let (s, e) = document.paragraphs[i];
+                    s < end.max(start + 1) && e > start
+                })
+                .collect(),
+            starts_mid_sentence: document.sentences.iter().any(|s| inside(s, start)),
+            ends_mid_sentence: document.sentences.iter().any(|s| inside(s, end)),
+        };
+        Ok(RangeAnalysis { analysis, sentences, suggestions, context })
+    }
+
+    pub fn optimize_text(&self, text: &str) -> Vec<OptimizationSuggestion> {
+        self.build_optimization_report(text).suggestions
+    }
+
+    pub fn optimize_text_report(&self, text: &str) -> OptimizationReport {
+        self.build_optimization_report(text)
+    }
+
+    /// The full analysis and the optimization report of `text` as a
+    /// "markdown" report, "csv" metric rows, or "json".
+    pub fn export_report(&self, text: &str, format: &str) -> Result<String, String> {
+        let format = report::ReportFormat::parse(format)?;
+        let full = self.analyze_full(text);
+        let optimization = self.build_optimization_report(text);
+        Ok(report::render(format, &full, &optimization, self.config.report_suggestion_rows))
+    }
+
+    /// `optimize_text` as a JSON string with camelCase keys.
+    pub fn optimize_text_json(&self, text: &str) -> Result<String, String> {
+        json::to_json(&self.optimize_text(text))
+    }
+
+    /// Suggestions narrowed by `query` (rule ids, minimum priority, a
+    /// selection), highest priority first, one page at a time.
+    pub fn optimize_text_filtered(&self, text: &str, query: &SuggestionQuery) -> SuggestionPage {
+        let wants = |rule: &str| query.wants(rule);
+        let report = match (query.start_pos, query.end_pos) {
+            (None, None) => self.filtered_optimization_report(text, &wants),
+            (start, end) => {
+                let start = offsets::char_floor(text, start.unwrap_or(0));
+                let end = offsets::char_ceil(text, end.unwrap_or(text.len())).max(start);
+                self.selection_optimization_report(text, start, end, &wants)
+            }
+        };
+        query.page(report.suggestions)
+    }
+
+    /// `text` with the replacements of the suggestions at `accepted` applied.
+    /// Suggestions without a replacement are skipped; overlapping accepted
+    /// spans are an error and nothing is applied.
+    pub fn apply_suggestions(&self, text: &str, suggestions: &[OptimizationSuggestion], accepted: &[usize]) -> Result<String, String> {
+        edits::apply_suggestions(text, suggestions, accepted)
+    }
+
+    /// `text` with the safe typography fixes `options` enables: runs of
+    /// spaces between words collapsed and straight quotes made curly.
+    /// Opaque tokens and, for Markdown, code and link targets are left as
+    /// they are.
+    pub fn normalize_typography(&self, text: &str, options: &TypographyOptions) -> String {
+        // Only bytes that survive preparation are prose
+        let mut editable = match self.prepared(text) {
+            Some(prepared) => {
+                let mut kept = vec![false; text.len()];
+                for i in 0..prepared.text.len() {
+                    if let Some(byte) = kept.get_mut(prepared.offset_map.to_original(i)) {
+                        *byte = true;
+                    }
+                }
+                kept
+            }
+            None => vec![true; text.len()],
+        };
+        for (start, end) in self.opaque_tokens(&self.index(text)) {
+            editable[start..end].fill(false);
+        }
+        typography::normalize(text, options, |at| editable[at])
+    }
+
+    /// Resolves `conflicts` under `policy`. Overlapping conflicts are merged
+    /// into one `composite` conflict, and `resolved` is sorted by
+    /// `start_pos`. With `text`, spans are first reconciled against it and
+    /// the outcome is listed in `reconciliation`.
+    pub fn resolve_conflicts(&self, mut conflicts: Vec<CollaborationConflict>, text: Option<&str>, policy: ConflictPolicy) -> ConflictResolutionResponse {
+        let reconciliation = match text {
+            Some(text) => reconcile::reconcile_all(text, &mut conflicts),
+            None => Vec::new(),
+        };
+        let mut resolved = self.auto_resolve_conflicts(conflicts, policy);
+        if let Some(text) = text {
+            let positions = TextPositions::new(text);
+            for conflict in &mut resolved {
+                conflict.locate(&positions);
+            }
+        }
+        ConflictResolutionResponse { resolved, rejected: Vec::new(), reconciliation }
+    }
+
+    /// `resolve_conflicts` from and to JSON strings with camelCase keys
+    /// (snake_case is accepted too). Malformed entries are listed in
+    /// `rejected` rather than failing the batch.
+    pub fn resolve_conflicts_json(&self, conflicts_json: &str, text: Option<&str>, policy: ConflictPolicy) -> Result<String, String> {
+        let entries = match json::from_json(conflicts_json)? {
+            serde_json::Value::Array(entries) => entries,
+            _ => return Err("Conflicts must be a JSON array".to_string()),
+        };
+        let (conflicts, rejected) = validate_entries(entries);
+        let response = ConflictResolutionResponse { rejected, ..self.resolve_conflicts(conflicts, text, policy) };
+        json::to_json(&response)
+    }
+
+    /// Three-way merge of two versions edited from `base`. Edits that don't
+    /// overlap are applied to `merged_text`; overlapping ones that differ are
+    /// returned as conflicts with spans into `base`, and `merged_spans`
+    /// locates each one in `merged_text`.
+    pub fn detect_conflicts(&self, base: &str, version_a: &str, version_b: &str) -> ThreeWayMerge {
+        let mut merged = threeway::merge(base, version_a, version_b);
+        let positions = TextPositions::new(base);
+        for conflict in &mut merged.conflicts {
+            conflict.locate(&positions);
+        }
+        merged
+    }
+
+    /// Merged text for `base_text` with `conflicts` resolved under `policy`,
+    /// and a marker span per applied conflict naming its source. Unresolved
+    /// conflicts are rendered with both versions between git-style conflict
+    /// markers.
+    pub fn preview_resolution(&self, base_text: &str, conflicts: Vec<CollaborationConflict>, policy: ConflictPolicy) -> ResolutionPreview {
+        let resolved = self.auto_resolve_conflicts(conflicts, policy);
+        merge::preview(base_text, &resolved)
+    }
+
+    /// `base_text` with the resolutions of `resolve_conflicts` output spliced
+    /// in. Conflicts needing manual resolution are left as they are and
+    /// listed in `skipped`; an out-of-bounds, reversed, or overlapping span
+    /// is an error naming its conflict, and nothing is applied.
+    pub fn apply_resolutions(&self, base_text: &str, resolved: &[CollaborationConflict]) -> Result<AppliedResolutions, String> {
+        merge::apply_strict(base_text, resolved)
+    }
+
+    /// Verifies each conflict's span against its `expected_text` and, on a
+    /// mismatch, searches nearby for the intended location.
+    pub fn reconcile_conflict_spans(&self, text: &str, conflicts: &[CollaborationConflict]) -> Vec<SpanReconciliation> {
+        conflicts.iter().map(|conflict| reconcile::reconcile(text, conflict)).collect()
+    }
+
+    /// Whole-word matches of `terms` (words or phrases); `fuzzy` also
+    /// matches obfuscated spellings. Spans refer to the raw text.
+    pub fn find_flagged_terms(&self, text: &str, terms: &[String], fuzzy: bool) -> Vec<FlaggedMatch> {
+        flagged::find_flagged_terms(text, terms, fuzzy)
+    }
+
+    /// Matches of the screening lists of `categories` (all of them when
+    /// `None`) with category, severity, and position, plus counts per
+    /// category. Obfuscated spellings match; words merely containing a
+    /// listed one don't.
+    pub fn screen_content(&self, text: &str, categories: Option<&[String]>) -> Result<ContentScreen, String> {
+        screening::screen(text, &self.config.screening_lists, categories)
+    }
+
+    /// Replaces the screening list of `category` (words or phrases), or adds
+    /// the category.
+    pub fn load_screening_list(&mut self, category: &str, words: Vec<String>) {
+        self.config.screening_lists.insert(category.to_string(), words);
+    }
+
+    pub fn compare_to_corpus(&self, text: &str, corpus: &ReferenceCorpus) -> CorpusComparison {
+        corpus.compare(self, text)
     }
 
-    #[wasm_bindgen]
     pub fn generate_content_hash(&self, text: &str) -> String {
         let mut hasher = Sha256::new();
         hasher.update(text.as_bytes());
         let result = hasher.finalize();
         general_purpose::STANDARD.encode(result)
     }
+
+    /// Hash of `text` under `algorithm` (sha256, sha512, or blake3) in
+    /// `encoding` (base64, base64url, or hex). `generate_content_hash` is
+    /// sha256 in base64.
+    pub fn generate_content_hash_with(&self, text: &str, algorithm: &str, encoding: &str) -> Result<String, String> {
+        hashing::hash(text, algorithm, encoding)
+    }
+
+    /// Hash of every paragraph (`chunk_mode` "paragraph") or run of N words
+    /// ("words:N"), with byte ranges that cover the whole text, and a Merkle
+    /// root over them that is stable for unchanged text.
+    pub fn generate_chunk_hashes(&self, text: &str, chunk_mode: &str) -> Result<ChunkHashes, String> {
+        let mode = ChunkMode::parse(chunk_mode)?;
+        Ok(chunks::chunk_hashes(&self.index(text), &mode, chunk_mode))
+    }
+
+    /// Ranges of the chunks that differ between two `generate_chunk_hashes`
+    /// results, on each side. Both must use the same chunk mode.
+    pub fn compare_chunk_hashes(&self, local: &ChunkHashes, remote: &ChunkHashes) -> Result<ChunkComparison, String> {
+        chunks::compare(local, remote)
+    }
+
+    /// Similarity of two texts in [0, 1] by `method`: "shingle" for the
+    /// Jaccard similarity of word trigrams ("shingle:N" for N-grams), or
+    /// "cosine" over term frequencies. Identical texts score 1.0.
+    pub fn similarity(&self, text_a: &str, text_b: &str, method: &str) -> Result<f64, String> {
+        let method = SimilarityMethod::parse(method)?;
+        Ok(method.score(&self.lowercase_words(text_a), &self.lowercase_words(text_b)))
+    }
+
+    /// A compact MinHash signature of `text`'s word trigrams, to store and
+    /// compare with `compare_fingerprints` instead of keeping the text.
+    pub fn fingerprint(&self, text: &str) -> Fingerprint {
+        similarity::fingerprint(&self.lowercase_words(text), similarity::DEFAULT_SHINGLE_SIZE)
+    }
+
+    /// Estimated "shingle" similarity of the texts behind two fingerprints.
+    pub fn compare_fingerprints(&self, a: &Fingerprint, b: &Fingerprint) -> Result<f64, String> {
+        similarity::compare_fingerprints(a, b)
+    }
+
+    /// Heading, span, word count, and content hash for every detected
+    /// chapter or section, plus the whole-document hash.
+    pub fn chapter_manifest(&self, text: &str) -> ChapterManifest {
+        self.build_chapter_manifest(text)
+    }
+
+    /// Markdown headings, "Chapter N" lines, and scene breaks (`***`, `---`,
+    /// `# # #`) in order, each with the span of the section it opens. Text
+    /// before the first one is an untitled level-0 section.
+    pub fn extract_outline(&self, text: &str) -> Vec<OutlineEntry> {
+        structure::detect_outline(text, &self.heading_patterns).iter().map(OutlineEntry::from).collect()
+    }
+
+    /// `analyze_text` on every `extract_outline` section on its own, with
+    /// the section's title and range.
+    pub fn analyze_by_section(&self, text: &str) -> Vec<SectionAnalysis> {
+        structure::detect_outline(text, &self.heading_patterns)
+            .into_iter()
+            .map(|section| {
+                let mut analysis = self.perform_analysis(&text[section.start..section.end]);
+                self.config.output.apply_to_analysis(&mut analysis);
+                SectionAnalysis {
+                    title: section.heading,
+                    level: section.level,
+                    start_pos: section.start,
+                    end_pos: section.end,
+                    analysis,
+                }
+            })
+            .collect()
+    }
+
+    /// Compares a previously stored manifest against `new_text`, reporting
+    /// chapters that changed, were added or removed, or merely moved.
+    pub fn diff_manifest(&self, old: &ChapterManifest, new_text: &str) -> ManifestDiff {
+        manifest::diff(old, self.build_chapter_manifest(new_text))
+    }
+
+    /// Up to three likely languages of `text` with confidences, from the
+    /// function words of English, Spanish, French, German, Italian, and
+    /// Portuguese. Texts under 20 words are marked low confidence.
+    pub fn detect_language(&self, text: &str) -> LanguageDetection {
+        language::detect_document(&self.index(text))
+    }
+
+    /// Detected language and confidence for every paragraph, flagging those
+    /// that are confidently not in the configured language. Paragraphs under
+    /// eight words inherit their neighbours' language.
+    pub fn language_map(&self, text: &str) -> LanguageMap {
+        let index = self.index(text);
+        language::language_map(&index, &self.document_language(&index))
+    }
+
+    /// Skimmability, engagement, and cognitive-load scores (0–100), each with
+    /// its contributing factors and the spans that hurt it most.
+    pub fn persona_scores(&self, text: &str) -> PersonaScores {
+        persona::persona_scores(self, &self.index(text))
+    }
+
+    /// The `top_n` most used words (all of them for 0), most frequent first
+    /// and ties alphabetical. Counts use the same tokenization as
+    /// `analyze_text`, so they add up to its `word_count` when stopwords are
+    /// kept.
+    pub fn word_frequencies(&self, text: &str, top_n: usize, exclude_stop_words: bool) -> Vec<WordFrequency> {
+        let prepared = self.prepared(text);
+        let index = self.index(prepared.as_ref().map_or(text, |r| &r.text));
+        let stopwords = exclude_stop_words.then_some(&self.stopwords);
+        let mut frequencies = frequency::word_frequencies(&index.word_strs(), stopwords, top_n);
+        for entry in &mut frequencies {
+            self.config.output.ratio(&mut entry.ratio);
+        }
+        frequencies
+    }
+
+    /// Every token of `text` (words, whitespace, punctuation, opening and
+    /// closing quotes, paragraph breaks) with its byte range and the style
+    /// flags the analysis gives it, for highlighting as the user types. The
+    /// flags agree with the metrics: as many tokens are flagged adverbs as
+    /// `adverb_ratio` counts. With stripped input, ranges are in `text` and
+    /// the markup between tokens has none.
+    pub fn tokenize(&self, text: &str) -> TokenStream {
+        let prepared = self.prepared(text);
+        let index = self.index(prepared.as_ref().map_or(text, |r| &r.text));
+        if self.config.language == lang::AUTO {
+            self.detected_language.set(Some(lang::resources(language::detect_document(&index).language().unwrap_or(lang::ENGLISH.code))));
+        }
+        let opaque_tokens = self.opaque_tokens(&index);
+        let masked = opaque::mask(index.text, &opaque_tokens);
+        let sorted = |mut spans: Vec<(usize, usize)>| {
+            spans.sort_unstable();
+            spans
+        };
+        let dialogue = self.dialogue_spans(index.text);
+        let spans = tokens::StyleSpans {
+            adverbs: sorted(self.adverbs_by_sentence(&index, &masked).into_iter().flatten().flatten().collect()),
+            fillers: self.fillers.find(&masked),
+            passive: sorted(self.passive_by_sentence(&index, &masked).into_iter().flatten().flatten().collect()),
+            filter_words: narrative::filter_words(&index, &narrative::word_classes(&index), &dialogue).into_iter().flatten().collect(),
+            dialogue,
+        };
+        self.detected_language.set(None);
+        let mut stream = tokens::token_stream(index.text, &spans);
+        if let Some(prepared) = &prepared {
+            for (start, end) in stream.starts.iter_mut().zip(stream.ends.iter_mut()) {
+                let (s, e) = prepared.offset_map.map_span(*start as usize, *end as usize);
+                (*start, *end) = (s as u32, e as u32);
+            }
+        }
+        stream
+    }
+
+    /// Word counts by Microsoft Word's rules and by the engine's, the count
+    /// without front matter, headings, and block quotes with a manuscript
+    /// page estimate, and character counts with and without spaces.
+    pub fn count_words(&self, text: &str) -> WordCountDetail {
+        let prepared = self.prepared(text);
+        let words = self.index(prepared.as_ref().map_or(text, |r| &r.text)).words.len();
+        let headings: Vec<(usize, usize)> = structure::detect_sections(text, &self.heading_patterns)
+            .iter()
+            .filter(|section| section.heading.is_some())
+            .map(|section| (section.start, text[section.start..].find('\n').map_or(text.len(), |end| section.start + end)))
+            .collect();
+        wordcount::count(text, words, &headings)
+    }
+
+    /// The front matter block opening `text`: its key/value pairs and byte
+    /// span. `None` without one, including when its closing fence is
+    /// missing.
+    pub fn parse_front_matter(&self, text: &str) -> Option<FrontMatter> {
+        front_matter::parse(text)
+    }
+
+    /// Counts, reading time, and style flags for a short UI string, without
+    /// the cost of a full analysis.
+    pub fn quick_metrics(&self, text: &str) -> QuickMetrics {
+        quick::quick_metrics(self, text)
+    }
+
+    /// `quick_metrics` for many strings. Results are in input order, and
+    /// strings whose terminal punctuation differs from the rest of the
+    /// batch are flagged.
+    pub fn quick_metrics_batch(&self, texts: &[String]) -> QuickBatch {
+        quick::quick_metrics_batch(self, texts)
+    }
+
+    /// Word-level diff between two versions of a document as hunks in text
+    /// order, with a summary of words added and removed and paragraphs
+    /// touched.
+    pub fn diff_texts(&self, old_text: &str, new_text: &str) -> TextDiffReport {
+        revisions::diff_texts(&self.index(old_text), &self.index(new_text))
+    }
+
+    /// `items` (suggestions, conflicts, or any records with `start_pos` and
+    /// `end_pos`) with their positions carried through `edits` made since
+    /// they were computed, in application order. Records whose span an edit
+    /// deleted or overlapped are marked `"stale": true`.
+    pub fn transform_positions(&self, items: Vec<serde_json::Value>, edits: &[PositionEdit]) -> Result<Vec<serde_json::Value>, String> {
+        transform::transform_positions(items, edits)
+    }
+
+    /// Words added and deleted, net change, characters typed, and paragraphs
+    /// touched between two snapshots of a writing session, with both content
+    /// hashes for chaining sessions. Moved paragraphs that weren't edited
+    /// count as moved, not as deleted and added.
+    pub fn session_stats(&self, previous_text: &str, current_text: &str) -> SessionStats {
+        let stats = revisions::session_stats(&self.index(previous_text), &self.index(current_text), |text| self.index(text));
+        SessionStats {
+            previous_hash: self.generate_content_hash(previous_text),
+            current_hash: self.generate_content_hash(current_text),
+            ..stats
+        }
+    }
+
+    /// Labels `ranges` of `text` with `label`, on top of `existing` if given.
+    pub fn mark_provenance(&self, text: &str, ranges: &[MarkedRange], label: &str, existing: Option<ProvenanceMap>) -> Result<ProvenanceMap, String> {
+        provenance::mark(text, self.generate_content_hash(text), existing, ranges, label)
+    }
+
+    /// Carries a provenance map from `old_text` to `new_text`. Inserted text
+    /// is unlabeled, so it splits the span it lands in; spans whose text was
+    /// deleted are listed in `dropped`.
+    pub fn remap_provenance(&self, map: &ProvenanceMap, old_text: &str, new_text: &str) -> Result<ProvenanceRemap, String> {
+        self.check_provenance_map(map, old_text)?;
+        Ok(provenance::remap(map, old_text, new_text, self.generate_content_hash(new_text)))
+    }
+
+    /// Word counts and word share per label.
+    pub fn provenance_report(&self, map: &ProvenanceMap, text: &str) -> Result<ProvenanceReport, String> {
+        self.check_provenance_map(map, text)?;
+        Ok(provenance::report(map, &self.index(text)))
+    }
+
+    /// Nests already-computed suggestions under their paragraph and
+    /// sentence, with per-group counts and worst priority.
+    pub fn group_suggestions(&self, text: &str, suggestions: Vec<OptimizationSuggestion>) -> SuggestionDigest {
+        grouping::group(&self.index(text), suggestions)
+    }
+
+    /// Compact, versioned health summary of `text` for trend dashboards;
+    /// feed a series of these to `health_history`.
+    pub fn snapshot(&self, text: &str, timestamp: &str) -> HealthSnapshot {
+        let analysis = self.perform_analysis(text);
+        let report = self.build_optimization_report(text);
+        let instances: Vec<&OptimizationSuggestion> =
+            report.suggestions.iter().filter(|s| s.scope != SCOPE_DOCUMENT).collect();
+        health::snapshot(&analysis, &instances, timestamp)
+    }
+
+    /// Checks `expected_hash` (optionally prefixed, e.g. `"sha256:..."`)
+    /// against the hash of `text` using a constant-time comparison.
+    pub fn verify_content_hash(&self, text: &str, expected_hash: &str, encoding: &str) -> Result<HashVerification, String> {
+        if !hashing::ENCODINGS.contains(&encoding) {
+            return Err(hashing::unsupported("encoding", encoding, hashing::ENCODINGS));
+        }
+        Ok(hashing::verify(text, expected_hash, encoding))
+    }
+
+    /// Constant-time check that `expected` is the `algorithm` hash of `text`
+    /// in `encoding`. Malformed values are simply false; unknown algorithms
+    /// and encodings are errors.
+    pub fn verify_content_hash_with(&self, text: &str, expected: &str, algorithm: &str, encoding: &str) -> Result<bool, String> {
+        hashing::matches(text, expected, algorithm, encoding)
+    }
+}
+
+impl Default for TextProcessor {
+    fn default() -> Self {
+        TextProcessor::new()
+    }
 }
 
 impl TextProcessor {
+    fn with_patterns(overrides: &BTreeMap<String, String>, config: SuggestionConfig) -> TextProcessor {
+        let mut compiler = PatternCompiler::new(overrides);
+
+        TextProcessor {
+            words: compiler.words(),
+            sentence_patterns: compiler.core("sentence"),
+            paragraph_patterns: compiler.core("paragraph"),
+            passive_voice_patterns: compiler.rule("passive_voice"),
+            adverb_patterns: compiler.rule("adverb"),
+            dialogue_patterns: compiler.rule("dialogue"),
+            person_pronoun_patterns: compiler.rule("person_pronoun"),
+            heading_patterns: HeadingPatterns::new(),
+            stopwords: Stopwords::from_config(&config),
+            fillers: FillerWords::from_config(&config),
+            weak_verbs: WeakVerbs::from_config(&config),
+            cliches: Cliches::from_config(&config),
+            variant_groups: VariantGroups::from_config(&config),
+            dictionary: Dictionary::from_config(&config),
+            sentiment: SentimentLexicon::from_config(&config),
+            pattern_failures: compiler.into_failures(),
+            config,
+            session: None,
+            stream: None,
+            cache: RefCell::new(AnalysisCache::new(cache::DEFAULT_CAPACITY)),
+            detected_language: Cell::new(None),
+        }
+    }
+
+    /// Resources for the configured language, or the detected one during an
+    /// "auto" analysis.
+    fn language(&self) -> &'static lang::Language {
+        self.detected_language.get().unwrap_or_else(|| lang::resources(&self.config.language))
+    }
+
+    /// The language the text of `index` is written in: the configured one,
+    /// or with "auto" the detected one (English when none is).
+    fn document_language<'l>(&'l self, index: &DocumentIndex) -> Cow<'l, str> {
+        if self.config.language != lang::AUTO {
+            return Cow::Borrowed(&self.config.language);
+        }
+        Cow::Owned(language::detect_document(index).language().unwrap_or(lang::ENGLISH.code).to_string())
+    }
+
+    fn lowercase_words(&self, text: &str) -> Vec<String> {
+        self.words.words(text).iter().map(|w| w.to_lowercase()).collect()
+    }
+
+    fn is_complex_word(&self, word: &str) -> bool {
+        self.count_syllables(word) >= self.config.analysis.complex_word_syllables
+    }
+
+    fn is_opaque(&self, word: &str) -> bool {
+        opaque::is_opaque(word, self.config.max_token_length)
+    }
+
+    fn opaque_tokens(&self, index: &DocumentIndex) -> Vec<(usize, usize)> {
+        opaque::opaque_tokens(index, self.config.max_token_length)
+    }
+
+    /// Quoted speech. The built-in pattern stands for the quote scanner, which
+    /// handles typographic quotes and quotations across paragraphs; a
+    /// pattern-pack override is matched as given.
+    fn dialogue_spans(&self, text: &str) -> Vec<(usize, usize)> {
+        match &self.dialogue_patterns {
+            Some(re) if patterns::is_builtin_rule("dialogue", re) => dialogue::spans(text),
+            Some(re) => re.find_iter(text).map(|m| (m.start(), m.end())).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Passive constructions in `text`. The built-in pattern stands for the
+    /// participle-aware detector; a pattern-pack override is matched as given.
+    fn passive_matches(&self, text: &str) -> Vec<(usize, usize)> {
+        match &self.passive_voice_patterns {
+            Some(re) if patterns::is_builtin_rule("passive_voice", re) => passive::find(text),
+            Some(re) => re.find_iter(text).map(|m| (m.start(), m.end())).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// `passive_matches` per sentence of `index`, searched in `haystack` (the
+    /// text or its masked copy); `None` when the rule is disabled.
+    fn passive_by_sentence(&self, index: &DocumentIndex, haystack: &str) -> Option<Vec<Vec<(usize, usize)>>> {
+        let re = self.passive_voice_patterns.as_ref()?;
+        if !patterns::is_builtin_rule("passive_voice", re) {
+            return Some(index.matches_by_sentence(re, haystack));
+        }
+        Some(index.spans_by_sentence(haystack, passive::find))
+    }
+
+    /// Adverbs per sentence of `index`, searched in `haystack`; `None` when
+    /// the rule is disabled. The built-in pattern stands for the detector
+    /// that skips non-adverb "-ly" words and names; a pattern-pack override
+    /// is matched as given.
+    fn adverbs_by_sentence(&self, index: &DocumentIndex, haystack: &str) -> Option<Vec<Vec<(usize, usize)>>> {
+        let re = self.adverb_patterns.as_ref()?;
+        if !patterns::is_builtin_rule("adverb", re) {
+            return Some(index.matches_by_sentence(re, haystack));
+        }
+        let near_verbs_only = self.config.analysis.adverbs_near_verbs_only;
+        let language = self.language();
+        Some(index.spans_by_sentence(haystack, |sentence| adverbs::find(sentence, near_verbs_only, language)))
+    }
+
+    fn check_provenance_map(&self, map: &ProvenanceMap, text: &str) -> Result<(), String> {
+        if map.document_hash != self.generate_content_hash(text) {
+            return Err("Provenance map does not match this text; remap it from the text it was built for".to_string());
+        }
+        Ok(())
+    }
+
+    /// Analysis of the session text from its retained index. Stripped or
+    /// reflowed input, and front matter, change what is analyzed, so they
+    /// fall back to a full analysis.
+    fn session_analysis(&self) -> Result<TextAnalysisResult, String> {
+        let session = self.session.as_ref().ok_or("No editing session; call begin_session first")?;
+        let mut result = if self.config.transforms_input() || front_matter::end(&session.text).is_some() {
+            self.perform_analysis(&session.text)
+        } else {
+            self.analyze_indexed(&session.index())
+        };
+        self.config.output.apply_to_analysis(&mut result);
+        Ok(result)
+    }
+
+    fn paragraph_languages(&self, index: &DocumentIndex) -> Option<language::LanguageMap> {
+        self.config.detect_paragraph_language.then(|| language::language_map(index, &self.document_language(index)))
+    }
+
+    fn segmenters(&self) -> Segmenters<'_> {
+        Segmenters { words: &self.words, sentences: &self.sentence_patterns, paragraphs: &self.paragraph_patterns }
+    }
+
+    fn index<'t>(&self, text: &'t str) -> DocumentIndex<'t> {
+        DocumentIndex::build(text, &self.words, &self.sentence_patterns, &self.paragraph_patterns)
+    }
+
+    fn index_in<'t>(&self, text: &'t str, buffers: &mut IndexBuffers) -> DocumentIndex<'t> {
+        DocumentIndex::build_in(text, &self.words, &self.sentence_patterns, &self.paragraph_patterns, buffers)
+    }
+
     fn perform_analysis(&self, text: &str) -> TextAnalysisResult {
+        let mut result = match self.prepared(text) {
+            Some(prepared) => {
+                let mut result = self.analyze_indexed(&self.index(&prepared.text));
+                self.note_preparation(&mut result, text, &prepared);
+                result
+            }
+            None => self.analyze_indexed(&self.index(text)),
+        };
+        note_progress(&mut result, front_matter::parse(text).as_ref());
+        result
+    }
+
+    fn prepared(&self, text: &str) -> Option<PreparedText> {
+        input::prepare(text, &self.config)
+    }
+
+    // The hash always identifies the text the caller sent, and warning
+    // spans point into it
+    fn note_preparation(&self, result: &mut TextAnalysisResult, original: &str, prepared: &PreparedText) {
+        result.content_hash = self.generate_content_hash(original);
+        for span in result.warnings.iter_mut().flat_map(|w| &mut w.spans) {
+            (span.start_pos, span.end_pos) = prepared.offset_map.map_span(span.start_pos, span.end_pos);
+        }
+        result.warnings.extend(prepared.warnings.iter().cloned());
+    }
+
+    fn analyze_indexed(&self, index: &DocumentIndex) -> TextAnalysisResult {
+        self.analyze_indexed_with(index, &mut WordTable::default())
+    }
+
+    /// `analyze_indexed`, looking words up in (and adding them to) `words`.
+    fn analyze_indexed_with(&self, index: &DocumentIndex, words: &mut WordTable) -> TextAnalysisResult {
+        if self.config.language != lang::AUTO {
+            return self.measure(index, words);
+        }
+        let detection = language::detect_document(index);
+        self.detected_language.set(Some(lang::resources(detection.language().unwrap_or(lang::ENGLISH.code))));
+        let mut result = self.measure(index, words);
+        self.detected_language.set(None);
+        match detection.language() {
+            Some(language) => result.detected_language = Some(language.to_string()),
+            None if result.word_count > 0 => result.warnings.push(AnalysisWarning::new(
+                "language_not_detected",
+                "No language could be detected; English resources were used.".to_string(),
+            )),
+            None => {}
+        }
+        result
+    }
+
+    fn measure(&self, index: &DocumentIndex, words: &mut WordTable) -> TextAnalysisResult {
+        let text = index.text;
+        if text.trim().is_empty() {
+            return self.empty_analysis(self.generate_content_hash(text));
+        }
         console_log!("Performing text analysis on {} characters", text.len());
-        
-        let words: Vec<&str> = self.word_patterns.find_iter(text).map(|m| m.as_str()).collect();
-        let sentences: Vec<&str> = text.split(&self.sentence_patterns).filter(|s| !s.trim().is_empty()).collect();
-        let paragraphs: Vec<&str> = self.paragraph_patterns.split(text).filter(|p| !p.trim().is_empty()).collect();
+        let foreign = self.config.detect_paragraph_language.then(|| ForeignParagraphs::new(&self.document_language(index)));
+        let mut totals = Totals::new(foreign);
+        totals.word_table = std::mem::take(words);
+        self.add_totals(index, 0, &mut totals);
+        *words = std::mem::take(&mut totals.word_table);
+        self.analysis_from_totals(totals, self.generate_content_hash(text))
+    }
+
+    fn empty_analysis(&self, content_hash: String) -> TextAnalysisResult {
+        TextAnalysisResult {
+            content_hash,
+            warnings: vec![AnalysisWarning::new("empty_input", "Text is empty or whitespace only.".to_string())],
+            low_confidence: true,
+            not_surfaced: self.not_surfaced_rules(),
+            ..TextAnalysisResult::default()
+        }
+    }
+
+    /// Adds the counts of `index`, whose text starts `offset` bytes into the
+    /// document, to `totals`.
+    fn add_totals(&self, index: &DocumentIndex, offset: usize, totals: &mut Totals) {
+        let text = index.text;
+        totals.nonblank |= !text.trim().is_empty();
+        // Opaque tokens count as words but not toward per-word averages
+        let opaque_tokens = self.opaque_tokens(index);
+        let masked = opaque::mask(text, &opaque_tokens);
+        if let Some(&(start, end)) = opaque_tokens.first().filter(|_| totals.first_opaque.is_none()) {
+            totals.first_opaque = Some(text[start..end].to_string());
+        }
+        totals.opaque_tokens.extend(opaque_tokens.iter().map(|&(start, end)| (offset + start, offset + end)));
+
+        totals.words += index.words.len();
+        totals.characters += text.chars().count();
+        totals.graphemes += text.graphemes(true).count();
+        totals.sentences += index.sentences.len();
+        totals.paragraphs += index.paragraphs.len();
+
+        let detected_stopwords = self.detected_language.get().map(|l| Stopwords::for_language(&self.config, l.code));
+        let stopwords = detected_stopwords.as_ref().unwrap_or(&self.stopwords);
+        let language = self.language().code;
+        let measure = |word: &str| {
+            let opaque = self.is_opaque(word);
+            Measured {
+                opaque,
+                syllables: if opaque { 0 } else { self.count_syllables(word) },
+                content: !opaque && stopwords.is_content_word(word),
+            }
+        };
+        let ids: Vec<u32> = index.words.iter().map(|&(start, end)| totals.word_table.intern(&text[start..end])).collect();
+        for (&id, word) in ids.iter().zip(index.word_strs()) {
+            let measured = totals.word_table.measured(id, word, language, measure);
+            let facts = totals.word_table.facts(id);
+            totals.vocabulary.insert(facts.lowercase);
+            totals.word_ids.push(facts.lowercase);
+            if measured.opaque {
+                continue;
+            }
+            let syllables = measured.syllables;
+            totals.measured_words += 1;
+            totals.syllables += syllables;
+            totals.complex_words += usize::from(syllables >= self.config.analysis.complex_word_syllables);
+            totals.polysyllables += usize::from(syllables >= 3);
+            totals.letters += facts.letters;
+            totals.long_words += usize::from(facts.chars > 6);
+            totals.longest_word = totals.longest_word.max(facts.chars);
+            if measured.content {
+                totals.content_words += 1;
+                totals.content_characters += facts.lowercase_chars;
+                totals.content_vocabulary.insert(facts.lowercase);
+            }
+        }
+        let classes: Vec<u8> = ids.iter().map(|&id| totals.word_table.facts(id).class).collect();
+        let languages: Vec<u8> = ids.iter().map(|&id| totals.word_table.facts(id).languages).collect();
+        totals.sentence_lengths.extend(index.sentences.iter().map(|&(s, e)| index.words_in(s, e).len() as f64));
+
+        let (letters, non_latin) = readability::letter_counts(&masked);
+        totals.alphabetic += letters;
+        totals.non_latin += non_latin;
+        totals.terminal_punctuation |= masked.contains(['.', '!', '?']);
+
+        totals.passive_voice += self.passive_by_sentence(index, &masked).map_or(0, |p| p.iter().map(Vec::len).sum());
+        totals.adverbs += self.adverbs_by_sentence(index, &masked).map_or(0, |a| a.iter().map(Vec::len).sum());
+
+        let dialogue_spans = self.dialogue_spans(text);
+        for mode in narrative::sentence_modes(index, &classes, &dialogue_spans) {
+            match mode {
+                SentenceMode::Dialogue => totals.dialogue_sentences += 1,
+                SentenceMode::Action => totals.action_sentences += 1,
+                SentenceMode::Description => totals.description_sentences += 1,
+                SentenceMode::Neutral => {}
+            }
+        }
+        totals.filtered_sentences += narrative::filter_words(index, &classes, &dialogue_spans).iter().filter(|hits| !hits.is_empty()).count();
+        let stems: Vec<&str> = ids.iter().map(|&id| &*totals.word_table.facts(id).stem).collect();
+        totals.cliches += self.cliches.find_stems(index, &stems).len();
+        totals.dialogue_words += index.words.iter().filter(|w| segmentation::within(&dialogue_spans, w.0, w.1)).count();
+        if self.config.person_pronoun_rule_active() {
+            totals.first_person += self.person_pronoun_hits(&masked).iter().filter(|h| h.2).count();
+        }
+        totals.valence = sentiment::valence_sum(index, &self.sentiment, totals.valence);
+        if let Some(foreign) = &mut totals.foreign {
+            foreign.add(index, &languages);
+        }
+    }
 
-        let word_count = words.len();
-        let character_count = text.chars().count();
-        let sentence_count = sentences.len();
-        let paragraph_count = paragraphs.len();
+    /// The analysis of a non-blank text with `totals`.
+    fn analysis_from_totals(&self, totals: Totals, content_hash: String) -> TextAnalysisResult {
+        let word_count = totals.words;
+        let measured = totals.measured_words;
+        let low_confidence = word_count < self.config.min_word_count;
+        let sentence_count = totals.sentences;
+        let paragraph_count = totals.paragraphs;
+        let per_word = |count: usize, words: usize| if words > 0 { count as f64 / words as f64 } else { 0.0 };
+        let per_sentence = |count: usize| if sentence_count > 0 { count as f64 / sentence_count as f64 } else { 0.0 };
 
         // Calculate complexity metrics
-        let avg_words_per_sentence = if sentence_count > 0 { word_count as f64 / sentence_count as f64 } else { 0.0 };
-        let avg_syllables_per_word = self.calculate_avg_syllables(&words);
-        let unique_words: std::collections::HashSet<_> = words.iter().map(|w| w.to_lowercase()).collect();
-        let unique_word_ratio = if word_count > 0 { unique_words.len() as f64 / word_count as f64 } else { 0.0 };
+        let avg_words_per_sentence = per_sentence(word_count);
+        let avg_syllables_per_word = per_word(totals.syllables, measured);
+        let unique_word_ratio = per_word(totals.vocabulary.len(), word_count);
+        let lexical_density = per_word(totals.content_words, measured);
+
+        // Without a word or a sentence the formulas give their intercepts
+        // ("!!! ???" would read as 206.8), so every score is zero instead
+        let scorable = word_count > 0 && sentence_count > 0;
 
         // Flesch Reading Ease
-        let flesch_reading_ease = 206.835 - 1.015 * avg_words_per_sentence - 84.6 * avg_syllables_per_word;
-        
+        let flesch_reading_ease =
+            if scorable { self.language().reading_ease.score(avg_words_per_sentence, avg_syllables_per_word) } else { 0.0 };
+        let mut warnings = Vec::new();
+        let (readability_score, clamp_warning) = readability::clamp_reading_ease(
+            flesch_reading_ease,
+            &ReadabilityInputs {
+                words: measured,
+                longest_word: totals.longest_word,
+                letters: totals.alphabetic,
+                non_latin_letters: totals.non_latin,
+                terminal_punctuation: totals.terminal_punctuation,
+                avg_words_per_sentence,
+                avg_syllables_per_word,
+            },
+        );
+        warnings.extend(clamp_warning);
+
+        let disabled_rules = patterns::report(&self.pattern_failures).disabled_rules;
+        if !disabled_rules.is_empty() {
+            warnings.push(AnalysisWarning::new(
+                "rules_disabled",
+                format!("Analysis ran with disabled rules after pattern compile errors: {}", disabled_rules.join(", ")),
+            ));
+        }
+
+        warnings.extend(opaque::warning(totals.first_opaque.as_deref().unwrap_or_default(), &totals.opaque_tokens, self.config.max_token_length));
+        warnings.extend(totals.foreign.as_ref().and_then(|foreign| foreign.warning(word_count)));
+        if low_confidence {
+            warnings.push(AnalysisWarning::new(
+                "low_confidence",
+                format!(
+                    "Only {} words; metrics are unreliable below {} and document-level suggestions are withheld.",
+                    word_count, self.config.min_word_count
+                ),
+            ));
+        }
+
         // Fog Index
-        let complex_words = words.iter().filter(|w| self.count_syllables(w) >= 3).count();
-        let fog_index = 0.4 * (avg_words_per_sentence + 100.0 * (complex_words as f64 / word_count as f64));
+        let fog_index = if scorable { 0.4 * (avg_words_per_sentence + 100.0 * per_word(totals.complex_words, measured)) } else { 0.0 };
+        let sentence_length_std_dev = readability::std_dev(&totals.sentence_lengths);
+
+        // Grade-level formulas
+        let grades = readability::grade_levels(&GradeInputs {
+            words: measured,
+            sentences: sentence_count,
+            letters: totals.letters,
+            polysyllables: totals.polysyllables,
+            long_words: totals.long_words,
+        });
 
         // Style metrics
-        let passive_voice_matches = self.passive_voice_patterns.find_iter(text).count();
-        let passive_voice_ratio = if sentence_count > 0 { passive_voice_matches as f64 / sentence_count as f64 } else { 0.0 };
-        
-        let adverb_matches = self.adverb_patterns.find_iter(text).count();
-        let adverb_ratio = if word_count > 0 { adverb_matches as f64 / word_count as f64 } else { 0.0 };
-        
-        let dialogue_matches = self.dialogue_patterns.find_iter(text).count();
-        let dialogue_ratio = if paragraph_count > 0 { dialogue_matches as f64 / paragraph_count as f64 } else { 0.0 };
+        let passive_voice_ratio = per_sentence(totals.passive_voice);
+        let adverb_ratio = per_word(totals.adverbs, word_count);
+
+        let analysis = &self.config.analysis;
+        for (code, label, ratio, level) in [
+            ("adverb_ratio_high", "Adverb ratio", adverb_ratio, analysis.adverb_ratio_warning_level),
+            ("passive_voice_ratio_high", "Passive voice ratio", passive_voice_ratio, analysis.passive_voice_ratio_warning_level),
+        ] {
+            if level.is_some_and(|level| ratio > level) {
+                warnings.push(AnalysisWarning::new(
+                    code,
+                    format!("{} of {:.3} is above the configured warning level of {:.3}.", label, ratio, level.unwrap_or_default()),
+                ));
+            }
+        }
+
+        // Reading and speaking time
+        let pauses = paragraph_count.saturating_sub(1) as f64 * analysis.paragraph_pause_seconds;
+        // A non-positive speed from the options contributes nothing rather than infinity
+        let minutes = |words: usize, per_minute: f64| if per_minute > 0.0 { words as f64 / per_minute } else { 0.0 };
+        let reading_minutes = minutes(word_count - totals.dialogue_words, analysis.reading_words_per_minute)
+            + minutes(totals.dialogue_words, analysis.reading_words_per_minute * analysis.dialogue_reading_speedup);
+        let estimated_reading_time_seconds = (reading_minutes * 60.0 + pauses).round();
+        let estimated_speaking_time_seconds = (minutes(word_count, analysis.speaking_words_per_minute) * 60.0 + pauses).round();
 
-        // Generate content hash
-        let content_hash = self.generate_content_hash(text);
+        let first_person_per_1000_words = self.config.person_pronoun_rule_active().then(|| per_word(totals.first_person * 1000, word_count));
 
         TextAnalysisResult {
             word_count,
-            character_count,
+            character_count: totals.characters,
+            grapheme_count: totals.graphemes,
             paragraph_count,
             sentence_count,
-            readability_score: flesch_reading_ease,
+            estimated_reading_time_seconds,
+            estimated_speaking_time_seconds,
+            readability_score,
+            readability_score_raw: flesch_reading_ease,
             complexity_metrics: ComplexityMetrics {
                 avg_words_per_sentence,
                 avg_syllables_per_word,
                 fog_index,
                 flesch_reading_ease,
                 unique_word_ratio,
+                content_word_count: totals.content_words,
+                unique_content_word_ratio: per_word(totals.content_vocabulary.len(), totals.content_words),
+                avg_content_word_length: per_word(totals.content_characters, totals.content_words),
+                smog_index: grades.smog_index,
+                coleman_liau_index: grades.coleman_liau_index,
+                automated_readability_index: grades.automated_readability_index,
+                lix: grades.lix,
+                sentence_length_std_dev,
+                mtld: diversity::mtld(&totals.word_ids),
+                mattr: diversity::mattr(&totals.word_ids, self.config.analysis.mattr_window),
+                lexical_density,
             },
             style_metrics: StyleMetrics {
                 passive_voice_ratio,
                 adverb_ratio,
-                dialogue_ratio,
-                action_ratio: 0.0, // Would need more sophisticated analysis
-                description_ratio: 0.0, // Would need more sophisticated analysis
+                dialogue_ratio: per_sentence(totals.dialogue_sentences),
+                action_ratio: per_sentence(totals.action_sentences),
+                description_ratio: per_sentence(totals.description_sentences),
+                filter_word_ratio: per_sentence(totals.filtered_sentences),
+                cliches_per_1000_words: per_word(totals.cliches * 1000, word_count),
             },
             content_hash,
+            first_person_per_1000_words,
+            warnings,
+            low_confidence,
+            not_surfaced: self.not_surfaced_rules(),
+            sentiment_score: if word_count > 0 { totals.valence / word_count as f64 } else { 0.0 },
+            detected_language: None,
+            progress_to_target: None,
         }
     }
 
-    fn generate_optimization_suggestions(&self, text: &str) -> Vec<OptimizationSuggestion> {
-        let mut suggestions = Vec::new();
-        
-        // Find overly long sentences
-        for (i, sentence) in text.split('.').enumerate() {
-            let word_count = self.word_patterns.find_iter(sentence).count();
-            if word_count > 25 {
-                suggestions.push(OptimizationSuggestion {
-                    suggestion_type: "sentence_length".to_string(),
-                    priority: "medium".to_string(),
-                    message: "Consider breaking this long sentence into shorter ones for better readability.".to_string(),
-                    start_pos: i * 50, // Approximate position
-                    end_pos: (i + 1) * 50,
-                    suggested_replacement: None,
-                });
-            }
-        }
-
-        // Find passive voice usage
-        for mat in self.passive_voice_patterns.find_iter(text) {
-            suggestions.push(OptimizationSuggestion {
-                suggestion_type: "passive_voice".to_string(),
-                priority: "low".to_string(),
-                message: "Consider using active voice for more engaging writing.".to_string(),
-                start_pos: mat.start(),
-                end_pos: mat.end(),
-                suggested_replacement: None,
-            });
-        }
-
-        // Find adverb overuse
-        for mat in self.adverb_patterns.find_iter(text) {
-            suggestions.push(OptimizationSuggestion {
-                suggestion_type: "adverb_usage".to_string(),
-                priority: "low".to_string(),
-                message: "Consider using stronger verbs instead of adverbs.".to_string(),
-                start_pos: mat.start(),
-                end_pos: mat.end(),
-                suggested_replacement: None,
-            });
+    fn build_chapter_manifest(&self, text: &str) -> ChapterManifest {
+        let index = self.index(text);
+        let chapters = structure::detect_sections(text, &self.heading_patterns)
+            .into_iter()
+            .enumerate()
+            .map(|(i, section)| ManifestEntry {
+                index: i,
+                heading: section.heading,
+                level: section.level,
+                start_pos: section.start,
+                end_pos: section.end,
+                word_count: index.words_in(section.start, section.end).len(),
+                content_hash: self.generate_content_hash(&text[section.start..section.end]),
+            })
+            .collect();
+
+        ChapterManifest {
+            version: manifest::MANIFEST_VERSION,
+            document_hash: self.generate_content_hash(text),
+            chapters,
         }
+    }
 
-        suggestions
+    /// `(start, end, word_count, total_syllables)` per sentence, leaving out
+    /// opaque tokens.
+    fn sentence_counts(&self, index: &DocumentIndex) -> Vec<(usize, usize, usize, usize)> {
+        index
+            .sentences
+            .iter()
+            .map(|&(start, end)| {
+                let words: Vec<&str> =
+                    index.words_in(start, end).iter().map(|&(s, e)| &index.text[s..e]).filter(|w| !self.is_opaque(w)).collect();
+                let syllables = words.iter().map(|w| self.count_syllables(w)).sum();
+                (start, end, words.len(), syllables)
+            })
+            .collect()
     }
 
-    fn auto_resolve_conflicts(&self, conflicts: Vec<CollaborationConflict>) -> Vec<CollaborationConflict> {
-        let mut resolved_conflicts = Vec::new();
-        
-        for mut conflict in conflicts {
-            // Simple conflict resolution algorithm
-            match conflict.conflict_type.as_str() {
-                "text_insertion" => {
-                    // Merge both insertions with proper spacing
-                    conflict.resolution_suggestion = format!("{} {}", conflict.user_a_change, conflict.user_b_change);
-                },
-                "text_deletion" => {
-                    // Keep the shorter deletion (less destructive)
-                    if conflict.user_a_change.len() < conflict.user_b_change.len() {
-                        conflict.resolution_suggestion = conflict.user_a_change.clone();
-                    } else {
-                        conflict.resolution_suggestion = conflict.user_b_change.clone();
-                    }
-                },
-                "text_modification" => {
-                    // Use timestamp to determine which change to keep
-                    conflict.resolution_suggestion = conflict.user_b_change.clone(); // Most recent
-                },
-                _ => {
-                    conflict.resolution_suggestion = "Manual resolution required".to_string();
-                }
+    fn sentence_difficulties(&self, index: &DocumentIndex) -> Vec<SentenceDifficulty> {
+        readability::sentence_difficulties(&self.sentence_counts(index), self.config.difficulty_normalization)
+    }
+
+    fn sentence_readability(&self, index: &DocumentIndex) -> Vec<SentenceReadability> {
+        let text = index.text;
+        let masked = opaque::mask(text, &self.opaque_tokens(index));
+        let passive = self.passive_by_sentence(index, &masked);
+        self.sentence_counts(index)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (start, end, words, syllables))| {
+                let has_passive = passive.as_ref().is_some_and(|p| !p[i].is_empty());
+                readability::sentence_readability(start, end, words, syllables, has_passive, self.language().reading_ease)
+            })
+            .collect()
+    }
+
+    /// `source` is the text `index` was prepared from. Markdown paragraphs
+    /// follow its block structure rather than the line breaks of
+    /// hard-wrapped text.
+    fn paragraph_analyses(&self, index: &DocumentIndex, source: Option<(&OffsetMap, &str)>) -> Vec<ParagraphAnalysis> {
+        let text = index.text;
+        let spans = match source.filter(|_| self.config.input_format == InputFormat::Markdown) {
+            Some((map, source)) => paragraphs::markdown_paragraphs(text, &self.paragraph_patterns, map, source),
+            None => index.paragraphs.clone(),
+        };
+        let dialogue_spans = self.dialogue_spans(text);
+        let modes = narrative::sentence_modes(index, &narrative::word_classes(index), &dialogue_spans);
+        spans
+            .into_iter()
+            .map(|(start, end)| {
+                let words = index.words_in(start, end);
+                let measured: Vec<&str> = words.iter().map(|&(s, e)| &text[s..e]).filter(|w| !self.is_opaque(w)).collect();
+                let sentence_modes: Vec<SentenceMode> = index
+                    .sentences
+                    .iter()
+                    .zip(&modes)
+                    .filter(|&(&(s, e), _)| s < end && e > start)
+                    .map(|(_, &mode)| mode)
+                    .collect();
+                let counts = ParagraphCounts {
+                    word_count: words.len(),
+                    measured_words: measured.len(),
+                    syllables: measured.iter().map(|w| self.count_syllables(w)).sum(),
+                    sentence_modes: &sentence_modes,
+                    has_dialogue: dialogue_spans.iter().any(|&(s, e)| s < end && e > start),
+                };
+                paragraphs::paragraph_analysis(start, end, &counts, self.language().reading_ease)
+            })
+            .collect()
+    }
+
+    fn build_optimization_report(&self, text: &str) -> OptimizationReport {
+        self.filtered_optimization_report(text, &|_| true)
+    }
+
+    /// Only rules `wants` accepts are run and reported.
+    fn filtered_optimization_report(&self, text: &str, wants: &dyn Fn(&str) -> bool) -> OptimizationReport {
+        let mut report = match self.prepared(text) {
+            Some(prepared) => self.prepared_optimization_report(text, &prepared, wants),
+            None => self.optimization_report_for(text, wants),
+        };
+        if self.config.context.enabled {
+            let index = self.index(text);
+            report.context_payload_bytes =
+                Some(context::attach(&index, &mut report.suggestions, &self.config.context));
+        }
+        let positions = TextPositions::new(text);
+        for suggestion in &mut report.suggestions {
+            suggestion.locate(&positions);
+        }
+        report
+    }
+
+    /// The report for the paragraphs around `start..end`, in positions of the
+    /// whole text, keeping only suggestions that overlap the selection.
+    fn selection_optimization_report(&self, text: &str, start: usize, end: usize, wants: &dyn Fn(&str) -> bool) -> OptimizationReport {
+        // Whole paragraphs, so sentence and paragraph rules see complete units
+        let index = self.index(text);
+        let from = index.paragraphs.iter().find(|p| p.1 > start).map_or(start, |p| p.0.min(start));
+        let to = index.paragraphs.iter().rev().find(|p| p.0 < end.max(start + 1)).map_or(end, |p| p.1.max(end));
+        let mut report = self.filtered_optimization_report(&text[from..to], wants);
+
+        report.suggestions.retain(|s| s.start_pos + from < end.max(start + 1) && s.end_pos + from > start);
+        shift_suggestions(&mut report.suggestions, from, &TextPositions::new(text));
+        report
+    }
+
+    // Suggestions are computed on the prepared text and mapped back
+    fn prepared_optimization_report(&self, text: &str, prepared: &PreparedText, wants: &dyn Fn(&str) -> bool) -> OptimizationReport {
+        let mut report = self.optimization_report_for(&prepared.text, wants);
+        let map = &prepared.offset_map;
+        // Spacing left by stripped markup ("a `code` span") isn't in the source
+        report.suggestions.retain(|s| {
+            if s.scope != SCOPE_INSTANCE || !typography::RULE_IDS.contains(&s.suggestion_type.as_ref()) {
+                return true;
+            }
+            let (start, end) = map.map_span(s.start_pos, s.end_pos);
+            text[start..end] == prepared.text[s.start_pos..s.end_pos]
+        });
+        for suggestion in &mut report.suggestions {
+            if (suggestion.start_pos, suggestion.end_pos) == (0, prepared.text.len()) {
+                suggestion.end_pos = text.len();
+            } else {
+                (suggestion.start_pos, suggestion.end_pos) = map.map_span(suggestion.start_pos, suggestion.end_pos);
+            }
+            for span in &mut suggestion.related_spans {
+                (span.start_pos, span.end_pos) = map.map_span(span.start_pos, span.end_pos);
             }
-            
-            resolved_conflicts.push(conflict);
         }
-        
-        resolved_conflicts
+        report
     }
 
-    fn count_syllables(&self, word: &str) -> usize {
-        let vowels = "aeiouyAEIOUY";
-        let mut syllable_count = 0;
-        let mut prev_was_vowel = false;
+    fn not_surfaced_rules(&self) -> Vec<String> {
+        if self.config.profile.surfaces_style_rules() {
+            return Vec::new();
+        }
+        capabilities::RULES
+            .iter()
+            .filter(|r| r.category == capabilities::CATEGORY_STYLE)
+            .map(|r| r.id.to_string())
+            .collect()
+    }
+
+    fn optimization_report_for(&self, text: &str, wants: &dyn Fn(&str) -> bool) -> OptimizationReport {
+        if text.trim().is_empty() {
+            return OptimizationReport { low_confidence: true, ..OptimizationReport::default() };
+        }
+        let mut suggestions = self.generate_optimization_suggestions(text, wants);
+        if !self.config.profile.surfaces_style_rules() {
+            suggestions.retain(|s| capabilities::rule_category(&s.suggestion_type) != capabilities::CATEGORY_STYLE);
+        }
+        let word_count = self.words.count(text);
+
+        // English-specific rules say nothing useful about a Spanish paragraph
+        let suppressed_by_language = match self.paragraph_languages(&self.index(text)) {
+            Some(map) => map.exclude_foreign(&mut suggestions),
+            None => 0,
+        };
+
+        // Protect deliberate dialect spellings inside quoted speech
+        let dialogue_spans = self.dialogue_spans(text);
+        let suppressed_by_dialect = suggestions::filter_dialect(&mut suggestions, text, &dialogue_spans, &self.config);
+
+        // Escalate rules that are a document-level problem rather than isolated hits
+        let rule_summaries = suggestions::escalate_by_density(&mut suggestions, word_count, text.len(), &self.config);
+
+        // A handful of words is not enough evidence for a document-level claim
+        let low_confidence = word_count < self.config.min_word_count;
+        if low_confidence {
+            suggestions.retain(|s| s.scope != SCOPE_DOCUMENT);
+        }
+
+        suggestions::assign_fingerprints(&mut suggestions, text);
+
+        OptimizationReport {
+            suggestions,
+            rule_summaries,
+            suppressed_by_dialect,
+            suppressed_by_language,
+            low_confidence,
+            context_payload_bytes: None,
+        }
+    }
+
+    /// Detectors for rules `wants` rejects don't run at all; passes covering
+    /// several rules run when any of them is wanted and are filtered after.
+    fn generate_optimization_suggestions(&self, text: &str, wants: &dyn Fn(&str) -> bool) -> Vec<OptimizationSuggestion> {
+        let mut suggestions = Vec::new();
+        let index = self.index(text);
+        // Style patterns never run over pasted data
+        let masked = opaque::mask(text, &self.opaque_tokens(&index));
+        let dialogue_spans = self.dialogue_spans(text);
         
-        for ch in word.chars() {
-            let is_vowel = vowels.contains(ch);
-            if is_vowel && !prev_was_vowel {
-                syllable_count += 1;
+        // Find overly long sentences
+        if wants("sentence_length") {
+            for &(start, end) in &index.sentences {
+                if index.words_in(start, end).len() > self.config.analysis.long_sentence_threshold {
+                    suggestions.push(OptimizationSuggestion::instance(
+                        "sentence_length",
+                        self.config.analysis.sentence_length_priority.as_str(),
+                        "Consider breaking this long sentence into shorter ones for better readability.",
+                        start,
+                        end,
+                    ));
+                }
             }
-            prev_was_vowel = is_vowel;
         }
-        
-        // Handle silent 'e' at the end
-        if word.ends_with('e') && syllable_count > 1 {
-            syllable_count -= 1;
+
+        // Find passive voice usage, rewritten to active where the clause is simple
+        if wants("passive_voice") {
+            for (start, end) in self.passive_matches(&masked) {
+                let rewrite = index
+                    .sentences
+                    .iter()
+                    .find(|s| s.0 <= start && end <= s.1)
+                    .and_then(|&sentence| rewrite::passive_to_active(&masked, sentence, start, end));
+                suggestions.push(with_rewrite(
+                    OptimizationSuggestion::instance(
+                        "passive_voice",
+                        self.config.analysis.passive_voice_priority.as_str(),
+                        "Consider using active voice for more engaging writing.",
+                        start,
+                        end,
+                    ),
+                    rewrite,
+                ));
+            }
         }
-        
-        std::cmp::max(syllable_count, 1)
+
+        // Find adverb overuse, with a stronger verb for common pairings
+        if wants("adverb_usage") {
+            for (start, end) in self.adverbs_by_sentence(&index, &masked).into_iter().flatten().flatten() {
+                suggestions.push(with_rewrite(
+                    OptimizationSuggestion::instance(
+                        "adverb_usage",
+                        self.config.analysis.adverb_priority.as_str(),
+                        "Consider using stronger verbs instead of adverbs.",
+                        start,
+                        end,
+                    ),
+                    rewrite::stronger_verb(&masked, start, end),
+                ));
+            }
+        }
+
+        // Perception verbs that tell rather than show
+        if wants("filter_word") {
+            for (start, end) in narrative::filter_words(&index, &narrative::word_classes(&index), &dialogue_spans).into_iter().flatten() {
+                suggestions.push(OptimizationSuggestion::instance(
+                    "filter_word",
+                    "low",
+                    format!(
+                        "\"{}\" filters the scene through a character's perception; showing what they sense directly brings the reader closer.",
+                        &text[start..end]
+                    ),
+                    start,
+                    end,
+                ));
+            }
+        }
+
+        // Said-bookisms and adverbs in dialogue tags
+        if wants("dialogue_tag") {
+            suggestions.extend(attribution::suggestions(&attribution::analyze(text, &dialogue_spans)));
+        }
+
+        // Filler words and wordy phrases
+        if wants("filler_word") {
+            suggestions.extend(self.fillers.suggestions(&masked));
+        }
+
+        // Stock phrases
+        if wants("cliche") {
+            suggestions.extend(self.cliches.suggestions(&index));
+        }
+
+        // Weak verb constructions and buried verbs
+        if wants("weak_verb") || wants("nominalization") {
+            suggestions.extend(self.weak_verbs.suggestions(&masked));
+        }
+        if wants("expletive_opener") {
+            suggestions.extend(verbs::expletive_openers(&index));
+        }
+
+        // Words missing from the dictionary
+        if wants("possible_typo") && self.document_language(&index) == lang::ENGLISH.code {
+            suggestions.extend(self.dictionary.suggestions(&index, &dialogue_spans, |w| self.is_opaque(w), &self.config));
+        }
+
+        // Words spelled more than one way
+        if wants("consistency") {
+            suggestions.extend(self.variant_groups.suggestions(&masked));
+        }
+
+        // Watchlist terms
+        if !self.config.flagged_terms.is_empty() && wants("flagged_term") {
+            for hit in flagged::find_flagged_terms(text, &self.config.flagged_terms, self.config.fuzzy_flagged_terms) {
+                suggestions.push(OptimizationSuggestion::instance(
+                    "flagged_term",
+                    "medium",
+                    format!("\"{}\" is on the flagged-terms list.", hit.term),
+                    hit.start_pos,
+                    hit.end_pos,
+                ));
+            }
+        }
+
+        // Paragraph-level structure and mechanics
+        if wants("unbalanced_quotes") {
+            suggestions.extend(structural::unbalanced_quotes(&index));
+        }
+        if wants("repeated_paragraph") {
+            suggestions.extend(structural::repeated_paragraphs(&index));
+        }
+        if wants("missing_punctuation") {
+            suggestions.extend(structural::missing_punctuation(&index));
+        }
+
+        // Spacing, punctuation, and quote-style mechanics
+        if typography::RULE_IDS.iter().any(|id| wants(id)) {
+            suggestions.extend(typography::suggestions(&index, &self.opaque_tokens(&index)));
+        }
+
+        // Chapters opening or closing on the same beat
+        if wants("chapter_opener_echo") || wants("chapter_closer_echo") {
+            let sections = structure::detect_sections(text, &self.heading_patterns);
+            if sections.len() >= 2 {
+                suggestions.extend(echoes::chapter_echoes(&index, &sections, self.config.echo_similarity_threshold));
+            }
+        }
+
+        // Acronyms used repeatedly without ever being spelled out
+        if wants("undefined_acronym") {
+            suggestions.extend(acronyms::undefined_acronyms(&index, &self.config.ignored_acronyms));
+        }
+
+        // Distinctive words repeated close together, and monotonous openers
+        if wants("repetitive_opener") {
+            suggestions.extend(repetition::repetitive_openers(&index, &dialogue_spans));
+        }
+        if self.config.analysis.word_echo_window > 0 && wants("word_echo") {
+            suggestions.extend(repetition::word_echoes(
+                &index,
+                &self.stopwords,
+                &dialogue_spans,
+                |w| self.is_opaque(w),
+                self.config.analysis.word_echo_window,
+            ));
+        }
+
+        // Flag first- and second-person pronouns in body text for formal profiles
+        if self.config.person_pronoun_rule_active() && (wants("first_person") || wants("second_person")) {
+            suggestions.extend(self.person_pronoun_suggestions(&masked));
+        }
+
+        suggestions.retain(|s| wants(&s.suggestion_type));
+        suggestions
+    }
+
+    /// Returns `(start, end, is_first_person)` for every personal pronoun
+    /// outside quoted material and block citations, minus configured allowances.
+    fn person_pronoun_hits(&self, text: &str) -> Vec<(usize, usize, bool)> {
+        let Some(pronouns) = &self.person_pronoun_patterns else {
+            return Vec::new();
+        };
+        let ignored = IgnoreRanges::detect(text, &self.dialogue_spans(text));
+
+        pronouns
+            .find_iter(text)
+            .filter(|m| !ignored.contains(m.start(), m.end()))
+            // "US" is almost always the country, not the pronoun
+            .filter(|m| m.as_str() != "US")
+            .filter(|m| !self.config.person_pronouns.allowed.iter().any(|a| a.eq_ignore_ascii_case(m.as_str())))
+            .map(|m| {
+                let is_first_person = !m.as_str().to_lowercase().starts_with("you");
+                (m.start(), m.end(), is_first_person)
+            })
+            .collect()
     }
 
-    fn calculate_avg_syllables(&self, words: &[&str]) -> f64 {
-        if words.is_empty() {
-            return 0.0;
+    fn person_pronoun_suggestions(&self, text: &str) -> Vec<OptimizationSuggestion> {
+        let hits = self.person_pronoun_hits(text);
+        let mut suggestions = Vec::new();
+
+        for &(start, end, is_first_person) in &hits {
+            let (suggestion_type, message) = if is_first_person {
+                ("first_person", "First-person pronouns are usually avoided in formal writing; consider an impersonal construction.")
+            } else {
+                ("second_person", "Second-person address is usually avoided in formal writing; consider addressing the reader indirectly.")
+            };
+            suggestions.push(OptimizationSuggestion::instance(suggestion_type, "low", message, start, end));
         }
-        
-        let total_syllables: usize = words.iter().map(|w| self.count_syllables(w)).sum();
-        total_syllables as f64 / words.len() as f64
+
+        // One document-level count per pronoun class
+        for (suggestion_type, label, first) in [("first_person", "first-person", true), ("second_person", "second-person", false)] {
+            let count = hits.iter().filter(|h| h.2 == first).count();
+            if count > 0 {
+                suggestions.push(OptimizationSuggestion::document(
+                    suggestion_type,
+                    "low",
+                    format!("{} {} pronoun(s) found in body text.", count, label),
+                    text.len(),
+                ));
+            }
+        }
+
+        suggestions
+    }
+
+    /// Resolved conflicts sorted by `start_pos`, with overlapping ones merged
+    /// into composites first.
+    fn auto_resolve_conflicts(&self, conflicts: Vec<CollaborationConflict>, policy: ConflictPolicy) -> Vec<CollaborationConflict> {
+        resolution::merge_overlapping(conflicts)
+            .into_iter()
+            .map(|conflict| CollaborationConflict { resolution_suggestion: resolution::resolve(&conflict, policy), ..conflict })
+            .collect()
+    }
+
+    fn count_syllables(&self, word: &str) -> usize {
+        self.language().count_syllables(word)
     }
 }
 
-// Export the main functions
-#[wasm_bindgen(start)]
-pub fn main() {
-    console_log!("OmniAuthor Rust Engine initialized successfully!");
-}
\ No newline at end of file
+fn note_progress(result: &mut TextAnalysisResult, front_matter: Option<&FrontMatter>) {
+    result.progress_to_target = front_matter.and_then(FrontMatter::target_words).map(|target| result.word_count as f64 / target);
+}
+
+fn map_sentence_spans(sentences: &mut [SentenceDifficulty], prepared: &PreparedText) {
+    for sentence in sentences {
+        (sentence.start_pos, sentence.end_pos) = prepared.offset_map.map_span(sentence.start_pos, sentence.end_pos);
+    }
+}
+
+/// Moves suggestions computed on a slice starting at `by` into positions of
+/// the whole text.
+fn shift_suggestions(suggestions: &mut [OptimizationSuggestion], by: usize, positions: &TextPositions) {
+    for suggestion in suggestions {
+        suggestion.start_pos += by;
+        suggestion.end_pos += by;
+        for span in &mut suggestion.related_spans {
+            span.start_pos += by;
+            span.end_pos += by;
+        }
+        if let Some(context) = &mut suggestion.context {
+            context.start_pos += by;
+        }
+        suggestion.locate(positions);
+    }
+}
@@ -0,0 +1,206 @@
+//! Per-chapter content hashes so clients can re-upload only what changed and
+//! the server can keep per-chapter comments attached across reorderings.
+
+use serde::{Deserialize, Serialize};
+
+pub const MANIFEST_VERSION: u32 = 1;
+
+pub const STATUS_UNCHANGED: &str = "unchanged";
+pub const STATUS_MODIFIED: &str = "modified";
+pub const STATUS_MOVED: &str = "moved";
+pub const STATUS_ADDED: &str = "added";
+pub const STATUS_REMOVED: &str = "removed";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ManifestEntry {
+    pub index: usize,
+    pub heading: Option<String>,
+    pub level: usize,
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub word_count: usize,
+    pub content_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ChapterManifest {
+    pub version: u32,
+    pub document_hash: String,
+    pub chapters: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChapterChange {
+    pub status: String,
+    pub old_index: Option<usize>,
+    pub new_index: Option<usize>,
+    pub heading: Option<String>,
+    pub content_hash: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ManifestDiff {
+    pub document_changed: bool,
+    pub changes: Vec<ChapterChange>,
+    pub manifest: ChapterManifest,
+}
+
+/// Longest common subsequence of two hash sequences, as matched index pairs.
+pub fn lcs_pairs(old: &[&str], new: &[&str]) -> Vec<(usize, usize)> {
+    let (n, m) = (old.len(), new.len());
+    let mut table = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if old[i] == new[j] { table[i + 1][j + 1] + 1 } else { table[i + 1][j].max(table[i][j + 1]) };
+        }
+    }
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Classifies chapters of `new` relative to `old`. Chapters whose hash is in
+/// the longest common ordered subsequence are unchanged even if their index
+/// shifted; hash matches outside it were moved. Remaining chapters pair up by
+/// heading as modified, and anything left over was added or removed.
+pub fn diff(old: &ChapterManifest, new: ChapterManifest) -> ManifestDiff {
+    let old_hashes: Vec<&str> = old.chapters.iter().map(|c| c.content_hash.as_str()).collect();
+    let new_hashes: Vec<&str> = new.chapters.iter().map(|c| c.content_hash.as_str()).collect();
+
+    let mut old_match: Vec<Option<(usize, &str)>> = vec![None; old.chapters.len()];
+    let mut new_match: Vec<Option<(usize, &str)>> = vec![None; new.chapters.len()];
+
+    for (i, j) in lcs_pairs(&old_hashes, &new_hashes) {
+        old_match[i] = Some((j, STATUS_UNCHANGED));
+        new_match[j] = Some((i, STATUS_UNCHANGED));
+    }
+
+    for j in 0..new.chapters.len() {
+        if new_match[j].is_some() {
+            continue;
+        }
+        if let Some(i) = (0..old.chapters.len()).find(|&i| old_match[i].is_none() && old_hashes[i] == new_hashes[j]) {
+            old_match[i] = Some((j, STATUS_MOVED));
+            new_match[j] = Some((i, STATUS_MOVED));
+        }
+    }
+
+    for (j, chapter) in new.chapters.iter().enumerate() {
+        if new_match[j].is_some() {
+            continue;
+        }
+        let heading = &chapter.heading;
+        let candidate = (0..old.chapters.len())
+            .find(|&i| old_match[i].is_none() && old.chapters[i].heading == *heading);
+        if let Some(i) = candidate {
+            old_match[i] = Some((j, STATUS_MODIFIED));
+            new_match[j] = Some((i, STATUS_MODIFIED));
+        }
+    }
+
+    let mut changes: Vec<ChapterChange> = new
+        .chapters
+        .iter()
+        .enumerate()
+        .map(|(j, chapter)| {
+            let (old_index, status) = match new_match[j] {
+                Some((i, status)) => (Some(i), status),
+                None => (None, STATUS_ADDED),
+            };
+            ChapterChange {
+                status: status.to_string(),
+                old_index,
+                new_index: Some(j),
+                heading: chapter.heading.clone(),
+                content_hash: Some(chapter.content_hash.clone()),
+            }
+        })
+        .collect();
+
+    for (i, chapter) in old.chapters.iter().enumerate() {
+        if old_match[i].is_none() {
+            changes.push(ChapterChange {
+                status: STATUS_REMOVED.to_string(),
+                old_index: Some(i),
+                new_index: None,
+                heading: chapter.heading.clone(),
+                content_hash: Some(chapter.content_hash.clone()),
+            });
+        }
+    }
+
+    ManifestDiff {
+        document_changed: old.document_hash != new.document_hash,
+        changes,
+        manifest: new,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    const ONE: &str = "# One\nThe storm came in the night.\n";
+    const TWO: &str = "# Two\nMara walked the field at dawn.\n";
+    const THREE: &str = "# Three\nThe letter arrived too late.\n";
+    const FOUR: &str = "# Four\nHer brother read it twice.\n";
+
+    fn statuses(diff: &ManifestDiff) -> Vec<(&str, Option<usize>, Option<usize>)> {
+        diff.changes.iter().map(|c| (c.status.as_str(), c.old_index, c.new_index)).collect()
+    }
+
+    #[test]
+    fn reordered_chapters_keep_their_hashes_and_are_reported_as_moved() {
+        let processor = TextProcessor::new();
+        let old = processor.build_chapter_manifest(&[ONE, TWO, THREE, FOUR].concat());
+        let reordered = processor.build_chapter_manifest(&[ONE, FOUR, TWO, THREE].concat());
+
+        let mut old_hashes: Vec<&str> = old.chapters.iter().map(|c| c.content_hash.as_str()).collect();
+        let mut new_hashes: Vec<&str> = reordered.chapters.iter().map(|c| c.content_hash.as_str()).collect();
+        old_hashes.sort_unstable();
+        new_hashes.sort_unstable();
+        assert_eq!(old_hashes, new_hashes);
+        assert_ne!(old.document_hash, reordered.document_hash);
+
+        let diff = diff(&old, reordered);
+        assert!(diff.document_changed);
+        assert_eq!(
+            statuses(&diff),
+            [(STATUS_UNCHANGED, Some(0), Some(0)), (STATUS_MOVED, Some(3), Some(1)), (STATUS_UNCHANGED, Some(1), Some(2)), (STATUS_UNCHANGED, Some(2), Some(3))]
+        );
+    }
+
+    #[test]
+    fn edits_additions_and_removals_are_classified() {
+        let processor = TextProcessor::new();
+        let old = processor.build_chapter_manifest(&[ONE, TWO, THREE].concat());
+        let edited_two = "# Two\nMara ran across the field at dawn.\n";
+        let five = "# Five\nAn ending.\n";
+        let diff = diff(&old, processor.build_chapter_manifest(&[ONE, edited_two, five].concat()));
+        assert_eq!(
+            statuses(&diff),
+            [(STATUS_UNCHANGED, Some(0), Some(0)), (STATUS_MODIFIED, Some(1), Some(1)), (STATUS_ADDED, None, Some(2)), (STATUS_REMOVED, Some(2), None)]
+        );
+        assert_eq!(diff.manifest.chapters[1].word_count, 8);
+
+        let same = diff_same(&processor, &[ONE, TWO].concat());
+        assert!(!same.document_changed);
+        assert!(same.changes.iter().all(|c| c.status == STATUS_UNCHANGED));
+    }
+
+    fn diff_same(processor: &TextProcessor, text: &str) -> ManifestDiff {
+        diff(&processor.build_chapter_manifest(text), processor.build_chapter_manifest(text))
+    }
+}
@@ -0,0 +1,345 @@
+//! Markdown syntax stripped ahead of analysis, so hashes, emphasis markers,
+//! link URLs, and code don't count as words. Keeps an offset map back to the
+//! Markdown source.
+
+use crate::input::StripBuilder;
+use crate::reflow::OffsetMap;
+
+/// A delimiter run of `*`, `_`, or `~` that may open or close emphasis.
+struct Delimiter {
+    marker: u8,
+    start: usize,
+    end: usize,
+    can_open: bool,
+    can_close: bool,
+}
+
+/// The marker and length of a code fence opening `line`.
+fn fence(line: &str) -> Option<(u8, usize)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let rest = &line[indent..];
+    let marker = *rest.as_bytes().first()?;
+    let len = rest.bytes().take_while(|&b| b == marker).count();
+    (indent <= 3 && matches!(marker, b'`' | b'~') && len >= 3).then_some((marker, len))
+}
+
+/// `[label]: destination` lines only define link targets.
+fn is_link_definition(line: &str) -> bool {
+    let rest = line.trim_start();
+    rest.starts_with('[') && rest.find("]:").is_some_and(|i| i > 1 && !rest[i + 2..].trim().is_empty())
+}
+
+/// Length of the heading hashes or list marker opening `line` (with its
+/// indentation and the spaces after), and whether it is a heading.
+fn block_prefix(line: &str) -> (usize, bool) {
+    let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+    let rest = &line[indent..];
+    let spaces_after = |at: usize| rest[at..].len() - rest[at..].trim_start_matches([' ', '\t']).len();
+
+    let hashes = rest.bytes().take_while(|&b| b == b'#').count();
+    if (1..=6).contains(&hashes) && (rest.len() == hashes || rest[hashes..].starts_with([' ', '\t'])) {
+        return (indent + hashes + spaces_after(hashes), true);
+    }
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    let marker_len = if rest.starts_with(['-', '*', '+']) {
+        1
+    } else if (1..=9).contains(&digits) && rest[digits..].starts_with(['.', ')']) {
+        digits + 1
+    } else {
+        return (0, false);
+    };
+    if !rest[marker_len..].starts_with([' ', '\t']) {
+        return (0, false);
+    }
+    let mut len = marker_len + spaces_after(marker_len);
+    // Task list checkboxes
+    if ["[ ] ", "[x] ", "[X] "].iter().any(|b| rest[len..].starts_with(b)) {
+        len += 4;
+    }
+    (indent + len, false)
+}
+
+/// End of `[text](destination)`, `[text][label]`, or `[text][]` opening at
+/// `open`, as (position of the closing `]`, end of the link).
+fn link_end(text: &str, open: usize, to: usize) -> Option<(usize, usize)> {
+    let b = text.as_bytes();
+    let mut depth = 0;
+    let mut i = open;
+    let close = loop {
+        if i >= to {
+            return None;
+        }
+        match b[i] {
+            b'\\' => i += 1,
+            b'[' => depth += 1,
+            b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    break i;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    };
+    let (opening, closing) = match b.get(close + 1) {
+        Some(b'(') => (b'(', b')'),
+        Some(b'[') => (b'[', b']'),
+        _ => return None,
+    };
+    let mut depth = 0;
+    for (j, &c) in b[close + 1..to].iter().enumerate() {
+        if c == opening {
+            depth += 1;
+        } else if c == closing {
+            depth -= 1;
+            if depth == 0 {
+                return Some((close, close + 1 + j + 1));
+            }
+        }
+    }
+    None
+}
+
+/// End of an autolink (`<https://...>`, `<someone@example.com>`) at `open`.
+fn autolink_end(text: &str, open: usize, to: usize) -> Option<usize> {
+    let close = open + text[open..to].find('>')?;
+    let inner = &text[open + 1..close];
+    let valid = !inner.is_empty() && !inner.contains(|c: char| c.is_whitespace() || c == '<') && inner.contains([':', '@']);
+    valid.then_some(close + 1)
+}
+
+/// Inline syntax in `from..to`: escapes, code spans, images, link and
+/// autolink syntax, and paired emphasis delimiters are dropped, and the
+/// rest kept.
+fn inline(builder: &mut StripBuilder, from: usize, to: usize) {
+    let text = builder.source;
+    let b = text.as_bytes();
+    let run = |at: usize, marker: u8| b[at..to].iter().take_while(|&&c| c == marker).count();
+
+    let mut dropped: Vec<(usize, usize)> = Vec::new();
+    let mut delimiters: Vec<Delimiter> = Vec::new();
+    // Closing `]` and end of each link whose text is being scanned
+    let mut open_links: Vec<(usize, usize)> = Vec::new();
+    let mut i = from;
+    while i < to {
+        if let Some(&(close, end)) = open_links.last().filter(|link| link.0 <= i) {
+            open_links.pop();
+            dropped.push((close, end));
+            i = i.max(end);
+            continue;
+        }
+        match b[i] {
+            b'\\' if b.get(i + 1).is_some_and(u8::is_ascii_punctuation) && i + 1 < to => {
+                dropped.push((i, i + 1));
+                i += 2;
+            }
+            b'`' => {
+                let n = run(i, b'`');
+                // A code span closes on a run of exactly the same length
+                let mut j = i + n;
+                let mut close = None;
+                while j < to {
+                    let m = if b[j] == b'`' { run(j, b'`') } else { 0 };
+                    if m == n {
+                        close = Some(j + m);
+                        break;
+                    }
+                    j += m.max(1);
+                }
+                match close {
+                    Some(end) => {
+                        dropped.push((i, end));
+                        i = end;
+                    }
+                    None => i += n,
+                }
+            }
+            b'!' if b.get(i + 1) == Some(&b'[') => match link_end(text, i + 1, to) {
+                Some((_, end)) => {
+                    dropped.push((i, end));
+                    i = end;
+                }
+                None => i += 1,
+            },
+            b'[' => {
+                if let Some(link) = link_end(text, i, to) {
+                    dropped.push((i, i + 1));
+                    open_links.push(link);
+                }
+                i += 1;
+            }
+            b'<' => match autolink_end(text, i, to) {
+                Some(end) => {
+                    dropped.push((i, end));
+                    i = end;
+                }
+                None => i += 1,
+            },
+            marker @ (b'*' | b'_' | b'~') => {
+                let n = run(i, marker);
+                let before = text[..i].chars().next_back().filter(|_| i > from);
+                let after = text[i + n..to].chars().next();
+                let left = after.is_some_and(|c| !c.is_whitespace());
+                let right = before.is_some_and(|c| !c.is_whitespace());
+                // Underscores inside a word (snake_case) are literal
+                let intraword = marker == b'_' && before.is_some_and(char::is_alphanumeric) && after.is_some_and(char::is_alphanumeric);
+                if !intraword && (marker != b'~' || n >= 2) {
+                    delimiters.push(Delimiter { marker, start: i, end: i + n, can_open: left, can_close: right });
+                }
+                i += n;
+            }
+            _ => i += 1,
+        }
+    }
+    for (close, end) in open_links {
+        dropped.push((close, end));
+    }
+
+    let mut openers: Vec<&Delimiter> = Vec::new();
+    for delimiter in &delimiters {
+        let opener = openers.iter().rposition(|o| o.marker == delimiter.marker).filter(|_| delimiter.can_close);
+        if let Some(k) = opener {
+            dropped.push((openers[k].start, openers[k].end));
+            dropped.push((delimiter.start, delimiter.end));
+            openers.truncate(k);
+        } else if delimiter.can_open {
+            openers.push(delimiter);
+        }
+    }
+
+    dropped.sort_unstable();
+    let mut cursor = from;
+    for (start, end) in dropped {
+        builder.keep(cursor, start.min(to));
+        cursor = cursor.max(end);
+    }
+    builder.keep(cursor, to);
+}
+
+/// `text` without Markdown syntax, and the map back to it. Removed lines
+/// (code fences and their contents, link definitions) keep their line
+/// break so block boundaries survive.
+pub fn strip(text: &str) -> (String, OffsetMap) {
+    let mut builder = StripBuilder::new(text);
+    let mut open_fence: Option<(u8, usize)> = None;
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let (start, content_end, end) = (line_start, line_start + content.len(), line_start + line.len());
+        line_start = end;
+
+        if let Some((marker, len)) = open_fence {
+            let closes = fence(content).is_some_and(|(m, l)| m == marker && l >= len && content.trim_start_matches(' ')[l..].trim().is_empty());
+            if closes {
+                open_fence = None;
+            }
+        } else if let Some(opened) = fence(content) {
+            open_fence = Some(opened);
+        } else if !is_link_definition(content) {
+            let (prefix, heading) = block_prefix(content);
+            let mut body_end = content_end;
+            if heading {
+                // Closing hashes ("## Title ##")
+                let trimmed = content[prefix..].trim_end();
+                let without = trimmed.trim_end_matches('#');
+                if without.len() < trimmed.len() && (without.is_empty() || without.ends_with([' ', '\t'])) {
+                    body_end = start + prefix + without.trim_end().len();
+                }
+            }
+            inline(&mut builder, start + prefix, body_end);
+        }
+        builder.keep(content_end, end);
+    }
+    builder.finish()
+}
+
+/// Whether the line break at `at` in `source` ends a Markdown block: a
+/// heading on either side of it, or a list item after it.
+pub fn is_block_break(source: &str, at: usize) -> bool {
+    if source.as_bytes().get(at) != Some(&b'\n') {
+        return false;
+    }
+    let before = source[..at].trim_end_matches('\r');
+    let line_before = &before[before.rfind('\n').map_or(0, |i| i + 1)..];
+    let after = &source[at + 1..];
+    let line_after = after[..after.find('\n').unwrap_or(after.len())].trim_end_matches('\r');
+    block_prefix(line_before).1 || block_prefix(line_after).0 > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+    use crate::config::{InputFormat, SuggestionConfig};
+    use crate::input;
+    use crate::test_support::Cases;
+
+    fn stripped(text: &str) -> String {
+        strip(text).0
+    }
+
+    /// Every byte of the stripped text maps back to the same byte of the source.
+    fn assert_maps_back(source: &str) {
+        let (text, map) = strip(source);
+        for (i, c) in text.char_indices() {
+            let original = map.to_original(i);
+            assert_eq!(source[original..].chars().next(), Some(c), "byte {} of {:?} from {:?}", i, text, source);
+        }
+    }
+
+    const DOCUMENT: &str = "# The *Storm* #\n\nMara read [the **report**](https://example.com/a_b \"title\") twice.\n\n\
+```rust\nlet x = very_long_name(1);\n```\n\n- First `code` item\n2. [x] Second item\n\n![map](map.png) See <https://example.com>.\n\n\
+[ref]: https://example.com\nsnake_case and \\*literal\\* and ~~gone~~ ~one~\n";
+
+    #[test]
+    fn syntax_is_stripped_and_prose_kept() {
+        assert_eq!(
+            stripped(DOCUMENT),
+            "The Storm\n\nMara read the report twice.\n\n\n\n\n\nFirst  item\nSecond item\n\n See .\n\n\nsnake_case and *literal* and gone ~one~\n"
+        );
+        assert_maps_back(DOCUMENT);
+    }
+
+    #[test]
+    fn unclosed_syntax_is_left_as_text() {
+        for text in ["A *lone star", "A [bracket", "`open tick", "<not a link>", "a_b_c", "#hashtag", "-dash", "1.5 times"] {
+            assert_eq!(stripped(text), text);
+        }
+        // An unclosed fence swallows the rest of the document
+        assert_eq!(stripped("Intro\n~~~\ncode\nmore"), "Intro\n\n\n");
+        assert_eq!(stripped("``a ` b``"), "");
+        assert_eq!(stripped("[text][label] and [x][]"), "text and x");
+    }
+
+    #[test]
+    fn random_markdown_maps_back_to_its_source() {
+        const PIECES: &[&str] = &["# ", "*", "**", "_", "~~", "`", "[", "](", ")", "![", "<", ">", "http://x", "\\", "\n", "```\n", "- ", "1. ", "caf\u{e9}", " ", "word"];
+        let mut cases = Cases(0x3c6e_f372_fe94_f82b);
+        for _ in 0..2000 {
+            let text: String = (0..cases.below(20)).map(|_| PIECES[cases.below(PIECES.len())]).collect();
+            assert_maps_back(&text);
+        }
+    }
+
+    #[test]
+    fn markdown_input_counts_prose_and_maps_suggestions_to_the_source() {
+        let source = "## Storm warning\n\nMara was very tired. She read [the report](https://example.com/very/long/path).\n\n\
+                      ```\nfn very_complicated_function() { return 1; }\n```\n";
+        let markdown = TextProcessor::with_patterns(&Default::default(), SuggestionConfig { input_format: InputFormat::Markdown, ..Default::default() });
+        let plain = TextProcessor::new();
+        assert_eq!(markdown.perform_analysis(source).word_count, 10);
+        assert!(plain.perform_analysis(source).word_count > 10);
+        assert_eq!(markdown.perform_analysis(source).content_hash, plain.generate_content_hash(source));
+
+        let report = markdown.build_optimization_report(source);
+        let fillers: Vec<(usize, &str)> =
+            report.suggestions.iter().filter(|s| s.suggestion_type == "filler_word").map(|s| (s.start_pos, &source[s.start_pos..s.end_pos])).collect();
+        assert_eq!(fillers, [(source.find("very").unwrap(), "very")]);
+        let prose = input::prepare(source, &markdown.config).unwrap().text;
+        let sentences = markdown.sentence_readability(&markdown.index(&prose));
+        let spans: Vec<&str> = sentences.iter().map(|s| prose[s.start_pos..s.end_pos].trim()).collect();
+        assert_eq!(spans.len(), 2, "the code fence adds no sentences: {:?}", spans);
+        assert_eq!(spans[1], "She read the report.");
+    }
+}
@@ -0,0 +1,355 @@
+//! Applying conflict resolutions to a base text, and a marked-up preview of
+//! the merge before it is applied.
+
+use serde::{Deserialize, Serialize};
+
+use crate::conflicts::RejectedConflict;
+use crate::CollaborationConflict;
+
+pub const SOURCE_USER_A: &str = "user_a";
+pub const SOURCE_USER_B: &str = "user_b";
+pub const SOURCE_AUTO_MERGE: &str = "auto_merge";
+pub const SOURCE_UNRESOLVED: &str = "unresolved";
+
+pub const MARKER_SOURCES: &[&str] = &[SOURCE_USER_A, SOURCE_USER_B, SOURCE_AUTO_MERGE, SOURCE_UNRESOLVED];
+
+const MARKER_OPEN: &str = "<<<<<<< user_a\n";
+const MARKER_SEPARATOR: &str = "\n=======\n";
+const MARKER_CLOSE: &str = "\n>>>>>>> user_b";
+
+/// What `auto_resolve_conflicts` writes when it has no rule for a conflict.
+pub const MANUAL_RESOLUTION: &str = "Manual resolution required";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SkippedConflict {
+    pub conflict_id: String,
+    /// `out_of_bounds`, `overlapping`, or (when applying strictly) `manual`.
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PreviewMarker {
+    pub conflict_id: String,
+    /// user_a, user_b, auto_merge, or unresolved.
+    pub source: String,
+    /// Span in the preview text.
+    pub start_pos: usize,
+    pub end_pos: usize,
+    /// Text that replaces the span when the marker is accepted: the
+    /// resolution under the previewed policy.
+    pub resolution: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResolutionPreview {
+    pub text: String,
+    pub markers: Vec<PreviewMarker>,
+    pub skipped: Vec<SkippedConflict>,
+    #[serde(default)]
+    pub rejected: Vec<RejectedConflict>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AppliedResolutions {
+    pub text: String,
+    pub skipped: Vec<SkippedConflict>,
+}
+
+/// Conflicts in application order. Spans past the end of the text, inside a
+/// character, or overlapping an earlier conflict are skipped; zero-width
+/// insertions at the same position are all kept, in batch order.
+fn plan<'a>(text: &str, conflicts: &'a [CollaborationConflict]) -> (Vec<&'a CollaborationConflict>, Vec<SkippedConflict>) {
+    let mut ordered: Vec<&CollaborationConflict> = conflicts.iter().collect();
+    ordered.sort_by_key(|c| (c.start_pos, c.end_pos));
+
+    let mut kept = Vec::new();
+    let mut skipped = Vec::new();
+    let mut covered_to = 0;
+    for conflict in ordered {
+        let reason = if conflict.end_pos > text.len()
+            || !text.is_char_boundary(conflict.start_pos)
+            || !text.is_char_boundary(conflict.end_pos)
+        {
+            Some("out_of_bounds")
+        } else if conflict.start_pos < covered_to {
+            Some("overlapping")
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => skipped.push(SkippedConflict {
+                conflict_id: conflict.conflict_id.clone(),
+                reason: reason.to_string(),
+            }),
+            None => {
+                covered_to = conflict.end_pos;
+                kept.push(conflict);
+            }
+        }
+    }
+    (kept, skipped)
+}
+
+/// Replaces each conflict span in the base text with its
+/// `resolution_suggestion`.
+pub fn apply(text: &str, resolved: &[CollaborationConflict]) -> AppliedResolutions {
+    let (kept, skipped) = plan(text, resolved);
+    let mut merged = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for conflict in kept {
+        merged.push_str(&text[cursor..conflict.start_pos]);
+        merged.push_str(&conflict.resolution_suggestion);
+        cursor = conflict.end_pos;
+    }
+    merged.push_str(&text[cursor..]);
+    AppliedResolutions { text: merged, skipped }
+}
+
+/// Like `apply`, but a span past the end of the text, inside a character,
+/// reversed, or overlapping another conflict is an error naming the
+/// conflict rather than a skip. Conflicts still needing manual resolution
+/// are left as they are and listed in `skipped`. Resolutions are spliced in
+/// from the end of the text back, so no span shifts before it is used.
+pub fn apply_strict(text: &str, resolved: &[CollaborationConflict]) -> Result<AppliedResolutions, String> {
+    let mut ordered: Vec<&CollaborationConflict> = resolved.iter().collect();
+    ordered.sort_by_key(|c| (c.start_pos, c.end_pos));
+    let mut previous: Option<&CollaborationConflict> = None;
+    for &conflict in &ordered {
+        let (start, end) = (conflict.start_pos, conflict.end_pos);
+        if start > end {
+            return Err(format!("Conflict \"{}\" ends ({}) before it starts ({})", conflict.conflict_id, end, start));
+        }
+        if end > text.len() {
+            return Err(format!(
+                "Conflict \"{}\" spans {}..{}, past the end of the {}-byte text",
+                conflict.conflict_id,
+                start,
+                end,
+                text.len()
+            ));
+        }
+        if !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+            return Err(format!("Conflict \"{}\" spans {}..{}, which splits a character", conflict.conflict_id, start, end));
+        }
+        if let Some(previous) = previous.filter(|p| start < p.end_pos) {
+            return Err(format!(
+                "Conflict \"{}\" ({}..{}) overlaps conflict \"{}\" ({}..{})",
+                conflict.conflict_id, start, end, previous.conflict_id, previous.start_pos, previous.end_pos
+            ));
+        }
+        previous = Some(conflict);
+    }
+
+    let mut merged = text.to_string();
+    let mut skipped = Vec::new();
+    for conflict in ordered.iter().rev() {
+        if conflict.resolution_suggestion == MANUAL_RESOLUTION {
+            skipped.push(SkippedConflict { conflict_id: conflict.conflict_id.clone(), reason: "manual".to_string() });
+        } else {
+            merged.replace_range(conflict.start_pos..conflict.end_pos, &conflict.resolution_suggestion);
+        }
+    }
+    skipped.reverse();
+    Ok(AppliedResolutions { text: merged, skipped })
+}
+
+/// Where a resolution came from, judged by which user's change
+/// it reproduces.
+fn auto_source(conflict: &CollaborationConflict) -> &'static str {
+    if conflict.resolution_suggestion == MANUAL_RESOLUTION {
+        SOURCE_UNRESOLVED
+    } else if conflict.resolution_suggestion == conflict.user_a_change {
+        SOURCE_USER_A
+    } else if conflict.resolution_suggestion == conflict.user_b_change {
+        SOURCE_USER_B
+    } else {
+        SOURCE_AUTO_MERGE
+    }
+}
+
+fn render_unresolved(conflict: &CollaborationConflict) -> String {
+    format!("{}{}{}{}{}", MARKER_OPEN, conflict.user_a_change, MARKER_SEPARATOR, conflict.user_b_change, MARKER_CLOSE)
+}
+
+/// Splices already resolved conflicts into `text` and records where each
+/// replaced region landed in the merged text, and whose change it is.
+/// Offsets accumulate the length change of every earlier replacement.
+pub fn preview(text: &str, resolved: &[CollaborationConflict]) -> ResolutionPreview {
+    let (kept, skipped) = plan(text, resolved);
+    let mut merged = String::with_capacity(text.len());
+    let mut markers = Vec::with_capacity(kept.len());
+    let mut cursor = 0;
+    for conflict in kept {
+        let source = auto_source(conflict);
+        let replacement = match source {
+            SOURCE_UNRESOLVED => render_unresolved(conflict),
+            _ => conflict.resolution_suggestion.clone(),
+        };
+
+        merged.push_str(&text[cursor..conflict.start_pos]);
+        let start_pos = merged.len();
+        merged.push_str(&replacement);
+        markers.push(PreviewMarker {
+            conflict_id: conflict.conflict_id.clone(),
+            source: source.to_string(),
+            start_pos,
+            end_pos: merged.len(),
+            resolution: conflict.resolution_suggestion.clone(),
+        });
+        cursor = conflict.end_pos;
+    }
+    merged.push_str(&text[cursor..]);
+    ResolutionPreview { text: merged, markers, skipped, rejected: Vec::new() }
+}
+
+/// Replaces every marker span in a preview with its `resolution`, giving the
+/// same text as applying the previewed resolutions to the base text.
+pub fn accept(preview: &ResolutionPreview) -> Result<String, String> {
+    let mut markers: Vec<&PreviewMarker> = preview.markers.iter().collect();
+    markers.sort_by_key(|m| m.start_pos);
+    let text = &preview.text;
+    let mut accepted = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for marker in markers {
+        if marker.start_pos < cursor
+            || marker.end_pos < marker.start_pos
+            || marker.end_pos > text.len()
+            || !text.is_char_boundary(marker.start_pos)
+            || !text.is_char_boundary(marker.end_pos)
+        {
+            return Err(format!(
+                "marker for conflict \"{}\" has an invalid span {}..{}",
+                marker.conflict_id, marker.start_pos, marker.end_pos
+            ));
+        }
+        accepted.push_str(&text[cursor..marker.start_pos]);
+        accepted.push_str(&marker.resolution);
+        cursor = marker.end_pos;
+    }
+    accepted.push_str(&text[cursor..]);
+    Ok(accepted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolution::ConflictPolicy;
+    use crate::TextProcessor;
+
+    const BASE: &str = "The storm came. Mara walked the field. The letter was late.";
+
+    fn conflict(id: &str, conflict_type: &str, span: &str, user_a_change: &str, user_b_change: &str) -> CollaborationConflict {
+        let start_pos = BASE.find(span).unwrap();
+        serde_json::from_value(serde_json::json!({
+            "conflict_id": id,
+            "conflict_type": conflict_type,
+            "start_pos": start_pos,
+            "end_pos": start_pos + span.len(),
+            "user_a_change": user_a_change,
+            "user_b_change": user_b_change,
+            "timestamp": "2024-01-01T00:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    fn resolved(policy: ConflictPolicy) -> Vec<CollaborationConflict> {
+        TextProcessor::new().auto_resolve_conflicts(vec![
+            conflict("late", "text_modification", "late", "delayed", "very late"),
+            conflict("storm", "text_modification", "storm", "gale", "tempest"),
+            conflict("walked", "text_deletion", "walked the field", "walked", "crossed the long field"),
+        ], policy)
+    }
+
+    #[test]
+    fn accepting_every_marker_reproduces_apply() {
+        assert_eq!(apply(BASE, &resolved(ConflictPolicy::Auto)).text, "The tempest came. Mara walked. The letter was very late.");
+        for name in ConflictPolicy::ALL {
+            let resolved = resolved(ConflictPolicy::parse(name).unwrap());
+            assert_eq!(accept(&preview(BASE, &resolved)).unwrap(), apply(BASE, &resolved).text, "{}", name);
+        }
+    }
+
+    #[test]
+    fn marker_offsets_track_earlier_length_changes() {
+        let preview = preview(BASE, &resolved(ConflictPolicy::PreferA));
+        assert_eq!(preview.text, "The gale came. Mara walked. The letter was delayed.");
+        let marked: Vec<(&str, &str, &str)> = preview
+            .markers
+            .iter()
+            .map(|m| (m.conflict_id.as_str(), m.source.as_str(), &preview.text[m.start_pos..m.end_pos]))
+            .collect();
+        assert_eq!(marked, [("storm", SOURCE_USER_A, "gale"), ("walked", SOURCE_USER_A, "walked"), ("late", SOURCE_USER_A, "delayed")]);
+
+        let auto = super::preview(BASE, &resolved(ConflictPolicy::Auto));
+        let sources: Vec<&str> = auto.markers.iter().map(|m| m.source.as_str()).collect();
+        assert_eq!(sources, [SOURCE_USER_B, SOURCE_USER_A, SOURCE_USER_B]);
+
+        let manual = super::preview(BASE, &resolved(ConflictPolicy::Manual));
+        assert!(manual.markers.iter().all(|m| m.source == SOURCE_UNRESOLVED));
+        assert!(manual.text.starts_with("The <<<<<<< user_a\ngale\n=======\ntempest\n>>>>>>> user_b came."));
+    }
+
+    #[test]
+    fn unresolved_conflicts_render_both_versions_inline() {
+        let mut unresolved = conflict("storm", "text_modification", "storm", "gale", "tempest");
+        unresolved.resolution_suggestion = MANUAL_RESOLUTION.to_string();
+        let preview = preview(BASE, &[unresolved]);
+        let marker = &preview.markers[0];
+        assert_eq!(marker.source, SOURCE_UNRESOLVED);
+        assert_eq!(&preview.text[marker.start_pos..marker.end_pos], "<<<<<<< user_a\ngale\n=======\ntempest\n>>>>>>> user_b");
+        assert!(preview.text.ends_with(" came. Mara walked the field. The letter was late."));
+    }
+
+    #[test]
+    fn overlapping_and_out_of_range_conflicts_are_skipped() {
+        let mut resolved = resolved(ConflictPolicy::Auto);
+        resolved.push(conflict("overlap", "text_modification", "field. The", "x", "y"));
+        let mut beyond = conflict("beyond", "text_modification", "late", "x", "y");
+        beyond.end_pos = BASE.len() + 4;
+        resolved.push(beyond);
+        let applied = apply(BASE, &resolved);
+        let skipped: Vec<(&str, &str)> = applied.skipped.iter().map(|s| (s.conflict_id.as_str(), s.reason.as_str())).collect();
+        assert_eq!(skipped, [("overlap", "overlapping"), ("beyond", "out_of_bounds")]);
+    }
+
+    fn spliced(id: &str, start_pos: usize, end_pos: usize, resolution: &str) -> CollaborationConflict {
+        serde_json::from_value(serde_json::json!({
+            "conflict_id": id,
+            "conflict_type": "text_modification",
+            "start_pos": start_pos,
+            "end_pos": end_pos,
+            "user_a_change": "",
+            "user_b_change": "",
+            "timestamp": "2024-01-01T00:00:00Z",
+            "resolution_suggestion": resolution,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn apply_resolutions_splices_and_skips_manual() {
+        let text = "The quick brown fox jumps over the lazy dog.";
+        let resolved = [
+            spliced("late", 35, 39, "sleepy"),
+            spliced("early", 4, 9, "swift"),
+            spliced("manual", 20, 25, MANUAL_RESOLUTION),
+            spliced("insert", 44, 44, " Again."),
+        ];
+        let applied = TextProcessor::new().apply_resolutions(text, &resolved).unwrap();
+        assert_eq!(applied.text, "The swift brown fox jumps over the sleepy dog. Again.");
+        let skipped: Vec<(&str, &str)> = applied.skipped.iter().map(|s| (s.conflict_id.as_str(), s.reason.as_str())).collect();
+        assert_eq!(skipped, [("manual", "manual")]);
+    }
+
+    #[test]
+    fn apply_resolutions_rejects_overlap_and_out_of_bounds() {
+        let text = "The quick brown fox.";
+        let processor = TextProcessor::new();
+        let overlap = processor.apply_resolutions(text, &[spliced("a", 4, 15, "x"), spliced("b", 10, 19, "y")]).err().unwrap();
+        assert!(overlap.contains("\"b\"") && overlap.contains("\"a\""));
+        let past_end = processor.apply_resolutions(text, &[spliced("c", 16, 40, "z")]).err().unwrap();
+        assert!(past_end.contains("\"c\""));
+        let reversed = processor.apply_resolutions(text, &[spliced("d", 9, 4, "z")]).err().unwrap();
+        assert!(reversed.contains("\"d\""));
+    }
+}
@@ -0,0 +1,283 @@
+//! Sentence-level narrative mode (action, description, dialogue) from small
+//! built-in word lists. Deterministic and deliberately simple: a sentence is
+//! judged by the verbs and descriptors it contains and by its length.
+//!
+//! Also finds filter words, the perception verbs that report a sensation
+//! instead of showing it ("she felt the cold").
+
+use serde::Serialize;
+
+use crate::segmentation::{self, DocumentIndex};
+
+const ACTION_VERBS: &[&str] = &[
+    "run", "ran", "hit", "punch", "kick", "grab", "throw", "threw", "jump", "leap", "leapt", "lunge", "strike",
+    "struck", "slam", "smash", "swing", "swung", "dodge", "duck", "charge", "shove", "push", "pull", "yank",
+    "tackle", "stab", "slash", "shoot", "shot", "fire", "fall", "fell", "crash", "sprint", "dash", "race",
+    "rush", "bolt", "dive", "dove", "roll", "spin", "spun", "twist", "block", "parry", "drag", "drop", "seize",
+    "snatch", "hurl", "fling", "flung", "tear", "tore", "rip", "break", "broke", "kill", "fight", "fought",
+    "climb", "scramble", "stumble", "slip", "burst", "explode", "flee", "fled", "chase", "catch", "caught",
+    "hurry", "swerve", "wrench", "crack", "slice", "thrust", "jab", "hammer", "batter", "flip", "vault",
+    "sweep", "swept", "stagger", "collapse", "grapple", "wrestle", "choke", "hack", "pound", "ram",
+];
+
+const LINKING_VERBS: &[&str] = &[
+    "is", "was", "were", "are", "be", "been", "being", "seem", "seems", "seemed", "appear", "appears",
+    "appeared", "look", "looks", "looked", "remain", "remains", "remained", "lay", "lies", "stretched",
+];
+
+const ADJECTIVES: &[&str] = &[
+    "old", "ancient", "tall", "small", "large", "vast", "quiet", "silent", "soft", "dark", "bright", "golden",
+    "green", "blue", "grey", "gray", "red", "white", "black", "pale", "cold", "warm", "wide", "narrow", "deep",
+    "still", "calm", "gentle", "distant", "lovely", "misty", "thick", "thin", "heavy", "sweet", "rich",
+    "smooth", "rough", "low", "high", "broad", "tiny", "huge", "empty", "lush", "wild", "bare", "faint",
+    "silver", "amber", "crimson", "purple", "hazy", "dusty", "mossy", "rolling", "sleepy", "dim", "velvet",
+];
+
+/// Perception and cognition verbs that put a character between the reader
+/// and the scene. Irregular forms are listed; regular inflections are
+/// matched through `verb_forms`.
+const FILTER_VERBS: &[&str] = &[
+    "feel", "felt", "see", "saw", "seen", "hear", "heard", "notice", "realize", "realise", "wonder", "seem",
+    "know", "knew", "known", "watch", "sense",
+];
+
+/// Subjects that make a filter verb a character's perception. "It" is left
+/// out: "it felt soft" describes the thing, not the character.
+const SUBJECT_PRONOUNS: &[&str] = &["i", "you", "he", "she", "we", "they"];
+
+/// Words allowed between the subject and the verb ("she could hear", "he
+/// suddenly realized"); words ending in "-ly" are allowed too.
+const AUXILIARIES: &[&str] = &["could", "can", "would", "did", "had", "has", "have", "not", "never", "just", "still", "also"];
+/// How far back from the verb the subject may be.
+const MAX_SUBJECT_DISTANCE: usize = 3;
+
+const ADJECTIVE_SUFFIXES: &[&str] = &["ous", "ful", "less", "ive", "able", "ible", "esque"];
+
+/// Sentences at most this long lean toward action...
+const SHORT_SENTENCE_WORDS: usize = 12;
+/// ...and at least this long toward description.
+const LONG_SENTENCE_WORDS: usize = 18;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SentenceMode {
+    Action,
+    Description,
+    Dialogue,
+    Neutral,
+}
+
+/// `word` and the base forms its verb inflections could come from
+/// ("grabbed" -> "grab", "lunged" -> "lunge", "races" -> "race").
+fn verb_forms(word: &str) -> Vec<String> {
+    let mut forms = vec![word.to_string()];
+    for suffix in ["ing", "ed", "es", "s", "d"] {
+        if let Some(stem) = word.strip_suffix(suffix).filter(|s| s.len() >= 2) {
+            forms.push(stem.to_string());
+            forms.push(format!("{}e", stem));
+            let mut chars = stem.chars().rev();
+            if let (Some(a), Some(b)) = (chars.next(), chars.next()) {
+                if a == b {
+                    forms.push(stem[..stem.len() - a.len_utf8()].to_string());
+                }
+            }
+        }
+    }
+    forms
+}
+
+fn is_action_verb(word: &str) -> bool {
+    verb_forms(word).iter().any(|form| ACTION_VERBS.contains(&form.as_str()))
+}
+
+fn is_descriptor(word: &str) -> bool {
+    ADJECTIVES.contains(&word)
+        || (word.len() > 5 && ADJECTIVE_SUFFIXES.iter().any(|s| word.ends_with(s)))
+        || (word.len() > 4 && word.ends_with("ly") && !matches!(word, "only" | "family" | "reply" | "supply" | "early"))
+}
+
+fn is_filter_verb(word: &str) -> bool {
+    verb_forms(word).iter().any(|form| FILTER_VERBS.contains(&form.as_str()))
+}
+
+pub const ACTION: u8 = 1;
+pub const DESCRIPTOR: u8 = 1 << 1;
+pub const LINKING: u8 = 1 << 2;
+pub const FILTER_VERB: u8 = 1 << 3;
+pub const SUBJECT_PRONOUN: u8 = 1 << 4;
+/// May stand between a subject and its filter verb.
+pub const AUXILIARY: u8 = 1 << 5;
+
+/// What the lists make of a lowercase word, as a set of the flags above.
+/// The lists never depend on context, so a `WordTable` classifies each word
+/// once.
+pub fn word_class(word: &str) -> u8 {
+    let flag = |set: bool, flag: u8| if set { flag } else { 0 };
+    flag(is_action_verb(word), ACTION)
+        | flag(is_descriptor(word), DESCRIPTOR)
+        | flag(LINKING_VERBS.contains(&word), LINKING)
+        | flag(is_filter_verb(word), FILTER_VERB)
+        | flag(SUBJECT_PRONOUNS.contains(&word), SUBJECT_PRONOUN)
+        | flag(AUXILIARIES.contains(&word) || (word.len() > 4 && word.ends_with("ly")), AUXILIARY)
+}
+
+/// `word_class` of every word of `index`, in order.
+pub fn word_classes(index: &DocumentIndex) -> Vec<u8> {
+    index.word_strs().into_iter().map(|word| word_class(&word.to_lowercase())).collect()
+}
+
+fn classify(classes: &[u8]) -> SentenceMode {
+    let count = |flag: u8| classes.iter().filter(|&&class| class & flag != 0).count();
+    let (action, descriptors, linking) = (count(ACTION), count(DESCRIPTOR), count(LINKING));
+    if action == 0 && descriptors == 0 {
+        return SentenceMode::Neutral;
+    }
+    let action_points = 2 * action + usize::from(classes.len() <= SHORT_SENTENCE_WORDS);
+    let description_points = 2 * descriptors + linking + usize::from(classes.len() >= LONG_SENTENCE_WORDS);
+    if action > 0 && action_points > description_points {
+        SentenceMode::Action
+    } else if descriptors > 0 && description_points > action_points {
+        SentenceMode::Description
+    } else {
+        SentenceMode::Neutral
+    }
+}
+
+/// Whether the filter verb at `classes[i]` has a subject pronoun shortly
+/// before it, with only auxiliaries and adverbs in between.
+fn has_pronoun_subject(classes: &[u8], i: usize) -> bool {
+    for back in 1..=MAX_SUBJECT_DISTANCE.min(i) {
+        let class = classes[i - back];
+        if class & SUBJECT_PRONOUN != 0 {
+            return true;
+        }
+        if class & AUXILIARY == 0 {
+            return false;
+        }
+    }
+    false
+}
+
+/// Filter-word spans of every sentence of `index`, outside `dialogue_spans`
+/// (speech reports perception naturally). A verb only counts with a subject
+/// pronoun shortly before it, so "she felt cold" is a hit but "the fabric
+/// felt soft" is not. `classes` are the `word_classes` of `index`.
+pub fn filter_words(index: &DocumentIndex, classes: &[u8], dialogue_spans: &[(usize, usize)]) -> Vec<Vec<(usize, usize)>> {
+    index
+        .sentences
+        .iter()
+        .map(|&(start, end)| {
+            let range = index.word_range(start, end);
+            let (spans, classes) = (&index.words[range.clone()], &classes[range]);
+            (0..classes.len())
+                .filter(|&i| classes[i] & FILTER_VERB != 0 && has_pronoun_subject(classes, i))
+                .map(|i| spans[i])
+                .filter(|w| !segmentation::within(dialogue_spans, w.0, w.1))
+                .collect()
+        })
+        .collect()
+}
+
+/// Mode of every sentence of `index`. A sentence with most of its words
+/// inside `dialogue_spans` is dialogue regardless of its wording. `classes`
+/// are the `word_classes` of `index`.
+pub fn sentence_modes(index: &DocumentIndex, classes: &[u8], dialogue_spans: &[(usize, usize)]) -> Vec<SentenceMode> {
+    index
+        .sentences
+        .iter()
+        .map(|&(start, end)| {
+            let range = index.word_range(start, end);
+            let quoted = index.words[range.clone()].iter().filter(|w| segmentation::within(dialogue_spans, w.0, w.1)).count();
+            if quoted * 2 > range.len() {
+                return SentenceMode::Dialogue;
+            }
+            classify(&classes[range])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    const FIGHT: &str = "He lunged at the door. Kara ducked and grabbed his arm. She twisted hard and threw him down. \
+        He rolled, kicked out, and scrambled up. She struck again and he fell.";
+    const SCENERY: &str = "The valley was quiet and green under a pale, distant sky that stretched over the rolling hills to the south. \
+        Ancient oaks stood still along the misty river, their heavy branches soft with moss and silver in the faint light. \
+        The air was cold and sweet.";
+    const DIALOGUE: &str = "\"Where were you last night?\" she asked.\n\n\"Out walking by the river with my brother,\" he said.\n\n\
+        \"You expect me to believe that?\"\n\n\"I do, because it is true.\"";
+
+    fn ratios(text: &str) -> (f64, f64, f64) {
+        let style = TextProcessor::new().perform_analysis(text).style_metrics;
+        (style.action_ratio, style.description_ratio, style.dialogue_ratio)
+    }
+
+    #[test]
+    fn each_fixture_is_dominated_by_its_own_mode() {
+        let (action, description, dialogue) = ratios(FIGHT);
+        assert!(action > 0.5 && action > description && action > dialogue, "{:?}", (action, description, dialogue));
+        let (action, description, dialogue) = ratios(SCENERY);
+        assert!(description > 0.5 && description > action && description > dialogue, "{:?}", (action, description, dialogue));
+        let (action, description, dialogue) = ratios(DIALOGUE);
+        assert!(dialogue > 0.5 && dialogue > action && dialogue > description, "{:?}", (action, description, dialogue));
+        for text in [FIGHT, SCENERY, DIALOGUE] {
+            let (a, d, q) = ratios(text);
+            assert!(a + d + q <= 1.0 + 1e-9);
+        }
+    }
+
+    #[test]
+    fn inflected_verbs_match_their_base_forms() {
+        for (word, base) in [("grabbed", "grab"), ("lunged", "lunge"), ("races", "race"), ("kicking", "kick"), ("dodges", "dodge")] {
+            assert!(verb_forms(word).iter().any(|f| f == base), "{} -> {}", word, base);
+        }
+        assert!(is_action_verb("slammed") && !is_action_verb("thought"));
+        assert!(is_descriptor("glorious") && is_descriptor("slowly") && !is_descriptor("family") && !is_descriptor("only"));
+    }
+
+    #[test]
+    fn plain_sentences_are_neutral() {
+        let words = |s: &str| s.split(' ').map(word_class).collect::<Vec<_>>();
+        assert!(classify(&words("she went to the shop")) == SentenceMode::Neutral);
+        assert!(classify(&words("he hit the wall")) == SentenceMode::Action);
+        assert!(classify(&words("the room was dark and quiet")) == SentenceMode::Description);
+        assert_eq!(ratios("").0, 0.0);
+    }
+
+    fn filter_hits(text: &str) -> Vec<&str> {
+        let processor = TextProcessor::new();
+        let index = processor.index(text);
+        let dialogue = processor.dialogue_spans(text);
+        filter_words(&index, &word_classes(&index), &dialogue).into_iter().flatten().map(|(start, end)| &text[start..end]).collect()
+    }
+
+    #[test]
+    fn filter_verbs_need_a_pronoun_subject() {
+        assert_eq!(filter_hits("She felt cold. He could hear the rain. They suddenly realized it was late."), ["felt", "hear", "realized"]);
+        assert!(filter_hits("The fabric felt soft. It seemed fine. The saw was blunt.").is_empty());
+        // Too far from the subject, or something other than an auxiliary in between
+        assert!(filter_hits("She and the boy heard it. He would not have never noticed.").is_empty());
+    }
+
+    #[test]
+    fn filter_words_in_dialogue_are_ignored_and_counted_per_sentence() {
+        assert!(filter_hits("\"I knew it,\" Mara said.").is_empty());
+        let style = TextProcessor::new().perform_analysis("She saw the ship and she heard the horn. The sea was grey.").style_metrics;
+        assert_eq!(style.filter_word_ratio, 0.5);
+    }
+
+    #[test]
+    fn filter_word_suggestions_point_at_the_verb() {
+        let text = format!("{}She noticed the smoke. The wool felt rough.", "Mara walked home. ".repeat(10));
+        let report = TextProcessor::new().build_optimization_report(&text);
+        let hits: Vec<(&str, &str)> = report
+            .suggestions
+            .iter()
+            .filter(|s| s.suggestion_type == "filter_word")
+            .map(|s| (&text[s.start_pos..s.end_pos], s.priority.as_ref()))
+            .collect();
+        assert_eq!(hits, [("noticed", "low")]);
+    }
+}
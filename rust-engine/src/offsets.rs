@@ -0,0 +1,407 @@
+//! Offset helpers for consumers that slice text at engine-provided positions.
+//!
+//! Engine positions are UTF-8 byte offsets; JavaScript strings index UTF-16
+//! code units. These helpers convert between the two and never split a
+//! character or a grapheme cluster.
+
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Serialize, Deserialize)]
+pub struct TextSlice {
+    pub text: String,
+    pub start_pos: usize,
+    pub end_pos: usize,
+    /// Bytes the start moved backwards to reach a grapheme boundary.
+    pub start_adjustment: usize,
+    /// Bytes the end moved forwards to reach a grapheme boundary.
+    pub end_adjustment: usize,
+    pub adjusted: bool,
+}
+
+pub fn char_floor(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+pub fn char_ceil(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset += 1;
+    }
+    offset
+}
+
+pub fn grapheme_floor(text: &str, offset: usize) -> usize {
+    if offset >= text.len() {
+        return text.len();
+    }
+    text.grapheme_indices(true).map(|(i, _)| i).take_while(|&i| i <= offset).last().unwrap_or(0)
+}
+
+pub fn grapheme_ceil(text: &str, offset: usize) -> usize {
+    text.grapheme_indices(true).map(|(i, _)| i).find(|&i| i >= offset).unwrap_or(text.len())
+}
+
+/// Slices `text` at byte offsets, widening the span to whole grapheme
+/// clusters when either end falls inside one.
+pub fn slice(text: &str, start: usize, end: usize) -> Result<TextSlice, String> {
+    if start > end {
+        return Err(format!("start ({}) is after end ({})", start, end));
+    }
+    if end > text.len() {
+        return Err(format!("end ({}) is past the end of the text ({} bytes)", end, text.len()));
+    }
+    let start_pos = grapheme_floor(text, start);
+    let end_pos = grapheme_ceil(text, end);
+    Ok(TextSlice {
+        text: text[start_pos..end_pos].to_string(),
+        start_pos,
+        end_pos,
+        start_adjustment: start - start_pos,
+        end_adjustment: end_pos - end,
+        adjusted: start_pos != start || end_pos != end,
+    })
+}
+
+/// Byte and UTF-16 start offsets of every character, plus an end sentinel,
+/// so either kind of offset can be converted with a binary search.
+struct OffsetTable {
+    bytes: Vec<usize>,
+    utf16: Vec<usize>,
+}
+
+impl OffsetTable {
+    fn build(text: &str) -> OffsetTable {
+        let mut bytes = Vec::with_capacity(text.len() + 1);
+        let mut utf16 = Vec::with_capacity(text.len() + 1);
+        let mut units = 0;
+        for (i, c) in text.char_indices() {
+            bytes.push(i);
+            utf16.push(units);
+            units += c.len_utf16();
+        }
+        bytes.push(text.len());
+        utf16.push(units);
+        OffsetTable { bytes, utf16 }
+    }
+
+    fn byte_len(&self) -> usize {
+        *self.bytes.last().unwrap()
+    }
+
+    fn utf16_len(&self) -> usize {
+        *self.utf16.last().unwrap()
+    }
+
+    /// A byte offset inside a character maps to that character's start.
+    fn to_utf16(&self, byte_offset: usize) -> Result<usize, String> {
+        if byte_offset > self.byte_len() {
+            return Err(format!("byte offset {} is past the end of the text ({} bytes)", byte_offset, self.byte_len()));
+        }
+        let i = self.bytes.partition_point(|&b| b <= byte_offset) - 1;
+        Ok(self.utf16[i])
+    }
+
+    /// A UTF-16 index between the halves of a surrogate pair maps to the
+    /// start of that character.
+    fn to_byte(&self, utf16_index: usize) -> Result<usize, String> {
+        if utf16_index > self.utf16_len() {
+            return Err(format!("UTF-16 index {} is past the end of the text ({} code units)", utf16_index, self.utf16_len()));
+        }
+        let i = self.utf16.partition_point(|&u| u <= utf16_index) - 1;
+        Ok(self.bytes[i])
+    }
+}
+
+/// UTF-16 offsets and zero-based line and column of byte offsets into one
+/// text, for editors that index JavaScript strings. Columns count UTF-16
+/// code units from the start of the line.
+pub struct TextPositions {
+    table: OffsetTable,
+    /// UTF-16 offset of the start of each line.
+    line_starts: Vec<usize>,
+}
+
+impl TextPositions {
+    pub fn new(text: &str) -> TextPositions {
+        let table = OffsetTable::build(text);
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i, _)| table.to_utf16(i + 1).unwrap()));
+        TextPositions { table, line_starts }
+    }
+
+    /// Offsets past the end clamp to it.
+    pub fn utf16(&self, byte_offset: usize) -> usize {
+        self.table.to_utf16(byte_offset.min(self.table.byte_len())).unwrap()
+    }
+
+    pub fn line_column(&self, byte_offset: usize) -> (usize, usize) {
+        let offset = self.utf16(byte_offset);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        (line, offset - self.line_starts[line])
+    }
+}
+
+pub fn to_utf16(text: &str, byte_offset: usize) -> Result<usize, String> {
+    OffsetTable::build(text).to_utf16(byte_offset)
+}
+
+pub fn to_byte(text: &str, utf16_index: usize) -> Result<usize, String> {
+    OffsetTable::build(text).to_byte(utf16_index)
+}
+
+/// Returns the substring of `text` between two byte offsets, snapped
+/// outwards to grapheme cluster boundaries, with the adjustment reported.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn slice_text(text: &str, start: usize, end: usize) -> Result<JsValue, JsError> {
+    let result = slice(text, start, end).map_err(|e| JsError::new(&e))?;
+    crate::wasm::to_js(&result)
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn offset_to_utf16(text: &str, byte_offset: usize) -> Result<usize, JsError> {
+    to_utf16(text, byte_offset).map_err(|e| JsError::new(&e))
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn utf16_to_offset(text: &str, utf16_index: usize) -> Result<usize, JsError> {
+    to_byte(text, utf16_index).map_err(|e| JsError::new(&e))
+}
+
+/// Largest char boundary at or before `offset`, clamped to the text length.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn char_boundary_floor(text: &str, offset: usize) -> usize {
+    char_floor(text, offset)
+}
+
+/// Smallest char boundary at or after `offset`, clamped to the text length.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn char_boundary_ceil(text: &str, offset: usize) -> usize {
+    char_ceil(text, offset)
+}
+
+/// Offset conversions for repeated lookups against one text. The lookup
+/// table is built once; each conversion is a binary search.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub struct OffsetIndex {
+    text: String,
+    table: OffsetTable,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl OffsetIndex {
+    #[wasm_bindgen(constructor)]
+    pub fn new(text: &str) -> OffsetIndex {
+        OffsetIndex { text: text.to_string(), table: OffsetTable::build(text) }
+    }
+
+    #[wasm_bindgen]
+    pub fn to_utf16(&self, byte_offset: usize) -> Result<usize, JsError> {
+        self.table.to_utf16(byte_offset).map_err(|e| JsError::new(&e))
+    }
+
+    #[wasm_bindgen]
+    pub fn to_offset(&self, utf16_index: usize) -> Result<usize, JsError> {
+        self.table.to_byte(utf16_index).map_err(|e| JsError::new(&e))
+    }
+
+    /// Converts a batch of byte offsets, e.g. every span in a report.
+    #[wasm_bindgen]
+    pub fn to_utf16_many(&self, byte_offsets: Vec<usize>) -> Result<Vec<usize>, JsError> {
+        byte_offsets.into_iter().map(|b| self.table.to_utf16(b).map_err(|e| JsError::new(&e))).collect()
+    }
+
+    #[wasm_bindgen]
+    pub fn slice(&self, start: usize, end: usize) -> Result<JsValue, JsError> {
+        slice_text(&self.text, start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OptimizationSuggestion, TextProcessor};
+    use crate::suggestions::SCOPE_INSTANCE;
+    use crate::threeway;
+    use crate::test_support::Cases;
+
+    const MULTILINGUAL: &str = "Café naïve — 日本語の文章。 Emoji 👩‍👩‍👧 and 🇯🇵 flags; e\u{301}clair, Ελληνικά, עברית, 𝔘𝔫𝔦𝔠𝔬𝔡𝔢.";
+
+    fn offsets(seed: u64, bound: usize, count: usize) -> Vec<usize> {
+        let mut cases = Cases(seed);
+        (0..count).map(|_| cases.below(bound + 1)).collect()
+    }
+
+    #[test]
+    fn random_offsets_round_trip_through_utf16() {
+        let table = OffsetTable::build(MULTILINGUAL);
+        for byte in offsets(0x9e37_79b9_7f4a_7c15, MULTILINGUAL.len(), 2000) {
+            let floor = char_floor(MULTILINGUAL, byte);
+            let units = to_utf16(MULTILINGUAL, byte).unwrap();
+            assert_eq!(units, MULTILINGUAL[..floor].encode_utf16().count(), "byte {}", byte);
+            assert_eq!(to_byte(MULTILINGUAL, units).unwrap(), floor, "byte {}", byte);
+            assert_eq!(table.to_utf16(byte).unwrap(), units);
+        }
+
+        let utf16_len = MULTILINGUAL.encode_utf16().count();
+        for units in offsets(0x2545_f491_4f6c_dd1d, utf16_len, 2000) {
+            let byte = to_byte(MULTILINGUAL, units).unwrap();
+            assert!(MULTILINGUAL.is_char_boundary(byte));
+            let back = to_utf16(MULTILINGUAL, byte).unwrap();
+            // Only the low half of a surrogate pair maps back one unit earlier
+            assert!(back == units || (back + 1 == units && MULTILINGUAL[byte..].chars().next().unwrap().len_utf16() == 2));
+        }
+    }
+
+    #[test]
+    fn random_slices_snap_outward_to_grapheme_boundaries() {
+        let boundaries: Vec<usize> =
+            MULTILINGUAL.grapheme_indices(true).map(|(i, _)| i).chain([MULTILINGUAL.len()]).collect();
+        let points = offsets(0xdead_beef_cafe_f00d, MULTILINGUAL.len(), 2000);
+        for pair in points.chunks(2) {
+            let (start, end) = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+            let slice = slice(MULTILINGUAL, start, end).unwrap();
+            assert!(boundaries.contains(&slice.start_pos) && boundaries.contains(&slice.end_pos), "{}..{}", start, end);
+            assert!(slice.start_pos <= start && slice.end_pos >= end);
+            assert_eq!(slice.start_pos + slice.start_adjustment, start);
+            assert_eq!(slice.end_pos - slice.end_adjustment, end);
+            assert_eq!(slice.adjusted, slice.start_adjustment + slice.end_adjustment > 0);
+            assert_eq!(slice.text, &MULTILINGUAL[slice.start_pos..slice.end_pos]);
+        }
+    }
+
+    #[test]
+    fn slicing_inside_a_cluster_widens_to_the_whole_cluster() {
+        let text = "a👩‍👩‍👧b";
+        let slice = slice(text, 2, 6).unwrap();
+        assert_eq!(slice.text, "👩‍👩‍👧");
+        assert!(slice.adjusted);
+        assert!(super::slice(text, 3, 2).is_err());
+        assert!(super::slice(text, 0, text.len() + 1).is_err());
+        assert!(to_utf16(text, text.len() + 1).is_err());
+        assert!(to_byte(text, 100).is_err());
+        assert_eq!((char_floor(text, 3), char_ceil(text, 3), char_ceil(text, 99)), (1, 5, text.len()));
+    }
+
+    #[test]
+    fn text_positions_give_utf16_offsets_lines_and_columns() {
+        let text = "第一行\n\u{201C}Quote\u{201D} 👋 x\r\nlast";
+        let positions = TextPositions::new(text);
+        for (byte, _) in text.char_indices().chain([(text.len(), ' ')]) {
+            assert_eq!(positions.utf16(byte), text[..byte].encode_utf16().count(), "byte {}", byte);
+        }
+        assert_eq!(positions.line_column(0), (0, 0));
+        // Curly quotes are one unit each, the emoji two
+        assert_eq!(positions.line_column(text.find('x').unwrap()), (1, 11));
+        assert_eq!(positions.line_column(text.find("last").unwrap()), (2, 0));
+        assert_eq!(positions.utf16(text.len() + 10), text.encode_utf16().count());
+        assert_eq!(TextPositions::new("").line_column(0), (0, 0));
+    }
+
+    #[test]
+    fn utf16_offsets_slice_the_javascript_string_to_the_flagged_text() {
+        let text = "👩\u{200d}👩\u{200d}👧 日本語の文章。 The letter was quickly written \u{2014} \u{201C}really\u{201D} basically fine.\n\
+            🇯🇵 She felt that it was very, very good. 𝔘𝔫𝔦𝔠𝔬𝔡𝔢 was seen by everyone.";
+        let units: Vec<u16> = text.encode_utf16().collect();
+        let line_starts: Vec<usize> =
+            [0].into_iter().chain(text.match_indices('\n').map(|(i, _)| text[..=i].encode_utf16().count())).collect();
+        let report = TextProcessor::new().build_optimization_report(text);
+        let instances: Vec<&OptimizationSuggestion> = report.suggestions.iter().filter(|s| s.scope == SCOPE_INSTANCE).collect();
+        assert!(instances.len() >= 4, "{} suggestions", instances.len());
+        for s in instances {
+            let flagged = String::from_utf16(&units[s.start_utf16..s.end_utf16]).unwrap();
+            assert_eq!(flagged, &text[s.start_pos..s.end_pos], "{}", s.suggestion_type);
+            assert_eq!(line_starts[s.line] + s.column, s.start_utf16);
+        }
+
+        let base = "Caf\u{e9} \u{201C}one\u{201D}\n👋 two three";
+        let mut merged = threeway::merge(base, &base.replace("two", "2"), &base.replace("two", "II"));
+        let positions = TextPositions::new(base);
+        for conflict in &mut merged.conflicts {
+            conflict.locate(&positions);
+        }
+        let conflict = &merged.conflicts[0];
+        let units: Vec<u16> = base.encode_utf16().collect();
+        let flagged = String::from_utf16(&units[conflict.start_utf16.unwrap()..conflict.end_utf16.unwrap()]).unwrap();
+        assert_eq!(flagged, &base[conflict.start_pos..conflict.end_pos]);
+        assert_eq!(conflict.line, Some(1));
+    }
+
+    #[test]
+    fn a_selection_reports_only_its_suggestions_in_whole_text_positions() {
+        let processor = TextProcessor::new();
+        let text = "The door was opened by Mara.\n\nHe ran really quickly. The vase was broken by Tom.\n\nShe was seen by nobody.";
+        let selection = (text.find("He ran").unwrap(), text.find("Tom.").unwrap() + 4);
+        let report = processor.selection_optimization_report(text, selection.0, selection.1, &|_| true);
+        let flagged: Vec<&str> = report
+            .suggestions
+            .iter()
+            .filter(|s| s.scope == SCOPE_INSTANCE)
+            .map(|s| &text[s.start_pos..s.end_pos])
+            .collect();
+        assert!(flagged.contains(&"really"), "{:?}", flagged);
+        assert!(flagged.iter().any(|f| f.contains("was broken")));
+        assert!(!flagged.iter().any(|f| f.contains("opened") || f.contains("seen")));
+        let units: Vec<u16> = text.encode_utf16().collect();
+        for s in report.suggestions.iter().filter(|s| s.scope == SCOPE_INSTANCE) {
+            assert_eq!(String::from_utf16(&units[s.start_utf16..s.end_utf16]).unwrap(), &text[s.start_pos..s.end_pos]);
+            assert_eq!(s.line, 2);
+        }
+    }
+
+    #[test]
+    fn a_range_is_analyzed_alone_in_whole_document_positions() {
+        let processor = TextProcessor::new();
+        let text = "Caf\u{e9} opened late.\n\nThe vase was broken by Mara. She ran really quickly home.\n\nThe end.";
+        let start = text.find("The vase").unwrap();
+        let end = text.find(" home").unwrap();
+        let Ok(range) = processor.analyze_range(text, start, end) else { panic!("valid range rejected") };
+        assert_eq!(range.analysis.word_count, 10);
+        let sentences: Vec<&str> = range.sentences.iter().map(|s| &text[s.start_pos..s.end_pos]).collect();
+        assert_eq!(sentences, ["The vase was broken by Mara.", "She ran really quickly"]);
+        let flagged: Vec<&str> =
+            range.suggestions.iter().filter(|s| s.scope == SCOPE_INSTANCE).map(|s| &text[s.start_pos..s.end_pos]).collect();
+        assert!(flagged.contains(&"really") && flagged.iter().any(|f| f.contains("was broken")), "{:?}", flagged);
+        let units: Vec<u16> = text.encode_utf16().collect();
+        for s in range.suggestions.iter().filter(|s| s.scope == SCOPE_INSTANCE) {
+            assert_eq!(String::from_utf16(&units[s.start_utf16..s.end_utf16]).unwrap(), &text[s.start_pos..s.end_pos]);
+        }
+        assert_eq!(range.context.paragraphs, [1]);
+        assert!(!range.context.starts_mid_sentence);
+        assert!(range.context.ends_mid_sentence);
+
+        let Ok(spanning) = processor.analyze_range(text, text.find("opened").unwrap(), text.find("vase").unwrap()) else {
+            panic!("valid range rejected")
+        };
+        assert_eq!(spanning.context.paragraphs, [0, 1]);
+        assert!(spanning.context.starts_mid_sentence);
+    }
+
+    #[test]
+    fn invalid_ranges_are_errors_and_empty_ranges_are_empty() {
+        let processor = TextProcessor::new();
+        let text = "Caf\u{e9} opened late.";
+        let Err(message) = processor.analyze_range(text, 4, 6) else { panic!("range inside a character accepted") };
+        assert!(message.contains("character boundaries"));
+        assert!(processor.analyze_range(text, 0, text.len() + 1).is_err());
+        assert!(processor.analyze_range(text, 6, 2).is_err());
+        let Ok(empty) = processor.analyze_range(text, 6, 6) else { panic!("empty range rejected") };
+        assert_eq!(empty.analysis.word_count, 0);
+        assert!(empty.sentences.is_empty());
+        assert!(empty.suggestions.iter().all(|s| s.scope != SCOPE_INSTANCE));
+        assert_eq!(empty.context.paragraphs, [0]);
+    }
+}
@@ -0,0 +1,134 @@
+//! Tokens too long to be words (pasted base64, minified URLs, hashes). They
+//! count as one word each but stay out of syllable and readability math and
+//! out of the regex-based style rules.
+
+use std::borrow::Cow;
+
+use crate::segmentation::DocumentIndex;
+use crate::warnings::AnalysisWarning;
+use crate::RelatedSpan;
+
+/// Characters of an opaque token quoted in messages.
+const PREVIEW_CHARS: usize = 24;
+
+/// Longer than `max_chars` characters.
+pub fn is_opaque(word: &str, max_chars: usize) -> bool {
+    // Byte length bounds the char count from above, so most words skip the count
+    word.len() > max_chars && word.chars().count() > max_chars
+}
+
+/// Byte spans of the opaque words of `index`.
+pub fn opaque_tokens(index: &DocumentIndex, max_chars: usize) -> Vec<(usize, usize)> {
+    index.words.iter().copied().filter(|&(s, e)| is_opaque(&index.text[s..e], max_chars)).collect()
+}
+
+/// `text` with every opaque token blanked to spaces of the same byte length,
+/// so pattern matches on it keep their offsets and never start inside one.
+pub fn mask<'t>(text: &'t str, tokens: &[(usize, usize)]) -> Cow<'t, str> {
+    if tokens.is_empty() {
+        return Cow::Borrowed(text);
+    }
+    let mut masked = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for &(start, end) in tokens {
+        masked.push_str(&text[cursor..start]);
+        masked.push_str(&" ".repeat(end - start));
+        cursor = end;
+    }
+    masked.push_str(&text[cursor..]);
+    Cow::Owned(masked)
+}
+
+/// `text` cut to `max_chars` characters with its full length noted, for
+/// quoting user text in messages.
+pub fn truncate(text: &str, max_chars: usize) -> String {
+    if !is_opaque(text, max_chars) {
+        return text.to_string();
+    }
+    let total = text.chars().count();
+    let head: String = text.chars().take(max_chars).collect();
+    format!("{}\u{2026} ({} characters)", head, total)
+}
+
+/// The warning for `tokens`, quoting `first`, the text of the first one.
+pub fn warning(first: &str, tokens: &[(usize, usize)], max_chars: usize) -> Option<AnalysisWarning> {
+    tokens.first()?;
+    let mut warning = AnalysisWarning::new(
+        "opaque_tokens",
+        format!(
+            "{} token(s) longer than {} characters (first: \"{}\") were counted as single words and left out of syllable, readability, and style-rule analysis.",
+            tokens.len(),
+            max_chars,
+            truncate(first, PREVIEW_CHARS),
+        ),
+    );
+    warning.spans = tokens.iter().map(|&(start_pos, end_pos)| RelatedSpan { start_pos, end_pos }).collect();
+    Some(warning)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+    use std::time::{Duration, Instant};
+
+    const PROSE: &str = "The letter was opened by her brother. Mara walked quickly across the frozen field and said nothing at all.";
+
+    fn blob(bytes: usize) -> String {
+        "aGVsbG8gd29ybGQ0".chars().cycle().take(bytes).collect()
+    }
+
+    #[test]
+    fn opaque_tokens_are_masked_and_truncated() {
+        assert!(!is_opaque("storm", 64));
+        assert!(!is_opaque(&"\u{e9}".repeat(40), 64));
+        assert!(is_opaque(&"x".repeat(65), 64));
+
+        let text = format!("see {} now", "x".repeat(70));
+        let tokens = [(4, 74)];
+        let masked = mask(&text, &tokens);
+        assert_eq!(masked, format!("see {} now", " ".repeat(70)));
+        assert!(matches!(mask(PROSE, &[]), Cow::Borrowed(_)));
+
+        assert_eq!(truncate("short heading", 24), "short heading");
+        assert_eq!(truncate(&"y".repeat(30), 24), format!("{}\u{2026} (30 characters)", "y".repeat(24)));
+    }
+
+    #[test]
+    fn a_500_kb_token_is_analyzed_in_bounded_time_without_distorting_scores() {
+        let processor = TextProcessor::new();
+        let token = blob(500 * 1024);
+        let text = format!("{} {} {}", PROSE, token, PROSE);
+        let plain = processor.perform_analysis(&format!("{} {}", PROSE, PROSE));
+
+        let started = Instant::now();
+        let analysis = processor.perform_analysis(&text);
+        let report = processor.build_optimization_report(&text);
+        assert!(started.elapsed() < Duration::from_secs(20), "took {:?}", started.elapsed());
+
+        assert_eq!(analysis.word_count, plain.word_count + 1);
+        assert_eq!(analysis.complexity_metrics.avg_syllables_per_word, plain.complexity_metrics.avg_syllables_per_word);
+        assert_eq!(analysis.complexity_metrics.fog_index.round(), plain.complexity_metrics.fog_index.round());
+        let adverbs = |a: &crate::TextAnalysisResult| (a.style_metrics.adverb_ratio * a.word_count as f64).round();
+        assert_eq!(adverbs(&analysis), adverbs(&plain));
+
+        let warning = analysis.warnings.iter().find(|w| w.code == "opaque_tokens").unwrap();
+        let start = PROSE.len() + 1;
+        assert_eq!((warning.spans[0].start_pos, warning.spans[0].end_pos), (start, start + token.len()));
+        assert!(warning.message.len() < 300);
+
+        let longest_message = report.suggestions.iter().map(|s| s.message.len()).max().unwrap();
+        assert!(longest_message < 300);
+        assert!(report.suggestions.iter().all(|s| s.scope != "instance" || s.end_pos <= start || s.start_pos >= start + token.len()));
+    }
+
+    #[test]
+    fn limit_is_configurable() {
+        let mut processor = TextProcessor::new();
+        let text = format!("{} {}", PROSE, "z".repeat(30));
+        assert!(processor.perform_analysis(&text).warnings.iter().all(|w| w.code != "opaque_tokens"));
+        processor.config.max_token_length = 20;
+        let warning = processor.perform_analysis(&text).warnings.into_iter().find(|w| w.code == "opaque_tokens").unwrap();
+        assert!(warning.message.starts_with("1 token(s) longer than 20 characters"));
+    }
+}
@@ -0,0 +1,159 @@
+//! Presentation of float metrics: rounding per metric class and ratios as
+//! percentages. Applied to results just before they are serialized, after
+//! every threshold has been evaluated on the unrounded values.
+
+use serde::{Deserialize, Serialize};
+
+use crate::readability::SentenceDifficulty;
+use crate::TextAnalysisResult;
+
+/// `None` leaves a class at full precision, which is the default for all.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct OutputOptions {
+    /// Readability scores and grade levels (Flesch, fog index).
+    pub readability_decimals: Option<u32>,
+    /// Fractions such as `unique_word_ratio` and the style ratios.
+    pub ratio_decimals: Option<u32>,
+    /// Per-word and per-sentence averages and per-1,000-word densities.
+    pub average_decimals: Option<u32>,
+    /// Durations in seconds (`reading_time_seconds`).
+    pub time_decimals: Option<u32>,
+    /// Express ratios as 0–100 instead of 0–1. Applied before rounding, so
+    /// `ratio_decimals` counts decimals of the percentage.
+    pub ratios_as_percentages: bool,
+}
+
+fn round(value: &mut f64, decimals: Option<u32>) {
+    if let Some(decimals) = decimals {
+        let factor = 10f64.powi(decimals.min(15) as i32);
+        *value = (*value * factor).round() / factor;
+    }
+}
+
+impl OutputOptions {
+    pub fn readability(&self, value: &mut f64) {
+        round(value, self.readability_decimals);
+    }
+
+    pub fn ratio(&self, value: &mut f64) {
+        if self.ratios_as_percentages {
+            *value *= 100.0;
+        }
+        round(value, self.ratio_decimals);
+    }
+
+    pub fn average(&self, value: &mut f64) {
+        round(value, self.average_decimals);
+    }
+
+    pub fn time(&self, value: &mut f64) {
+        round(value, self.time_decimals);
+    }
+
+    pub fn apply_to_analysis(&self, result: &mut TextAnalysisResult) {
+        self.readability(&mut result.readability_score);
+        self.readability(&mut result.readability_score_raw);
+
+        let complexity = &mut result.complexity_metrics;
+        self.average(&mut complexity.avg_words_per_sentence);
+        self.average(&mut complexity.avg_syllables_per_word);
+        self.average(&mut complexity.avg_content_word_length);
+        self.readability(&mut complexity.fog_index);
+        self.readability(&mut complexity.flesch_reading_ease);
+        self.readability(&mut complexity.smog_index);
+        self.readability(&mut complexity.coleman_liau_index);
+        self.readability(&mut complexity.automated_readability_index);
+        self.readability(&mut complexity.lix);
+        self.average(&mut complexity.sentence_length_std_dev);
+        self.ratio(&mut complexity.unique_word_ratio);
+        self.ratio(&mut complexity.unique_content_word_ratio);
+        self.average(&mut complexity.mtld);
+        self.ratio(&mut complexity.mattr);
+        self.ratio(&mut complexity.lexical_density);
+
+        let style = &mut result.style_metrics;
+        for ratio in [
+            &mut style.passive_voice_ratio,
+            &mut style.adverb_ratio,
+            &mut style.dialogue_ratio,
+            &mut style.action_ratio,
+            &mut style.description_ratio,
+            &mut style.filter_word_ratio,
+        ] {
+            self.ratio(ratio);
+        }
+
+        self.average(&mut result.style_metrics.cliches_per_1000_words);
+        if let Some(density) = &mut result.first_person_per_1000_words {
+            self.average(density);
+        }
+        self.average(&mut result.sentiment_score);
+        if let Some(progress) = &mut result.progress_to_target {
+            self.ratio(progress);
+        }
+    }
+
+    pub fn apply_to_sentences(&self, sentences: &mut [SentenceDifficulty]) {
+        for sentence in sentences {
+            self.average(&mut sentence.syllables_per_word);
+            self.readability(&mut sentence.grade_level);
+            self.readability(&mut sentence.score);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DifficultyNormalization;
+    use crate::readability;
+    use crate::TextProcessor;
+
+    fn decimals(value: &serde_json::Value) -> usize {
+        let text = value.to_string();
+        text.split_once('.').map_or(0, |(_, fraction)| fraction.len())
+    }
+
+    const TEXT: &str = "The committee was finally convinced. They walked home slowly, talking about the unexpected decision.";
+
+    #[test]
+    fn serialized_metrics_respect_rounding_and_percentages() {
+        let options = OutputOptions {
+            readability_decimals: Some(1),
+            ratio_decimals: Some(3),
+            average_decimals: Some(2),
+            time_decimals: Some(0),
+            ratios_as_percentages: true,
+        };
+        let unrounded = TextProcessor::new().perform_analysis(TEXT);
+        let mut result = TextProcessor::new().perform_analysis(TEXT);
+        options.apply_to_analysis(&mut result);
+        let json = serde_json::to_value(&result).unwrap();
+
+        assert!(decimals(&json["readabilityScore"]) <= 1);
+        assert!(decimals(&json["complexityMetrics"]["fogIndex"]) <= 1);
+        assert!(decimals(&json["complexityMetrics"]["avgSyllablesPerWord"]) <= 2);
+        assert!(decimals(&json["complexityMetrics"]["uniqueWordRatio"]) <= 3);
+        let ratio = json["styleMetrics"]["adverbRatio"].as_f64().unwrap();
+        assert!((ratio - unrounded.style_metrics.adverb_ratio * 100.0).abs() < 0.0005);
+        assert!(ratio > 1.0);
+    }
+
+    #[test]
+    fn defaults_keep_full_precision() {
+        let mut result = TextProcessor::new().perform_analysis(TEXT);
+        let before = serde_json::to_value(&result).unwrap();
+        OutputOptions::default().apply_to_analysis(&mut result);
+        assert_eq!(serde_json::to_value(&result).unwrap(), before);
+    }
+
+    #[test]
+    fn buckets_are_decided_before_rounding() {
+        // 24 words and 33 syllables is grade 9.995: "medium", though it prints as 10.0
+        let mut sentences = readability::sentence_difficulties(&[(0, 100, 24, 33)], DifficultyNormalization::Absolute);
+        OutputOptions { readability_decimals: Some(1), ..OutputOptions::default() }.apply_to_sentences(&mut sentences);
+        assert_eq!(sentences[0].grade_level, 10.0);
+        assert_eq!(sentences[0].bucket, "medium");
+    }
+}
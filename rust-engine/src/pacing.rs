@@ -0,0 +1,159 @@
+//! Pacing over a rolling window of words: short sentences and dialogue read
+//! fast, long descriptive sentences slow.
+
+use serde::Serialize;
+
+use crate::segmentation::{self, DocumentIndex};
+
+pub const DEFAULT_WINDOW_WORDS: usize = 250;
+
+/// Average sentence lengths at or below this read at full speed...
+const FAST_SENTENCE_WORDS: f64 = 8.0;
+/// ...and at or above this at none.
+const SLOW_SENTENCE_WORDS: f64 = 28.0;
+
+/// Share of the pacing score that comes from sentence length; the rest
+/// comes from dialogue.
+const SENTENCE_LENGTH_WEIGHT: f64 = 0.5;
+
+#[derive(Serialize)]
+pub struct PacingPoint {
+    /// Index of the window's first word, for the chart's x axis.
+    pub start_word: usize,
+    pub word_count: usize,
+    pub start_pos: usize,
+    pub end_pos: usize,
+    /// Mean length in words of the sentences overlapping the window, counted
+    /// in full.
+    pub avg_sentence_length: f64,
+    /// Share of the window's words inside quoted speech.
+    pub dialogue_ratio: f64,
+    /// 0 (slow) to 1 (fast).
+    pub pacing_score: f64,
+}
+
+/// One point per window of `window_words` words, advancing half a window at
+/// a time; the last windows may be shorter. Text no longer than one window is
+/// a single point, and text without words has none.
+pub fn profile(index: &DocumentIndex, dialogue_spans: &[(usize, usize)], window_words: usize) -> Vec<PacingPoint> {
+    let words = &index.words;
+    if words.is_empty() {
+        return Vec::new();
+    }
+    let window = window_words.max(1);
+    let stride = (window / 2).max(1);
+    let starts: Vec<usize> = if words.len() <= window { vec![0] } else { (0..words.len()).step_by(stride).collect() };
+    let sentence_lengths: Vec<usize> = index.sentences.iter().map(|&(s, e)| index.words_in(s, e).len()).collect();
+
+    starts
+        .into_iter()
+        .map(|first| {
+            let in_window = &words[first..(first + window).min(words.len())];
+            let (start_pos, end_pos) = (in_window[0].0, in_window[in_window.len() - 1].1);
+            let lengths: Vec<usize> = index
+                .sentences
+                .iter()
+                .zip(&sentence_lengths)
+                .filter(|&(&(s, e), _)| s < end_pos && e > start_pos)
+                .map(|(_, &n)| n)
+                .collect();
+            let avg_sentence_length = if lengths.is_empty() {
+                in_window.len() as f64
+            } else {
+                lengths.iter().sum::<usize>() as f64 / lengths.len() as f64
+            };
+            let quoted = in_window.iter().filter(|w| segmentation::within(dialogue_spans, w.0, w.1)).count();
+            let dialogue_ratio = quoted as f64 / in_window.len() as f64;
+            let sentence_speed = ((SLOW_SENTENCE_WORDS - avg_sentence_length) / (SLOW_SENTENCE_WORDS - FAST_SENTENCE_WORDS)).clamp(0.0, 1.0);
+            PacingPoint {
+                start_word: first,
+                word_count: in_window.len(),
+                start_pos,
+                end_pos,
+                avg_sentence_length,
+                dialogue_ratio,
+                pacing_score: SENTENCE_LENGTH_WEIGHT * sentence_speed + (1.0 - SENTENCE_LENGTH_WEIGHT) * dialogue_ratio,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TextProcessor;
+    use crate::config::{AnalysisOptions, SuggestionConfig};
+    use crate::test_support::index;
+
+    use super::*;
+    use crate::dialogue;
+
+    fn points(text: &str, window_words: usize) -> Vec<PacingPoint> {
+        let index = index(text);
+        profile(&index, &dialogue::spans(text), window_words)
+    }
+
+    const DESCRIPTION: &str = "The long grey valley lay beneath a heavy blanket of autumn fog that clung to the slow river and the bare \
+        and silent trees along its banks for many miles. ";
+    const DIALOGUE: &str = "\"Run!\" \"Where?\" \"Now!\" \"Why?\" \"Go!\" ";
+
+    #[test]
+    fn a_dialogue_scene_between_descriptions_rises_then_falls() {
+        let text = format!("{}{}{}", DESCRIPTION.repeat(3), DIALOGUE.repeat(12), DESCRIPTION.repeat(3));
+        let scores: Vec<f64> = points(&text, 40).iter().map(|p| p.pacing_score).collect();
+        let peak = scores.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+        assert!(peak > 0 && peak < scores.len() - 1, "{:?}", scores);
+        assert!(scores[..=peak].windows(2).all(|w| w[0] <= w[1]), "{:?}", scores);
+        assert!(scores[peak..].windows(2).all(|w| w[0] >= w[1]), "{:?}", scores);
+        assert!(scores[0] < 0.1 && scores[peak] > 0.9, "{:?}", scores);
+    }
+
+    #[test]
+    fn windows_advance_by_half_and_the_tail_is_reported() {
+        let text = "one two three four five six seven eight nine ten eleven.";
+        let windows: Vec<(usize, usize)> = points(text, 4).iter().map(|p| (p.start_word, p.word_count)).collect();
+        assert_eq!(windows, [(0, 4), (2, 4), (4, 4), (6, 4), (8, 3), (10, 1)]);
+        let last = points(text, 4).pop().unwrap();
+        assert_eq!(&text[last.start_pos..last.end_pos], "eleven");
+    }
+
+    #[test]
+    fn short_and_empty_documents() {
+        let single = points("Mara ran. She hid.", 250);
+        assert_eq!(single.len(), 1);
+        assert_eq!((single[0].word_count, single[0].avg_sentence_length, single[0].dialogue_ratio), (4, 2.0, 0.0));
+        // Two-word sentences read at full speed
+        assert_eq!(single[0].pacing_score, SENTENCE_LENGTH_WEIGHT);
+        assert!(points(" \n ", 250).is_empty());
+    }
+
+    #[test]
+    fn reading_and_speaking_time_follow_the_configured_speeds() {
+        let processor = TextProcessor::new();
+        let paragraph = "Mara walked slowly to the old mill. ".repeat(68);
+        let result = processor.perform_analysis(&paragraph);
+        assert_eq!(result.word_count, 476);
+        assert_eq!((result.estimated_reading_time_seconds, result.estimated_speaking_time_seconds), (120.0, 190.0));
+
+        let empty = processor.perform_analysis("");
+        assert_eq!((empty.estimated_reading_time_seconds, empty.estimated_speaking_time_seconds), (0.0, 0.0));
+
+        let analysis = AnalysisOptions { reading_words_per_minute: 476.0, speaking_words_per_minute: 0.0, ..Default::default() };
+        let fast = TextProcessor::with_patterns(&Default::default(), SuggestionConfig { analysis, ..Default::default() });
+        let result = fast.perform_analysis(&paragraph);
+        assert_eq!((result.estimated_reading_time_seconds, result.estimated_speaking_time_seconds), (60.0, 0.0));
+    }
+
+    #[test]
+    fn dialogue_reads_faster_and_paragraph_breaks_add_pauses() {
+        let processor = TextProcessor::new();
+        let narration = "Mara walked slowly to the old mill. ".repeat(34);
+        let seconds = |text: &str| processor.perform_analysis(text).estimated_reading_time_seconds;
+        assert_eq!(seconds(&narration), 60.0);
+        // 238 words of dialogue at 1.1x speed
+        assert_eq!(seconds(&format!("\"{}\"", narration.trim_end())), (60.0f64 / 1.1).round());
+        // Twenty paragraph breaks at half a second each
+        let paragraphs = vec!["Mara walked slowly to the old mill. ".repeat(34 / 2 - 6); 21].join("\n\n");
+        let words = processor.perform_analysis(&paragraphs).word_count as f64;
+        assert_eq!(seconds(&paragraphs), (words * 60.0 / 238.0 + 10.0).round());
+    }
+}
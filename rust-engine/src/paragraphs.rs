@@ -0,0 +1,186 @@
+//! Per-paragraph stats for outline views: size, readability, and the
+//! narrative mode that dominates each paragraph.
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::markdown;
+use crate::narrative::SentenceMode;
+use crate::readability::{ReadingEase, READING_EASE_MAX, READING_EASE_MIN};
+use crate::reflow::OffsetMap;
+use crate::segmentation;
+
+#[derive(Serialize)]
+pub struct ParagraphAnalysis {
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub word_count: usize,
+    /// Sentences overlapping the paragraph; one running across a paragraph
+    /// break counts in both.
+    pub sentence_count: usize,
+    /// Flesch reading ease of the paragraph on its own, clamped to [0, 100].
+    pub flesch_reading_ease: f64,
+    pub has_dialogue: bool,
+    /// The most common of dialogue, action, and description among the
+    /// paragraph's sentences (earlier ones win ties); neutral when none is.
+    pub dominant_style: SentenceMode,
+}
+
+/// Counts for one paragraph; `measured_words` and `syllables` leave out
+/// opaque tokens, as the document averages do.
+pub struct ParagraphCounts<'m> {
+    pub word_count: usize,
+    pub measured_words: usize,
+    pub syllables: usize,
+    pub sentence_modes: &'m [SentenceMode],
+    pub has_dialogue: bool,
+}
+
+pub fn paragraph_analysis(start_pos: usize, end_pos: usize, counts: &ParagraphCounts, formula: ReadingEase) -> ParagraphAnalysis {
+    let sentence_count = counts.sentence_modes.len();
+    let flesch = if counts.measured_words > 0 && sentence_count > 0 {
+        formula.score(
+            counts.measured_words as f64 / sentence_count as f64,
+            counts.syllables as f64 / counts.measured_words as f64,
+        )
+    } else {
+        READING_EASE_MAX
+    };
+    let dominant_style = [SentenceMode::Dialogue, SentenceMode::Action, SentenceMode::Description]
+        .into_iter()
+        .map(|mode| (mode, counts.sentence_modes.iter().filter(|&&m| m == mode).count()))
+        .filter(|&(_, n)| n > 0)
+        .fold(None, |best: Option<(SentenceMode, usize)>, (mode, n)| match best {
+            Some((_, most)) if most >= n => best,
+            _ => Some((mode, n)),
+        })
+        .map_or(SentenceMode::Neutral, |(mode, _)| mode);
+    ParagraphAnalysis {
+        start_pos,
+        end_pos,
+        word_count: counts.word_count,
+        sentence_count,
+        flesch_reading_ease: flesch.clamp(READING_EASE_MIN, READING_EASE_MAX),
+        has_dialogue: counts.has_dialogue,
+        dominant_style,
+    }
+}
+
+/// Paragraphs of Markdown-stripped `text`. Breaks of the `paragraph`
+/// pattern within a lone line break don't split hard-wrapped text; line
+/// breaks around headings and before list items in `source` do, whatever
+/// the pattern, and even where reflowing turned them into spaces.
+pub fn markdown_paragraphs(text: &str, paragraph_re: &Regex, map: &OffsetMap, source: &str) -> Vec<(usize, usize)> {
+    // The whole whitespace run around a break decides, so a pattern
+    // matching each line break of a blank line still splits there
+    let lone_line_break = |start: usize, end: usize| {
+        let run_start = text[..start].trim_end().len();
+        let run_end = end + (text[end..].len() - text[end..].trim_start().len());
+        let run = &text[run_start..run_end];
+        run.trim().is_empty() && run.matches('\n').count() == 1
+    };
+    let mut breaks: Vec<(usize, usize)> = paragraph_re
+        .find_iter(text)
+        .map(|m| (m.start(), m.end()))
+        .filter(|&(start, end)| !lone_line_break(start, end))
+        .collect();
+    breaks.extend(
+        text.char_indices()
+            .filter(|&(i, c)| c.is_whitespace() && markdown::is_block_break(source, map.to_original(i)))
+            .map(|(i, c)| (i, i + c.len_utf8())),
+    );
+    breaks.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in breaks {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    segmentation::paragraphs_at(text, merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+    use crate::config::{InputFormat, SuggestionConfig};
+
+    fn counts(sentence_modes: &[SentenceMode]) -> ParagraphCounts<'_> {
+        ParagraphCounts { word_count: 12, measured_words: 12, syllables: 15, sentence_modes, has_dialogue: false }
+    }
+
+    fn style(modes: &[SentenceMode]) -> SentenceMode {
+        paragraph_analysis(0, 10, &counts(modes), ReadingEase::Flesch).dominant_style
+    }
+
+    #[test]
+    fn the_most_common_mode_dominates_and_earlier_ones_win_ties() {
+        use SentenceMode::*;
+        assert!(style(&[Action, Description, Description]) == Description);
+        assert!(style(&[Description, Action]) == Action);
+        assert!(style(&[Action, Dialogue]) == Dialogue);
+        assert!(style(&[Neutral, Neutral]) == Neutral);
+        assert!(style(&[]) == Neutral);
+    }
+
+    #[test]
+    fn flesch_is_per_paragraph_and_clamped() {
+        use SentenceMode::Neutral;
+        // 12 words in 2 sentences, 1.25 syllables a word
+        let analysis = paragraph_analysis(0, 10, &counts(&[Neutral, Neutral]), ReadingEase::Flesch);
+        assert!((analysis.flesch_reading_ease - 94.995).abs() < 1e-9, "{}", analysis.flesch_reading_ease);
+        let empty = ParagraphCounts { word_count: 0, measured_words: 0, syllables: 0, sentence_modes: &[], has_dialogue: false };
+        assert_eq!(paragraph_analysis(0, 0, &empty, ReadingEase::Flesch).flesch_reading_ease, READING_EASE_MAX);
+        let dense = ParagraphCounts { syllables: 60, ..counts(&[Neutral]) };
+        assert_eq!(paragraph_analysis(0, 10, &dense, ReadingEase::Flesch).flesch_reading_ease, READING_EASE_MIN);
+    }
+
+    fn paragraphs(source: &str, paragraph_re: &str) -> Vec<String> {
+        let (text, map) = markdown::strip(source);
+        markdown_paragraphs(&text, &Regex::new(paragraph_re).unwrap(), &map, source).into_iter().map(|(s, e)| text[s..e].to_string()).collect()
+    }
+
+    #[test]
+    fn hard_wrapped_markdown_keeps_its_paragraphs() {
+        let source = "# Storm\nThe rain came\ndown all night.\n\nMara waited\nby the door.\n- one\n- two";
+        let expected = ["Storm", "The rain came\ndown all night.", "Mara waited\nby the door.", "one", "two"];
+        assert_eq!(paragraphs(source, r"\n\s*\n"), expected);
+        // A pattern splitting on every line break leaves wrapped lines together
+        assert_eq!(paragraphs(source, r"\n"), expected);
+    }
+
+    #[test]
+    fn paragraph_word_counts_add_up_to_the_document_word_count() {
+        let processor = TextProcessor::new();
+        let text = "  \t\"Run now, all of you!\" she said.\n\n\n   \nThe rain fell hard and fast.\r\n\r\nShe waited  by the\u{a0}door\n  \n\u{3000}\nand then   slept ";
+        let index = processor.index(text);
+        let paragraphs = processor.paragraph_analyses(&index, None);
+        let spans: Vec<&str> = paragraphs.iter().map(|p| &text[p.start_pos..p.end_pos]).collect();
+        assert_eq!(spans, ["\"Run now, all of you!\" she said.", "The rain fell hard and fast.", "She waited  by the\u{a0}door", "and then   slept"]);
+        let total: usize = paragraphs.iter().map(|p| p.word_count).sum();
+        assert_eq!(total, processor.perform_analysis(text).word_count);
+        assert!(paragraphs[0].has_dialogue && paragraphs[0].dominant_style == SentenceMode::Dialogue);
+        assert!(!paragraphs[1].has_dialogue);
+        assert_eq!(paragraphs.iter().map(|p| p.sentence_count).collect::<Vec<_>>(), [1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn markdown_paragraphs_follow_blocks_and_sum_to_the_word_count() {
+        let source = "## Storm\nThe rain came\ndown all night.\n\n- Mara waited\n- by the door\n";
+        let processor = TextProcessor::with_patterns(&Default::default(), SuggestionConfig { input_format: InputFormat::Markdown, ..Default::default() });
+        let prepared = processor.prepared(source).unwrap();
+        let index = processor.index(&prepared.text);
+        let paragraphs = processor.paragraph_analyses(&index, Some((&prepared.offset_map, source)));
+        let spans: Vec<&str> = paragraphs
+            .iter()
+            .map(|p| {
+                let (start, end) = prepared.offset_map.map_span(p.start_pos, p.end_pos);
+                &source[start..end]
+            })
+            .collect();
+        assert_eq!(spans, ["Storm", "The rain came\ndown all night.", "Mara waited", "by the door"]);
+        let total: usize = paragraphs.iter().map(|p| p.word_count).sum();
+        assert_eq!(total, processor.perform_analysis(source).word_count);
+    }
+}
@@ -0,0 +1,337 @@
+//! Passive voice: a form of "to be", up to two adverbs, then a past
+//! participle. Participles are the regular "-ed" forms that aren't usually
+//! adjectives, plus the common irregular ones.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+const BE_FORMS: &[&str] = &["am", "is", "are", "was", "were", "be", "been", "being"];
+
+/// Adverbs that sit between the auxiliary and the participle besides
+/// "-ly" words ("was never seen", "were quickly taken").
+const ADVERBS: &[&str] = &[
+    "all", "already", "also", "always", "even", "ever", "just", "never", "not", "often", "soon", "still", "then",
+    "thus",
+];
+
+/// The most adverbs allowed between auxiliary and participle.
+const MAX_ADVERBS: usize = 2;
+
+/// `(participle, simple past)` of common irregular verbs.
+const IRREGULAR: &[(&str, &str)] = &[
+    ("beaten", "beat"),
+    ("begun", "began"),
+    ("bent", "bent"),
+    ("bitten", "bit"),
+    ("blown", "blew"),
+    ("born", "bore"),
+    ("borne", "bore"),
+    ("bound", "bound"),
+    ("bought", "bought"),
+    ("bred", "bred"),
+    ("broadcast", "broadcast"),
+    ("broken", "broke"),
+    ("brought", "brought"),
+    ("built", "built"),
+    ("burnt", "burnt"),
+    ("burst", "burst"),
+    ("cast", "cast"),
+    ("caught", "caught"),
+    ("chosen", "chose"),
+    ("cut", "cut"),
+    ("dealt", "dealt"),
+    ("done", "did"),
+    ("drawn", "drew"),
+    ("driven", "drove"),
+    ("dug", "dug"),
+    ("eaten", "ate"),
+    ("fed", "fed"),
+    ("felt", "felt"),
+    ("forbidden", "forbade"),
+    ("forgiven", "forgave"),
+    ("forgotten", "forgot"),
+    ("fought", "fought"),
+    ("found", "found"),
+    ("frozen", "froze"),
+    ("given", "gave"),
+    ("grown", "grew"),
+    ("heard", "heard"),
+    ("held", "held"),
+    ("hidden", "hid"),
+    ("hit", "hit"),
+    ("hung", "hung"),
+    ("hurt", "hurt"),
+    ("kept", "kept"),
+    ("known", "knew"),
+    ("laid", "laid"),
+    ("led", "led"),
+    ("left", "left"),
+    ("lent", "lent"),
+    ("let", "let"),
+    ("lost", "lost"),
+    ("made", "made"),
+    ("meant", "meant"),
+    ("met", "met"),
+    ("overcome", "overcame"),
+    ("overtaken", "overtook"),
+    ("paid", "paid"),
+    ("proven", "proved"),
+    ("put", "put"),
+    ("read", "read"),
+    ("ridden", "rode"),
+    ("rung", "rang"),
+    ("said", "said"),
+    ("seen", "saw"),
+    ("sent", "sent"),
+    ("set", "set"),
+    ("sewn", "sewed"),
+    ("shaken", "shook"),
+    ("shed", "shed"),
+    ("shot", "shot"),
+    ("shown", "showed"),
+    ("shut", "shut"),
+    ("slain", "slew"),
+    ("sold", "sold"),
+    ("sought", "sought"),
+    ("sown", "sowed"),
+    ("spent", "spent"),
+    ("split", "split"),
+    ("spoken", "spoke"),
+    ("spread", "spread"),
+    ("spun", "spun"),
+    ("stolen", "stole"),
+    ("struck", "struck"),
+    ("stung", "stung"),
+    ("sung", "sang"),
+    ("sunk", "sank"),
+    ("sworn", "swore"),
+    ("swung", "swung"),
+    ("taken", "took"),
+    ("taught", "taught"),
+    ("thought", "thought"),
+    ("thrown", "threw"),
+    ("told", "told"),
+    ("torn", "tore"),
+    ("understood", "understood"),
+    ("undertaken", "undertook"),
+    ("upheld", "upheld"),
+    ("won", "won"),
+    ("worn", "wore"),
+    ("woven", "wove"),
+    ("wound", "wound"),
+    ("written", "wrote"),
+];
+
+/// "-ed" words mostly used as adjectives after "to be" ("was excited to
+/// go"); they still count as participles when a "by" agent follows.
+const ADJECTIVAL: &[&str] = &[
+    "advanced", "aged", "amazed", "annoyed", "ashamed", "balanced", "beloved", "bored", "complicated", "concerned",
+    "confused", "convinced", "crowded", "dedicated", "delighted", "detailed", "determined", "devoted", "disappointed",
+    "embarrassed", "engaged", "excited", "exhausted", "experienced", "frightened", "hundred", "interested", "involved",
+    "limited", "married", "naked", "pleased", "prepared", "qualified", "related", "relieved", "rugged", "sacred",
+    "satisfied", "scared", "shocked", "sophisticated", "supposed", "surprised", "talented", "terrified", "thrilled",
+    "tired", "used", "wicked", "worried",
+];
+
+/// "-eed" words that are participles; the rest ("indeed", "need") aren't.
+const EED_PARTICIPLES: &[&str] = &["agreed", "decreed", "freed", "guaranteed", "refereed"];
+
+fn is_regular_participle(word: &str) -> bool {
+    word.len() > 4 && word.ends_with("ed") && (!word.ends_with("eed") || EED_PARTICIPLES.contains(&word))
+}
+
+fn is_adverb(word: &str) -> bool {
+    (word.len() > 4 && word.ends_with("ly")) || ADVERBS.contains(&word)
+}
+
+/// A regular "-ed" or a common irregular past participle.
+pub fn is_participle(word: &str) -> bool {
+    let lower = word.to_lowercase();
+    is_regular_participle(&lower) || IRREGULAR.iter().any(|(p, _)| *p == lower)
+}
+
+/// The simple past of `participle` ("kicked" -> "kicked", "written" ->
+/// "wrote"), for rewrites to active voice.
+pub fn past_tense(participle: &str) -> Option<String> {
+    let lower = participle.to_lowercase();
+    if let Some((_, past)) = IRREGULAR.iter().find(|(p, _)| *p == lower) {
+        return Some(past.to_string());
+    }
+    is_regular_participle(&lower).then_some(lower)
+}
+
+/// Byte spans of passive constructions in `text`, each from the auxiliary
+/// through the participle ("was quickly taken"). Words of one construction
+/// must be separated by whitespace only.
+pub fn find(text: &str) -> Vec<(usize, usize)> {
+    let words: Vec<(usize, usize)> = text.unicode_word_indices().map(|(i, w)| (i, i + w.len())).collect();
+    let lower = |k: usize| text[words[k].0..words[k].1].to_lowercase();
+    let adjacent = |a: usize, b: usize| text[words[a].1..words[b].0].chars().all(char::is_whitespace);
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        let word = &text[words[i].0..words[i].1];
+        if !BE_FORMS.iter().any(|b| b.eq_ignore_ascii_case(word)) {
+            i += 1;
+            continue;
+        }
+        let mut j = i + 1;
+        let mut adverbs = 0;
+        while j < words.len() && adverbs < MAX_ADVERBS && adjacent(j - 1, j) && is_adverb(&lower(j)) {
+            j += 1;
+            adverbs += 1;
+        }
+        // "is being built"
+        if j < words.len() && adjacent(j - 1, j) && lower(j) == "being" {
+            j += 1;
+        }
+        let is_participle = j < words.len() && adjacent(j - 1, j) && {
+            let w = lower(j);
+            let agent_follows = j + 1 < words.len() && adjacent(j, j + 1) && lower(j + 1) == "by";
+            let regular = is_regular_participle(&w) && (agent_follows || !ADJECTIVAL.contains(&w.as_str()));
+            regular || IRREGULAR.iter().any(|(p, _)| *p == w)
+        };
+        if is_participle {
+            spans.push((words[i].0, words[j].1));
+            i = j + 1;
+        } else {
+            i += 1;
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+    use crate::suggestions::SCOPE_INSTANCE;
+
+    /// Sentences labelled by whether they contain a passive construction.
+    const LABELLED: &[(&str, bool)] = &[
+        ("The letter was written by her brother.", true),
+        ("The samples were taken at dawn.", true),
+        ("She has been given a second chance.", true),
+        ("The bridge is being built.", true),
+        ("The results are published every year.", true),
+        ("Mistakes were made.", true),
+        ("The door was quickly opened.", true),
+        ("The town was never seen again.", true),
+        ("He is known for his temper.", true),
+        ("The house was sold last spring.", true),
+        ("The window was broken by the storm.", true),
+        ("I am told the train is late.", true),
+        ("The prize will be awarded tomorrow.", true),
+        ("The rules were clearly explained.", true),
+        ("The song was sung at every wedding.", true),
+        ("The thief was caught.", true),
+        ("The meeting was postponed.", true),
+        ("Her name was forgotten.", true),
+        ("The crops were destroyed by the flood.", true),
+        ("The cake is eaten before noon.", true),
+        ("The grant was approved.", true),
+        ("The children were taught at home.", true),
+        ("The ship was sunk by a torpedo.", true),
+        ("The report was already finished.", true),
+        ("He was excited by the news.", true),
+        ("The deal was agreed on Monday.", true),
+        ("She was excited to go.", false),
+        ("They were tired after the hike.", false),
+        ("He was indeed happy.", false),
+        ("The room was crowded.", false),
+        ("She is married to a sailor.", false),
+        ("I am interested in history.", false),
+        ("We were worried about the rent.", false),
+        ("He is very talented.", false),
+        ("The dog barked all night.", false),
+        ("She walked to the station.", false),
+        ("They are at home.", false),
+        ("It was a red car.", false),
+        ("Mara is here.", false),
+        ("He was in the garden.", false),
+        ("The sky was blue.", false),
+        ("They were friends.", false),
+        ("She was reading a book.", false),
+        ("He kicked the ball.", false),
+        ("I need a break.", false),
+        ("We are going home.", false),
+        ("The weather is nice today.", false),
+        ("She was used to the cold.", false),
+        ("It is a hundred miles away.", false),
+        ("He was bored.", false),
+        ("They were supposed to call.", false),
+        ("I was there.", false),
+    ];
+
+    fn spans(text: &str) -> Vec<&str> {
+        find(text).into_iter().map(|(s, e)| &text[s..e]).collect()
+    }
+
+    #[test]
+    fn labelled_sentences_reach_ninety_percent_precision_and_recall() {
+        assert!(LABELLED.len() >= 50);
+        let (mut true_positive, mut false_positive, mut false_negative) = (0, 0, 0);
+        let mut wrong = Vec::new();
+        for &(sentence, passive) in LABELLED {
+            let detected = !find(sentence).is_empty();
+            match (detected, passive) {
+                (true, true) => true_positive += 1,
+                (true, false) => false_positive += 1,
+                (false, true) => false_negative += 1,
+                (false, false) => {}
+            }
+            if detected != passive {
+                wrong.push(sentence);
+            }
+        }
+        let precision = true_positive as f64 / (true_positive + false_positive) as f64;
+        let recall = true_positive as f64 / (true_positive + false_negative) as f64;
+        assert!(precision >= 0.9 && recall >= 0.9, "precision {:.2}, recall {:.2}; wrong: {:?}", precision, recall, wrong);
+    }
+
+    #[test]
+    fn spans_cover_the_whole_construction() {
+        assert!(spans("The ball was quickly and loudly taken.").is_empty());
+        assert_eq!(spans("It was really quickly taken."), ["was really quickly taken"]);
+        assert_eq!(spans("It is being built and was then sold."), ["is being built", "was then sold"]);
+        // Up to two adverbs; punctuation breaks a construction
+        assert!(spans("It was not often really taken.").is_empty());
+        assert!(spans("It was, taken.").is_empty());
+        // Adjectival words count when an agent follows
+        assert_eq!(spans("She was tired. She was tired by the climb."), ["was tired"]);
+    }
+
+    #[test]
+    fn past_tense_of_participles() {
+        assert_eq!(past_tense("Written").as_deref(), Some("wrote"));
+        assert_eq!(past_tense("kicked").as_deref(), Some("kicked"));
+        assert_eq!(past_tense("indeed"), None);
+        assert_eq!(past_tense("happy"), None);
+    }
+
+    #[test]
+    fn generate_optimization_suggestions_flags_passive_voice() {
+        let processor = TextProcessor::new();
+        let text = "The letter was written by the clerk. She read it twice.";
+        let suggestions = processor.generate_optimization_suggestions(text, &|_| true);
+        let passive = suggestions.iter().find(|s| s.suggestion_type == "passive_voice").expect("passive voice flagged");
+        assert!(text[passive.start_pos..passive.end_pos].contains("was written"));
+        assert!(suggestions.iter().all(|s| s.start_pos <= s.end_pos && s.end_pos <= text.len()));
+    }
+
+    #[test]
+    fn passive_suggestions_span_the_construction_and_skip_adjectives() {
+        let processor = TextProcessor::new();
+        let text = "The letter was quickly written. She was excited to go. The vase was broken by Mara.";
+        let report = processor.build_optimization_report(text);
+        let passive: Vec<&str> = report
+            .suggestions
+            .iter()
+            .filter(|s| s.suggestion_type == "passive_voice" && s.scope == SCOPE_INSTANCE)
+            .map(|s| &text[s.start_pos..s.end_pos])
+            .collect();
+        // The rewritable one covers the clause its rewrite replaces
+        assert_eq!(passive, ["was quickly written", "The vase was broken by Mara"]);
+        let ratio = processor.perform_analysis(text).style_metrics.passive_voice_ratio;
+        assert!((ratio - 2.0 / 3.0).abs() < 1e-9, "{}", ratio);
+    }
+}
@@ -0,0 +1,175 @@
+//! Fallible compilation of the engine's regex patterns, with optional
+//! overrides from a user-supplied pattern pack.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::segmentation::WordSegmenter;
+
+/// Replaces the built-in Unicode (UAX #29) word segmentation when given.
+pub const WORD_PATTERN_ID: &str = "word";
+
+/// Core patterns fall back to their built-in form when an override fails to
+/// compile; rule patterns are disabled instead, along with the rules they feed.
+pub const CORE_PATTERNS: &[(&str, &str)] = &[
+    ("sentence", r"[.!?]+"),
+    ("paragraph", r"\n\s*\n"),
+];
+
+pub const RULE_PATTERNS: &[(&str, &str, &[&str])] = &[
+    ("passive_voice", r"\b(was|were|been|being)\s+\w+ed\b", &["passive_voice"]),
+    ("adverb", r"\b\w+ly\b", &["adverb_usage"]),
+    ("dialogue", r#""[^"]*""#, &["dialogue_detection"]),
+    (
+        "person_pronoun",
+        r"(?i)\b(i|me|my|mine|myself|we|us|our|ours|ourselves|you|your|yours|yourself|yourselves)\b",
+        &["first_person", "second_person"],
+    ),
+];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PatternFailure {
+    pub pattern_id: String,
+    pub pattern: String,
+    pub error: String,
+    pub disabled_rules: Vec<String>,
+    pub fell_back_to_default: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InitializationReport {
+    pub disabled_rules: Vec<String>,
+    pub failures: Vec<PatternFailure>,
+}
+
+pub struct PatternCompiler<'a> {
+    overrides: &'a BTreeMap<String, String>,
+    failures: Vec<PatternFailure>,
+}
+
+impl<'a> PatternCompiler<'a> {
+    pub fn new(overrides: &'a BTreeMap<String, String>) -> PatternCompiler<'a> {
+        let mut failures = Vec::new();
+        for id in overrides.keys() {
+            let known = id == WORD_PATTERN_ID
+                || CORE_PATTERNS.iter().any(|p| p.0 == id)
+                || RULE_PATTERNS.iter().any(|p| p.0 == id);
+            if !known {
+                failures.push(PatternFailure {
+                    pattern_id: id.clone(),
+                    pattern: overrides[id].clone(),
+                    error: "unknown pattern id".to_string(),
+                    disabled_rules: Vec::new(),
+                    fell_back_to_default: false,
+                });
+            }
+        }
+        PatternCompiler { overrides, failures }
+    }
+
+    /// The override for a core pattern, if one was given and compiles.
+    fn core_override(&mut self, id: &str) -> Option<Regex> {
+        let pattern = self.overrides.get(id)?;
+        match Regex::new(pattern) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                self.failures.push(PatternFailure {
+                    pattern_id: id.to_string(),
+                    pattern: pattern.clone(),
+                    error: e.to_string(),
+                    disabled_rules: Vec::new(),
+                    fell_back_to_default: true,
+                });
+                None
+            }
+        }
+    }
+
+    pub fn core(&mut self, id: &str) -> Regex {
+        let default = CORE_PATTERNS.iter().find(|p| p.0 == id).map(|p| p.1).unwrap_or_default();
+        self.core_override(id).unwrap_or_else(|| Regex::new(default).expect("built-in core pattern compiles"))
+    }
+
+    pub fn words(&mut self) -> WordSegmenter {
+        self.core_override(WORD_PATTERN_ID).map_or(WordSegmenter::Unicode, WordSegmenter::Pattern)
+    }
+
+    pub fn rule(&mut self, id: &str) -> Option<Regex> {
+        let (_, default, rules) = RULE_PATTERNS.iter().find(|p| p.0 == id)?;
+        let pattern = self.overrides.get(id).map(String::as_str).unwrap_or(default);
+        match Regex::new(pattern) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                self.failures.push(PatternFailure {
+                    pattern_id: id.to_string(),
+                    pattern: pattern.to_string(),
+                    error: e.to_string(),
+                    disabled_rules: rules.iter().map(|r| r.to_string()).collect(),
+                    fell_back_to_default: false,
+                });
+                None
+            }
+        }
+    }
+
+    pub fn into_failures(self) -> Vec<PatternFailure> {
+        self.failures
+    }
+}
+
+/// Whether `regex` is the built-in form of core pattern `id`.
+pub fn is_builtin_core(id: &str, regex: &Regex) -> bool {
+    CORE_PATTERNS.iter().any(|p| p.0 == id && p.1 == regex.as_str())
+}
+
+/// Whether `regex` is the built-in form of rule pattern `id`.
+pub fn is_builtin_rule(id: &str, regex: &Regex) -> bool {
+    RULE_PATTERNS.iter().any(|p| p.0 == id && p.1 == regex.as_str())
+}
+
+pub fn report(failures: &[PatternFailure]) -> InitializationReport {
+    let mut disabled_rules: Vec<String> = failures.iter().flat_map(|f| f.disabled_rules.iter().cloned()).collect();
+    disabled_rules.sort();
+    disabled_rules.dedup();
+    InitializationReport { disabled_rules, failures: failures.to_vec() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    fn pack(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries.iter().map(|(id, pattern)| (id.to_string(), pattern.to_string())).collect()
+    }
+
+    #[test]
+    fn broken_pattern_pack_disables_its_rules_and_analysis_still_runs() {
+        let overrides = pack(&[("passive_voice", r"\b(was|were"), ("sentence", "[.!?"), ("mystery", "x")]);
+        let processor = TextProcessor::with_patterns(&overrides, Default::default());
+
+        let report = report(&processor.pattern_failures);
+        assert_eq!(report.disabled_rules, ["passive_voice"]);
+        let failed: Vec<(&str, bool)> = report.failures.iter().map(|f| (f.pattern_id.as_str(), f.fell_back_to_default)).collect();
+        assert_eq!(failed, [("mystery", false), ("sentence", true), ("passive_voice", false)]);
+        assert!(report.failures.iter().all(|f| !f.error.is_empty()));
+
+        let text = "The door was opened slowly. She walked in.";
+        let result = processor.perform_analysis(text);
+        assert_eq!((result.word_count, result.sentence_count), (8, 2));
+        assert_eq!(result.style_metrics.passive_voice_ratio, 0.0);
+        assert!(result.warnings.iter().any(|w| w.code == "rules_disabled" && w.message.contains("passive_voice")));
+
+        let suggestions = processor.build_optimization_report(text).suggestions;
+        assert!(suggestions.iter().any(|s| s.suggestion_type == "adverb_usage"));
+        assert!(suggestions.iter().all(|s| s.suggestion_type != "passive_voice"));
+    }
+
+    #[test]
+    fn default_patterns_compile_cleanly() {
+        let processor = TextProcessor::new();
+        assert!(processor.pattern_failures.is_empty());
+        assert!(processor.perform_analysis("The door was opened.").warnings.iter().all(|w| w.code != "rules_disabled"));
+    }
+}
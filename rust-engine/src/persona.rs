@@ -0,0 +1,402 @@
+//! Reader-persona scores: how a text reads to a skimmer, how engaging it is
+//! to a careful reader, and how much effort it demands. Each score is a
+//! weighted mean of factor scores built from signals the engine already
+//! computes; every factor is a plain function of its inputs.
+
+use serde::{Deserialize, Serialize};
+
+use crate::readability;
+use crate::segmentation::DocumentIndex;
+use crate::structure;
+use crate::{RelatedSpan, TextProcessor};
+
+/// Worst-offender spans reported per factor.
+const WORST_OFFENDER_LIMIT: usize = 3;
+/// One heading per this many words counts as fully skimmable.
+const WORDS_PER_HEADING: f64 = 300.0;
+/// Paragraphs longer than this are a wall of text to a skimmer.
+const LONG_PARAGRAPH_WORDS: usize = 100;
+/// List items per 1,000 words for a full list-usage score.
+const FULL_LIST_ITEMS_PER_1000: f64 = 10.0;
+/// Words before the main clause that stop a sentence counting as front-loaded.
+const MAX_LEAD_IN_WORDS: usize = 6;
+/// Dialogue share of words where dialogue and narration balance best.
+const IDEAL_DIALOGUE_SHARE: f64 = 0.35;
+/// Coefficient of variation of sentence length for a full variety score.
+const FULL_SENTENCE_VARIETY: f64 = 0.5;
+/// Questions per 1,000 words for a full question-density score.
+const FULL_QUESTIONS_PER_1000: f64 = 5.0;
+/// Mean clause markers per sentence at which clause depth scores 100.
+const MAX_CLAUSE_DEPTH: f64 = 4.0;
+/// Share of 3+ syllable words at which polysyllable density scores 100.
+const MAX_POLYSYLLABLE_SHARE: f64 = 0.2;
+/// Mean sentence length (words) where length starts adding load, and the
+/// span over which it climbs to 100.
+const LOAD_FREE_SENTENCE_WORDS: f64 = 10.0;
+const LOAD_SENTENCE_WORDS_RANGE: f64 = 20.0;
+
+/// Openers of a subordinate clause; a sentence starting with one puts its
+/// main clause second.
+const SUBORDINATORS: &[&str] = &[
+    "although", "though", "because", "since", "when", "whenever", "while", "if", "unless", "after", "before",
+    "as", "once", "until", "despite", "whereas", "given",
+];
+/// Words that open a nested clause mid-sentence, counted with commas and
+/// semicolons as a depth approximation.
+const CLAUSE_MARKERS: &[&str] = &[
+    "which", "that", "who", "whom", "whose", "because", "although", "while", "whereas", "if", "when", "since",
+    "unless", "where",
+];
+
+#[derive(Serialize, Deserialize)]
+pub struct PersonaFactor {
+    pub name: String,
+    /// The measured signal, in the unit named by the factor.
+    pub value: f64,
+    /// 0–100.
+    pub score: f64,
+    /// Share of the persona score, summing to 1 across factors.
+    pub weight: f64,
+    /// Spans that pull the score the wrong way the most.
+    pub worst: Vec<RelatedSpan>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PersonaScore {
+    /// 0–100: weighted mean of the factor scores.
+    pub score: f64,
+    pub factors: Vec<PersonaFactor>,
+}
+
+/// Skimmability and engagement are better high; cognitive load is better low.
+#[derive(Serialize, Deserialize)]
+pub struct PersonaScores {
+    /// Fewer words than `min_word_count`; the scores rest on little evidence.
+    pub low_confidence: bool,
+    pub skimmability: PersonaScore,
+    pub engagement: PersonaScore,
+    pub cognitive_load: PersonaScore,
+}
+
+/// Per-sentence signals shared by several factors.
+pub struct SentenceStats {
+    pub start: usize,
+    pub end: usize,
+    pub words: usize,
+    pub polysyllables: usize,
+    /// Commas, semicolons, and clause-opening words.
+    pub clause_markers: usize,
+    /// Words before the first comma when the sentence opens with a
+    /// subordinate clause; `None` when the main clause comes first.
+    pub lead_in_words: Option<usize>,
+    pub question: bool,
+}
+
+fn factor(name: &str, value: f64, score: f64, weight: f64, worst: Vec<RelatedSpan>) -> PersonaFactor {
+    PersonaFactor { name: name.to_string(), value, score: score.clamp(0.0, 100.0), weight, worst }
+}
+
+fn combine(factors: Vec<PersonaFactor>) -> PersonaScore {
+    let score = factors.iter().map(|f| f.score * f.weight).sum();
+    PersonaScore { score, factors }
+}
+
+/// The `WORST_OFFENDER_LIMIT` spans with the highest badness, worst first;
+/// ties keep document order.
+fn worst_by(candidates: impl Iterator<Item = (f64, usize, usize)>) -> Vec<RelatedSpan> {
+    let mut candidates: Vec<(f64, usize, usize)> = candidates.filter(|c| c.0 > 0.0).collect();
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+    candidates.into_iter().take(WORST_OFFENDER_LIMIT).map(|(_, start_pos, end_pos)| RelatedSpan { start_pos, end_pos }).collect()
+}
+
+fn per_1000(count: usize, words: usize) -> f64 {
+    if words == 0 { 0.0 } else { count as f64 * 1000.0 / words as f64 }
+}
+
+/// Headings per 1,000 words. Scores 100 at one heading per 300 words; texts
+/// shorter than that need none.
+pub fn heading_density(headings: usize, words: usize) -> PersonaFactor {
+    let score = if (words as f64) < WORDS_PER_HEADING {
+        100.0
+    } else {
+        100.0 * headings as f64 * WORDS_PER_HEADING / words as f64
+    };
+    factor("heading_density", per_1000(headings, words), score, 0.3, Vec::new())
+}
+
+/// Share of paragraphs of at most 100 words, as a percentage. Worst: the
+/// longest paragraphs over that length.
+pub fn paragraph_length(paragraphs: &[(usize, usize, usize)]) -> PersonaFactor {
+    if paragraphs.is_empty() {
+        return factor("paragraph_length", 100.0, 100.0, 0.3, Vec::new());
+    }
+    let short = paragraphs.iter().filter(|p| p.2 <= LONG_PARAGRAPH_WORDS).count();
+    let share = 100.0 * short as f64 / paragraphs.len() as f64;
+    let worst = worst_by(
+        paragraphs.iter().filter(|p| p.2 > LONG_PARAGRAPH_WORDS).map(|&(start, end, words)| (words as f64, start, end)),
+    );
+    factor("paragraph_length", share, share, 0.3, worst)
+}
+
+/// List items ("- ", "* ", "1. ") per 1,000 words; 10 or more scores 100.
+pub fn list_usage(list_items: usize, words: usize) -> PersonaFactor {
+    let density = per_1000(list_items, words);
+    factor("list_usage", density, 100.0 * density / FULL_LIST_ITEMS_PER_1000, 0.1, Vec::new())
+}
+
+/// Share of sentences whose main clause comes first, as a percentage. A
+/// sentence opening with a subordinate clause still counts if the clause
+/// is at most six words. Worst: the longest lead-ins.
+pub fn front_loaded(sentences: &[SentenceStats]) -> PersonaFactor {
+    if sentences.is_empty() {
+        return factor("front_loaded_sentences", 100.0, 100.0, 0.3, Vec::new());
+    }
+    let delayed = |s: &SentenceStats| s.lead_in_words.filter(|&w| w > MAX_LEAD_IN_WORDS);
+    let front = sentences.iter().filter(|s| delayed(s).is_none()).count();
+    let share = 100.0 * front as f64 / sentences.len() as f64;
+    let worst = worst_by(sentences.iter().filter_map(|s| delayed(s).map(|w| (w as f64, s.start, s.end))));
+    factor("front_loaded_sentences", share, share, 0.3, worst)
+}
+
+/// Dialogue share of words, as a percentage. Scores 100 at 35% and falls
+/// linearly to 0 at all-dialogue; pure narration scores about 46.
+pub fn dialogue_balance(dialogue_words: usize, words: usize) -> PersonaFactor {
+    let share = if words == 0 { 0.0 } else { dialogue_words as f64 / words as f64 };
+    let distance = (share - IDEAL_DIALOGUE_SHARE).abs() / (1.0 - IDEAL_DIALOGUE_SHARE);
+    factor("dialogue_balance", 100.0 * share, 100.0 * (1.0 - distance), 0.3, Vec::new())
+}
+
+/// Coefficient of variation of sentence lengths; 0.5 or more scores 100.
+/// Worst: runs of three or more consecutive sentences within 20% of the
+/// same length, longest run first.
+pub fn sentence_variety(sentences: &[SentenceStats]) -> PersonaFactor {
+    if sentences.len() < 2 {
+        return factor("sentence_variety", 0.0, 100.0, 0.45, Vec::new());
+    }
+    let lengths: Vec<f64> = sentences.iter().map(|s| s.words as f64).collect();
+    let mean = lengths.iter().sum::<f64>() / lengths.len() as f64;
+    let cv = if mean > 0.0 { readability::std_dev(&lengths) / mean } else { 0.0 };
+
+    let similar = |a: f64, b: f64| (a - b).abs() <= 0.2 * a.max(b);
+    let mut runs = Vec::new();
+    let mut run_start = 0;
+    for i in 1..=sentences.len() {
+        if i == sentences.len() || !similar(lengths[i - 1], lengths[i]) {
+            if i - run_start >= 3 {
+                runs.push(((i - run_start) as f64, sentences[run_start].start, sentences[i - 1].end));
+            }
+            run_start = i;
+        }
+    }
+    factor("sentence_variety", cv, 100.0 * cv / FULL_SENTENCE_VARIETY, 0.45, worst_by(runs.into_iter()))
+}
+
+/// Questions per 1,000 words; 5 or more scores 100.
+pub fn question_density(sentences: &[SentenceStats], words: usize) -> PersonaFactor {
+    let density = per_1000(sentences.iter().filter(|s| s.question).count(), words);
+    factor("question_density", density, 100.0 * density / FULL_QUESTIONS_PER_1000, 0.25, Vec::new())
+}
+
+/// Mean clause markers (commas, semicolons, clause-opening words) per
+/// sentence; 4 or more scores 100. Worst: the most nested sentences.
+pub fn clause_depth(sentences: &[SentenceStats]) -> PersonaFactor {
+    if sentences.is_empty() {
+        return factor("clause_depth", 0.0, 0.0, 0.4, Vec::new());
+    }
+    let mean = sentences.iter().map(|s| s.clause_markers).sum::<usize>() as f64 / sentences.len() as f64;
+    let worst = worst_by(sentences.iter().map(|s| (s.clause_markers as f64, s.start, s.end)));
+    factor("clause_depth", mean, 100.0 * mean / MAX_CLAUSE_DEPTH, 0.4, worst)
+}
+
+/// Share of words with three or more syllables, as a percentage; 20% or
+/// more scores 100. Worst: sentences with the highest share.
+pub fn polysyllable_density(sentences: &[SentenceStats]) -> PersonaFactor {
+    let words: usize = sentences.iter().map(|s| s.words).sum();
+    let polysyllables: usize = sentences.iter().map(|s| s.polysyllables).sum();
+    let share = if words == 0 { 0.0 } else { polysyllables as f64 / words as f64 };
+    let worst = worst_by(
+        sentences.iter().filter(|s| s.words > 0).map(|s| (s.polysyllables as f64 / s.words as f64, s.start, s.end)),
+    );
+    factor("polysyllable_density", 100.0 * share, 100.0 * share / MAX_POLYSYLLABLE_SHARE, 0.35, worst)
+}
+
+/// Mean words per sentence. Adds no load up to 10 words and climbs to 100
+/// at 30. Worst: the longest sentences.
+pub fn sentence_length_load(sentences: &[SentenceStats]) -> PersonaFactor {
+    if sentences.is_empty() {
+        return factor("sentence_length", 0.0, 0.0, 0.25, Vec::new());
+    }
+    let mean = sentences.iter().map(|s| s.words).sum::<usize>() as f64 / sentences.len() as f64;
+    let score = 100.0 * (mean - LOAD_FREE_SENTENCE_WORDS).max(0.0) / LOAD_SENTENCE_WORDS_RANGE;
+    let worst = worst_by(sentences.iter().map(|s| (s.words as f64, s.start, s.end)));
+    factor("sentence_length", mean, score, 0.25, worst)
+}
+
+fn is_list_item(line: &str) -> bool {
+    let line = line.trim_start();
+    if line.starts_with("- ") || line.starts_with("* ") || line.starts_with("+ ") || line.starts_with("• ") {
+        return true;
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") "))
+}
+
+fn sentence_stats(processor: &TextProcessor, index: &DocumentIndex) -> Vec<SentenceStats> {
+    let text = index.text;
+    index
+        .sentences
+        .iter()
+        .map(|&(start, end)| {
+            // The sentence splitter leaves a Markdown heading glued to the
+            // sentence after it
+            let mut start = start;
+            while text[start..end].starts_with('#') {
+                start = text[start..end].find('\n').map_or(end, |i| start + i);
+                start += text[start..end].len() - text[start..end].trim_start().len();
+            }
+            let sentence = &text[start..end];
+            let words: Vec<String> =
+                index.words_in(start, end).iter().map(|&(s, e)| text[s..e].to_lowercase()).collect();
+            let punctuation = sentence.matches([',', ';']).count();
+            let markers = words.iter().skip(1).filter(|w| CLAUSE_MARKERS.contains(&w.as_str())).count();
+            let lead_in_words = words
+                .first()
+                .filter(|w| SUBORDINATORS.contains(&w.as_str()))
+                .map(|_| {
+                    let comma = sentence.find(',').map_or(end, |i| start + i);
+                    index.words_in(start, comma).len()
+                });
+            SentenceStats {
+                start,
+                end,
+                words: words.len(),
+                polysyllables: words.iter().filter(|w| !processor.is_opaque(w) && processor.is_complex_word(w)).count(),
+                clause_markers: punctuation + markers,
+                lead_in_words,
+                question: sentence.trim_end_matches(['"', '\'', '\u{201D}', ')']).ends_with('?'),
+            }
+        })
+        .collect()
+}
+
+pub fn persona_scores(processor: &TextProcessor, index: &DocumentIndex) -> PersonaScores {
+    let text = index.text;
+    let words = index.words.len();
+    let sentences = sentence_stats(processor, index);
+
+    let headings = structure::detect_sections(text, &processor.heading_patterns).iter().filter(|s| s.heading.is_some()).count();
+    let paragraphs: Vec<(usize, usize, usize)> =
+        index.paragraphs.iter().map(|&(start, end)| (start, end, index.words_in(start, end).len())).collect();
+    let list_items = text.lines().filter(|line| is_list_item(line)).count();
+    let dialogue_words = processor.dialogue_spans(text).iter().map(|&(start, end)| index.words_in(start, end).len()).sum();
+
+    PersonaScores {
+        low_confidence: words < processor.config.min_word_count,
+        skimmability: combine(vec![
+            heading_density(headings, words),
+            paragraph_length(&paragraphs),
+            list_usage(list_items, words),
+            front_loaded(&sentences),
+        ]),
+        engagement: combine(vec![
+            dialogue_balance(dialogue_words, words),
+            sentence_variety(&sentences),
+            question_density(&sentences, words),
+        ]),
+        cognitive_load: combine(vec![
+            clause_depth(&sentences),
+            polysyllable_density(&sentences),
+            sentence_length_load(&sentences),
+        ]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(words: usize, clause_markers: usize, lead_in_words: Option<usize>, start: usize) -> SentenceStats {
+        SentenceStats { start, end: start + 10, words, polysyllables: 0, clause_markers, lead_in_words, question: false }
+    }
+
+    fn spans(factor: &PersonaFactor) -> Vec<(usize, usize)> {
+        factor.worst.iter().map(|s| (s.start_pos, s.end_pos)).collect()
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn skimmability_factors() {
+        assert_eq!(heading_density(0, 200).score, 100.0);
+        let sparse = heading_density(1, 600);
+        assert!(close(sparse.score, 50.0) && close(sparse.value, 1000.0 / 600.0));
+
+        let paragraphs = paragraph_length(&[(0, 10, 50), (10, 20, 150), (20, 30, 120)]);
+        assert!(close(paragraphs.score, 100.0 / 3.0));
+        assert_eq!(spans(&paragraphs), [(10, 20), (20, 30)]);
+
+        assert!(close(list_usage(5, 1000).score, 50.0));
+        assert_eq!(list_usage(40, 1000).score, 100.0);
+
+        let front = front_loaded(&[stats(12, 0, Some(8), 0), stats(12, 0, Some(3), 10), stats(12, 0, None, 20)]);
+        assert!(close(front.score, 200.0 / 3.0));
+        assert_eq!(spans(&front), [(0, 10)]);
+    }
+
+    #[test]
+    fn engagement_factors() {
+        assert!(close(dialogue_balance(35, 100).score, 100.0));
+        assert!(close(dialogue_balance(0, 100).score, 100.0 * (1.0 - 0.35 / 0.65)));
+        assert!(close(dialogue_balance(100, 100).score, 0.0));
+
+        let monotone = sentence_variety(&[stats(10, 0, None, 0), stats(10, 0, None, 10), stats(11, 0, None, 20), stats(30, 0, None, 30)]);
+        assert_eq!(spans(&monotone), [(0, 30)]);
+        assert!(monotone.value > 0.5 && monotone.score == 100.0);
+        let flat = sentence_variety(&[stats(10, 0, None, 0), stats(10, 0, None, 10)]);
+        assert_eq!((flat.value, flat.score), (0.0, 0.0));
+
+        let mut question = stats(20, 0, None, 0);
+        question.question = true;
+        assert!(close(question_density(&[question], 200).score, 100.0));
+    }
+
+    #[test]
+    fn cognitive_load_factors() {
+        let depth = clause_depth(&[stats(10, 0, None, 0), stats(10, 2, None, 10), stats(10, 6, None, 20)]);
+        assert!(close(depth.score, 200.0 / 3.0));
+        assert_eq!(spans(&depth), [(20, 30), (10, 20)]);
+
+        let mut dense = stats(10, 0, None, 0);
+        dense.polysyllables = 2;
+        let poly = polysyllable_density(&[dense, stats(10, 0, None, 10)]);
+        assert!(close(poly.value, 10.0) && close(poly.score, 50.0));
+        assert_eq!(spans(&poly), [(0, 10)]);
+
+        let length = sentence_length_load(&[stats(15, 0, None, 0), stats(25, 0, None, 10)]);
+        assert!(close(length.score, 50.0));
+        assert_eq!(spans(&length), [(10, 20), (0, 10)]);
+        assert_eq!(sentence_length_load(&[stats(5, 0, None, 0)]).score, 0.0);
+    }
+
+    #[test]
+    fn scores_are_weighted_means_of_their_factors() {
+        let text = "# The Farm\n\nAlthough the night was cold and the wind was loud over the hills, she walked. \
+            He asked, \"Was it late?\" She said nothing, which was her way, and left.\n\n- one item\n- another item\n";
+        let processor = TextProcessor::new();
+        let scores = persona_scores(&processor, &processor.index(text));
+        for persona in [&scores.skimmability, &scores.engagement, &scores.cognitive_load] {
+            assert!(close(persona.factors.iter().map(|f| f.weight).sum(), 1.0));
+            assert!(close(persona.score, persona.factors.iter().map(|f| f.score * f.weight).sum()));
+            assert!((0.0..=100.0).contains(&persona.score));
+        }
+        let factor = |persona: &PersonaScore, name: &str| persona.factors.iter().find(|f| f.name == name).unwrap().value;
+        assert!(close(factor(&scores.skimmability, "list_usage"), per_1000(2, processor.index(text).words.len())));
+        assert!(factor(&scores.engagement, "question_density") > 0.0);
+
+        let delayed = &scores.skimmability.factors.iter().find(|f| f.name == "front_loaded_sentences").unwrap().worst;
+        assert_eq!(delayed.len(), 1);
+        assert!(text[delayed[0].start_pos..delayed[0].end_pos].starts_with("Although the night"));
+    }
+}
@@ -0,0 +1,194 @@
+//! Phrases of a few words an author leans on ("a moment later", "let out a
+//! breath"), counted by hashing every n-gram once rather than comparing
+//! passages, so a full manuscript stays fast.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::lexicon::Stopwords;
+use crate::segmentation::DocumentIndex;
+use crate::RelatedSpan;
+
+pub const DEFAULT_MIN_WORDS: usize = 3;
+pub const DEFAULT_MAX_WORDS: usize = 5;
+pub const DEFAULT_MIN_COUNT: usize = 3;
+
+#[derive(Serialize)]
+pub struct RepeatedPhrase {
+    /// Lowercased, words separated by single spaces.
+    pub phrase: String,
+    pub word_count: usize,
+    pub count: usize,
+    /// Every occurrence in text order.
+    pub positions: Vec<RelatedSpan>,
+}
+
+/// N-grams of `min_n` to `max_n` words, within one sentence, occurring at
+/// least `min_count` times, most frequent first (longer phrases first on
+/// ties). Phrases of stopwords alone are left out, and so is any phrase
+/// that only ever occurs inside a longer one reported as often.
+pub fn repeated(index: &DocumentIndex, stopwords: &Stopwords, min_n: usize, max_n: usize, min_count: usize) -> Vec<RepeatedPhrase> {
+    let text = index.text;
+    let words = &index.words;
+
+    // Words as small ids, so an n-gram is a slice of integers to hash
+    let mut vocabulary: HashMap<String, u32> = HashMap::new();
+    let mut content: Vec<bool> = Vec::new();
+    let ids: Vec<u32> = words
+        .iter()
+        .map(|&(s, e)| {
+            let word = text[s..e].to_lowercase().replace('\u{2019}', "'");
+            let next = vocabulary.len() as u32;
+            *vocabulary.entry(word).or_insert_with(|| {
+                content.push(stopwords.is_content_word(&text[s..e]));
+                next
+            })
+        })
+        .collect();
+    // Sentence of each word, so n-grams never run across a full stop
+    let mut sentence = Vec::with_capacity(words.len());
+    let mut k = 0;
+    for &(s, _) in words.iter() {
+        while k + 1 < index.sentences.len() && index.sentences[k].1 <= s {
+            k += 1;
+        }
+        sentence.push(k);
+    }
+
+    // Longest first, so a reported phrase can claim the shorter ones inside it
+    let mut found: Vec<(&[u32], Vec<usize>)> = Vec::new();
+    let mut covered: HashMap<&[u32], usize> = HashMap::new();
+    for n in (min_n..=max_n).rev() {
+        let mut starts: HashMap<&[u32], Vec<usize>> = HashMap::new();
+        for first in 0..words.len().saturating_sub(n - 1) {
+            let gram = &ids[first..first + n];
+            if sentence[first] != sentence[first + n - 1] || !gram.iter().any(|&id| content[id as usize]) {
+                continue;
+            }
+            // "ha ha ha ha" holds one "ha ha ha", not two
+            let firsts = starts.entry(gram).or_default();
+            if firsts.last().is_none_or(|&last| last + n <= first) {
+                firsts.push(first);
+            }
+        }
+        for (gram, firsts) in starts {
+            if firsts.len() < min_count || covered.get(gram).is_some_and(|&c| c >= firsts.len()) {
+                continue;
+            }
+            for m in min_n..n {
+                for sub in gram.windows(m) {
+                    let claimed = covered.entry(sub).or_insert(0);
+                    *claimed = (*claimed).max(firsts.len());
+                }
+            }
+            found.push((gram, firsts));
+        }
+    }
+
+    let mut phrases: Vec<RepeatedPhrase> = found
+        .into_iter()
+        .map(|(gram, firsts)| {
+            let n = gram.len();
+            let first = firsts[0];
+            RepeatedPhrase {
+                phrase: words[first..first + n].iter().map(|&(s, e)| text[s..e].to_lowercase()).collect::<Vec<_>>().join(" "),
+                word_count: n,
+                count: firsts.len(),
+                positions: firsts
+                    .iter()
+                    .map(|&f| RelatedSpan { start_pos: words[f].0, end_pos: words[f + n - 1].1 })
+                    .collect(),
+            }
+        })
+        .collect();
+    phrases.sort_by(|a, b| {
+        b.count.cmp(&a.count).then(b.word_count.cmp(&a.word_count)).then(a.positions[0].start_pos.cmp(&b.positions[0].start_pos))
+    });
+    phrases
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{index, Cases};
+
+    use super::*;
+    use crate::config::SuggestionConfig;
+
+    fn phrases(text: &str, min_n: usize, max_n: usize, min_count: usize) -> Vec<RepeatedPhrase> {
+        let index = index(text);
+        repeated(&index, &Stopwords::from_config(&SuggestionConfig::default()), min_n, max_n, min_count)
+    }
+
+    fn counts(found: &[RepeatedPhrase]) -> Vec<(&str, usize)> {
+        found.iter().map(|p| (p.phrase.as_str(), p.count)).collect()
+    }
+
+    #[test]
+    fn a_longer_phrase_suppresses_the_shorter_ones_inside_it() {
+        let text = "She let out a breath. Then she let out a breath. He waited, and she let out a breath. \
+            Let out a breath, she told herself. At last she let out a breath. She let out a breath again.";
+        let found = phrases(text, DEFAULT_MIN_WORDS, DEFAULT_MAX_WORDS, DEFAULT_MIN_COUNT);
+        // "she let out a breath" (5 words, 5 times) does not hide the 4-gram seen 6 times
+        assert_eq!(counts(&found), [("let out a breath", 6), ("she let out a breath", 5)]);
+        let spans: Vec<&str> = found[0].positions.iter().map(|p| &text[p.start_pos..p.end_pos]).collect();
+        assert_eq!(spans, ["let out a breath", "let out a breath", "let out a breath", "Let out a breath", "let out a breath", "let out a breath"]);
+        assert_eq!(found[0].word_count, 4);
+    }
+
+    #[test]
+    fn a_shorter_phrase_seen_more_often_than_its_longer_ones_is_kept() {
+        let text = "A moment later he left. A moment later she left. A moment later, nothing. Only a moment later.";
+        let found = phrases(text, 3, 5, 3);
+        assert_eq!(counts(&found), [("a moment later", 4)]);
+        let found = phrases(text, 2, 5, 2);
+        assert_eq!(counts(&found)[0], ("a moment later", 4));
+        assert!(found.iter().all(|p| p.phrase != "moment later"));
+    }
+
+    #[test]
+    fn stopword_phrases_and_cross_sentence_ngrams_are_left_out() {
+        let text = "It was all of them. It was all of them. It was all of them.";
+        assert!(phrases(text, 3, 5, 3).is_empty());
+        // "storm. The storm" would repeat, but a phrase never runs over a full stop
+        let text = "The storm. The storm. The storm. The storm.";
+        assert!(phrases(text, 3, 5, 3).is_empty());
+        assert_eq!(counts(&phrases(text, 2, 5, 3)), [("the storm", 4)]);
+    }
+
+    #[test]
+    fn overlapping_occurrences_of_a_phrase_are_counted_once() {
+        let found = phrases("Ha ha ha ha ha ha, laughed the crowd.", 3, 3, 2);
+        assert_eq!(counts(&found), [("ha ha ha", 2)]);
+        assert_eq!(phrases("Ha ha ha ha, laughed the crowd.", 3, 3, 2).len(), 0);
+    }
+
+    #[test]
+    fn phrases_differing_only_in_case_or_apostrophe_match() {
+        let text = "Don\u{2019}t look back now. Don't look back now! DON'T LOOK BACK NOW?";
+        let found = phrases(text, 3, 5, 3);
+        assert_eq!(counts(&found), [("don\u{2019}t look back now", 3)]);
+    }
+
+    #[test]
+    fn text_without_repeats_finds_nothing() {
+        assert!(phrases("", 3, 5, 3).is_empty());
+        assert!(phrases("One short line with nothing said twice.", 3, 5, 2).is_empty());
+    }
+
+    #[test]
+    fn a_large_manuscript_finds_its_planted_phrase() {
+        // Pseudo-random words, with one phrase planted every 500 words
+        let vocabulary = ["river", "stone", "lantern", "orchard", "glass", "window", "copper", "meadow", "thread", "harbor"];
+        let mut cases = Cases(0x2545_f491_4f6c_dd1d);
+        let mut text = String::new();
+        for i in 0..120_000 {
+            if i % 500 == 0 {
+                text.push_str("she let out a breath. ");
+            }
+            text.push_str(vocabulary[cases.below(vocabulary.len())]);
+            text.push_str(if i % 12 == 11 { ". " } else { " " });
+        }
+        let found = phrases(&text, 3, 5, 200);
+        assert_eq!(counts(&found), [("she let out a breath", 240)]);
+    }
+}
@@ -0,0 +1,273 @@
+//! Labeled spans (e.g. machine-assisted passages) kept attached to the text
+//! they describe as the manuscript is revised. The engine never decides what
+//! a label means; it only keeps the spans accurate.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::diff::TextDiff;
+use crate::segmentation::DocumentIndex;
+
+pub const PROVENANCE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProvenanceSpan {
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub label: String,
+}
+
+/// Labeled spans of one version of a text, sorted and non-overlapping.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProvenanceMap {
+    pub version: u32,
+    /// Hash of the text the spans refer to.
+    pub document_hash: String,
+    pub spans: Vec<ProvenanceSpan>,
+}
+
+#[derive(Deserialize)]
+pub struct MarkedRange {
+    pub start_pos: usize,
+    pub end_pos: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ProvenanceRemap {
+    pub map: ProvenanceMap,
+    /// Spans of the old map with no surviving text, in old-text offsets.
+    pub dropped: Vec<ProvenanceSpan>,
+    /// Old spans that came out as more than one span.
+    pub split: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LabelSummary {
+    pub label: String,
+    pub spans: usize,
+    pub word_count: usize,
+    /// Share of all words in the text, 0–1.
+    pub word_share: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ProvenanceReport {
+    pub word_count: usize,
+    pub unlabeled_word_count: usize,
+    pub labels: Vec<LabelSummary>,
+}
+
+fn check_range(text: &str, start: usize, end: usize) -> Result<(), String> {
+    if start > end {
+        return Err(format!("start_pos {} is after end_pos {}", start, end));
+    }
+    if end > text.len() {
+        return Err(format!("end_pos {} is past the end of the text ({} bytes)", end, text.len()));
+    }
+    if !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+        return Err(format!("span {}..{} does not fall on character boundaries", start, end));
+    }
+    Ok(())
+}
+
+/// Sorts spans and merges same-label spans that overlap, touch, or (given
+/// `text`) are separated only by whitespace.
+fn normalize(mut spans: Vec<ProvenanceSpan>, text: Option<&str>) -> Vec<ProvenanceSpan> {
+    spans.retain(|s| s.start_pos < s.end_pos);
+    spans.sort_by_key(|s| (s.start_pos, s.end_pos));
+    let mut merged: Vec<ProvenanceSpan> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match merged.last_mut() {
+            Some(last)
+                if last.label == span.label
+                    && (span.start_pos <= last.end_pos
+                        || text.is_some_and(|t| t[last.end_pos..span.start_pos].trim().is_empty())) =>
+            {
+                last.end_pos = last.end_pos.max(span.end_pos);
+            }
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+/// Labels `ranges` of `text`, on top of `existing` spans if given. Newly
+/// marked ranges replace whatever label covered them before.
+pub fn mark(
+    text: &str,
+    document_hash: String,
+    existing: Option<ProvenanceMap>,
+    ranges: &[MarkedRange],
+    label: &str,
+) -> Result<ProvenanceMap, String> {
+    if label.is_empty() {
+        return Err("label must not be empty".to_string());
+    }
+    for range in ranges {
+        check_range(text, range.start_pos, range.end_pos)?;
+    }
+    let mut spans = match existing {
+        Some(map) if map.document_hash != document_hash => {
+            return Err("provenance map was built for a different text; remap it first".to_string());
+        }
+        Some(map) => map.spans,
+        None => Vec::new(),
+    };
+
+    for range in ranges {
+        spans = spans
+            .into_iter()
+            .flat_map(|span| {
+                let before = ProvenanceSpan { end_pos: span.end_pos.min(range.start_pos), ..span.clone() };
+                let after = ProvenanceSpan { start_pos: span.start_pos.max(range.end_pos), ..span };
+                [before, after]
+            })
+            .filter(|s| s.start_pos < s.end_pos)
+            .collect();
+        spans.push(ProvenanceSpan { start_pos: range.start_pos, end_pos: range.end_pos, label: label.to_string() });
+    }
+
+    Ok(ProvenanceMap { version: PROVENANCE_VERSION, document_hash, spans: normalize(spans, None) })
+}
+
+/// Shrinks a span to exclude whitespace at either edge; `None` if nothing
+/// else is left.
+fn trim_span(text: &str, start: usize, end: usize) -> Option<(usize, usize)> {
+    let slice = &text[start..end];
+    let trimmed = slice.trim_start();
+    let start = start + slice.len() - trimmed.len();
+    let trimmed = trimmed.trim_end();
+    (!trimmed.is_empty()).then_some((start, start + trimmed.len()))
+}
+
+/// Carries every span forward from `old_text` to `new_text`. Only text that
+/// survived the edit keeps its label: insertions inside a span split it,
+/// deleting the text between two same-label spans merges them, and a span
+/// whose text was deleted entirely is dropped.
+pub fn remap(map: &ProvenanceMap, old_text: &str, new_text: &str, new_hash: String) -> ProvenanceRemap {
+    let diff = TextDiff::compute(old_text, new_text);
+    let mut spans = Vec::new();
+    let mut dropped = Vec::new();
+    let mut split = 0;
+    for span in &map.spans {
+        let pieces: Vec<ProvenanceSpan> = diff
+            .map_span(span.start_pos.min(old_text.len()), span.end_pos.min(old_text.len()))
+            .into_iter()
+            .filter_map(|(start, end)| trim_span(new_text, start, end))
+            .map(|(start_pos, end_pos)| ProvenanceSpan { start_pos, end_pos, label: span.label.clone() })
+            .collect();
+        let pieces = normalize(pieces, Some(new_text));
+        match pieces.len() {
+            0 => dropped.push(span.clone()),
+            1 => {}
+            _ => split += 1,
+        }
+        spans.extend(pieces);
+    }
+    ProvenanceRemap {
+        map: ProvenanceMap { version: PROVENANCE_VERSION, document_hash: new_hash, spans: normalize(spans, Some(new_text)) },
+        dropped,
+        split,
+    }
+}
+
+/// Word counts per label. A word belongs to the span containing its first
+/// byte.
+pub fn report(map: &ProvenanceMap, index: &DocumentIndex) -> ProvenanceReport {
+    let mut labels: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    let mut labeled = 0;
+    for span in &map.spans {
+        let words = index.words.iter().filter(|w| w.0 >= span.start_pos && w.0 < span.end_pos).count();
+        let entry = labels.entry(span.label.as_str()).or_default();
+        entry.0 += 1;
+        entry.1 += words;
+        labeled += words;
+    }
+    let word_count = index.words.len();
+    ProvenanceReport {
+        word_count,
+        unlabeled_word_count: word_count.saturating_sub(labeled),
+        labels: labels
+            .into_iter()
+            .map(|(label, (spans, words))| LabelSummary {
+                label: label.to_string(),
+                spans,
+                word_count: words,
+                word_share: if word_count == 0 { 0.0 } else { words as f64 / word_count as f64 },
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    const OLD: &str = "Mara walked the field. The storm came at night and the barn held. She slept.";
+
+    fn range(text: &str, needle: &str) -> MarkedRange {
+        let start_pos = text.find(needle).unwrap();
+        MarkedRange { start_pos, end_pos: start_pos + needle.len() }
+    }
+
+    fn labeled<'t>(map: &ProvenanceMap, text: &'t str) -> Vec<(&'t str, String)> {
+        map.spans.iter().map(|s| (&text[s.start_pos..s.end_pos], s.label.clone())).collect()
+    }
+
+    fn assisted() -> ProvenanceMap {
+        mark(OLD, "old".to_string(), None, &[range(OLD, "The storm came at night and the barn held.")], "assisted").unwrap()
+    }
+
+    #[test]
+    fn inserting_unlabeled_text_splits_a_region() {
+        let new = "Mara walked the field. The storm came at night, loud as a train, and the barn held. She slept.";
+        let remapped = remap(&assisted(), OLD, new, "new".to_string());
+        assert_eq!(
+            labeled(&remapped.map, new),
+            [("The storm came at night", "assisted".to_string()), ("and the barn held.", "assisted".to_string())]
+        );
+        assert_eq!(remapped.split, 1);
+        assert!(remapped.dropped.is_empty());
+        assert_eq!(remapped.map.document_hash, "new");
+    }
+
+    #[test]
+    fn deleting_a_region_entirely_drops_it() {
+        let mut map = assisted();
+        map = mark(OLD, "old".to_string(), Some(map), &[range(OLD, "She slept.")], "human").unwrap();
+        let new = "Mara walked the field. She slept.";
+        let remapped = remap(&map, OLD, new, "new".to_string());
+        assert_eq!(labeled(&remapped.map, new), [("She slept.", "human".to_string())]);
+        assert_eq!(remapped.dropped.len(), 1);
+        assert_eq!(remapped.dropped[0].label, "assisted");
+        assert_eq!(remapped.split, 0);
+    }
+
+    #[test]
+    fn deleting_the_gap_between_same_label_spans_merges_them() {
+        let map = mark(OLD, "old".to_string(), None, &[range(OLD, "Mara walked"), range(OLD, "She slept.")], "assisted").unwrap();
+        let new = "Mara walked She slept.";
+        assert_eq!(labeled(&remap(&map, OLD, new, "new".to_string()).map, new), [("Mara walked She slept.", "assisted".to_string())]);
+    }
+
+    #[test]
+    fn marking_replaces_earlier_labels_and_validates_ranges() {
+        let map = mark(OLD, "old".to_string(), Some(assisted()), &[range(OLD, "the barn")], "human").unwrap();
+        let spans = labeled(&map, OLD);
+        assert_eq!(spans.iter().map(|s| s.0).collect::<Vec<_>>(), ["The storm came at night and ", "the barn", " held."]);
+        assert!(mark(OLD, "old".to_string(), None, &[MarkedRange { start_pos: 4, end_pos: 2 }], "x").is_err());
+        assert!(mark(OLD, "old".to_string(), None, &[MarkedRange { start_pos: 0, end_pos: OLD.len() + 1 }], "x").is_err());
+        assert!(mark(OLD, "old".to_string(), None, &[range(OLD, "Mara")], "").is_err());
+        assert!(mark(OLD, "other".to_string(), Some(assisted()), &[range(OLD, "Mara")], "x").is_err());
+    }
+
+    #[test]
+    fn report_counts_words_per_label() {
+        let report = report(&assisted(), &TextProcessor::new().index(OLD));
+        assert_eq!((report.word_count, report.unlabeled_word_count), (15, 6));
+        assert_eq!(report.labels.len(), 1);
+        assert_eq!((report.labels[0].spans, report.labels[0].word_count), (1, 9));
+        assert!((report.labels[0].word_share - 0.6).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,147 @@
+//! Filtered, ordered, paged views of the suggestion list, so an editor can
+//! ask for one rule, the urgent ones, or a screenful at a time instead of
+//! pulling every suggestion for a manuscript across the boundary.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Priority;
+use crate::suggestions::priority_rank;
+use crate::OptimizationSuggestion;
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct SuggestionQuery {
+    /// Rule ids to report; empty reports every rule.
+    pub include_types: Vec<String>,
+    /// Rule ids never to report, even when included. Their detectors don't run.
+    pub exclude_types: Vec<String>,
+    /// Drops suggestions below this priority, after density escalation.
+    pub min_priority: Option<Priority>,
+    /// Suggestions to skip from the start of the ordered list.
+    pub offset: usize,
+    /// Page size; unset returns everything after `offset`.
+    pub limit: Option<usize>,
+    /// Byte range of a selection. Analysis covers the paragraphs around it
+    /// and only suggestions overlapping it are kept.
+    pub start_pos: Option<usize>,
+    pub end_pos: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct SuggestionPage {
+    pub suggestions: Vec<OptimizationSuggestion>,
+    /// Suggestions matching the query before `offset` and `limit` applied.
+    pub total: usize,
+    pub offset: usize,
+}
+
+impl SuggestionQuery {
+    pub fn wants(&self, rule: &str) -> bool {
+        (self.include_types.is_empty() || self.include_types.iter().any(|t| t == rule))
+            && !self.exclude_types.iter().any(|t| t == rule)
+    }
+
+    /// Highest priority first, then in text order.
+    pub fn page(&self, mut suggestions: Vec<OptimizationSuggestion>) -> SuggestionPage {
+        suggestions.retain(|s| self.wants(&s.suggestion_type));
+        if let Some(min) = self.min_priority {
+            let floor = priority_rank(min.as_str());
+            suggestions.retain(|s| priority_rank(&s.priority) >= floor);
+        }
+        suggestions.sort_by(|a, b| {
+            priority_rank(&b.priority)
+                .cmp(&priority_rank(&a.priority))
+                .then(a.start_pos.cmp(&b.start_pos))
+                .then(a.end_pos.cmp(&b.end_pos))
+        });
+        let total = suggestions.len();
+        let suggestions = suggestions
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect();
+        SuggestionPage { suggestions, total, offset: self.offset }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use crate::TextProcessor;
+
+    fn suggestion(rule: &'static str, priority: &'static str, start: usize) -> OptimizationSuggestion {
+        OptimizationSuggestion::instance(rule, priority, "", start, start + 1)
+    }
+
+    fn listed(page: &SuggestionPage) -> Vec<(&str, usize)> {
+        page.suggestions.iter().map(|s| (s.priority.as_ref(), s.start_pos)).collect()
+    }
+
+    #[test]
+    fn exclusions_win_over_inclusions() {
+        let query = SuggestionQuery {
+            include_types: vec!["adverb_usage".into(), "passive_voice".into()],
+            exclude_types: vec!["passive_voice".into()],
+            ..Default::default()
+        };
+        assert!(query.wants("adverb_usage"));
+        assert!(!query.wants("passive_voice"));
+        assert!(!query.wants("filler_word"));
+        assert!(SuggestionQuery::default().wants("filler_word"));
+    }
+
+    #[test]
+    fn pages_are_ordered_by_priority_then_position() {
+        let all = || {
+            vec![
+                suggestion("filler_word", "low", 3),
+                suggestion("passive_voice", "high", 9),
+                suggestion("adverb_usage", "medium", 1),
+                suggestion("filler_word", "high", 2),
+                suggestion("adverb_usage", "low", 0),
+            ]
+        };
+        let page = SuggestionQuery::default().page(all());
+        assert_eq!(listed(&page), [("high", 2), ("high", 9), ("medium", 1), ("low", 0), ("low", 3)]);
+
+        let page = SuggestionQuery { offset: 1, limit: Some(2), ..Default::default() }.page(all());
+        assert_eq!(listed(&page), [("high", 9), ("medium", 1)]);
+        assert_eq!((page.total, page.offset), (5, 1));
+
+        let page = SuggestionQuery { min_priority: Some(Priority::Medium), ..Default::default() }.page(all());
+        assert_eq!((page.suggestions.len(), page.total), (3, 3));
+
+        let page = SuggestionQuery { offset: 10, ..Default::default() }.page(all());
+        assert!(page.suggestions.is_empty());
+        assert_eq!(page.total, 5);
+    }
+
+    #[test]
+    fn generate_optimization_suggestions_runs_only_wanted_rules() {
+        let processor = TextProcessor::new();
+        let text = "The letter was written by the clerk. She really read it very slowly.";
+        let suggestions = processor.generate_optimization_suggestions(text, &|rule| rule == "adverb_usage");
+        assert!(!suggestions.is_empty());
+        assert!(suggestions.iter().all(|s| s.suggestion_type == "adverb_usage"));
+        assert!(processor.generate_optimization_suggestions(text, &|_| false).is_empty());
+    }
+
+    #[test]
+    fn excluded_rules_skip_their_detectors() {
+        let processor = TextProcessor::new();
+        let text = "She really quickly ran. He was very slowly walking. The letter was written by Mara.";
+        let asked = RefCell::new(Vec::new());
+        let wants = |rule: &str| {
+            asked.borrow_mut().push(rule.to_string());
+            rule != "adverb_usage"
+        };
+        let suggestions = processor.generate_optimization_suggestions(text, &wants);
+        assert!(suggestions.iter().all(|s| s.suggestion_type != "adverb_usage"));
+        assert!(suggestions.iter().any(|s| s.suggestion_type == "passive_voice"));
+        // Asked once before the detector; a detector that ran would be asked
+        // again for each of its suggestions when the list is filtered
+        assert_eq!(asked.borrow().iter().filter(|r| *r == "adverb_usage").count(), 1);
+        assert!(asked.borrow().iter().filter(|r| *r == "passive_voice").count() > 1);
+    }
+}
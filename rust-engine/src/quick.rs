@@ -0,0 +1,186 @@
+//! Lightweight metrics for UI strings (button labels, tooltips, error
+//! messages), where full analysis is overkill and most metrics mean nothing.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::TextProcessor;
+
+/// Share of words with three or more syllables above which a string is
+/// flagged difficult (given at least two such words).
+const DIFFICULT_POLYSYLLABLE_SHARE: f64 = 1.0 / 3.0;
+/// Longer than this is flagged difficult regardless of vocabulary.
+const DIFFICULT_WORD_COUNT: usize = 25;
+/// All-caps words shorter than this are usually acronyms ("OK", "ID").
+const MIN_SHOUTED_WORD_LETTERS: usize = 3;
+
+const TERMINAL_PUNCTUATION: &[char] = &['.', '!', '?', '\u{2026}', ':'];
+
+#[derive(Serialize, Deserialize)]
+pub struct QuickMetrics {
+    pub word_count: usize,
+    pub character_count: usize,
+    /// User-perceived characters, the count to check against length limits.
+    pub grapheme_count: usize,
+    pub character_count_no_spaces: usize,
+    pub reading_time_seconds: f64,
+    pub difficult: bool,
+    /// At least half the words are all-caps, not counting short acronyms.
+    pub shouting: bool,
+    /// Final punctuation mark, ignoring closing quotes and brackets.
+    pub terminal_punctuation: Option<String>,
+    /// Ends differently from most strings in the batch; always false for a
+    /// single string.
+    pub punctuation_inconsistent: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct QuickBatch {
+    /// One entry per input string, in input order.
+    pub results: Vec<QuickMetrics>,
+    /// Most common ending across the batch (`None`: no terminal punctuation).
+    pub majority_terminal_punctuation: Option<String>,
+    /// Indices of strings whose ending differs from the majority.
+    pub inconsistent_punctuation: Vec<usize>,
+}
+
+fn terminal_punctuation(text: &str) -> Option<String> {
+    let trimmed = text.trim_end().trim_end_matches(['"', '\'', '\u{201D}', '\u{2019}', ')', ']']);
+    if trimmed.ends_with("...") {
+        return Some("...".to_string());
+    }
+    trimmed.chars().next_back().filter(|c| TERMINAL_PUNCTUATION.contains(c)).map(String::from)
+}
+
+pub fn quick_metrics(processor: &TextProcessor, text: &str) -> QuickMetrics {
+    let words = processor.words.words(text);
+    let word_count = words.len();
+    let polysyllables = words.iter().filter(|w| !processor.is_opaque(w) && processor.is_complex_word(w)).count();
+
+    let lettered: Vec<&&str> = words.iter().filter(|w| w.chars().filter(|c| c.is_alphabetic()).count() >= MIN_SHOUTED_WORD_LETTERS).collect();
+    let shouted = lettered.iter().filter(|w| w.chars().filter(|c| c.is_alphabetic()).all(char::is_uppercase)).count();
+
+    let words_per_minute = processor.config.analysis.reading_words_per_minute;
+    let mut reading_time_seconds = if words_per_minute > 0.0 { word_count as f64 * 60.0 / words_per_minute } else { 0.0 };
+    processor.config.output.time(&mut reading_time_seconds);
+
+    QuickMetrics {
+        word_count,
+        character_count: text.chars().count(),
+        grapheme_count: text.graphemes(true).count(),
+        character_count_no_spaces: text.chars().filter(|c| !c.is_whitespace()).count(),
+        reading_time_seconds,
+        difficult: (polysyllables >= 2 && polysyllables as f64 > word_count as f64 * DIFFICULT_POLYSYLLABLE_SHARE)
+            || word_count > DIFFICULT_WORD_COUNT,
+        shouting: shouted > 0 && shouted * 2 >= lettered.len(),
+        terminal_punctuation: terminal_punctuation(text),
+        punctuation_inconsistent: false,
+    }
+}
+
+/// `quick_metrics` for every string, plus the batch's dominant ending and
+/// the strings that break from it. Ties favour no punctuation, then the
+/// earliest ending seen.
+pub fn quick_metrics_batch(processor: &TextProcessor, texts: &[String]) -> QuickBatch {
+    let mut results: Vec<QuickMetrics> = texts.iter().map(|t| quick_metrics(processor, t)).collect();
+
+    // Ending -> (count, first index)
+    let mut counts: BTreeMap<Option<&str>, (usize, usize)> = BTreeMap::new();
+    for (i, result) in results.iter().enumerate() {
+        counts.entry(result.terminal_punctuation.as_deref()).or_insert((0, i)).0 += 1;
+    }
+    let mut majority: Option<(Option<&str>, usize, usize)> = None;
+    for (&ending, &(count, first)) in &counts {
+        let better = match majority {
+            None => true,
+            Some((best, best_count, best_first)) => {
+                (count, ending.is_none(), std::cmp::Reverse(first)) > (best_count, best.is_none(), std::cmp::Reverse(best_first))
+            }
+        };
+        if better {
+            majority = Some((ending, count, first));
+        }
+    }
+    let majority = majority.and_then(|(ending, _, _)| ending.map(str::to_string));
+
+    let mut inconsistent = Vec::new();
+    if counts.len() > 1 {
+        for (i, result) in results.iter_mut().enumerate() {
+            if result.terminal_punctuation != majority {
+                result.punctuation_inconsistent = true;
+                inconsistent.push(i);
+            }
+        }
+    }
+    QuickBatch { results, majority_terminal_punctuation: majority, inconsistent_punctuation: inconsistent }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SuggestionConfig;
+    use crate::output::OutputOptions;
+
+    #[test]
+    fn batch_results_stay_aligned_with_their_inputs() {
+        let processor = TextProcessor::new();
+        let texts: Vec<String> = (0..500)
+            .map(|i| {
+                let words = vec!["file"; i % 7].join(" ");
+                let ending = if i % 50 == 0 { "!" } else { "." };
+                format!("Save {} {}{}", words, i, ending)
+            })
+            .collect();
+        let batch = quick_metrics_batch(&processor, &texts);
+        assert_eq!(batch.results.len(), 500);
+        for (i, result) in batch.results.iter().enumerate() {
+            assert_eq!(result.word_count, i % 7 + 2, "result {} is out of place", i);
+            assert_eq!(result.character_count, texts[i].chars().count());
+            assert_eq!(result.punctuation_inconsistent, i % 50 == 0);
+        }
+        assert_eq!(batch.majority_terminal_punctuation.as_deref(), Some("."));
+        assert_eq!(batch.inconsistent_punctuation, (0..500).step_by(50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn single_string_metrics() {
+        let processor = TextProcessor::new();
+        let label = quick_metrics(&processor, "Save your changes?");
+        assert_eq!((label.word_count, label.character_count, label.character_count_no_spaces), (3, 18, 16));
+        assert!((label.reading_time_seconds - 3.0 * 60.0 / 238.0).abs() < 1e-9);
+        assert_eq!(label.terminal_punctuation.as_deref(), Some("?"));
+        assert!(!label.difficult && !label.shouting && !label.punctuation_inconsistent);
+
+        assert!(quick_metrics(&processor, "DO NOT unplug the DEVICE").shouting);
+        assert!(!quick_metrics(&processor, "Press OK to save your ID").shouting);
+        assert!(quick_metrics(&processor, "Unauthorized configuration modification").difficult);
+        assert!(quick_metrics(&processor, &"word ".repeat(26)).difficult);
+
+        let endings: Vec<Option<String>> = ["Saved.\"", "Loading...", "Loading\u{2026}", "Note:", "(Optional)", "Done"]
+            .iter()
+            .map(|t| quick_metrics(&processor, t).terminal_punctuation)
+            .collect();
+        let expected = [Some("."), Some("..."), Some("\u{2026}"), Some(":"), None, None];
+        assert_eq!(endings, expected.map(|e| e.map(String::from)));
+    }
+
+    #[test]
+    fn ties_favour_no_punctuation_then_the_first_ending() {
+        let processor = TextProcessor::new();
+        let batch = |texts: &[&str]| quick_metrics_batch(&processor, &texts.iter().map(|t| t.to_string()).collect::<Vec<_>>());
+        let tied = batch(&["Save", "Cancel.", "Open", "Close."]);
+        assert_eq!(tied.majority_terminal_punctuation, None);
+        assert_eq!(tied.inconsistent_punctuation, [1, 3]);
+        let punctuated = batch(&["Save!", "Cancel.", "Open.", "Close!"]);
+        assert_eq!(punctuated.majority_terminal_punctuation.as_deref(), Some("!"));
+        assert!(batch(&["Save.", "Open."]).inconsistent_punctuation.is_empty());
+    }
+
+    #[test]
+    fn reading_time_follows_the_time_rounding_option() {
+        let config = SuggestionConfig { output: OutputOptions { time_decimals: Some(1), ..OutputOptions::default() }, ..Default::default() };
+        let processor = TextProcessor::with_patterns(&Default::default(), config);
+        assert_eq!(quick_metrics(&processor, "Save your changes now").reading_time_seconds, 1.0);
+    }
+}
@@ -0,0 +1,501 @@
+//! Readability score post-processing (clamping to the displayable range and
+//! explaining out-of-range raw values) and per-sentence difficulty.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::DifficultyNormalization;
+use crate::warnings::AnalysisWarning;
+
+pub const READING_EASE_MIN: f64 = 0.0;
+pub const READING_EASE_MAX: f64 = 100.0;
+
+// Tokens longer than this are almost never prose words
+const LONG_TOKEN_CHARS: usize = 30;
+
+/// What the clamp warning explains an out-of-range score from.
+pub struct ReadabilityInputs {
+    /// Words in the score.
+    pub words: usize,
+    /// Characters of the longest of them.
+    pub longest_word: usize,
+    /// Alphabetic characters of the text, and how many are not Latin.
+    pub letters: usize,
+    pub non_latin_letters: usize,
+    /// The text has a '.', '!', or '?'.
+    pub terminal_punctuation: bool,
+    pub avg_words_per_sentence: f64,
+    pub avg_syllables_per_word: f64,
+}
+
+/// Clamps Flesch Reading Ease to [0, 100]. When clamping was needed, returns
+/// a warning stating the raw value and the most likely cause.
+pub fn clamp_reading_ease(raw: f64, inputs: &ReadabilityInputs) -> (f64, Option<AnalysisWarning>) {
+    let clamped = raw.clamp(READING_EASE_MIN, READING_EASE_MAX);
+    if clamped == raw || inputs.words == 0 {
+        return (clamped, None);
+    }
+
+    let (cause, explanation) = likely_cause(raw, inputs);
+    let mut warning = AnalysisWarning::new(
+        "readability_clamped",
+        format!("Flesch reading ease of {:.1} was clamped to {:.0}: {}.", raw, clamped, explanation),
+    );
+    warning.cause = Some(cause.to_string());
+    (clamped, Some(warning))
+}
+
+fn likely_cause(raw: f64, inputs: &ReadabilityInputs) -> (&'static str, String) {
+    if inputs.letters > 0 && inputs.non_latin_letters * 2 > inputs.letters {
+        return (
+            "non_latin_script",
+            "most letters are outside the Latin script, where English syllable counting does not apply".to_string(),
+        );
+    }
+
+    if inputs.longest_word > LONG_TOKEN_CHARS {
+        return (
+            "extremely_long_tokens",
+            format!("the text contains a {}-character token; check for pasted URLs, code, or encoded data", inputs.longest_word),
+        );
+    }
+
+    if raw < READING_EASE_MIN {
+        if !inputs.terminal_punctuation {
+            return (
+                "no_terminal_punctuation",
+                format!(
+                    "average sentence length of {:.0} words; no sentence punctuation was found, so the text reads as one sentence",
+                    inputs.avg_words_per_sentence
+                ),
+            );
+        }
+        return (
+            "very_long_sentences",
+            format!(
+                "average sentence length of {:.0} words and {:.2} syllables per word",
+                inputs.avg_words_per_sentence, inputs.avg_syllables_per_word
+            ),
+        );
+    }
+
+    (
+        "very_short_sentences",
+        format!(
+            "average sentence length of {:.1} words; fragments or single-word sentences push the score past the scale",
+            inputs.avg_words_per_sentence
+        ),
+    )
+}
+
+fn is_latin(c: char) -> bool {
+    c.is_ascii_alphabetic() || matches!(c, '\u{00C0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}')
+}
+
+/// Alphabetic characters of `text`, and how many of them are not Latin.
+pub fn letter_counts(text: &str) -> (usize, usize) {
+    text.chars().filter(|c| c.is_alphabetic()).fold((0, 0), |(letters, non_latin), c| (letters + 1, non_latin + usize::from(!is_latin(c))))
+}
+
+/// Population standard deviation; 0.0 for fewer than two values.
+pub fn std_dev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt()
+}
+
+/// Flesch reading ease and its adaptations to other languages, all on the
+/// same 0-100 scale where higher is easier.
+#[derive(Clone, Copy)]
+pub enum ReadingEase {
+    Flesch,
+    /// Szigriszt-Pazos for Spanish.
+    FleschSzigriszt,
+    /// Amstad's German adaptation.
+    Amstad,
+}
+
+impl ReadingEase {
+    pub fn score(self, words_per_sentence: f64, syllables_per_word: f64) -> f64 {
+        match self {
+            ReadingEase::Flesch => 206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word,
+            ReadingEase::FleschSzigriszt => 206.835 - words_per_sentence - 62.3 * syllables_per_word,
+            ReadingEase::Amstad => 180.0 - words_per_sentence - 58.5 * syllables_per_word,
+        }
+    }
+}
+
+/// Counts behind the grade-level formulas.
+pub struct GradeInputs {
+    pub words: usize,
+    pub sentences: usize,
+    /// Letters and digits in words; punctuation is not counted.
+    pub letters: usize,
+    /// Words of three or more syllables.
+    pub polysyllables: usize,
+    /// Words longer than six characters.
+    pub long_words: usize,
+}
+
+pub struct GradeLevels {
+    pub smog_index: f64,
+    pub coleman_liau_index: f64,
+    pub automated_readability_index: f64,
+    pub lix: f64,
+}
+
+/// SMOG, Coleman-Liau, ARI, and LIX. All are 0.0 for text without words
+/// or sentences.
+pub fn grade_levels(inputs: &GradeInputs) -> GradeLevels {
+    if inputs.words == 0 || inputs.sentences == 0 {
+        return GradeLevels { smog_index: 0.0, coleman_liau_index: 0.0, automated_readability_index: 0.0, lix: 0.0 };
+    }
+    let (words, sentences) = (inputs.words as f64, inputs.sentences as f64);
+    let words_per_sentence = words / sentences;
+    let letters_per_word = inputs.letters as f64 / words;
+    GradeLevels {
+        // Normalized to a 30-sentence sample, which also covers shorter texts
+        smog_index: 1.043 * (inputs.polysyllables as f64 * 30.0 / sentences).sqrt() + 3.1291,
+        coleman_liau_index: 0.0588 * letters_per_word * 100.0 - 0.296 * sentences / words * 100.0 - 15.8,
+        automated_readability_index: 4.71 * letters_per_word + 0.5 * words_per_sentence - 21.43,
+        lix: words_per_sentence + 100.0 * inputs.long_words as f64 / words,
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SentenceReadability {
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub word_count: usize,
+    pub avg_syllables_per_word: f64,
+    /// Reading ease of the sentence on its own, in the language's Flesch
+    /// variant, clamped to [0, 100].
+    pub flesch_reading_ease: f64,
+    pub passive_voice: bool,
+}
+
+pub fn sentence_readability(
+    start_pos: usize,
+    end_pos: usize,
+    word_count: usize,
+    syllables: usize,
+    passive_voice: bool,
+    formula: ReadingEase,
+) -> SentenceReadability {
+    let avg_syllables_per_word = if word_count > 0 { syllables as f64 / word_count as f64 } else { 0.0 };
+    let flesch = if word_count > 0 { formula.score(word_count as f64, avg_syllables_per_word) } else { READING_EASE_MAX };
+    SentenceReadability {
+        start_pos,
+        end_pos,
+        word_count,
+        avg_syllables_per_word,
+        flesch_reading_ease: flesch.clamp(READING_EASE_MIN, READING_EASE_MAX),
+        passive_voice,
+    }
+}
+
+pub const DIFFICULTY_BUCKETS: &[&str] = &["easy", "medium", "hard", "very hard"];
+
+// Grade-level cut-offs between buckets in absolute mode
+const ABSOLUTE_GRADE_THRESHOLDS: [f64; 3] = [6.0, 10.0, 14.0];
+// Percentile cut-offs between buckets in document-relative mode
+const PERCENTILE_THRESHOLDS: [f64; 3] = [50.0, 75.0, 90.0];
+
+#[derive(Serialize, Deserialize)]
+pub struct SentenceDifficulty {
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub word_count: usize,
+    pub syllables_per_word: f64,
+    /// Flesch-Kincaid grade level of the sentence on its own.
+    pub grade_level: f64,
+    /// Grade level in absolute mode, percentile within the document in
+    /// percentile mode.
+    pub score: f64,
+    pub bucket: String,
+}
+
+/// Scores each sentence from its length and syllable density. `sentences`
+/// holds `(start, end, word_count, total_syllables)` per sentence.
+pub fn sentence_difficulties(
+    sentences: &[(usize, usize, usize, usize)],
+    normalization: DifficultyNormalization,
+) -> Vec<SentenceDifficulty> {
+    let grades: Vec<f64> = sentences
+        .iter()
+        .map(|&(_, _, words, syllables)| {
+            if words == 0 {
+                return 0.0;
+            }
+            let syllables_per_word = syllables as f64 / words as f64;
+            (0.39 * words as f64 + 11.8 * syllables_per_word - 15.59).max(0.0)
+        })
+        .collect();
+
+    sentences
+        .iter()
+        .zip(&grades)
+        .map(|(&(start_pos, end_pos, word_count, syllables), &grade_level)| {
+            let (score, thresholds) = match normalization {
+                DifficultyNormalization::Absolute => (grade_level, ABSOLUTE_GRADE_THRESHOLDS),
+                DifficultyNormalization::Percentile => {
+                    let below = grades.iter().filter(|&&g| g < grade_level).count() as f64;
+                    let equal = grades.iter().filter(|&&g| g == grade_level).count() as f64;
+                    (100.0 * (below + equal / 2.0) / grades.len() as f64, PERCENTILE_THRESHOLDS)
+                }
+            };
+            let bucket = thresholds.iter().filter(|&&t| score >= t).count();
+            SentenceDifficulty {
+                start_pos,
+                end_pos,
+                word_count,
+                syllables_per_word: if word_count > 0 { syllables as f64 / word_count as f64 } else { 0.0 },
+                grade_level,
+                score,
+                bucket: DIFFICULTY_BUCKETS[bucket].to_string(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    fn cause(raw: f64, text: &str, avg_words_per_sentence: f64) -> Option<String> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let (letters, non_latin_letters) = letter_counts(text);
+        let inputs = ReadabilityInputs {
+            words: words.len(),
+            longest_word: words.iter().map(|w| w.chars().count()).max().unwrap_or(0),
+            letters,
+            non_latin_letters,
+            terminal_punctuation: text.contains(['.', '!', '?']),
+            avg_words_per_sentence,
+            avg_syllables_per_word: 1.5,
+        };
+        let (clamped, warning) = clamp_reading_ease(raw, &inputs);
+        assert!((READING_EASE_MIN..=READING_EASE_MAX).contains(&clamped));
+        warning.map(|w| {
+            assert_eq!(w.code, "readability_clamped");
+            assert!(w.message.contains(&format!("{:.1}", raw)), "{}", w.message);
+            w.cause.unwrap()
+        })
+    }
+
+    #[test]
+    fn in_range_scores_are_not_explained() {
+        assert_eq!(cause(64.2, "The dog sat by the gate.", 6.0), None);
+        assert_eq!(cause(-40.0, "", 0.0), None);
+    }
+
+    #[test]
+    fn each_out_of_range_cause_is_detected() {
+        assert_eq!(cause(-12.0, "Это очень длинное предложение без конца", 60.0).as_deref(), Some("non_latin_script"));
+        assert_eq!(
+            cause(-3.0, "see aGVsbG8gd29ybGQgdGhpcyBpcyBiYXNlNjQgZGF0YQ for the data.", 8.0).as_deref(),
+            Some("extremely_long_tokens")
+        );
+        assert_eq!(cause(-85.0, "and then we walked and walked and walked", 214.0).as_deref(), Some("no_terminal_punctuation"));
+        assert_eq!(cause(-20.0, "It went on and on. Then it stopped.", 90.0).as_deref(), Some("very_long_sentences"));
+        assert_eq!(cause(121.2, "Go. Run. Now.", 1.0).as_deref(), Some("very_short_sentences"));
+    }
+
+    #[test]
+    fn analysis_reports_raw_and_clamped_scores() {
+        let result = crate::TextProcessor::new().perform_analysis("Go. Run. Stop. Now.");
+        assert_eq!(result.readability_score, READING_EASE_MAX);
+        assert!(result.readability_score_raw > READING_EASE_MAX);
+        assert_eq!(result.warnings[0].cause.as_deref(), Some("very_short_sentences"));
+    }
+
+    #[test]
+    fn sentence_difficulty_buckets_by_grade_or_percentile() {
+        // (start, end, words, syllables): grades 0, 6.8, 10.7 and 19.7
+        let sentences = [(0, 10, 4, 4), (11, 60, 12, 18), (61, 120, 22, 33), (121, 300, 30, 60)];
+        let buckets = |normalization| {
+            sentence_difficulties(&sentences, normalization).into_iter().map(|s| s.bucket).collect::<Vec<_>>()
+        };
+        assert_eq!(buckets(DifficultyNormalization::Absolute), ["easy", "medium", "hard", "very hard"]);
+        assert_eq!(buckets(DifficultyNormalization::Percentile), ["easy", "easy", "medium", "hard"]);
+
+        let scored = sentence_difficulties(&sentences, DifficultyNormalization::Percentile);
+        let scores: Vec<f64> = scored.iter().map(|s| s.score).collect();
+        assert_eq!(scores, [12.5, 37.5, 62.5, 87.5]);
+        assert_eq!((scored[3].start_pos, scored[3].end_pos, scored[3].syllables_per_word), (121, 300, 2.0));
+        assert_eq!(sentence_difficulties(&[(0, 0, 0, 0)], DifficultyNormalization::Absolute)[0].grade_level, 0.0);
+    }
+
+    #[test]
+    fn analyze_full_matches_the_separate_calls() {
+        let processor = crate::TextProcessor::new();
+        let text = "The cat sat. Notwithstanding considerable institutional reluctance, the committee eventually authorized comprehensive reorganization.";
+        let index = processor.index(text);
+        let sentences = processor.sentence_difficulties(&index);
+        assert_eq!(sentences.len(), processor.perform_analysis(text).sentence_count);
+        assert_eq!(sentences[0].bucket, "easy");
+        assert_eq!(sentences[1].bucket, "very hard");
+        assert_eq!(&text[sentences[0].start_pos..sentences[0].end_pos], "The cat sat.");
+    }
+
+    #[test]
+    fn sentence_readability_is_clamped_and_safe_on_empty_sentences() {
+        let easy = sentence_readability(0, 10, 4, 4, false, ReadingEase::Flesch);
+        assert_eq!(easy.flesch_reading_ease, READING_EASE_MAX);
+        let hard = sentence_readability(0, 10, 40, 160, true, ReadingEase::Flesch);
+        assert_eq!((hard.flesch_reading_ease, hard.avg_syllables_per_word, hard.passive_voice), (READING_EASE_MIN, 4.0, true));
+        let medium = sentence_readability(3, 9, 10, 15, false, ReadingEase::Flesch);
+        assert!((medium.flesch_reading_ease - ReadingEase::Flesch.score(10.0, 1.5)).abs() < 1e-9);
+        assert_eq!((medium.start_pos, medium.end_pos), (3, 9));
+        let empty = sentence_readability(0, 0, 0, 0, false, ReadingEase::Flesch);
+        assert_eq!((empty.avg_syllables_per_word, empty.flesch_reading_ease), (0.0, READING_EASE_MAX));
+    }
+
+    fn assert_grades(grades: &GradeLevels, expected: [f64; 4]) {
+        let actual = [grades.smog_index, grades.coleman_liau_index, grades.automated_readability_index, grades.lix];
+        for (name, (a, e)) in ["smog", "coleman_liau", "ari", "lix"].iter().zip(actual.iter().zip(expected)) {
+            assert!((a - e).abs() < 0.01, "{}: {} vs {}", name, a, e);
+        }
+    }
+
+    /// McLaughlin, "SMOG Grading: A New Readability Formula", Journal of
+    /// Reading 12(8), 1969: the grade of 30 sentences is 3 plus the square
+    /// root of their polysyllable count. The regression form used here gives
+    /// the same whole grade at every perfect square of the conversion table.
+    #[test]
+    fn smog_matches_mclaughlins_conversion_table() {
+        let processor = TextProcessor::new();
+        for root in 1..=15usize {
+            let mut polysyllables = root * root;
+            let sentences: Vec<String> = (0..30)
+                .map(|i| {
+                    let here = polysyllables.div_ceil(30 - i);
+                    polysyllables -= here;
+                    format!("The dog ran home{}.", " important".repeat(here))
+                })
+                .collect();
+            let smog = processor.perform_analysis(&sentences.join(" ")).complexity_metrics.smog_index;
+            assert_eq!(smog.floor() as usize, 3 + root, "{} polysyllables scored {}", root * root, smog);
+        }
+    }
+
+    /// The sentence of the Wikipedia article "Flesch–Kincaid readability
+    /// tests" whose grade level is published as 11.3; the other grade
+    /// formulas should land near it.
+    #[test]
+    fn grade_formulas_agree_with_the_published_grade_of_the_example() {
+        let text = "The Australian platypus is seemingly a hybrid of a mammal and reptilian creature.";
+        let metrics = TextProcessor::new().perform_analysis(text).complexity_metrics;
+        for (name, grade) in [("coleman_liau", metrics.coleman_liau_index), ("ari", metrics.automated_readability_index)] {
+            assert!((grade - 11.3).abs() < 2.0, "{}: {}", name, grade);
+        }
+    }
+
+    /// Björnsson's LIX scale: under 30 very easy, 30-40 easy (fiction), up to
+    /// 50 medium, up to 60 difficult, over 60 very difficult (official and
+    /// scientific prose).
+    #[test]
+    fn lix_places_public_domain_passages_on_bjornssons_scale() {
+        let processor = TextProcessor::new();
+        let lix = |text: &str| processor.perform_analysis(text).complexity_metrics.lix;
+        // Beatrix Potter, The Tale of Peter Rabbit (1902)
+        let fiction = lix("Once upon a time there were four little Rabbits, and their names were Flopsy, Mopsy, Cotton-tail, and \
+            Peter. They lived with their Mother in a sand-bank, underneath the root of a very big fir-tree. 'Now, my dears,' \
+            said old Mrs. Rabbit one morning, 'you may go into the fields or down the lane, but don't go into Mr. McGregor's \
+            garden: your Father had an accident there; he was put in a pie by Mrs. McGregor.'");
+        assert!((30.0..40.0).contains(&fiction), "{}", fiction);
+        // The Declaration of Independence (1776)
+        let official = lix("We hold these truths to be self-evident, that all men are created equal, that they are endowed by their \
+            Creator with certain unalienable Rights, that among these are Life, Liberty and the pursuit of Happiness. That to \
+            secure these rights, Governments are instituted among Men, deriving their just powers from the consent of the \
+            governed, That whenever any Form of Government becomes destructive of these ends, it is the Right of the People to \
+            alter or to abolish it, and to institute new Government, laying its foundation on such principles and organizing \
+            its powers in such form, as to them shall seem most likely to effect their Safety and Happiness.");
+        assert!(official > 60.0, "{}", official);
+    }
+
+    #[test]
+    fn smog_is_normalized_to_thirty_sentences() {
+        let short = grade_levels(&GradeInputs { words: 100, sentences: 10, letters: 450, polysyllables: 10, long_words: 0 });
+        let long = grade_levels(&GradeInputs { words: 300, sentences: 30, letters: 1350, polysyllables: 30, long_words: 0 });
+        assert!((short.smog_index - long.smog_index).abs() < 1e-9);
+        assert!((long.smog_index - (1.043 * 30f64.sqrt() + 3.1291)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn grade_levels_are_zero_without_words_or_sentences() {
+        for (words, sentences) in [(0, 0), (0, 3), (5, 0)] {
+            assert_grades(&grade_levels(&GradeInputs { words, sentences, letters: 20, polysyllables: 2, long_words: 1 }), [0.0; 4]);
+        }
+    }
+
+    #[test]
+    fn std_dev_is_the_population_spread() {
+        assert_eq!(std_dev(&[]), 0.0);
+        assert_eq!(std_dev(&[7.0]), 0.0);
+        assert_eq!(std_dev(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), 2.0);
+    }
+
+    #[test]
+    fn sentence_readability_uses_real_offsets_and_flags_passive_sentences() {
+        let processor = TextProcessor::new();
+        let text = "Dr. Lee opened the door. The letter was opened by her brother! Why?";
+        let sentences = processor.sentence_readability(&processor.index(text));
+        let spans: Vec<(&str, bool)> = sentences.iter().map(|s| (&text[s.start_pos..s.end_pos], s.passive_voice)).collect();
+        assert_eq!(spans, [("Dr. Lee opened the door.", false), ("The letter was opened by her brother!", true), ("Why?", false)]);
+        assert_eq!(sentences[1].word_count, 7);
+        assert!(processor.sentence_readability(&processor.index("")).is_empty());
+        assert!(processor.sentence_readability(&processor.index("  \n ")).is_empty());
+    }
+
+    #[test]
+    fn grade_levels_are_computed_from_letters_only() {
+        let processor = TextProcessor::new();
+        // Hand-counted: 14 words, 2 sentences, 41 letters, no polysyllables or
+        // long words; then 12 words, 2 sentences, 145 letters, every word
+        // polysyllabic and long
+        let passages = [
+            ("The cat sat on the mat. The dog ran to the big red ball.", [3.1291, -2.8088, -4.1357, 7.0]),
+            (
+                "Comprehensive educational assessments necessitate considerable institutional cooperation. \
+                 Administrators frequently underestimate implementation difficulties.",
+                [17.1223, 50.3167, 38.4825, 106.0],
+            ),
+        ];
+        for (passage, expected) in passages {
+            let metrics = processor.perform_analysis(passage).complexity_metrics;
+            let actual = [metrics.smog_index, metrics.coleman_liau_index, metrics.automated_readability_index, metrics.lix];
+            for (a, e) in actual.iter().zip(expected) {
+                assert!((a - e).abs() < 0.01, "{:?} vs {:?} for {:?}", actual, expected, passage);
+            }
+        }
+        // Punctuation adds no letters
+        let plain = processor.perform_analysis("The cat sat on the mat.").complexity_metrics;
+        let punctuated = processor.perform_analysis("The cat, sat (on) the \"mat\"!").complexity_metrics;
+        assert_eq!(plain.automated_readability_index, punctuated.automated_readability_index);
+    }
+
+    /// The worked example of the Wikipedia article "Flesch–Kincaid
+    /// readability tests", section "Flesch reading ease": the sentence "is
+    /// 37.5 as it has 24 syllables and 13 words".
+    #[test]
+    fn flesch_score_of_the_published_example() {
+        let processor = TextProcessor::new();
+        let platypus = processor.perform_analysis("The Australian platypus is seemingly a hybrid of a mammal and reptilian creature.");
+        assert_eq!(platypus.word_count, 13);
+        let metrics = platypus.complexity_metrics;
+        assert!((metrics.avg_syllables_per_word - 24.0 / 13.0).abs() < 1e-9, "{}", metrics.avg_syllables_per_word * 13.0);
+        assert!((metrics.flesch_reading_ease - 37.5).abs() < 0.1, "{}", metrics.flesch_reading_ease);
+    }
+
+    #[test]
+    fn sentence_length_std_dev_is_over_words_per_sentence() {
+        let processor = TextProcessor::new();
+        let metrics = |text: &str| processor.perform_analysis(text).complexity_metrics.sentence_length_std_dev;
+        assert_eq!(metrics("One two three four five six seven."), 0.0);
+        assert_eq!(metrics("One two. One two three four five six."), 2.0);
+        assert_eq!(metrics("One two. Three four."), 0.0);
+    }
+}
@@ -0,0 +1,238 @@
+//! Re-anchors conflict spans computed against a slightly different copy of
+//! the text (NBSP vs space, CRLF vs LF, a few bytes of drift).
+
+use serde::{Deserialize, Serialize};
+
+use crate::CollaborationConflict;
+
+pub const STATUS_EXACT: &str = "exact";
+pub const STATUS_SHIFTED: &str = "shifted";
+pub const STATUS_FUZZY: &str = "fuzzy";
+pub const STATUS_NOT_FOUND: &str = "not_found";
+/// The conflict carried no `expected_text`, so its span was kept as sent.
+pub const STATUS_UNVERIFIED: &str = "unverified";
+
+/// Bytes searched on either side of the submitted span.
+const SEARCH_WINDOW: usize = 256;
+/// Maximum edit distance for a fuzzy match, as a fraction of the excerpt length.
+const FUZZY_TOLERANCE: f64 = 0.2;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpanReconciliation {
+    pub conflict_id: String,
+    pub status: String,
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub original_start_pos: usize,
+    pub original_end_pos: usize,
+    /// Edit distance of a fuzzy match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance: Option<usize>,
+}
+
+fn is_space(c: char) -> bool {
+    c.is_whitespace()
+}
+
+/// Matches `expected` at byte `pos` treating any whitespace run (spaces,
+/// NBSP, CR/LF) as equal to any other. Returns the end of the match.
+fn whitespace_insensitive_match(text: &str, pos: usize, expected: &str) -> Option<usize> {
+    let mut haystack = text[pos..].char_indices().peekable();
+    let mut needle = expected.chars().peekable();
+    let mut end = pos;
+    while let Some(&n) = needle.peek() {
+        let &(i, h) = haystack.peek()?;
+        if is_space(n) {
+            if !is_space(h) {
+                return None;
+            }
+            while needle.peek().is_some_and(|c| is_space(*c)) {
+                needle.next();
+            }
+            while let Some(&(i, c)) = haystack.peek() {
+                if !is_space(c) {
+                    break;
+                }
+                end = pos + i + c.len_utf8();
+                haystack.next();
+            }
+        } else if n == h {
+            needle.next();
+            haystack.next();
+            end = pos + i + h.len_utf8();
+        } else {
+            return None;
+        }
+    }
+    Some(end)
+}
+
+fn window(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let mut low = start.min(text.len()).saturating_sub(SEARCH_WINDOW);
+    while !text.is_char_boundary(low) {
+        low -= 1;
+    }
+    let mut high = (end + SEARCH_WINDOW).min(text.len());
+    while !text.is_char_boundary(high) {
+        high += 1;
+    }
+    (low, high)
+}
+
+fn normalize_char(c: char) -> char {
+    if is_space(c) { ' ' } else { c }
+}
+
+/// Best approximate occurrence of `expected` inside `text[low..high]` by
+/// edit distance (Sellers' algorithm), preferring the candidate nearest
+/// `anchor` among equal distances. Returns `(start, end, distance)`.
+fn fuzzy_find(text: &str, low: usize, high: usize, anchor: usize, expected: &str) -> Option<(usize, usize, usize)> {
+    let needle: Vec<char> = expected.chars().filter(|c| *c != '\r').map(normalize_char).collect();
+    if needle.is_empty() {
+        return None;
+    }
+    let hay: Vec<(usize, char)> = text[low..high].char_indices().map(|(i, c)| (low + i, normalize_char(c))).collect();
+
+    // column[j] = (distance, match start index in `hay`) for needle[..j]
+    let mut column: Vec<(usize, usize)> = (0..=needle.len()).map(|j| (j, 0)).collect();
+    let mut best: Option<(usize, usize, usize)> = None;
+    for (h, &(_, c)) in hay.iter().enumerate() {
+        let mut previous_diagonal = (0, h);
+        column[0] = (0, h + 1);
+        for j in 1..=needle.len() {
+            let substitution = (previous_diagonal.0 + usize::from(needle[j - 1] != c), previous_diagonal.1);
+            let deletion = (column[j].0 + 1, column[j].1);
+            let insertion = (column[j - 1].0 + 1, column[j - 1].1);
+            previous_diagonal = column[j];
+            column[j] = [substitution, deletion, insertion].into_iter().min_by_key(|x| x.0).unwrap();
+        }
+        let (distance, start_index) = column[needle.len()];
+        let Some(&(start, _)) = hay.get(start_index) else { continue };
+        let end = hay[h].0 + text[hay[h].0..].chars().next().map_or(0, char::len_utf8);
+        let better = match best {
+            None => true,
+            Some((best_start, _, best_distance)) => {
+                distance < best_distance || (distance == best_distance && start.abs_diff(anchor) < best_start.abs_diff(anchor))
+            }
+        };
+        if better {
+            best = Some((start, end, distance));
+        }
+    }
+    best.filter(|&(_, _, distance)| distance as f64 <= needle.len() as f64 * FUZZY_TOLERANCE)
+}
+
+pub fn reconcile(text: &str, conflict: &CollaborationConflict) -> SpanReconciliation {
+    let (start, end) = (conflict.start_pos, conflict.end_pos);
+    let result = |status: &str, start_pos: usize, end_pos: usize, distance: Option<usize>| SpanReconciliation {
+        conflict_id: conflict.conflict_id.clone(),
+        status: status.to_string(),
+        start_pos,
+        end_pos,
+        original_start_pos: start,
+        original_end_pos: end,
+        distance,
+    };
+
+    let Some(expected) = conflict.expected_text.as_deref() else {
+        return result(STATUS_UNVERIFIED, start, end, None);
+    };
+    if text.get(start..end) == Some(expected) {
+        return result(STATUS_EXACT, start, end, None);
+    }
+
+    let (low, high) = window(text, start, end);
+    let mut candidates: Vec<usize> = (low..high).filter(|&i| text.is_char_boundary(i)).collect();
+    candidates.sort_by_key(|&i| i.abs_diff(start));
+    for candidate in candidates {
+        if let Some(match_end) = whitespace_insensitive_match(text, candidate, expected) {
+            if match_end <= high {
+                return result(STATUS_SHIFTED, candidate, match_end, None);
+            }
+        }
+    }
+
+    match fuzzy_find(text, low, high, start, expected) {
+        Some((fuzzy_start, fuzzy_end, distance)) => result(STATUS_FUZZY, fuzzy_start, fuzzy_end, Some(distance)),
+        None => result(STATUS_NOT_FOUND, start, end, None),
+    }
+}
+
+/// Reconciles every conflict and moves its span to the corrected location.
+/// Spans that could not be found are left untouched.
+pub fn reconcile_all(text: &str, conflicts: &mut [CollaborationConflict]) -> Vec<SpanReconciliation> {
+    conflicts
+        .iter_mut()
+        .map(|conflict| {
+            let reconciliation = reconcile(text, conflict);
+            conflict.start_pos = reconciliation.start_pos;
+            conflict.end_pos = reconciliation.end_pos;
+            reconciliation
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conflict(start_pos: usize, end_pos: usize, expected_text: Option<&str>) -> CollaborationConflict {
+        serde_json::from_value(serde_json::json!({
+            "conflict_id": "c1",
+            "conflict_type": "text_modification",
+            "start_pos": start_pos,
+            "end_pos": end_pos,
+            "user_a_change": "a",
+            "user_b_change": "b",
+            "timestamp": "2024-01-01T00:00:00Z",
+            "expected_text": expected_text,
+        }))
+        .unwrap()
+    }
+
+    fn outcome<'t>(text: &'t str, conflict: &CollaborationConflict) -> (String, &'t str, Option<usize>) {
+        let r = reconcile(text, conflict);
+        (r.status, &text[r.start_pos..r.end_pos], r.distance)
+    }
+
+    #[test]
+    fn nbsp_on_the_server_side_shifts_the_span() {
+        // The client computed offsets against "Intro. Mara walked the field."
+        let server = "Intro.\u{a0}Mara\u{a0}walked the field.";
+        let result = outcome(server, &conflict(7, 28, Some("Mara walked the field")));
+        assert_eq!(result, (STATUS_SHIFTED.to_string(), "Mara\u{a0}walked the field", None));
+    }
+
+    #[test]
+    fn crlf_on_the_server_side_shifts_the_span() {
+        // The client computed offsets against "Line one.\nThe storm came.\nEnd."
+        let server = "Line one.\r\nThe storm came.\r\nEnd.";
+        let result = outcome(server, &conflict(10, 30, Some("The storm came.\nEnd.")));
+        assert_eq!(result, (STATUS_SHIFTED.to_string(), "The storm came.\r\nEnd.", None));
+
+        // And the other way round: the client sent CRLF, the server has LF
+        let server = "Line one.\nThe storm came.\nEnd.";
+        let result = outcome(server, &conflict(11, 32, Some("The storm came.\r\nEnd.")));
+        assert_eq!(result, (STATUS_SHIFTED.to_string(), "The storm came.\nEnd.", None));
+    }
+
+    #[test]
+    fn exact_fuzzy_missing_and_unverified_spans() {
+        let text = "Line one. The storn came at night.";
+        assert_eq!(outcome(text, &conflict(0, 9, Some("Line one."))).0, STATUS_EXACT);
+        assert_eq!(outcome(text, &conflict(9, 23, Some("The storm came"))), (STATUS_FUZZY.to_string(), "The storn came", Some(1)));
+        assert_eq!(outcome(text, &conflict(0, 9, Some("a completely different passage"))).0, STATUS_NOT_FOUND);
+        assert_eq!(outcome(text, &conflict(3, 5, None)), (STATUS_UNVERIFIED.to_string(), "e ", None));
+    }
+
+    #[test]
+    fn reconcile_all_moves_found_spans_only() {
+        let text = "Intro.\u{a0}Mara walked.";
+        let mut conflicts = vec![conflict(7, 18, Some("Mara walked")), conflict(0, 3, Some("nowhere to be seen"))];
+        let statuses: Vec<String> = reconcile_all(text, &mut conflicts).into_iter().map(|r| r.status).collect();
+        assert_eq!(statuses, [STATUS_SHIFTED, STATUS_NOT_FOUND]);
+        assert_eq!((conflicts[0].start_pos, conflicts[0].end_pos), (8, 19));
+        assert_eq!((conflicts[1].start_pos, conflicts[1].end_pos), (0, 3));
+    }
+}
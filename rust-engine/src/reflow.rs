@@ -0,0 +1,262 @@
+//! Cleanup for text pasted from PDFs: rejoins words hyphen-split across
+//! lines and unwraps hard line breaks inside paragraphs, keeping an offset
+//! map back to the original text.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::warnings::AnalysisWarning;
+
+/// Prefixes that form hyphenated compounds ("self-aware", "well-known"), so
+/// a line-end hyphen after them is kept.
+const COMPOUND_PREFIXES: &[&str] = &[
+    "all", "anti", "co", "cross", "ex", "half", "ill", "mid", "non", "post", "pre", "quasi", "self", "semi",
+    "well",
+];
+
+/// Piecewise offset map from cleaned text back to the original. Each anchor
+/// `(clean, original)` applies from `clean` up to the next anchor: a cleaned
+/// offset `x` maps to `original + (x - clean)`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OffsetMap {
+    pub anchors: Vec<(usize, usize)>,
+}
+
+impl OffsetMap {
+    pub fn to_original(&self, clean_offset: usize) -> usize {
+        let i = self.anchors.partition_point(|a| a.0 <= clean_offset).saturating_sub(1);
+        let (clean, original) = self.anchors[i];
+        original + (clean_offset - clean)
+    }
+
+    /// `self` (from text cleaned a second time to `outer`'s cleaned text)
+    /// composed with `outer`, mapping straight back to the original.
+    pub fn then(&self, outer: &OffsetMap) -> OffsetMap {
+        let mut points: Vec<usize> = self.anchors.iter().map(|a| a.0).collect();
+        // Where this map's segments cross an anchor of `outer`
+        for &(outer_clean, _) in &outer.anchors {
+            let k = self.anchors.partition_point(|a| a.1 <= outer_clean).saturating_sub(1);
+            let (clean, original) = self.anchors[k];
+            let point = clean + outer_clean.saturating_sub(original);
+            if self.anchors.get(k + 1).is_none_or(|next| point < next.0) {
+                points.push(point);
+            }
+        }
+        points.sort_unstable();
+        points.dedup();
+        OffsetMap { anchors: points.into_iter().map(|p| (p, outer.to_original(self.to_original(p)))).collect() }
+    }
+
+    /// Maps a cleaned span back; the end is mapped through its last byte so
+    /// it never absorbs whitespace removed after it.
+    pub fn map_span(&self, start: usize, end: usize) -> (usize, usize) {
+        if end > start {
+            (self.to_original(start), self.to_original(end - 1) + 1)
+        } else {
+            let start = self.to_original(start);
+            (start, start)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReflowResult {
+    pub text: String,
+    pub offset_map: OffsetMap,
+    pub joined_hyphenations: usize,
+    pub kept_hyphenations: usize,
+    pub unwrapped_line_breaks: usize,
+}
+
+impl ReflowResult {
+    pub fn warning(&self) -> AnalysisWarning {
+        AnalysisWarning::new(
+            "reflow_applied",
+            format!(
+                "Input was reflowed before analysis: {} hyphenated words joined, {} line breaks unwrapped",
+                self.joined_hyphenations, self.unwrapped_line_breaks
+            ),
+        )
+    }
+}
+
+fn is_structural_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    line.starts_with('\t')
+        || line.starts_with("    ")
+        || trimmed.starts_with('#')
+        || trimmed.starts_with('>')
+        || trimmed.starts_with("- ")
+        || trimmed.starts_with("* ")
+        || trimmed.starts_with('\u{2022}')
+        || trimmed.split_once(". ").is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn words_of(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphabetic() && c != '-').filter(|w| !w.is_empty())
+}
+
+/// Decides whether `head-` at a line end followed by `tail` is a split word
+/// (join without the hyphen) or a hyphenated compound (keep the hyphen).
+/// Forms seen elsewhere in the document win; otherwise compound prefixes,
+/// capitalized tails, and tails that are words in their own right keep the
+/// hyphen.
+fn should_join(head: &str, tail: &str, vocabulary: &HashMap<String, usize>) -> bool {
+    let head_lower = head.to_lowercase();
+    let tail_lower = tail.to_lowercase();
+    // The split fragments themselves are counted once each
+    let seen_elsewhere = |word: &str, own: usize| vocabulary.get(word).copied().unwrap_or(0) > own;
+    if seen_elsewhere(&format!("{}-{}", head_lower, tail_lower), 0) {
+        return false;
+    }
+    if seen_elsewhere(&format!("{}{}", head_lower, tail_lower), 0) {
+        return true;
+    }
+    if COMPOUND_PREFIXES.contains(&head_lower.as_str()) || tail.starts_with(char::is_uppercase) {
+        return false;
+    }
+    let standalone = |word: &str| word.chars().count() >= 3 && seen_elsewhere(word, 1);
+    !(standalone(&head_lower) && standalone(&tail_lower))
+}
+
+pub fn reflow_text(text: &str) -> ReflowResult {
+    let mut vocabulary: HashMap<String, usize> = HashMap::new();
+    for word in text.split_inclusive('\n').flat_map(|line| words_of(line.trim_end().trim_end_matches('-'))) {
+        *vocabulary.entry(word.to_lowercase()).or_insert(0) += 1;
+    }
+
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut out = String::with_capacity(text.len());
+    let mut anchors = vec![(0, 0)];
+    let (mut joined_hyphenations, mut kept_hyphenations, mut unwrapped_line_breaks) = (0, 0, 0);
+
+    let mut line_start = 0;
+    let mut skip_indent = false;
+    for (i, line) in lines.iter().enumerate() {
+        let mut line = *line;
+        if skip_indent {
+            let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+            line = &line[indent..];
+            anchors.push((out.len(), line_start + indent));
+            skip_indent = false;
+        }
+        let content = line.trim_end_matches(['\r', '\n']);
+        let next = lines.get(i + 1).map(|l| l.trim_end_matches(['\r', '\n']));
+
+        let unwrap = match next {
+            Some(next) => {
+                line.ends_with('\n')
+                    && !content.trim().is_empty()
+                    && !next.trim().is_empty()
+                    && !is_structural_line(content)
+                    && !is_structural_line(next)
+            }
+            None => false,
+        };
+        if !unwrap {
+            out.push_str(line);
+            line_start += lines[i].len();
+            continue;
+        }
+
+        let kept = content.trim_end();
+        let head = kept.strip_suffix('-').map(|h| h.rsplit(|c: char| !c.is_alphabetic()).next().unwrap_or(""));
+        let tail = next.unwrap().trim_start().split(|c: char| !c.is_alphabetic()).next().unwrap_or("");
+        match head {
+            Some(head) if !head.is_empty() && !tail.is_empty() => {
+                if should_join(head, tail, &vocabulary) {
+                    out.push_str(&kept[..kept.len() - 1]);
+                    joined_hyphenations += 1;
+                } else {
+                    out.push_str(kept);
+                    kept_hyphenations += 1;
+                }
+            }
+            _ => {
+                out.push_str(kept);
+                out.push(' ');
+                unwrapped_line_breaks += 1;
+            }
+        }
+        line_start += lines[i].len();
+        skip_indent = true;
+    }
+
+    ReflowResult {
+        text: out,
+        offset_map: OffsetMap { anchors },
+        joined_hyphenations,
+        kept_hyphenations,
+        unwrapped_line_breaks,
+    }
+}
+
+/// Joins hyphen-split words and unwraps single line breaks inside
+/// paragraphs. Blank-line paragraph breaks, headings, list items, and block
+/// quotes are preserved.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn reflow(text: &str) -> Result<JsValue, JsError> {
+    crate::wasm::to_js(&reflow_text(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SuggestionConfig;
+    use crate::TextProcessor;
+
+    // Copied out of a two-column PDF: hard wraps, split words, and a list
+    const PDF_PASTE: &str = "After consid-\nering the evidence, the committee was well-\nknown for its self-\naware and\n  careful approach.\n\nThe long-\nterm plan was a long-term success.\n- first item\n- second item\n";
+
+    #[test]
+    fn pdf_paste_is_rejoined_and_unwrapped() {
+        let result = reflow_text(PDF_PASTE);
+        assert_eq!(
+            result.text,
+            "After considering the evidence, the committee was well-known for its self-aware and careful approach.\n\nThe long-term plan was a long-term success.\n- first item\n- second item\n"
+        );
+        assert_eq!((result.joined_hyphenations, result.kept_hyphenations, result.unwrapped_line_breaks), (1, 3, 1));
+    }
+
+    #[test]
+    fn offset_map_points_every_word_back_at_the_original() {
+        let result = reflow_text(PDF_PASTE);
+        for word in ["After", "evidence", "committee", "and", "careful", "approach", "plan", "success", "second"] {
+            let clean = result.text.find(word).unwrap();
+            let (start, end) = result.offset_map.map_span(clean, clean + word.len());
+            assert_eq!(&PDF_PASTE[start..end], word);
+        }
+        let clean = result.text.find("considering").unwrap();
+        let (start, end) = result.offset_map.map_span(clean, clean + "considering".len());
+        assert_eq!(&PDF_PASTE[start..end], "consid-\nering");
+    }
+
+    #[test]
+    fn split_words_seen_elsewhere_are_joined_and_standalone_halves_kept() {
+        let text = "The data-\nbase holds the database.\nThe dog-\nsled hit the dog on the sled.";
+        let result = reflow_text(text);
+        assert_eq!(result.text, "The database holds the database. The dog-sled hit the dog on the sled.");
+        assert_eq!(reflow_text("Already clean.\n\nTwo paragraphs.").text, "Already clean.\n\nTwo paragraphs.");
+    }
+
+    #[test]
+    fn analysis_option_reflows_and_reports_original_positions() {
+        let config = SuggestionConfig { reflow_input: true, ..SuggestionConfig::default() };
+        let processor = TextProcessor::with_patterns(&Default::default(), config);
+        let text = "The letter was sign-\ned by her\nbrother quietly.";
+        let result = processor.perform_analysis(text);
+        assert_eq!((result.word_count, result.sentence_count), (8, 1));
+        assert!(result.warnings.iter().any(|w| w.code == "reflow_applied"));
+        assert_eq!(result.content_hash, processor.generate_content_hash(text));
+
+        let report = processor.build_optimization_report(text);
+        let adverb = report.suggestions.iter().find(|s| s.suggestion_type == "adverb_usage" && s.scope == "instance").unwrap();
+        assert_eq!(&text[adverb.start_pos..adverb.end_pos], "quietly");
+        let passive = report.suggestions.iter().find(|s| s.suggestion_type == "passive_voice" && s.scope == "instance").unwrap();
+        assert_eq!(&text[passive.start_pos..passive.end_pos], "The letter was sign-\ned by her\nbrother quietly");
+        assert_eq!(passive.suggested_replacement.as_deref(), Some("Her brother quietly signed the letter"));
+    }
+}
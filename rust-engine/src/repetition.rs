@@ -0,0 +1,235 @@
+//! Distinctive words repeated within a few sentences of each other
+//! ("echoes"), matched loosely across inflections, and runs of sentences
+//! that all open on the same word.
+
+use std::collections::HashMap;
+
+use crate::lexicon::Stopwords;
+use crate::segmentation::{self, DocumentIndex};
+use crate::{OptimizationSuggestion, RelatedSpan};
+
+/// Shorter words repeat too naturally to be worth flagging.
+const MIN_ECHO_WORD_CHARS: usize = 4;
+/// Consecutive sentences sharing an opener before it is reported.
+const MIN_OPENER_RUN: usize = 3;
+/// Suffix stripping never leaves fewer characters than this.
+const MIN_STEM_CHARS: usize = 3;
+
+/// Crude English stem, so "run", "runs", and "running" (or "make" and
+/// "making") compare equal. Only needs to be consistent, not a real word.
+fn stem(word: &str) -> String {
+    let word = word.to_lowercase().replace('\u{2019}', "'");
+    let mut stem = word.strip_suffix("'s").unwrap_or(&word).to_string();
+    for suffix in ["ing", "ed", "s"] {
+        if stem.ends_with(suffix) && stem.chars().count() - suffix.len() >= MIN_STEM_CHARS && !stem.ends_with("ss") {
+            stem.truncate(stem.len() - suffix.len());
+            break;
+        }
+    }
+    if stem.ends_with('e') && stem.chars().count() > MIN_STEM_CHARS {
+        stem.pop();
+    }
+    // "running" -> "runn" -> "run"
+    let chars: Vec<char> = stem.chars().collect();
+    if let [.., a, b] = chars[..] {
+        if a == b && chars.len() > MIN_STEM_CHARS && !"aeiouls".contains(b) {
+            stem.pop();
+        }
+    }
+    stem
+}
+
+/// One `word_echo` suggestion per content word of 4+ characters whose stem
+/// was already used within the previous `window` words, spanning the repeat
+/// with the earlier use in `related_spans`. Repeats inside dialogue are
+/// often deliberate and drop to low priority.
+pub fn word_echoes(
+    index: &DocumentIndex,
+    stopwords: &Stopwords,
+    dialogue_spans: &[(usize, usize)],
+    is_opaque: impl Fn(&str) -> bool,
+    window: usize,
+) -> Vec<OptimizationSuggestion> {
+    let text = index.text;
+    let in_dialogue = |pos: usize| segmentation::within(dialogue_spans, pos, pos + 1);
+    // Stem -> (word position, span) of its latest use
+    let mut last_seen: HashMap<String, (usize, (usize, usize))> = HashMap::new();
+    let mut suggestions = Vec::new();
+
+    for (position, &(start, end)) in index.words.iter().enumerate() {
+        let word = &text[start..end];
+        if !stopwords.is_content_word(word) || is_opaque(word) {
+            continue;
+        }
+        // Short words are still recorded, so "running" echoes "run"
+        if let Some((previous, (prev_start, prev_end))) = last_seen.insert(stem(word), (position, (start, end))) {
+            let distance = position - previous;
+            if distance > window || word.chars().count() < MIN_ECHO_WORD_CHARS {
+                continue;
+            }
+            let priority = if in_dialogue(start) { "low" } else { "medium" };
+            let message = format!(
+                "\"{}\" echoes \"{}\" used {} word{} earlier; consider a different word.",
+                word,
+                &text[prev_start..prev_end],
+                distance,
+                if distance == 1 { "" } else { "s" }
+            );
+            suggestions.push(OptimizationSuggestion {
+                related_spans: vec![RelatedSpan { start_pos: prev_start, end_pos: prev_end }],
+                ..OptimizationSuggestion::instance("word_echo", priority, message, start, end)
+            });
+        }
+    }
+    suggestions
+}
+
+/// First word of every sentence, lowercased, skipping words inside
+/// `dialogue_spans` so a quoted line followed by "she said" opens on "she".
+/// `None` for a sentence with no words outside dialogue.
+fn openers(index: &DocumentIndex, dialogue_spans: &[(usize, usize)]) -> Vec<Option<String>> {
+    index
+        .sentences
+        .iter()
+        .map(|&(start, end)| {
+            index
+                .words_in(start, end)
+                .iter()
+                .find(|w| !segmentation::within(dialogue_spans, w.0, w.1))
+                .map(|&(s, e)| index.text[s..e].to_lowercase())
+        })
+        .collect()
+}
+
+/// One `repetitive_opener` suggestion per run of three or more consecutive
+/// sentences opening on the same word, spanning the whole run.
+pub fn repetitive_openers(index: &DocumentIndex, dialogue_spans: &[(usize, usize)]) -> Vec<OptimizationSuggestion> {
+    let openers = openers(index, dialogue_spans);
+    let mut suggestions = Vec::new();
+    let mut run_start = 0;
+    for i in 1..=openers.len() {
+        if i < openers.len() && openers[i].is_some() && openers[i] == openers[run_start] {
+            continue;
+        }
+        if let Some(opener) = &openers[run_start] {
+            let run = i - run_start;
+            if run >= MIN_OPENER_RUN {
+                let (start, end) = (index.sentences[run_start].0, index.sentences[i - 1].1);
+                let first = index.words_in(start, end).iter().find(|w| index.text[w.0..w.1].to_lowercase() == *opener).copied();
+                let label = first.map_or(opener.as_str(), |(s, e)| &index.text[s..e]);
+                suggestions.push(OptimizationSuggestion::instance(
+                    "repetitive_opener",
+                    "low",
+                    format!("{} sentences in a row open with \"{}\"; consider varying how they begin.", run, label),
+                    start,
+                    end,
+                ));
+            }
+        }
+        run_start = i;
+    }
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SuggestionConfig;
+    use crate::test_support::index;
+
+    /// `(message, priority, repeat, earlier use)` for each echo in `text`.
+    fn echoes<'t>(text: &'t str, dialogue_spans: &[(usize, usize)], window: usize) -> Vec<(String, String, &'t str, &'t str)> {
+        let index = index(text);
+        let stopwords = Stopwords::from_config(&SuggestionConfig::default());
+        word_echoes(&index, &stopwords, dialogue_spans, |_| false, window)
+            .into_iter()
+            .map(|s| {
+                let earlier = &s.related_spans[0];
+                (s.message.to_string(), s.priority.to_string(), &text[s.start_pos..s.end_pos], &text[earlier.start_pos..earlier.end_pos])
+            })
+            .collect()
+    }
+
+    /// "lantern", `gap - 1` short filler words, then "lantern" again.
+    fn lanterns(gap: usize) -> String {
+        format!("The lantern {}lantern glowed.", "cat ".repeat(gap - 1))
+    }
+
+    #[test]
+    fn repeats_inside_the_window_are_flagged_with_their_distance() {
+        let text = lanterns(10);
+        let found = echoes(&text, &[], 50);
+        assert_eq!(found.len(), 1);
+        let (message, priority, repeat, earlier) = &found[0];
+        assert_eq!((priority.as_str(), *repeat, *earlier), ("medium", "lantern", "lantern"));
+        assert_eq!(message, "\"lantern\" echoes \"lantern\" used 10 words earlier; consider a different word.");
+        assert_eq!(text.rfind("lantern"), Some(text.len() - "lantern glowed.".len()));
+    }
+
+    #[test]
+    fn repeats_beyond_the_window_are_not_flagged() {
+        assert!(echoes(&lanterns(200), &[], 50).is_empty());
+        assert_eq!(echoes(&lanterns(50), &[], 50).len(), 1);
+        assert!(echoes(&lanterns(51), &[], 50).is_empty());
+        assert_eq!(echoes(&lanterns(200), &[], 200).len(), 1);
+    }
+
+    #[test]
+    fn stopwords_and_short_words_are_never_flagged() {
+        assert!(echoes("the the the cat cat. There there, about about.", &[], 50).is_empty());
+    }
+
+    #[test]
+    fn inflections_echo_and_dialogue_repeats_are_low_priority() {
+        let found = echoes("Ravens run. Running ravens, she said.", &[], 50);
+        let pairs: Vec<(&str, &str)> = found.iter().map(|(_, _, repeat, earlier)| (*repeat, *earlier)).collect();
+        assert_eq!(pairs, [("Running", "run"), ("ravens", "Ravens")]);
+
+        let text = "\"Never, never, never,\" he said.";
+        let found = echoes(text, &[(0, 22)], 50);
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|(message, priority, ..)| priority == "low" && message.contains("1 word earlier")));
+    }
+
+    #[test]
+    fn stems_match_across_common_inflections() {
+        for (a, b) in [("run", "running"), ("make", "making"), ("walked", "walks"), ("dragon's", "dragons"), ("Stone", "stones")] {
+            assert_eq!(stem(a), stem(b), "{} / {}", a, b);
+        }
+        assert_ne!(stem("glass"), stem("glas"));
+        assert_eq!(stem("sing"), "sing");
+    }
+
+    fn opener_runs<'t>(text: &'t str, dialogue_spans: &[(usize, usize)]) -> Vec<(String, &'t str)> {
+        let index = index(text);
+        repetitive_openers(&index, dialogue_spans).into_iter().map(|s| (s.message.to_string(), &text[s.start_pos..s.end_pos])).collect()
+    }
+
+    #[test]
+    fn four_sentences_opening_alike_are_one_suggestion() {
+        let text = "Mara woke early. She dressed. she ate. She left. SHE ran. The bus was late.";
+        let runs = opener_runs(text, &[]);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0], ("4 sentences in a row open with \"She\"; consider varying how they begin.".to_string(), "She dressed. she ate. She left. SHE ran."));
+    }
+
+    #[test]
+    fn varied_or_short_runs_yield_nothing() {
+        assert!(opener_runs("Mara woke early. She dressed. The bus was late. She left. She ran.", &[]).is_empty());
+        assert!(opener_runs("", &[]).is_empty());
+    }
+
+    #[test]
+    fn dialogue_at_the_start_of_a_sentence_is_skipped() {
+        let text = "\"Go away,\" he said. \"Now,\" he added. He left.";
+        let dialogue: Vec<(usize, usize)> = ["\"Go away,\"", "\"Now,\""]
+            .iter()
+            .map(|quote| {
+                let start = text.find(quote).unwrap();
+                (start, start + quote.len())
+            })
+            .collect();
+        assert_eq!(opener_runs(text, &dialogue).len(), 1);
+        assert!(opener_runs(text, &[]).is_empty());
+    }
+}
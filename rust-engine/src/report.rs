@@ -0,0 +1,286 @@
+//! Shareable reports of a full analysis: a readable Markdown document, flat
+//! CSV metric rows, or the JSON of the analysis and optimization results.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::hashing::unsupported;
+use crate::suggestions::priority_rank;
+use crate::{FullAnalysis, OptimizationReport, OptimizationSuggestion, TextAnalysisResult};
+
+pub const FORMATS: &[&str] = &["markdown", "csv", "json"];
+
+#[derive(Clone, Copy)]
+pub enum ReportFormat {
+    Markdown,
+    Csv,
+    Json,
+}
+
+impl ReportFormat {
+    pub fn parse(value: &str) -> Result<ReportFormat, String> {
+        match value {
+            "markdown" => Ok(ReportFormat::Markdown),
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(unsupported("report format", value, FORMATS)),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    #[serde(flatten)]
+    full: &'a FullAnalysis,
+    optimization: &'a OptimizationReport,
+}
+
+/// `full` and `optimization` rendered as `format`. The Markdown suggestions
+/// table lists at most `max_rows` suggestions, highest priority first.
+pub fn render(format: ReportFormat, full: &FullAnalysis, optimization: &OptimizationReport, max_rows: usize) -> String {
+    match format {
+        ReportFormat::Markdown => markdown(&full.analysis, optimization, max_rows),
+        ReportFormat::Csv => csv(&full.analysis),
+        ReportFormat::Json => serde_json::to_string_pretty(&JsonReport { full, optimization }).unwrap_or_default(),
+    }
+}
+
+fn number(value: f64) -> String {
+    format!("{:.2}", value)
+}
+
+/// A table cell: pipes escaped and line breaks flattened.
+fn cell(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ").replace('|', "\\|")
+}
+
+fn table(out: &mut String, rows: &[(&str, String)]) {
+    out.push_str("| Metric | Value |\n| --- | --- |\n");
+    for (label, value) in rows {
+        out.push_str(&format!("| {} | {} |\n", label, value));
+    }
+    out.push('\n');
+}
+
+fn markdown(analysis: &TextAnalysisResult, optimization: &OptimizationReport, max_rows: usize) -> String {
+    let complexity = &analysis.complexity_metrics;
+    let style = &analysis.style_metrics;
+    let mut out = String::from("# Analysis report\n\n");
+
+    out.push_str("## Counts\n\n");
+    table(&mut out, &[
+        ("Words", analysis.word_count.to_string()),
+        ("Characters", analysis.character_count.to_string()),
+        ("Sentences", analysis.sentence_count.to_string()),
+        ("Paragraphs", analysis.paragraph_count.to_string()),
+        ("Reading time (seconds)", number(analysis.estimated_reading_time_seconds)),
+        ("Speaking time (seconds)", number(analysis.estimated_speaking_time_seconds)),
+    ]);
+
+    out.push_str("## Readability\n\n");
+    table(&mut out, &[
+        ("Flesch reading ease", number(analysis.readability_score)),
+        ("Fog index", number(complexity.fog_index)),
+        ("SMOG index", number(complexity.smog_index)),
+        ("Coleman-Liau index", number(complexity.coleman_liau_index)),
+        ("Automated readability index", number(complexity.automated_readability_index)),
+        ("LIX", number(complexity.lix)),
+        ("Words per sentence", number(complexity.avg_words_per_sentence)),
+        ("Syllables per word", number(complexity.avg_syllables_per_word)),
+        ("Unique word ratio", number(complexity.unique_word_ratio)),
+    ]);
+
+    out.push_str("## Style\n\n");
+    table(&mut out, &[
+        ("Passive voice ratio", number(style.passive_voice_ratio)),
+        ("Adverb ratio", number(style.adverb_ratio)),
+        ("Dialogue ratio", number(style.dialogue_ratio)),
+        ("Action ratio", number(style.action_ratio)),
+        ("Description ratio", number(style.description_ratio)),
+        ("Filter word ratio", number(style.filter_word_ratio)),
+        ("Clichés per 1,000 words", number(style.cliches_per_1000_words)),
+        ("Sentiment", number(analysis.sentiment_score)),
+    ]);
+
+    if !analysis.warnings.is_empty() {
+        out.push_str("## Warnings\n\n");
+        for warning in &analysis.warnings {
+            out.push_str(&format!("- {}\n", cell(&warning.message)));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("## Suggestions\n\n");
+    let mut suggestions: Vec<&OptimizationSuggestion> = optimization.suggestions.iter().collect();
+    if suggestions.is_empty() {
+        out.push_str("No suggestions.\n\n");
+    } else {
+        suggestions.sort_by(|a, b| priority_rank(&b.priority).cmp(&priority_rank(&a.priority)).then(a.start_pos.cmp(&b.start_pos)));
+        out.push_str(&format!("{} suggestions", suggestions.len()));
+        if suggestions.len() > max_rows {
+            out.push_str(&format!(", the first {} by priority", max_rows));
+        }
+        out.push_str(".\n\n| Rule | Priority | Line | Message |\n| --- | --- | --- | --- |\n");
+        for s in suggestions.iter().take(max_rows) {
+            out.push_str(&format!("| {} | {} | {} | {} |\n", s.suggestion_type, s.priority, s.line + 1, cell(&s.message)));
+        }
+        out.push('\n');
+    }
+
+    out.push_str(&format!("---\n\nContent hash: `{}`\n", analysis.content_hash));
+    out
+}
+
+/// A CSV field, quoted when it holds a separator, quote, or line break.
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Every scalar of `value`, keyed by its dotted path; lists are skipped.
+fn flatten(prefix: &str, value: &Value, rows: &mut Vec<(String, String)>) {
+    let key = |name: &str| if prefix.is_empty() { name.to_string() } else { format!("{}.{}", prefix, name) };
+    match value {
+        Value::Object(fields) => fields.iter().for_each(|(name, value)| flatten(&key(name), value, rows)),
+        Value::Array(_) | Value::Null => {}
+        Value::String(s) => rows.push((prefix.to_string(), s.clone())),
+        other => rows.push((prefix.to_string(), other.to_string())),
+    }
+}
+
+fn csv(analysis: &TextAnalysisResult) -> String {
+    let mut rows = Vec::new();
+    flatten("", &serde_json::to_value(analysis).unwrap_or(Value::Null), &mut rows);
+    let mut out = String::from("metric,value\n");
+    for (metric, value) in rows {
+        out.push_str(&format!("{},{}\n", field(&metric), field(&value)));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TextProcessor;
+    use crate::config::SuggestionConfig;
+
+    const REPORT_FIXTURE: &str = "The report was written quickly by the team. It covers the launch.\n\nAt the end of the day, we shipped.";
+
+    const MARKDOWN_SNAPSHOT: &str = r#"# Analysis report
+
+## Counts
+
+| Metric | Value |
+| --- | --- |
+| Words | 20 |
+| Characters | 101 |
+| Sentences | 3 |
+| Paragraphs | 2 |
+| Reading time (seconds) | 6.00 |
+| Speaking time (seconds) | 9.00 |
+
+## Readability
+
+| Metric | Value |
+| --- | --- |
+| Flesch reading ease | 98.55 |
+| Fog index | 2.67 |
+| SMOG index | 3.13 |
+| Coleman-Liau index | 2.40 |
+| Automated readability index | 0.04 |
+| LIX | 21.67 |
+| Words per sentence | 6.67 |
+| Syllables per word | 1.20 |
+| Unique word ratio | 0.80 |
+
+## Style
+
+| Metric | Value |
+| --- | --- |
+| Passive voice ratio | 0.33 |
+| Adverb ratio | 0.05 |
+| Dialogue ratio | 0.00 |
+| Action ratio | 0.00 |
+| Description ratio | 0.33 |
+| Filter word ratio | 0.00 |
+| Clichés per 1,000 words | 50.00 |
+| Sentiment | 0.00 |
+
+## Suggestions
+
+3 suggestions, the first 2 by priority.
+
+| Rule | Priority | Line | Message |
+| --- | --- | --- | --- |
+| passive_voice | low | 1 | Consider using active voice for more engaging writing. |
+| adverb_usage | low | 1 | Consider using stronger verbs instead of adverbs. |
+
+---
+
+Content hash: `jO9zpya94JgaauexzcGyC5YyQTpC0B4WQq7V+D3jSjE=`
+"#;
+
+    const CSV_SNAPSHOT: &str = r#"metric,value
+characterCount,101
+complexityMetrics.automatedReadabilityIndex,0.03683333333333394
+complexityMetrics.avgContentWordLength,5.444444444444445
+complexityMetrics.avgSyllablesPerWord,1.2
+complexityMetrics.avgWordsPerSentence,6.666666666666667
+complexityMetrics.colemanLiauIndex,2.3979999999999997
+complexityMetrics.contentWordCount,9
+complexityMetrics.fleschReadingEase,98.54833333333333
+complexityMetrics.fogIndex,2.666666666666667
+complexityMetrics.lexicalDensity,0.45
+complexityMetrics.lix,21.666666666666668
+complexityMetrics.mattr,0.8
+complexityMetrics.mtld,20.0
+complexityMetrics.sentenceLengthStdDev,1.8856180831641267
+complexityMetrics.smogIndex,3.1291
+complexityMetrics.uniqueContentWordRatio,1.0
+complexityMetrics.uniqueWordRatio,0.8
+contentHash,jO9zpya94JgaauexzcGyC5YyQTpC0B4WQq7V+D3jSjE=
+estimatedReadingTimeSeconds,6.0
+estimatedSpeakingTimeSeconds,9.0
+graphemeCount,101
+lowConfidence,false
+paragraphCount,2
+readabilityScore,98.54833333333333
+readabilityScoreRaw,98.54833333333333
+sentenceCount,3
+sentimentScore,0.0
+styleMetrics.actionRatio,0.0
+styleMetrics.adverbRatio,0.05
+styleMetrics.clichesPer1000Words,50.0
+styleMetrics.descriptionRatio,0.3333333333333333
+styleMetrics.dialogueRatio,0.0
+styleMetrics.filterWordRatio,0.0
+styleMetrics.passiveVoiceRatio,0.3333333333333333
+wordCount,20
+"#;
+
+    #[test]
+    fn export_report_snapshot_markdown() {
+        let processor = TextProcessor::with_config(SuggestionConfig { report_suggestion_rows: 2, ..SuggestionConfig::default() });
+        assert_eq!(processor.export_report(REPORT_FIXTURE, "markdown").unwrap(), MARKDOWN_SNAPSHOT);
+    }
+
+    #[test]
+    fn export_report_snapshot_csv() {
+        assert_eq!(TextProcessor::new().export_report(REPORT_FIXTURE, "csv").unwrap(), CSV_SNAPSHOT);
+    }
+
+    #[test]
+    fn export_report_json_and_unknown_format() {
+        let processor = TextProcessor::new();
+        let report: serde_json::Value = serde_json::from_str(&processor.export_report(REPORT_FIXTURE, "json").unwrap()).unwrap();
+        // Both through the same float parsing
+        let full: serde_json::Value = serde_json::from_str(&serde_json::to_string(&processor.analyze_full(REPORT_FIXTURE)).unwrap()).unwrap();
+        assert_eq!(report["analysis"], full["analysis"]);
+        assert_eq!(report["sentences"], full["sentences"]);
+        assert_eq!(report["optimization"]["suggestions"].as_array().map(Vec::len), Some(3));
+        let error = processor.export_report(REPORT_FIXTURE, "pdf").unwrap_err();
+        assert!(error.contains("markdown, csv, json"));
+    }
+}
@@ -0,0 +1,483 @@
+//! Per-document policies for automatically resolving collaboration
+//! conflicts, and the RFC 3339 timestamps the latest-wins policy compares.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::conflicts::COMPOSITE_CONFLICT;
+use crate::merge::MANUAL_RESOLUTION;
+use crate::rewrite;
+use crate::similarity;
+use crate::CollaborationConflict;
+
+/// Word-level Jaccard similarity at which two insertions are taken for the
+/// same text typed twice, and only the longer is kept.
+const DUPLICATE_INSERTION_SIMILARITY: f64 = 0.8;
+
+/// Punctuation ending a sentence, and the closing marks that may follow it.
+const SENTENCE_END: &[char] = &['.', '!', '?', '\u{2026}'];
+const CLOSERS: &[char] = &['"', '\u{201D}', '\u{2019}', '\'', ')', ']'];
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// The built-in rules: insertions merged (`merge_insertions`), the
+    /// shorter deletion kept, modifications going to user B.
+    #[default]
+    Auto,
+    PreferA,
+    PreferB,
+    /// The change with the later `user_a_timestamp` / `user_b_timestamp`.
+    PreferLatest,
+    /// The change with more characters.
+    PreferLongest,
+    /// Both insertions merged, A first (`merge_insertions`). Other conflict
+    /// types can't be combined.
+    MergeBoth,
+    Manual,
+}
+
+impl ConflictPolicy {
+    pub const ALL: &'static [&'static str] =
+        &["auto", "prefer_a", "prefer_b", "prefer_latest", "prefer_longest", "merge_both", "manual"];
+
+    pub fn parse(name: &str) -> Result<ConflictPolicy, String> {
+        match name {
+            "auto" => Ok(ConflictPolicy::Auto),
+            // user_a and user_b are the names preview_resolution once took
+            "prefer_a" | "user_a" => Ok(ConflictPolicy::PreferA),
+            "prefer_b" | "user_b" => Ok(ConflictPolicy::PreferB),
+            "prefer_latest" => Ok(ConflictPolicy::PreferLatest),
+            "prefer_longest" => Ok(ConflictPolicy::PreferLongest),
+            "merge_both" => Ok(ConflictPolicy::MergeBoth),
+            "manual" => Ok(ConflictPolicy::Manual),
+            other => Err(format!("unknown conflict policy \"{}\" (expected one of {})", other, Self::ALL.join(", "))),
+        }
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Nanoseconds since the Unix epoch of an RFC 3339 timestamp
+/// ("2024-05-01T12:30:00.25+02:00"); `None` if it is malformed.
+pub fn parse_rfc3339(timestamp: &str) -> Option<i128> {
+    let bytes = timestamp.as_bytes();
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = bytes.get(range)?;
+        digits.iter().all(u8::is_ascii_digit).then(|| digits.iter().fold(0, |n, d| n * 10 + i64::from(d - b'0')))
+    };
+    let separators = [(4, b'-'), (7, b'-'), (13, b':'), (16, b':')];
+    if separators.iter().any(|&(i, c)| bytes.get(i) != Some(&c)) || !matches!(bytes.get(10), Some(b'T' | b't' | b' ')) {
+        return None;
+    }
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let mut i = 19;
+    let mut nanos: i128 = 0;
+    if bytes.get(i) == Some(&b'.') {
+        let digits = bytes[i + 1..].iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return None;
+        }
+        // Digits beyond nanosecond precision are ignored
+        for (place, d) in bytes[i + 1..i + 1 + digits].iter().take(9).enumerate() {
+            nanos += i128::from(d - b'0') * 10i128.pow(8 - place as u32);
+        }
+        i += 1 + digits;
+    }
+    let offset_minutes = match bytes.get(i) {
+        Some(b'Z' | b'z') if i + 1 == bytes.len() => 0,
+        Some(&sign @ (b'+' | b'-')) if i + 6 == bytes.len() && bytes[i + 3] == b':' => {
+            let (hours, minutes) = (number(i + 1..i + 3)?, number(i + 4..i + 6)?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = hours * 60 + minutes;
+            if sign == b'-' { -offset } else { offset }
+        }
+        _ => return None,
+    };
+
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second - offset_minutes * 60;
+    Some(i128::from(seconds) * 1_000_000_000 + nanos)
+}
+
+/// Sorts conflicts by span and merges each run of overlapping ones into a
+/// single `composite` conflict covering all of them, so no two returned
+/// spans overlap and they can be applied back to front. A composite's ids
+/// are joined with "+" and its changes concatenated in position order.
+/// Zero-width conflicts at the same position don't overlap.
+pub fn merge_overlapping(mut conflicts: Vec<CollaborationConflict>) -> Vec<CollaborationConflict> {
+    conflicts.sort_by_key(|c| (c.start_pos, c.end_pos));
+    let mut groups: Vec<Vec<CollaborationConflict>> = Vec::new();
+    for conflict in conflicts {
+        match groups.last_mut() {
+            Some(group) if conflict.start_pos < group.iter().map(|c| c.end_pos).max().unwrap_or(0) => group.push(conflict),
+            _ => groups.push(vec![conflict]),
+        }
+    }
+    groups.into_iter().map(composite).collect()
+}
+
+fn composite(mut group: Vec<CollaborationConflict>) -> CollaborationConflict {
+    if group.len() == 1 {
+        return group.remove(0);
+    }
+    let join = |field: fn(&CollaborationConflict) -> &str, separator: &str| group.iter().map(field).collect::<Vec<_>>().join(separator);
+    CollaborationConflict {
+        conflict_id: join(|c| &c.conflict_id, "+"),
+        conflict_type: COMPOSITE_CONFLICT.to_string(),
+        start_pos: group[0].start_pos,
+        end_pos: group.iter().map(|c| c.end_pos).max().unwrap_or(group[0].end_pos),
+        user_a_change: join(|c| &c.user_a_change, " "),
+        user_b_change: join(|c| &c.user_b_change, " "),
+        timestamp: group[0].timestamp.clone(),
+        // Latest of each user's parseable timestamps
+        user_a_timestamp: group.iter().filter_map(|c| c.user_a_timestamp.clone()).max_by_key(|t| parse_rfc3339(t)),
+        user_b_timestamp: group.iter().filter_map(|c| c.user_b_timestamp.clone()).max_by_key(|t| parse_rfc3339(t)),
+        resolution_suggestion: String::new(),
+        expected_text: None,
+        start_utf16: None,
+        end_utf16: None,
+        line: None,
+        column: None,
+    }
+}
+
+fn ends_sentence(fragment: &str) -> bool {
+    fragment.trim_end_matches(CLOSERS).ends_with(SENTENCE_END)
+}
+
+/// Whether an insertion reads as a sentence of its own: it opens with a
+/// capital letter or closes with terminal punctuation.
+fn is_sentence(fragment: &str) -> bool {
+    fragment.trim_start_matches(|c: char| !c.is_alphanumeric()).starts_with(char::is_uppercase) || ends_sentence(fragment)
+}
+
+/// Both users' insertions at one position as a single insertion. Near
+/// duplicates (word-level Jaccard of at least 0.8) keep only the longer, A
+/// on a tie; an insertion that is only whitespace gives way to the other.
+/// Otherwise A and B are joined by one space, with A's leading and B's
+/// trailing whitespace kept; when both read as sentences A gets a full
+/// stop if it has no terminal punctuation and B is capitalized, and B's
+/// leading punctuation is dropped after A's.
+fn merge_insertions(a: &str, b: &str) -> String {
+    if b.trim().is_empty() {
+        return a.to_string();
+    }
+    if a.trim().is_empty() {
+        return b.to_string();
+    }
+    let words = |text: &str| text.unicode_words().map(str::to_lowercase).collect::<Vec<String>>();
+    if similarity::shingle_jaccard(&words(a), &words(b), 1) >= DUPLICATE_INSERTION_SIMILARITY {
+        let longer = if b.chars().count() > a.chars().count() { b } else { a };
+        return longer.to_string();
+    }
+
+    let leading = &a[..a.len() - a.trim_start().len()];
+    let trailing = &b[b.trim_end().len()..];
+    let (mut first, mut second) = (a.trim().to_string(), b.trim().to_string());
+    if ends_sentence(&first) {
+        second = second.trim_start_matches(SENTENCE_END).trim_start().to_string();
+    }
+    if is_sentence(&first) && is_sentence(&second) {
+        if !ends_sentence(&first) {
+            first = format!("{}.", first.trim_end_matches([',', ';', ':']));
+        }
+        let opening = second.len() - second.trim_start_matches(|c: char| !c.is_alphanumeric()).len();
+        second = format!("{}{}", &second[..opening], rewrite::capitalize(&second[opening..]));
+    }
+    format!("{}{} {}{}", leading, first, second, trailing)
+}
+
+/// The built-in rules behind `ConflictPolicy::Auto`.
+fn auto_resolution(conflict: &CollaborationConflict) -> Option<String> {
+    match conflict.conflict_type.as_str() {
+        "text_insertion" => Some(merge_insertions(&conflict.user_a_change, &conflict.user_b_change)),
+        // Keep the shorter deletion (less destructive)
+        "text_deletion" if conflict.user_a_change.len() < conflict.user_b_change.len() => Some(conflict.user_a_change.clone()),
+        "text_deletion" => Some(conflict.user_b_change.clone()),
+        "text_modification" => Some(conflict.user_b_change.clone()),
+        _ => None,
+    }
+}
+
+fn latest(conflict: &CollaborationConflict) -> Option<String> {
+    let a = parse_rfc3339(conflict.user_a_timestamp.as_deref()?)?;
+    let b = parse_rfc3339(conflict.user_b_timestamp.as_deref()?)?;
+    match a.cmp(&b) {
+        std::cmp::Ordering::Greater => Some(conflict.user_a_change.clone()),
+        std::cmp::Ordering::Less => Some(conflict.user_b_change.clone()),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+fn longest(conflict: &CollaborationConflict) -> Option<String> {
+    let (a, b) = (conflict.user_a_change.chars().count(), conflict.user_b_change.chars().count());
+    match a.cmp(&b) {
+        std::cmp::Ordering::Greater => Some(conflict.user_a_change.clone()),
+        std::cmp::Ordering::Less => Some(conflict.user_b_change.clone()),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+/// The resolution `policy` picks for `conflict`, or `MANUAL_RESOLUTION`
+/// when it can't decide: a tie, a missing or malformed timestamp, or a
+/// conflict type the policy has no rule for.
+pub fn resolve(conflict: &CollaborationConflict, policy: ConflictPolicy) -> String {
+    let resolution = match policy {
+        ConflictPolicy::Auto => auto_resolution(conflict),
+        ConflictPolicy::PreferA => Some(conflict.user_a_change.clone()),
+        ConflictPolicy::PreferB => Some(conflict.user_b_change.clone()),
+        ConflictPolicy::PreferLatest => latest(conflict),
+        ConflictPolicy::PreferLongest => longest(conflict),
+        ConflictPolicy::MergeBoth => match conflict.conflict_type.as_str() {
+            "text_insertion" => Some(merge_insertions(&conflict.user_a_change, &conflict.user_b_change)),
+            _ => None,
+        },
+        ConflictPolicy::Manual => None,
+    };
+    resolution.unwrap_or_else(|| MANUAL_RESOLUTION.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    fn conflict(conflict_type: &str, a: &str, b: &str, a_time: Option<&str>, b_time: Option<&str>) -> CollaborationConflict {
+        serde_json::from_value(serde_json::json!({
+            "conflict_id": "c1",
+            "conflict_type": conflict_type,
+            "start_pos": 0,
+            "end_pos": 4,
+            "user_a_change": a,
+            "user_b_change": b,
+            "timestamp": "2024-01-01T00:00:00Z",
+            "user_a_timestamp": a_time,
+            "user_b_timestamp": b_time,
+        }))
+        .unwrap()
+    }
+
+    const MANUAL: &str = MANUAL_RESOLUTION;
+
+    #[test]
+    fn every_policy_on_the_same_conflicts() {
+        let conflicts = [
+            // A is later despite the earlier wall-clock reading
+            conflict("text_modification", "grey", "gray sky", Some("2024-05-01T12:30:00+02:00"), Some("2024-05-01T10:15:00Z")),
+            conflict("text_insertion", "Then", "Later", Some("2024-05-01T10:00:00Z"), Some("2024-05-01T10:00:00.5Z")),
+            conflict("text_deletion", "", "the", Some("not a time"), Some("2024-05-01T10:00:00Z")),
+            conflict("text_move", "abc", "xyz", None, None),
+        ];
+        let expected: [(&str, [&str; 4]); 7] = [
+            ("auto", ["gray sky", "Then. Later", "", MANUAL]),
+            ("prefer_a", ["grey", "Then", "", "abc"]),
+            ("prefer_b", ["gray sky", "Later", "the", "xyz"]),
+            ("prefer_latest", ["grey", "Later", MANUAL, MANUAL]),
+            ("prefer_longest", ["gray sky", "Later", "the", MANUAL]),
+            ("merge_both", [MANUAL, "Then. Later", MANUAL, MANUAL]),
+            ("manual", [MANUAL; 4]),
+        ];
+        for (name, resolutions) in expected {
+            let policy = ConflictPolicy::parse(name).unwrap();
+            let actual: Vec<String> = conflicts.iter().map(|c| resolve(c, policy)).collect();
+            assert_eq!(actual, resolutions, "{}", name);
+        }
+        assert_eq!(ConflictPolicy::ALL.len(), expected.len());
+        assert!(ConflictPolicy::parse("user_a") == Ok(ConflictPolicy::PreferA) && ConflictPolicy::parse("user_b") == Ok(ConflictPolicy::PreferB));
+        assert_eq!(
+            ConflictPolicy::parse("newest").err().unwrap(),
+            "unknown conflict policy \"newest\" (expected one of auto, prefer_a, prefer_b, prefer_latest, prefer_longest, merge_both, manual)"
+        );
+    }
+
+    #[test]
+    fn identical_insertions_merge_once() {
+        assert_eq!(resolve(&conflict("text_insertion", "Then", "Then", None, None), ConflictPolicy::MergeBoth), "Then");
+    }
+
+    #[test]
+    fn rfc3339_timestamps_compare_as_instants() {
+        let epoch = |t: &str| parse_rfc3339(t);
+        assert_eq!(epoch("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(epoch("1970-01-01T01:00:00+01:00"), Some(0));
+        assert_eq!(epoch("1969-12-31T23:59:59.5-00:00"), Some(-500_000_000));
+        assert_eq!(epoch("2000-03-01t00:00:00z"), Some(951_868_800 * 1_000_000_000));
+        assert_eq!(epoch("2024-02-29 23:59:60.123456789123Z"), Some(1_709_251_200 * 1_000_000_000 + 123_456_789));
+        for malformed in [
+            "",
+            "2024-02-30T00:00:00Z",
+            "2023-02-29T00:00:00Z",
+            "2024-13-01T00:00:00Z",
+            "2024-01-01T24:00:00Z",
+            "2024-01-01T00:00:00",
+            "2024-01-01T00:00:00.Z",
+            "2024-01-01T00:00:00+0100",
+            "2024-01-01T00:00:00+24:00",
+            "2024-01-01T00:00:00Z trailing",
+            "2024-1-01T00:00:00Z",
+            "\u{661}\u{669}\u{669}\u{669}-01-01T00:00:00Z",
+        ] {
+            assert_eq!(epoch(malformed), None, "{:?}", malformed);
+        }
+    }
+
+    fn at(id: &str, start_pos: usize, end_pos: usize, a_time: &str) -> CollaborationConflict {
+        CollaborationConflict {
+            conflict_id: id.to_string(),
+            start_pos,
+            end_pos,
+            user_a_change: format!("{}-a", id),
+            user_b_change: format!("{}-b", id),
+            user_a_timestamp: Some(a_time.to_string()),
+            ..conflict("text_modification", "", "", None, None)
+        }
+    }
+
+    #[test]
+    fn a_conflict_overlapping_both_neighbours_merges_all_three() {
+        let processor = crate::TextProcessor::new();
+        let conflicts = vec![
+            at("last", 20, 30, "2024-05-01T09:00:00Z"),
+            at("middle", 8, 22, "2024-05-01T11:00:00+02:00"),
+            at("first", 0, 10, "2024-05-01T09:30:00Z"),
+            at("apart", 40, 45, "2024-05-01T08:00:00Z"),
+            // Touches "apart" without overlapping it
+            at("after", 45, 50, "2024-05-01T08:00:00Z"),
+        ];
+        let resolved = processor.auto_resolve_conflicts(conflicts, ConflictPolicy::Auto);
+        let summary: Vec<(&str, &str, usize, usize)> =
+            resolved.iter().map(|c| (c.conflict_id.as_str(), c.conflict_type.as_str(), c.start_pos, c.end_pos)).collect();
+        assert_eq!(
+            summary,
+            [("first+middle+last", COMPOSITE_CONFLICT, 0, 30), ("apart", "text_modification", 40, 45), ("after", "text_modification", 45, 50)]
+        );
+        let composite = &resolved[0];
+        assert_eq!((composite.user_a_change.as_str(), composite.user_b_change.as_str()), ("first-a middle-a last-a", "first-b middle-b last-b"));
+        assert_eq!(composite.user_a_timestamp.as_deref(), Some("2024-05-01T09:30:00Z"));
+        assert_eq!(composite.resolution_suggestion, MANUAL);
+        assert_eq!(resolve(composite, ConflictPolicy::PreferA), "first-a middle-a last-a");
+    }
+
+    #[test]
+    fn zero_width_conflicts_at_one_point_stay_separate() {
+        let merged = merge_overlapping(vec![at("y", 5, 5, ""), at("x", 5, 5, ""), at("z", 0, 5, "")]);
+        let ids: Vec<&str> = merged.iter().map(|c| c.conflict_id.as_str()).collect();
+        assert_eq!(ids.len(), 3);
+        assert!(merged.windows(2).all(|w| w[0].start_pos <= w[1].start_pos));
+        assert!(merge_overlapping(Vec::new()).is_empty());
+    }
+
+    fn positioned(id: &str, conflict_type: &str, start_pos: usize, end_pos: usize, a: &str, b: &str) -> CollaborationConflict {
+        CollaborationConflict {
+            conflict_id: id.to_string(),
+            conflict_type: conflict_type.to_string(),
+            start_pos,
+            end_pos,
+            user_a_change: a.to_string(),
+            user_b_change: b.to_string(),
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            user_a_timestamp: None,
+            user_b_timestamp: None,
+            resolution_suggestion: String::new(),
+            expected_text: None,
+            start_utf16: None,
+            end_utf16: None,
+            line: None,
+            column: None,
+        }
+    }
+
+    #[test]
+    fn auto_resolve_conflicts_applies_type_rules() {
+        let processor = TextProcessor::new();
+        let resolved = processor.auto_resolve_conflicts(
+            vec![
+                positioned("m", "text_modification", 20, 25, "quick", "swift"),
+                positioned("i", "text_insertion", 4, 4, "very", "rather"),
+                positioned("d", "text_deletion", 10, 15, "abc", "a"),
+            ],
+            ConflictPolicy::Auto,
+        );
+        let ids: Vec<&str> = resolved.iter().map(|c| c.conflict_id.as_str()).collect();
+        assert_eq!(ids, ["i", "d", "m"]);
+        assert_eq!(resolved[0].resolution_suggestion, "very rather");
+        assert_eq!(resolved[1].resolution_suggestion, "a");
+        assert_eq!(resolved[2].resolution_suggestion, "swift");
+    }
+
+    #[test]
+    fn auto_resolve_conflicts_keeps_the_longer_of_near_identical_insertions() {
+        let processor = TextProcessor::new();
+        let resolved = processor.auto_resolve_conflicts(
+            vec![positioned("i", "text_insertion", 0, 0, "The storm broke at dawn over the harbor.", "The storm broke at dawn over the harbor, loudly.")],
+            ConflictPolicy::Auto,
+        );
+        assert_eq!(resolved[0].resolution_suggestion, "The storm broke at dawn over the harbor, loudly.");
+        let same = processor.auto_resolve_conflicts(vec![positioned("i", "text_insertion", 0, 0, "rain", "Rain")], ConflictPolicy::MergeBoth);
+        assert_eq!(same[0].resolution_suggestion, "rain");
+    }
+
+    #[test]
+    fn auto_resolve_conflicts_joins_complementary_insertions_as_sentences() {
+        let processor = TextProcessor::new();
+        let resolved = processor.auto_resolve_conflicts(
+            vec![
+                positioned("s", "text_insertion", 0, 0, " The storm broke at dawn  ", "  the boats stayed in.\n"),
+                positioned("p", "text_insertion", 50, 50, "It rained!", ". Then it cleared."),
+            ],
+            ConflictPolicy::MergeBoth,
+        );
+        assert_eq!(resolved[0].resolution_suggestion, " The storm broke at dawn. The boats stayed in.\n");
+        assert_eq!(resolved[1].resolution_suggestion, "It rained! Then it cleared.");
+    }
+
+    #[test]
+    fn auto_resolve_conflicts_keeps_the_only_nonempty_insertion() {
+        let processor = TextProcessor::new();
+        let resolved = processor.auto_resolve_conflicts(
+            vec![positioned("a", "text_insertion", 0, 0, "", " quietly"), positioned("b", "text_insertion", 9, 9, "Softly. ", "  ")],
+            ConflictPolicy::Auto,
+        );
+        assert_eq!(resolved[0].resolution_suggestion, " quietly");
+        assert_eq!(resolved[1].resolution_suggestion, "Softly. ");
+    }
+
+    #[test]
+    fn auto_resolve_conflicts_merges_overlapping_spans() {
+        let processor = TextProcessor::new();
+        let resolved = processor.auto_resolve_conflicts(
+            vec![
+                positioned("b", "text_modification", 5, 12, "x", "y"),
+                positioned("a", "text_modification", 0, 8, "p", "q"),
+            ],
+            ConflictPolicy::PreferA,
+        );
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].conflict_id, "a+b");
+        assert_eq!(resolved[0].conflict_type, COMPOSITE_CONFLICT);
+        assert_eq!((resolved[0].start_pos, resolved[0].end_pos), (0, 12));
+        assert_eq!(resolved[0].resolution_suggestion, "p x");
+    }
+}
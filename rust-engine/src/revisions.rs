@@ -0,0 +1,328 @@
+//! Version-history diffs: the hunks between two snapshots of a document, a
+//! summary for history badges, and writing-session statistics.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::diff::{Segment, TextDiff};
+use crate::segmentation::DocumentIndex;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HunkOp {
+    Equal,
+    Delete,
+    Insert,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct TextRange {
+    pub start_pos: usize,
+    pub end_pos: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Hunk {
+    pub op: HunkOp,
+    /// Byte range in the old text; empty for an insertion.
+    pub old_range: TextRange,
+    /// Byte range in the new text; empty for a deletion.
+    pub new_range: TextRange,
+    /// The deleted text for a deletion, otherwise the new text of the range.
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct DiffSummary {
+    pub words_added: usize,
+    pub words_removed: usize,
+    /// Paragraphs of the new text with an insertion or deletion in them,
+    /// plus paragraphs of the old text that were deleted outright.
+    pub paragraphs_touched: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TextDiffReport {
+    /// In text order, covering both texts end to end. A replacement is a
+    /// deletion followed by an insertion.
+    pub hunks: Vec<Hunk>,
+    pub summary: DiffSummary,
+}
+
+fn hunk(op: HunkOp, old: (usize, usize), new: (usize, usize), text: &str) -> Hunk {
+    Hunk {
+        op,
+        old_range: TextRange { start_pos: old.0, end_pos: old.1 },
+        new_range: TextRange { start_pos: new.0, end_pos: new.1 },
+        text: text.to_string(),
+    }
+}
+
+/// Word-level diff of `old` to `new`, built on the line-then-token matching
+/// of `TextDiff`, so it stays near-linear on long manuscripts with few edits.
+pub fn diff_texts(old: &DocumentIndex, new: &DocumentIndex) -> TextDiffReport {
+    let (old_text, new_text) = (old.text, new.text);
+    let diff = TextDiff::compute(old_text, new_text);
+
+    let mut hunks = Vec::new();
+    let mut cursor = (0, 0);
+    // A trailing empty segment closes the gap after the last equal run
+    let end = Segment { old_start: old_text.len(), new_start: new_text.len(), len: 0 };
+    for segment in diff.segments.iter().chain(std::iter::once(&end)) {
+        if cursor.0 < segment.old_start {
+            let range = (cursor.0, segment.old_start);
+            hunks.push(hunk(HunkOp::Delete, range, (cursor.1, cursor.1), &old_text[range.0..range.1]));
+        }
+        if cursor.1 < segment.new_start {
+            let range = (cursor.1, segment.new_start);
+            hunks.push(hunk(HunkOp::Insert, (segment.old_start, segment.old_start), range, &new_text[range.0..range.1]));
+        }
+        if segment.len > 0 {
+            let old_range = (segment.old_start, segment.old_start + segment.len);
+            let new_range = (segment.new_start, segment.new_start + segment.len);
+            hunks.push(hunk(HunkOp::Equal, old_range, new_range, &new_text[new_range.0..new_range.1]));
+        }
+        cursor = (segment.old_start + segment.len, segment.new_start + segment.len);
+    }
+
+    let summary = summarize(old, new, &diff, &hunks);
+    TextDiffReport { hunks, summary }
+}
+
+/// Words whose span overlaps `range`, so a word edited in place counts once
+/// on each side.
+fn words_overlapping(index: &DocumentIndex, range: TextRange) -> usize {
+    index.words.iter().filter(|w| w.0 < range.end_pos && range.start_pos < w.1).count()
+}
+
+fn summarize(old: &DocumentIndex, new: &DocumentIndex, diff: &TextDiff, hunks: &[Hunk]) -> DiffSummary {
+    let changed: Vec<&Hunk> = hunks.iter().filter(|h| h.op != HunkOp::Equal).collect();
+    let words_added = changed.iter().filter(|h| h.op == HunkOp::Insert).map(|h| words_overlapping(new, h.new_range)).sum();
+    let words_removed = changed.iter().filter(|h| h.op == HunkOp::Delete).map(|h| words_overlapping(old, h.old_range)).sum();
+
+    // A deletion touches the paragraph its removal point falls in
+    let touched_new = new
+        .paragraphs
+        .iter()
+        .filter(|&&(start, end)| {
+            changed.iter().any(|h| match h.op {
+                HunkOp::Insert => h.new_range.start_pos < end && start < h.new_range.end_pos,
+                _ => start <= h.new_range.start_pos && h.new_range.start_pos <= end,
+            })
+        })
+        .count();
+    let removed_old = old.paragraphs.iter().filter(|&&(start, end)| diff.map_span(start, end).is_empty()).count();
+    DiffSummary { words_added, words_removed, paragraphs_touched: touched_new + removed_old }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct SessionStats {
+    pub words_added: usize,
+    pub words_deleted: usize,
+    /// Words in the current text minus words in the previous one.
+    pub net_change: i64,
+    /// Characters inserted, as an estimate of typing; pasted text counts.
+    pub characters_typed: usize,
+    pub paragraphs_touched: usize,
+    /// Unedited paragraphs that changed place; they add nothing to the word
+    /// counts.
+    pub paragraphs_moved: usize,
+    pub previous_hash: String,
+    pub current_hash: String,
+}
+
+/// Paragraphs found unchanged in both texts, as `(old, new)` paragraph
+/// indexes. Identical paragraphs pair up in text order.
+fn unchanged_paragraphs(old: &DocumentIndex, new: &DocumentIndex) -> Vec<(usize, usize)> {
+    let mut by_text: HashMap<&str, VecDeque<usize>> = HashMap::new();
+    for (i, &(start, end)) in old.paragraphs.iter().enumerate() {
+        by_text.entry(&old.text[start..end]).or_default().push_back(i);
+    }
+    new.paragraphs
+        .iter()
+        .enumerate()
+        .filter_map(|(j, &(start, end))| Some((by_text.get_mut(&new.text[start..end])?.pop_front()?, j)))
+        .collect()
+}
+
+/// Length of the longest increasing run of `values`, not necessarily
+/// contiguous.
+fn longest_increasing(values: &[usize]) -> usize {
+    let mut tails: Vec<usize> = Vec::new();
+    for &value in values {
+        let at = tails.partition_point(|&t| t < value);
+        match tails.get_mut(at) {
+            Some(tail) => *tail = value,
+            None => tails.push(value),
+        }
+    }
+    tails.len()
+}
+
+/// The paragraphs of `index` not in `kept`, one per blank-line-separated
+/// block.
+fn remaining_paragraphs(index: &DocumentIndex, kept: &HashSet<usize>) -> String {
+    let parts: Vec<&str> =
+        index.paragraphs.iter().enumerate().filter(|(i, _)| !kept.contains(i)).map(|(_, &(s, e))| &index.text[s..e]).collect();
+    parts.join("\n\n")
+}
+
+/// Word-level session statistics from `old` to `new`. Paragraphs present
+/// unchanged in both are set aside before diffing, so moving a paragraph
+/// doesn't count as deleting and retyping it; `index` segments the rest.
+/// The hashes are left for the caller.
+pub fn session_stats(
+    old: &DocumentIndex,
+    new: &DocumentIndex,
+    index: impl for<'t> Fn(&'t str) -> DocumentIndex<'t>,
+) -> SessionStats {
+    let unchanged = unchanged_paragraphs(old, new);
+    let old_rest = remaining_paragraphs(old, &unchanged.iter().map(|p| p.0).collect());
+    let new_rest = remaining_paragraphs(new, &unchanged.iter().map(|p| p.1).collect());
+    let report = diff_texts(&index(&old_rest), &index(&new_rest));
+
+    // Pairs come in new-text order; those off the longest in-order run moved
+    let old_order: Vec<usize> = unchanged.iter().map(|p| p.0).collect();
+    SessionStats {
+        words_added: report.summary.words_added,
+        words_deleted: report.summary.words_removed,
+        net_change: new.words.len() as i64 - old.words.len() as i64,
+        characters_typed: report.hunks.iter().filter(|h| h.op == HunkOp::Insert).map(|h| h.text.chars().count()).sum(),
+        paragraphs_touched: report.summary.paragraphs_touched,
+        paragraphs_moved: unchanged.len() - longest_increasing(&old_order),
+        ..SessionStats::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+    use crate::test_support::Cases;
+
+    fn diff(old: &str, new: &str) -> TextDiffReport {
+        let processor = TextProcessor::new();
+        diff_texts(&processor.index(old), &processor.index(new))
+    }
+
+    fn changes(report: &TextDiffReport) -> Vec<(&'static str, &str)> {
+        let name = |op: HunkOp| match op {
+            HunkOp::Equal => "equal",
+            HunkOp::Delete => "delete",
+            HunkOp::Insert => "insert",
+        };
+        report.hunks.iter().filter(|h| h.op != HunkOp::Equal).map(|h| (name(h.op), h.text.as_str())).collect()
+    }
+
+    /// The old and new texts rebuilt from the hunks, checking that the
+    /// ranges tile both texts.
+    fn rebuild(old: &str, new: &str, report: &TextDiffReport) -> (String, String) {
+        let (mut rebuilt_old, mut rebuilt_new) = (String::new(), String::new());
+        for hunk in &report.hunks {
+            assert_eq!(hunk.old_range.start_pos, rebuilt_old.len());
+            assert_eq!(hunk.new_range.start_pos, rebuilt_new.len());
+            rebuilt_old.push_str(&old[hunk.old_range.start_pos..hunk.old_range.end_pos]);
+            rebuilt_new.push_str(&new[hunk.new_range.start_pos..hunk.new_range.end_pos]);
+            let side = if hunk.op == HunkOp::Delete { &old[hunk.old_range.start_pos..hunk.old_range.end_pos] } else { &new[hunk.new_range.start_pos..hunk.new_range.end_pos] };
+            assert_eq!(hunk.text, side);
+        }
+        (rebuilt_old, rebuilt_new)
+    }
+
+    #[test]
+    fn a_replaced_word_is_a_deletion_then_an_insertion() {
+        let (old, new) = ("Mara walked home.\n\nThe rain fell.", "Mara ran home.\n\nThe rain fell.");
+        let report = diff(old, new);
+        assert_eq!(changes(&report), [("delete", "walked"), ("insert", "ran")]);
+        assert_eq!(rebuild(old, new, &report), (old.to_string(), new.to_string()));
+        let summary = &report.summary;
+        assert_eq!((summary.words_added, summary.words_removed, summary.paragraphs_touched), (1, 1, 1));
+    }
+
+    #[test]
+    fn either_side_may_be_empty() {
+        let text = "Mara walked home.\n\nThe rain fell.";
+        let added = diff("", text);
+        assert_eq!((added.hunks.len(), changes(&added)), (1, vec![("insert", text)]));
+        assert_eq!((added.summary.words_added, added.summary.words_removed, added.summary.paragraphs_touched), (6, 0, 2));
+        let removed = diff(text, "");
+        assert_eq!((removed.hunks.len(), changes(&removed)), (1, vec![("delete", text)]));
+        assert_eq!((removed.summary.words_added, removed.summary.words_removed, removed.summary.paragraphs_touched), (0, 6, 2));
+        assert!(diff("", "").hunks.is_empty());
+        let same = diff(text, text);
+        assert_eq!((same.hunks.len(), changes(&same)), (1, vec![]));
+        assert_eq!((same.summary.words_added, same.summary.words_removed, same.summary.paragraphs_touched), (0, 0, 0));
+    }
+
+    #[test]
+    fn hunks_rebuild_both_texts_after_random_edits() {
+        const WORDS: &[&str] = &["Mara", "walked", "home", "the", "rain", "fell", "caf\u{e9}", "\u{201c}no\u{201d}", "\n\n", ".", ","];
+        let processor = TextProcessor::new();
+        let mut cases = Cases(0x2545_f491_4f6c_dd1d);
+        for _ in 0..300 {
+            let old: Vec<&str> = (0..cases.below(40)).map(|_| WORDS[cases.below(WORDS.len())]).collect();
+            let mut new = old.clone();
+            for _ in 0..cases.below(5) {
+                let at = cases.below(new.len() + 1);
+                match cases.below(3) {
+                    0 => new.insert(at, WORDS[cases.below(WORDS.len())]),
+                    1 if at < new.len() => {
+                        new.remove(at);
+                    }
+                    _ if at < new.len() => new[at] = WORDS[cases.below(WORDS.len())],
+                    _ => {}
+                }
+            }
+            let (old, new) = (old.join(" "), new.join(" "));
+            let report = diff_texts(&processor.index(&old), &processor.index(&new));
+            assert_eq!(rebuild(&old, &new, &report), (old.clone(), new.clone()), "{:?} -> {:?}", old, new);
+        }
+    }
+
+    #[test]
+    fn long_manuscripts_with_few_edits_diff_quickly() {
+        let old = "The old mill stood by the river in the valley. ".repeat(10_000);
+        let new = format!("{}Nobody came. {}", &old[..old.len() / 2], &old[old.len() / 2..]);
+        let started = std::time::Instant::now();
+        let report = diff(&old, &new);
+        assert!(started.elapsed().as_secs() < 10);
+        assert_eq!(report.summary.words_removed, 0);
+        assert_eq!(report.summary.words_added, 2);
+    }
+
+    #[test]
+    fn session_stats_counts_pure_addition() {
+        let stats = TextProcessor::new().session_stats("The cat sat.\n\nIt rained.", "The cat sat on the mat.\n\nIt rained.");
+        assert_eq!((stats.words_added, stats.words_deleted, stats.net_change), (3, 0, 3));
+        assert_eq!(stats.characters_typed, " on the mat".len());
+        assert_eq!((stats.paragraphs_touched, stats.paragraphs_moved), (1, 0));
+        assert_eq!(stats.current_hash, TextProcessor::new().generate_content_hash("The cat sat on the mat.\n\nIt rained."));
+    }
+
+    #[test]
+    fn session_stats_counts_pure_deletion() {
+        let stats = TextProcessor::new().session_stats("One two three four.\n\nKeep this.", "One four.\n\nKeep this.");
+        assert_eq!((stats.words_added, stats.words_deleted, stats.net_change), (0, 2, -2));
+        assert_eq!(stats.characters_typed, 0);
+        assert_eq!(stats.paragraphs_touched, 1);
+    }
+
+    #[test]
+    fn session_stats_sees_a_moved_paragraph() {
+        let previous = "First paragraph here.\n\nSecond one follows.\n\nThird closes it out.";
+        let current = "Third closes it out.\n\nFirst paragraph here.\n\nSecond one follows.";
+        let stats = TextProcessor::new().session_stats(previous, current);
+        assert_eq!((stats.words_added, stats.words_deleted, stats.net_change), (0, 0, 0));
+        assert_eq!((stats.paragraphs_moved, stats.paragraphs_touched, stats.characters_typed), (1, 0, 0));
+        assert_ne!(stats.previous_hash, stats.current_hash);
+    }
+
+    #[test]
+    fn session_stats_counts_paste_over_selection() {
+        let stats = TextProcessor::new().session_stats("The quick brown fox jumps.", "The slow red fox jumps.");
+        assert_eq!((stats.words_added, stats.words_deleted, stats.net_change), (2, 2, 0));
+        // The space between the new words matches the old one
+        assert_eq!(stats.characters_typed, "slowred".len());
+        assert_eq!(stats.paragraphs_touched, 1);
+    }
+}
@@ -0,0 +1,230 @@
+//! Conservative rewrites offered as `suggested_replacement`: simple passive
+//! clauses turned active, and adverb+verb pairs replaced by a stronger verb.
+//! Each returns the exact span it replaces, or nothing when the pattern is
+//! not simple enough to rewrite safely.
+
+use crate::passive;
+
+/// Longest subject or agent phrase a passive rewrite will move.
+const MAX_PHRASE_WORDS: usize = 4;
+
+const DETERMINERS: &[&str] = &[
+    "the", "a", "an", "this", "that", "these", "those", "my", "your", "his", "her", "its", "our", "their", "some",
+    "every", "each",
+];
+
+/// Subject and object forms of the personal pronouns.
+const PRONOUN_CASES: &[(&str, &str)] =
+    &[("i", "me"), ("he", "him"), ("she", "her"), ("we", "us"), ("they", "them"), ("it", "it"), ("you", "you")];
+
+/// `(verb, adverb, stronger verb)`, all lowercase, in matching tenses.
+const STRONGER_VERBS: &[(&str, &str, &str)] = &[
+    ("ran", "quickly", "sprinted"),
+    ("run", "quickly", "sprint"),
+    ("runs", "quickly", "sprints"),
+    ("running", "quickly", "sprinting"),
+    ("ran", "fast", "sprinted"),
+    ("said", "loudly", "shouted"),
+    ("said", "quietly", "whispered"),
+    ("said", "softly", "murmured"),
+    ("said", "angrily", "snapped"),
+    ("says", "loudly", "shouts"),
+    ("says", "quietly", "whispers"),
+    ("spoke", "quietly", "murmured"),
+    ("spoke", "softly", "murmured"),
+    ("walked", "slowly", "trudged"),
+    ("walked", "quietly", "crept"),
+    ("walked", "proudly", "strode"),
+    ("walks", "slowly", "trudges"),
+    ("went", "quickly", "hurried"),
+    ("moved", "quickly", "darted"),
+    ("ate", "quickly", "devoured"),
+    ("ate", "greedily", "gobbled"),
+    ("looked", "quickly", "glanced"),
+    ("looked", "closely", "scrutinized"),
+    ("looked", "angrily", "glared"),
+    ("looked", "intently", "stared"),
+    ("closed", "loudly", "slammed"),
+    ("shut", "loudly", "slammed"),
+    ("cried", "loudly", "wailed"),
+    ("laughed", "quietly", "chuckled"),
+    ("laughed", "loudly", "guffawed"),
+    ("held", "tightly", "clutched"),
+    ("drank", "quickly", "gulped"),
+    ("breathed", "heavily", "panted"),
+    ("knocked", "loudly", "pounded"),
+];
+
+/// A pronoun, a name ("John", "Mary Ann"), or a determiner followed by up
+/// to three words: the noun phrases a rewrite can move without re-parsing.
+fn is_phrase(phrase: &str) -> bool {
+    let words: Vec<&str> = phrase.split(' ').collect();
+    if words.len() > MAX_PHRASE_WORDS
+        || !words.iter().all(|w| !w.is_empty() && w.chars().all(|c| c.is_alphabetic() || c == '\'' || c == '-'))
+    {
+        return false;
+    }
+    let lower = phrase.to_lowercase();
+    PRONOUN_CASES.iter().any(|(subject, object)| *subject == lower || *object == lower)
+        || words.iter().all(|w| w.starts_with(char::is_uppercase)) && !DETERMINERS.contains(&words[0].to_lowercase().as_str())
+        || words.len() > 1 && DETERMINERS.contains(&words[0].to_lowercase().as_str())
+}
+
+pub fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// A moved subject becomes an object: pronouns change case and a leading
+/// determiner is lowercased. Other words (names) keep their capitals.
+fn as_object(phrase: &str) -> String {
+    let lower = phrase.to_lowercase();
+    if let Some((_, object)) = PRONOUN_CASES.iter().find(|(subject, _)| *subject == lower) {
+        return object.to_string();
+    }
+    let (first, rest) = phrase.split_once(' ').unwrap_or((phrase, ""));
+    if DETERMINERS.contains(&first.to_lowercase().as_str()) {
+        let first = first.to_lowercase();
+        return if rest.is_empty() { first } else { format!("{} {}", first, rest) };
+    }
+    phrase.to_string()
+}
+
+fn as_subject(phrase: &str) -> String {
+    let lower = phrase.to_lowercase();
+    if let Some((subject, _)) = PRONOUN_CASES.iter().find(|(_, object)| *object == lower) {
+        return if *subject == "i" { "I".to_string() } else { capitalize(subject) };
+    }
+    capitalize(phrase)
+}
+
+/// "The ball was kicked by the boy" -> "The boy kicked the ball", for a
+/// passive match `start..end` ("was kicked") inside `sentence`. Only fires
+/// when a short subject opens the sentence and a short "by" agent closes
+/// the clause; the returned span runs from the subject to the agent's end.
+pub fn passive_to_active(text: &str, sentence: (usize, usize), start: usize, end: usize) -> Option<(usize, usize, String)> {
+    let matched = &text[start..end];
+    let (auxiliary, participle) = matched.split_once(char::is_whitespace)?;
+    if !matches!(auxiliary.to_lowercase().as_str(), "was" | "were") {
+        return None;
+    }
+    // A lone participle; "was quickly taken" keeps its adverb where it is
+    let participle = participle.trim_start();
+    if participle.contains(char::is_whitespace) {
+        return None;
+    }
+    let past = passive::past_tense(participle)?;
+
+    let subject = text[sentence.0..start].strip_suffix(' ')?;
+    let agent_text = text[end..sentence.1].strip_prefix(" by ")?;
+    let agent = &agent_text[..agent_text.find(['.', '!', '?', ',', ';']).unwrap_or(agent_text.len())];
+    if !is_phrase(subject) || !is_phrase(agent) {
+        return None;
+    }
+
+    let replacement = format!("{} {} {}", as_subject(agent), past, as_object(subject));
+    Some((sentence.0, end + " by ".len() + agent.len(), replacement))
+}
+
+/// `replacement` written with the capitalization of `original`.
+pub fn match_case(original: &str, replacement: &str) -> String {
+    let letters: Vec<char> = original.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.len() > 1 && letters.iter().all(|c| c.is_uppercase()) {
+        replacement.to_uppercase()
+    } else if original.starts_with(char::is_uppercase) {
+        capitalize(replacement)
+    } else {
+        replacement.to_string()
+    }
+}
+
+/// "ran quickly" or "quickly ran" -> "sprinted", for an adverb match
+/// `start..end`. The returned span covers the verb, the adverb, and the
+/// single space between them.
+pub fn stronger_verb(text: &str, start: usize, end: usize) -> Option<(usize, usize, String)> {
+    let adverb = text[start..end].to_lowercase();
+    let verb_before = text[..start].strip_suffix(' ').map(|before| {
+        let verb_start = before.char_indices().rfind(|(_, c)| !c.is_alphabetic()).map_or(0, |(i, c)| i + c.len_utf8());
+        (verb_start, end, &before[verb_start..])
+    });
+    let verb_after = text[end..].strip_prefix(' ').map(|after| {
+        let verb_len = after.find(|c: char| !c.is_alphabetic()).unwrap_or(after.len());
+        (start, end + 1 + verb_len, &after[..verb_len])
+    });
+
+    [verb_before, verb_after].into_iter().flatten().find_map(|(span_start, span_end, verb)| {
+        let verb = verb.to_lowercase();
+        let (_, _, stronger) = STRONGER_VERBS.iter().find(|(v, a, _)| *v == verb && *a == adverb)?;
+        Some((span_start, span_end, match_case(&text[span_start..span_end], stronger)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SuggestionConfig;
+    use crate::TextProcessor;
+
+    /// `text` with every suggested replacement spliced in.
+    fn applied(text: &str, rule: &str) -> String {
+        let processor = TextProcessor::with_patterns(&Default::default(), SuggestionConfig::default());
+        let mut rewrites: Vec<(usize, usize, String)> = processor
+            .build_optimization_report(text)
+            .suggestions
+            .into_iter()
+            .filter(|s| s.suggestion_type == rule && s.scope == "instance")
+            .filter_map(|s| s.suggested_replacement.map(|r| (s.start_pos, s.end_pos, r)))
+            .collect();
+        rewrites.sort_by_key(|r| std::cmp::Reverse(r.0));
+        let mut result = text.to_string();
+        for (start, end, replacement) in rewrites {
+            result.replace_range(start..end, &replacement);
+        }
+        result
+    }
+
+    #[test]
+    fn simple_passive_clauses_are_spliced_back_as_active_voice() {
+        assert_eq!(applied("The ball was kicked by the boy.", "passive_voice"), "The boy kicked the ball.");
+        assert_eq!(applied("Mara waited. She was helped by them.", "passive_voice"), "Mara waited. They helped her.");
+        assert_eq!(applied("She was helped by them at noon.", "passive_voice"), "She was helped by them at noon.");
+        assert_eq!(applied("The letters were opened by Mary Ann!", "passive_voice"), "Mary Ann opened the letters!");
+        assert_eq!(applied("Then the old report was finished by me, finally.", "passive_voice"), "Then the old report was finished by me, finally.");
+    }
+
+    #[test]
+    fn passive_rewrites_only_fire_on_simple_clauses() {
+        let sentence = |text: &str| (0, text.len());
+        let text = "The ball was kicked by the boy.";
+        assert_eq!(passive_to_active(text, sentence(text), 9, 19), Some((0, 30, "The boy kicked the ball".to_string())));
+        let no_agent = "The ball was kicked.";
+        assert_eq!(passive_to_active(no_agent, sentence(no_agent), 9, 19), None);
+        let long_subject = "The big red rubber ball was kicked by the boy.";
+        assert_eq!(passive_to_active(long_subject, sentence(long_subject), 24, 34), None);
+        let present = "The ball is kicked by the boy.";
+        assert_eq!(passive_to_active(present, sentence(present), 9, 18), None);
+    }
+
+    #[test]
+    fn adverb_pairs_become_stronger_verbs_with_case_preserved() {
+        assert_eq!(applied("She ran quickly home and said loudly that she was back.", "adverb_usage"), "She sprinted home and shouted that she was back.");
+        assert_eq!(applied("Quickly ran the dog.", "adverb_usage"), "Sprinted the dog.");
+        assert_eq!(applied("She sang loudly.", "adverb_usage"), "She sang loudly.");
+        assert_eq!(applied("\u{201c}Ran quickly,\u{201d} she said quietly.", "adverb_usage"), "\u{201c}Sprinted,\u{201d} she whispered.");
+    }
+
+    #[test]
+    fn replacement_spans_cover_exactly_the_replaced_text() {
+        let text = "He walked slowly to the door.";
+        let start = text.find("slowly").unwrap();
+        let (s, e, replacement) = stronger_verb(text, start, start + "slowly".len()).unwrap();
+        assert_eq!((&text[s..e], replacement.as_str()), ("walked slowly", "trudged"));
+        assert_eq!(match_case("Said Loudly", "shouted"), "Shouted");
+        assert_eq!(match_case("SAID LOUDLY", "shouted"), "SHOUTED");
+        assert_eq!(as_object("She"), "her");
+        assert_eq!(as_subject("me"), "I");
+    }
+}
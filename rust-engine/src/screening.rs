@@ -0,0 +1,143 @@
+//! Content screening against bundled category lists (profanity, slurs,
+//! violent language), for a quick pass over submissions before human review.
+//!
+//! Matching is `flagged::find_flagged_terms` in fuzzy mode: leetspeak,
+//! stretched letters, and spelled-out words are normalized first, and only
+//! whole words match, so innocent words containing a listed one
+//! ("Scunthorpe", "assessment", "hello") are never flagged.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::config::Priority;
+use crate::flagged;
+
+/// Bundled categories, their severity, and their words and phrases.
+const BUILTIN: &[(&str, Priority, &[&str])] = &[
+    ("profanity", Priority::Medium, &[
+        "arse", "arsehole", "ass", "asshole", "bastard", "bitch", "bollocks", "bullshit", "cock", "crap", "cunt",
+        "damn", "dick", "dickhead", "fuck", "fucked", "fucker", "fucking", "goddamn", "motherfucker", "piss",
+        "pissed", "prick", "shit", "shitty", "twat", "wanker",
+    ]),
+    ("slurs", Priority::High, &[
+        "fag", "faggot", "gook", "kike", "nigga", "nigger", "raghead", "spic", "tranny", "wetback",
+    ]),
+    ("violence", Priority::Medium, &[
+        "behead", "beheaded", "beheading", "bloodbath", "disembowel", "disemboweled", "dismember", "dismembered",
+        "gut you", "kill you", "massacre", "massacred", "mutilate", "mutilated", "shoot up", "slaughter",
+        "slaughtered", "slit your throat", "strangle", "strangled", "torture", "tortured",
+    ]),
+];
+
+pub const BUILTIN_CATEGORIES: &[&str] = &["profanity", "slurs", "violence"];
+
+/// Severity of categories that only exist as loaded lists.
+const LOADED_CATEGORY_SEVERITY: Priority = Priority::Medium;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScreeningMatch {
+    pub category: String,
+    pub severity: Priority,
+    /// The list entry that matched.
+    pub term: String,
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub matched_text: String,
+    /// Only the obfuscation normalization made it match ("sh1t").
+    pub obfuscated: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ContentScreen {
+    /// By position; a span on the lists of two categories is reported for
+    /// each.
+    pub matches: Vec<ScreeningMatch>,
+    /// Matches per screened category, zero counts included.
+    pub counts: BTreeMap<String, usize>,
+}
+
+/// Bundled and loaded category names, sorted.
+fn categories(lists: &BTreeMap<String, Vec<String>>) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_CATEGORIES.iter().map(|name| name.to_string()).chain(lists.keys().cloned()).collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+/// Screens `text` for `categories` (all of them when `None`). `lists`
+/// replaces the bundled list of a category or adds a category.
+pub fn screen(
+    text: &str,
+    lists: &BTreeMap<String, Vec<String>>,
+    categories: Option<&[String]>,
+) -> Result<ContentScreen, String> {
+    let known = self::categories(lists);
+    let wanted = categories.map_or_else(|| known.clone(), <[String]>::to_vec);
+    if let Some(unknown) = wanted.iter().find(|c| !known.contains(c)) {
+        return Err(format!("Unknown screening category \"{}\"; known categories are {}", unknown, known.join(", ")));
+    }
+
+    let mut matches = Vec::new();
+    let mut counts = BTreeMap::new();
+    for category in wanted {
+        let builtin = BUILTIN.iter().find(|(name, _, _)| *name == category);
+        let severity = builtin.map_or(LOADED_CATEGORY_SEVERITY, |&(_, severity, _)| severity);
+        let terms: Vec<String> = match lists.get(&category) {
+            Some(words) => words.clone(),
+            None => builtin.map_or_else(Vec::new, |(_, _, words)| words.iter().map(|w| w.to_string()).collect()),
+        };
+        let hits = flagged::find_flagged_terms(text, &terms, true);
+        counts.insert(category.clone(), hits.len());
+        matches.extend(hits.into_iter().map(|hit| ScreeningMatch {
+            category: category.clone(),
+            severity,
+            term: hit.term,
+            start_pos: hit.start_pos,
+            end_pos: hit.end_pos,
+            matched_text: hit.matched_text,
+            obfuscated: hit.obfuscated,
+        }));
+    }
+    matches.sort_by(|a, b| a.start_pos.cmp(&b.start_pos).then_with(|| a.category.cmp(&b.category)));
+    Ok(ContentScreen { matches, counts })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    #[test]
+    fn screen_content_sees_through_obfuscation() {
+        let text = "Well, sh1t. That was a fuuuuck-up, a total s.h.i.t show, and $hit happens.";
+        let screen = TextProcessor::new().screen_content(text, None).unwrap();
+        let found: Vec<(&str, bool)> = screen.matches.iter().map(|m| (m.matched_text.as_str(), m.obfuscated)).collect();
+        assert_eq!(found, [("sh1t", true), ("fuuuuck", true), ("s.h.i.t", true), ("$hit", true)]);
+        assert!(screen.matches.iter().all(|m| m.category == "profanity" && m.severity == Priority::Medium));
+        assert_eq!(screen.counts["profanity"], 4);
+        assert_eq!(screen.counts["slurs"], 0);
+        assert_eq!(&text[screen.matches[0].start_pos..screen.matches[0].end_pos], "sh1t");
+    }
+
+    #[test]
+    fn screen_content_ignores_words_containing_listed_ones() {
+        let text = "The Scunthorpe assessment classified the cocktail as passable. Hello, Dickens fans; \
+            the shiitake harassment class met at the assassin's grassy cockpit.";
+        let screen = TextProcessor::new().screen_content(text, None).unwrap();
+        assert!(screen.matches.is_empty(), "{:?}", screen.matches.iter().map(|m| &m.matched_text).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn screening_lists_can_be_replaced_and_added() {
+        let mut processor = TextProcessor::new();
+        processor.load_screening_list("profanity", vec!["heck".to_string()]);
+        processor.load_screening_list("competitors", vec!["acme corp".to_string()]);
+        let text = "What the h3ck, Acme Corp shipped it? Damn.";
+        let screen = processor.screen_content(text, None).unwrap();
+        let found: Vec<(&str, &str)> = screen.matches.iter().map(|m| (m.category.as_str(), m.matched_text.as_str())).collect();
+        assert_eq!(found, [("profanity", "h3ck"), ("competitors", "Acme Corp")]);
+        let only = processor.screen_content(text, Some(&["competitors".to_string()])).unwrap();
+        assert_eq!(only.counts.len(), 1);
+        assert!(processor.screen_content(text, Some(&["spam".to_string()])).is_err());
+    }
+}
@@ -0,0 +1,499 @@
+//! One tokenization pass over a document, shared by every API that needs
+//! word, sentence, or paragraph positions.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::patterns;
+use crate::tokens::{self, TokenKind};
+
+/// How text is split into words.
+pub enum WordSegmenter {
+    /// Unicode word boundaries: contractions ("don't") stay whole, and CJK
+    /// ideographs count one word each.
+    Unicode,
+    /// A pattern-pack override; every match is a word.
+    Pattern(Regex),
+}
+
+impl WordSegmenter {
+    /// Byte spans of the words of `text`.
+    pub fn spans(&self, text: &str) -> Vec<(usize, usize)> {
+        match self {
+            WordSegmenter::Unicode => text.unicode_word_indices().map(|(i, w)| (i, i + w.len())).collect(),
+            WordSegmenter::Pattern(re) => re.find_iter(text).map(|m| (m.start(), m.end())).collect(),
+        }
+    }
+
+    pub fn words<'t>(&self, text: &'t str) -> Vec<&'t str> {
+        match self {
+            WordSegmenter::Unicode => text.unicode_words().collect(),
+            WordSegmenter::Pattern(re) => re.find_iter(text).map(|m| m.as_str()).collect(),
+        }
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        match self {
+            WordSegmenter::Unicode => text.unicode_words().count(),
+            WordSegmenter::Pattern(re) => re.find_iter(text).count(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SentenceSpan {
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub text: String,
+}
+
+pub struct DocumentIndex<'t> {
+    pub text: &'t str,
+    /// Byte spans of words.
+    pub words: Vec<(usize, usize)>,
+    /// Byte spans of sentences, trimmed, including terminal punctuation.
+    pub sentences: Vec<(usize, usize)>,
+    /// Byte spans of paragraphs, trimmed.
+    pub paragraphs: Vec<(usize, usize)>,
+}
+
+/// The vectors a `DocumentIndex` is built in, kept between documents so a
+/// batch grows them once instead of once per document.
+#[derive(Default)]
+pub struct IndexBuffers {
+    words: Vec<(usize, usize)>,
+    terminators: Vec<(usize, usize)>,
+    breaks: Vec<(usize, usize)>,
+    sentences: Vec<(usize, usize)>,
+    paragraphs: Vec<(usize, usize)>,
+}
+
+impl IndexBuffers {
+    /// Takes back the vectors of an index that is no longer needed.
+    pub fn reclaim(&mut self, index: DocumentIndex) {
+        (self.words, self.sentences, self.paragraphs) = (index.words, index.sentences, index.paragraphs);
+    }
+}
+
+impl<'t> DocumentIndex<'t> {
+    /// With the built-in patterns, every span comes from one `tokenize`
+    /// pass; pattern-pack overrides fall back to a regex scan per kind.
+    pub fn build(text: &'t str, words: &WordSegmenter, sentence_re: &Regex, paragraph_re: &Regex) -> DocumentIndex<'t> {
+        DocumentIndex::build_in(text, words, sentence_re, paragraph_re, &mut IndexBuffers::default())
+    }
+
+    /// `build`, filling the vectors of `buffers`.
+    pub fn build_in(
+        text: &'t str,
+        words: &WordSegmenter,
+        sentence_re: &Regex,
+        paragraph_re: &Regex,
+        buffers: &mut IndexBuffers,
+    ) -> DocumentIndex<'t> {
+        let builtin = matches!(words, WordSegmenter::Unicode)
+            && patterns::is_builtin_core("sentence", sentence_re)
+            && patterns::is_builtin_core("paragraph", paragraph_re);
+        if builtin {
+            let mut words = std::mem::take(&mut buffers.words);
+            let (terminators, breaks) = (&mut buffers.terminators, &mut buffers.breaks);
+            words.clear();
+            terminators.clear();
+            breaks.clear();
+            tokens::scan(text, |token| match token.kind {
+                TokenKind::Word => {
+                    words.push((token.start, token.end));
+                    terminators.extend(tokens::inner_periods(text, token));
+                }
+                TokenKind::Terminator => terminators.push((token.start, token.end)),
+                TokenKind::ParagraphBreak => breaks.push((token.start, token.end)),
+                _ => {}
+            });
+            let mut sentences = std::mem::take(&mut buffers.sentences);
+            sentences_into(text, terminators.iter().copied(), &mut sentences);
+            let mut paragraphs = std::mem::take(&mut buffers.paragraphs);
+            paragraphs_into(text, breaks.iter().copied(), &mut paragraphs);
+            return DocumentIndex { text, words, sentences, paragraphs };
+        }
+        let words = words.spans(text);
+        let (sentences, _) = sentence_spans(text, sentence_re);
+        let paragraphs = paragraph_spans(text, paragraph_re);
+        DocumentIndex { text, words, sentences, paragraphs }
+    }
+
+    pub fn word_strs(&self) -> Vec<&'t str> {
+        self.words.iter().map(|&(s, e)| &self.text[s..e]).collect()
+    }
+
+    /// Matches of `re` in `haystack` (the text or a same-length masked copy)
+    /// per sentence. Each sentence is searched on its own, so no match runs
+    /// across a sentence boundary.
+    pub fn matches_by_sentence(&self, re: &Regex, haystack: &str) -> Vec<Vec<(usize, usize)>> {
+        self.sentences
+            .iter()
+            .map(|&(start, end)| {
+                let searched = &haystack[..end];
+                let mut matches = Vec::new();
+                let mut at = start;
+                while let Some(m) = re.find_at(searched, at) {
+                    matches.push((m.start(), m.end()));
+                    at = if m.end() > m.start() {
+                        m.end()
+                    } else if let Some(c) = searched[m.end()..].chars().next() {
+                        m.end() + c.len_utf8()
+                    } else {
+                        break;
+                    };
+                }
+                matches
+            })
+            .collect()
+    }
+
+    /// `find` run on each sentence of `haystack` (the text or a same-length
+    /// masked copy), with its spans shifted to document offsets.
+    pub fn spans_by_sentence(&self, haystack: &str, find: impl Fn(&str) -> Vec<(usize, usize)>) -> Vec<Vec<(usize, usize)>> {
+        self.sentences
+            .iter()
+            .map(|&(start, end)| find(&haystack[start..end]).into_iter().map(|(s, e)| (start + s, start + e)).collect())
+            .collect()
+    }
+
+    /// Words whose span lies within `start..end`.
+    pub fn words_in(&self, start: usize, end: usize) -> &[(usize, usize)] {
+        &self.words[self.word_range(start, end)]
+    }
+
+    /// Positions in `words` of the words within `start..end`.
+    pub fn word_range(&self, start: usize, end: usize) -> Range<usize> {
+        let first = self.words.partition_point(|w| w.0 < start);
+        let last = self.words.partition_point(|w| w.1 <= end);
+        first..last.max(first)
+    }
+}
+
+/// Whether `start..end` lies inside one of `spans`, which must be sorted and
+/// disjoint (as regex matches are).
+pub fn within(spans: &[(usize, usize)], start: usize, end: usize) -> bool {
+    let i = spans.partition_point(|s| s.0 <= start);
+    i > 0 && end <= spans[i - 1].1
+}
+
+/// Sentence spans of `text`, and whether it ends in a fragment with no
+/// terminator. A sentence runs up to and including its terminator (see
+/// `is_boundary` for the ones that don't end a sentence); a trailing
+/// fragment without one still counts.
+pub fn sentence_spans(text: &str, sentence_re: &Regex) -> (Vec<(usize, usize)>, bool) {
+    sentences_at(text, sentence_re.find_iter(text).map(|m| (m.start(), m.end())))
+}
+
+/// `sentence_spans` from terminator spans already found. A sentence takes
+/// the closing quotes and brackets after its terminator.
+fn sentences_at(text: &str, terminators: impl IntoIterator<Item = (usize, usize)>) -> (Vec<(usize, usize)>, bool) {
+    let mut sentences = Vec::new();
+    let fragment = sentences_into(text, terminators, &mut sentences);
+    (sentences, fragment)
+}
+
+/// `sentences_at` into the emptied `sentences`; true when the text ends in
+/// a fragment.
+fn sentences_into(text: &str, terminators: impl IntoIterator<Item = (usize, usize)>, sentences: &mut Vec<(usize, usize)>) -> bool {
+    sentences.clear();
+    let mut start = 0;
+    for (m_start, m_end) in terminators.into_iter().filter(|&(s, e)| is_boundary(text, s, e)) {
+        let end = closers_end(text, m_end);
+        if !text[start..m_start].trim().is_empty() {
+            sentences.push(trim_span(text, start, end));
+        }
+        start = end;
+    }
+    let fragment = !text[start..].trim().is_empty();
+    if fragment {
+        sentences.push(trim_span(text, start, text.len()));
+    }
+    fragment
+}
+
+pub fn paragraph_spans(text: &str, paragraph_re: &Regex) -> Vec<(usize, usize)> {
+    paragraphs_at(text, paragraph_re.find_iter(text).map(|m| (m.start(), m.end())))
+}
+
+/// `paragraph_spans` from paragraph break spans already found.
+pub fn paragraphs_at(text: &str, breaks: impl IntoIterator<Item = (usize, usize)>) -> Vec<(usize, usize)> {
+    let mut paragraphs = Vec::new();
+    paragraphs_into(text, breaks, &mut paragraphs);
+    paragraphs
+}
+
+fn paragraphs_into(text: &str, breaks: impl IntoIterator<Item = (usize, usize)>, paragraphs: &mut Vec<(usize, usize)>) {
+    paragraphs.clear();
+    let mut start = 0;
+    for (m_start, m_end) in breaks {
+        if !text[start..m_start].trim().is_empty() {
+            paragraphs.push(trim_span(text, start, m_start));
+        }
+        start = m_end;
+    }
+    if !text[start..].trim().is_empty() {
+        paragraphs.push(trim_span(text, start, text.len()));
+    }
+}
+
+/// Abbreviations whose period never ends a sentence. "etc." is left out
+/// because it usually does; it continues one only before a lowercase word.
+const ABBREVIATIONS: &[&str] = &[
+    "dr.", "mr.", "mrs.", "ms.", "prof.", "st.", "jr.", "sr.", "vs.", "e.g.", "i.e.", "cf.", "approx.", "fig.",
+    "vol.", "pp.", "capt.", "lt.", "sgt.", "mt.",
+];
+
+/// Closing quotes and brackets that belong to the sentence they follow.
+const CLOSERS: &[char] = &['"', '\'', '\u{201D}', '\u{2019}', '\u{00BB}', ')', ']'];
+
+/// The whitespace-delimited token ending in the terminator at `start..end`,
+/// without surrounding quotes, brackets, and punctuation.
+fn token_before(text: &str, start: usize, end: usize) -> &str {
+    let token_start = text[..start].char_indices().rfind(|(_, c)| c.is_whitespace()).map_or(0, |(i, c)| i + c.len_utf8());
+    let token_end = text[end..].find(char::is_whitespace).map_or(text.len(), |i| end + i);
+    text[token_start..token_end]
+        .trim_start_matches(['(', '[', '"', '\'', '\u{201C}', '\u{2018}'])
+        .trim_end_matches([',', ';', ':', ')', ']', '"', '\'', '\u{201D}', '\u{2019}'])
+}
+
+/// End of the closing quotes and brackets right after a terminator ending at
+/// `end`.
+fn closers_end(text: &str, end: usize) -> usize {
+    end + text[end..].len() - text[end..].trim_start_matches(CLOSERS).len()
+}
+
+/// Whether the next word after `at` starts lowercase, within the same
+/// paragraph.
+fn continues_lowercase(text: &str, at: usize) -> bool {
+    let rest = text[at..].trim_start();
+    let gap = &text[at..text.len() - rest.len()];
+    gap.matches('\n').count() < 2 && rest.chars().next().is_some_and(char::is_lowercase)
+}
+
+/// Whether the terminator at `start..end` ends a sentence. It doesn't when
+/// it is a period inside a word (decimals, "e.g", "D.C", domains), the
+/// period of an abbreviation or an initial ("J. K. Rowling"), or when a
+/// lowercase word follows an ellipsis, an acronym ("D.C. at"), "etc.", or
+/// quoted dialogue ("Stop!" she said).
+fn is_boundary(text: &str, start: usize, end: usize) -> bool {
+    let terminator = &text[start..end];
+    let before = text[..start].chars().next_back();
+    let after = text[end..].chars().next();
+    if terminator == "." && before.is_some_and(char::is_alphanumeric) && after.is_some_and(char::is_alphanumeric) {
+        return false;
+    }
+    let token = token_before(text, start, end);
+    if terminator == "." {
+        // "e.g." is matched at both periods; either one is part of the token
+        if ABBREVIATIONS.iter().any(|a| a.eq_ignore_ascii_case(token)) {
+            return false;
+        }
+        // Initials, but not the pronoun ("so did I.")
+        let mut chars = token.chars();
+        if chars.next().is_some_and(|c| c.is_uppercase() && c != 'I') && chars.as_str() == "." {
+            return false;
+        }
+    }
+    let closed = closers_end(text, end);
+    let ellipsis = terminator.len() > 1 && terminator.bytes().all(|b| b == b'.');
+    let acronym = terminator == "." && (token.eq_ignore_ascii_case("etc.") || token.trim_end_matches('.').contains('.'));
+    let quoted = closed > end;
+    !((ellipsis || acronym || quoted) && continues_lowercase(text, closed))
+}
+
+fn trim_span(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let slice = &text[start..end];
+    let leading = slice.len() - slice.trim_start().len();
+    let trailing = slice.len() - slice.trim_end().len();
+    (start + leading, (end - trailing).max(start + leading))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+    use crate::suggestions::SCOPE_INSTANCE;
+    use crate::test_support::{index, Cases};
+
+    #[test]
+    fn sentences_and_paragraphs_are_trimmed_spans() {
+        let text = "  It rained. Did it stop?!\n\nNo  \n\n\n and then ";
+        let index = index(text);
+        let spans = |spans: &[(usize, usize)]| spans.iter().map(|&(s, e)| &text[s..e]).collect::<Vec<_>>();
+        assert_eq!(spans(&index.sentences), ["It rained.", "Did it stop?!", "No  \n\n\n and then"]);
+        assert_eq!(spans(&index.paragraphs), ["It rained. Did it stop?!", "No", "and then"]);
+        assert_eq!(index.word_strs(), ["It", "rained", "Did", "it", "stop", "No", "and", "then"]);
+    }
+
+    #[test]
+    fn words_in_returns_words_inside_a_span() {
+        let text = "One two. Three four five.";
+        let index = index(text);
+        let (start, end) = index.sentences[1];
+        let words: Vec<&str> = index.words_in(start, end).iter().map(|&(s, e)| &text[s..e]).collect();
+        assert_eq!(words, ["Three", "four", "five"]);
+        assert!(index.words_in(3, 3).is_empty());
+        assert!(crate::test_support::index("").sentences.is_empty());
+    }
+
+    #[test]
+    fn unicode_words_keep_contractions_and_split_ideographs() {
+        let words = |text| WordSegmenter::Unicode.words(text);
+        assert_eq!(words("Don't say they're sure it\u{2019}s Mara's."), ["Don't", "say", "they're", "sure", "it\u{2019}s", "Mara's"]);
+        assert_eq!(words("cafe\u{301} na\u{308}ive"), ["cafe\u{301}", "na\u{308}ive"]);
+        assert_eq!(words("\u{6211}\u{7231}\u{5317}\u{4eac}").len(), 4);
+        assert!(words("\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467} !!").is_empty());
+
+        let text = "Don't stop.";
+        assert_eq!(WordSegmenter::Unicode.spans(text), [(0, 5), (6, 10)]);
+        let pattern = WordSegmenter::Pattern(Regex::new(r"\b\w+\b").unwrap());
+        assert_eq!(pattern.words(text), ["Don", "t", "stop"]);
+        assert_eq!(pattern.count(text), 3);
+    }
+
+    #[test]
+    fn abbreviations_do_not_end_sentences() {
+        let text = "Dr. Lee arrived at noon, e.g. before lunch. Then (Prof. Hart) left with Mr. Smith; the end. It was 5 p.m. etc. Done.";
+        let index = index(text);
+        let sentences: Vec<&str> = index.sentences.iter().map(|&(s, e)| &text[s..e]).collect();
+        assert_eq!(
+            sentences,
+            ["Dr. Lee arrived at noon, e.g. before lunch.", "Then (Prof. Hart) left with Mr. Smith; the end.", "It was 5 p.m. etc.", "Done."]
+        );
+        // A multi-byte space before the abbreviation
+        let text = "Then\u{a0}Dr. Lee left.";
+        assert_eq!(crate::test_support::index(text).sentences, [(0, text.len())]);
+    }
+
+    fn sentences(text: &str) -> Vec<&str> {
+        index(text).sentences.iter().map(|&(s, e)| &text[s..e]).collect()
+    }
+
+    #[test]
+    fn decimals_initials_and_acronyms_do_not_end_sentences() {
+        assert_eq!(sentences("Dr. Smith went to Washington D.C. at 3.5 mph"), ["Dr. Smith went to Washington D.C. at 3.5 mph"]);
+        assert_eq!(
+            sentences("J. K. Rowling wrote it. Mrs. Dalloway, i.e. the novel, sold well at St. Ives. Visit example.com today."),
+            ["J. K. Rowling wrote it.", "Mrs. Dalloway, i.e. the novel, sold well at St. Ives.", "Visit example.com today."]
+        );
+        // An acronym or "etc." before a capital still ends the sentence
+        assert_eq!(sentences("She moved to the U.S. Then she left. Pens, ink, etc. All gone."), ["She moved to the U.S.", "Then she left.", "Pens, ink, etc.", "All gone."]);
+        // The pronoun is not an initial
+        assert_eq!(sentences("So did I. We left."), ["So did I.", "We left."]);
+    }
+
+    #[test]
+    fn ellipses_continue_before_lowercase_words() {
+        assert_eq!(sentences("I waited... and waited. Then... Nothing."), ["I waited... and waited.", "Then...", "Nothing."]);
+    }
+
+    #[test]
+    fn closing_quotes_belong_to_their_sentence() {
+        assert_eq!(
+            sentences("\"Are you coming?\" she asked. \"No.\" He left. (It was late.) \u{201C}Stop!\u{201D} she said."),
+            ["\"Are you coming?\" she asked.", "\"No.\"", "He left.", "(It was late.)", "\u{201C}Stop!\u{201D} she said."]
+        );
+    }
+
+    #[test]
+    fn a_trailing_fragment_is_a_sentence() {
+        assert_eq!(sentences("It rained. Then the wind came"), ["It rained.", "Then the wind came"]);
+        assert_eq!(sentences("no punctuation at all"), ["no punctuation at all"]);
+        // A lowercase word in the next paragraph still starts a new sentence
+        assert_eq!(sentences("Wait...\n\nand then"), ["Wait...", "and then"]);
+    }
+
+    #[test]
+    fn the_token_scan_matches_the_regex_scan() {
+        // Equivalent to the built-in patterns, but not recognised as them
+        let sentence = Regex::new(r"(?:[.!?])+").unwrap();
+        let paragraph = Regex::new(r"(?:\n\s*\n)").unwrap();
+        const PIECES: &[&str] = &[
+            "Mara", "walked", " ", "  ", ".", "...", "!?", "\n", "\n\n", "\n \r\n", "\u{a0}", "Dr.", "e.g.", "3.14", "don't",
+            "caf\u{e9}", "\u{201c}", "\u{201d}", "\u{2014}", "\u{5bc6}\u{7801}", "\u{1f600}",
+        ];
+        let mut cases = Cases(0x6a09_e667_f3bc_c908);
+        for _ in 0..1000 {
+            let text: String = (0..cases.below(40)).map(|_| PIECES[cases.below(PIECES.len())]).collect();
+            let tokenized = index(&text);
+            let scanned = DocumentIndex::build(&text, &WordSegmenter::Unicode, &sentence, &paragraph);
+            assert_eq!(tokenized.words, scanned.words, "{:?}", text);
+            assert_eq!(tokenized.sentences, scanned.sentences, "{:?}", text);
+            assert_eq!(tokenized.paragraphs, scanned.paragraphs, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn matches_stay_inside_their_sentence() {
+        let text = "It was late. Designed by him. Slowly";
+        let index = index(text);
+        let re = Regex::new(r"(?i)\w+ly\b|late\. designed").unwrap();
+        let matches: Vec<Vec<&str>> = index.matches_by_sentence(&re, text).iter().map(|m| m.iter().map(|&(s, e)| &text[s..e]).collect()).collect();
+        assert_eq!(matches, [vec![], vec![], vec!["Slowly"]]);
+        let empty = Regex::new(r"x*").unwrap();
+        assert_eq!(index.matches_by_sentence(&empty, text)[0].len(), "It was late.".len() + 1);
+    }
+
+    #[test]
+    fn within_checks_containment_in_sorted_spans() {
+        let spans = [(2, 5), (8, 12)];
+        assert!(within(&spans, 2, 5) && within(&spans, 9, 12) && within(&spans, 3, 3));
+        assert!(!within(&spans, 1, 3) && !within(&spans, 4, 9) && !within(&spans, 12, 13) && !within(&[], 0, 0));
+    }
+
+    #[test]
+    fn perform_analysis_counts_units() {
+        let processor = TextProcessor::new();
+        let result = processor.perform_analysis("The cat sat on the mat. It was warm.\n\nThe dog slept.");
+        assert_eq!(result.word_count, 12);
+        assert_eq!(result.sentence_count, 3);
+        assert_eq!(result.paragraph_count, 2);
+    }
+
+    #[test]
+    fn sentence_length_suggestions_cover_exactly_the_long_sentence() {
+        let long = |end: &str| format!("{}{}", vec!["word"; 30].join(" "), end);
+        let processor = TextProcessor::new();
+        for ending in [".", "!", "?"] {
+            let sentence = long(ending);
+            let text = format!("Caf\u{e9} first. A short one here. {} Then another short sentence. The end.", sentence);
+            let spans: Vec<&str> = processor
+                .build_optimization_report(&text)
+                .suggestions
+                .iter()
+                .filter(|s| s.suggestion_type == "sentence_length" && s.scope == SCOPE_INSTANCE)
+                .map(|s| &text[s.start_pos..s.end_pos])
+                .collect();
+            assert_eq!(spans, [sentence.as_str()]);
+        }
+    }
+
+    #[test]
+    fn characters_are_counted_as_graphemes_and_words_per_unicode() {
+        let processor = TextProcessor::new();
+        let family = processor.perform_analysis("Family \u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}");
+        assert_eq!((family.character_count, family.grapheme_count, family.word_count), (12, 8, 1));
+
+        // NFD: each accent is a separate combining mark
+        let nfd = processor.perform_analysis("Cafe\u{301} re\u{301}sume\u{301}");
+        assert_eq!((nfd.character_count, nfd.grapheme_count, nfd.word_count), (14, 11, 2));
+
+        let text = "I don't think they're coming. She'd've known if it wasn't Mara's. We can't, won't, shouldn't.";
+        let apostrophes = processor.perform_analysis(text);
+        assert_eq!(apostrophes.word_count, 15);
+        assert_eq!(apostrophes.grapheme_count, text.len());
+
+        assert_eq!(processor.perform_analysis("\u{6211}\u{7231}\u{5317}\u{4eac}\u{3002}").word_count, 4);
+    }
+
+    #[test]
+    fn abbreviations_and_decimals_do_not_inflate_the_sentence_count() {
+        let analysis = TextProcessor::new().perform_analysis("Dr. Smith went to Washington D.C. at 3.5 mph. He was late.");
+        assert_eq!(analysis.sentence_count, 2);
+        assert_eq!(analysis.complexity_metrics.avg_words_per_sentence, 6.0);
+    }
+}
@@ -0,0 +1,237 @@
+//! Lexicon-based sentiment: each word's valence (about -5 to 5) summed per
+//! paragraph, with a preceding negation flipping its sign, and normalized by
+//! word count so long paragraphs don't dominate the arc.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::config::SuggestionConfig;
+use crate::segmentation::DocumentIndex;
+
+/// Built-in English valences, sorted.
+const LEXICON: &[(&str, i8)] = &[
+    ("abandon", -2), ("abandoned", -2), ("abuse", -3), ("abused", -3), ("accept", 1), ("accepted", 1),
+    ("accident", -2), ("ache", -2), ("admire", 3), ("admired", 3), ("adore", 3), ("adored", 3), ("afraid", -2),
+    ("agony", -3), ("alarm", -2), ("alarmed", -2), ("alive", 1), ("alone", -2), ("amazing", 4), ("anger", -3),
+    ("angry", -3), ("anguish", -3), ("annoyed", -2), ("anxious", -2), ("appreciate", 2), ("ashamed", -2),
+    ("attack", -2), ("attacked", -2), ("awful", -3), ("bad", -3), ("beautiful", 3), ("best", 3), ("betray", -3),
+    ("betrayed", -3), ("bitter", -2), ("bleak", -2), ("bless", 2), ("blessed", 3), ("bliss", 3), ("blood", -1),
+    ("brave", 2), ("bright", 1), ("brilliant", 4), ("broken", -2), ("calm", 2), ("care", 2), ("cared", 2),
+    ("celebrate", 3), ("charming", 3), ("cheer", 2), ("cheerful", 2), ("cherish", 2), ("clever", 2),
+    ("comfort", 2), ("confident", 2), ("confused", -2), ("content", 1), ("courage", 2), ("cried", -2),
+    ("cruel", -3), ("cry", -1), ("curse", -2), ("damn", -2), ("danger", -2), ("dark", -1), ("dead", -3),
+    ("death", -2), ("defeat", -2), ("defeated", -2), ("delight", 3), ("delighted", 3), ("depressed", -2),
+    ("despair", -3), ("desperate", -3), ("destroy", -3), ("destroyed", -3), ("died", -3), ("disaster", -2),
+    ("disgust", -3), ("disgusted", -3), ("dread", -2), ("eager", 2), ("ecstatic", 4), ("elated", 3),
+    ("embarrassed", -2), ("enemy", -2), ("enjoy", 2), ("enjoyed", 2), ("evil", -3), ("excellent", 3),
+    ("excited", 3), ("exciting", 3), ("fail", -2), ("failed", -2), ("failure", -2), ("fantastic", 4),
+    ("fear", -2), ("fine", 2), ("fond", 2), ("fool", -2), ("forgive", 1), ("free", 1), ("friend", 1),
+    ("friendly", 2), ("fright", -2), ("frightened", -2), ("fun", 4), ("funny", 4), ("furious", -3),
+    ("gentle", 2), ("gift", 2), ("glad", 3), ("glorious", 2), ("good", 3), ("grace", 2), ("grateful", 3),
+    ("great", 3), ("grief", -2), ("grim", -2), ("guilt", -3), ("guilty", -3), ("happiness", 3), ("happy", 3),
+    ("harm", -2), ("hate", -3), ("hated", -3), ("hatred", -3), ("heartbroken", -3), ("hell", -4), ("help", 2),
+    ("helpless", -2), ("hero", 2), ("hope", 2), ("hopeful", 2), ("hopeless", -2), ("horrible", -3),
+    ("horror", -3), ("hostile", -2), ("hurt", -2), ("ill", -2), ("joy", 3), ("joyful", 3), ("kill", -3),
+    ("killed", -3), ("kind", 2), ("kiss", 2), ("laugh", 1), ("laughed", 1), ("liar", -3), ("like", 2),
+    ("lonely", -2), ("lose", -3), ("loss", -3), ("lost", -3), ("love", 3), ("loved", 3), ("lovely", 3),
+    ("loving", 2), ("lucky", 3), ("mad", -3), ("magnificent", 3), ("merry", 3), ("miserable", -3),
+    ("misery", -2), ("miss", -2), ("mourn", -2), ("murder", -2), ("nervous", -2), ("nice", 3), ("nightmare", -3),
+    ("pain", -2), ("painful", -2), ("panic", -3), ("peace", 2), ("peaceful", 2), ("perfect", 3),
+    ("pleasant", 3), ("pleased", 3), ("poor", -2), ("pretty", 1), ("proud", 2), ("rage", -2), ("rejected", -1),
+    ("relief", 1), ("relieved", 2), ("sad", -2), ("sadness", -2), ("safe", 1), ("scared", -2), ("scream", -2),
+    ("screamed", -2), ("shame", -2), ("shock", -2), ("shocked", -2), ("sick", -2), ("smile", 2), ("smiled", 2),
+    ("sorrow", -2), ("sorry", -1), ("splendid", 3), ("strong", 2), ("stupid", -2), ("succeed", 3),
+    ("success", 2), ("suffer", -2), ("suffering", -2), ("sweet", 2), ("tears", -2), ("terrible", -3),
+    ("terrified", -3), ("terror", -3), ("thank", 2), ("thanks", 2), ("threat", -2), ("thrilled", 5),
+    ("torture", -4), ("tragedy", -2), ("tragic", -2), ("triumph", 4), ("trouble", -2), ("trust", 1),
+    ("ugly", -3), ("unhappy", -2), ("upset", -2), ("victory", 3), ("violent", -3), ("wonderful", 4),
+    ("worried", -3), ("worry", -3), ("worse", -3), ("worst", -3), ("wrong", -2),
+];
+
+/// Words that flip the valence of a word shortly after them ("not happy").
+const NEGATORS: &[&str] = &["hardly", "neither", "never", "no", "nobody", "none", "nor", "not", "nothing", "without"];
+
+/// How many words after a negator it still applies to.
+const NEGATION_WINDOW: usize = 3;
+
+/// Punctuation that ends a negation's reach.
+const CLAUSE_BREAKS: &[char] = &['.', '!', '?', ';', ':', ',', '\u{2014}'];
+
+/// Words reported per paragraph in `dominant_words`.
+const DOMINANT_WORDS: usize = 3;
+
+/// The active valence lexicon: `sentiment_lexicon` from the configuration,
+/// or the built-in English one.
+pub struct SentimentLexicon {
+    scores: HashMap<String, f64>,
+}
+
+impl SentimentLexicon {
+    pub fn from_config(config: &SuggestionConfig) -> SentimentLexicon {
+        let scores = match &config.sentiment_lexicon {
+            Some(custom) => custom.iter().map(|(w, &s)| (w.to_lowercase(), s)).collect(),
+            None => LEXICON.iter().map(|&(w, s)| (w.to_string(), s as f64)).collect(),
+        };
+        SentimentLexicon { scores }
+    }
+
+    fn valence(&self, word: &str) -> f64 {
+        self.scores.get(word).copied().unwrap_or(0.0)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ParagraphSentiment {
+    pub start_pos: usize,
+    pub end_pos: usize,
+    pub word_count: usize,
+    /// Summed valence per word.
+    pub score: f64,
+    /// The words contributing most to the score, strongest first.
+    pub dominant_words: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SentimentProfile {
+    pub paragraphs: Vec<ParagraphSentiment>,
+    /// Summed valence per word over the whole document.
+    pub average_score: f64,
+}
+
+fn is_negator(word: &str) -> bool {
+    NEGATORS.contains(&word) || word.ends_with("n't") || word.ends_with("n\u{2019}t")
+}
+
+/// `(lowercased word, signed valence)` of every scored word in `start..end`.
+fn scored_words(index: &DocumentIndex, lexicon: &SentimentLexicon, start: usize, end: usize) -> Vec<(String, f64)> {
+    let text = index.text;
+    let mut scored = Vec::new();
+    // Word index and end of the last negator still in reach
+    let mut negation: Option<(usize, usize)> = None;
+    for (k, &(s, e)) in index.words_in(start, end).iter().enumerate() {
+        let word = text[s..e].to_lowercase();
+        if let Some((at, negator_end)) = negation {
+            if k - at > NEGATION_WINDOW || text[negator_end..s].contains(CLAUSE_BREAKS) {
+                negation = None;
+            }
+        }
+        if is_negator(&word) {
+            negation = Some((k, e));
+            continue;
+        }
+        let valence = lexicon.valence(&word);
+        if valence != 0.0 {
+            scored.push((word, if negation.is_some() { -valence } else { valence }));
+        }
+    }
+    scored
+}
+
+pub fn profile(index: &DocumentIndex, lexicon: &SentimentLexicon) -> SentimentProfile {
+    let paragraphs: Vec<ParagraphSentiment> = index
+        .paragraphs
+        .iter()
+        .map(|&(start, end)| {
+            let word_count = index.words_in(start, end).len();
+            let scored = scored_words(index, lexicon, start, end);
+            // Summed from +0.0, so a paragraph with no scored words isn't -0.0
+            let total = scored.iter().fold(0.0, |sum, (_, v)| sum + v);
+            let mut by_word: BTreeMap<&str, f64> = BTreeMap::new();
+            for (word, valence) in &scored {
+                *by_word.entry(word).or_default() += valence;
+            }
+            let mut dominant: Vec<(&str, f64)> = by_word.into_iter().filter(|&(_, v)| v != 0.0).collect();
+            dominant.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+            ParagraphSentiment {
+                start_pos: start,
+                end_pos: end,
+                word_count,
+                score: if word_count > 0 { total / word_count as f64 } else { 0.0 },
+                dominant_words: dominant.into_iter().take(DOMINANT_WORDS).map(|(w, _)| w.to_string()).collect(),
+            }
+        })
+        .collect();
+    SentimentProfile { paragraphs, average_score: document_score(index, lexicon) }
+}
+
+/// Summed valence per word over all of `index`'s text.
+pub fn document_score(index: &DocumentIndex, lexicon: &SentimentLexicon) -> f64 {
+    let total = valence_sum(index, lexicon, 0.0);
+    if index.words.is_empty() { 0.0 } else { total / index.words.len() as f64 }
+}
+
+/// `sum` plus the valence of every scored word of `index`, added in text
+/// order so running sums over consecutive runs of a text match one sum
+/// over all of it.
+pub fn valence_sum(index: &DocumentIndex, lexicon: &SentimentLexicon, sum: f64) -> f64 {
+    index.paragraphs.iter().flat_map(|&(s, e)| scored_words(index, lexicon, s, e)).fold(sum, |sum, (_, v)| sum + v)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TextProcessor;
+    use crate::test_support::index;
+
+    use super::*;
+
+    fn sentiment(text: &str, lexicon: &SentimentLexicon) -> SentimentProfile {
+        profile(&index(text), lexicon)
+    }
+
+    fn english() -> SentimentLexicon {
+        SentimentLexicon::from_config(&SuggestionConfig::default())
+    }
+
+    #[test]
+    fn positive_and_negative_paragraphs_score_per_word() {
+        let text = "What a wonderful, happy morning. She smiled.\n\nThe war was terrible and cruel. Everyone was afraid.\n\nThe door was green.";
+        let profile = sentiment(text, &english());
+        let scores: Vec<f64> = profile.paragraphs.iter().map(|p| p.score).collect();
+        // (4 + 3 + 2) / 7, (-3 - 3 - 2) / 9, and nothing scored
+        assert_eq!(scores, [9.0 / 7.0, -8.0 / 9.0, 0.0]);
+        assert_eq!(profile.paragraphs[0].dominant_words, ["wonderful", "happy", "smiled"]);
+        assert_eq!(profile.paragraphs[1].dominant_words, ["cruel", "terrible", "afraid"]);
+        assert!(profile.paragraphs[2].dominant_words.is_empty());
+        assert_eq!(profile.average_score, 1.0 / 20.0);
+    }
+
+    #[test]
+    fn negation_flips_valence_within_its_clause() {
+        let score = |text: &str| sentiment(text, &english()).average_score * text.split_whitespace().count() as f64;
+        assert_eq!(score("She was not happy"), -3.0);
+        assert_eq!(score("He wasn't very good"), -3.0);
+        assert_eq!(score("She didn\u{2019}t feel happy"), -3.0);
+        // Out of reach: beyond three words, or past a clause break
+        assert_eq!(score("Not that she was ever happy"), 3.0);
+        assert_eq!(score("Not now, she was happy"), 3.0);
+        // "bad" is the third word after "never", "fool" the fourth
+        assert_eq!(score("Never be a bad fool"), 3.0 - 2.0);
+    }
+
+    #[test]
+    fn a_custom_lexicon_replaces_the_built_in_one() {
+        let config = SuggestionConfig {
+            sentiment_lexicon: Some(BTreeMap::from([("Feliz".to_string(), 3.0), ("triste".to_string(), -2.0)])),
+            ..Default::default()
+        };
+        let profile = sentiment("Ella estaba feliz, no triste. Happy.", &SentimentLexicon::from_config(&config));
+        assert_eq!(profile.paragraphs[0].score, 5.0 / 6.0);
+        assert_eq!(profile.paragraphs[0].dominant_words, ["feliz", "triste"]);
+    }
+
+    #[test]
+    fn the_built_in_lexicon_is_sorted_and_unique() {
+        assert!(LEXICON.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(LEXICON.iter().all(|&(_, v)| (-5..=5).contains(&v) && v != 0));
+        assert!(sentiment("", &english()).paragraphs.is_empty());
+        assert_eq!(sentiment("", &english()).average_score, 0.0);
+    }
+
+    #[test]
+    fn sentiment_score_is_reported_with_the_analysis() {
+        let processor = TextProcessor::new();
+        assert!(processor.perform_analysis("A wonderful, happy day.").sentiment_score > 0.0);
+        assert!(processor.perform_analysis("She was not happy. It was awful.").sentiment_score < 0.0);
+        assert_eq!(processor.perform_analysis("The door was green.").sentiment_score, 0.0);
+    }
+}
@@ -0,0 +1,184 @@
+//! Editing sessions for live typing: the processor keeps the text and its
+//! token spans, and an edit re-tokenizes only the paragraphs around it.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::segmentation::{self, DocumentIndex, WordSegmenter};
+
+/// A replacement of `start..end` (byte offsets) in the session text.
+#[derive(Serialize, Deserialize)]
+pub struct TextEdit {
+    pub start: usize,
+    pub end: usize,
+    pub replacement: String,
+}
+
+/// The patterns an index is built with.
+pub struct Segmenters<'p> {
+    pub words: &'p WordSegmenter,
+    pub sentences: &'p Regex,
+    pub paragraphs: &'p Regex,
+}
+
+type Spans = Vec<(usize, usize)>;
+
+/// Session text and the spans `DocumentIndex::build` would give it.
+pub struct Session {
+    pub text: String,
+    words: Spans,
+    sentences: Spans,
+    paragraphs: Spans,
+}
+
+/// Whether any span strictly contains `offset`.
+fn straddles(spans: &[(usize, usize)], offset: usize) -> bool {
+    let i = spans.partition_point(|s| s.1 <= offset);
+    spans.get(i).is_some_and(|s| s.0 < offset)
+}
+
+/// `old` with everything inside `from..to` replaced by `window` (offset by
+/// `from`), and spans after the window shifted by the edit's length change.
+fn splice(old: &[(usize, usize)], window: Spans, from: usize, to: usize, shift: isize) -> Spans {
+    let before = old.partition_point(|s| s.1 <= from);
+    let after = old.partition_point(|s| s.0 < to);
+    let mut spans = Vec::with_capacity(old.len());
+    spans.extend_from_slice(&old[..before]);
+    spans.extend(window.into_iter().map(|(s, e)| (s + from, e + from)));
+    spans.extend(old[after..].iter().map(|&(s, e)| ((s as isize + shift) as usize, (e as isize + shift) as usize)));
+    spans
+}
+
+impl Session {
+    pub fn new(text: String, segmenters: &Segmenters) -> Session {
+        let index = DocumentIndex::build(&text, segmenters.words, segmenters.sentences, segmenters.paragraphs);
+        let (words, sentences, paragraphs) = (index.words, index.sentences, index.paragraphs);
+        Session { text, words, sentences, paragraphs }
+    }
+
+    /// The index of the current text. Copies the spans, not the text.
+    pub fn index(&self) -> DocumentIndex<'_> {
+        DocumentIndex {
+            text: &self.text,
+            words: self.words.clone(),
+            sentences: self.sentences.clone(),
+            paragraphs: self.paragraphs.clone(),
+        }
+    }
+
+    /// A window boundary must fall between paragraphs with no sentence
+    /// running across it, so tokenizing either side alone gives the same
+    /// spans as tokenizing the whole text.
+    fn is_boundary(&self, offset: usize) -> bool {
+        !straddles(&self.sentences, offset) && !straddles(&self.paragraphs, offset)
+    }
+
+    /// Applies `edit` and re-tokenizes the paragraphs it touches, widening
+    /// the window while a sentence would run across its end.
+    pub fn apply(&mut self, edit: &TextEdit, segmenters: &Segmenters) -> Result<(), String> {
+        let TextEdit { start, end, ref replacement } = *edit;
+        if start > end || end > self.text.len() || !self.text.is_char_boundary(start) || !self.text.is_char_boundary(end) {
+            return Err(format!("edit range {}..{} is not a valid range of the {}-byte session text", start, end, self.text.len()));
+        }
+        let shift = replacement.len() as isize - (end - start) as isize;
+
+        // Window in old-text offsets: from the nearest paragraph start at or
+        // before the edit to the nearest paragraph end at or after it
+        let window_start = self.paragraphs.iter().rev().map(|p| p.0).filter(|&s| s <= start).find(|&s| self.is_boundary(s)).unwrap_or(0);
+        let mut ends: Vec<usize> = self.paragraphs.iter().map(|p| p.1).filter(|&e| e >= end && self.is_boundary(e)).collect();
+        ends.push(self.text.len());
+
+        self.text.replace_range(start..end, replacement);
+        for window_end in ends {
+            let new_end = (window_end as isize + shift) as usize;
+            let window = &self.text[window_start..new_end];
+            let (sentences, fragment) = segmentation::sentence_spans(window, segmenters.sentences);
+            // An unterminated fragment would run on into the next paragraph
+            if fragment && new_end < self.text.len() {
+                continue;
+            }
+            self.words = splice(&self.words, segmenters.words.spans(window), window_start, window_end, shift);
+            self.sentences = splice(&self.sentences, sentences, window_start, window_end, shift);
+            self.paragraphs =
+                splice(&self.paragraphs, segmentation::paragraph_spans(window, segmenters.paragraphs), window_start, window_end, shift);
+            break;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+    use crate::test_support::Cases;
+
+    const PIECES: &[&str] = &[
+        "Mara ", "walked ", "home", ". ", "!", "? ", "\n\n", "\n", "Dr. ", "caf\u{e9} ", "\u{201c}Run,\u{201d} ", "was seen ", "  ", "e.g. ",
+    ];
+
+    fn random_text(cases: &mut Cases, pieces: usize) -> String {
+        (0..pieces).map(|_| PIECES[cases.below(PIECES.len())]).collect()
+    }
+
+    fn assert_matches_full_analysis(processor: &TextProcessor, session: &Session, context: &str) {
+        let incremental = session.index();
+        let full = processor.index(&session.text);
+        assert_eq!(incremental.words, full.words, "words after {}", context);
+        assert_eq!(incremental.sentences, full.sentences, "sentences after {}", context);
+        assert_eq!(incremental.paragraphs, full.paragraphs, "paragraphs after {}", context);
+        assert_eq!(
+            serde_json::to_value(processor.analyze_indexed(&incremental)).unwrap(),
+            serde_json::to_value(processor.perform_analysis(&session.text)).unwrap(),
+            "analysis after {}",
+            context
+        );
+    }
+
+    #[test]
+    fn random_edits_match_a_full_reanalysis() {
+        let processor = TextProcessor::new();
+        let mut cases = Cases(0x51_7cc1_b727_220a);
+        for _ in 0..40 {
+            let mut session = Session::new(random_text(&mut cases, 30), &processor.segmenters());
+            for _ in 0..15 {
+                let (a, b) = (cases.boundary(&session.text), cases.boundary(&session.text));
+                let pieces = cases.below(4);
+                let edit = TextEdit { start: a.min(b), end: a.max(b), replacement: random_text(&mut cases, pieces) };
+                let context = format!("{:?} <- {}..{} {:?}", session.text, edit.start, edit.end, edit.replacement);
+                session.apply(&edit, &processor.segmenters()).unwrap();
+                assert_matches_full_analysis(&processor, &session, &context);
+            }
+        }
+    }
+
+    #[test]
+    fn typing_one_character_at_a_time_matches_a_full_reanalysis() {
+        let processor = TextProcessor::new();
+        let target = "The storm came. Mara ran.\n\nDr. Lee was seen by him! Was he?\n\nShe slept";
+        let mut session = Session::new(String::new(), &processor.segmenters());
+        for c in target.chars() {
+            let end = session.text.len();
+            session.apply(&TextEdit { start: end, end, replacement: c.to_string() }, &processor.segmenters()).unwrap();
+            assert_matches_full_analysis(&processor, &session, &format!("typing {:?}", session.text));
+        }
+        // A paragraph break typed in the middle of a sentence, then removed
+        let middle = target.find("Mara").unwrap();
+        session.apply(&TextEdit { start: middle, end: middle, replacement: "\n\n".to_string() }, &processor.segmenters()).unwrap();
+        assert_matches_full_analysis(&processor, &session, "splitting a paragraph");
+        session.apply(&TextEdit { start: middle, end: middle + 2, replacement: String::new() }, &processor.segmenters()).unwrap();
+        assert_eq!(session.text, target);
+        assert_matches_full_analysis(&processor, &session, "joining it again");
+    }
+
+    #[test]
+    fn invalid_edit_ranges_are_rejected_without_changing_the_text() {
+        let processor = TextProcessor::new();
+        let mut session = Session::new("Caf\u{e9} au lait.".to_string(), &processor.segmenters());
+        for (start, end) in [(5, 2), (0, 40), (4, 4)] {
+            let error = session.apply(&TextEdit { start, end, replacement: "x".to_string() }, &processor.segmenters()).err().unwrap();
+            assert_eq!(error, format!("edit range {}..{} is not a valid range of the 14-byte session text", start, end));
+        }
+        assert_eq!(session.text, "Caf\u{e9} au lait.");
+    }
+}
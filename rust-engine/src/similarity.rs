@@ -0,0 +1,256 @@
+//! Word-level similarity measures over lowercased token sequences, and
+//! MinHash fingerprints that estimate shingle similarity without the texts.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+pub const DEFAULT_SHINGLE_SIZE: usize = 3;
+
+/// Slots in a MinHash fingerprint; the estimate's standard error is about
+/// 1 / sqrt(slots).
+const FINGERPRINT_SLOTS: usize = 128;
+
+/// Bumped whenever the hashing changes, which makes older fingerprints
+/// incomparable.
+pub const FINGERPRINT_VERSION: u32 = 1;
+
+pub enum SimilarityMethod {
+    /// Jaccard similarity of word n-grams.
+    Shingle(usize),
+    /// Cosine similarity of term frequencies.
+    Cosine,
+}
+
+impl SimilarityMethod {
+    pub const ALL: &'static [&'static str] = &["shingle", "cosine"];
+
+    /// "shingle" (n = 3), "shingle:N", or "cosine".
+    pub fn parse(value: &str) -> Result<SimilarityMethod, String> {
+        match value {
+            "shingle" => return Ok(SimilarityMethod::Shingle(DEFAULT_SHINGLE_SIZE)),
+            "cosine" => return Ok(SimilarityMethod::Cosine),
+            _ => {}
+        }
+        match value.strip_prefix("shingle:").map(str::parse::<usize>) {
+            Some(Ok(n)) if n > 0 => Ok(SimilarityMethod::Shingle(n)),
+            _ => Err(format!(
+                "Unknown similarity method \"{}\"; expected \"shingle\", \"shingle:N\" with N > 0, or \"cosine\"",
+                value
+            )),
+        }
+    }
+
+    /// Similarity in [0, 1]; identical sequences (also two empty ones) score
+    /// 1.0.
+    pub fn score(&self, a: &[String], b: &[String]) -> f64 {
+        match *self {
+            SimilarityMethod::Shingle(n) => shingle_jaccard(a, b, n),
+            SimilarityMethod::Cosine => cosine(a, b),
+        }
+    }
+}
+
+/// Jaccard similarity of the two sequences' word n-gram sets. Sequences
+/// shorter than `n` contribute themselves as a single shingle. Two empty
+/// sequences are identical; one empty sequence shares nothing.
+pub fn shingle_jaccard(a: &[String], b: &[String], n: usize) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let shingles_a = shingles(a, n);
+    let shingles_b = shingles(b, n);
+    let shared = shingles_a.intersection(&shingles_b).count();
+    let union = shingles_a.len() + shingles_b.len() - shared;
+    if union == 0 { 0.0 } else { shared as f64 / union as f64 }
+}
+
+fn shingles(words: &[String], n: usize) -> HashSet<&[String]> {
+    let n = n.max(1);
+    if words.len() < n {
+        return if words.is_empty() { HashSet::new() } else { HashSet::from([words]) };
+    }
+    words.windows(n).collect()
+}
+
+/// Cosine similarity of the two sequences' term-frequency vectors. Two empty
+/// sequences are identical; one empty sequence shares nothing.
+pub fn cosine(a: &[String], b: &[String]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let (tf_a, tf_b) = (frequencies(a), frequencies(b));
+    let mut dot = 0.0;
+    for (word, x) in &tf_a {
+        if let Some(y) = tf_b.get(word) {
+            dot += x * y;
+        }
+    }
+    let norm = |tf: &HashMap<&str, f64>| tf.values().map(|x| x * x).sum::<f64>().sqrt();
+    let norms = norm(&tf_a) * norm(&tf_b);
+    // Rounding can put identical vectors a hair above 1
+    if norms == 0.0 { 0.0 } else { (dot / norms).min(1.0) }
+}
+
+fn frequencies(words: &[String]) -> HashMap<&str, f64> {
+    let mut counts: HashMap<&str, f64> = HashMap::new();
+    for word in words {
+        *counts.entry(word).or_default() += 1.0;
+    }
+    counts
+}
+
+/// A MinHash signature of a text's word shingles: for each slot, the least
+/// hash of any shingle under that slot's hash function. The share of equal
+/// slots between two fingerprints estimates their shingle Jaccard
+/// similarity.
+#[derive(Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub version: u32,
+    pub shingle_size: usize,
+    pub word_count: usize,
+    pub minhashes: Vec<u32>,
+}
+
+pub fn fingerprint(words: &[String], n: usize) -> Fingerprint {
+    let mut minhashes = vec![u32::MAX; FINGERPRINT_SLOTS];
+    for shingle in shingles(words, n) {
+        let base = fnv1a(shingle);
+        for (slot, min) in minhashes.iter_mut().enumerate() {
+            *min = (*min).min(slot_hash(base, slot));
+        }
+    }
+    Fingerprint { version: FINGERPRINT_VERSION, shingle_size: n.max(1), word_count: words.len(), minhashes }
+}
+
+/// Estimated shingle Jaccard similarity of the texts behind two fingerprints,
+/// which must come from the same version and shingle size.
+pub fn compare_fingerprints(a: &Fingerprint, b: &Fingerprint) -> Result<f64, String> {
+    if a.version != b.version || a.shingle_size != b.shingle_size || a.minhashes.len() != b.minhashes.len() {
+        return Err("Fingerprints differ in version or shingle size and can't be compared".to_string());
+    }
+    if a.word_count == 0 || b.word_count == 0 {
+        return Ok(if a.word_count == b.word_count { 1.0 } else { 0.0 });
+    }
+    let equal = a.minhashes.iter().zip(&b.minhashes).filter(|(x, y)| x == y).count();
+    Ok(if a.minhashes.is_empty() { 0.0 } else { equal as f64 / a.minhashes.len() as f64 })
+}
+
+/// 64-bit FNV-1a over the shingle's words, separated so that ["ab", "c"]
+/// and ["a", "bc"] differ. Stable across platforms and builds, unlike the
+/// standard library's hasher.
+fn fnv1a(shingle: &[String]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in shingle.iter().flat_map(|w| w.bytes().chain([0x1f])) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// The shingle hash under slot `slot`'s hash function (a SplitMix64 round).
+fn slot_hash(base: u64, slot: usize) -> u32 {
+    let mut z = base.wrapping_add((slot as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    ((z ^ (z >> 31)) >> 32) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn shingle_jaccard_compares_ngram_sets() {
+        assert_eq!(shingle_jaccard(&words("the sun rose"), &words("the sun rose"), 2), 1.0);
+        // {the sun, sun rose} vs {the sun, sun set}
+        assert_eq!(shingle_jaccard(&words("the sun rose"), &words("the sun set"), 2), 1.0 / 3.0);
+        assert_eq!(shingle_jaccard(&words("rain"), &words("rain"), 3), 1.0);
+        assert_eq!(shingle_jaccard(&[], &[], 2), 1.0);
+        assert_eq!(shingle_jaccard(&words("rain"), &[], 2), 0.0);
+    }
+
+    /// A 400-word text of varied words.
+    fn chapter() -> Vec<String> {
+        (0..400).map(|i| format!("w{}", (i * 7919) % 1000)).collect()
+    }
+
+    #[test]
+    fn a_tenth_of_the_words_changed_stays_similar() {
+        let original = chapter();
+        let mut scattered = original.clone();
+        for i in (5..400).step_by(10) {
+            scattered[i] = format!("changed{}", i);
+        }
+        let mut passage = original.clone();
+        for (i, word) in passage[200..240].iter_mut().enumerate() {
+            *word = format!("changed{}", i);
+        }
+        let shingle = SimilarityMethod::parse("shingle").unwrap();
+        assert!(shingle.score(&original, &passage) > 0.8, "{}", shingle.score(&original, &passage));
+        // Scattered edits touch three trigrams each, but still share most words
+        let unigram = SimilarityMethod::parse("shingle:1").unwrap();
+        assert!(unigram.score(&original, &scattered) > 0.8, "{}", unigram.score(&original, &scattered));
+        assert!(SimilarityMethod::Cosine.score(&original, &scattered) > 0.85);
+        assert!(shingle.score(&original, &scattered) > 0.5, "{}", shingle.score(&original, &scattered));
+    }
+
+    #[test]
+    fn identical_disjoint_and_empty_texts() {
+        let a = chapter();
+        let b: Vec<String> = (0..300).map(|i| format!("other{}", i)).collect();
+        for method in ["shingle", "shingle:5", "cosine"] {
+            let method = SimilarityMethod::parse(method).unwrap();
+            assert_eq!(method.score(&a, &a), 1.0);
+            assert_eq!(method.score(&a, &b), 0.0);
+            assert_eq!(method.score(&[], &[]), 1.0);
+            assert_eq!((method.score(&a, &[]), method.score(&[], &a)), (0.0, 0.0));
+        }
+        assert!((cosine(&words("a a b"), &words("a b b")) - 0.8).abs() < 1e-12);
+    }
+
+    #[test]
+    fn unknown_methods_are_rejected() {
+        for bad in ["", "jaccard", "shingle:0", "shingle:x", "Cosine"] {
+            let error = SimilarityMethod::parse(bad).err().unwrap();
+            assert!(error.contains(&format!("\"{}\"", bad)), "{}", error);
+        }
+    }
+
+    #[test]
+    fn fingerprints_estimate_shingle_similarity() {
+        let original = chapter();
+        let mut passage = original.clone();
+        for (i, word) in passage[100..200].iter_mut().enumerate() {
+            *word = format!("changed{}", i);
+        }
+        let exact = shingle_jaccard(&original, &passage, DEFAULT_SHINGLE_SIZE);
+        let (a, b) = (fingerprint(&original, DEFAULT_SHINGLE_SIZE), fingerprint(&passage, DEFAULT_SHINGLE_SIZE));
+        assert_eq!(a.minhashes.len(), FINGERPRINT_SLOTS);
+        let estimate = compare_fingerprints(&a, &b).unwrap();
+        // Within three standard errors
+        assert!((estimate - exact).abs() < 3.0 / (FINGERPRINT_SLOTS as f64).sqrt(), "{} vs {}", estimate, exact);
+        assert_eq!(compare_fingerprints(&a, &a).unwrap(), 1.0);
+
+        let empty = fingerprint(&[], DEFAULT_SHINGLE_SIZE);
+        assert_eq!((compare_fingerprints(&empty, &empty).unwrap(), compare_fingerprints(&a, &empty).unwrap()), (1.0, 0.0));
+        assert!(compare_fingerprints(&a, &fingerprint(&original, 4)).is_err());
+        // Pinned so stored fingerprints stay comparable across builds
+        assert_eq!(fingerprint(&words("the storm came"), 3).minhashes[..2], [1574253108, 4268048138]);
+    }
+
+    #[test]
+    fn similarity_ignores_case_and_punctuation() {
+        let processor = TextProcessor::new();
+        let a = "The storm came in from the hills, and Mara waited.";
+        let b = "the STORM came in from the hills; and mara waited!";
+        for method in ["shingle", "shingle:2", "cosine"] {
+            assert_eq!(processor.similarity(a, b, method).ok(), Some(1.0), "{}", method);
+            assert_eq!(processor.similarity(a, "", method).ok(), Some(0.0), "{}", method);
+        }
+    }
+}
@@ -0,0 +1,390 @@
+//! Spell-check-lite: words used once or twice that are in neither a bundled
+//! English word list nor the user's dictionary, with the nearest listed word
+//! offered as the fix when only one is a likely slip away.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use crate::config::SuggestionConfig;
+use crate::rewrite;
+use crate::segmentation::{self, DocumentIndex};
+use crate::suggestions;
+use crate::OptimizationSuggestion;
+
+/// About 95,000 lowercase words: the 82,765 of the SymSpell English
+/// frequency dictionary, inflections included (see `words_en.LICENSE`), and
+/// a curated list of base forms with their regular inflections. Sorted and
+/// front-coded: each entry is a capital letter giving how many leading
+/// characters it shares with the previous entry ('A' for none), then the
+/// rest of the word. Line breaks only keep the file readable.
+const WORD_LIST: &str = include_str!("words_en.txt");
+
+/// A word used more often than this is deliberate (an invented name, a term
+/// of art), however it is spelled.
+const MAX_TYPO_USES: usize = 2;
+
+/// Prefixes making new words of listed ones ("undisguised").
+const PREFIXES: &[&str] = &["un", "non", "re", "pre", "mis", "dis", "il", "ir", "anti", "inter", "mid", "multi", "semi", "over", "under", "out", "self"];
+
+/// Letters in the shortest word a prefix or compound is built on.
+const MIN_PART: usize = 3;
+
+/// Letters tried when editing a word; the apostrophe lets "dont" find
+/// "don't".
+const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz'";
+
+/// Letters in the shortest base word a regular inflection is built on.
+const MIN_INFLECTED_BASE: usize = 4;
+
+/// The letter rows of a QWERTY keyboard, top first.
+const KEYBOARD_ROWS: [&[u8]; 3] = [b"qwertyuiop", b"asdfghjkl", b"zxcvbnm"];
+
+/// The bundled list, decoded on first use.
+fn bundled_words() -> &'static HashSet<String> {
+    static WORDS: OnceLock<HashSet<String>> = OnceLock::new();
+    WORDS.get_or_init(|| {
+        let mut words = HashSet::new();
+        let mut word = String::new();
+        let mut chars = WORD_LIST.chars().filter(|&c| c != '\n').peekable();
+        while let Some(shared) = chars.next() {
+            word.truncate((shared as u8 - b'A') as usize);
+            while let Some(c) = chars.next_if(|c| !c.is_ascii_uppercase()) {
+                word.push(c);
+            }
+            words.insert(word.clone());
+        }
+        words
+    })
+}
+
+/// Lowercase, with typographic apostrophes made straight.
+fn normalize(word: &str) -> String {
+    word.trim().replace('\u{2019}', "'").to_lowercase()
+}
+
+fn is_vowel(c: u8) -> bool {
+    b"aeiou".contains(&c)
+}
+
+/// Whether `word` ends in a consonant after a single vowel ("pad", "occur"),
+/// which doubles before a vowel-initial ending often enough that an
+/// undoubled inflection ("occured") is suspect.
+fn doubles_final_consonant(word: &[u8]) -> bool {
+    matches!(word, [.., a, b, c] if !is_vowel(*a) && is_vowel(*b) && !is_vowel(*c) && !b"wxy".contains(c))
+}
+
+/// The base words `word` is a regular inflection of: plurals and third
+/// persons ("fuzzes"), past and present participles and comparatives with
+/// the final "e" dropped or the final consonant doubled ("hoped",
+/// "padded"), "-y" made "-i-" ("tidier"), and "-ly" adverbs.
+fn regular_bases(word: &str) -> Vec<String> {
+    let mut bases = Vec::new();
+    let sibilant = |stem: &str| ["s", "x", "z", "ch", "sh"].iter().any(|end| stem.ends_with(end));
+    if let Some(stem) = word.strip_suffix("ies").or_else(|| word.strip_suffix("ied")) {
+        bases.push(format!("{}y", stem));
+    } else if let Some(stem) = word.strip_suffix("es").filter(|stem| sibilant(stem)) {
+        bases.push(stem.to_string());
+    } else if let Some(stem) = word.strip_suffix('s').filter(|stem| !sibilant(stem) && !stem.ends_with('y')) {
+        bases.push(stem.to_string());
+    }
+    for ending in ["ed", "ing", "er", "est"] {
+        let Some(stem) = word.strip_suffix(ending) else { continue };
+        let bytes = stem.as_bytes();
+        match bytes {
+            [.., a, b] if a == b && !is_vowel(*b) && doubles_final_consonant(&bytes[..bytes.len() - 1]) => {
+                bases.push(stem[..stem.len() - 1].to_string());
+            }
+            [.., b'i'] if ending != "ing" => bases.push(format!("{}y", &stem[..stem.len() - 1])),
+            _ if !doubles_final_consonant(bytes) => bases.push(stem.to_string()),
+            _ => {}
+        }
+        bases.push(format!("{}e", stem));
+    }
+    if let Some(stem) = word.strip_suffix("ily") {
+        bases.push(format!("{}y", stem));
+    } else if let Some(stem) = word.strip_suffix("ly") {
+        bases.push(stem.to_string());
+    }
+    bases.retain(|base| base.len() >= MIN_INFLECTED_BASE);
+    bases
+}
+
+/// Row and column of a letter on a QWERTY keyboard.
+fn key(c: u8) -> Option<(usize, usize)> {
+    KEYBOARD_ROWS.iter().enumerate().find_map(|(row, keys)| keys.iter().position(|&k| k == c).map(|column| (row, column)))
+}
+
+/// Whether `a` and `b` are the same letter or neighbouring keys. Each row
+/// sits half a key right of the one above, so a key touches the one above
+/// it and the one to that key's right.
+fn near(a: u8, b: u8) -> bool {
+    let (Some((row_a, column_a)), Some((row_b, column_b))) = (key(a), key(b)) else { return a == b };
+    let ((upper, upper_column), (lower, lower_column)) =
+        if row_a <= row_b { ((row_a, column_a), (row_b, column_b)) } else { ((row_b, column_b), (row_a, column_a)) };
+    match lower - upper {
+        0 => upper_column.abs_diff(lower_column) <= 1,
+        1 => upper_column == lower_column || upper_column == lower_column + 1,
+        _ => false,
+    }
+}
+
+/// Every string one likely slip away from `word`, each with whether it is
+/// the transposition: two letters swapped, or past the first letter one
+/// struck on a neighbouring key, one doubled or added beside a
+/// neighbouring key, or one dropped beside a letter it doubles or
+/// neighbours. A missing apostrophe counts anywhere. Edits a slip seldom
+/// makes ("infections" for "inflections") are left out, so a word the list
+/// happens to lack is not "corrected" into another.
+fn edits(word: &str) -> Vec<(String, bool)> {
+    let bytes = word.as_bytes();
+    let beside = |i: usize, c: u8| (i > 0 && near(c, bytes[i - 1])) || bytes.get(i).is_some_and(|&next| near(c, next));
+    let mut edits = Vec::new();
+    let mut push = |edit: Vec<u8>, transposed: bool| edits.push((String::from_utf8(edit).unwrap_or_default(), transposed));
+    for i in 0..=bytes.len() {
+        let (head, tail) = bytes.split_at(i);
+        if let [c, rest @ ..] = tail {
+            if i > 0 && (near(*c, bytes[i - 1]) || rest.first().is_some_and(|&next| near(*c, next))) {
+                push([head, rest].concat(), false);
+            }
+        }
+        if let [a, b, rest @ ..] = tail {
+            push([head, &[*b, *a], rest].concat(), true);
+        }
+        for &c in ALPHABET {
+            if let [old, rest @ ..] = tail {
+                if i > 0 && *old != c && near(*old, c) {
+                    push([head, &[c], rest].concat(), false);
+                }
+            }
+            if c == b'\'' || (i > 0 && beside(i, c)) {
+                push([head, &[c], tail].concat(), false);
+            }
+        }
+    }
+    edits
+}
+
+/// Keeps the `candidates` that pass `prefer`, if there is more than one
+/// candidate and any passes.
+fn narrow<T>(candidates: &mut Vec<T>, prefer: impl Fn(&T) -> bool) {
+    if candidates.len() > 1 && candidates.iter().any(&prefer) {
+        candidates.retain(prefer);
+    }
+}
+
+/// Where a word is used, whether it is ever used as a name, and whether it
+/// is ever written in lowercase.
+#[derive(Default)]
+struct Uses {
+    spans: Vec<(usize, usize)>,
+    name: bool,
+    lowercase: bool,
+}
+
+/// The bundled list plus the user's dictionary words and ignored words.
+pub struct Dictionary {
+    /// Accepted and offered as replacements.
+    words: HashSet<String>,
+    /// Accepted but never offered.
+    ignored: HashSet<String>,
+}
+
+impl Dictionary {
+    pub fn from_config(config: &SuggestionConfig) -> Dictionary {
+        let normalized = |words: &[String]| words.iter().map(|w| normalize(w)).filter(|w| !w.is_empty()).collect();
+        Dictionary { words: normalized(&config.dictionary_words), ignored: normalized(&config.ignored_words) }
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        bundled_words().contains(word) || self.words.contains(word)
+    }
+
+    /// Whether `word` is listed, or is a regular inflection of a listed word.
+    fn is_listed(&self, word: &str) -> bool {
+        self.is_word(word) || regular_bases(word).iter().any(|base| self.is_word(base))
+    }
+
+    /// Whether `word` (normalized), or the word it is the possessive of, is
+    /// listed (or inflects a listed word) or ignored, or is a listed word
+    /// with a prefix or two run together ("horseshoe").
+    fn accepts(&self, word: &str) -> bool {
+        let owner = word.strip_suffix("'s").or_else(|| word.strip_suffix('\'')).unwrap_or(word);
+        [word, owner].into_iter().any(|w| {
+            self.is_listed(w)
+                || self.ignored.contains(w)
+                || PREFIXES.iter().any(|p| w.strip_prefix(p).is_some_and(|rest| rest.len() >= MIN_PART && self.is_listed(rest)))
+                || (MIN_PART..=w.len().saturating_sub(MIN_PART)).any(|i| self.is_word(&w[..i]) && self.is_listed(&w[i..]))
+        })
+    }
+
+    /// The listed word nearest `word`: the only one a likely slip away, with
+    /// ties going to a swap of two letters (the commonest slip), then to a
+    /// missing apostrophe, then to a word with the same first letter.
+    fn nearest(&self, word: &str) -> Option<String> {
+        let first = word.as_bytes().first();
+        let mut close: Vec<(String, bool)> = edits(word).into_iter().filter(|(e, _)| self.is_word(e)).collect();
+        close.sort();
+        close.dedup();
+        narrow(&mut close, |&(_, transposed)| transposed);
+        narrow(&mut close, |(e, _)| e.len() == word.len() + 1 && e.replace('\'', "") == word);
+        narrow(&mut close, |(e, _)| e.as_bytes().first() == first);
+        (close.len() == 1).then(|| close.remove(0).0)
+    }
+
+    /// One `possible_typo` suggestion per use of each unknown word used at
+    /// most twice. Capitalized words other than sentence openers are taken
+    /// for names (as is a word ever capitalized mid-sentence), as are
+    /// all-caps and mixed-case words, and so is a capitalized opener never
+    /// written in lowercase unless it is a likely slip ("Teh"). `opaque` words (pasted data) are
+    /// skipped, and so is any quotation written in dialect when
+    /// `protect_dialogue_dialect` is set.
+    pub fn suggestions(
+        &self,
+        index: &DocumentIndex,
+        dialogue_spans: &[(usize, usize)],
+        opaque: impl Fn(&str) -> bool,
+        config: &SuggestionConfig,
+    ) -> Vec<OptimizationSuggestion> {
+        let text = index.text;
+        let openers: HashSet<usize> =
+            index.sentences.iter().filter_map(|&(start, end)| index.words_in(start, end).first().map(|w| w.0)).collect();
+        let dialect: Vec<(usize, usize)> = if config.protect_dialogue_dialect {
+            dialogue_spans
+                .iter()
+                .copied()
+                .filter(|&(start, end)| index.words_in(start, end).iter().any(|w| suggestions::is_protected_dialect(text, w.0, w.1, config)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut uses: HashMap<String, Uses> = HashMap::new();
+        for &(start, end) in &index.words {
+            let word = &text[start..end];
+            if opaque(word) || !word.chars().all(|c| c.is_ascii_alphabetic() || c == '\'' || c == '\u{2019}') {
+                continue;
+            }
+            let entry = uses.entry(normalize(word)).or_default();
+            entry.spans.push((start, end));
+            let capitalized = word.starts_with(|c: char| c.is_ascii_uppercase());
+            entry.name |= word.chars().skip(1).any(|c| c.is_ascii_uppercase()) || (capitalized && !openers.contains(&start));
+            entry.lowercase |= !capitalized;
+        }
+
+        let mut found: Vec<OptimizationSuggestion> = Vec::new();
+        for (word, Uses { spans, name, lowercase }) in uses {
+            if name || spans.len() > MAX_TYPO_USES || self.accepts(&word) {
+                continue;
+            }
+            let nearest = self.nearest(&word);
+            if !lowercase && nearest.is_none() {
+                continue;
+            }
+            for (start, end) in spans {
+                if segmentation::within(&dialect, start, end) {
+                    continue;
+                }
+                let original = &text[start..end];
+                let replacement = nearest.as_deref().map(|fix| rewrite::match_case(original, fix));
+                let message = match &replacement {
+                    Some(fix) => format!("\"{}\" is not in the dictionary; did you mean \"{}\"?", original, fix),
+                    None => format!("\"{}\" is not in the dictionary; check the spelling, or add it to the dictionary if it is intended.", original),
+                };
+                found.push(OptimizationSuggestion {
+                    suggested_replacement: replacement,
+                    ..OptimizationSuggestion::instance("possible_typo", "medium", message, start, end)
+                });
+            }
+        }
+        found.sort_by_key(|s| s.start_pos);
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    #[test]
+    fn possible_typos_suggest_the_nearest_word() {
+        let text = "Teh storm broke over teh hills. \"Ah reckon it's comin' fer us,\" the old farmer muttered.";
+        let processor = TextProcessor::new();
+        let suggestions = processor.generate_optimization_suggestions(text, &|rule| rule == "possible_typo");
+        let fixes: Vec<(&str, Option<&str>)> =
+            suggestions.iter().map(|s| (&text[s.start_pos..s.end_pos], s.suggested_replacement.as_deref())).collect();
+        assert_eq!(fixes, [("Teh", Some("The")), ("teh", Some("the"))]);
+        assert!(suggestions[1].message.contains("did you mean \"the\""));
+    }
+
+    #[test]
+    fn possible_typos_skip_frequent_names_and_dictionary_words() {
+        let named = "Zorvath climbed the stair. ".repeat(15) + "The guard saluted.";
+        let mut processor = TextProcessor::new();
+        assert!(processor.generate_optimization_suggestions(&named, &|rule| rule == "possible_typo").is_empty());
+
+        let text = "They baked zelpbread at dawn, and the zelpbrad burned.";
+        let typos = |processor: &TextProcessor| -> Vec<String> {
+            let suggestions = processor.optimize_text(text);
+            suggestions.iter().filter(|s| s.suggestion_type == "possible_typo").map(|s| text[s.start_pos..s.end_pos].to_string()).collect()
+        };
+        assert_eq!(typos(&processor), ["zelpbread", "zelpbrad"]);
+        processor.add_dictionary_words(vec!["zelpbread".to_string()]);
+        assert_eq!(typos(&processor), ["zelpbrad"]);
+        let suggestions = processor.generate_optimization_suggestions(text, &|rule| rule == "possible_typo");
+        assert_eq!(suggestions[0].suggested_replacement.as_deref(), Some("zelpbread"));
+        processor.ignore_word("zelpbrad");
+        assert!(typos(&processor).is_empty());
+    }
+
+    #[test]
+    fn plain_english_prose_is_not_flagged() {
+        let text = "Margaret read the draft twice before she said anything. The prices had gone up again, and the \
+            price of paper alone was closer to ruin than she liked. Her editor's notes were substantive but \
+            contradictory: one page asked for fewer citations, the next for more, and a third complained that \
+            the narration padded every scene. He had circled each pronoun with a fuzzy pencil, and the pronouns \
+            in the opening chapter were, he claimed, a cognitive burden.\n\n\
+            She clamped the pages together. A hyphen here, a comma there; the inflections of a voice were not \
+            degenerate just because they were hers. The regressions he feared were zeros in a ledger. Outside, \
+            kelp dried on the rocks. Her old kettle still fuzzes and ticks when it boils. She didn't answer his letter \
+            until morning, and when she did, it was kind.";
+        let processor = TextProcessor::new();
+        let flagged: Vec<&str> = processor
+            .generate_optimization_suggestions(text, &|rule| rule == "possible_typo")
+            .iter()
+            .map(|s| &text[s.start_pos..s.end_pos])
+            .collect();
+        assert!(flagged.is_empty(), "{:?}", flagged);
+    }
+
+    #[test]
+    fn only_likely_slips_get_a_replacement() {
+        let dictionary = Dictionary::from_config(&SuggestionConfig::default());
+        // Swapped, doubled, dropped beside a neighbouring key, and a missing
+        // apostrophe
+        for (typo, fix) in [("teh", "the"), ("recieve", "receive"), ("occured", "occurred"), ("goverment", "government"), ("dont", "don't")] {
+            assert!(!dictionary.accepts(typo), "{}", typo);
+            assert_eq!(dictionary.nearest(typo).as_deref(), Some(fix), "{}", typo);
+        }
+        // A letter no key near it would have struck
+        assert!(dictionary.nearest("kelq").is_none());
+        assert!(dictionary.nearest("inflecions").is_none());
+        // Regular inflections of listed words are words
+        for word in ["fuzzes", "padded", "clamped", "closer", "tidier"] {
+            assert!(dictionary.accepts(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn a_capitalized_opener_is_a_name_unless_it_is_a_likely_slip() {
+        let processor = TextProcessor::new();
+        let flagged = |text: &str| -> Vec<String> {
+            let suggestions = processor.generate_optimization_suggestions(text, &|rule| rule == "possible_typo");
+            suggestions.iter().map(|s| text[s.start_pos..s.end_pos].to_string()).collect()
+        };
+        assert!(flagged("Zorvenna waited by the gate.").is_empty());
+        assert_eq!(flagged("Hte gate was shut."), ["Hte"]);
+        // Written in lowercase too, it is not a name
+        assert_eq!(flagged("Zorvenna waited. The zorvenna was gone."), ["Zorvenna", "zorvenna"]);
+    }
+}
@@ -0,0 +1,246 @@
+//! Analysis of a document fed in chunks. Each run of whole paragraphs is
+//! measured once it is complete and folded into running totals, so the
+//! text kept is only the unfinished tail; what grows with the document is
+//! the vocabulary and one word id per word, which MTLD's backward pass
+//! needs.
+
+use base64::{engine::general_purpose, Engine as _};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+use crate::front_matter::{self, FrontMatter};
+use crate::interned::WordTable;
+use crate::language::ForeignParagraphs;
+use crate::segmentation::DocumentIndex;
+
+/// The counts and sums every `TextAnalysisResult` metric is computed from.
+/// Measuring a text whole and measuring it a run of paragraphs at a time
+/// (cut where `last_cut` allows) end with equal totals.
+#[derive(Default)]
+pub struct Totals {
+    /// Some of the text is not whitespace.
+    pub nonblank: bool,
+    pub words: usize,
+    pub characters: usize,
+    pub graphemes: usize,
+    pub sentences: usize,
+    pub paragraphs: usize,
+    /// Words other than opaque tokens, and their syllables and letters.
+    pub measured_words: usize,
+    pub syllables: usize,
+    pub complex_words: usize,
+    pub polysyllables: usize,
+    pub letters: usize,
+    pub long_words: usize,
+    /// Characters of the longest measured word.
+    pub longest_word: usize,
+    pub content_words: usize,
+    pub content_characters: usize,
+    pub content_vocabulary: HashSet<u32>,
+    /// Lowercase ids of the distinct words.
+    pub vocabulary: HashSet<u32>,
+    /// The lowercase id of every word, in text order.
+    pub word_ids: Vec<u32>,
+    /// Every word seen, measured; shared across the documents of a batch.
+    pub word_table: WordTable,
+    pub sentence_lengths: Vec<f64>,
+    /// Alphabetic characters outside opaque tokens, and how many of them are
+    /// not Latin.
+    pub alphabetic: usize,
+    pub non_latin: usize,
+    pub terminal_punctuation: bool,
+    pub passive_voice: usize,
+    pub adverbs: usize,
+    pub dialogue_sentences: usize,
+    pub action_sentences: usize,
+    pub description_sentences: usize,
+    /// Sentences with a filter word.
+    pub filtered_sentences: usize,
+    pub cliches: usize,
+    pub dialogue_words: usize,
+    pub first_person: usize,
+    /// Valence of the scored words, summed in text order.
+    pub valence: f64,
+    /// Opaque tokens in whole-text offsets, and the text of the first.
+    pub opaque_tokens: Vec<(usize, usize)>,
+    pub first_opaque: Option<String>,
+    /// `None` when paragraph languages are not detected.
+    pub foreign: Option<ForeignParagraphs>,
+}
+
+impl Totals {
+    pub fn new(foreign: Option<ForeignParagraphs>) -> Totals {
+        Totals { foreign, ..Totals::default() }
+    }
+}
+
+pub struct Stream {
+    hasher: Sha256,
+    /// Text after the last measured run.
+    pub pending: String,
+    /// Offset of `pending` in the whole text.
+    pub consumed: usize,
+    pub totals: Totals,
+    /// The whole text, for configurations that need it at once.
+    pub whole: Option<String>,
+    /// Nothing is measured while the text may still open with front matter.
+    pub awaiting_front_matter: bool,
+    pub front_matter: Option<FrontMatter>,
+}
+
+impl Stream {
+    pub fn new(totals: Totals, buffered: bool) -> Stream {
+        Stream {
+            hasher: Sha256::new(),
+            pending: String::new(),
+            consumed: 0,
+            totals,
+            whole: buffered.then(String::new),
+            awaiting_front_matter: true,
+            front_matter: None,
+        }
+    }
+
+    pub fn push(&mut self, chunk: &str) {
+        self.hasher.update(chunk.as_bytes());
+        self.whole.as_mut().unwrap_or(&mut self.pending).push_str(chunk);
+    }
+
+    /// Drops the first `len` bytes of `pending`, which have been measured.
+    pub fn advance(&mut self, len: usize) {
+        self.pending.drain(..len);
+        self.consumed += len;
+    }
+
+    /// Reads the front matter opening the text once more text can't change
+    /// it, or at the end of the stream (`finished`), and with `skip` steps
+    /// past it unmeasured.
+    pub fn settle_front_matter(&mut self, skip: bool, finished: bool) {
+        self.awaiting_front_matter = !finished && front_matter::may_open(&self.pending);
+        if self.awaiting_front_matter {
+            return;
+        }
+        self.front_matter = front_matter::parse(&self.pending);
+        if let Some(end) = self.front_matter.as_ref().filter(|_| skip).map(|f| f.end_pos) {
+            self.advance(end);
+        }
+    }
+
+    /// `generate_content_hash` of everything pushed.
+    pub fn content_hash(self) -> String {
+        general_purpose::STANDARD.encode(self.hasher.finalize())
+    }
+}
+
+/// Whether any span strictly contains `offset`.
+fn straddles(spans: &[(usize, usize)], offset: usize) -> bool {
+    let i = spans.partition_point(|s| s.1 <= offset);
+    spans.get(i).is_some_and(|s| s.0 < offset)
+}
+
+/// The latest offset of `index` where its text can be cut so measuring each
+/// side alone adds up to measuring it whole: the start of a paragraph's
+/// first line, with no sentence or quotation (`quotations`) running across
+/// it, and more than whitespace, hyphens, and commas between the words
+/// either side so no cliché can. The last paragraph is never cut off, as
+/// it may go on in the next chunk.
+pub fn last_cut(index: &DocumentIndex, quotations: &[(usize, usize)]) -> Option<usize> {
+    let text = index.text;
+    index.paragraphs.iter().skip(1).rev().map(|p| text[..p.0].rfind('\n').map_or(0, |i| i + 1)).find(|&cut| {
+        let next = index.words.partition_point(|w| w.0 < cut);
+        let joinable = match (next.checked_sub(1).map(|i| index.words[i]), index.words.get(next)) {
+            (Some(before), Some(after)) => text[before.1..after.0].chars().all(|c| c.is_whitespace() || c == '-' || c == ','),
+            _ => false,
+        };
+        cut > 0
+            && !joinable
+            && !straddles(&index.sentences, cut)
+            && !straddles(&index.paragraphs, cut)
+            && !straddles(quotations, cut)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{TextAnalysisResult, TextProcessor};
+    use crate::test_support::Cases;
+
+    const FRONT_MATTER: &str = "---\ntitle: \"The Long Road\"\nauthor: A. Writer\ngenre: literary fiction\ntarget_words: 20\n---\n";
+
+    const STREAM_FIXTURE: &str = "# Chapter One\n\nThe letter was written by hand. She read it twice, slowly, and at the end of the day \
+she burned it.\n\n\u{201C}Don\u{2019}t go,\u{201D} he said. \u{201C}Not yet.\n\n\u{201C}Stay until the morning.\u{201D}\n\n\
+It was a piece\nof cake, she thought. I felt the cold.\n\n    I quoted this line from the report.\n\n\
+Le chat est sur la table et il dort dans la maison avec le chien.\n\n\
+The checksum aGVsbG8gd29ybGQgdGhpcyBpcyBhIHZlcnkgbG9uZyB0b2tlbiB0aGF0IGtlZXBzIGdvaW5nIG9u was pasted in\r\n\r\nThe end.";
+
+    /// `text` cut at `cuts` random interior char boundaries.
+    fn random_chunks<'t>(text: &'t str, cases: &mut Cases, cuts: usize) -> Vec<&'t str> {
+        let boundaries: Vec<usize> = (1..text.len()).filter(|&i| text.is_char_boundary(i)).collect();
+        let mut offsets: Vec<usize> = (0..cuts).map(|_| boundaries[cases.below(boundaries.len())]).collect();
+        offsets.extend([0, text.len()]);
+        offsets.sort_unstable();
+        offsets.windows(2).map(|w| &text[w[0]..w[1]]).collect()
+    }
+
+    fn stream_analysis(processor: &mut TextProcessor, chunks: &[&str]) -> TextAnalysisResult {
+        processor.start_stream_analysis();
+        for chunk in chunks {
+            processor.feed_chunk(chunk).unwrap();
+        }
+        processor.finish_stream_analysis().unwrap()
+    }
+
+    #[test]
+    fn stream_analysis_matches_one_shot_at_random_splits() {
+        let mut processor = TextProcessor::new();
+        let expected = serde_json::to_value(processor.analyze_text(STREAM_FIXTURE)).unwrap();
+        let codes: Vec<&str> = expected["warnings"].as_array().unwrap().iter().filter_map(|w| w["code"].as_str()).collect();
+        assert!(codes.contains(&"foreign_language_excluded") && codes.contains(&"opaque_tokens"));
+        let mut cases = Cases(0x9E37_79B9_7F4A_7C15);
+        for round in 0..60 {
+            let chunks = random_chunks(STREAM_FIXTURE, &mut cases, 1 + round % 12);
+            let streamed = serde_json::to_value(stream_analysis(&mut processor, &chunks)).unwrap();
+            assert_eq!(streamed, expected, "chunks {:?}", chunks);
+        }
+
+        // Paragraphs are measured as they complete rather than kept
+        processor.start_stream_analysis();
+        for line in STREAM_FIXTURE.split_inclusive('\n') {
+            processor.feed_chunk(line).unwrap();
+        }
+        assert!(processor.stream.as_ref().unwrap().pending.len() < STREAM_FIXTURE.len() / 4);
+        assert_eq!(serde_json::to_value(processor.finish_stream_analysis().unwrap()).unwrap(), expected);
+    }
+
+    #[test]
+    fn stream_analysis_of_buffered_configurations_and_misuse() {
+        let chunks = ["Short text. ", "", "Over \n", "\n two chunks."];
+        for language in ["auto", "en"] {
+            let mut processor = TextProcessor::with_language(language);
+            let expected = serde_json::to_value(processor.analyze_text(&chunks.concat())).unwrap();
+            assert_eq!(serde_json::to_value(stream_analysis(&mut processor, &chunks)).unwrap(), expected);
+        }
+        let mut processor = TextProcessor::new();
+        assert_eq!(stream_analysis(&mut processor, &[" \n", "\t"]).warnings[0].code, "empty_input");
+        assert!(processor.feed_chunk("text").is_err());
+        assert!(processor.finish_stream_analysis().is_err());
+    }
+
+    #[test]
+    fn stream_analysis_skips_front_matter_wherever_the_chunks_split_it() {
+        let mut processor = TextProcessor::new();
+        let text = format!("{}{}", FRONT_MATTER, STREAM_FIXTURE);
+        let expected = serde_json::to_value(processor.analyze_text(&text)).unwrap();
+        assert!(expected["wordCount"].is_u64());
+        assert_eq!(expected["wordCount"], serde_json::to_value(processor.analyze_text(STREAM_FIXTURE)).unwrap()["wordCount"]);
+        let mut cases = Cases(0x2545_F491_4F6C_DD1D);
+        for round in 0..30 {
+            let chunks = random_chunks(&text, &mut cases, 1 + round % 8);
+            assert_eq!(serde_json::to_value(stream_analysis(&mut processor, &chunks)).unwrap(), expected, "chunks {:?}", chunks);
+        }
+
+        // An unterminated block is body text
+        let chunks = ["---\ntitle: Dr", "aft\ntarget_words: 10\nThe letter ", "was written by the clerk."];
+        assert_eq!(stream_analysis(&mut processor, &chunks).word_count, 11);
+    }
+}
@@ -0,0 +1,181 @@
+//! Paragraph-level structural checks: unbalanced quotation marks, repeated
+//! paragraphs, and paragraphs missing terminal punctuation. Finer-grained
+//! punctuation mechanics live in `typography`.
+
+use std::collections::HashMap;
+
+use crate::segmentation::DocumentIndex;
+use crate::{OptimizationSuggestion, RelatedSpan};
+
+// Paragraphs shorter than this are usually headings, captions, or list items
+const MIN_WORDS_FOR_PUNCTUATION: usize = 8;
+const MIN_WORDS_FOR_REPEAT: usize = 5;
+
+const TERMINAL_CHARS: &[char] =
+    &['.', '!', '?', '\u{2026}', ':', ';', '"', '\u{201D}', '\u{2019}', '\'', ')', ']', '*', '_'];
+
+/// Opening minus closing quotation marks. Straight quotes alternate, so an
+/// odd count leaves one open.
+fn open_quotes(paragraph: &str) -> i64 {
+    let straight = paragraph.matches('"').count() as i64;
+    let opening = paragraph.matches('\u{201C}').count() as i64;
+    let closing = paragraph.matches('\u{201D}').count() as i64;
+    straight % 2 + opening - closing
+}
+
+/// Position of the quotation mark left unclosed in `paragraph`: the last
+/// straight quote when there is an odd number, else the last curly opener
+/// without a closer, else the first curly closer without an opener.
+fn unclosed_quote(paragraph: &str) -> Option<usize> {
+    let mut straight = None;
+    let mut opening: Vec<usize> = Vec::new();
+    let mut stray_closing = None;
+    for (i, c) in paragraph.char_indices() {
+        match c {
+            '"' => straight = if straight.is_some() { None } else { Some(i) },
+            '\u{201C}' => opening.push(i),
+            '\u{201D}' if opening.pop().is_none() => stray_closing = stray_closing.or(Some(i)),
+            _ => {}
+        }
+    }
+    straight.or(opening.last().copied()).or(stray_closing)
+}
+
+fn starts_with_quote(paragraph: &str) -> bool {
+    paragraph.starts_with(['"', '\u{201C}'])
+}
+
+pub fn unbalanced_quotes(index: &DocumentIndex) -> Vec<OptimizationSuggestion> {
+    let text = index.text;
+    let mut suggestions = Vec::new();
+    for (i, &(start, end)) in index.paragraphs.iter().enumerate() {
+        let paragraph = &text[start..end];
+        let open = open_quotes(paragraph);
+        // Speech running across paragraphs conventionally leaves each
+        // paragraph open and reopens the next one
+        let continues_speech = open > 0
+            && index.paragraphs.get(i + 1).is_some_and(|&(s, e)| starts_with_quote(&text[s..e]));
+        if open != 0 && !continues_speech {
+            // Point at the mark itself, with the paragraph for context
+            let (mark_start, mark_end) = match unclosed_quote(paragraph) {
+                Some(at) => (start + at, start + at + paragraph[at..].chars().next().map_or(1, char::len_utf8)),
+                None => (start, end),
+            };
+            suggestions.push(OptimizationSuggestion {
+                related_spans: vec![RelatedSpan { start_pos: start, end_pos: end }],
+                ..OptimizationSuggestion::instance(
+                    "unbalanced_quotes",
+                    "medium",
+                    "This paragraph has an unmatched quotation mark.",
+                    mark_start,
+                    mark_end,
+                )
+            });
+        }
+    }
+    suggestions
+}
+
+pub fn repeated_paragraphs(index: &DocumentIndex) -> Vec<OptimizationSuggestion> {
+    let text = index.text;
+    let mut seen: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut suggestions = Vec::new();
+    for &(start, end) in &index.paragraphs {
+        if index.words_in(start, end).len() < MIN_WORDS_FOR_REPEAT {
+            continue;
+        }
+        let key = text[start..end].split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        match seen.get(&key) {
+            Some(&(first_start, first_end)) => suggestions.push(OptimizationSuggestion {
+                related_spans: vec![
+                    RelatedSpan { start_pos: first_start, end_pos: first_end },
+                    RelatedSpan { start_pos: start, end_pos: end },
+                ],
+                ..OptimizationSuggestion::instance(
+                    "repeated_paragraph",
+                    "medium",
+                    "This paragraph repeats an earlier one word for word.",
+                    start,
+                    end,
+                )
+            }),
+            None => {
+                seen.insert(key, (start, end));
+            }
+        }
+    }
+    suggestions
+}
+
+pub fn missing_punctuation(index: &DocumentIndex) -> Vec<OptimizationSuggestion> {
+    let text = index.text;
+    let mut suggestions = Vec::new();
+    for &(start, end) in &index.paragraphs {
+        let paragraph = &text[start..end];
+        if paragraph.starts_with('#') || index.words_in(start, end).len() < MIN_WORDS_FOR_PUNCTUATION {
+            continue;
+        }
+        if paragraph.ends_with(TERMINAL_CHARS) {
+            continue;
+        }
+        let Some(&(word_start, word_end)) = index.words_in(start, end).last() else { continue };
+        suggestions.push(OptimizationSuggestion::instance(
+            "missing_punctuation",
+            "low",
+            "This paragraph does not end with terminal punctuation.",
+            word_start,
+            word_end.max(end),
+        ));
+    }
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    fn flagged(text: &str, check: fn(&DocumentIndex) -> Vec<OptimizationSuggestion>) -> Vec<String> {
+        let index = TextProcessor::new().index(text);
+        check(&index).iter().map(|s| text[s.start_pos..s.end_pos].to_string()).collect()
+    }
+
+    #[test]
+    fn unmatched_quotes_are_flagged_unless_speech_continues() {
+        let text = "\u{201C}Come in,\u{201D} she said.\n\n\"It was late, he began.\n\n\"And then the rain came.\"\n\nHe shouted \"stop and ran.";
+        assert_eq!(flagged(text, unbalanced_quotes), ["\""]);
+        // At the unclosed mark, with its paragraph as context
+        let found = unbalanced_quotes(&TextProcessor::new().index(text));
+        assert_eq!(found[0].start_pos, text.rfind('"').unwrap());
+        let paragraph = found[0].related_spans[0];
+        assert_eq!(&text[paragraph.start_pos..paragraph.end_pos], "He shouted \"stop and ran.");
+    }
+
+    #[test]
+    fn the_unclosed_curly_quote_is_the_one_reported() {
+        fn at(paragraph: &str) -> Option<&str> {
+            unclosed_quote(paragraph).map(|i| &paragraph[i..])
+        }
+        assert_eq!(at("\u{201C}One,\u{201D} then \u{201C}two"), Some("\u{201C}two"));
+        assert_eq!(at("Stray\u{201D} closer and \u{201C}fine\u{201D}"), Some("\u{201D} closer and \u{201C}fine\u{201D}"));
+        assert_eq!(at("\"a\" \"b\" \"c"), Some("\"c"));
+        assert_eq!(at("\"a\" \u{201C}b\u{201D}"), None);
+    }
+
+    #[test]
+    fn repeated_paragraphs_point_at_the_first_copy() {
+        let text = "The rain fell on the farm all night.\n\nShe waited.\n\nThe rain  fell on the FARM all night.\n\nShe waited.";
+        let index = TextProcessor::new().index(text);
+        let repeats = repeated_paragraphs(&index);
+        assert_eq!(repeats.len(), 1);
+        let first = repeats[0].related_spans[0];
+        assert_eq!(&text[first.start_pos..first.end_pos], "The rain fell on the farm all night.");
+        assert_eq!(&text[repeats[0].start_pos..repeats[0].end_pos], "The rain  fell on the FARM all night.");
+    }
+
+    #[test]
+    fn long_paragraphs_without_terminal_punctuation_are_flagged_at_the_last_word() {
+        let text = "# A heading without any punctuation at all here\n\nShort line\n\nThis paragraph goes on for a while and never stops\n\nThis one ends properly with a period at the end.";
+        assert_eq!(flagged(text, missing_punctuation), ["stops"]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Detection of chapter and section headings, and of the scene breaks
+//! within them.
+
+use regex::Regex;
+use serde::Serialize;
+
+pub struct Section {
+    pub heading: Option<String>,
+    pub level: usize,
+    /// Start of the heading line (or of the text for an implicit section).
+    pub start: usize,
+    /// End of the section: the start of the next heading, or end of text.
+    pub end: usize,
+    /// Opened by a scene break rather than a heading; untitled.
+    pub scene_break: bool,
+}
+
+/// A section as `extract_outline` reports it.
+#[derive(Serialize)]
+pub struct OutlineEntry {
+    pub level: usize,
+    pub title: Option<String>,
+    pub start_pos: usize,
+    /// End of the section the entry opens (not of its heading line).
+    pub end_pos: usize,
+    pub scene_break: bool,
+}
+
+impl From<&Section> for OutlineEntry {
+    fn from(section: &Section) -> OutlineEntry {
+        OutlineEntry {
+            level: section.level,
+            title: section.heading.clone(),
+            start_pos: section.start,
+            end_pos: section.end,
+            scene_break: section.scene_break,
+        }
+    }
+}
+
+pub struct HeadingPatterns {
+    markdown: Regex,
+    chapter: Regex,
+    scene_break: Regex,
+}
+
+impl HeadingPatterns {
+    pub fn new() -> HeadingPatterns {
+        HeadingPatterns {
+            markdown: Regex::new(r"(?m)^[ \t]{0,3}(#{1,6})[ \t]+(.+?)[ \t#]*$").unwrap(),
+            chapter: Regex::new(r"(?mi)^[ \t]*(chapter[ \t]+(?:\d+|[ivxlcdm]+|[a-z]+(?:[- ][a-z]+)?)\b[^\n]{0,80})$").unwrap(),
+            // "***", "* * *", "---", "# # #"
+            scene_break: Regex::new(r"(?m)^[ \t]*(?:(?:\*[ \t]*){3,}|(?:-[ \t]*){3,}|(?:#[ \t]*){3,})$").unwrap(),
+        }
+    }
+}
+
+/// Splits `text` into sections at Markdown headings and "Chapter N" lines.
+/// Text before the first heading becomes an untitled section; a document
+/// with no headings is one implicit section spanning the whole text.
+pub fn detect_sections(text: &str, patterns: &HeadingPatterns) -> Vec<Section> {
+    split(text, patterns, false)
+}
+
+/// `detect_sections`, also splitting at scene breaks. A scene break opens an
+/// untitled section one level below the heading it falls under (level 1
+/// before any heading).
+pub fn detect_outline(text: &str, patterns: &HeadingPatterns) -> Vec<Section> {
+    split(text, patterns, true)
+}
+
+fn split(text: &str, patterns: &HeadingPatterns, scene_breaks: bool) -> Vec<Section> {
+    let breaks: Vec<usize> = patterns.scene_break.find_iter(text).map(|m| m.start()).collect();
+    // (start, level, title); scene breaks have no title
+    let mut headings: Vec<(usize, usize, Option<String>)> = Vec::new();
+    for caps in patterns.markdown.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        // "# # #" is a scene break, not a heading titled "#"
+        if !breaks.contains(&whole.start()) {
+            headings.push((whole.start(), caps[1].len(), Some(caps[2].trim().to_string())));
+        }
+    }
+    for caps in patterns.chapter.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        if !headings.iter().any(|h| h.0 == whole.start()) {
+            headings.push((whole.start(), 1, Some(caps[1].trim().to_string())));
+        }
+    }
+    headings.sort_by_key(|h| h.0);
+    if scene_breaks {
+        let scenes: Vec<_> = breaks
+            .iter()
+            .map(|&start| (start, headings.iter().rev().find(|h| h.0 < start).map_or(1, |h| h.1 + 1), None))
+            .collect();
+        headings.extend(scenes);
+        headings.sort_by_key(|h| h.0);
+    }
+
+    let mut sections = Vec::new();
+    let first_start = headings.first().map_or(text.len(), |h| h.0);
+    if !text[..first_start].trim().is_empty() || headings.is_empty() {
+        sections.push(Section { heading: None, level: 0, start: 0, end: first_start, scene_break: false });
+    }
+    for (i, (start, level, title)) in headings.iter().enumerate() {
+        let end = headings.get(i + 1).map_or(text.len(), |next| next.0);
+        sections.push(Section { heading: title.clone(), level: *level, start: *start, end, scene_break: title.is_none() });
+    }
+    sections
+}
+
+impl Default for HeadingPatterns {
+    fn default() -> Self {
+        HeadingPatterns::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    #[test]
+    fn markdown_and_chapter_headings_split_sections() {
+        let text = "Front matter.\n\n# Part One\n\nChapter 1: The Storm\nIt rained.\n\n## Aftermath ##\nQuiet.\nCHAPTER TWENTY-ONE\nEnd.";
+        let sections = detect_sections(text, &HeadingPatterns::new());
+        let headings: Vec<(Option<&str>, usize)> = sections.iter().map(|s| (s.heading.as_deref(), s.level)).collect();
+        assert_eq!(
+            headings,
+            [(None, 0), (Some("Part One"), 1), (Some("Chapter 1: The Storm"), 1), (Some("Aftermath"), 2), (Some("CHAPTER TWENTY-ONE"), 1)]
+        );
+        assert!(sections.windows(2).all(|w| w[0].end == w[1].start));
+        assert_eq!((sections[0].start, sections[4].end), (0, text.len()));
+    }
+
+    #[test]
+    fn text_without_headings_is_one_implicit_section() {
+        let sections = detect_sections("The chapter ended.\nNothing else.", &HeadingPatterns::new());
+        assert_eq!(sections.len(), 1);
+        assert_eq!((sections[0].heading.as_deref(), sections[0].end), (None, 32));
+    }
+
+    #[test]
+    fn scene_breaks_open_untitled_sections_below_their_heading() {
+        let text = "Opening.\n***\nChapter Two\nA scene.\n* * *\nAnother.\n---\n## Notes\nx\n# # #\ny";
+        let sections = detect_outline(text, &HeadingPatterns::new());
+        let outline: Vec<(Option<&str>, usize, bool, &str)> =
+            sections.iter().map(|s| (s.heading.as_deref(), s.level, s.scene_break, text[s.start..s.end].lines().next().unwrap())).collect();
+        assert_eq!(
+            outline,
+            [
+                (None, 0, false, "Opening."),
+                (None, 1, true, "***"),
+                (Some("Chapter Two"), 1, false, "Chapter Two"),
+                (None, 2, true, "* * *"),
+                (None, 2, true, "---"),
+                (Some("Notes"), 2, false, "## Notes"),
+                (None, 3, true, "# # #"),
+            ]
+        );
+        // Sections for chapter analysis ignore the breaks
+        assert_eq!(detect_sections(text, &HeadingPatterns::new()).len(), 3);
+    }
+
+    #[test]
+    fn outline_entries_span_their_whole_section() {
+        let text = "# One\nFirst.\n# Two\nSecond.";
+        let entries: Vec<OutlineEntry> = detect_outline(text, &HeadingPatterns::new()).iter().map(OutlineEntry::from).collect();
+        let spans: Vec<(usize, &str)> = entries.iter().map(|e| (e.level, &text[e.start_pos..e.end_pos])).collect();
+        assert_eq!(spans, [(1, "# One\nFirst.\n"), (1, "# Two\nSecond.")]);
+        assert_eq!(entries[1].title.as_deref(), Some("Two"));
+    }
+
+    #[test]
+    fn sections_of_mixed_markdown_and_prose_chapters_are_analyzed_alone() {
+        let processor = TextProcessor::new();
+        let text = "## Prologue\nThe sea was calm.\n\nCHAPTER ONE\nMara ran. She hid.\n\n***\n\nChapter 2: Night\nIt rained all night long.";
+        let sections = detect_outline(text, &processor.heading_patterns);
+        let titles: Vec<Option<&str>> = sections.iter().map(|s| s.heading.as_deref()).collect();
+        assert_eq!(titles, [Some("Prologue"), Some("CHAPTER ONE"), None, Some("Chapter 2: Night")]);
+        let words: Vec<usize> = sections.iter().map(|s| processor.perform_analysis(&text[s.start..s.end]).word_count).collect();
+        // Heading words count in their section
+        assert_eq!(words, [5, 6, 0, 8]);
+        assert_eq!(words.iter().sum::<usize>(), processor.perform_analysis(text).word_count);
+    }
+}
@@ -0,0 +1,516 @@
+//! Post-processing stages applied to raw rule output before it is returned.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::config::SuggestionConfig;
+use crate::hashing;
+use crate::context::SuggestionContext;
+use crate::{OptimizationSuggestion, RelatedSpan};
+
+pub const SCOPE_INSTANCE: &str = "instance";
+pub const SCOPE_DOCUMENT: &str = "document";
+
+/// Below these a rule is never escalated, however dense: two hits in a
+/// short note are not a document-level pattern.
+const MIN_ESCALATION_COUNT: usize = 3;
+const MIN_ESCALATION_WORDS: usize = 100;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleSummary {
+    pub rule: String,
+    pub count: usize,
+    pub density_per_1000_words: f64,
+    pub priority: String,
+    pub escalated: bool,
+}
+
+pub fn priority_rank(priority: &str) -> u8 {
+    match priority {
+        "high" => 2,
+        "medium" => 1,
+        _ => 0,
+    }
+}
+
+pub fn priority_name(rank: u8) -> &'static str {
+    match rank {
+        2 => "high",
+        1 => "medium",
+        _ => "low",
+    }
+}
+
+/// Escalates per-instance priorities for rules whose hit density crosses the
+/// configured thresholds and appends one document-scope suggestion per
+/// escalated rule. Rules with fewer than `MIN_ESCALATION_COUNT` hits, and
+/// documents under `MIN_ESCALATION_WORDS` words, are left alone. Returns a
+/// summary row for every rule that fired, sorted by rule id so the output
+/// is deterministic.
+pub fn escalate_by_density(
+    suggestions: &mut Vec<OptimizationSuggestion>,
+    word_count: usize,
+    text_len: usize,
+    config: &SuggestionConfig,
+) -> Vec<RuleSummary> {
+    let mut counts: BTreeMap<Cow<'static, str>, (usize, u8)> = BTreeMap::new();
+    for suggestion in suggestions.iter().filter(|s| s.scope == SCOPE_INSTANCE) {
+        let entry = counts.entry(suggestion.suggestion_type.clone()).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 = entry.1.max(priority_rank(&suggestion.priority));
+    }
+
+    let mut summaries = Vec::new();
+    let mut document_suggestions = Vec::new();
+
+    for (rule, (count, base_rank)) in counts {
+        let density = if word_count > 0 { count as f64 * 1000.0 / word_count as f64 } else { 0.0 };
+
+        let target_rank = match config.density_thresholds.get(rule.as_ref()) {
+            _ if count < MIN_ESCALATION_COUNT || word_count < MIN_ESCALATION_WORDS => 0,
+            Some(t) if density >= t.high_per_1000_words => 2,
+            Some(t) if density >= t.medium_per_1000_words => 1,
+            _ => 0,
+        };
+        let escalated = target_rank > base_rank;
+        let final_rank = base_rank.max(target_rank);
+
+        if escalated {
+            for suggestion in suggestions.iter_mut() {
+                if suggestion.scope == SCOPE_INSTANCE && suggestion.suggestion_type == rule {
+                    let rank = priority_rank(&suggestion.priority).max(target_rank);
+                    suggestion.priority = priority_name(rank).into();
+                }
+            }
+
+            let message = format!(
+                "\"{}\" was flagged {} times ({:.1} per 1,000 words); this is a document-level pattern rather than isolated instances.",
+                rule, count, density
+            );
+            document_suggestions.push(OptimizationSuggestion::document(
+                rule.clone(),
+                priority_name(final_rank),
+                message,
+                text_len,
+            ));
+        }
+
+        summaries.push(RuleSummary {
+            rule: rule.into_owned(),
+            count,
+            density_per_1000_words: density,
+            priority: priority_name(final_rank).to_string(),
+            escalated,
+        });
+    }
+
+    suggestions.extend(document_suggestions);
+    summaries
+}
+
+/// Drops word-level suggestions that land on deliberate dialect inside
+/// dialogue. `dialogue_spans` are byte ranges of quoted speech. Returns the
+/// number of suggestions suppressed so callers can audit the filter.
+pub fn filter_dialect(
+    suggestions: &mut Vec<OptimizationSuggestion>,
+    text: &str,
+    dialogue_spans: &[(usize, usize)],
+    config: &SuggestionConfig,
+) -> usize {
+    if !config.protect_dialogue_dialect {
+        return 0;
+    }
+
+    let before = suggestions.len();
+    suggestions.retain(|s| {
+        if s.scope != SCOPE_INSTANCE || !config.dialect_protected_rules.iter().any(|r| *r == s.suggestion_type) {
+            return true;
+        }
+        let in_dialogue = dialogue_spans.iter().any(|&(start, end)| s.start_pos >= start && s.end_pos <= end);
+        !(in_dialogue && is_protected_dialect(text, s.start_pos, s.end_pos, config))
+    });
+    before - suggestions.len()
+}
+
+/// Whether the word at `start..end` is dialect: apostrophe-final or one of
+/// `protected_tokens`.
+pub fn is_protected_dialect(text: &str, start: usize, end: usize, config: &SuggestionConfig) -> bool {
+    let Some(flagged) = text.get(start..end) else {
+        return false;
+    };
+    // A dropped-g word is often flagged without its trailing apostrophe
+    let trailing_apostrophe = text[end.min(text.len())..].starts_with(['\'', '\u{2019}']);
+
+    let tokens: Vec<&str> = flagged
+        .split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '\u{2019}'))
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    tokens.iter().enumerate().any(|(i, token)| {
+        let mut normalized = token.replace('\u{2019}', "'").to_lowercase();
+        if i == tokens.len() - 1 && trailing_apostrophe && !normalized.ends_with('\'') {
+            normalized.push('\'');
+        }
+        let apostrophe_final = normalized.len() > 1 && normalized.ends_with('\'');
+        apostrophe_final || config.protected_tokens.iter().any(|p| p.eq_ignore_ascii_case(&normalized))
+    })
+}
+
+// Words of surrounding context folded into a fingerprint on each side
+const FINGERPRINT_CONTEXT_WORDS: usize = 3;
+
+fn normalize_for_fingerprint<'a>(words: impl Iterator<Item = &'a str>) -> Vec<String> {
+    words
+        .map(|w| w.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// Assigns each suggestion a fingerprint derived from its rule, the flagged
+/// text, and a few words of context on either side, never from absolute
+/// offsets, so it survives edits elsewhere in the document but changes when
+/// the flagged text is rewritten. Identical fingerprints are disambiguated by
+/// their order of occurrence.
+pub fn assign_fingerprints(suggestions: &mut [OptimizationSuggestion], text: &str) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for suggestion in suggestions.iter_mut() {
+        let mut hasher = Sha256::new();
+        hasher.update(suggestion.suggestion_type.as_bytes());
+        hasher.update([0]);
+        hasher.update(suggestion.scope.as_bytes());
+
+        if suggestion.scope == SCOPE_INSTANCE {
+            let start = suggestion.start_pos.min(text.len());
+            let end = suggestion.end_pos.clamp(start, text.len());
+            if let (Some(before), Some(flagged), Some(after)) = (text.get(..start), text.get(start..end), text.get(end..)) {
+                let flagged = normalize_for_fingerprint(flagged.split_whitespace());
+                let mut before = normalize_for_fingerprint(before.split_whitespace().rev().take(FINGERPRINT_CONTEXT_WORDS));
+                before.reverse();
+                let after = normalize_for_fingerprint(after.split_whitespace().take(FINGERPRINT_CONTEXT_WORDS));
+
+                for part in [before, flagged, after] {
+                    hasher.update([0]);
+                    hasher.update(part.join(" ").as_bytes());
+                }
+            }
+        }
+        // Pair-style document suggestions are identified by the spans they relate
+        for span in &suggestion.related_spans {
+            if let Some(related) = text.get(span.start_pos..span.end_pos) {
+                hasher.update([0]);
+                hasher.update(normalize_for_fingerprint(related.split_whitespace()).join(" ").as_bytes());
+            }
+        }
+
+        let base = hashing::to_hex(&hasher.finalize()[..8]);
+        let occurrence = seen.entry(base.clone()).or_insert(0);
+        suggestion.fingerprint = if *occurrence == 0 { base } else { format!("{}-{}", base, occurrence) };
+        *occurrence += 1;
+    }
+}
+
+pub fn filter_dismissed(suggestions: Vec<OptimizationSuggestion>, dismissed: &[String]) -> Vec<OptimizationSuggestion> {
+    let dismissed: HashSet<&str> = dismissed.iter().map(String::as_str).collect();
+    suggestions.into_iter().filter(|s| !dismissed.contains(s.fingerprint.as_str())).collect()
+}
+
+/// Compact wire form of a suggestion list: every rule id, priority, message,
+/// replacement and scope is stored once in `strings`, and each suggestion is
+/// a tuple of
+/// `(type, priority, message, start_pos, end_pos, replacement, scope,
+/// fingerprint, start_utf16, end_utf16, line, column, related_spans, score,
+/// context)`. The first three, `replacement` and `scope` are indices into
+/// `strings`; `replacement`, `score` and `context` are null when absent.
+/// Fingerprints are unique per suggestion, so they stay inline rather than
+/// growing the table. [`from_compact`] restores the full suggestions.
+#[derive(Serialize, Deserialize)]
+pub struct CompactSuggestions<'a> {
+    #[serde(borrow)]
+    pub strings: Vec<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub suggestions: Vec<CompactRow<'a>>,
+}
+
+pub type CompactRow<'a> = (
+    u32,
+    u32,
+    u32,
+    usize,
+    usize,
+    Option<u32>,
+    u32,
+    Cow<'a, str>,
+    usize,
+    usize,
+    usize,
+    usize,
+    Cow<'a, [RelatedSpan]>,
+    Option<f64>,
+    Option<Cow<'a, SuggestionContext>>,
+);
+
+pub fn to_compact<'a>(suggestions: &'a [OptimizationSuggestion]) -> CompactSuggestions<'a> {
+    let mut strings: Vec<Cow<'a, str>> = Vec::new();
+    let mut index: HashMap<&str, u32> = HashMap::new();
+    let mut rows = Vec::with_capacity(suggestions.len());
+    for s in suggestions {
+        let mut id = |value: &'a str| *index.entry(value).or_insert_with(|| {
+            strings.push(Cow::Borrowed(value));
+            (strings.len() - 1) as u32
+        });
+        rows.push((
+            id(&s.suggestion_type),
+            id(&s.priority),
+            id(&s.message),
+            s.start_pos,
+            s.end_pos,
+            s.suggested_replacement.as_deref().map(&mut id),
+            id(&s.scope),
+            Cow::Borrowed(s.fingerprint.as_str()),
+            s.start_utf16,
+            s.end_utf16,
+            s.line,
+            s.column,
+            Cow::Borrowed(s.related_spans.as_slice()),
+            s.score,
+            s.context.as_ref().map(Cow::Borrowed),
+        ));
+    }
+
+    CompactSuggestions { strings, suggestions: rows }
+}
+
+/// The suggestions `to_compact` encoded, or an error naming the first row
+/// whose string index is out of range.
+pub fn from_compact(compact: &CompactSuggestions) -> Result<Vec<OptimizationSuggestion>, String> {
+    compact
+        .suggestions
+        .iter()
+        .enumerate()
+        .map(|(row_number, row)| {
+            let text = |i: u32| {
+                compact
+                    .strings
+                    .get(i as usize)
+                    .map(|s| s.to_string())
+                    .ok_or_else(|| format!("row {}: string index {} out of range", row_number, i))
+            };
+            Ok(OptimizationSuggestion {
+                suggestion_type: Cow::Owned(text(row.0)?),
+                priority: Cow::Owned(text(row.1)?),
+                message: Cow::Owned(text(row.2)?),
+                start_pos: row.3,
+                end_pos: row.4,
+                suggested_replacement: row.5.map(text).transpose()?,
+                scope: Cow::Owned(text(row.6)?),
+                fingerprint: row.7.to_string(),
+                start_utf16: row.8,
+                end_utf16: row.9,
+                line: row.10,
+                column: row.11,
+                related_spans: row.12.to_vec(),
+                score: row.13,
+                context: row.14.as_ref().map(|context| context.clone().into_owned()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Cases;
+
+    fn hit(rule: &str, priority: &'static str, start_pos: usize) -> OptimizationSuggestion {
+        OptimizationSuggestion::instance(rule.to_string(), priority, "", start_pos, start_pos + 4)
+    }
+
+    #[test]
+    fn dense_rules_escalate_every_instance_and_get_a_document_suggestion() {
+        let mut suggestions: Vec<OptimizationSuggestion> = (0..3).map(|i| hit("adverb_usage", "low", i * 10)).collect();
+        suggestions.push(hit("passive_voice", "medium", 50));
+        let summaries = escalate_by_density(&mut suggestions, 100, 600, &SuggestionConfig::default());
+
+        // 30 adverbs per 1,000 words is past medium (20) but not high (40)
+        let adverbs: Vec<&OptimizationSuggestion> = suggestions.iter().filter(|s| s.suggestion_type == "adverb_usage").collect();
+        assert_eq!(adverbs.len(), 4);
+        assert!(adverbs.iter().all(|s| s.priority == "medium"));
+        let document: Vec<&&OptimizationSuggestion> = adverbs.iter().filter(|s| s.scope == SCOPE_DOCUMENT).collect();
+        assert_eq!(document.len(), 1);
+        assert_eq!((document[0].start_pos, document[0].end_pos), (0, 600));
+        assert!(document[0].message.starts_with("\"adverb_usage\" was flagged 3 times (30.0 per 1,000 words)"));
+
+        let adverb_summary = summaries.iter().find(|s| s.rule == "adverb_usage").unwrap();
+        assert_eq!((adverb_summary.count, adverb_summary.escalated, adverb_summary.priority.as_str()), (3, true, "medium"));
+        assert_eq!(adverb_summary.density_per_1000_words, 30.0);
+    }
+
+    #[test]
+    fn sparse_or_unconfigured_rules_are_left_alone() {
+        let mut suggestions = vec![hit("adverb_usage", "low", 0), hit("custom_rule", "low", 10)];
+        let summaries = escalate_by_density(&mut suggestions, 1000, 6000, &SuggestionConfig::default());
+        assert_eq!(suggestions.len(), 2);
+        assert!(suggestions.iter().all(|s| s.priority == "low" && s.scope == SCOPE_INSTANCE));
+        assert_eq!(summaries.iter().map(|s| s.rule.as_str()).collect::<Vec<_>>(), ["adverb_usage", "custom_rule"]);
+        assert!(summaries.iter().all(|s| !s.escalated));
+    }
+
+    #[test]
+    fn few_hits_or_short_documents_are_not_escalated() {
+        // Two hits in 50 words is 40 per 1,000, but too few to call a pattern
+        let mut suggestions = vec![hit("adverb_usage", "low", 0), hit("adverb_usage", "low", 10)];
+        let summaries = escalate_by_density(&mut suggestions, 50, 300, &SuggestionConfig::default());
+        assert_eq!(suggestions.len(), 2);
+        assert!(!summaries[0].escalated && summaries[0].priority == "low");
+
+        // Three hits, but in a 20-word note
+        let mut suggestions: Vec<OptimizationSuggestion> = (0..3).map(|i| hit("adverb_usage", "low", i * 10)).collect();
+        let summaries = escalate_by_density(&mut suggestions, 20, 120, &SuggestionConfig::default());
+        assert!(suggestions.iter().all(|s| s.priority == "low" && s.scope == SCOPE_INSTANCE));
+        assert!(!summaries[0].escalated);
+        assert_eq!(summaries[0].density_per_1000_words, 150.0);
+    }
+
+    #[test]
+    fn dialect_is_protected_inside_dialogue_only() {
+        let text = "\"I'm gonna be runnin' late,\" she said. He was gonna wait, runnin' out of patience.";
+        let span = |word: &str, from: usize| {
+            let start = from + text[from..].find(word).unwrap();
+            OptimizationSuggestion { end_pos: start + word.len(), ..hit("adverb_usage", "low", start) }
+        };
+        let quote_end = text[1..].find('"').unwrap() + 2;
+        let mut suggestions = vec![span("gonna", 0), span("runnin", 0), span("gonna", quote_end), span("runnin", quote_end)];
+        let suppressed = filter_dialect(&mut suggestions, text, &[(0, quote_end)], &SuggestionConfig::default());
+        assert_eq!(suppressed, 2);
+        assert!(suggestions.iter().all(|s| s.start_pos > quote_end));
+        assert_eq!(suggestions.len(), 2);
+
+        let mut unprotected = vec![span("gonna", 0)];
+        let config = SuggestionConfig { protect_dialogue_dialect: false, ..SuggestionConfig::default() };
+        assert_eq!(filter_dialect(&mut unprotected, text, &[(0, quote_end)], &config), 0);
+    }
+
+    const FILLER: &[&str] = &["the", "dog", "sat", "near", "a", "gate", "and", "rain", "fell", "on", "hills", "she", "ran", "home"];
+
+    fn words(cases: &mut Cases, min: usize) -> String {
+        let count = min + cases.below(20);
+        (0..count).map(|_| FILLER[cases.below(FILLER.len())]).collect::<Vec<_>>().join(" ")
+    }
+
+    fn passive_fingerprint(processor: &crate::TextProcessor, text: &str) -> String {
+        let report = processor.build_optimization_report(text);
+        let passive: Vec<&OptimizationSuggestion> = report.suggestions.iter().filter(|s| s.suggestion_type == "passive_voice" && s.scope == SCOPE_INSTANCE).collect();
+        assert_eq!(passive.len(), 1, "{}", text);
+        passive[0].fingerprint.clone()
+    }
+
+    #[test]
+    fn fingerprints_survive_edits_away_from_the_flagged_text() {
+        let processor = crate::TextProcessor::new();
+        let mut cases = Cases(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..200 {
+            let (before, after) = (words(&mut cases, 5), words(&mut cases, 5));
+            let original = format!("{}. The letter was signed by her brother. {}.", before, after);
+            let fingerprint = passive_fingerprint(&processor, &original);
+
+            let edited = match cases.below(3) {
+                0 => format!("{} {}. The letter was signed by her brother. {}.", words(&mut cases, 1), before, after),
+                1 => format!("{}. The letter was signed by her brother. {} {}.", before, after, words(&mut cases, 1)),
+                _ => format!("{}\n\n{}. The letter was signed by her brother. {}.", words(&mut cases, 1), before, after),
+            };
+            assert_eq!(passive_fingerprint(&processor, &edited), fingerprint, "{} -> {}", original, edited);
+        }
+    }
+
+    #[test]
+    fn fingerprints_change_when_the_flagged_text_is_rewritten() {
+        let processor = crate::TextProcessor::new();
+        let mut cases = Cases(0x2545_f491_4f6c_dd1d);
+        let verbs = ["signed", "sealed", "posted", "burned", "opened", "stamped"];
+        for _ in 0..200 {
+            let (before, after) = (words(&mut cases, 5), words(&mut cases, 5));
+            let verb = verbs[cases.below(verbs.len())];
+            let rewrite = verbs.iter().filter(|v| **v != verb).nth(cases.below(verbs.len() - 1)).unwrap();
+            let original = format!("{}. The letter was {} by her brother. {}.", before, verb, after);
+            let rewritten = format!("{}. The letter was {} by her brother. {}.", before, rewrite, after);
+            assert_ne!(passive_fingerprint(&processor, &original), passive_fingerprint(&processor, &rewritten), "{} -> {}", verb, rewrite);
+        }
+    }
+
+    #[test]
+    fn dismissed_fingerprints_are_filtered_after_an_edit() {
+        let processor = crate::TextProcessor::new();
+        let text = "The gate was opened by the wind. It was closed again by dawn.";
+        let report = processor.build_optimization_report(text);
+        let dismissed = vec![report.suggestions[0].fingerprint.clone()];
+        let kept = report.suggestions[1].fingerprint.clone();
+
+        let edited = format!("Rain fell all night.\n\n{}", text);
+        let remaining = filter_dismissed(processor.build_optimization_report(&edited).suggestions, &dismissed);
+        assert!(remaining.iter().all(|s| s.fingerprint != dismissed[0]));
+        assert!(remaining.iter().any(|s| s.fingerprint == kept));
+    }
+
+    #[test]
+    fn fixed_fields_of_generated_suggestions_are_static() {
+        let text = "He walked slowly and quietly. The door was opened. The window was closed. She smiled sadly.";
+        let report = crate::TextProcessor::new().build_optimization_report(text);
+        let instances: Vec<&OptimizationSuggestion> = report.suggestions.iter().filter(|s| s.scope == SCOPE_INSTANCE).collect();
+        assert!(instances.len() >= 5);
+        assert!(instances
+            .iter()
+            .all(|s| [&s.suggestion_type, &s.priority, &s.message, &s.scope].iter().all(|f| matches!(f, Cow::Borrowed(_)))));
+    }
+
+    #[test]
+    fn compact_form_stores_each_string_once() {
+        let text = "He walked slowly and quietly. The door was opened. The window was closed. She smiled sadly.";
+        let report = crate::TextProcessor::new().build_optimization_report(text);
+        let compact = to_compact(&report.suggestions);
+        let mut distinct = compact.strings.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        assert_eq!(distinct.len(), compact.strings.len());
+        assert_eq!(compact.strings.iter().filter(|s| *s == "Consider using active voice for more engaging writing.").count(), 1);
+        assert!(report.suggestions.iter().all(|s| !compact.strings.contains(&Cow::Borrowed(s.fingerprint.as_str()))));
+
+        for (row, suggestion) in compact.suggestions.iter().zip(&report.suggestions) {
+            let text = |i: u32| compact.strings[i as usize].as_ref();
+            assert_eq!(text(row.0), suggestion.suggestion_type);
+            assert_eq!(text(row.1), suggestion.priority);
+            assert_eq!(text(row.2), suggestion.message);
+            assert_eq!((row.3, row.4), (suggestion.start_pos, suggestion.end_pos));
+            assert_eq!(row.5.map(text), suggestion.suggested_replacement.as_deref());
+            assert_eq!(text(row.6), suggestion.scope);
+            assert_eq!(row.7, suggestion.fingerprint);
+        }
+    }
+
+    #[test]
+    fn compact_form_round_trips_every_field() {
+        let text = "He walked slowly and quietly.\nThe door was opened.\n\nThe window was closed by her.";
+        let context = crate::context::ContextOptions { enabled: true, ..Default::default() };
+        let processor = crate::TextProcessor::with_patterns(&Default::default(), SuggestionConfig { context, ..SuggestionConfig::default() });
+        let mut suggestions = processor.build_optimization_report(text).suggestions;
+        suggestions[0].related_spans = vec![RelatedSpan { start_pos: 0, end_pos: 2 }, RelatedSpan { start_pos: 30, end_pos: 34 }];
+        suggestions[0].score = Some(0.75);
+        assert!(suggestions.iter().all(|s| s.context.is_some()));
+        assert!(suggestions.iter().any(|s| s.line > 0 && s.column > 0));
+
+        let json = serde_json::to_string(&to_compact(&suggestions)).unwrap();
+        let decoded: CompactSuggestions = serde_json::from_str(&json).unwrap();
+        assert!(from_compact(&decoded).unwrap() == suggestions);
+    }
+
+    #[test]
+    fn out_of_range_string_index_is_an_error() {
+        let suggestions = vec![hit("adverb", "low", 0)];
+        let mut compact = to_compact(&suggestions);
+        compact.suggestions[0].2 = 7;
+        assert_eq!(from_compact(&compact).err().as_deref(), Some("row 0: string index 7 out of range"));
+    }
+}
@@ -0,0 +1,276 @@
+//! Syllable counts for the readability formulas. English counts vowel
+//! groups corrected for silent endings and split vowel pairs, with a small
+//! dictionary of frequent words the rules get wrong; other languages count
+//! vowel groups from the rules in their `lang` table.
+
+/// How a language counts syllables.
+pub enum Syllables {
+    English,
+    VowelGroups(VowelGroups),
+}
+
+impl Syllables {
+    /// Syllables in `word`; at least 1.
+    pub fn count(&self, word: &str) -> usize {
+        match self {
+            Syllables::English => count(word),
+            Syllables::VowelGroups(rules) => rules.count(word),
+        }
+    }
+}
+
+/// One syllable per run of vowels, for languages spelled close to how they
+/// are said.
+pub struct VowelGroups {
+    /// Lowercase vowels, "y" included where it is one.
+    pub vowels: &'static str,
+    /// Vowel pairs said as two syllables.
+    pub splits: &'static [&'static str],
+    /// Letters after which "u" is silent or part of the consonant.
+    pub silent_u_after: &'static str,
+}
+
+impl VowelGroups {
+    fn count(&self, word: &str) -> usize {
+        let letters: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).flat_map(char::to_lowercase).collect();
+        let is_vowel = |i: usize| match letters[i] {
+            'u' => !(i > 0 && self.silent_u_after.contains(letters[i - 1]) && i + 1 < letters.len()),
+            // "y" opening a syllable is a consonant ("mayo")
+            'y' => self.vowels.contains('y') && !letters.get(i + 1).is_some_and(|&c| self.vowels.contains(c)),
+            c => self.vowels.contains(c),
+        };
+        let mut syllables = 0usize;
+        for i in 0..letters.len() {
+            if !is_vowel(i) {
+                continue;
+            }
+            let continues = i > 0 && is_vowel(i - 1);
+            let split = continues && self.splits.iter().any(|pair| pair.chars().eq([letters[i - 1], letters[i]]));
+            if !continues || split {
+                syllables += 1;
+            }
+        }
+        syllables.max(1)
+    }
+}
+
+/// Irregular words, sorted for binary search.
+const EXCEPTIONS: &[(&str, usize)] = &[
+    ("anyone", 3),
+    ("areas", 3),
+    ("business", 2),
+    ("businesses", 3),
+    ("cafe", 2),
+    ("colonel", 2),
+    ("create", 2),
+    ("created", 3),
+    ("creates", 2),
+    ("evening", 2),
+    ("every", 2),
+    ("everything", 3),
+    ("ideas", 3),
+    ("interesting", 3),
+    ("maybe", 2),
+    ("naive", 2),
+    ("people", 2),
+    ("peoples", 2),
+    ("poem", 2),
+    ("poems", 2),
+    ("poet", 2),
+    ("poetry", 3),
+    ("quiet", 2),
+    ("react", 2),
+    ("recipe", 3),
+    ("science", 2),
+    ("someone", 2),
+    ("sometimes", 2),
+    ("wednesday", 2),
+];
+
+const ACCENTED_VOWELS: &str = "àáâãäåæāèéêëēėęìíîïīįòóôõöøōœùúûüūÿ";
+
+/// Stands in for accented vowels ("é" is never a silent e).
+const OTHER_VOWEL: u8 = b'*';
+/// Stands in for non-ASCII consonants.
+const OTHER_CONSONANT: u8 = b'#';
+
+/// Letters of `word`, lowercased to ASCII; apostrophes, digits, and other
+/// marks are dropped.
+fn letters(word: &str) -> Vec<u8> {
+    word.chars()
+        .filter(|c| c.is_alphabetic())
+        .map(|c| match c.to_lowercase().next().unwrap_or(c) {
+            c if c.is_ascii() => c as u8,
+            c if ACCENTED_VOWELS.contains(c) => OTHER_VOWEL,
+            _ => OTHER_CONSONANT,
+        })
+        .collect()
+}
+
+fn is_vowel(letters: &[u8], i: usize) -> bool {
+    match letters[i] {
+        b'a' | b'e' | b'i' | b'o' | OTHER_VOWEL => true,
+        // "u" after "q" is part of the consonant ("quick")
+        b'u' => i == 0 || letters[i - 1] != b'q',
+        // "y" is a consonant opening a word or a syllable ("yes", "beyond")
+        b'y' => i > 0 && !letters.get(i + 1).is_some_and(|&c| matches!(c, b'a' | b'e' | b'o' | b'u')),
+        _ => false,
+    }
+}
+
+/// Vowel pairs said as two syllables ("video", "usual"), unless the word
+/// around them says otherwise ("nation", "quality", "special").
+fn splits(letters: &[u8], i: usize) -> bool {
+    let before = if i > 0 { letters[i - 1] } else { 0 };
+    match (letters[i], letters[i + 1]) {
+        // "-lian", "-lion", "-liar" after another syllable are one too
+        // ("Italian", "million", "familiar"; but "lion", "alias")
+        (b'i', b'a') | (b'i', b'o') => {
+            let glide = before == b'l' && i >= 2 && matches!(letters.get(i + 2), Some(b'n' | b'r'));
+            !matches!(before, b'c' | b's' | b't' | b'x') && i > 0 && !glide
+        }
+        (b'e', b'o') => letters.get(i + 2) != Some(&b'u'),
+        // "diet", "society" (but not "patient"); "fuel", "cruel"
+        (b'i', b'e') => letters.get(i + 2) == Some(&b't'),
+        (b'u', b'e') => letters.get(i + 2) == Some(&b'l'),
+        // "museum", "linoleum"
+        (b'e', b'u') => letters.get(i + 2) == Some(&b'm'),
+        (b'i', b'u') | (b'u', b'a') | (b'u', b'o') => i > 0 && !matches!(before, b'q' | b'g'),
+        // Word-final "ea" ("idea" is in the exceptions; "sea" is one)
+        (b'e', b'a') => i + 2 == letters.len() && i > 1,
+        _ => false,
+    }
+}
+
+fn is_consonant(letters: &[u8], i: usize) -> bool {
+    !is_vowel(letters, i)
+}
+
+/// Syllables in an English word; at least 1.
+pub fn count(word: &str) -> usize {
+    let letters = letters(word);
+    if let Ok(found) = EXCEPTIONS.binary_search_by(|(w, _)| w.as_bytes().cmp(&letters)) {
+        return EXCEPTIONS[found].1;
+    }
+    let n = letters.len();
+    let mut syllables = 0usize;
+    let mut prev_vowel = false;
+    for i in 0..n {
+        let vowel = is_vowel(&letters, i);
+        if vowel && (!prev_vowel || splits(&letters, i - 1)) {
+            syllables += 1;
+        }
+        prev_vowel = vowel;
+    }
+
+    let ends = |suffix: &[u8]| letters.ends_with(suffix);
+    // `letters[n - k]` is a consonant, when it exists
+    let consonant_at = |k: usize| n >= k && is_consonant(&letters, n - k);
+    if ends(b"e") && consonant_at(2) && !(ends(b"le") && consonant_at(3)) {
+        // Silent final e ("make"), but "table" keeps its "-le"
+        syllables = syllables.saturating_sub(1);
+    } else if ends(b"es") && consonant_at(3) {
+        // "makes" loses the e; "boxes", "pages", "wishes", "tables" keep it
+        let sounded = matches!(letters[n - 3], b's' | b'x' | b'z' | b'c' | b'g')
+            || (ends(b"hes") && n >= 4 && matches!(letters[n - 4], b'c' | b's'))
+            || (ends(b"les") && consonant_at(4));
+        if !sounded {
+            syllables = syllables.saturating_sub(1);
+        }
+    } else if ends(b"ed") && consonant_at(3) {
+        // "walked" and "called" lose the e; "wanted", "needed", "tabled", and
+        // "hundred" keep it
+        let sounded = matches!(letters[n - 3], b't' | b'd')
+            || ((ends(b"led") || ends(b"red")) && consonant_at(4) && !matches!(letters[n - 4], b'l' | b'r'));
+        if !sounded {
+            syllables = syllables.saturating_sub(1);
+        }
+    }
+    // A silent e before a suffix ("lovely", "movement", "carefully")
+    for suffix in [&b"fully"[..], b"ly", b"ment", b"ful", b"ness", b"less"] {
+        let k = suffix.len() + 1;
+        let silent = n > k + 1 && ends(suffix) && letters[n - k] == b'e' && consonant_at(k + 1);
+        // "gentleness" keeps its "-le"
+        if silent && !(letters[n - k - 1] == b'l' && consonant_at(k + 2)) {
+            syllables = syllables.saturating_sub(1);
+            break;
+        }
+    }
+    // Comparatives split "ie" ("happier", "easiest")
+    if (ends(b"ier") && n >= 5 && consonant_at(4)) || (ends(b"iest") && n >= 6 && consonant_at(5)) {
+        syllables += 1;
+    }
+    // "-ing" after a vowel is its own syllable ("going", "saying")
+    if ends(b"ing") && n >= 4 && matches!(letters[n - 4], b'a' | b'e' | b'o' | b'u' | b'y') {
+        syllables += 1;
+    }
+    // "-ism" and "-asm" end on a syllabic m ("prism", "sarcasm")
+    if ends(b"sm") && n >= 3 {
+        syllables += 1;
+    }
+    // "didn't", "couldn't"
+    if (word.ends_with("n't") || word.ends_with("n\u{2019}t")) && consonant_at(3) {
+        syllables += 1;
+    }
+    syllables.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Dictionary syllable counts of frequent and awkward words.
+    const WORDS: &[(&str, usize)] = &[
+        ("a", 1), ("the", 1), ("cat", 1), ("make", 1), ("makes", 1), ("made", 1), ("time", 1), ("one", 1),
+        ("queue", 1), ("quick", 1), ("quite", 1), ("quiet", 2), ("walked", 1), ("called", 1), ("jumped", 1),
+        ("wanted", 2), ("needed", 2), ("started", 2), ("hundred", 2), ("tabled", 2), ("table", 2), ("little", 2),
+        ("apple", 2), ("simple", 2), ("people", 2), ("bottle", 2), ("tables", 2), ("boxes", 2), ("pages", 2),
+        ("wishes", 2), ("watches", 2), ("horses", 2), ("houses", 2), ("places", 2), ("yes", 1), ("you", 1),
+        ("year", 1), ("beyond", 2), ("happy", 2), ("happier", 3), ("easiest", 3), ("sky", 1), ("my", 1),
+        ("rhythm", 2), ("system", 2), ("every", 2), ("everything", 3), ("evening", 2), ("business", 2),
+        ("video", 3), ("radio", 3), ("usual", 3), ("nation", 2), ("special", 2), ("patient", 2), ("diet", 2),
+        ("society", 4), ("fuel", 2), ("cruel", 2), ("museum", 3), ("idea", 3), ("sea", 1), ("area", 3),
+        ("going", 2), ("saying", 2), ("being", 2), ("seeing", 2), ("doing", 2), ("prism", 2), ("sarcasm", 3),
+        ("didn't", 2), ("couldn't", 2), ("lovely", 2), ("movement", 2), ("carefully", 3), ("hopeless", 2),
+        ("gentleness", 3), ("late", 1), ("lately", 2), ("water", 2), ("never", 2), ("over", 2), ("about", 2),
+        ("because", 2), ("before", 2), ("another", 3), ("family", 3), ("different", 3), ("important", 3),
+        ("beautiful", 3), ("computer", 3), ("understand", 3), ("education", 4), ("information", 4),
+        ("university", 5), ("responsibility", 6), ("organization", 5), ("communication", 5), ("generally", 4),
+        ("mountain", 2), ("rain", 1), ("boat", 1), ("food", 1), ("through", 1), ("thought", 1), ("house", 1),
+        ("bread", 1), ("great", 1), ("tree", 1), ("free", 1), ("coin", 1), ("boy", 1), ("toy", 1), ("player", 2),
+        ("cafe", 2), ("caf\u{e9}", 2), ("naive", 2), ("recipe", 3), ("poem", 2), ("poetry", 3), ("science", 2),
+        ("create", 2), ("created", 3), ("someone", 2), ("sometimes", 2), ("maybe", 2), ("colonel", 2),
+        ("wednesday", 2), ("interesting", 3), ("storm", 1), ("strength", 1), ("window", 2), ("garden", 2),
+        ("question", 2), ("language", 2), ("guard", 1), ("guess", 1), ("argue", 2), ("value", 2), ("continue", 3),
+        ("million", 2), ("brilliant", 2), ("Italian", 3), ("civilian", 3), ("familiar", 3), ("lion", 2), ("alias", 3),
+        ("Mara", 2), ("MAKE", 1), ("don't", 1), ("i", 1), ("o", 1),
+    ];
+
+    #[test]
+    fn known_words_count_at_least_ninety_percent_right() {
+        assert!(WORDS.len() >= 100);
+        let wrong: Vec<(&str, usize, usize)> =
+            WORDS.iter().map(|&(word, expected)| (word, expected, count(word))).filter(|&(_, expected, got)| expected != got).collect();
+        let accuracy = 1.0 - wrong.len() as f64 / WORDS.len() as f64;
+        assert!(accuracy >= 0.9, "{:.1}% right; wrong (word, expected, got): {:?}", accuracy * 100.0, wrong);
+    }
+
+    #[test]
+    fn the_reported_miscounts_are_fixed() {
+        for (word, expected) in [("people", 2), ("queue", 1), ("table", 2), ("little", 2), ("walked", 1)] {
+            assert_eq!(count(word), expected, "{}", word);
+        }
+    }
+
+    #[test]
+    fn every_word_has_at_least_one_syllable() {
+        for word in ["", "'", "123", "hmm", "nth", "\u{4e2d}\u{6587}", "x"] {
+            assert_eq!(count(word), 1, "{:?}", word);
+        }
+    }
+
+    #[test]
+    fn exceptions_are_sorted_for_binary_search() {
+        assert!(EXCEPTIONS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+}
@@ -0,0 +1,31 @@
+//! Fixtures shared by the unit tests.
+
+use regex::Regex;
+
+use crate::segmentation::{DocumentIndex, WordSegmenter};
+
+/// Xorshift, so the randomized cases are reproducible.
+pub struct Cases(pub u64);
+
+impl Cases {
+    pub fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+
+    /// A char boundary of `text`.
+    pub fn boundary(&mut self, text: &str) -> usize {
+        let boundaries: Vec<usize> = (0..=text.len()).filter(|&i| text.is_char_boundary(i)).collect();
+        boundaries[self.below(boundaries.len())]
+    }
+}
+
+/// `text` indexed with Unicode words and plain sentence and paragraph
+/// patterns, as the rule modules see it.
+pub fn index(text: &str) -> DocumentIndex<'_> {
+    let sentence = Regex::new(r"[.!?]+").unwrap();
+    let paragraph = Regex::new(r"\n\s*\n").unwrap();
+    DocumentIndex::build(text, &WordSegmenter::Unicode, &sentence, &paragraph)
+}
@@ -0,0 +1,229 @@
+//! Three-way merge of two versions edited from a common base: edits that
+//! don't overlap are merged, overlapping ones become conflicts for the
+//! `resolve_conflicts` flow.
+
+use serde::{Deserialize, Serialize};
+
+use crate::diff::{Segment, TextDiff};
+use crate::revisions::TextRange;
+use crate::CollaborationConflict;
+
+#[derive(Serialize, Deserialize)]
+pub struct ThreeWayMerge {
+    /// The base with every non-conflicting edit applied. Conflicted regions
+    /// keep their base text.
+    pub merged_text: String,
+    /// Spans are into the base text; `expected_text` is the base text of the span.
+    pub conflicts: Vec<CollaborationConflict>,
+    /// Where each conflict's base text sits in `merged_text`, in the same
+    /// order as `conflicts`.
+    pub merged_spans: Vec<TextRange>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Side {
+    A,
+    B,
+}
+
+/// `base[start..end]` replaced by `text` in one version.
+struct Change<'t> {
+    side: Side,
+    start: usize,
+    end: usize,
+    text: &'t str,
+}
+
+/// The edits that turn `base` into `version`, in base order.
+fn changes<'t>(base: &str, version: &'t str, side: Side) -> Vec<Change<'t>> {
+    let diff = TextDiff::compute(base, version);
+    let end = Segment { old_start: base.len(), new_start: version.len(), len: 0 };
+    let mut changes = Vec::new();
+    let mut cursor = (0, 0);
+    for segment in diff.segments.iter().chain(std::iter::once(&end)) {
+        if cursor.0 < segment.old_start || cursor.1 < segment.new_start {
+            changes.push(Change { side, start: cursor.0, end: segment.old_start, text: &version[cursor.1..segment.new_start] });
+        }
+        cursor = (segment.old_start + segment.len, segment.new_start + segment.len);
+    }
+    changes
+}
+
+/// Whether two edits from different versions touch the same base text.
+/// Edits that only meet at a boundary don't, except two insertions at the
+/// same point, whose order would be ambiguous.
+fn overlaps(x: &Change, y: &Change) -> bool {
+    match (x.start == x.end, y.start == y.end) {
+        (true, true) => x.start == y.start,
+        (true, false) => y.start < x.start && x.start < y.end,
+        (false, true) => x.start < y.start && y.start < x.end,
+        (false, false) => x.start < y.end && y.start < x.end,
+    }
+}
+
+/// `base[start..end]` with the given side's edits in the cluster applied.
+fn apply_side(base: &str, start: usize, end: usize, cluster: &[&Change], side: Side) -> String {
+    let mut text = String::new();
+    let mut cursor = start;
+    for change in cluster.iter().filter(|c| c.side == side) {
+        text.push_str(&base[cursor..change.start]);
+        text.push_str(change.text);
+        cursor = change.end;
+    }
+    text.push_str(&base[cursor..end]);
+    text
+}
+
+fn conflict_type(base_span: &str, a: &str, b: &str) -> &'static str {
+    if base_span.is_empty() {
+        "text_insertion"
+    } else if a.is_empty() || b.is_empty() {
+        "text_deletion"
+    } else {
+        "text_modification"
+    }
+}
+
+pub fn merge(base: &str, version_a: &str, version_b: &str) -> ThreeWayMerge {
+    let mut all = changes(base, version_a, Side::A);
+    all.extend(changes(base, version_b, Side::B));
+    all.sort_by_key(|c| (c.start, c.end, c.side == Side::B));
+
+    // Clusters of edits linked by overlaps between the two versions. Each
+    // version's own edits are disjoint, so a cluster closes as soon as the
+    // next edit overlaps none of its members.
+    let mut clusters: Vec<Vec<&Change>> = Vec::new();
+    for change in &all {
+        match clusters.last_mut() {
+            Some(cluster) if cluster.iter().any(|member| member.side != change.side && overlaps(member, change)) => cluster.push(change),
+            _ => clusters.push(vec![change]),
+        }
+    }
+
+    let mut merged = ThreeWayMerge { merged_text: String::with_capacity(base.len()), conflicts: Vec::new(), merged_spans: Vec::new() };
+    let mut cursor = 0;
+    for cluster in &clusters {
+        let start = cluster.iter().map(|c| c.start).min().unwrap_or(cursor);
+        let end = cluster.iter().map(|c| c.end).max().unwrap_or(cursor);
+        let a = apply_side(base, start, end, cluster, Side::A);
+        let b = apply_side(base, start, end, cluster, Side::B);
+        merged.merged_text.push_str(&base[cursor..start]);
+
+        let sides = (cluster.iter().any(|c| c.side == Side::A), cluster.iter().any(|c| c.side == Side::B));
+        if sides == (true, true) && a != b {
+            let merged_start = merged.merged_text.len();
+            merged.merged_text.push_str(&base[start..end]);
+            merged.merged_spans.push(TextRange { start_pos: merged_start, end_pos: merged.merged_text.len() });
+            merged.conflicts.push(CollaborationConflict {
+                conflict_id: format!("merge-{}", merged.conflicts.len() + 1),
+                conflict_type: conflict_type(&base[start..end], &a, &b).to_string(),
+                start_pos: start,
+                end_pos: end,
+                user_a_change: a,
+                user_b_change: b,
+                timestamp: String::new(),
+                user_a_timestamp: None,
+                user_b_timestamp: None,
+                resolution_suggestion: String::new(),
+                expected_text: Some(base[start..end].to_string()),
+                start_utf16: None,
+                end_utf16: None,
+                line: None,
+                column: None,
+            });
+        } else {
+            // One side only, or both made the same edit
+            merged.merged_text.push_str(if sides.0 { &a } else { &b });
+        }
+        cursor = end;
+    }
+    merged.merged_text.push_str(&base[cursor..]);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Cases;
+
+    const BASE: &str = "Mara walked home.\nThe rain fell hard.\nShe slept.";
+
+    fn conflicts(merged: &ThreeWayMerge) -> Vec<(&str, &str, &str, &str)> {
+        merged
+            .conflicts
+            .iter()
+            .map(|c| (c.conflict_type.as_str(), &BASE[c.start_pos..c.end_pos], c.user_a_change.as_str(), c.user_b_change.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn edits_on_different_lines_merge_cleanly() {
+        let a = "Mara ran home.\nThe rain fell hard.\nShe slept.";
+        let b = "Mara walked home.\nThe rain fell hard.\nShe slept late.";
+        let merged = merge(BASE, a, b);
+        assert_eq!(merged.merged_text, "Mara ran home.\nThe rain fell hard.\nShe slept late.");
+        assert!(merged.conflicts.is_empty() && merged.merged_spans.is_empty());
+    }
+
+    #[test]
+    fn overlapping_edits_become_conflicts_on_the_base_text() {
+        let a = "Mara ran home.\nThe rain fell hard.\nShe slept.";
+        let b = "Mara drove home.\nThe rain fell hard.\nShe dreamed.";
+        let merged = merge(BASE, a, b);
+        assert_eq!(conflicts(&merged), [("text_modification", "walked", "ran", "drove")]);
+        assert_eq!(merged.merged_text, "Mara walked home.\nThe rain fell hard.\nShe dreamed.");
+        let span = &merged.merged_spans[0];
+        assert_eq!(&merged.merged_text[span.start_pos..span.end_pos], "walked");
+        let conflict = &merged.conflicts[0];
+        assert_eq!(conflict.expected_text.as_deref(), Some("walked"));
+        assert_eq!(conflict.conflict_id, "merge-1");
+    }
+
+    #[test]
+    fn identical_edits_are_not_conflicts() {
+        let edited = "Mara ran home.\nThe rain fell softly.\nShe slept.";
+        let merged = merge(BASE, edited, edited);
+        assert_eq!(merged.merged_text, edited);
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn adjacent_edits_merge_and_insertions_at_one_point_conflict() {
+        // A edits "walked", B edits "home" right after the space following it
+        let merged = merge(BASE, &BASE.replace("walked", "ran"), &BASE.replace("home.", "away."));
+        assert_eq!(merged.merged_text, BASE.replace("walked home.", "ran away."));
+        assert!(merged.conflicts.is_empty());
+
+        let merged = merge(BASE, &BASE.replace("She slept.", "She slept. Well."), &BASE.replace("She slept.", "She slept. Badly."));
+        assert_eq!(merged.merged_text, BASE);
+        let (kind, base_text, a, b) = conflicts(&merged)[0];
+        assert_eq!(conflicts(&merged).len(), 1);
+        assert!(a.contains("Well") && b.contains("Badly") && !base_text.contains("Well"), "{:?}", (kind, base_text, a, b));
+
+        let merged = merge(BASE, &BASE.replace("\nShe slept.", ""), &BASE.replace("She slept.", "She woke."));
+        assert_eq!(merged.conflicts[0].conflict_type, "text_deletion");
+    }
+
+    #[test]
+    fn one_sided_edits_reproduce_that_side() {
+        const WORDS: &[&str] = &["Mara", "walked", "home", "the", "rain", "\n", ".", "caf\u{e9}"];
+        let mut cases = Cases(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..300 {
+            let base: Vec<&str> = (0..cases.below(30)).map(|_| WORDS[cases.below(WORDS.len())]).collect();
+            let mut edited = base.clone();
+            for _ in 0..cases.below(4) {
+                let at = cases.below(edited.len() + 1);
+                if cases.below(2) == 0 || at == edited.len() {
+                    edited.insert(at, WORDS[cases.below(WORDS.len())]);
+                } else {
+                    edited.remove(at);
+                }
+            }
+            let (base, edited) = (base.join(" "), edited.join(" "));
+            for merged in [merge(&base, &edited, &base), merge(&base, &base, &edited), merge(&base, &edited, &edited)] {
+                assert_eq!(merged.merged_text, edited, "{:?} -> {:?}", base, edited);
+                assert!(merged.conflicts.is_empty());
+            }
+        }
+    }
+}
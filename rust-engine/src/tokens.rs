@@ -0,0 +1,285 @@
+//! Single-pass tokenizer: one scan of a document yields words, whitespace,
+//! punctuation, quotes, and paragraph breaks with their byte positions, from
+//! which the index derives sentences and paragraphs without separate regex
+//! passes.
+
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A Unicode (UAX #29) word.
+    Word,
+    Whitespace,
+    /// A whitespace run holding two or more line breaks.
+    ParagraphBreak,
+    /// A run of sentence terminators: `.`, `!`, `?`.
+    Terminator,
+    /// Any other single character outside a word.
+    Punctuation,
+    /// A straight or curly quotation mark, or a guillemet.
+    Quote,
+}
+
+#[derive(Clone, Copy)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+const QUOTES: &[char] = &['"', '\'', '\u{201C}', '\u{201D}', '\u{2018}', '\u{2019}', '\u{00AB}', '\u{00BB}'];
+
+fn is_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?')
+}
+
+/// Calls `emit` with each token of `text` in order; together they cover
+/// every byte. Tokens are handed over rather than collected, since a long
+/// document has hundreds of thousands of them.
+pub fn scan(text: &str, mut emit: impl FnMut(Token)) {
+    let mut cursor = 0;
+    for (start, word) in text.unicode_word_indices() {
+        gap_tokens(text, cursor, start, &mut emit);
+        emit(Token { kind: TokenKind::Word, start, end: start + word.len() });
+        cursor = start + word.len();
+    }
+    gap_tokens(text, cursor, text.len(), &mut emit);
+}
+
+/// Tokens for the text between two words. Whitespace and terminators group
+/// into runs; everything else is one token per character.
+fn gap_tokens(text: &str, start: usize, end: usize, emit: &mut impl FnMut(Token)) {
+    // Most gaps are a single space
+    if end == start + 1 && text.as_bytes()[start] == b' ' {
+        emit(Token { kind: TokenKind::Whitespace, start, end });
+        return;
+    }
+    let mut chars = text[start..end].char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        let token_start = start + offset;
+        let mut token_end = token_start + c.len_utf8();
+        let kind = if c.is_whitespace() {
+            let mut newlines = usize::from(c == '\n');
+            while let Some(&(next_offset, next)) = chars.peek().filter(|(_, next)| next.is_whitespace()) {
+                newlines += usize::from(next == '\n');
+                token_end = start + next_offset + next.len_utf8();
+                chars.next();
+            }
+            if newlines >= 2 { TokenKind::ParagraphBreak } else { TokenKind::Whitespace }
+        } else if is_terminator(c) {
+            while let Some(&(next_offset, next)) = chars.peek().filter(|(_, next)| is_terminator(*next)) {
+                token_end = start + next_offset + next.len_utf8();
+                chars.next();
+            }
+            TokenKind::Terminator
+        } else if QUOTES.contains(&c) {
+            TokenKind::Quote
+        } else {
+            TokenKind::Punctuation
+        };
+        emit(Token { kind, start: token_start, end: token_end });
+    }
+}
+
+/// Periods inside a word ("e.g", "3.14"). The built-in sentence pattern
+/// treats each as a terminator of its own, so the index does too.
+pub fn inner_periods<'t>(text: &'t str, word: Token) -> impl Iterator<Item = (usize, usize)> + 't {
+    text[word.start..word.end].match_indices('.').map(move |(i, _)| (word.start + i, word.start + i + 1))
+}
+
+/// Names of the token kinds in a `TokenStream`, by kind number.
+pub const KINDS: &[&str] = &["word", "whitespace", "paragraph_break", "punctuation", "quote_open", "quote_close"];
+
+const KIND_WORD: u8 = 0;
+const KIND_WHITESPACE: u8 = 1;
+const KIND_PARAGRAPH_BREAK: u8 = 2;
+const KIND_PUNCTUATION: u8 = 3;
+const KIND_QUOTE_OPEN: u8 = 4;
+const KIND_QUOTE_CLOSE: u8 = 5;
+
+/// Names of the `TokenStream` style flags, by bit.
+pub const FLAGS: &[&str] = &["adverb", "filler", "in_dialogue", "passive", "filter_word"];
+
+pub const FLAG_ADVERB: u8 = 1;
+pub const FLAG_FILLER: u8 = 1 << 1;
+pub const FLAG_IN_DIALOGUE: u8 = 1 << 2;
+pub const FLAG_PASSIVE: u8 = 1 << 3;
+pub const FLAG_FILTER_WORD: u8 = 1 << 4;
+
+/// Every token of a text as parallel arrays, one entry per token, which
+/// serialize far smaller than an object per token.
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenStream {
+    /// Index into `kind_names`.
+    pub kinds: Vec<u8>,
+    /// Byte ranges.
+    pub starts: Vec<u32>,
+    pub ends: Vec<u32>,
+    /// Style flags, bit `i` meaning `flag_names[i]`.
+    pub flags: Vec<u8>,
+    pub kind_names: &'static [&'static str],
+    pub flag_names: &'static [&'static str],
+}
+
+/// Spans the analysis found, each list sorted, for the flags of the tokens
+/// overlapping them.
+pub struct StyleSpans {
+    pub adverbs: Vec<(usize, usize)>,
+    pub fillers: Vec<(usize, usize)>,
+    pub dialogue: Vec<(usize, usize)>,
+    pub passive: Vec<(usize, usize)>,
+    pub filter_words: Vec<(usize, usize)>,
+}
+
+/// Whether `start..end` overlaps a span of `spans`, moving `cursor` past
+/// the spans that end before it. Tokens must be visited in order.
+fn overlaps(spans: &[(usize, usize)], cursor: &mut usize, start: usize, end: usize) -> bool {
+    while spans.get(*cursor).is_some_and(|s| s.1 <= start) {
+        *cursor += 1;
+    }
+    spans.get(*cursor).is_some_and(|s| s.0 < end)
+}
+
+/// Kind number of the quote token at `start..end`: the opening or closing
+/// quote of a quotation in `dialogue`, or else judged from the character
+/// and what precedes it.
+fn quote_kind(text: &str, start: usize, end: usize, dialogue: &[(usize, usize)]) -> u8 {
+    // Quotations are disjoint, so their ends are sorted too
+    if dialogue.binary_search_by_key(&start, |s| s.0).is_ok() {
+        return KIND_QUOTE_OPEN;
+    }
+    if dialogue.binary_search_by_key(&end, |s| s.1).is_ok() {
+        return KIND_QUOTE_CLOSE;
+    }
+    match text[start..end].chars().next() {
+        Some('\u{201C}' | '\u{2018}' | '\u{00AB}') => KIND_QUOTE_OPEN,
+        Some('"' | '\'') if text[..start].chars().next_back().is_none_or(|c| c.is_whitespace() || "([{".contains(c)) => KIND_QUOTE_OPEN,
+        _ => KIND_QUOTE_CLOSE,
+    }
+}
+
+/// The tokens of `text` with their style flags.
+pub fn token_stream(text: &str, spans: &StyleSpans) -> TokenStream {
+    let mut stream = TokenStream { kind_names: KINDS, flag_names: FLAGS, ..TokenStream::default() };
+    let lists: [(&[(usize, usize)], u8); 5] = [
+        (&spans.adverbs, FLAG_ADVERB),
+        (&spans.fillers, FLAG_FILLER),
+        (&spans.dialogue, FLAG_IN_DIALOGUE),
+        (&spans.passive, FLAG_PASSIVE),
+        (&spans.filter_words, FLAG_FILTER_WORD),
+    ];
+    let mut cursors = [0; 5];
+    scan(text, |token| {
+        let kind = match token.kind {
+            TokenKind::Word => KIND_WORD,
+            TokenKind::Whitespace => KIND_WHITESPACE,
+            TokenKind::ParagraphBreak => KIND_PARAGRAPH_BREAK,
+            TokenKind::Terminator | TokenKind::Punctuation => KIND_PUNCTUATION,
+            TokenKind::Quote => quote_kind(text, token.start, token.end, &spans.dialogue),
+        };
+        let mut flags = 0;
+        for ((list, flag), cursor) in lists.iter().zip(&mut cursors) {
+            if overlaps(list, cursor, token.start, token.end) {
+                flags |= flag;
+            }
+        }
+        stream.kinds.push(kind);
+        stream.starts.push(token.start as u32);
+        stream.ends.push(token.end as u32);
+        stream.flags.push(flags);
+    });
+    stream
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    fn tokens(text: &str) -> Vec<(&'static str, &str)> {
+        let mut tokens = Vec::new();
+        scan(text, |token| {
+            let kind = match token.kind {
+                TokenKind::Word => "word",
+                TokenKind::Whitespace => "space",
+                TokenKind::ParagraphBreak => "break",
+                TokenKind::Terminator => "end",
+                TokenKind::Punctuation => "punct",
+                TokenKind::Quote => "quote",
+            };
+            tokens.push((kind, &text[token.start..token.end]));
+        });
+        tokens
+    }
+
+    #[test]
+    fn tokens_are_typed_and_cover_every_byte() {
+        let text = "\u{201c}Run!\u{201d} she said\u{a0}\u{2014} twice?!\n \n\u{ab}e.g. caf\u{e9}\u{bb}";
+        assert_eq!(
+            tokens(text),
+            [
+                ("quote", "\u{201c}"),
+                ("word", "Run"),
+                ("end", "!"),
+                ("quote", "\u{201d}"),
+                ("space", " "),
+                ("word", "she"),
+                ("space", " "),
+                ("word", "said"),
+                ("space", "\u{a0}"),
+                ("punct", "\u{2014}"),
+                ("space", " "),
+                ("word", "twice"),
+                ("end", "?!"),
+                ("break", "\n \n"),
+                ("quote", "\u{ab}"),
+                ("word", "e.g"),
+                ("end", "."),
+                ("space", " "),
+                ("word", "caf\u{e9}"),
+                ("quote", "\u{bb}"),
+            ]
+        );
+        assert_eq!(tokens(text).iter().map(|t| t.1).collect::<String>(), text);
+        assert!(tokens("").is_empty());
+        assert_eq!(tokens("a\n\r\nb")[1], ("break", "\n\r\n"));
+    }
+
+    #[test]
+    fn inner_periods_are_found_inside_words() {
+        let text = "Pi is 3.14 e.g";
+        let mut periods = Vec::new();
+        scan(text, |token| {
+            if token.kind == TokenKind::Word {
+                periods.extend(inner_periods(text, token));
+            }
+        });
+        assert_eq!(periods, [(7, 8), (12, 13)]);
+    }
+
+    #[test]
+    fn tokenize_flags_agree_with_metrics() {
+        let text = "\u{201C}Come quickly,\u{201D} she said softly. He really felt the letter was written hastily, \
+and he saw it clearly in order to leave.\n\nThey walked slowly home.";
+        let processor = TextProcessor::new();
+        let tokens = processor.tokenize(text);
+        let analysis = processor.analyze_text(text);
+        let flagged = |flag: u8| tokens.flags.iter().filter(|&&f| f & flag != 0).count();
+        let adverbs = (analysis.style_metrics.adverb_ratio * analysis.word_count as f64).round() as usize;
+        assert!(adverbs >= 4);
+        assert_eq!(flagged(FLAG_ADVERB), adverbs);
+        let word = |w: &str| (0..tokens.kinds.len()).find(|&i| &text[tokens.starts[i] as usize..tokens.ends[i] as usize] == w).unwrap();
+        assert_ne!(tokens.flags[word("Come")] & FLAG_IN_DIALOGUE, 0);
+        assert_eq!(tokens.flags[word("she")] & FLAG_IN_DIALOGUE, 0);
+        assert_ne!(tokens.flags[word("written")] & FLAG_PASSIVE, 0);
+        assert_ne!(tokens.flags[word("really")] & FLAG_FILLER, 0);
+        assert_ne!(tokens.flags[word("felt")] & FLAG_FILTER_WORD, 0);
+        let kind = |i: usize| tokens.kind_names[tokens.kinds[i] as usize];
+        assert_eq!((kind(0), kind(word("Come")), kind(word("Come") + 3)), ("quote_open", "word", "punctuation"));
+        assert_eq!(kind(word("Come") + 4), "quote_close");
+        assert_eq!(tokens.kinds.iter().filter(|&&k| tokens.kind_names[k as usize] == "paragraph_break").count(), 1);
+        assert_eq!((tokens.starts[0], *tokens.ends.last().unwrap()), (0, text.len() as u32));
+    }
+}
@@ -0,0 +1,116 @@
+//! Operational-transform remapping of record positions (suggestions,
+//! conflicts) through edits made after they were computed, so highlights
+//! stay put between analysis runs.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// `start..end` of the text at the time of the edit replaced by
+/// `replacement_len` bytes.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct PositionEdit {
+    pub start: usize,
+    pub end: usize,
+    pub replacement_len: usize,
+}
+
+/// Position keys a record may carry, in order of preference.
+const POSITION_KEYS: &[(&str, &str)] = &[("startPos", "endPos"), ("start_pos", "end_pos"), ("start", "end")];
+
+/// `(start, end)` after `edit`, and whether the edit touched the span. An
+/// insertion at the start of a span goes before it; one at the end goes
+/// after it. A span the edit deletes outright collapses to where the edit
+/// was; one it overlaps covers the text that replaced the overlap.
+fn transform(start: usize, end: usize, edit: PositionEdit) -> (usize, usize, bool) {
+    let shift = |p: usize| p - (edit.end - edit.start) + edit.replacement_len;
+    if edit.end <= start {
+        return (shift(start), shift(end), false);
+    }
+    if edit.start >= end {
+        return (start, end, false);
+    }
+    if edit.start <= start && edit.end >= end {
+        return (edit.start, edit.start, true);
+    }
+    let new_start = start.min(edit.start);
+    let new_end = if edit.end >= end { edit.start + edit.replacement_len } else { shift(end) };
+    (new_start, new_end, true)
+}
+
+/// `items` with their positions carried through `edits`, applied in order.
+/// Records an edit deleted or overlapped get `"stale": true`; positions
+/// derived from the old text (`startUtf16`, `line`, ...) are not updated.
+pub fn transform_positions(mut items: Vec<Value>, edits: &[PositionEdit]) -> Result<Vec<Value>, String> {
+    if let Some(i) = edits.iter().position(|e| e.start > e.end) {
+        return Err(format!("Edit {} ends before it starts", i));
+    }
+    for (i, item) in items.iter_mut().enumerate() {
+        let keys = POSITION_KEYS.iter().find(|(s, e)| item.get(s).is_some_and(Value::is_u64) && item.get(e).is_some_and(Value::is_u64));
+        let Some(&(start_key, end_key)) = keys else {
+            return Err(format!("Item {} has no startPos/endPos (or start_pos/end_pos, start/end) offsets", i));
+        };
+        let (mut start, mut end) = (item[start_key].as_u64().unwrap() as usize, item[end_key].as_u64().unwrap() as usize);
+        if start > end {
+            return Err(format!("Item {} ends before it starts", i));
+        }
+        let mut stale = false;
+        for &edit in edits {
+            let (new_start, new_end, touched) = transform(start, end, edit);
+            (start, end) = (new_start, new_end);
+            stale |= touched;
+        }
+        item[start_key] = start.into();
+        item[end_key] = end.into();
+        if stale {
+            item["stale"] = true.into();
+        }
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    fn transformed(start: usize, end: usize, edits: &[(usize, usize, usize)]) -> serde_json::Value {
+        let item = serde_json::json!({ "suggestionType": "adverb_usage", "startPos": start, "endPos": end });
+        let edits: Vec<PositionEdit> =
+            edits.iter().map(|&(start, end, replacement_len)| PositionEdit { start, end, replacement_len }).collect();
+        TextProcessor::new().transform_positions(vec![item], &edits).unwrap().remove(0)
+    }
+
+    fn span(item: &serde_json::Value) -> (u64, u64, bool) {
+        (item["startPos"].as_u64().unwrap(), item["endPos"].as_u64().unwrap(), item.get("stale").is_some())
+    }
+
+    #[test]
+    fn transform_positions_around_insertions() {
+        // Before the span, at its start, inside it, at its end, after it
+        assert_eq!(span(&transformed(10, 20, &[(2, 2, 5)])), (15, 25, false));
+        assert_eq!(span(&transformed(10, 20, &[(10, 10, 5)])), (15, 25, false));
+        assert_eq!(span(&transformed(10, 20, &[(14, 14, 5)])), (10, 25, true));
+        assert_eq!(span(&transformed(10, 20, &[(20, 20, 5)])), (10, 20, false));
+        assert_eq!(span(&transformed(10, 20, &[(30, 30, 5)])), (10, 20, false));
+        assert_eq!(transformed(10, 20, &[(2, 2, 5)])["suggestionType"], "adverb_usage");
+    }
+
+    #[test]
+    fn transform_positions_collapses_deleted_spans() {
+        assert_eq!(span(&transformed(10, 20, &[(5, 25, 0)])), (5, 5, true));
+        assert_eq!(span(&transformed(10, 20, &[(10, 20, 3)])), (10, 10, true));
+        // Deleting the tail of the span keeps what survives, plus the replacement
+        assert_eq!(span(&transformed(10, 20, &[(15, 25, 2)])), (10, 17, true));
+    }
+
+    #[test]
+    fn transform_positions_composes_sequential_edits() {
+        // Type 4 bytes before the span, delete 6 before it, then type 1 after it
+        let edits = [(0, 0, 4), (1, 7, 0), (20, 20, 1)];
+        assert_eq!(span(&transformed(10, 20, &edits)), (8, 18, false));
+        let one_edit = [(0, 7, 5)];
+        assert_eq!(span(&transformed(10, 20, &edits[..2])), span(&transformed(10, 20, &one_edit)));
+        let error = TextProcessor::new().transform_positions(vec![serde_json::json!({ "id": 1 })], &[]).err();
+        assert!(error.is_some());
+    }
+}
@@ -0,0 +1,223 @@
+//! TypeScript declarations for the main result shapes, emitted into the
+//! generated `.d.ts` so `analyze_text`, `optimize_text` and
+//! `resolve_conflicts` are typed rather than `any`. Field names match the serialized (camelCase) structs; keep
+//! the two in step when a field is added.
+
+// Only wasm32 builds emit custom sections
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub const RESULT_TYPES: &str = r#"
+export interface RelatedSpan {
+  startPos: number;
+  endPos: number;
+}
+
+export interface AnalysisWarning {
+  code: string;
+  message: string;
+  cause?: string;
+  spans?: RelatedSpan[];
+}
+
+export interface ComplexityMetrics {
+  avgWordsPerSentence: number;
+  avgSyllablesPerWord: number;
+  fogIndex: number;
+  fleschReadingEase: number;
+  uniqueWordRatio: number;
+  contentWordCount: number;
+  uniqueContentWordRatio: number;
+  avgContentWordLength: number;
+  smogIndex: number;
+  colemanLiauIndex: number;
+  automatedReadabilityIndex: number;
+  lix: number;
+  sentenceLengthStdDev: number;
+  mtld: number;
+  mattr: number;
+  lexicalDensity: number;
+}
+
+export interface StyleMetrics {
+  passiveVoiceRatio: number;
+  adverbRatio: number;
+  dialogueRatio: number;
+  actionRatio: number;
+  descriptionRatio: number;
+  filterWordRatio: number;
+  clichesPer1000Words: number;
+}
+
+export interface TextAnalysisResult {
+  wordCount: number;
+  characterCount: number;
+  graphemeCount: number;
+  paragraphCount: number;
+  sentenceCount: number;
+  estimatedReadingTimeSeconds: number;
+  estimatedSpeakingTimeSeconds: number;
+  readabilityScore: number;
+  readabilityScoreRaw: number;
+  complexityMetrics: ComplexityMetrics;
+  styleMetrics: StyleMetrics;
+  contentHash: string;
+  firstPersonPer1000Words?: number;
+  warnings: AnalysisWarning[];
+  lowConfidence: boolean;
+  notSurfaced?: string[];
+  sentimentScore: number;
+  detectedLanguage?: string;
+  progressToTarget?: number;
+}
+
+export interface SuggestionContext {
+  text: string;
+  startPos: number;
+  highlightStart: number;
+  highlightEnd: number;
+  truncated: boolean;
+}
+
+export interface OptimizationSuggestion {
+  suggestionType: string;
+  priority: "low" | "medium" | "high";
+  message: string;
+  startPos: number;
+  endPos: number;
+  suggestedReplacement: string | null;
+  scope: "instance" | "document";
+  fingerprint: string;
+  relatedSpans?: RelatedSpan[];
+  score?: number;
+  context?: SuggestionContext;
+  startUtf16: number;
+  endUtf16: number;
+  line: number;
+  column: number;
+}
+
+export interface RuleSummary {
+  rule: string;
+  count: number;
+  densityPer1000Words: number;
+  priority: "low" | "medium" | "high";
+  escalated: boolean;
+}
+
+export interface OptimizationReport {
+  suggestions: OptimizationSuggestion[];
+  ruleSummaries: RuleSummary[];
+  suppressedByDialect: number;
+  suppressedByLanguage: number;
+  lowConfidence: boolean;
+  contextPayloadBytes?: number;
+}
+
+export interface CollaborationConflict {
+  conflictId: string;
+  conflictType: string;
+  startPos: number;
+  endPos: number;
+  userAChange: string;
+  userBChange: string;
+  timestamp: string;
+  userATimestamp?: string;
+  userBTimestamp?: string;
+  resolutionSuggestion: string;
+  expectedText?: string;
+  startUtf16?: number;
+  endUtf16?: number;
+  line?: number;
+  column?: number;
+}
+
+export interface RejectedConflict {
+  index: number;
+  conflictId: string | null;
+  reason: string;
+  detail: string;
+}
+
+export interface SpanReconciliation {
+  conflictId: string;
+  status: string;
+  startPos: number;
+  endPos: number;
+  originalStartPos: number;
+  originalEndPos: number;
+  distance?: number;
+}
+
+export interface ConflictResolutionResponse {
+  resolved: CollaborationConflict[];
+  rejected: RejectedConflict[];
+  reconciliation?: SpanReconciliation[];
+}
+
+export interface TokenStream {
+  kinds: Uint8Array;
+  starts: Uint32Array;
+  ends: Uint32Array;
+  flags: Uint8Array;
+  kindNames: string[];
+  flagNames: string[];
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolution::ConflictPolicy;
+    use crate::{RelatedSpan, TextProcessor};
+    use crate::context::SuggestionContext;
+    use crate::warnings::AnalysisWarning;
+
+    /// Every key a populated result serializes must be declared for TypeScript.
+    fn assert_declared(value: &serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    let declared = [format!("  {}: ", key), format!("  {}?: ", key)];
+                    assert!(declared.iter().any(|d| RESULT_TYPES.contains(d.as_str())), "{} is not declared", key);
+                    assert_declared(value);
+                }
+            }
+            serde_json::Value::Array(items) => items.iter().for_each(assert_declared),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn typescript_declarations_cover_result_fields() {
+        let processor = TextProcessor::new();
+        let mut analysis = processor.analyze_text("I wrote this. You read it.");
+        analysis.first_person_per_1000_words.get_or_insert(1.0);
+        analysis.not_surfaced.push("passive_voice".to_string());
+        let mut warning = AnalysisWarning::new("code", "message".to_string());
+        warning.cause = Some("cause".to_string());
+        warning.spans.push(RelatedSpan { start_pos: 0, end_pos: 1 });
+        analysis.warnings.push(warning);
+        assert_declared(&serde_json::to_value(&analysis).unwrap());
+
+        let mut suggestion = processor.optimize_text("The letter was written by the clerk.").remove(0);
+        suggestion.related_spans.push(RelatedSpan { start_pos: 0, end_pos: 1 });
+        suggestion.score = Some(0.5);
+        suggestion.context = Some(SuggestionContext { text: String::new(), start_pos: 0, highlight_start: 0, highlight_end: 0, truncated: false });
+        assert_declared(&serde_json::to_value(&suggestion).unwrap());
+
+        let mut report = processor.optimize_text_report("He walked slowly. The door was opened.");
+        report.context_payload_bytes = Some(0);
+        assert!(!report.rule_summaries.is_empty());
+        assert_declared(&serde_json::to_value(&report).unwrap());
+        assert_declared(&serde_json::to_value(processor.tokenize("It was very cold.")).unwrap());
+
+        let input = r#"[{"conflictId": "c1", "conflictType": "text_insertion", "startPos": 4, "endPos": 4,
+            "userAChange": "very", "userBChange": "rather", "timestamp": "2024-01-01T00:00:00Z",
+            "userATimestamp": "2024-01-01T00:00:00Z", "userBTimestamp": "2024-01-01T00:00:01Z", "expectedText": ""},
+            {"conflictId": "c2"}]"#;
+        let response = processor.resolve_conflicts_json(input, Some("The cold night."), ConflictPolicy::Auto).unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert!(response["reconciliation"].as_array().is_some_and(|r| !r.is_empty()));
+        assert!(response["resolved"][0]["line"].is_u64());
+        assert_declared(&response);
+    }
+}
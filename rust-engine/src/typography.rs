@@ -0,0 +1,426 @@
+//! Mechanical typography: stray spacing, doubled and misplaced punctuation,
+//! unclosed parentheses, straight quotes among curly ones, and tabs inside a
+//! line. Each finding is a rule of its own, with a replacement wherever the
+//! fix is certain.
+
+use serde::Deserialize;
+
+use crate::segmentation::DocumentIndex;
+use crate::{OptimizationSuggestion, RelatedSpan};
+
+/// Rule ids of the suggestions below, for callers that treat them as a group.
+pub const RULE_IDS: &[&str] = &[
+    "double_space",
+    "tab_in_text",
+    "space_before_punctuation",
+    "missing_space_after_punctuation",
+    "repeated_punctuation",
+    "unmatched_parenthesis",
+    "mixed_quotes",
+];
+
+/// Which fixes `normalize` applies.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct TypographyOptions {
+    /// Runs of spaces between words become one space. Indentation and
+    /// trailing spaces are left alone.
+    pub collapse_spaces: bool,
+    /// Straight quotation marks and apostrophes become curly ones.
+    pub curly_quotes: bool,
+}
+
+impl Default for TypographyOptions {
+    fn default() -> Self {
+        TypographyOptions { collapse_spaces: true, curly_quotes: true }
+    }
+}
+
+const SPACED_PUNCTUATION: &[u8] = b",;:.!?";
+
+fn char_before(text: &str, at: usize) -> Option<char> {
+    text[..at].chars().next_back()
+}
+
+fn char_after(text: &str, at: usize) -> Option<char> {
+    text[at..].chars().next()
+}
+
+fn touches(spans: &[(usize, usize)], start: usize, end: usize) -> bool {
+    spans.iter().any(|&(s, e)| s < end && start < e)
+}
+
+/// Runs of spaces and tabs with text on both sides on the same line, as
+/// `(start, end)`.
+fn inner_blank_runs(text: &str) -> Vec<(usize, usize)> {
+    let b = text.as_bytes();
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i < b.len() {
+        if b[i] != b' ' && b[i] != b'\t' {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < b.len() && (b[i] == b' ' || b[i] == b'\t') {
+            i += 1;
+        }
+        let inside_line = char_before(text, start).is_some_and(|c| !c.is_whitespace())
+            && char_after(text, i).is_some_and(|c| !c.is_whitespace());
+        if inside_line {
+            runs.push((start, i));
+        }
+    }
+    runs
+}
+
+/// The curly form of the straight quote at `at`: opening after whitespace,
+/// an opening bracket or dash, or another opening quote; closing (or an
+/// apostrophe) otherwise. An apostrophe before a digit ("'90s") stays closing.
+fn curly(text: &str, at: usize) -> &'static str {
+    let opens = char_before(text, at).is_none_or(|c| c.is_whitespace() || "([{\u{2014}\u{2013}\u{201C}\u{2018}".contains(c));
+    match &text[at..at + 1] {
+        "\"" if opens => "\u{201C}",
+        "\"" => "\u{201D}",
+        _ if opens && !char_after(text, at + 1).is_some_and(|c| c.is_ascii_digit()) => "\u{2018}",
+        _ => "\u{2019}",
+    }
+}
+
+/// Whether the parenthesis at `at` is part of a smiley (":)", ";-(").
+fn is_smiley(text: &str, at: usize) -> bool {
+    let before = text[..at].strip_suffix('-').unwrap_or(&text[..at]);
+    before.ends_with([':', ';'])
+}
+
+/// Whether the `)` at `at` closes a list marker ("1)", "b)") at the start
+/// of a line.
+fn is_list_marker(text: &str, at: usize) -> bool {
+    let marker = text[text[..at].rfind('\n').map_or(0, |i| i + 1)..at].trim_start();
+    !marker.is_empty() && marker.len() <= 3 && marker.chars().all(char::is_alphanumeric)
+}
+
+fn spacing(text: &str, opaque: &[(usize, usize)], suggestions: &mut Vec<OptimizationSuggestion>) {
+    for (start, end) in inner_blank_runs(text) {
+        if touches(opaque, start, end) {
+            continue;
+        }
+        let run = &text[start..end];
+        let found = if run.contains('\t') {
+            OptimizationSuggestion::instance("tab_in_text", "low", "A tab inside a line; use a space instead.", start, end)
+        } else if run.len() > 1 {
+            OptimizationSuggestion::instance("double_space", "low", "More than one space between words.", start, end)
+        } else {
+            continue;
+        };
+        suggestions.push(OptimizationSuggestion { suggested_replacement: Some(" ".to_string()), ..found });
+    }
+}
+
+fn punctuation_spacing(text: &str, opaque: &[(usize, usize)], suggestions: &mut Vec<OptimizationSuggestion>) {
+    let b = text.as_bytes();
+    for (i, &c) in b.iter().enumerate() {
+        if !SPACED_PUNCTUATION.contains(&c) || touches(opaque, i, i + 1) {
+            continue;
+        }
+        let next = b.get(i + 1).copied();
+
+        // "word ," but not an ellipsis (" ..."), a decimal (" .5"), or a smiley
+        let blanks = text[..i].len() - text[..i].trim_end_matches([' ', '\t']).len();
+        let leading = blanks > 0 && char_before(text, i - blanks).is_some_and(|c| c.is_alphanumeric() || "\"')]\u{201D}\u{2019}".contains(c));
+        let exempt = next.is_some_and(|n| n == b'.' || n.is_ascii_digit() || (matches!(c, b':' | b';') && matches!(n, b')' | b'(' | b'-')));
+        if leading && !exempt && next.is_none_or(|n| n.is_ascii_whitespace() || SPACED_PUNCTUATION.contains(&n)) {
+            suggestions.push(OptimizationSuggestion {
+                suggested_replacement: Some((c as char).to_string()),
+                ..OptimizationSuggestion::instance(
+                    "space_before_punctuation",
+                    "low",
+                    format!("Remove the space before \"{}\".", c as char),
+                    i - blanks,
+                    i + 1,
+                )
+            });
+        }
+
+        // "red,blue" and "end.Next", but not "1,000", "e.g.", or "example.com"
+        let (before, after) = (char_before(text, i), char_after(text, i + 1));
+        let missing = match c {
+            b',' | b';' => before.is_some_and(char::is_alphabetic) && after.is_some_and(char::is_alphabetic),
+            b'.' | b'!' | b'?' => {
+                before.is_some_and(char::is_lowercase)
+                    && after.is_some_and(char::is_uppercase)
+                    && text[i + 1..].chars().nth(1).is_some_and(char::is_lowercase)
+            }
+            _ => false,
+        };
+        if missing {
+            suggestions.push(OptimizationSuggestion {
+                suggested_replacement: Some(format!("{} ", c as char)),
+                ..OptimizationSuggestion::instance(
+                    "missing_space_after_punctuation",
+                    "low",
+                    format!("Add a space after \"{}\".", c as char),
+                    i,
+                    i + 1,
+                )
+            });
+        }
+    }
+}
+
+/// "!!", "??", "?!?", ",,", and ".." (an ellipsis is three).
+fn repeated_punctuation(text: &str, opaque: &[(usize, usize)], suggestions: &mut Vec<OptimizationSuggestion>) {
+    let b = text.as_bytes();
+    let mut i = 0;
+    while i < b.len() {
+        let c = b[i];
+        let class: &[u8] = match c {
+            b'!' | b'?' => b"!?",
+            b',' | b';' | b'.' => std::slice::from_ref(&b[i]),
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        let start = i;
+        while i < b.len() && class.contains(&b[i]) {
+            i += 1;
+        }
+        let run = &text[start..i];
+        let exempt = run.len() < 2 || run == "?!" || run == "!?" || (c == b'.' && run.len() != 2);
+        if exempt || touches(opaque, start, i) {
+            continue;
+        }
+        // A mixed run keeps one of each mark, the first one first
+        let replacement = match c {
+            _ if run.bytes().all(|x| x == c) => (c as char).to_string(),
+            b'!' => "!?".to_string(),
+            _ => "?!".to_string(),
+        };
+        suggestions.push(OptimizationSuggestion {
+            suggested_replacement: Some(replacement),
+            ..OptimizationSuggestion::instance(
+                "repeated_punctuation",
+                "low",
+                format!("\"{}\" repeats punctuation; one mark is enough.", run),
+                start,
+                i,
+            )
+        });
+    }
+}
+
+/// Each paragraph's "(" without a ")" and ")" without a "(", reported at the
+/// mark itself.
+fn unmatched_parentheses(index: &DocumentIndex, opaque: &[(usize, usize)], suggestions: &mut Vec<OptimizationSuggestion>) {
+    let text = index.text;
+    for &(start, end) in &index.paragraphs {
+        let mut open: Vec<usize> = Vec::new();
+        let mut stray: Vec<usize> = Vec::new();
+        for (i, c) in text[start..end].char_indices().map(|(i, c)| (start + i, c)) {
+            if touches(opaque, i, i + 1) {
+                continue;
+            }
+            match c {
+                _ if is_smiley(text, i) => {}
+                '(' => open.push(i),
+                ')' if open.pop().is_none() && !is_list_marker(text, i) => stray.push(i),
+                _ => {}
+            }
+        }
+        for at in open {
+            suggestions.push(OptimizationSuggestion {
+                related_spans: vec![RelatedSpan { start_pos: start, end_pos: end }],
+                ..OptimizationSuggestion::instance("unmatched_parenthesis", "medium", "This parenthesis is never closed.", at, at + 1)
+            });
+        }
+        for at in stray {
+            suggestions.push(OptimizationSuggestion {
+                related_spans: vec![RelatedSpan { start_pos: start, end_pos: end }],
+                ..OptimizationSuggestion::instance(
+                    "unmatched_parenthesis",
+                    "medium",
+                    "This closing parenthesis has no opening one.",
+                    at,
+                    at + 1,
+                )
+            });
+        }
+    }
+}
+
+/// Straight quotes in a document that mostly uses curly ones, or the other
+/// way round; double and single quotes (apostrophes included) are weighed
+/// separately, and ties count against straight quotes.
+fn mixed_quotes(text: &str, opaque: &[(usize, usize)], suggestions: &mut Vec<OptimizationSuggestion>) {
+    for (straight, curly_forms, name) in [('"', ['\u{201C}', '\u{201D}'], "quotation marks"), ('\'', ['\u{2018}', '\u{2019}'], "apostrophes and single quotes")] {
+        let marks: Vec<(usize, char)> =
+            text.char_indices().filter(|&(i, c)| (c == straight || curly_forms.contains(&c)) && !touches(opaque, i, i + 1)).collect();
+        let straight_count = marks.iter().filter(|m| m.1 == straight).count();
+        let curly_count = marks.len() - straight_count;
+        if straight_count == 0 || curly_count == 0 {
+            continue;
+        }
+        let flag_straight = straight_count <= curly_count;
+        for &(at, c) in &marks {
+            if (c == straight) != flag_straight {
+                continue;
+            }
+            let (replacement, message) = if flag_straight {
+                (curly(text, at).to_string(), format!("Straight quote in a document that mostly uses curly {}.", name))
+            } else {
+                (straight.to_string(), format!("Curly quote in a document that mostly uses straight {}.", name))
+            };
+            suggestions.push(OptimizationSuggestion {
+                suggested_replacement: Some(replacement),
+                ..OptimizationSuggestion::instance("mixed_quotes", "low", message, at, at + c.len_utf8())
+            });
+        }
+    }
+}
+
+/// Every typography finding in `index`'s text, skipping opaque tokens.
+pub fn suggestions(index: &DocumentIndex, opaque: &[(usize, usize)]) -> Vec<OptimizationSuggestion> {
+    let text = index.text;
+    let mut suggestions = Vec::new();
+    spacing(text, opaque, &mut suggestions);
+    punctuation_spacing(text, opaque, &mut suggestions);
+    repeated_punctuation(text, opaque, &mut suggestions);
+    unmatched_parentheses(index, opaque, &mut suggestions);
+    mixed_quotes(text, opaque, &mut suggestions);
+    suggestions
+}
+
+/// `text` with the fixes in `options` applied wherever `editable` holds for
+/// the bytes involved.
+pub fn normalize(text: &str, options: &TypographyOptions, editable: impl Fn(usize) -> bool) -> String {
+    let collapsed: Vec<(usize, usize)> = if options.collapse_spaces {
+        inner_blank_runs(text)
+            .into_iter()
+            .filter(|&(s, e)| e - s > 1 && text[s..e].bytes().all(|c| c == b' ') && (s..e).all(&editable))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let mut out = String::with_capacity(text.len());
+    let mut runs = collapsed.iter().peekable();
+    let mut i = 0;
+    while i < text.len() {
+        if let Some(&&(_, end)) = runs.peek().filter(|r| r.0 == i) {
+            out.push(' ');
+            i = end;
+            runs.next();
+            continue;
+        }
+        let c = char_after(text, i).unwrap();
+        if options.curly_quotes && (c == '"' || c == '\'') && editable(i) {
+            out.push_str(curly(text, i));
+        } else {
+            out.push(c);
+        }
+        i += c.len_utf8();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TextProcessor;
+    use crate::config::{InputFormat, SuggestionConfig};
+    use crate::test_support::index;
+
+    use super::*;
+
+    /// `(rule, flagged text, replacement)` of every finding.
+    fn findings(text: &str) -> Vec<(String, &str, Option<String>)> {
+        let index = index(text);
+        let mut found: Vec<(usize, String, &str, Option<String>)> = suggestions(&index, &[])
+            .into_iter()
+            .map(|s| (s.start_pos, s.suggestion_type.to_string(), &text[s.start_pos..s.end_pos], s.suggested_replacement))
+            .collect();
+        found.sort_by_key(|f| f.0);
+        found.into_iter().map(|(_, rule, span, replacement)| (rule, span, replacement)).collect()
+    }
+
+    fn finding<'t>(rule: &str, span: &'t str, replacement: &str) -> (String, &'t str, Option<String>) {
+        (rule.to_string(), span, Some(replacement.to_string()))
+    }
+
+    #[test]
+    fn spacing_findings_have_exact_spans_and_fixes() {
+        assert_eq!(
+            findings("She  left\tthe room , then came back.Later she slept."),
+            [
+                finding("double_space", "  ", " "),
+                finding("tab_in_text", "\t", " "),
+                finding("space_before_punctuation", " ,", ","),
+                finding("missing_space_after_punctuation", ".", ". "),
+            ]
+        );
+        // Indentation, trailing spaces, numbers, abbreviations, and domains are fine
+        assert!(findings("    Indented line.  \nIt cost 1,000 dollars, e.g. at example.com .5 inch... done").is_empty());
+    }
+
+    #[test]
+    fn repeated_punctuation_keeps_one_mark() {
+        assert_eq!(
+            findings("What?? No!! Really?!? Fine,, then.. Wait... Why?!"),
+            [
+                finding("repeated_punctuation", "??", "?"),
+                finding("repeated_punctuation", "!!", "!"),
+                finding("repeated_punctuation", "?!?", "?!"),
+                finding("repeated_punctuation", ",,", ","),
+                finding("repeated_punctuation", "..", "."),
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_parentheses_point_at_the_mark() {
+        let found = findings("She left (for good.\n\nHe stayed) home. Smile :) and list:\n1) first\nb) second");
+        let spans: Vec<(&str, &str)> = found.iter().map(|f| (f.0.as_str(), f.1)).collect();
+        assert_eq!(spans, [("unmatched_parenthesis", "("), ("unmatched_parenthesis", ")")]);
+        assert!(found.iter().all(|f| f.2.is_none()));
+    }
+
+    #[test]
+    fn the_minority_quote_style_is_flagged() {
+        let text = "\u{201C}Go,\u{201D} she said. \u{201C}Now.\u{201D} He said \"no\" and didn't.";
+        assert_eq!(
+            findings(text),
+            [finding("mixed_quotes", "\"", "\u{201C}"), finding("mixed_quotes", "\"", "\u{201D}")]
+        );
+        // One style throughout is fine
+        assert!(findings("\"Go,\" she said. \"Now.\" It's late.").is_empty());
+    }
+
+    #[test]
+    fn normalize_collapses_spaces_and_curls_quotes() {
+        let text = "She said  \"it's 'late'\" (\"really\").  '90s rock  ";
+        assert_eq!(
+            normalize(text, &TypographyOptions::default(), |_| true),
+            "She said \u{201C}it\u{2019}s \u{2018}late\u{2019}\u{201D} (\u{201C}really\u{201D}). \u{2019}90s rock  "
+        );
+        let spaces_only = TypographyOptions { curly_quotes: false, ..Default::default() };
+        assert_eq!(normalize("a  \"b\"", &spaces_only, |_| true), "a \"b\"");
+        // Bytes that aren't editable are kept
+        let code = "x  `a  \"b\"`";
+        let editable = |at: usize| at < code.find('`').unwrap();
+        assert_eq!(normalize(code, &TypographyOptions::default(), editable), "x `a  \"b\"`");
+    }
+
+    #[test]
+    fn typography_findings_from_stripped_markup_are_dropped() {
+        let source = "Run the `build` step now.  Then wait!!";
+        let markdown = TextProcessor::with_patterns(&Default::default(), SuggestionConfig { input_format: InputFormat::Markdown, ..Default::default() });
+        let report = markdown.build_optimization_report(source);
+        let found: Vec<(&str, &str)> = report
+            .suggestions
+            .iter()
+            .filter(|s| RULE_IDS.contains(&s.suggestion_type.as_ref()))
+            .map(|s| (s.suggestion_type.as_ref(), &source[s.start_pos..s.end_pos]))
+            .collect();
+        // The gap left by the code span is not in the source
+        assert_eq!(found, [("double_space", "  "), ("repeated_punctuation", "!!")]);
+    }
+}
@@ -0,0 +1,358 @@
+//! Weak verb constructions: "to be" + adjective where one verb says it
+//! ("was aware of" -> "knew"), verbs buried in nouns ("made a decision" ->
+//! "decided"), and sentences opening on "There is/are". Replacements keep
+//! the tense of the original.
+
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::config::SuggestionConfig;
+use crate::rewrite;
+use crate::segmentation::DocumentIndex;
+use crate::OptimizationSuggestion;
+
+/// Adjective (or noun) phrases after a form of "to be", and the verb that
+/// replaces both.
+const BUILTIN_WEAK_VERBS: &[(&str, &str)] = &[
+    ("aware of", "know"),
+    ("afraid of", "fear"),
+    ("appreciative of", "appreciate"),
+    ("critical of", "criticize"),
+    ("dependent on", "depend on"),
+    ("descriptive of", "describe"),
+    ("desirous of", "want"),
+    ("envious of", "envy"),
+    ("fearful of", "fear"),
+    ("hesitant to", "hesitate to"),
+    ("illustrative of", "illustrate"),
+    ("in agreement with", "agree with"),
+    ("in attendance at", "attend"),
+    ("in need of", "need"),
+    ("in possession of", "have"),
+    ("indicative of", "indicate"),
+    ("insistent on", "insist on"),
+    ("protective of", "protect"),
+    ("reliant on", "rely on"),
+    ("suggestive of", "suggest"),
+    ("supportive of", "support"),
+];
+
+/// Light verb + noun phrases (keyed by the verb's base form) and the verb
+/// the noun buries.
+const BUILTIN_NOMINALIZATIONS: &[(&str, &str)] = &[
+    ("come to a conclusion", "conclude"),
+    ("conduct an analysis", "analyze"),
+    ("conduct an investigation", "investigate"),
+    ("conduct a review", "review"),
+    ("give an answer", "answer"),
+    ("give consideration to", "consider"),
+    ("give a description of", "describe"),
+    ("give a description", "describe"),
+    ("give an explanation of", "explain"),
+    ("give an explanation", "explain"),
+    ("have a discussion about", "discuss"),
+    ("have a discussion", "discuss"),
+    ("make an assumption", "assume"),
+    ("make an attempt", "attempt"),
+    ("make a choice", "choose"),
+    ("make a decision", "decide"),
+    ("make an effort", "try"),
+    ("make a recommendation", "recommend"),
+    ("make a suggestion", "suggest"),
+    ("perform an analysis", "analyze"),
+    ("provide assistance to", "assist"),
+    ("provide assistance", "assist"),
+    ("reach an agreement", "agree"),
+    ("reach a decision", "decide"),
+    ("take action", "act"),
+    ("take into consideration", "consider"),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Form {
+    Base,
+    Third,
+    Past,
+    Participle,
+    Ing,
+}
+
+/// The forms of "to be" a weak verb phrase follows.
+const BE_FORMS: &[(&str, Form)] = &[
+    ("be", Form::Base),
+    ("am", Form::Base),
+    ("are", Form::Base),
+    ("is", Form::Third),
+    ("was", Form::Past),
+    ("were", Form::Past),
+    ("been", Form::Participle),
+    ("being", Form::Ing),
+];
+
+/// `(base, third person, past, past participle)` of the irregular verbs the
+/// tables use; everything else is inflected by rule.
+const IRREGULAR: &[(&str, &str, &str, &str)] = &[
+    ("choose", "chooses", "chose", "chosen"),
+    ("come", "comes", "came", "come"),
+    ("do", "does", "did", "done"),
+    ("give", "gives", "gave", "given"),
+    ("have", "has", "had", "had"),
+    ("know", "knows", "knew", "known"),
+    ("make", "makes", "made", "made"),
+    ("take", "takes", "took", "taken"),
+];
+
+/// Words that make a following past or base form a participle ("had made",
+/// "have come"), and the contractions that do when they end a word.
+const HAVE_FORMS: &[&str] = &["have", "has", "had", "having"];
+const HAVE_CONTRACTIONS: &[&str] = &["'ve", "'d"];
+
+/// Forms of "to be" that open an expletive sentence on their own ("There
+/// is"), and auxiliaries that do with a following "be" or "been" ("There
+/// will be", "There has been").
+const EXPLETIVE_VERBS: &[&str] = &["is", "are", "was", "were", "isn't", "aren't", "wasn't", "weren't"];
+const EXPLETIVE_AUXILIARIES: &[&str] = &["will", "would", "could", "might", "may", "must", "should", "can", "has", "have", "had"];
+
+fn ends_in_consonant_y(word: &str) -> bool {
+    let mut chars = word.chars().rev();
+    chars.next() == Some('y') && chars.next().is_some_and(|c| !"aeiou".contains(c))
+}
+
+fn third_person(verb: &str) -> String {
+    if ends_in_consonant_y(verb) {
+        format!("{}ies", &verb[..verb.len() - 1])
+    } else if ["s", "sh", "ch", "x", "z", "o"].iter().any(|end| verb.ends_with(end)) {
+        format!("{}es", verb)
+    } else {
+        format!("{}s", verb)
+    }
+}
+
+fn past(verb: &str) -> String {
+    if verb.ends_with('e') {
+        format!("{}d", verb)
+    } else if ends_in_consonant_y(verb) {
+        format!("{}ied", &verb[..verb.len() - 1])
+    } else {
+        format!("{}ed", verb)
+    }
+}
+
+fn present_participle(verb: &str) -> String {
+    if let Some(stem) = verb.strip_suffix("ie") {
+        format!("{}ying", stem)
+    } else if verb.ends_with('e') && !["ee", "ye", "oe"].iter().any(|end| verb.ends_with(end)) {
+        format!("{}ing", &verb[..verb.len() - 1])
+    } else {
+        format!("{}ing", verb)
+    }
+}
+
+/// `verb` (a verb, optionally followed by a particle: "rely on") in `form`.
+fn inflect(verb: &str, form: Form) -> String {
+    let (head, rest) = verb.split_once(' ').map_or((verb, None), |(head, rest)| (head, Some(rest)));
+    let irregular = IRREGULAR.iter().find(|v| v.0 == head);
+    let inflected = match form {
+        Form::Base => head.to_string(),
+        Form::Third => irregular.map_or_else(|| third_person(head), |v| v.1.to_string()),
+        Form::Past => irregular.map_or_else(|| past(head), |v| v.2.to_string()),
+        Form::Participle => irregular.map_or_else(|| past(head), |v| v.3.to_string()),
+        Form::Ing => present_participle(head),
+    };
+    match rest {
+        Some(rest) => format!("{} {}", inflected, rest),
+        None => inflected,
+    }
+}
+
+/// Lowercase with internal whitespace collapsed and curly apostrophes
+/// straightened.
+fn normalize(phrase: &str) -> String {
+    phrase.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase().replace('\u{2019}', "'")
+}
+
+/// Inflected phrases to the verb replacing them and the form to put it in,
+/// compiled into one whole-word, case-insensitive pattern.
+struct PhraseTable {
+    pattern: Option<Regex>,
+    replacements: HashMap<String, (String, Form)>,
+}
+
+impl PhraseTable {
+    fn new(replacements: HashMap<String, (String, Form)>) -> PhraseTable {
+        // Longest phrases first, so "give an explanation of" wins over
+        // "give an explanation"
+        let mut phrases: Vec<&String> = replacements.keys().collect();
+        phrases.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        let alternatives: Vec<String> =
+            phrases.iter().map(|p| p.split(' ').map(regex::escape).collect::<Vec<_>>().join(r"\s+")).collect();
+        let pattern =
+            if alternatives.is_empty() { None } else { Regex::new(&format!(r"(?i)\b(?:{})\b", alternatives.join("|"))).ok() };
+        PhraseTable { pattern, replacements }
+    }
+
+    /// `(start, end, replacement)` for every match in `text`.
+    fn matches(&self, text: &str) -> Vec<(usize, usize, String)> {
+        let Some(pattern) = &self.pattern else {
+            return Vec::new();
+        };
+        pattern
+            .find_iter(text)
+            .filter_map(|m| {
+                let (verb, mut form) = self.replacements.get(&normalize(m.as_str()))?.clone();
+                let previous = text[..m.start()].split_whitespace().next_back().map(normalize).unwrap_or_default();
+                let after_have = HAVE_FORMS.contains(&previous.as_str()) || HAVE_CONTRACTIONS.iter().any(|c| previous.ends_with(c));
+                if after_have && (form == Form::Past || form == Form::Base) {
+                    form = Form::Participle;
+                }
+                Some((m.start(), m.end(), rewrite::match_case(m.as_str(), &inflect(&verb, form))))
+            })
+            .collect()
+    }
+}
+
+/// The built-in weak verb and nominalization tables plus the configured
+/// extras.
+pub struct WeakVerbs {
+    weak_verbs: PhraseTable,
+    nominalizations: PhraseTable,
+}
+
+impl WeakVerbs {
+    pub fn from_config(config: &SuggestionConfig) -> WeakVerbs {
+        let weak_verbs = BUILTIN_WEAK_VERBS
+            .iter()
+            .map(|&(phrase, verb)| (phrase.to_string(), verb.to_string()))
+            .chain(config.extra_weak_verbs.iter().map(|(phrase, verb)| (normalize(phrase), normalize(verb))))
+            .filter(|(phrase, verb)| !phrase.is_empty() && !verb.is_empty())
+            .flat_map(|(phrase, verb)| {
+                BE_FORMS.iter().map(move |&(be, form)| (format!("{} {}", be, phrase), (verb.clone(), form)))
+            })
+            .collect();
+
+        let mut nominalizations = HashMap::new();
+        let extras = config.extra_nominalizations.iter().map(|(phrase, verb)| (normalize(phrase), normalize(verb)));
+        for (phrase, verb) in BUILTIN_NOMINALIZATIONS.iter().map(|&(p, v)| (p.to_string(), v.to_string())).chain(extras) {
+            let Some((light, object)) = phrase.split_once(' ') else { continue };
+            if verb.is_empty() {
+                continue;
+            }
+            // A participle that doubles as another form ("made", "come") keys
+            // as that form; `matches` tells them apart by a preceding "have"
+            for form in [Form::Participle, Form::Base, Form::Third, Form::Past, Form::Ing] {
+                nominalizations.insert(format!("{} {}", inflect(light, form), object), (verb.clone(), form));
+            }
+        }
+
+        WeakVerbs { weak_verbs: PhraseTable::new(weak_verbs), nominalizations: PhraseTable::new(nominalizations) }
+    }
+
+    /// `weak_verb` and `nominalization` suggestions for `text`, replacing
+    /// the whole construction with the verb.
+    pub fn suggestions(&self, text: &str) -> Vec<OptimizationSuggestion> {
+        let weak = self.weak_verbs.matches(text).into_iter().map(|(start, end, verb)| OptimizationSuggestion {
+            suggested_replacement: Some(verb.clone()),
+            ..OptimizationSuggestion::instance(
+                "weak_verb",
+                "low",
+                format!("\"{}\" can be one stronger verb: \"{}\".", &text[start..end], verb),
+                start,
+                end,
+            )
+        });
+        let buried = self.nominalizations.matches(text).into_iter().map(|(start, end, verb)| OptimizationSuggestion {
+            suggested_replacement: Some(verb.clone()),
+            ..OptimizationSuggestion::instance(
+                "nominalization",
+                "low",
+                format!("\"{}\" buries the verb in a noun; \"{}\" is more direct.", &text[start..end], verb),
+                start,
+                end,
+            )
+        });
+        weak.chain(buried).collect()
+    }
+}
+
+/// One `expletive_opener` suggestion per sentence opening on "There is",
+/// "There are", "There's", "There will be", and the like. "There" anywhere
+/// else ("over there") is a location, not an expletive.
+pub fn expletive_openers(index: &DocumentIndex) -> Vec<OptimizationSuggestion> {
+    let text = index.text;
+    let mut suggestions = Vec::new();
+    for &(start, end) in &index.sentences {
+        let words: Vec<(usize, usize, String)> =
+            index.words_in(start, end).iter().take(3).map(|&(s, e)| (s, e, normalize(&text[s..e]))).collect();
+        let Some((opener_start, opener_end, first)) = words.first() else { continue };
+        // Nothing but whitespace between the words of the opener
+        let adjacent = |k: usize| words.get(k).filter(|(s, _, _)| text[words[k - 1].1..*s].chars().all(char::is_whitespace));
+        let span_end = if first == "there's" || first == "there're" {
+            Some(*opener_end)
+        } else if first != "there" {
+            None
+        } else {
+            match adjacent(1) {
+                Some((_, e, verb)) if EXPLETIVE_VERBS.contains(&verb.as_str()) => Some(*e),
+                Some((_, _, auxiliary)) if EXPLETIVE_AUXILIARIES.contains(&auxiliary.as_str()) => {
+                    adjacent(2).filter(|(_, _, be)| be == "be" || be == "been").map(|(_, e, _)| *e)
+                }
+                _ => None,
+            }
+        };
+        if let Some(span_end) = span_end {
+            suggestions.push(OptimizationSuggestion::instance(
+                "expletive_opener",
+                "low",
+                format!(
+                    "\"{}\" delays the subject; consider opening with what the sentence is about.",
+                    &text[*opener_start..span_end]
+                ),
+                *opener_start,
+                span_end,
+            ));
+        }
+    }
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use crate::TextProcessor;
+
+    #[test]
+    fn nominalization_replaced_with_verb_in_its_tense() {
+        let text = "She made a decision yesterday. He has made a choice, and they make an effort to give an explanation of it.";
+        let suggestions = TextProcessor::new().generate_optimization_suggestions(text, &|rule| rule == "nominalization");
+        let found: Vec<(&str, Option<&str>)> =
+            suggestions.iter().map(|s| (&text[s.start_pos..s.end_pos], s.suggested_replacement.as_deref())).collect();
+        assert_eq!(
+            found,
+            [
+                ("made a decision", Some("decided")),
+                ("made a choice", Some("chosen")),
+                ("make an effort", Some("try")),
+                ("give an explanation of", Some("explain")),
+            ]
+        );
+    }
+
+    #[test]
+    fn weak_verbs_are_extensible() {
+        let text = "Was she aware of the risk? The team is reliant on him and is cognizant of it.";
+        let mut processor = TextProcessor::new();
+        processor.add_weak_verbs(BTreeMap::from([("cognizant of".to_string(), "know".to_string())]));
+        let suggestions = processor.generate_optimization_suggestions(text, &|rule| rule == "weak_verb");
+        let found: Vec<(&str, Option<&str>)> =
+            suggestions.iter().map(|s| (&text[s.start_pos..s.end_pos], s.suggested_replacement.as_deref())).collect();
+        assert_eq!(found, [("is reliant on", Some("relies on")), ("is cognizant of", Some("knows"))]);
+    }
+
+    #[test]
+    fn expletive_opener_only_at_sentence_start() {
+        let text = "There are three reasons that explain the delay. We left the car over there. There, on the hill, stood a house. There's time.";
+        let suggestions = TextProcessor::new().generate_optimization_suggestions(text, &|rule| rule == "expletive_opener");
+        let spans: Vec<&str> = suggestions.iter().map(|s| &text[s.start_pos..s.end_pos]).collect();
+        assert_eq!(spans, ["There are", "There's"]);
+    }
+}
@@ -0,0 +1,219 @@
+//! Non-fatal diagnostics attached to analysis results.
+
+use serde::{Deserialize, Serialize};
+
+use crate::RelatedSpan;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisWarning {
+    /// Machine-readable warning code.
+    pub code: String,
+    pub message: String,
+    /// Machine-readable likely cause, when the engine can tell.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cause: Option<String>,
+    /// Text the warning is about, when it concerns specific ranges.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<RelatedSpan>,
+}
+
+impl AnalysisWarning {
+    pub fn new(code: &str, message: String) -> AnalysisWarning {
+        AnalysisWarning { code: code.to_string(), message, cause: None, spans: Vec::new() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CollaborationConflict, OptimizationSuggestion, TextProcessor};
+    use crate::corpus::ReferenceCorpus;
+    use crate::provenance::MarkedRange;
+    use crate::resolution::ConflictPolicy;
+    use crate::suggestions::SCOPE_DOCUMENT;
+    use crate::{flagged, grouping, hashing, health, language, manifest, merge, persona, provenance, quick, reconcile};
+    use crate::test_support::Cases;
+
+    #[test]
+    fn perform_analysis_warns_on_empty_input() {
+        let result = TextProcessor::new().perform_analysis("  \n ");
+        assert_eq!(result.word_count, 0);
+        assert!(result.warnings.iter().any(|w| w.code == "empty_input"));
+    }
+
+    /// Inputs of 0..=50 bytes: hand-picked edge cases and all their prefixes,
+    /// plus deterministic noise over an alphabet heavy in whitespace,
+    /// punctuation, and multi-byte characters.
+    fn tiny_inputs() -> Vec<String> {
+        let seeds = [
+            "",
+            " ",
+            "\n\n\t\r\n",
+            "!!! ???",
+            "...",
+            "a",
+            "I.",
+            "The storm came. Mara walked the field at dawn.",
+            "# Chapter 1\n\nIt was written by me.",
+            "\"Quoted\" \u{201c}curly\u{201d} \u{2018}x\u{2019} -- \u{2014}",
+            "na\u{ef}ve caf\u{e9} \u{65e5}\u{672c}\u{8a9e} \u{1f469}\u{200d}\u{1f467}",
+            "word word word word word word word word word word",
+            "-\n-\n-\n> quote\n    indented",
+            "\u{201c}Ran quickly,\u{201d} she said.\u{a0}Dr. Lee was seen by him.",
+        ];
+        let mut inputs: Vec<String> = Vec::new();
+        for seed in seeds {
+            for end in (0..=seed.len().min(50)).filter(|&i| seed.is_char_boundary(i)) {
+                inputs.push(seed[..end].to_string());
+            }
+        }
+        let alphabet: Vec<char> = " \n.!?,;:'\"-\u{2014}aeIxyzThe\u{e9}\u{1f600}\u{a0}\t#>".chars().collect();
+        let mut cases = Cases(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..200 {
+            let mut input = String::new();
+            loop {
+                let c = alphabet[cases.below(alphabet.len())];
+                if input.len() + c.len_utf8() > 50 || cases.0.is_multiple_of(53) {
+                    break;
+                }
+                input.push(c);
+            }
+            inputs.push(input);
+        }
+        inputs
+    }
+
+    /// Fails on any NaN or infinity in `value`'s serialized form. serde_json
+    /// writes non-finite floats as `null`, so nulls are only accepted for
+    /// fields that are optional by design.
+    fn assert_finite<T: Serialize>(entry_point: &str, input: &str, value: &T) {
+        const OPTIONAL: &[&str] = &[
+            "firstPersonPer1000Words",
+            "heading",
+            "majority_terminal_punctuation",
+            "suggestedReplacement",
+            "terminal_punctuation",
+            "worst_priority",
+        ];
+        fn walk(value: &serde_json::Value, key: &str, entry_point: &str, input: &str) {
+            match value {
+                serde_json::Value::Null => {
+                    assert!(OPTIONAL.contains(&key), "{}({:?}) produced a non-finite or null `{}`", entry_point, input, key)
+                }
+                serde_json::Value::Array(items) => items.iter().for_each(|v| walk(v, key, entry_point, input)),
+                serde_json::Value::Object(fields) => fields.iter().for_each(|(k, v)| walk(v, k, entry_point, input)),
+                _ => {}
+            }
+        }
+        walk(&serde_json::to_value(value).unwrap(), "", entry_point, input);
+    }
+
+    fn assert_spans_in_bounds(entry_point: &str, input: &str, spans: impl IntoIterator<Item = (usize, usize)>) {
+        for (start, end) in spans {
+            assert!(
+                start <= end && input.get(start..end).is_some(),
+                "{}({:?}) returned span {}..{} outside the text",
+                entry_point,
+                input,
+                start,
+                end
+            );
+        }
+    }
+
+    /// The empty/tiny-input contract, checked against the native core of
+    /// every public entry point. New entry points belong here too.
+    #[test]
+    fn every_entry_point_honours_the_tiny_input_contract() {
+        let processor = TextProcessor::new();
+        let mut corpus = ReferenceCorpus::new();
+        corpus.add_document("The storm came at night. Mara walked the field at dawn and found the barn held.");
+        for input in tiny_inputs() {
+            let input = input.as_str();
+            let blank = input.trim().is_empty();
+
+            let analysis = processor.perform_analysis(input);
+            assert_finite("analyze_text", input, &analysis);
+            assert_eq!(analysis.low_confidence, analysis.word_count < processor.config.min_word_count, "{:?}", input);
+            if blank {
+                assert_eq!(analysis.warnings.iter().map(|w| w.code.as_str()).collect::<Vec<_>>(), ["empty_input"]);
+                assert_eq!((analysis.word_count, analysis.sentence_count, analysis.paragraph_count), (0, 0, 0));
+                assert_eq!(analysis.readability_score, 0.0);
+                assert_eq!(analysis.complexity_metrics.avg_words_per_sentence, 0.0);
+            } else if analysis.low_confidence {
+                assert!(analysis.warnings.iter().any(|w| w.code == "low_confidence"), "{:?}", input);
+            }
+            if analysis.word_count == 0 || analysis.sentence_count == 0 {
+                let metrics = &analysis.complexity_metrics;
+                let scores = [
+                    analysis.readability_score,
+                    analysis.readability_score_raw,
+                    metrics.flesch_reading_ease,
+                    metrics.fog_index,
+                    metrics.smog_index,
+                    metrics.coleman_liau_index,
+                    metrics.automated_readability_index,
+                    metrics.lix,
+                ];
+                assert_eq!(scores, [0.0; 8], "{:?}", input);
+            }
+
+            let index = processor.index(input);
+            let sentences = processor.sentence_difficulties(&index);
+            assert_finite("sentence_difficulty", input, &sentences);
+            assert_spans_in_bounds("sentence_difficulty", input, sentences.iter().map(|s| (s.start_pos, s.end_pos)));
+            assert_finite("analyze_full", input, &processor.analyze_indexed(&index));
+            let readability = processor.sentence_readability(&index);
+            assert_finite("analyze_sentences", input, &readability);
+            assert_spans_in_bounds("analyze_sentences", input, readability.iter().map(|s| (s.start_pos, s.end_pos)));
+
+            let report = processor.build_optimization_report(input);
+            assert_finite("optimize_text_report", input, &report);
+            assert_spans_in_bounds("optimize_text", input, report.suggestions.iter().map(|s| (s.start_pos, s.end_pos)));
+            if report.low_confidence {
+                assert!(report.suggestions.iter().all(|s| s.scope != SCOPE_DOCUMENT), "{:?}", input);
+            }
+            if blank {
+                assert!(report.suggestions.is_empty() && report.low_confidence);
+            }
+            #[cfg(feature = "wasm")]
+            assert_eq!(crate::suggestions::to_compact(&report.suggestions).suggestions.len(), report.suggestions.len());
+
+            let manifest = processor.build_chapter_manifest(input);
+            assert_finite("chapter_manifest", input, &manifest);
+            assert_finite("diff_manifest", input, &manifest::diff(&manifest, processor.build_chapter_manifest(input)));
+            assert_finite("language_map", input, &language::language_map(&index, "en"));
+            assert_finite("persona_scores", input, &persona::persona_scores(&processor, &index));
+            assert_finite("quick_metrics_batch", input, &quick::quick_metrics_batch(&processor, &[input.to_string(), String::new()]));
+            assert_finite("find_flagged_terms", input, &flagged::find_flagged_terms(input, &["storm".to_string()], true));
+            assert_finite("compare_to_corpus", input, &corpus.compare(&processor, input));
+
+            let whole = [MarkedRange { start_pos: 0, end_pos: input.len() }];
+            let map = provenance::mark(input, processor.generate_content_hash(input), None, &whole, "assisted").unwrap();
+            assert_finite("provenance_report", input, &provenance::report(&map, &index));
+            assert_finite("remap_provenance", input, &provenance::remap(&map, input, "", processor.generate_content_hash("")));
+            let instances: Vec<&OptimizationSuggestion> = report.suggestions.iter().collect();
+            assert_finite("snapshot", input, &health::snapshot(&analysis, &instances, "2024-01-01T00:00:00Z"));
+            assert_finite("group_suggestions", input, &grouping::group(&index, report.suggestions));
+            assert_finite("verify_content_hash", input, &hashing::verify(input, "sha256:", "base64"));
+
+            let conflict: CollaborationConflict = serde_json::from_value(serde_json::json!({
+                "conflict_id": "c1",
+                "conflict_type": "text_modification",
+                "start_pos": 0,
+                "end_pos": input.len(),
+                "user_a_change": "a",
+                "user_b_change": "b",
+                "timestamp": "2024-01-01T00:00:00Z",
+                "expected_text": input.trim(),
+            }))
+            .unwrap();
+            assert_finite("reconcile_conflict_spans", input, &reconcile::reconcile(input, &conflict));
+            let resolved = processor.auto_resolve_conflicts(vec![conflict], ConflictPolicy::Manual);
+            let preview = merge::preview(input, &resolved);
+            assert_finite("preview_resolution", input, &preview);
+            assert_eq!(merge::accept(&preview).unwrap(), merge::apply(input, &resolved).text);
+        }
+    }
+}
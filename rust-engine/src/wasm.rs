@@ -0,0 +1,783 @@
+//! The JavaScript API: `#[wasm_bindgen]` wrappers that convert arguments
+//! and results around the plain Rust methods of `TextProcessor`. Built with
+//! the `wasm` feature, which is on by default.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+use crate::batch::BatchDocument;
+use crate::chunks::ChunkHashes;
+use crate::manifest::ChapterManifest;
+use crate::merge::ResolutionPreview;
+use crate::provenance::{MarkedRange, ProvenanceMap};
+use crate::query::SuggestionQuery;
+use crate::conflicts::RejectedConflict;
+use crate::reconcile::SpanReconciliation;
+use crate::session::TextEdit;
+use crate::similarity::Fingerprint;
+use crate::typography::TypographyOptions;
+use crate::{
+    capabilities, conflicts, delta, health, merge, suggestions, AnalysisOptions, CollaborationConflict, ConflictPolicy,
+    ConflictResolutionResponse, OptimizationSuggestion, PositionEdit, ReferenceCorpus, SuggestionConfig, TextProcessor,
+};
+
+#[wasm_bindgen(typescript_custom_section)]
+const RESULT_TYPES: &str = crate::typings::RESULT_TYPES;
+
+/// `TextProcessor` as JavaScript sees it.
+#[wasm_bindgen(js_name = TextProcessor)]
+pub struct JsTextProcessor(TextProcessor);
+
+#[wasm_bindgen(js_class = TextProcessor)]
+impl JsTextProcessor {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsTextProcessor {
+        console_log!("Initializing Rust Text Processing Engine");
+        JsTextProcessor(TextProcessor::new())
+    }
+
+    /// A processor for text in `language` (e.g. "es", "de"); codes without
+    /// their own resources get English.
+    pub fn with_language(language: &str) -> JsTextProcessor {
+        JsTextProcessor(TextProcessor::with_language(language))
+    }
+
+    /// A processor with the default configuration except for `options`
+    /// (rule thresholds and priorities; see `AnalysisOptions`).
+    pub fn with_options(options: JsValue) -> Result<JsTextProcessor, JsError> {
+        let analysis: AnalysisOptions = from_js(options, "Invalid analysis options")?;
+        Ok(JsTextProcessor(TextProcessor::with_options(analysis)))
+    }
+
+    /// Replaces the built-in patterns with those in `pack` (an object mapping
+    /// pattern ids to regex sources) and returns the initialization report.
+    /// Patterns that fail to compile are reported rather than thrown.
+    pub fn load_pattern_pack(&mut self, pack: JsValue) -> Result<JsValue, JsError> {
+        let overrides: BTreeMap<String, String> = from_js(pack, "Invalid pattern pack")?;
+        to_js(&self.0.load_pattern_pack(&overrides))
+    }
+
+    pub fn initialization_report(&self) -> Result<JsValue, JsError> {
+        to_js(&self.0.initialization_report())
+    }
+
+    /// Unchanged text is served from the analysis cache; see
+    /// `set_cache_capacity`.
+    #[wasm_bindgen(unchecked_return_type = "TextAnalysisResult")]
+    pub fn analyze_text(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.analyze_text(text))
+    }
+
+    /// `analyze_text` as a JSON string with camelCase keys, for persisting
+    /// or for bundlers that would hand back `Map`s.
+    pub fn analyze_text_json(&self, text: &str) -> Result<String, JsError> {
+        self.0.analyze_text_json(text).map_err(|e| JsError::new(&e))
+    }
+
+    /// `analyze_text` on each of an array of strings or `{id, text}`
+    /// objects, with corpus totals. Bare strings are identified by position.
+    pub fn analyze_batch(&self, texts: JsValue) -> Result<JsValue, JsError> {
+        let documents: Vec<BatchDocument> = from_js(texts, "Invalid batch")?;
+        to_js(&self.0.analyze_batch(documents))
+    }
+
+    /// How many `analyze_text` results to keep (8 by default); 0 disables
+    /// the cache. Shrinking evicts the least recently used results.
+    pub fn set_cache_capacity(&mut self, capacity: usize) {
+        self.0.set_cache_capacity(capacity);
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.0.clear_cache();
+    }
+
+    /// Capacity, stored entries, and hit and miss counts of the analysis
+    /// cache, for diagnostics.
+    pub fn cache_stats(&self) -> Result<JsValue, JsError> {
+        to_js(&self.0.cache_stats())
+    }
+
+    /// Starts an editing session on `text` and returns its analysis. Later
+    /// edits go through `analyze_incremental`.
+    #[wasm_bindgen(unchecked_return_type = "TextAnalysisResult")]
+    pub fn begin_session(&mut self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.begin_session(text))
+    }
+
+    /// Applies `edit` (`{start, end, replacement}`, byte offsets into the
+    /// session text) and returns the analysis of the edited text, identical
+    /// to `analyze_text` on it. Only the paragraphs around the edit are
+    /// re-tokenized.
+    #[wasm_bindgen(unchecked_return_type = "TextAnalysisResult")]
+    pub fn analyze_incremental(&mut self, edit: JsValue) -> Result<JsValue, JsError> {
+        let edit: TextEdit = from_js(edit, "Invalid edit")?;
+        to_js(&self.0.analyze_incremental(&edit).map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Ends the editing session and frees the retained text.
+    pub fn reset_session(&mut self) {
+        self.0.reset_session();
+    }
+
+    /// Starts analyzing a text fed in pieces with `feed_chunk`, dropping any
+    /// stream not finished. Memory stays near the chunk size plus the
+    /// vocabulary rather than the whole text.
+    pub fn start_stream_analysis(&mut self) {
+        self.0.start_stream_analysis();
+    }
+
+    /// Appends `chunk` to the streamed text, measuring the paragraphs it
+    /// completes.
+    pub fn feed_chunk(&mut self, chunk: &str) -> Result<(), JsError> {
+        self.0.feed_chunk(chunk).map_err(|e| JsError::new(&e))
+    }
+
+    /// Ends the stream and returns the analysis of everything fed, identical
+    /// to `analyze_text` on the chunks joined.
+    #[wasm_bindgen(unchecked_return_type = "TextAnalysisResult")]
+    pub fn finish_stream_analysis(&mut self) -> Result<JsValue, JsError> {
+        to_js(&self.0.finish_stream_analysis().map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Per-sentence difficulty scores and buckets for editor heatmaps.
+    pub fn sentence_difficulty(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.sentence_difficulty(text))
+    }
+
+    /// How far `text` is from a readability `target` ("8th grade",
+    /// "general adult", "academic", ...), with the longest sentences to split
+    /// and the most frequent complex words to replace to get there. Throws
+    /// on an unknown target.
+    pub fn readability_gap(&self, text: &str, target: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.readability_gap(text, target).map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Word count, syllable density, Flesch reading ease, and passive-voice
+    /// presence for every sentence, with byte ranges into `text`. Empty
+    /// text gives an empty array.
+    pub fn analyze_sentences(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.analyze_sentences(text))
+    }
+
+    /// Byte range, word and sentence counts, Flesch reading ease, dialogue
+    /// presence, and dominant style of every paragraph, for outline views.
+    /// The word counts add up to `analyze_text`'s `word_count`.
+    pub fn analyze_paragraphs(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.analyze_paragraphs(text))
+    }
+
+    /// Dialogue tags ("she said", "said Harry", "he whispered softly") before,
+    /// after, or interrupting each quotation, with the share that use
+    /// "said" or "asked", said-bookisms, and adverbs.
+    pub fn analyze_dialogue(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.analyze_dialogue(text))
+    }
+
+    /// Pacing across the document for a line chart: sentence length,
+    /// dialogue, and a 0 (slow) to 1 (fast) score per window of
+    /// `window_words` words (0 for the default of 250), half a window apart.
+    pub fn pacing_profile(&self, text: &str, window_words: usize) -> Result<JsValue, JsError> {
+        to_js(&self.0.pacing_profile(text, window_words))
+    }
+
+    /// Sentiment per paragraph for an emotional arc: valence per word, with
+    /// the words that weigh most, and the document's average.
+    pub fn sentiment_profile(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.sentiment_profile(text))
+    }
+
+    /// Recurring character names with every mention, most mentioned first.
+    /// Short forms count toward the full name they start ("Elizabeth" in
+    /// "Elizabeth Bennet"), and possessives count as mentions.
+    pub fn extract_entities(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.extract_entities(text))
+    }
+
+    /// Every acronym in order of first use, with its expansion when the text
+    /// spells it out and whether that came before its first use.
+    pub fn extract_acronyms(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.extract_acronyms(text))
+    }
+
+    /// Phrases of `min_n` to `max_n` words (default 3 to 5) repeated at
+    /// least `min_count` times (default 3), with every position; pass 0 for
+    /// a default. A phrase only ever seen inside a longer reported one is
+    /// left out.
+    pub fn repeated_phrases(&self, text: &str, min_n: usize, max_n: usize, min_count: usize) -> Result<JsValue, JsError> {
+        to_js(&self.0.repeated_phrases(text, min_n, max_n, min_count))
+    }
+
+    /// The sentences of `text` with their byte ranges, as every analysis
+    /// counts them.
+    pub fn split_sentences(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.split_sentences(text))
+    }
+
+    /// `analyze_text` and `sentence_difficulty` from a single tokenization pass.
+    pub fn analyze_full(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.analyze_full(text))
+    }
+
+    /// The full analysis and optimization report of `text` rendered as
+    /// "markdown", "csv", or "json". Throws on any other format.
+    pub fn export_report(&self, text: &str, format: &str) -> Result<String, JsError> {
+        self.0.export_report(text, format).map_err(|e| JsError::new(&e))
+    }
+
+    /// Analysis, sentence difficulty, and suggestions for `text[start..end]`
+    /// alone, in positions of the whole text, with where the range sits in
+    /// the document. An empty range gives an empty analysis.
+    pub fn analyze_range(&self, text: &str, start: usize, end: usize) -> Result<JsValue, JsError> {
+        to_js(&self.0.analyze_range(text, start, end).map_err(|e| JsError::new(&e))?)
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "OptimizationSuggestion[]")]
+    pub fn optimize_text(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.optimize_text(text))
+    }
+
+    /// `optimize_text` as a JSON string with camelCase keys.
+    pub fn optimize_text_json(&self, text: &str) -> Result<String, JsError> {
+        self.0.optimize_text_json(text).map_err(|e| JsError::new(&e))
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "OptimizationReport")]
+    pub fn optimize_text_report(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.optimize_text_report(text))
+    }
+
+    /// Same suggestions as `optimize_text`, serialized with a shared string
+    /// table so repeated rule ids and messages cross the boundary once.
+    pub fn optimize_text_compact(&self, text: &str) -> Result<JsValue, JsError> {
+        let suggestions = self.0.optimize_text(text);
+        to_js(&suggestions::to_compact(&suggestions))
+    }
+
+    /// Suggestions narrowed by a `SuggestionQuery` (rule ids, minimum
+    /// priority, a selection), highest priority first, one page at a time.
+    pub fn optimize_text_filtered(&self, text: &str, query: JsValue) -> Result<JsValue, JsError> {
+        let query: SuggestionQuery = if query.is_undefined() || query.is_null() {
+            SuggestionQuery::default()
+        } else {
+            from_js(query, "Invalid suggestion query")?
+        };
+        to_js(&self.0.optimize_text_filtered(text, &query))
+    }
+
+    /// `text` with the replacements of the suggestions at `accepted_indices`
+    /// applied. Suggestions without a replacement are skipped; overlapping
+    /// accepted spans are an error and nothing is applied.
+    pub fn apply_suggestions(&self, text: &str, suggestions: JsValue, accepted_indices: JsValue) -> Result<String, JsError> {
+        let suggestions: Vec<OptimizationSuggestion> = from_js(suggestions, "Invalid suggestions")?;
+        let accepted: Vec<usize> = from_js(accepted_indices, "Accepted indices must be an array of integers")?;
+        self.0.apply_suggestions(text, &suggestions, &accepted).map_err(|e| JsError::new(&e))
+    }
+
+    /// `text` with the safe typography fixes applied: runs of spaces between
+    /// words collapsed and straight quotes made curly, unless `options`
+    /// (`collapse_spaces`, `curly_quotes`; both default to true) turns them
+    /// off. Opaque tokens and, for Markdown, code and link targets are left
+    /// as they are.
+    pub fn normalize_typography(&self, text: &str, options: JsValue) -> Result<String, JsError> {
+        let options: TypographyOptions = if options.is_undefined() || options.is_null() {
+            TypographyOptions::default()
+        } else {
+            from_js(options, "Invalid typography options")?
+        };
+        Ok(self.0.normalize_typography(text, &options))
+    }
+
+    pub fn set_suggestion_config(&mut self, config: JsValue) -> Result<(), JsError> {
+        let config: SuggestionConfig = from_js(config, "Invalid suggestion config")?;
+        self.0.set_suggestion_config(config);
+        Ok(())
+    }
+
+    pub fn set_language(&mut self, language: &str) {
+        self.0.set_language(language);
+    }
+
+    /// Replaces the sentiment lexicon (an object mapping words to valences,
+    /// about -5 to 5), e.g. with one for another language.
+    pub fn load_sentiment_lexicon(&mut self, lexicon: JsValue) -> Result<(), JsError> {
+        let lexicon: BTreeMap<String, f64> = from_js(lexicon, "Invalid sentiment lexicon")?;
+        self.0.load_sentiment_lexicon(lexicon);
+        Ok(())
+    }
+
+    /// Adds genre-specific crutch words (an array of strings; phrases are
+    /// allowed) to the `filler_word` rule.
+    pub fn add_filler_words(&mut self, words: JsValue) -> Result<(), JsError> {
+        let words: Vec<String> = from_js(words, "Filler words must be an array of strings")?;
+        self.0.add_filler_words(words);
+        Ok(())
+    }
+
+    /// Adds phrases (an array of strings) to the `cliche` rule.
+    pub fn add_cliches(&mut self, phrases: JsValue) -> Result<(), JsError> {
+        let phrases: Vec<String> = from_js(phrases, "Clichés must be an array of strings")?;
+        self.0.add_cliches(phrases);
+        Ok(())
+    }
+
+    /// Adds "to be" + adjective phrases to the `weak_verb` rule (an object of
+    /// phrases to verbs, e.g. `{ "cognizant of": "know" }`).
+    pub fn add_weak_verbs(&mut self, phrases: JsValue) -> Result<(), JsError> {
+        let phrases: BTreeMap<String, String> = from_js(phrases, "Weak verbs must be an object of phrases to verbs")?;
+        self.0.add_weak_verbs(phrases);
+        Ok(())
+    }
+
+    /// Adds light verb + noun phrases to the `nominalization` rule (an
+    /// object of phrases to verbs, e.g. `{ "make a purchase": "buy" }`).
+    pub fn add_nominalizations(&mut self, phrases: JsValue) -> Result<(), JsError> {
+        let phrases: BTreeMap<String, String> = from_js(phrases, "Nominalizations must be an object of phrases to verbs")?;
+        self.0.add_nominalizations(phrases);
+        Ok(())
+    }
+
+    /// Adds groups of spellings to keep consistent (an array of string
+    /// arrays, e.g. `[["Caitlin", "Kaitlyn"]]`) to the `consistency` rule.
+    pub fn add_variant_groups(&mut self, groups: JsValue) -> Result<(), JsError> {
+        let groups: Vec<Vec<String>> = from_js(groups, "Variant groups must be an array of string arrays")?;
+        self.0.add_variant_groups(groups);
+        Ok(())
+    }
+
+    /// Adds words (an array of strings: names, invented terms) to the
+    /// `possible_typo` rule's dictionary.
+    pub fn add_dictionary_words(&mut self, words: JsValue) -> Result<(), JsError> {
+        let words: Vec<String> = from_js(words, "Dictionary words must be an array of strings")?;
+        self.0.add_dictionary_words(words);
+        Ok(())
+    }
+
+    /// Stops the `possible_typo` rule flagging `word`.
+    pub fn ignore_word(&mut self, word: &str) {
+        self.0.ignore_word(word);
+    }
+
+    /// Resolves a batch of conflicts. Entries are validated individually:
+    /// malformed ones are listed in `rejected` with their batch index and a
+    /// reason code, and the rest are resolved as usual. Overlapping conflicts
+    /// are merged into one `composite` conflict, and `resolved` is sorted by
+    /// `start_pos`.
+    ///
+    /// When `text` is given, spans are first reconciled against it (see
+    /// `reconcile_conflict_spans`) and the outcome is listed in `reconciliation`.
+    ///
+    /// `policy` picks the resolution: auto (the default), prefer_a,
+    /// prefer_b, prefer_latest, prefer_longest, merge_both, or manual.
+    /// Conflicts a policy can't decide get "Manual resolution required".
+    #[wasm_bindgen(unchecked_return_type = "ConflictResolutionResponse")]
+    pub fn resolve_conflicts(&self, conflicts_js: &JsValue, text: Option<String>, policy: Option<String>) -> Result<JsValue, JsError> {
+        let policy = policy.as_deref().map_or(Ok(ConflictPolicy::Auto), ConflictPolicy::parse).map_err(|e| JsError::new(&e))?;
+        let (conflicts, rejected) = conflict_entries(conflicts_js)?;
+        let response = ConflictResolutionResponse { rejected, ..self.0.resolve_conflicts(conflicts, text.as_deref(), policy) };
+        to_js(&response)
+    }
+
+    /// `resolve_conflicts` taking and returning JSON strings with camelCase
+    /// keys; snake_case keys are accepted too.
+    pub fn resolve_conflicts_json(&self, conflicts_json: &str, text: Option<String>, policy: Option<String>) -> Result<String, JsError> {
+        let policy = policy.as_deref().map_or(Ok(ConflictPolicy::Auto), ConflictPolicy::parse).map_err(|e| JsError::new(&e))?;
+        self.0.resolve_conflicts_json(conflicts_json, text.as_deref(), policy).map_err(|e| JsError::new(&e))
+    }
+
+    /// Three-way merge of two versions edited from `base`. Edits that don't
+    /// overlap are applied to `merged_text`; overlapping ones that differ are
+    /// returned as conflicts with spans into `base`, ready for
+    /// `resolve_conflicts`, and `merged_spans` locates each one in
+    /// `merged_text`. Identical edits on both sides are not conflicts.
+    pub fn detect_conflicts(&self, base: &str, version_a: &str, version_b: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.detect_conflicts(base, version_a, version_b))
+    }
+
+    /// Merged text for `base_text` with the conflicts resolved under `policy`
+    /// (any `resolve_conflicts` policy; user_a and user_b still name
+    /// prefer_a and prefer_b) and a marker span per applied conflict naming
+    /// its source.
+    /// Unresolved conflicts are rendered with both versions between
+    /// git-style conflict markers. Overlapping or out-of-range conflicts are
+    /// listed in `skipped`, malformed ones in `rejected`.
+    pub fn preview_resolution(&self, base_text: &str, conflicts_js: &JsValue, policy: &str) -> Result<JsValue, JsError> {
+        let policy = ConflictPolicy::parse(policy).map_err(|e| JsError::new(&e))?;
+        let (conflicts, rejected) = conflict_entries(conflicts_js)?;
+        let preview = ResolutionPreview { rejected, ..self.0.preview_resolution(base_text, conflicts, policy) };
+        to_js(&preview)
+    }
+
+    /// `base_text` with the resolutions of `resolve_conflicts` output spliced
+    /// in, as `{ text, skipped }`. Conflicts needing manual resolution are
+    /// left as they are and listed in `skipped`; an out-of-bounds, reversed,
+    /// or overlapping span throws an error naming its conflict.
+    pub fn apply_resolutions(&self, base_text: &str, resolved_conflicts: JsValue) -> Result<JsValue, JsError> {
+        let resolved: Vec<CollaborationConflict> = from_js(resolved_conflicts, "Resolved conflicts must be an array")?;
+        to_js(&self.0.apply_resolutions(base_text, &resolved).map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Verifies each conflict's span against its `expected_text` and, on a
+    /// mismatch, searches nearby (whitespace-insensitively, then by edit
+    /// distance) for the intended location. Status is exact, shifted,
+    /// fuzzy, not_found, or unverified when no excerpt was sent.
+    pub fn reconcile_conflict_spans(&self, text: &str, conflicts_js: &JsValue) -> Result<JsValue, JsError> {
+        let (conflicts, rejected) = conflict_entries(conflicts_js)?;
+        let spans = self.0.reconcile_conflict_spans(text, &conflicts);
+        to_js(&ReconciliationResponse { spans, rejected })
+    }
+
+    /// Whole-word matches of `terms` (an array of words or phrases); `fuzzy`
+    /// also matches obfuscated spellings. Spans refer to the raw text.
+    pub fn find_flagged_terms(&self, text: &str, terms: JsValue, fuzzy: bool) -> Result<JsValue, JsError> {
+        let terms: Vec<String> = from_js(terms, "Terms must be an array of strings")?;
+        to_js(&self.0.find_flagged_terms(text, &terms, fuzzy))
+    }
+
+    /// Matches of the screening lists of `categories` (an array of category
+    /// names; all of them when omitted) with category, severity, and
+    /// position, plus counts per category.
+    pub fn screen_content(&self, text: &str, categories: JsValue) -> Result<JsValue, JsError> {
+        let categories: Option<Vec<String>> = if categories.is_undefined() || categories.is_null() {
+            None
+        } else {
+            Some(from_js(categories, "Categories must be an array of strings")?)
+        };
+        to_js(&self.0.screen_content(text, categories.as_deref()).map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Replaces the screening list of `category` (an array of words or
+    /// phrases), or adds the category.
+    pub fn load_screening_list(&mut self, category: &str, words: JsValue) -> Result<(), JsError> {
+        let words: Vec<String> = from_js(words, "Screening list must be an array of strings")?;
+        self.0.load_screening_list(category, words);
+        Ok(())
+    }
+
+    pub fn compare_to_corpus(&self, text: &str, corpus: &ReferenceCorpus) -> Result<JsValue, JsError> {
+        to_js(&self.0.compare_to_corpus(text, corpus))
+    }
+
+    pub fn generate_content_hash(&self, text: &str) -> String {
+        self.0.generate_content_hash(text)
+    }
+
+    /// Hash of `text` under `algorithm` (sha256, sha512, or blake3) in
+    /// `encoding` (base64, base64url, or hex). `generate_content_hash` is
+    /// sha256 in base64.
+    pub fn generate_content_hash_with(&self, text: &str, algorithm: &str, encoding: &str) -> Result<String, JsError> {
+        self.0.generate_content_hash_with(text, algorithm, encoding).map_err(|e| JsError::new(&e))
+    }
+
+    /// Hash of every paragraph (`chunk_mode` "paragraph") or run of N words
+    /// ("words:N"), with byte ranges that cover the whole text, and a Merkle
+    /// root over them that is stable for unchanged text.
+    pub fn generate_chunk_hashes(&self, text: &str, chunk_mode: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.generate_chunk_hashes(text, chunk_mode).map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Ranges of the chunks that differ between two `generate_chunk_hashes`
+    /// results, on each side. Both must use the same chunk mode.
+    pub fn compare_chunk_hashes(&self, local: JsValue, remote: JsValue) -> Result<JsValue, JsError> {
+        let local: ChunkHashes = from_js(local, "Invalid chunk hashes")?;
+        let remote: ChunkHashes = from_js(remote, "Invalid chunk hashes")?;
+        to_js(&self.0.compare_chunk_hashes(&local, &remote).map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Similarity of two texts in [0, 1] by `method`: "shingle" for the
+    /// Jaccard similarity of word trigrams ("shingle:N" for N-grams), or
+    /// "cosine" over term frequencies. Identical texts score 1.0.
+    pub fn similarity(&self, text_a: &str, text_b: &str, method: &str) -> Result<f64, JsError> {
+        self.0.similarity(text_a, text_b, method).map_err(|e| JsError::new(&e))
+    }
+
+    /// A compact MinHash signature of `text`'s word trigrams, to store and
+    /// compare with `compare_fingerprints` instead of keeping the text.
+    pub fn fingerprint(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.fingerprint(text))
+    }
+
+    /// Estimated "shingle" similarity of the texts behind two fingerprints.
+    pub fn compare_fingerprints(&self, a: JsValue, b: JsValue) -> Result<f64, JsError> {
+        let a: Fingerprint = from_js(a, "Invalid fingerprint")?;
+        let b: Fingerprint = from_js(b, "Invalid fingerprint")?;
+        self.0.compare_fingerprints(&a, &b).map_err(|e| JsError::new(&e))
+    }
+
+    /// Heading, span, word count, and content hash for every detected
+    /// chapter or section, plus the whole-document hash.
+    pub fn chapter_manifest(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.chapter_manifest(text))
+    }
+
+    /// Markdown headings, "Chapter N" lines, and scene breaks (`***`, `---`,
+    /// `# # #`) in order, each with the span of the section it opens. Text
+    /// before the first one is an untitled level-0 section.
+    pub fn extract_outline(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.extract_outline(text))
+    }
+
+    /// `analyze_text` on every `extract_outline` section on its own, with
+    /// the section's title and range.
+    pub fn analyze_by_section(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.analyze_by_section(text))
+    }
+
+    /// Compares a previously stored manifest against `new_text`, reporting
+    /// chapters that changed, were added or removed, or merely moved.
+    pub fn diff_manifest(&self, old_manifest: JsValue, new_text: &str) -> Result<JsValue, JsError> {
+        let old: ChapterManifest = from_js(old_manifest, "Invalid manifest")?;
+        to_js(&self.0.diff_manifest(&old, new_text))
+    }
+
+    /// Up to three likely languages of `text` with confidences; texts under
+    /// 20 words are marked low confidence.
+    pub fn detect_language(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.detect_language(text))
+    }
+
+    /// Detected language and confidence for every paragraph, flagging those
+    /// that are confidently not in the configured language. Paragraphs under
+    /// eight words inherit their neighbours' language.
+    pub fn language_map(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.language_map(text))
+    }
+
+    /// Skimmability, engagement, and cognitive-load scores (0–100), each with
+    /// its contributing factors and the spans that hurt it most.
+    pub fn persona_scores(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.persona_scores(text))
+    }
+
+    /// The `top_n` most used words (all of them for 0) as `{word, count,
+    /// ratio}`, most frequent first and ties alphabetical. Counts use the
+    /// same tokenization as `analyze_text`, so they add up to its
+    /// `word_count` when stopwords are kept.
+    pub fn word_frequencies(&self, text: &str, top_n: usize, exclude_stop_words: bool) -> Result<JsValue, JsError> {
+        to_js(&self.0.word_frequencies(text, top_n, exclude_stop_words))
+    }
+
+    /// Every token of `text` with its kind, byte range, and style flags, as
+    /// `{kinds, starts, ends, flags}` typed arrays (one entry per token) with
+    /// the `kind_names` and `flag_names` (by bit) that decode them. The
+    /// flags agree with the metrics of `analyze_text`.
+    #[wasm_bindgen(unchecked_return_type = "TokenStream")]
+    pub fn tokenize(&self, text: &str) -> Result<JsValue, JsError> {
+        let tokens = self.0.tokenize(text);
+        let object = js_sys::Object::new();
+        let fields: [(&str, JsValue); 6] = [
+            ("kinds", js_sys::Uint8Array::from(&tokens.kinds[..]).into()),
+            ("starts", js_sys::Uint32Array::from(&tokens.starts[..]).into()),
+            ("ends", js_sys::Uint32Array::from(&tokens.ends[..]).into()),
+            ("flags", js_sys::Uint8Array::from(&tokens.flags[..]).into()),
+            ("kind_names", to_js(&tokens.kind_names)?),
+            ("flag_names", to_js(&tokens.flag_names)?),
+        ];
+        for (key, value) in fields {
+            // Defining a property on a fresh plain object cannot fail
+            let _ = js_sys::Reflect::set(&object, &key.into(), &value);
+        }
+        Ok(object.into())
+    }
+
+    /// Word counts by Microsoft Word's rules and by the engine's, the count
+    /// without front matter, headings, and block quotes with a manuscript
+    /// page estimate, and character counts with and without spaces.
+    pub fn count_words(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.count_words(text))
+    }
+
+    /// `{fields, start_pos, end_pos}` for the front matter block opening
+    /// `text`, or null without one (including when its closing fence is
+    /// missing).
+    pub fn parse_front_matter(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.parse_front_matter(text))
+    }
+
+    /// Counts, reading time, and style flags for a short UI string, without
+    /// the cost of a full analysis.
+    pub fn quick_metrics(&self, text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.quick_metrics(text))
+    }
+
+    /// `quick_metrics` for an array of strings in one call. Results are in
+    /// input order, and strings whose terminal punctuation differs from the
+    /// rest of the batch are flagged.
+    pub fn quick_metrics_batch(&self, texts: JsValue) -> Result<JsValue, JsError> {
+        let texts: Vec<String> = from_js(texts, "Texts must be an array of strings")?;
+        to_js(&self.0.quick_metrics_batch(&texts))
+    }
+
+    /// Word-level diff between two versions of a document as `{op,
+    /// old_range, new_range, text}` hunks in text order, with a summary of
+    /// words added and removed and paragraphs touched.
+    pub fn diff_texts(&self, old_text: &str, new_text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.diff_texts(old_text, new_text))
+    }
+
+    /// `items` (an array of suggestions, conflicts, or any records with
+    /// `start_pos` and `end_pos`) with their positions carried through
+    /// `edits` (an array of `{start, end, replacement_len}` in application
+    /// order). Records an edit deleted or overlapped are marked `stale`.
+    pub fn transform_positions(&self, items: JsValue, edits: JsValue) -> Result<JsValue, JsError> {
+        let items: Vec<serde_json::Value> = from_js(items, "Items must be an array of records")?;
+        let edits: Vec<PositionEdit> = from_js(edits, "Edits must be an array of {start, end, replacement_len}")?;
+        to_js(&self.0.transform_positions(items, &edits).map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Words added and deleted, net change, characters typed, paragraphs
+    /// touched and moved, and the content hashes of both snapshots.
+    pub fn session_stats(&self, previous_text: &str, current_text: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.session_stats(previous_text, current_text))
+    }
+
+    /// Labels `ranges` (an array of `{start_pos, end_pos}`) of `text` with
+    /// `label`, on top of `existing` if given. Returns the provenance map.
+    pub fn mark_provenance(&self, text: &str, ranges: JsValue, label: &str, existing: Option<JsValue>) -> Result<JsValue, JsError> {
+        let ranges: Vec<MarkedRange> = from_js(ranges, "Ranges must be an array of spans")?;
+        let existing: Option<ProvenanceMap> = existing
+            .filter(|v| !v.is_undefined() && !v.is_null())
+            .map(|v| from_js(v, "Invalid provenance map"))
+            .transpose()?;
+        to_js(&self.0.mark_provenance(text, &ranges, label, existing).map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Carries a provenance map from `old_text` to `new_text`. Inserted text
+    /// is unlabeled, so it splits the span it lands in; spans whose text was
+    /// deleted are listed in `dropped`.
+    pub fn remap_provenance(&self, map: JsValue, old_text: &str, new_text: &str) -> Result<JsValue, JsError> {
+        let map: ProvenanceMap = from_js(map, "Invalid provenance map")?;
+        to_js(&self.0.remap_provenance(&map, old_text, new_text).map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Word counts and word share per label.
+    pub fn provenance_report(&self, map: JsValue, text: &str) -> Result<JsValue, JsError> {
+        let map: ProvenanceMap = from_js(map, "Invalid provenance map")?;
+        to_js(&self.0.provenance_report(&map, text).map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Nests already-computed suggestions under their paragraph and
+    /// sentence, with per-group counts and worst priority.
+    pub fn group_suggestions(&self, text: &str, suggestions: JsValue) -> Result<JsValue, JsError> {
+        let suggestions: Vec<OptimizationSuggestion> = from_js(suggestions, "Invalid suggestions")?;
+        to_js(&self.0.group_suggestions(text, suggestions))
+    }
+
+    /// Compact, versioned health summary of `text` for trend dashboards;
+    /// feed a series of these to `health_history`.
+    pub fn snapshot(&self, text: &str, timestamp: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.snapshot(text, timestamp))
+    }
+
+    /// Checks `expected_hash` (optionally prefixed, e.g. `"sha256:..."`)
+    /// against the hash of `text` using a constant-time comparison.
+    pub fn verify_content_hash(&self, text: &str, expected_hash: &str, encoding: &str) -> Result<JsValue, JsError> {
+        to_js(&self.0.verify_content_hash(text, expected_hash, encoding).map_err(|e| JsError::new(&e))?)
+    }
+
+    /// Constant-time check that `expected` is the `algorithm` hash of `text`
+    /// in `encoding`. Malformed values are simply false; unknown algorithms
+    /// and encodings are errors.
+    pub fn verify_content_hash_with(&self, text: &str, expected: &str, algorithm: &str, encoding: &str) -> Result<bool, JsError> {
+        self.0.verify_content_hash_with(text, expected, algorithm, encoding).map_err(|e| JsError::new(&e))
+    }
+}
+
+impl Default for JsTextProcessor {
+    fn default() -> Self {
+        JsTextProcessor::new()
+    }
+}
+
+#[derive(Serialize)]
+struct ReconciliationResponse {
+    spans: Vec<SpanReconciliation>,
+    rejected: Vec<RejectedConflict>,
+}
+
+fn from_js<T: DeserializeOwned>(value: JsValue, what: &str) -> Result<T, JsError> {
+    serde_wasm_bindgen::from_value(value).map_err(|e| JsError::new(&format!("{}: {}", what, e)))
+}
+
+/// Conflict entries are validated one by one, so malformed ones can be
+/// reported back instead of failing the whole batch.
+fn conflict_entries(conflicts_js: &JsValue) -> Result<(Vec<CollaborationConflict>, Vec<RejectedConflict>), JsError> {
+    let entries: Vec<serde_json::Value> = from_js(conflicts_js.clone(), "Conflicts must be an array")?;
+    Ok(conflicts::validate_entries(entries))
+}
+
+/// Nested structs become plain objects rather than `Map`s, whatever the
+/// bundler.
+const SERIALIZER: serde_wasm_bindgen::Serializer = serde_wasm_bindgen::Serializer::json_compatible();
+
+/// Serialization failures surface as a thrown JS error instead of a trap.
+pub(crate) fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    value.serialize(&SERIALIZER).map_err(|e| JsError::new(&format!("Failed to serialize result: {}", e)))
+}
+
+#[wasm_bindgen]
+pub fn engine_info() -> Result<JsValue, JsError> {
+    to_js(&capabilities::engine_info())
+}
+
+/// Drops suggestions whose fingerprint the user previously dismissed.
+#[wasm_bindgen]
+pub fn filter_dismissed(suggestions: JsValue, dismissed_fingerprints: JsValue) -> Result<JsValue, JsError> {
+    let suggestions: Vec<OptimizationSuggestion> = from_js(suggestions, "Invalid suggestions")?;
+    let dismissed: Vec<String> = from_js(dismissed_fingerprints, "Invalid dismissed fingerprints")?;
+    let remaining = suggestions::filter_dismissed(suggestions, &dismissed);
+    to_js(&remaining)
+}
+
+/// Added, removed, moved, and updated suggestions between two lists from
+/// the optimize APIs, matched by fingerprint. A suggestion that only changed
+/// position is sent as a move.
+#[wasm_bindgen]
+pub fn diff_suggestions(old_suggestions: JsValue, new_suggestions: JsValue) -> Result<JsValue, JsError> {
+    let old: Vec<OptimizationSuggestion> = from_js(old_suggestions, "Invalid old suggestions")?;
+    let new: Vec<OptimizationSuggestion> = from_js(new_suggestions, "Invalid new suggestions")?;
+    let delta = delta::diff(&old, &new).map_err(|e| JsError::new(&e))?;
+    to_js(&delta)
+}
+
+/// Reconstructs the new suggestion list from the old one and a delta from
+/// `diff_suggestions`.
+#[wasm_bindgen]
+pub fn apply_suggestion_diff(old: JsValue, delta: JsValue) -> Result<JsValue, JsError> {
+    let old: Vec<OptimizationSuggestion> = from_js(old, "Invalid suggestions")?;
+    let delta: delta::SuggestionDelta = from_js(delta, "Invalid suggestion delta")?;
+    let suggestions = delta::apply(old, delta).map_err(|e| JsError::new(&e))?;
+    to_js(&suggestions)
+}
+
+/// Per-metric series, consecutive deltas, and regression flags over health
+/// snapshots given oldest first. `regression_threshold` defaults to 5 points.
+#[wasm_bindgen]
+pub fn health_history(entries: JsValue, regression_threshold: Option<f64>) -> Result<JsValue, JsError> {
+    let snapshots: Vec<health::HealthSnapshot> = from_js(entries, "Invalid health snapshots")?;
+    let trend = health::history(&snapshots, regression_threshold.unwrap_or(health::DEFAULT_REGRESSION_THRESHOLD));
+    to_js(&trend)
+}
+
+/// Replaces each conflict span of `text` with its `resolution_suggestion`,
+/// as returned by `resolve_conflicts`. Spans refer to `text` as given.
+/// Unlike `TextProcessor.apply_resolutions`, out-of-bounds and overlapping
+/// spans are skipped rather than thrown.
+#[wasm_bindgen]
+pub fn apply_resolutions_lenient(text: &str, resolved: JsValue) -> Result<JsValue, JsError> {
+    let resolved: Vec<CollaborationConflict> = from_js(resolved, "Resolved conflicts must be an array")?;
+    to_js(&merge::apply(text, &resolved))
+}
+
+/// Text of a `preview_resolution` result with every marker accepted.
+#[wasm_bindgen]
+pub fn accept_preview(preview: JsValue) -> Result<String, JsError> {
+    let preview: ResolutionPreview = from_js(preview, "Invalid resolution preview")?;
+    merge::accept(&preview).map_err(|e| JsError::new(&e))
+}
+
+#[wasm_bindgen(start)]
+pub fn main() {
+    console_log!("OmniAuthor Rust Engine initialized successfully!");
+}
\ No newline at end of file
@@ -0,0 +1,96 @@
+//! Word and character counts under the rules different publishing contexts
+//! use: Microsoft Word's whitespace tokens, the engine's own word
+//! boundaries, and body text without front matter, headings, and block
+//! quotes, with a manuscript page estimate.
+
+use serde::{Deserialize, Serialize};
+
+use crate::front_matter;
+
+/// Words on a standard manuscript page.
+pub const WORDS_PER_MANUSCRIPT_PAGE: usize = 250;
+
+#[derive(Serialize, Deserialize)]
+pub struct WordCountDetail {
+    /// Whitespace-separated tokens containing a letter or digit, as
+    /// Microsoft Word counts them: "well-known", "1,000", and
+    /// "clauses—joined" by a dash are one word each.
+    pub word_compatible: usize,
+    /// Words as `analyze_text` counts them, at Unicode word boundaries.
+    pub words: usize,
+    /// `word_compatible` without front matter, headings, and block quotes.
+    pub body_words: usize,
+    /// `body_words` at 250 words a page, rounded up.
+    pub manuscript_pages: usize,
+    /// Characters other than line breaks.
+    pub characters_with_spaces: usize,
+    pub characters_without_spaces: usize,
+}
+
+/// Byte spans of Markdown block quote lines.
+fn block_quotes(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        if indent <= 3 && line[indent..].starts_with('>') {
+            spans.push((offset, offset + line.len()));
+        }
+        offset += line.len();
+    }
+    spans
+}
+
+/// Start offsets of the whitespace-separated tokens of `text` that contain
+/// a letter or digit.
+fn word_starts(text: &str) -> Vec<usize> {
+    text.split_whitespace()
+        .filter(|token| token.chars().any(char::is_alphanumeric))
+        .map(|token| token.as_ptr() as usize - text.as_ptr() as usize)
+        .collect()
+}
+
+/// Counts for `text`. `words` is the engine's own word count; `headings`
+/// holds the byte span of every heading line.
+pub fn count(text: &str, words: usize, headings: &[(usize, usize)]) -> WordCountDetail {
+    let mut excluded: Vec<(usize, usize)> = headings.to_vec();
+    excluded.extend(front_matter::end(text).map(|end| (0, end)));
+    excluded.extend(block_quotes(text));
+
+    let starts = word_starts(text);
+    let body_words = starts.iter().filter(|&&start| !excluded.iter().any(|&(s, e)| s <= start && start < e)).count();
+    WordCountDetail {
+        word_compatible: starts.len(),
+        words,
+        body_words,
+        manuscript_pages: body_words.div_ceil(WORDS_PER_MANUSCRIPT_PAGE),
+        characters_with_spaces: text.chars().filter(|&c| c != '\n' && c != '\r').count(),
+        characters_without_spaces: text.chars().filter(|c| !c.is_whitespace()).count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TextProcessor;
+
+    #[test]
+    fn count_words_matches_word_on_compounds_numbers_and_dashes() {
+        let text = "A well-known author sold 1,000,000 copies\u{2014}an astonishing run\u{2014}by 2024. Self-published e-books count too.";
+        let processor = TextProcessor::new();
+        let counts = processor.count_words(text);
+        assert_eq!(counts.word_compatible, 13);
+        assert_eq!(counts.words, processor.analyze_text(text).word_count);
+        assert!(counts.words > counts.word_compatible);
+        assert_eq!(counts.characters_with_spaces, text.chars().count());
+        assert_eq!(counts.characters_without_spaces, text.chars().filter(|c| !c.is_whitespace()).count());
+    }
+
+    #[test]
+    fn count_words_body_excludes_front_matter_headings_and_quotes() {
+        let text = "---\ntitle: Draft\n---\n# Chapter One\n\nShe left.\n\n> Quoted line here.\n\nThe end.";
+        let counts = TextProcessor::new().count_words(text);
+        assert_eq!((counts.word_compatible, counts.body_words, counts.manuscript_pages), (11, 4, 1));
+        assert_eq!(counts.characters_with_spaces, text.chars().filter(|&c| c != '\n').count());
+        assert_eq!(TextProcessor::new().count_words("").manuscript_pages, 0);
+    }
+}
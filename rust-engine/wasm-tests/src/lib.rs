@@ -0,0 +1 @@
+//! Empty: the tests live in `tests/`.
@@ -0,0 +1,62 @@
+//! `analyze_batch` against one `analyze_text` call per chapter through the
+//! JavaScript exports, so the conversions at the boundary are in the
+//! timings. Run with `wasm-pack test --node --release -- --nocapture` from
+//! `wasm-tests/`; the timings are logged, not asserted.
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::{Array, Date, Reflect};
+use omniauthor_engine::JsTextProcessor;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::{console_log, wasm_bindgen_test};
+
+const CHAPTERS: usize = 30;
+const PARAGRAPHS_PER_CHAPTER: usize = 60;
+
+/// Prose whose rarer words differ from chapter to chapter, the way a
+/// novel's do.
+fn chapter(number: usize) -> String {
+    let paragraphs: Vec<String> = (0..PARAGRAPHS_PER_CHAPTER)
+        .map(|p| {
+            let place = format!("Kar{}en", (number * PARAGRAPHS_PER_CHAPTER + p) % 700);
+            format!(
+                "The rain had not stopped since morning, and Mara watched it run down the window at {}. \
+                 \u{201C}He will not come tonight,\u{201D} she said quietly. The letter was written by her \
+                 brother and was sealed in haste, and for a while neither of them spoke about {} or the debts.",
+                place,
+                place.to_lowercase()
+            )
+        })
+        .collect();
+    format!("Chapter {}\n\n{}", number, paragraphs.join("\n\n"))
+}
+
+fn word_count(analysis: &JsValue) -> f64 {
+    Reflect::get(analysis, &JsValue::from_str("wordCount")).unwrap().as_f64().unwrap()
+}
+
+#[wasm_bindgen_test]
+fn batch_and_separate_calls_agree_and_are_timed() {
+    let chapters: Vec<String> = (1..=CHAPTERS).map(chapter).collect();
+    let mut processor = JsTextProcessor::new();
+    processor.set_cache_capacity(0);
+
+    let started = Date::now();
+    let separate: f64 = chapters.iter().map(|chapter| word_count(&processor.analyze_text(chapter).unwrap())).sum();
+    let separate_ms = Date::now() - started;
+
+    let documents: Array = chapters.iter().map(|chapter| JsValue::from_str(chapter)).collect();
+    let started = Date::now();
+    let batch = processor.analyze_batch(documents.into()).unwrap();
+    let batch_ms = Date::now() - started;
+
+    let results = Array::from(&Reflect::get(&batch, &JsValue::from_str("results")).unwrap());
+    assert_eq!(results.length() as usize, CHAPTERS);
+    assert_eq!(results.iter().map(|result| word_count(&result)).sum::<f64>(), separate);
+    console_log!(
+        "{} chapters: separate calls {:.0} ms, analyze_batch {:.0} ms ({:.0}%)",
+        CHAPTERS,
+        separate_ms,
+        batch_ms,
+        100.0 * batch_ms / separate_ms
+    );
+}
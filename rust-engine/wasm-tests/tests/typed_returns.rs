@@ -0,0 +1,46 @@
+//! The typed `analyze_text` and `optimize_text` returns, read field by field
+//! from the JavaScript side. Run with `wasm-pack test --node` from
+//! `wasm-tests/`.
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::{Array, Reflect};
+use omniauthor_engine::JsTextProcessor;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const TEXT: &str = "The letter was written by the clerk. She walked very slowly to the mill.";
+
+fn field(object: &JsValue, key: &str) -> JsValue {
+    Reflect::get(object, &JsValue::from_str(key)).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn analysis_fields_are_plain_properties() {
+    let analysis = JsTextProcessor::new().analyze_text(TEXT).unwrap();
+    assert_eq!(field(&analysis, "wordCount").as_f64(), Some(14.0));
+    assert_eq!(field(&analysis, "sentenceCount").as_f64(), Some(2.0));
+    assert!(field(&analysis, "contentHash").as_string().is_some_and(|hash| !hash.is_empty()));
+
+    // Nested metrics are objects, not `Map`s
+    let complexity = field(&analysis, "complexityMetrics");
+    assert!(field(&complexity, "fleschReadingEase").as_f64().is_some());
+    let style = field(&analysis, "styleMetrics");
+    assert!(field(&style, "passiveVoiceRatio").as_f64().is_some_and(|ratio| ratio > 0.0));
+    assert!(field(&analysis, "word_count").is_undefined());
+}
+
+#[wasm_bindgen_test]
+fn suggestions_are_an_array_of_plain_objects() {
+    let suggestions = JsTextProcessor::new().optimize_text(TEXT).unwrap();
+    assert!(Array::is_array(&suggestions));
+    let suggestions = Array::from(&suggestions);
+    assert!(suggestions.length() > 0);
+    for suggestion in suggestions.iter() {
+        assert!(field(&suggestion, "suggestionType").as_string().is_some());
+        let priority = field(&suggestion, "priority").as_string().unwrap();
+        assert!(["low", "medium", "high"].contains(&priority.as_str()));
+        let start = field(&suggestion, "startPos").as_f64().unwrap();
+        let end = field(&suggestion, "endPos").as_f64().unwrap();
+        assert!(start <= end && end <= TEXT.len() as f64);
+    }
+}
@@ -303,14 +303,14 @@ class CollaborationManager {
     try {
       // Use Rust engine for conflict resolution
       const conflicts = conflictingChanges.map(c => ({
-        conflict_id: c.changeId,
-        conflict_type: 'text_modification',
-        start_pos: c.position,
-        end_pos: c.position + (c.length || c.content.length),
-        user_a_change: c.content,
-        user_b_change: incomingChange.content,
+        conflictId: c.changeId,
+        conflictType: 'text_modification',
+        startPos: c.position,
+        endPos: c.position + (c.length || c.content.length),
+        userAChange: c.content,
+        userBChange: incomingChange.content,
         timestamp: new Date(c.timestamp).toISOString(),
-        resolution_suggestion: ''
+        resolutionSuggestion: ''
       }));
 
       const resolvedConflicts = await rustEngine.resolveConflicts(conflicts);